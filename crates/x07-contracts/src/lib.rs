@@ -42,6 +42,7 @@ pub const X07_HOST_RUNNER_REPORT_SCHEMA_VERSION: &str = "x07-host-runner.report@
 pub const X07_OS_RUNNER_REPORT_SCHEMA_VERSION: &str = "x07-os-runner.report@0.5.0";
 pub const X07_RUN_REPORT_SCHEMA_VERSION: &str = "x07.run.report@0.3.0";
 pub const X07_BUNDLE_REPORT_SCHEMA_VERSION: &str = "x07.bundle.report@0.4.0";
+pub const X07_BUNDLE_MANIFEST_SCHEMA_VERSION: &str = "x07.bundle.manifest@0.1.0";
 pub const X07_DOC_REPORT_SCHEMA_VERSION: &str = "x07.doc.report@0.1.0";
 pub const X07_VERIFY_REPORT_SCHEMA_VERSION: &str = "x07.verify.report@0.8.0";
 pub const X07_VERIFY_COVERAGE_SCHEMA_VERSION: &str = "x07.verify.coverage@0.4.0";
@@ -2,7 +2,9 @@
 #![allow(clippy::missing_safety_doc)]
 
 use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -16,6 +18,28 @@ extern "C" {
     fn ev_trap(code: i32) -> !;
 }
 
+/// Defines stand-ins for the host runtime symbols the staticlibs normally
+/// link against. For `#[cfg(test)]` modules only.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ev_test_stubs {
+    () => {
+        #[no_mangle]
+        extern "C" fn ev_bytes_alloc(len: u32) -> $crate::ev_bytes {
+            let buf = Box::leak(vec![0u8; len as usize].into_boxed_slice());
+            $crate::ev_bytes {
+                ptr: buf.as_mut_ptr(),
+                len,
+            }
+        }
+
+        #[no_mangle]
+        extern "C" fn ev_trap(code: i32) -> ! {
+            panic!("ev_trap({code})")
+        }
+    };
+}
+
 pub const EV_TRAP_DB_INTERNAL: i32 = 9400;
 
 pub fn trap(code: i32) -> ! {
@@ -35,6 +59,12 @@ pub const OP_OPEN_V1: u32 = 1;
 pub const OP_EXEC_V1: u32 = 2;
 pub const OP_QUERY_V1: u32 = 3;
 pub const OP_CLOSE_V1: u32 = 4;
+pub const OP_TXN_BEGIN_V1: u32 = 5;
+pub const OP_TXN_COMMIT_V1: u32 = 6;
+pub const OP_TXN_ROLLBACK_V1: u32 = 7;
+
+/// `QUERY` request flag: add a `"col_types"` seq parallel to `"cols"` in the rows doc.
+pub const QUERY_FLAG_COL_TYPES_V1: u32 = 1 << 0;
 
 pub fn env_bool(name: &str, default: bool) -> bool {
     std::env::var(name)
@@ -107,24 +137,37 @@ pub struct DbCapsV1 {
     pub query_timeout_ms: u32,
     pub max_rows: u32,
     pub max_resp_bytes: u32,
+    /// Version 2 blobs only (0 otherwise): argument count and per-argument
+    /// size caps for argv-style drivers (Redis).
+    pub max_argv: u32,
+    pub max_arg_bytes: u32,
 }
 
+/// Parses an `X7DC` blob: version 1 (24 bytes) or version 2 (32 bytes, adds
+/// `max_argv` and `max_arg_bytes`).
 pub fn parse_db_caps_v1(b: &[u8]) -> Result<DbCapsV1, u32> {
-    if b.len() != 24 {
-        return Err(DB_ERR_BAD_REQ);
-    }
-    if &b[0..4] != b"X7DC" {
+    if b.len() < 8 || &b[0..4] != b"X7DC" {
         return Err(DB_ERR_BAD_REQ);
     }
     let ver = read_u32_le(b, 4).ok_or(DB_ERR_BAD_REQ)?;
-    if ver != 1 {
-        return Err(DB_ERR_BAD_REQ);
+    match (ver, b.len()) {
+        (1, 24) | (2, 32) => {}
+        _ => return Err(DB_ERR_BAD_REQ),
     }
+    let v2_field = |off: usize| {
+        if ver >= 2 {
+            read_u32_le(b, off)
+        } else {
+            Some(0)
+        }
+    };
     Ok(DbCapsV1 {
         connect_timeout_ms: read_u32_le(b, 8).ok_or(DB_ERR_BAD_REQ)?,
         query_timeout_ms: read_u32_le(b, 12).ok_or(DB_ERR_BAD_REQ)?,
         max_rows: read_u32_le(b, 16).ok_or(DB_ERR_BAD_REQ)?,
         max_resp_bytes: read_u32_le(b, 20).ok_or(DB_ERR_BAD_REQ)?,
+        max_argv: v2_field(24).ok_or(DB_ERR_BAD_REQ)?,
+        max_arg_bytes: v2_field(28).ok_or(DB_ERR_BAD_REQ)?,
     })
 }
 
@@ -156,6 +199,229 @@ pub fn evict_conn_slot<T>(table: &Mutex<Vec<Option<T>>>, conn_id: u32) {
     *slot = None;
 }
 
+pub fn live_conn_count<T>(table: &Mutex<Vec<Option<T>>>) -> u32 {
+    let Ok(table) = table.lock() else {
+        return 0;
+    };
+    table.iter().skip(1).filter(|s| s.is_some()).count() as u32
+}
+
+/// Idle connections parked by `*_close_v1` when `X07_OS_DB_POOLING=1`, for
+/// reuse by a later `*_open_v1` with the same [`pool_key`]. Parked
+/// connections hold no conn_id and do not count as live, but live plus
+/// parked connections stay within `max_live_conns`.
+pub struct IdlePool<T> {
+    entries: Mutex<Vec<IdleConn<T>>>,
+}
+
+struct IdleConn<T> {
+    key: Vec<u8>,
+    parked_at: Instant,
+    conn: T,
+}
+
+impl<T> Default for IdlePool<T> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<T> IdlePool<T> {
+    /// Parks `conn`, dropping expired entries first and then the oldest entry
+    /// while `live` plus the parked entries would exceed `max_conns`
+    /// (0 = unlimited). `conn` itself is dropped when `live` alone is at the
+    /// limit.
+    pub fn checkin(
+        &self,
+        key: Vec<u8>,
+        conn: T,
+        max_conns: u32,
+        live: u32,
+        idle_timeout: Duration,
+    ) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        entries.retain(|e| e.parked_at.elapsed() < idle_timeout);
+        if max_conns != 0 {
+            let max_idle = max_conns.saturating_sub(live) as usize;
+            if max_idle == 0 {
+                entries.clear();
+                return;
+            }
+            while entries.len() >= max_idle {
+                entries.remove(0);
+            }
+        }
+        entries.push(IdleConn {
+            key,
+            parked_at: Instant::now(),
+            conn,
+        });
+    }
+
+    /// Takes the most recently parked, unexpired connection for `key`.
+    pub fn checkout(&self, key: &[u8], idle_timeout: Duration) -> Option<T> {
+        let mut entries = self.entries.lock().ok()?;
+        entries.retain(|e| e.parked_at.elapsed() < idle_timeout);
+        let idx = entries.iter().rposition(|e| e.key == key)?;
+        Some(entries.remove(idx).conn)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Pool key for an open request: every part length-prefixed, so distinct
+/// targets or credentials never share a key.
+pub fn pool_key(parts: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for part in parts {
+        out.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        out.extend_from_slice(part);
+    }
+    out
+}
+
+/// Upper bounds of the [`OpLatency`] buckets; the last bucket is open-ended.
+pub const LATENCY_BUCKET_LABELS: [&str; LATENCY_BUCKETS] =
+    ["lt_1ms", "lt_10ms", "lt_100ms", "lt_1s", "ge_1s"];
+pub const LATENCY_BUCKETS: usize = 5;
+
+/// Index of the [`OpLatency`] bucket `elapsed` falls into.
+pub fn latency_bucket(elapsed: Duration) -> usize {
+    match elapsed.as_micros() {
+        0..=999 => 0,
+        1_000..=9_999 => 1,
+        10_000..=99_999 => 2,
+        100_000..=999_999 => 3,
+        _ => 4,
+    }
+}
+
+/// Per-backend wall-time histogram of query/exec/cmd calls. Buckets are
+/// plain atomic counters, so recording never takes a lock.
+pub struct OpLatency {
+    buckets: [AtomicU64; LATENCY_BUCKETS],
+}
+
+impl Default for OpLatency {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpLatency {
+    pub const fn new() -> Self {
+        Self {
+            buckets: [
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+            ],
+        }
+    }
+
+    pub fn record(&self, elapsed: Duration) {
+        self.buckets[latency_bucket(elapsed)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> [u64; LATENCY_BUCKETS] {
+        std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed))
+    }
+}
+
+/// Runs one backend operation, records its wall time in `hist` and appends
+/// `elapsed_ms=N` to the detail of an error response.
+pub fn timed_op(hist: &OpLatency, op: impl FnOnce() -> Vec<u8>) -> Vec<u8> {
+    let started = Instant::now();
+    let resp = op();
+    let elapsed = started.elapsed();
+    hist.record(elapsed);
+    with_elapsed_ms(resp, elapsed)
+}
+
+/// Rewrites an `evdb_err` response so its detail ends with `elapsed_ms=N`
+/// (joined to an existing detail with `"; "`). Ok responses pass through.
+pub fn with_elapsed_ms(resp: Vec<u8>, elapsed: Duration) -> Vec<u8> {
+    if resp.len() < 24 || &resp[0..4] != b"X7DB" || read_u32_le(&resp, 8) != Some(0) {
+        return resp;
+    }
+    let (Some(op), Some(code), Some(msg_len)) = (
+        read_u32_le(&resp, 12),
+        read_u32_le(&resp, 16),
+        read_u32_le(&resp, 20),
+    ) else {
+        return resp;
+    };
+    let Some(msg) = resp.get(24..24 + msg_len as usize) else {
+        return resp;
+    };
+    let mut detail = msg.to_vec();
+    if !detail.is_empty() {
+        detail.extend_from_slice(b"; ");
+    }
+    detail.extend_from_slice(format!("elapsed_ms={}", elapsed.as_millis()).as_bytes());
+    evdb_err(op, code, &detail)
+}
+
+/// Copies a snapshot of `hist` into `out` (which must hold
+/// [`LATENCY_BUCKETS`] `u64`s) and returns the bucket count.
+pub unsafe fn copy_latency_hist(hist: &OpLatency, out: *mut u64) -> u32 {
+    if out.is_null() {
+        return 0;
+    }
+    let snap = hist.snapshot();
+    std::ptr::copy_nonoverlapping(snap.as_ptr(), out, LATENCY_BUCKETS);
+    LATENCY_BUCKETS as u32
+}
+
+/// Read-only policy usage snapshot returned by the `*_stats_v1` exports.
+/// A `max_*` of 0 means unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbStatsV1 {
+    pub live_conns: u32,
+    pub max_live_conns: u32,
+    pub queries_used: u32,
+    pub max_queries: u32,
+    /// [`OpLatency::snapshot`] of the backend's query/exec/cmd calls.
+    pub op_latency: [u64; LATENCY_BUCKETS],
+}
+
+impl DbStatsV1 {
+    /// DataModel doc whose root is a map of the four counters plus an
+    /// `op_latency_ms` map keyed by [`LATENCY_BUCKET_LABELS`].
+    pub fn to_dm_doc(&self) -> Vec<u8> {
+        let num = |v: u64| dm_value_number_ascii(v.to_string().as_bytes());
+        let latency = dm_value_map(
+            LATENCY_BUCKET_LABELS
+                .iter()
+                .zip(self.op_latency)
+                .map(|(label, n)| (label.as_bytes().to_vec(), num(n)))
+                .collect(),
+        )
+        .unwrap_or_else(|_| trap_db_internal());
+        let map = dm_value_map(vec![
+            (b"live_conns".to_vec(), num(self.live_conns.into())),
+            (b"max_live_conns".to_vec(), num(self.max_live_conns.into())),
+            (b"queries_used".to_vec(), num(self.queries_used.into())),
+            (b"max_queries".to_vec(), num(self.max_queries.into())),
+            (b"op_latency_ms".to_vec(), latency),
+        ])
+        .unwrap_or_else(|_| trap_db_internal());
+        dm_doc_ok(&map)
+    }
+}
+
 pub fn evdb_ok(op: u32, ok_payload: &[u8]) -> Vec<u8> {
     let mut out = Vec::with_capacity(20 + ok_payload.len());
     out.extend_from_slice(b"X7DB");
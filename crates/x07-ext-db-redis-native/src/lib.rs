@@ -57,12 +57,24 @@ struct Policy {
     max_query_timeout_ms: u32,
     max_resp_bytes: u32,
     max_req_bytes: u32,
+    pooling: bool,
+    idle_timeout_ms: u32,
+    max_scan_keys: u32,
+    max_argv: u32,
+    max_arg_bytes: u32,
+    /// `X07_OS_DB_REDIS_PIN_DB`: keep each connection on the db it was opened
+    /// with; see [`cmd_leaves_db`].
+    pin_db: bool,
+    /// `X07_OS_DB_REDIS_ALLOWED_DBS`; `None` allows every db.
+    allowed_dbs: Option<Vec<u32>>,
 }
 
 static POLICY: OnceCell<Policy> = OnceCell::new();
 static RT: OnceCell<Runtime> = OnceCell::new();
 static CONNS: OnceCell<Mutex<RedisConnTable>> = OnceCell::new();
 static QUERIES: AtomicU32 = AtomicU32::new(0);
+static OP_LATENCY: dbcore::OpLatency = dbcore::OpLatency::new();
+static POOL: OnceCell<dbcore::IdlePool<RedisConn>> = OnceCell::new();
 
 #[derive(Debug)]
 struct AcceptAllVerifier;
@@ -140,6 +152,10 @@ fn conns() -> &'static Mutex<RedisConnTable> {
     CONNS.get_or_init(|| Mutex::new(vec![None; 4096]))
 }
 
+fn pool() -> &'static dbcore::IdlePool<RedisConn> {
+    POOL.get_or_init(Default::default)
+}
+
 fn load_policy() -> Policy {
     let sandboxed = dbcore::env_bool("X07_OS_SANDBOXED", false);
     let enabled = dbcore::env_bool("X07_OS_DB", !sandboxed);
@@ -165,20 +181,42 @@ fn load_policy() -> Policy {
         max_query_timeout_ms: dbcore::env_u32_nonzero("X07_OS_DB_MAX_QUERY_TIMEOUT_MS", 60_000),
         max_resp_bytes: dbcore::env_u32_nonzero("X07_OS_DB_MAX_RESP_BYTES", 32 * 1024 * 1024),
         max_req_bytes: dbcore::env_u32_nonzero("X07_OS_DB_MAX_SQL_BYTES", 1024 * 1024),
+        pooling: dbcore::env_bool("X07_OS_DB_POOLING", false),
+        idle_timeout_ms: dbcore::env_u32_nonzero("X07_OS_DB_IDLE_TIMEOUT_MS", 30_000),
+        max_scan_keys: dbcore::env_u32_nonzero("X07_OS_DB_REDIS_MAX_SCAN_KEYS", 10_000),
+        max_argv: dbcore::env_u32_nonzero("X07_OS_DB_REDIS_MAX_ARGV", 1024),
+        max_arg_bytes: dbcore::env_u32_nonzero("X07_OS_DB_REDIS_MAX_ARG_BYTES", 1024 * 1024),
+        pin_db: dbcore::env_bool("X07_OS_DB_REDIS_PIN_DB", false),
+        allowed_dbs: env_allowed_dbs(),
     }
 }
 
+/// Unparsable entries are dropped, so a set but garbled list denies every db.
+fn env_allowed_dbs() -> Option<Vec<u32>> {
+    std::env::var_os("X07_OS_DB_REDIS_ALLOWED_DBS")?;
+    Some(
+        dbcore::env_list("X07_OS_DB_REDIS_ALLOWED_DBS", ',')
+            .into_iter()
+            .filter_map(|s| s.parse::<u32>().ok())
+            .collect(),
+    )
+}
+
+fn db_allowed(pol: &Policy, db: u32) -> bool {
+    pol.allowed_dbs.as_ref().is_none_or(|dbs| dbs.contains(&db))
+}
+
 fn policy() -> &'static Policy {
     POLICY.get_or_init(load_policy)
 }
 
-fn count_query_or_deny(pol: &Policy, op: u32) -> Result<(), dbcore::ev_bytes> {
+fn count_query_or_deny(pol: &Policy, op: u32) -> Result<(), Vec<u8>> {
     if pol.max_queries == 0 {
         return Ok(());
     }
     let prev = QUERIES.fetch_add(1, Ordering::Relaxed);
     if prev >= pol.max_queries {
-        return Err(alloc_return_bytes(&evdb_err(op, DB_ERR_POLICY_DENIED, &[])));
+        return Err(evdb_err(op, DB_ERR_POLICY_DENIED, &[]));
     }
     Ok(())
 }
@@ -335,30 +373,70 @@ fn parse_evrx_close_req(req: &[u8]) -> Result<u32, u32> {
     Ok(conn_id)
 }
 
-fn parse_evrv_argv<'a>(argv: &'a [u8]) -> Result<Vec<&'a [u8]>, u32> {
+/// Per-command argv caps: the policy's `X07_OS_DB_REDIS_MAX_ARGV` /
+/// `X07_OS_DB_REDIS_MAX_ARG_BYTES`, lowered (never raised) by the caps blob.
+#[derive(Debug, Clone, Copy)]
+struct ArgvLimits {
+    max_argv: u32,
+    max_arg_bytes: u32,
+}
+
+impl ArgvLimits {
+    fn new(pol: &Policy, caps: dbcore::DbCapsV1) -> Self {
+        ArgvLimits {
+            max_argv: effective_max(pol.max_argv, caps.max_argv),
+            max_arg_bytes: effective_max(pol.max_arg_bytes, caps.max_arg_bytes),
+        }
+    }
+}
+
+/// `DB_ERR_TOO_LARGE` with `argv[index]: <what> exceeds limit <limit>` as detail.
+fn argv_too_large(index: usize, what: std::fmt::Arguments<'_>, limit: u32) -> (u32, Vec<u8>) {
+    (
+        DB_ERR_TOO_LARGE,
+        format!("argv[{index}]: {what} exceeds limit {limit}").into_bytes(),
+    )
+}
+
+fn parse_evrv_argv(argv: &[u8], limits: ArgvLimits) -> Result<Vec<&[u8]>, (u32, Vec<u8>)> {
+    let bad = || (DB_ERR_BAD_REQ, Vec::new());
     if argv.len() < 12 {
-        return Err(DB_ERR_BAD_REQ);
+        return Err(bad());
     }
     if &argv[0..4] != b"X7RV" {
-        return Err(DB_ERR_BAD_REQ);
+        return Err(bad());
     }
-    let ver = read_u32_le(argv, 4).ok_or(DB_ERR_BAD_REQ)?;
+    let ver = read_u32_le(argv, 4).ok_or_else(bad)?;
     if ver != 1 {
-        return Err(DB_ERR_BAD_REQ);
+        return Err(bad());
+    }
+    let count = read_u32_le(argv, 8).ok_or_else(bad)?;
+    if count > limits.max_argv {
+        return Err(argv_too_large(
+            limits.max_argv as usize,
+            format_args!("argument count {count}"),
+            limits.max_argv,
+        ));
     }
-    let count = read_u32_le(argv, 8).ok_or(DB_ERR_BAD_REQ)? as usize;
     let mut off = 12usize;
-    let mut out: Vec<&'a [u8]> = Vec::with_capacity(count);
-    for _ in 0..count {
-        let len = read_u32_le(argv, off).ok_or(DB_ERR_BAD_REQ)? as usize;
+    let mut out: Vec<&[u8]> = Vec::with_capacity(count as usize);
+    for index in 0..count as usize {
+        let len = read_u32_le(argv, off).ok_or_else(bad)?;
         off += 4;
-        let end = off.checked_add(len).ok_or(DB_ERR_BAD_REQ)?;
-        let slice = argv.get(off..end).ok_or(DB_ERR_BAD_REQ)?;
+        if len > limits.max_arg_bytes {
+            return Err(argv_too_large(
+                index,
+                format_args!("{len} bytes"),
+                limits.max_arg_bytes,
+            ));
+        }
+        let end = off.checked_add(len as usize).ok_or_else(bad)?;
+        let slice = argv.get(off..end).ok_or_else(bad)?;
         out.push(slice);
         off = end;
     }
     if off != argv.len() {
-        return Err(DB_ERR_BAD_REQ);
+        return Err(bad());
     }
     Ok(out)
 }
@@ -383,6 +461,113 @@ fn bytes_to_utf8_path(b: &[u8]) -> Result<PathBuf, u32> {
 
 struct RedisConn {
     io: BufStream<DynStream>,
+    pool_key: Vec<u8>,
+    /// Cleared once a command leaves session state behind (pub/sub,
+    /// transactions, `SELECT`, ...); such connections are never pooled.
+    reusable: bool,
+    /// The db chosen at open, under `X07_OS_DB_REDIS_PIN_DB=1`.
+    pinned_db: Option<u32>,
+}
+
+impl RedisConn {
+    fn new(stream: DynStream, pool_key: Vec<u8>) -> Self {
+        RedisConn {
+            io: BufStream::with_capacity(8 * 1024, 8 * 1024, stream),
+            pool_key,
+            reusable: true,
+            pinned_db: None,
+        }
+    }
+}
+
+/// Whether `argv` switches to or writes into another logical database:
+/// `SELECT`, `SWAPDB`, `MOVE` and `COPY ... DB n`. Scripts (`EVAL`, `FCALL`)
+/// run server-side and are not inspected.
+fn cmd_leaves_db(argv: &[&[u8]]) -> bool {
+    const CROSS_DB: &[&[u8]] = &[b"MOVE", b"SELECT", b"SWAPDB"];
+    let Some(name) = argv.first() else {
+        return false;
+    };
+    if CROSS_DB.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+        return true;
+    }
+    name.eq_ignore_ascii_case(b"COPY") && argv.iter().skip(3).any(|a| a.eq_ignore_ascii_case(b"DB"))
+}
+
+fn cmd_keeps_conn_reusable(name: &[u8]) -> bool {
+    const STATEFUL: &[&[u8]] = &[
+        b"AUTH",
+        b"CLIENT",
+        b"HELLO",
+        b"MONITOR",
+        b"MULTI",
+        b"PSUBSCRIBE",
+        b"READONLY",
+        b"READWRITE",
+        b"RESET",
+        b"SELECT",
+        b"SSUBSCRIBE",
+        b"SUBSCRIBE",
+        b"WATCH",
+    ];
+    !STATEFUL.iter().any(|s| s.eq_ignore_ascii_case(name))
+}
+
+fn open_pool_key(pol: &Policy, open: &RedisOpenReq<'_>) -> Vec<u8> {
+    let db = open.db.to_le_bytes();
+    match open.addr {
+        RedisAddr::Tcp { host, port } => {
+            let port = port.to_le_bytes();
+            let tls = [
+                (pol.sandboxed && pol.require_tls) as u8,
+                pol.require_verify as u8,
+            ];
+            dbcore::pool_key(&[b"tcp", host, &port, open.user, open.pass, &db, &tls])
+        }
+        RedisAddr::Unix { path } => dbcore::pool_key(&[b"unix", path, open.user, open.pass, &db]),
+    }
+}
+
+/// Hands back a parked connection for `key` that still answers `PING`;
+/// dead or expired ones are dropped along the way.
+fn checkout_pooled(pol: &Policy, key: &[u8], timeout_ms: u32) -> Option<RedisConn> {
+    let idle_timeout = Duration::from_millis(pol.idle_timeout_ms as u64);
+    while let Some(mut conn) = pool().checkout(key, idle_timeout) {
+        let alive = runtime().block_on(async {
+            let ping = cmd_simple(&mut conn, &[b"PING"], 64);
+            let resp = if timeout_ms != 0 {
+                match tokio::time::timeout(Duration::from_millis(timeout_ms as u64), ping).await {
+                    Ok(r) => r,
+                    Err(_) => return false,
+                }
+            } else {
+                ping.await
+            };
+            matches!(resp, Ok(Resp3::String(ref s)) if s == b"PONG")
+        });
+        if alive {
+            return Some(conn);
+        }
+    }
+    None
+}
+
+fn park_conn(pol: &Policy, conn: RedisConnHandle) {
+    let Ok(conn) = Arc::try_unwrap(conn) else {
+        return;
+    };
+    let conn = conn.into_inner();
+    if !conn.reusable {
+        return;
+    }
+    let idle_timeout = Duration::from_millis(pol.idle_timeout_ms as u64);
+    pool().checkin(
+        conn.pool_key.clone(),
+        conn,
+        pol.max_live_conns,
+        dbcore::live_conn_count(conns()),
+        idle_timeout,
+    );
 }
 
 enum Resp3 {
@@ -393,6 +578,8 @@ enum Resp3 {
     Seq(Vec<Resp3>),
     Map(Vec<(Resp3, Resp3)>),
     Set(Vec<Resp3>),
+    /// Out-of-band `>` frame (pub/sub messages, keyspace notifications).
+    Push(Vec<Resp3>),
     Error(Vec<u8>),
 }
 
@@ -504,7 +691,7 @@ fn read_resp3<'a>(io: &'a mut BufStream<DynStream>, depth: usize) -> Resp3ReadFu
                     Ok(Resp3::Map(entries))
                 }
             }
-            b'~' => {
+            b'~' | b'>' => {
                 let n_b = read_line_crlf(io).await?;
                 let n_s =
                     std::str::from_utf8(&n_b).map_err(|_| (DB_ERR_REDIS_PROTOCOL, Vec::new()))?;
@@ -519,7 +706,11 @@ fn read_resp3<'a>(io: &'a mut BufStream<DynStream>, depth: usize) -> Resp3ReadFu
                 for _ in 0..n {
                     out.push(read_resp3(io, depth - 1).await?);
                 }
-                Ok(Resp3::Set(out))
+                if prefix[0] == b'>' {
+                    Ok(Resp3::Push(out))
+                } else {
+                    Ok(Resp3::Set(out))
+                }
             }
             _ => Err((DB_ERR_REDIS_PROTOCOL, Vec::new())),
         }
@@ -569,6 +760,13 @@ fn resp_to_dm_value(v: Resp3) -> Result<Vec<u8>, u32> {
             vals.sort();
             Ok(dm_value_seq(&vals))
         }
+        Resp3::Push(items) => {
+            let mut vals: Vec<Vec<u8>> = Vec::with_capacity(items.len());
+            for it in items {
+                vals.push(resp_to_dm_value(it)?);
+            }
+            Ok(dm_value_seq(&vals))
+        }
         Resp3::Error(_) => Err(DB_ERR_REDIS_PROTOCOL),
     }
 }
@@ -603,6 +801,46 @@ async fn cmd_simple(
     read_resp3(&mut conn.io, depth).await
 }
 
+/// Waits up to `timeout_ms` (0 = no limit) for the next frame and returns it
+/// if it is a push. Waiting for the first byte is cancel-safe, so `Ok(None)`
+/// leaves a quiet connection usable; a timeout mid-frame is reported as
+/// `timeout` and the caller evicts the connection.
+async fn read_push(conn: &mut RedisConn, timeout_ms: u32) -> Result<Option<Resp3>, (u32, Vec<u8>)> {
+    let deadline = (timeout_ms != 0)
+        .then(|| tokio::time::Instant::now() + Duration::from_millis(timeout_ms as u64));
+
+    let ready = async {
+        conn.io
+            .fill_buf()
+            .await
+            .map(|buf| !buf.is_empty())
+            .map_err(|e| (DB_ERR_REDIS_PROTOCOL, e.to_string().into_bytes()))
+    };
+    let ready = match deadline {
+        Some(deadline) => match tokio::time::timeout_at(deadline, ready).await {
+            Ok(r) => r?,
+            Err(_) => return Ok(None),
+        },
+        None => ready.await?,
+    };
+    if !ready {
+        return Err((DB_ERR_REDIS_PROTOCOL, b"connection closed".to_vec()));
+    }
+
+    let frame = read_resp3(&mut conn.io, 64);
+    let frame = match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, frame)
+            .await
+            .map_err(|_| (DB_ERR_REDIS_CMD, b"timeout".to_vec()))??,
+        None => frame.await?,
+    };
+    match frame {
+        Resp3::Push(_) => Ok(Some(frame)),
+        Resp3::Error(msg) => Err((DB_ERR_REDIS_SERVER, msg)),
+        _ => Err((DB_ERR_REDIS_PROTOCOL, Vec::new())),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn x07_ext_db_redis_open_v1(
     req: dbcore::ev_bytes,
@@ -610,24 +848,46 @@ pub extern "C" fn x07_ext_db_redis_open_v1(
 ) -> dbcore::ev_bytes {
     let req = unsafe { bytes_as_slice(req) };
     let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&open_v1(policy(), req, caps_raw))
+}
 
-    let pol = policy();
+fn open_v1(pol: &Policy, req: &[u8], caps_raw: &[u8]) -> Vec<u8> {
+    open_conn(pol, req, caps_raw, pol.pooling)
+}
+
+/// Like [`x07_ext_db_redis_open_v1`], but always tries the idle pool first,
+/// whatever `X07_OS_DB_POOLING` says. Pair with [`x07_ext_db_redis_release_v1`].
+#[no_mangle]
+pub extern "C" fn x07_ext_db_redis_acquire_v1(
+    req: dbcore::ev_bytes,
+    caps: dbcore::ev_bytes,
+) -> dbcore::ev_bytes {
+    let req = unsafe { bytes_as_slice(req) };
+    let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&open_conn(policy(), req, caps_raw, true))
+}
+
+fn open_conn(pol: &Policy, req: &[u8], caps_raw: &[u8], use_pool: bool) -> Vec<u8> {
     if !pol.enabled || !pol.redis_enabled {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
     }
 
     let caps = match parse_db_caps_v1(caps_raw) {
         Ok(c) => c,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_OPEN_V1, code, &[])),
+        Err(code) => return evdb_err(OP_OPEN_V1, code, &[]),
     };
 
     let open = match parse_evro_open_req(req) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_OPEN_V1, code, &[])),
+        Err(code) => return evdb_err(OP_OPEN_V1, code, &[]),
     };
     if open.flags != 0 {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_BAD_REQ, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_BAD_REQ, &[]);
     }
+    if !db_allowed(pol, open.db) {
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
+    }
+    let pinned_db = pol.pin_db.then_some(open.db);
 
     let connect_timeout_ms = effective_connect_timeout_ms(pol.max_connect_timeout_ms, caps);
     let connect_code = if pol.sandboxed && pol.require_tls {
@@ -636,6 +896,14 @@ pub extern "C" fn x07_ext_db_redis_open_v1(
         DB_ERR_REDIS_CONNECT
     };
 
+    let key = open_pool_key(pol, &open);
+    if use_pool {
+        if let Some(mut conn) = checkout_pooled(pol, &key, connect_timeout_ms) {
+            conn.pinned_db = pinned_db;
+            return open_slot_reply(conn, pol);
+        }
+    }
+
     let conn = match runtime().block_on(async move {
         let fut = async {
             let stream: DynStream = match open.addr {
@@ -655,7 +923,7 @@ pub extern "C" fn x07_ext_db_redis_open_v1(
                             tls_config_no_verify()
                         };
                         let connector = TlsConnector::from(Arc::new(cfg));
-                        let server_name = ServerName::try_from(host_s)
+                        let server_name = ServerName::try_from(host_s.to_owned())
                             .map_err(|_| (DB_ERR_BAD_REQ, Vec::new()))?;
                         let tls = connector
                             .connect(server_name, tcp)
@@ -690,9 +958,7 @@ pub extern "C" fn x07_ext_db_redis_open_v1(
                 }
             };
 
-            let mut conn = RedisConn {
-                io: BufStream::with_capacity(8 * 1024, 8 * 1024, stream),
-            };
+            let mut conn = RedisConn::new(stream, key);
 
             let hello = cmd_simple(&mut conn, &[b"HELLO", b"3"], 64).await?;
             if let Resp3::Error(msg) = hello {
@@ -730,14 +996,27 @@ pub extern "C" fn x07_ext_db_redis_open_v1(
             fut.await
         }
     }) {
-        Ok(v) => v,
-        Err((code, msg)) => return alloc_return_bytes(&evdb_err(OP_OPEN_V1, code, &msg)),
+        Ok(mut conn) => {
+            conn.pinned_db = pinned_db;
+            conn
+        }
+        Err((code, msg)) => return evdb_err(OP_OPEN_V1, code, &msg),
     };
 
+    open_slot_reply(conn, pol)
+}
+
+/// OK payload: `conn_id`, then the pinned db when the connection has one.
+fn open_slot_reply(conn: RedisConn, pol: &Policy) -> Vec<u8> {
+    let pinned_db = conn.pinned_db;
     let Some(conn_id) = open_slot(conn, pol) else {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_TOO_LARGE, &[]);
     };
-    alloc_return_bytes(&evdb_ok(OP_OPEN_V1, &conn_id.to_le_bytes()))
+    let mut payload = conn_id.to_le_bytes().to_vec();
+    if let Some(db) = pinned_db {
+        payload.extend_from_slice(&db.to_le_bytes());
+    }
+    evdb_ok(OP_OPEN_V1, &payload)
 }
 
 #[no_mangle]
@@ -746,22 +1025,70 @@ pub extern "C" fn x07_ext_db_redis_close_v1(
     _caps: dbcore::ev_bytes,
 ) -> dbcore::ev_bytes {
     let req = unsafe { bytes_as_slice(req) };
+    alloc_return_bytes(&close_v1(policy(), req))
+}
 
-    let pol = policy();
+/// With `X07_OS_DB_POOLING=1` the connection is parked for reuse by a later
+/// open of the same target instead of being dropped; the conn_id is freed
+/// either way.
+fn close_v1(pol: &Policy, req: &[u8]) -> Vec<u8> {
     if !pol.enabled || !pol.redis_enabled {
-        return alloc_return_bytes(&evdb_err(OP_CLOSE_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_CLOSE_V1, DB_ERR_POLICY_DENIED, &[]);
     }
 
     let conn_id = match parse_evrx_close_req(req) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_CLOSE_V1, code, &[])),
+        Err(code) => return evdb_err(OP_CLOSE_V1, code, &[]),
+    };
+
+    close_conn(pol, conn_id, pol.pooling)
+}
+
+/// Frees `conn_id` and parks the connection in the idle pool for a later
+/// [`x07_ext_db_redis_acquire_v1`] of the same target, whatever
+/// `X07_OS_DB_POOLING` says. Connections left in session state are closed.
+#[no_mangle]
+pub extern "C" fn x07_ext_db_redis_release_v1(conn_id: u32) -> dbcore::ev_bytes {
+    let pol = policy();
+    if !pol.enabled || !pol.redis_enabled {
+        return alloc_return_bytes(&evdb_err(OP_CLOSE_V1, DB_ERR_POLICY_DENIED, &[]));
+    }
+    alloc_return_bytes(&close_conn(pol, conn_id, true))
+}
+
+fn close_conn(pol: &Policy, conn_id: u32, park: bool) -> Vec<u8> {
+    let Some(conn) = take_conn(conn_id) else {
+        return evdb_err(OP_CLOSE_V1, DB_ERR_BAD_CONN, &[]);
     };
+    if park {
+        park_conn(pol, conn);
+    }
+
+    evdb_ok(OP_CLOSE_V1, &[])
+}
 
-    if take_conn(conn_id).is_none() {
-        return alloc_return_bytes(&evdb_err(OP_CLOSE_V1, DB_ERR_BAD_CONN, &[]));
+fn stats() -> dbcore::DbStatsV1 {
+    let pol = policy();
+    dbcore::DbStatsV1 {
+        live_conns: dbcore::live_conn_count(conns()),
+        max_live_conns: pol.max_live_conns,
+        queries_used: QUERIES.load(Ordering::Relaxed),
+        max_queries: pol.max_queries,
+        op_latency: OP_LATENCY.snapshot(),
     }
+}
+
+#[no_mangle]
+pub extern "C" fn x07_ext_db_redis_stats_v1() -> dbcore::ev_bytes {
+    alloc_return_bytes(&stats().to_dm_doc())
+}
 
-    alloc_return_bytes(&evdb_ok(OP_CLOSE_V1, &[]))
+/// Copies the query/exec/cmd wall-time histogram (bucket bounds in
+/// [`dbcore::LATENCY_BUCKET_LABELS`]) into `out`, which must hold
+/// [`dbcore::LATENCY_BUCKETS`] counters, and returns the bucket count.
+#[no_mangle]
+pub unsafe extern "C" fn x07_ext_db_redis_latency_hist_v1(out: *mut u64) -> u32 {
+    dbcore::copy_latency_hist(&OP_LATENCY, out)
 }
 
 #[no_mangle]
@@ -771,10 +1098,14 @@ pub extern "C" fn x07_ext_db_redis_cmd_v1(
 ) -> dbcore::ev_bytes {
     let req = unsafe { bytes_as_slice(req) };
     let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&dbcore::timed_op(&OP_LATENCY, || {
+        cmd_v1(policy(), req, caps_raw)
+    }))
+}
 
-    let pol = policy();
+fn cmd_v1(pol: &Policy, req: &[u8], caps_raw: &[u8]) -> Vec<u8> {
     if !pol.enabled || !pol.redis_enabled {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_POLICY_DENIED, &[]);
     }
     if let Err(out) = count_query_or_deny(pol, OP_QUERY_V1) {
         return out;
@@ -782,25 +1113,25 @@ pub extern "C" fn x07_ext_db_redis_cmd_v1(
 
     let caps = match parse_db_caps_v1(caps_raw) {
         Ok(c) => c,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_QUERY_V1, code, &[])),
+        Err(code) => return evdb_err(OP_QUERY_V1, code, &[]),
     };
 
     let (conn_id, _flags, argv_bytes) = match parse_evrq_cmd_req(req) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_QUERY_V1, code, &[])),
+        Err(code) => return evdb_err(OP_QUERY_V1, code, &[]),
     };
 
     if argv_bytes.len() > pol.max_req_bytes as usize {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]);
     }
 
-    let argv = match parse_evrv_argv(argv_bytes) {
+    let argv = match parse_evrv_argv(argv_bytes, ArgvLimits::new(pol, caps)) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_QUERY_V1, code, &[])),
+        Err((code, detail)) => return evdb_err(OP_QUERY_V1, code, &detail),
     };
 
     let Some(conn) = get_conn(conn_id) else {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, &[]);
     };
 
     let timeout_ms = effective_query_timeout_ms(pol.max_query_timeout_ms, caps);
@@ -808,6 +1139,19 @@ pub extern "C" fn x07_ext_db_redis_cmd_v1(
     let doc = match runtime().block_on(async move {
         let fut = async {
             let mut conn = conn.lock().await;
+            if let Some(db) = conn.pinned_db.filter(|_| cmd_leaves_db(&argv)) {
+                let name = String::from_utf8_lossy(argv[0]).to_ascii_uppercase();
+                return Err((
+                    DB_ERR_POLICY_DENIED,
+                    format!("{name} denied: connection is pinned to db {db}").into_bytes(),
+                ));
+            }
+            if argv
+                .first()
+                .is_some_and(|name| !cmd_keeps_conn_reusable(name))
+            {
+                conn.reusable = false;
+            }
             let resp = cmd_simple(&mut conn, &argv, 64).await?;
             if let Resp3::Error(msg) = resp {
                 return Err((DB_ERR_REDIS_SERVER, msg));
@@ -829,14 +1173,885 @@ pub extern "C" fn x07_ext_db_redis_cmd_v1(
             if msg.as_slice() == b"timeout" {
                 dbcore::evict_conn_slot(conns(), conn_id);
             }
-            return alloc_return_bytes(&evdb_err(OP_QUERY_V1, code, &msg));
+            return evdb_err(OP_QUERY_V1, code, &msg);
         }
     };
 
     let max_resp = effective_max(pol.max_resp_bytes, caps.max_resp_bytes);
     if max_resp != 0 && doc.len() > max_resp as usize {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]);
     }
 
-    alloc_return_bytes(&evdb_ok(OP_QUERY_V1, &doc))
+    evdb_ok(OP_QUERY_V1, &doc)
+}
+
+/// Drains one pending push message (pub/sub message or keyspace notification)
+/// from `conn_id` and returns it as a `dm_value` seq, or null when none arrives
+/// within `timeout_ms` (capped by `X07_OS_DB_MAX_QUERY_TIMEOUT_MS`; 0 means the
+/// policy max). Only useful after `SUBSCRIBE`/`PSUBSCRIBE` through `cmd_v1`:
+/// from then on the connection is dedicated to pub/sub and must not be used
+/// for regular commands. Pushes larger than `X07_OS_DB_MAX_RESP_BYTES` are
+/// consumed and reported as `DB_ERR_TOO_LARGE`. Each call counts as one query.
+#[no_mangle]
+pub extern "C" fn x07_ext_db_redis_get_push_v1(conn_id: u32, timeout_ms: u32) -> dbcore::ev_bytes {
+    alloc_return_bytes(&dbcore::timed_op(&OP_LATENCY, || {
+        get_push_v1(policy(), conn_id, timeout_ms)
+    }))
+}
+
+fn get_push_v1(pol: &Policy, conn_id: u32, timeout_ms: u32) -> Vec<u8> {
+    if !pol.enabled || !pol.redis_enabled {
+        return evdb_err(OP_QUERY_V1, DB_ERR_POLICY_DENIED, &[]);
+    }
+    if let Err(out) = count_query_or_deny(pol, OP_QUERY_V1) {
+        return out;
+    }
+
+    let Some(conn) = get_conn(conn_id) else {
+        return evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, &[]);
+    };
+
+    let timeout_ms = effective_max(pol.max_query_timeout_ms, timeout_ms);
+
+    let doc = match runtime().block_on(async move {
+        let mut conn = conn.lock().await;
+        let value = match read_push(&mut conn, timeout_ms).await? {
+            Some(push) => resp_to_dm_value(push).map_err(|code| (code, Vec::new()))?,
+            None => dm_value_null(),
+        };
+        Ok::<Vec<u8>, (u32, Vec<u8>)>(dm_doc_ok(&value))
+    }) {
+        Ok(v) => v,
+        Err((code, msg)) => {
+            if msg.as_slice() == b"timeout" {
+                dbcore::evict_conn_slot(conns(), conn_id);
+            }
+            return evdb_err(OP_QUERY_V1, code, &msg);
+        }
+    };
+
+    if pol.max_resp_bytes != 0 && doc.len() > pol.max_resp_bytes as usize {
+        return evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]);
+    }
+
+    evdb_ok(OP_QUERY_V1, &doc)
+}
+
+/// Pages through `SCAN` until the cursor wraps to 0 (`complete`) or
+/// `max_keys` keys were collected. Keys come back deduplicated and sorted;
+/// a page that would overshoot `max_keys` is cut and reported incomplete.
+async fn scan_keys(
+    conn: &mut RedisConn,
+    pattern: &[u8],
+    count_hint: u32,
+    max_keys: usize,
+) -> Result<(Vec<Vec<u8>>, bool), (u32, Vec<u8>)> {
+    let mut count_buf = itoa::Buffer::new();
+    let count_s = count_buf.format(count_hint).as_bytes().to_vec();
+    let mut cursor: Vec<u8> = b"0".to_vec();
+    let mut keys: std::collections::BTreeSet<Vec<u8>> = std::collections::BTreeSet::new();
+
+    loop {
+        let mut argv: Vec<&[u8]> = vec![b"SCAN", &cursor];
+        if !pattern.is_empty() {
+            argv.extend_from_slice(&[b"MATCH", pattern]);
+        }
+        if count_hint != 0 {
+            argv.extend_from_slice(&[b"COUNT", &count_s]);
+        }
+        let resp = cmd_simple(conn, &argv, 8).await?;
+        let (next, page) = match resp {
+            Resp3::Seq(mut items) if items.len() == 2 => {
+                let page = items.pop();
+                let next = items.pop();
+                match (next, page) {
+                    (Some(Resp3::String(next)), Some(Resp3::Seq(page))) => (next, page),
+                    _ => return Err((DB_ERR_REDIS_PROTOCOL, Vec::new())),
+                }
+            }
+            Resp3::Error(msg) => return Err((DB_ERR_REDIS_SERVER, msg)),
+            _ => return Err((DB_ERR_REDIS_PROTOCOL, Vec::new())),
+        };
+        for key in page {
+            if keys.len() >= max_keys {
+                return Ok((keys.into_iter().collect(), false));
+            }
+            keys.insert(key_bytes(key).map_err(|code| (code, Vec::new()))?);
+        }
+        if next.as_slice() == b"0" {
+            return Ok((keys.into_iter().collect(), true));
+        }
+        if keys.len() >= max_keys {
+            return Ok((keys.into_iter().collect(), false));
+        }
+        cursor = next;
+    }
+}
+
+/// Iterates the keyspace with `SCAN` (never `KEYS`) and returns a `dm_value`
+/// map `{complete: bool, keys: seq}`. Stops once the cursor wraps
+/// (`complete = true`) or `max_keys` keys were collected, capped by
+/// `X07_OS_DB_REDIS_MAX_SCAN_KEYS` (0 means the policy max). An empty
+/// `match_pattern` scans every key and `count_hint = 0` leaves `COUNT` to the
+/// server. The whole loop shares one query timeout and counts as one query.
+#[no_mangle]
+pub extern "C" fn x07_ext_db_redis_scan_v1(
+    conn_id: u32,
+    match_pattern: dbcore::ev_bytes,
+    count_hint: u32,
+    max_keys: u32,
+    caps: dbcore::ev_bytes,
+) -> dbcore::ev_bytes {
+    let pattern = unsafe { bytes_as_slice(match_pattern) };
+    let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&scan_v1(
+        policy(),
+        conn_id,
+        pattern,
+        count_hint,
+        max_keys,
+        caps_raw,
+    ))
+}
+
+fn scan_v1(
+    pol: &Policy,
+    conn_id: u32,
+    pattern: &[u8],
+    count_hint: u32,
+    max_keys: u32,
+    caps_raw: &[u8],
+) -> Vec<u8> {
+    if !pol.enabled || !pol.redis_enabled {
+        return evdb_err(OP_QUERY_V1, DB_ERR_POLICY_DENIED, &[]);
+    }
+    if let Err(out) = count_query_or_deny(pol, OP_QUERY_V1) {
+        return out;
+    }
+
+    let caps = match parse_db_caps_v1(caps_raw) {
+        Ok(c) => c,
+        Err(code) => return evdb_err(OP_QUERY_V1, code, &[]),
+    };
+    if pattern.len() > pol.max_req_bytes as usize {
+        return evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]);
+    }
+
+    let Some(conn) = get_conn(conn_id) else {
+        return evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, &[]);
+    };
+
+    let max_keys = effective_max(pol.max_scan_keys, max_keys);
+    let max_keys = if max_keys == 0 {
+        usize::MAX
+    } else {
+        max_keys as usize
+    };
+    let timeout_ms = effective_query_timeout_ms(pol.max_query_timeout_ms, caps);
+
+    let doc = match runtime().block_on(async move {
+        let fut = async {
+            let mut conn = conn.lock().await;
+            let (keys, complete) = scan_keys(&mut conn, pattern, count_hint, max_keys).await?;
+            let keys: Vec<Vec<u8>> = keys.iter().map(|k| dm_value_string(k)).collect();
+            let value = dm_value_map(vec![
+                (b"complete".to_vec(), dm_value_bool(complete)),
+                (b"keys".to_vec(), dm_value_seq(&keys)),
+            ])
+            .map_err(|code| (code, Vec::new()))?;
+            Ok::<Vec<u8>, (u32, Vec<u8>)>(dm_doc_ok(&value))
+        };
+
+        if timeout_ms != 0 {
+            tokio::time::timeout(Duration::from_millis(timeout_ms as u64), fut)
+                .await
+                .map_err(|_| (DB_ERR_REDIS_CMD, b"timeout".to_vec()))?
+        } else {
+            fut.await
+        }
+    }) {
+        Ok(v) => v,
+        Err((code, msg)) => {
+            if msg.as_slice() == b"timeout" {
+                dbcore::evict_conn_slot(conns(), conn_id);
+            }
+            return evdb_err(OP_QUERY_V1, code, &msg);
+        }
+    };
+
+    let max_resp = effective_max(pol.max_resp_bytes, caps.max_resp_bytes);
+    if max_resp != 0 && doc.len() > max_resp as usize {
+        return evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]);
+    }
+
+    evdb_ok(OP_QUERY_V1, &doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    dbcore::ev_test_stubs!();
+
+    // Serializes tests that open and close slots in the shared conn table.
+    static CONN_TABLE_LOCK: Mutex<()> = Mutex::new(());
+
+    fn dummy_conn() -> RedisConn {
+        let (client, _server) = tokio::io::duplex(64);
+        RedisConn::new(Box::pin(client), Vec::new())
+    }
+
+    #[test]
+    fn get_push_drains_one_push_and_times_out_when_idle() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut conn = RedisConn::new(Box::pin(client), Vec::new());
+
+        runtime().block_on(async {
+            assert!(read_push(&mut conn, 20).await.expect("idle").is_none());
+
+            server
+                .write_all(b">3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$5\r\nhello\r\n")
+                .await
+                .unwrap();
+            let push = read_push(&mut conn, 1_000).await.expect("push");
+            let value = resp_to_dm_value(push.expect("some push")).unwrap();
+            let expected = dm_value_seq(&[
+                dm_value_string(b"message"),
+                dm_value_string(b"ch"),
+                dm_value_string(b"hello"),
+            ]);
+            assert_eq!(value, expected);
+
+            // A regular reply is not a push.
+            server.write_all(b"+OK\r\n").await.unwrap();
+            let err = read_push(&mut conn, 1_000).await.err().expect("not a push");
+            assert_eq!(err.0, DB_ERR_REDIS_PROTOCOL);
+        });
+    }
+
+    #[test]
+    fn stats_tracks_live_conns() {
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        let pol = policy();
+        let before = stats();
+        assert_eq!(before.max_live_conns, pol.max_live_conns);
+        assert_eq!(before.max_queries, pol.max_queries);
+
+        let conn_id = open_slot(dummy_conn(), pol).expect("open slot");
+        let opened = stats();
+        assert_eq!(opened.live_conns, before.live_conns + 1);
+
+        let out = x07_ext_db_redis_stats_v1();
+        let doc = unsafe { bytes_as_slice(out) };
+        assert_eq!(doc, opened.to_dm_doc().as_slice());
+        assert_eq!(doc[..2], [1, 5]);
+
+        take_conn(conn_id).expect("take conn");
+        assert_eq!(stats().live_conns, before.live_conns);
+    }
+
+    #[test]
+    fn get_push_is_budgeted_and_timed() {
+        QUERIES.fetch_add(1, Ordering::Relaxed);
+        let pol = Policy {
+            max_queries: QUERIES.load(Ordering::Relaxed),
+            ..test_policy(false, 0)
+        };
+        // Denied by the budget before the (bad) conn id is looked at.
+        let out = get_push_v1(&pol, 0, 0);
+        assert_eq!(read_u32_le(&out, 8), Some(0));
+        assert_eq!(read_u32_le(&out, 16), Some(DB_ERR_POLICY_DENIED));
+
+        let out = unsafe { bytes_as_slice(x07_ext_db_redis_get_push_v1(0, 0)) };
+        assert_eq!(read_u32_le(out, 8), Some(0));
+        assert!(
+            out.windows(b"elapsed_ms=".len())
+                .any(|w| w == b"elapsed_ms="),
+            "{out:?}"
+        );
+    }
+
+    fn test_policy(pooling: bool, idle_timeout_ms: u32) -> Policy {
+        Policy {
+            sandboxed: false,
+            enabled: true,
+            redis_enabled: true,
+            allow_dns: Vec::new(),
+            allow_cidrs: Vec::new(),
+            allow_ports: Vec::new(),
+            require_tls: false,
+            require_verify: false,
+            max_live_conns: 8,
+            max_queries: 0,
+            max_connect_timeout_ms: 5_000,
+            max_query_timeout_ms: 5_000,
+            max_resp_bytes: 0,
+            max_req_bytes: 1024 * 1024,
+            pooling,
+            idle_timeout_ms,
+            max_scan_keys: 10_000,
+            max_argv: 1024,
+            max_arg_bytes: 1024 * 1024,
+            pin_db: false,
+            allowed_dbs: None,
+        }
+    }
+
+    /// Plain-TCP RESP server that answers every command and counts accepted
+    /// connections, i.e. `HELLO` handshakes.
+    fn counting_server() -> (u16, Arc<AtomicU32>) {
+        use std::io::{BufRead as _, BufReader, Write as _};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accepted = Arc::new(AtomicU32::new(0));
+        let counter = accepted.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    break;
+                };
+                counter.fetch_add(1, Ordering::SeqCst);
+                std::thread::spawn(move || {
+                    let mut out = stream.try_clone().unwrap();
+                    let mut lines = BufReader::new(stream).lines().map_while(Result::ok);
+                    while let Some(header) = lines.next() {
+                        let argc: usize = header.trim_start_matches('*').parse().unwrap_or(0);
+                        let mut argv = Vec::with_capacity(argc);
+                        for _ in 0..argc {
+                            let _len = lines.next();
+                            argv.push(lines.next().unwrap_or_default());
+                        }
+                        let reply: &[u8] = match argv.first().map(String::as_str) {
+                            Some("HELLO") => b"%1\r\n+proto\r\n:3\r\n",
+                            Some("PING") => b"+PONG\r\n",
+                            _ => b"+OK\r\n",
+                        };
+                        if out.write_all(reply).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (port, accepted)
+    }
+
+    fn open_req(port: u16) -> Vec<u8> {
+        open_req_db(port, 0)
+    }
+
+    fn open_req_db(port: u16, db: u32) -> Vec<u8> {
+        let host = b"127.0.0.1";
+        let mut out = Vec::new();
+        out.extend_from_slice(b"X7RO");
+        for v in [1u32, 0, 1, host.len() as u32] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(host);
+        for v in [port as u32, 0, 0, db] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out
+    }
+
+    fn close_req(conn_id: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"X7RX");
+        for v in [1u32, 0, conn_id] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out
+    }
+
+    fn caps() -> Vec<u8> {
+        let mut out = b"X7DC".to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&[0; 16]);
+        out
+    }
+
+    #[test]
+    fn pooling_skips_handshakes_on_reopen() {
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        for (pooling, idle_timeout_ms, handshakes) in
+            [(false, 30_000, 3), (true, 30_000, 1), (true, 1, 3)]
+        {
+            let pol = test_policy(pooling, idle_timeout_ms);
+            let (port, accepted) = counting_server();
+            let live_before = dbcore::live_conn_count(conns());
+            for _ in 0..3 {
+                let out = open_v1(&pol, &open_req(port), &caps());
+                assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+                let conn_id = read_u32_le(&out, 20).unwrap();
+                assert_eq!(dbcore::live_conn_count(conns()), live_before + 1);
+
+                let out = close_v1(&pol, &close_req(conn_id));
+                assert_eq!(read_u32_le(&out, 8), Some(1), "close failed: {out:?}");
+                assert_eq!(dbcore::live_conn_count(conns()), live_before);
+                // A closed conn_id stays closed even when the connection is parked.
+                let out = close_v1(&pol, &close_req(conn_id));
+                assert_eq!(read_u32_le(&out, 8), Some(0));
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            assert_eq!(
+                accepted.load(Ordering::SeqCst),
+                handshakes,
+                "pooling={pooling} idle_timeout_ms={idle_timeout_ms}"
+            );
+        }
+    }
+
+    #[test]
+    fn acquire_after_release_reuses_the_connection() {
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        // Pooling is off in the policy; acquire/release pool regardless.
+        let pol = test_policy(false, 30_000);
+        let (port, accepted) = counting_server();
+        let live_before = dbcore::live_conn_count(conns());
+
+        for _ in 0..3 {
+            let out = open_conn(&pol, &open_req(port), &caps(), true);
+            assert_eq!(read_u32_le(&out, 8), Some(1), "acquire failed: {out:?}");
+            let conn_id = read_u32_le(&out, 20).unwrap();
+            assert_eq!(dbcore::live_conn_count(conns()), live_before + 1);
+
+            let out = close_conn(&pol, conn_id, true);
+            assert_eq!(read_u32_le(&out, 8), Some(1), "release failed: {out:?}");
+            assert_eq!(dbcore::live_conn_count(conns()), live_before);
+            let out = close_conn(&pol, conn_id, true);
+            assert_eq!(read_u32_le(&out, 8), Some(0));
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        // A plain open still connects fresh under this policy, even with a
+        // parked connection for the same target.
+        let out = open_v1(&pol, &open_req(port), &caps());
+        let conn_id = read_u32_le(&out, 20).unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        close_v1(&pol, &close_req(conn_id));
+    }
+
+    fn scan_reply(cursor: &str, keys: &[&str]) -> Vec<u8> {
+        let mut out = format!("*2\r\n${}\r\n{cursor}\r\n*{}\r\n", cursor.len(), keys.len());
+        for k in keys {
+            out.push_str(&format!("${}\r\n{k}\r\n", k.len()));
+        }
+        out.into_bytes()
+    }
+
+    type ScanResult = Result<(Vec<Vec<u8>>, bool), (u32, Vec<u8>)>;
+
+    /// Runs `scan_keys` against scripted replies; returns its result and the
+    /// number of `SCAN` commands the client sent.
+    fn scripted_scan(
+        replies: &[Vec<u8>],
+        pattern: &[u8],
+        count_hint: u32,
+        max_keys: usize,
+    ) -> (ScanResult, usize, Vec<u8>) {
+        let (client, mut server) = tokio::io::duplex(64 * 1024);
+        let mut conn = RedisConn::new(Box::pin(client), Vec::new());
+        runtime().block_on(async {
+            for r in replies {
+                server.write_all(r).await.unwrap();
+            }
+            let res = scan_keys(&mut conn, pattern, count_hint, max_keys).await;
+            let mut sent = vec![0u8; 64 * 1024];
+            let n = server.read(&mut sent).await.unwrap();
+            sent.truncate(n);
+            let scans = sent.windows(6).filter(|w| w == b"\r\nSCAN").count();
+            (res, scans, sent)
+        })
+    }
+
+    #[test]
+    fn scan_follows_cursor_until_it_wraps() {
+        let replies = [
+            scan_reply("17", &["b", "a"]),
+            scan_reply("9", &[]),
+            scan_reply("0", &["c", "a"]),
+        ];
+        let (res, scans, sent) = scripted_scan(&replies, b"user:*", 100, 10);
+        let (keys, complete) = res.expect("scan ok");
+        assert!(complete);
+        assert_eq!(keys, vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+        assert_eq!(scans, 3);
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("$5\r\nMATCH\r\n$6\r\nuser:*\r\n$5\r\nCOUNT\r\n$3\r\n100\r\n"));
+        assert!(sent.contains("$4\r\nSCAN\r\n$2\r\n17\r\n"));
+        assert!(!sent.contains("KEYS"));
+    }
+
+    #[test]
+    fn scan_stops_at_max_keys() {
+        let replies = [
+            scan_reply("5", &["k1", "k2"]),
+            scan_reply("6", &["k3", "k4"]),
+            scan_reply("0", &["k5"]),
+        ];
+        // Cut mid-page: the rest of that page is dropped and no SCAN follows.
+        let (res, scans, _) = scripted_scan(&replies, b"", 0, 3);
+        let (keys, complete) = res.expect("scan ok");
+        assert!(!complete);
+        assert_eq!(keys, vec![b"k1".to_vec(), b"k2".to_vec(), b"k3".to_vec()]);
+        assert_eq!(scans, 2);
+
+        // Reaching the cap on a page boundary also stops before the next SCAN.
+        let (res, scans, sent) = scripted_scan(&replies, b"", 0, 2);
+        assert!(!res.expect("scan ok").1);
+        assert_eq!(scans, 1);
+        assert!(!String::from_utf8(sent).unwrap().contains("MATCH"));
+
+        // The cap is not hit when the cursor wraps on the same page.
+        let (res, _, _) = scripted_scan(&[scan_reply("0", &["x", "y"])], b"", 0, 2);
+        assert!(res.expect("scan ok").1);
+    }
+
+    #[test]
+    fn scan_surfaces_server_errors() {
+        let (res, scans, _) = scripted_scan(&[b"-ERR invalid cursor\r\n".to_vec()], b"", 0, 10);
+        let (code, msg) = res.expect_err("server error");
+        assert_eq!(code, DB_ERR_REDIS_SERVER);
+        assert_eq!(msg, b"ERR invalid cursor");
+        assert_eq!(scans, 1);
+    }
+
+    /// Needs a reachable plaintext Redis at `X07_TEST_REDIS_ADDR` (`host:port`);
+    /// skipped otherwise. Seeds its own keys under a unique prefix.
+    #[test]
+    fn scan_v1_over_seeded_keyspace() {
+        let Ok(addr) = std::env::var("X07_TEST_REDIS_ADDR") else {
+            return;
+        };
+        let (_, port) = addr.rsplit_once(':').expect("host:port");
+        let port: u16 = port.parse().expect("port");
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        let pol = test_policy(false, 30_000);
+
+        let out = open_v1(&pol, &open_req(port), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+        let conn_id = read_u32_le(&out, 20).unwrap();
+
+        let prefix = format!("x07scan:{}:", std::process::id());
+        runtime().block_on(async {
+            let conn = get_conn(conn_id).unwrap();
+            let mut conn = conn.lock().await;
+            for i in 0..250 {
+                let key = format!("{prefix}{i:03}");
+                cmd_simple(&mut conn, &[b"SET", key.as_bytes(), b"1"], 8)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let pattern = format!("{prefix}*");
+        let out = scan_v1(&pol, conn_id, pattern.as_bytes(), 50, 0, &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "scan failed: {out:?}");
+        let keys: Vec<Vec<u8>> = (0..250)
+            .map(|i| dm_value_string(format!("{prefix}{i:03}").as_bytes()))
+            .collect();
+        let expected = dm_doc_ok(
+            &dm_value_map(vec![
+                (b"complete".to_vec(), dm_value_bool(true)),
+                (b"keys".to_vec(), dm_value_seq(&keys)),
+            ])
+            .unwrap(),
+        );
+        assert_eq!(out, evdb_ok(OP_QUERY_V1, &expected));
+
+        // Which 120 keys come back depends on the server's hash order.
+        let full = out;
+        let out = scan_v1(&pol, conn_id, pattern.as_bytes(), 50, 120, &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "scan failed: {out:?}");
+        assert!(out.len() < full.len());
+
+        runtime().block_on(async {
+            let conn = get_conn(conn_id).unwrap();
+            let mut conn = conn.lock().await;
+            for i in 0..250 {
+                let key = format!("{prefix}{i:03}");
+                let _ = cmd_simple(&mut conn, &[b"DEL", key.as_bytes()], 8).await;
+            }
+        });
+        close_v1(&pol, &close_req(conn_id));
+    }
+
+    /// Needs a reachable plaintext Redis at `X07_TEST_REDIS_ADDR` (`host:port`);
+    /// skipped otherwise.
+    #[test]
+    fn cmd_v1_fills_the_latency_histogram() {
+        let Ok(addr) = std::env::var("X07_TEST_REDIS_ADDR") else {
+            return;
+        };
+        let (_, port) = addr.rsplit_once(':').expect("host:port");
+        let port: u16 = port.parse().expect("port");
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        let pol = test_policy(false, 30_000);
+
+        let out = open_v1(&pol, &open_req(port), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+        let conn_id = read_u32_le(&out, 20).unwrap();
+
+        let ev = |b: &[u8]| dbcore::ev_bytes {
+            ptr: b.as_ptr() as *mut u8,
+            len: b.len() as u32,
+        };
+        let caps = caps();
+        let cmds: [&[&[u8]]; 3] = [&[b"PING"], &[b"ECHO", b"x07"], &[b"NOSUCHCMD"]];
+        for argv in cmds {
+            let req = cmd_req(conn_id, argv);
+            let out = x07_ext_db_redis_cmd_v1(ev(&req), ev(&caps));
+            let out = unsafe { bytes_as_slice(out) };
+            if read_u32_le(out, 8) == Some(0) {
+                let msg_len = read_u32_le(out, 20).unwrap() as usize;
+                let msg = String::from_utf8_lossy(&out[24..24 + msg_len]).into_owned();
+                assert!(msg.contains("elapsed_ms="), "{msg}");
+            }
+        }
+
+        let mut hist = [0u64; dbcore::LATENCY_BUCKETS];
+        let n = unsafe { x07_ext_db_redis_latency_hist_v1(hist.as_mut_ptr()) };
+        assert_eq!(n as usize, dbcore::LATENCY_BUCKETS);
+        assert!(hist.iter().sum::<u64>() >= 3, "{hist:?}");
+        assert_eq!(stats().op_latency, hist);
+        close_v1(&pol, &close_req(conn_id));
+    }
+
+    fn cmd_req(conn_id: u32, args: &[&[u8]]) -> Vec<u8> {
+        let argv = argv_bytes(args);
+        let mut req = b"X7RQ".to_vec();
+        for v in [1, 0, conn_id, argv.len() as u32] {
+            req.extend_from_slice(&v.to_le_bytes());
+        }
+        req.extend_from_slice(&argv);
+        req
+    }
+
+    #[test]
+    fn allowed_dbs_are_checked_before_connecting() {
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        let mut pol = test_policy(false, 30_000);
+        pol.allowed_dbs = Some(vec![0, 2]);
+        let (port, accepted) = counting_server();
+
+        let out = open_v1(&pol, &open_req_db(port, 5), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(0));
+        assert_eq!(read_u32_le(&out, 16), Some(DB_ERR_POLICY_DENIED));
+        assert_eq!(accepted.load(Ordering::SeqCst), 0);
+
+        let out = open_v1(&pol, &open_req_db(port, 2), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+        close_v1(&pol, &close_req(read_u32_le(&out, 20).unwrap()));
+
+        // A set list with nothing usable in it allows nothing.
+        pol.allowed_dbs = Some(Vec::new());
+        let out = open_v1(&pol, &open_req(port), &caps());
+        assert_eq!(read_u32_le(&out, 16), Some(DB_ERR_POLICY_DENIED));
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn pinned_connections_cannot_leave_their_db() {
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        let mut pol = test_policy(false, 30_000);
+        pol.pin_db = true;
+        let (port, _) = counting_server();
+
+        let out = open_v1(&pol, &open_req_db(port, 3), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+        assert_eq!(read_u32_le(&out, 16), Some(8));
+        let conn_id = read_u32_le(&out, 20).unwrap();
+        assert_eq!(read_u32_le(&out, 24), Some(3));
+
+        let denied: [&[&[u8]]; 5] = [
+            &[b"SELECT", b"5"],
+            &[b"select", b"3"],
+            &[b"SWAPDB", b"3", b"0"],
+            &[b"MOVE", b"k", b"0"],
+            &[b"COPY", b"a", b"b", b"db", b"0"],
+        ];
+        for argv in denied {
+            let out = cmd_v1(&pol, &cmd_req(conn_id, argv), &caps());
+            assert_eq!(read_u32_le(&out, 8), Some(0), "{argv:?}");
+            assert_eq!(read_u32_le(&out, 16), Some(DB_ERR_POLICY_DENIED));
+            let msg_len = read_u32_le(&out, 20).unwrap() as usize;
+            let msg = String::from_utf8_lossy(&out[24..24 + msg_len]).into_owned();
+            assert!(msg.ends_with("pinned to db 3"), "{msg}");
+        }
+
+        let allowed: [&[&[u8]]; 2] = [&[b"GET", b"k"], &[b"COPY", b"a", b"b", b"REPLACE"]];
+        for argv in allowed {
+            let out = cmd_v1(&pol, &cmd_req(conn_id, argv), &caps());
+            assert_eq!(read_u32_le(&out, 8), Some(1), "{argv:?}: {out:?}");
+        }
+        close_v1(&pol, &close_req(conn_id));
+    }
+
+    #[test]
+    fn unpinned_connections_may_select() {
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        let pol = test_policy(false, 30_000);
+        let (port, _) = counting_server();
+
+        let out = open_v1(&pol, &open_req_db(port, 3), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+        assert_eq!(read_u32_le(&out, 16), Some(4));
+        let conn_id = read_u32_le(&out, 20).unwrap();
+
+        let out = cmd_v1(&pol, &cmd_req(conn_id, &[b"SELECT", b"5"]), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "select failed: {out:?}");
+        close_v1(&pol, &close_req(conn_id));
+    }
+
+    #[test]
+    fn pool_key_separates_tls_from_plaintext() {
+        let req = open_req(6379);
+        let open = parse_evro_open_req(&req).unwrap();
+        let plain = test_policy(true, 30_000);
+        let tls = Policy {
+            sandboxed: true,
+            require_tls: true,
+            ..test_policy(true, 30_000)
+        };
+        let verified = Policy {
+            require_verify: true,
+            ..test_policy(true, 30_000)
+        };
+        assert_ne!(open_pool_key(&plain, &open), open_pool_key(&tls, &open));
+        let verified_tls = Policy {
+            sandboxed: true,
+            require_tls: true,
+            ..verified
+        };
+        assert_ne!(
+            open_pool_key(&tls, &open),
+            open_pool_key(&verified_tls, &open)
+        );
+    }
+
+    #[test]
+    fn checkin_keeps_live_plus_parked_within_max() {
+        let _table = CONN_TABLE_LOCK.lock().unwrap();
+        let pol = Policy {
+            max_live_conns: dbcore::live_conn_count(conns()) + 2,
+            ..test_policy(true, 30_000)
+        };
+        let (port_a, _) = counting_server();
+        let (port_b, _) = counting_server();
+        let open = |port| {
+            let out = open_v1(&pol, &open_req(port), &caps());
+            assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+            read_u32_le(&out, 20).unwrap()
+        };
+
+        let first = open(port_a);
+        let second = open(port_a);
+        close_v1(&pol, &close_req(first));
+        assert_eq!(pool().len(), 1);
+        // A different target connects fresh, so live + parked is now 3.
+        let third = open(port_b);
+        close_v1(&pol, &close_req(second));
+        assert_eq!(pool().len(), 1);
+        close_v1(&pol, &close_req(third));
+        assert_eq!(pool().len(), 2);
+    }
+
+    #[test]
+    fn stateful_commands_keep_conn_out_of_pool() {
+        assert!(cmd_keeps_conn_reusable(b"get"));
+        assert!(cmd_keeps_conn_reusable(b"PING"));
+        assert!(!cmd_keeps_conn_reusable(b"subscribe"));
+        assert!(!cmd_keeps_conn_reusable(b"MULTI"));
+        assert!(!cmd_keeps_conn_reusable(b"select"));
+    }
+
+    fn argv_bytes(args: &[&[u8]]) -> Vec<u8> {
+        let mut out = b"X7RV".to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&(args.len() as u32).to_le_bytes());
+        for a in args {
+            out.extend_from_slice(&(a.len() as u32).to_le_bytes());
+            out.extend_from_slice(a);
+        }
+        out
+    }
+
+    fn caps_v2(max_argv: u32, max_arg_bytes: u32) -> Vec<u8> {
+        let mut out = b"X7DC".to_vec();
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&[0; 16]);
+        out.extend_from_slice(&max_argv.to_le_bytes());
+        out.extend_from_slice(&max_arg_bytes.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn argv_limits_hold_at_the_boundaries() {
+        let limits = ArgvLimits {
+            max_argv: 3,
+            max_arg_bytes: 4,
+        };
+        let too_large =
+            |detail: &str| Err::<Vec<&[u8]>, _>((DB_ERR_TOO_LARGE, detail.as_bytes().to_vec()));
+
+        let at_count = argv_bytes(&[b"DEL", b"a", b"b"]);
+        assert_eq!(parse_evrv_argv(&at_count, limits).unwrap().len(), 3);
+        let over_count = argv_bytes(&[b"DEL", b"a", b"b", b"c"]);
+        assert_eq!(
+            parse_evrv_argv(&over_count, limits),
+            too_large("argv[3]: argument count 4 exceeds limit 3")
+        );
+
+        let at_size = argv_bytes(&[b"GET", b"abcd"]);
+        assert_eq!(parse_evrv_argv(&at_size, limits).unwrap()[1], b"abcd");
+        let over_size = argv_bytes(&[b"GET", b"abcde"]);
+        assert_eq!(
+            parse_evrv_argv(&over_size, limits),
+            too_large("argv[1]: 5 bytes exceeds limit 4")
+        );
+
+        // A huge count of empty arguments is rejected from the header alone,
+        // before anything is allocated for it.
+        let pol = test_policy(false, 30_000);
+        let defaults = ArgvLimits::new(&pol, parse_db_caps_v1(&caps()).unwrap());
+        let mut million = argv_bytes(&[]);
+        million[8..12].copy_from_slice(&1_000_000u32.to_le_bytes());
+        million.extend(std::iter::repeat_n(0u8, 4 * 1_000_000));
+        assert_eq!(
+            parse_evrv_argv(&million, defaults),
+            too_large("argv[1024]: argument count 1000000 exceeds limit 1024")
+        );
+        let at_default = vec![&b""[..]; 1024];
+        assert!(parse_evrv_argv(&argv_bytes(&at_default), defaults).is_ok());
+    }
+
+    #[test]
+    fn caps_can_only_lower_argv_limits() {
+        let pol = test_policy(false, 30_000);
+        let limits = |caps: &[u8]| {
+            let l = ArgvLimits::new(&pol, parse_db_caps_v1(caps).unwrap());
+            (l.max_argv, l.max_arg_bytes)
+        };
+
+        assert_eq!(limits(&caps()), (1024, 1024 * 1024));
+        assert_eq!(limits(&caps_v2(0, 0)), (1024, 1024 * 1024));
+        assert_eq!(limits(&caps_v2(2, 16)), (2, 16));
+        assert_eq!(
+            limits(&caps_v2(5_000, 8 * 1024 * 1024)),
+            (1024, 1024 * 1024)
+        );
+
+        let lowered = ArgvLimits::new(&pol, parse_db_caps_v1(&caps_v2(2, 0)).unwrap());
+        assert!(parse_evrv_argv(&argv_bytes(&[b"GET", b"k"]), lowered).is_ok());
+        assert_eq!(
+            parse_evrv_argv(&argv_bytes(&[b"MGET", b"a", b"b"]), lowered)
+                .unwrap_err()
+                .0,
+            DB_ERR_TOO_LARGE
+        );
+
+        // v2 fields need the 32-byte layout.
+        assert!(parse_db_caps_v1(&caps_v2(2, 16)[..24]).is_err());
+    }
 }
@@ -7,6 +7,7 @@ use dbcore::{
     effective_max, effective_query_timeout_ms, env_bool, env_u32_nonzero, evdb_err, evdb_ok,
     parse_db_caps_v1, parse_params_doc_v1, read_u32_le, DmScalar, DB_ERR_BAD_CONN, DB_ERR_BAD_REQ,
     DB_ERR_POLICY_DENIED, DB_ERR_TOO_LARGE, OP_CLOSE_V1, OP_EXEC_V1, OP_OPEN_V1, OP_QUERY_V1,
+    QUERY_FLAG_COL_TYPES_V1,
 };
 use libsqlite3_sys as sqlite;
 use once_cell::sync::OnceCell;
@@ -28,6 +29,10 @@ const SQLITE_DONE: c_int = sqlite::SQLITE_DONE as c_int;
 const OPEN_FLAG_READONLY_V1: u32 = 1 << 0;
 const OPEN_FLAG_CREATE_V1: u32 = 1 << 1;
 
+const OPEN_MODE_FILE_V1: u8 = 0;
+const OPEN_MODE_MEMORY_V1: u8 = 1;
+const OPEN_MODE_SHARED_MEMORY_V1: u8 = 2;
+
 #[derive(Debug, Clone)]
 struct Policy {
     sandboxed: bool,
@@ -100,8 +105,7 @@ fn policy() -> &'static Policy {
     POLICY.get_or_init(load_policy)
 }
 
-fn is_sqlite_path_allowed(path: &Path) -> bool {
-    let pol = policy();
+fn is_sqlite_path_allowed(pol: &Policy, path: &Path) -> bool {
     if !pol.sandboxed {
         return true;
     }
@@ -116,6 +120,7 @@ unsafe impl Send for SqliteConn {}
 
 static CONNS: OnceCell<Mutex<Vec<Option<SqliteConn>>>> = OnceCell::new();
 static QUERIES: AtomicU32 = AtomicU32::new(0);
+static OP_LATENCY: dbcore::OpLatency = dbcore::OpLatency::new();
 
 fn conns() -> &'static Mutex<Vec<Option<SqliteConn>>> {
     CONNS.get_or_init(|| Mutex::new(vec![None; 4096]))
@@ -193,7 +198,11 @@ struct SqlReq<'a> {
     params: &'a [u8],
 }
 
-fn parse_evsq_req<'a>(req: &'a [u8], magic: &[u8; 4]) -> Result<SqlReq<'a>, u32> {
+fn parse_evsq_req<'a>(
+    req: &'a [u8],
+    magic: &[u8; 4],
+    allowed_flags: u32,
+) -> Result<SqlReq<'a>, u32> {
     if req.len() < 24 {
         return Err(DB_ERR_BAD_REQ);
     }
@@ -206,6 +215,9 @@ fn parse_evsq_req<'a>(req: &'a [u8], magic: &[u8; 4]) -> Result<SqlReq<'a>, u32>
     }
     let conn_id = read_u32_le(req, 8).ok_or(DB_ERR_BAD_REQ)?;
     let flags = read_u32_le(req, 12).ok_or(DB_ERR_BAD_REQ)?;
+    if flags & !allowed_flags != 0 {
+        return Err(DB_ERR_BAD_REQ);
+    }
     let sql_len = read_u32_le(req, 16).ok_or(DB_ERR_BAD_REQ)? as usize;
     if req.len() < 20 + sql_len + 4 {
         return Err(DB_ERR_BAD_REQ);
@@ -289,13 +301,13 @@ unsafe fn bytes_to_utf8_path(b: &[u8]) -> Result<PathBuf, u32> {
     Ok(PathBuf::from(s))
 }
 
-fn count_query_or_deny(pol: &Policy, op: u32) -> Result<(), ev_bytes> {
+fn count_query_or_deny(pol: &Policy, op: u32) -> Result<(), Vec<u8>> {
     if pol.max_queries == 0 {
         return Ok(());
     }
     let prev = QUERIES.fetch_add(1, Ordering::Relaxed);
     if prev >= pol.max_queries {
-        return Err(alloc_return_bytes(&evdb_err(op, DB_ERR_POLICY_DENIED, &[])));
+        return Err(evdb_err(op, DB_ERR_POLICY_DENIED, &[]));
     }
     Ok(())
 }
@@ -304,59 +316,106 @@ fn count_query_or_deny(pol: &Policy, op: u32) -> Result<(), ev_bytes> {
 pub extern "C" fn x07_ext_db_sqlite_open_v1(req: ev_bytes, caps: ev_bytes) -> ev_bytes {
     let req = unsafe { bytes_as_slice(req) };
     let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&open_v1(policy(), req, caps_raw))
+}
 
-    let pol = policy();
+/// How an open resolved its path, returned after `conn_id` in the open
+/// response so guests can assert what they got.
+fn open_mode_v1(path_bytes: &[u8]) -> u8 {
+    if path_bytes == b":memory:" {
+        OPEN_MODE_MEMORY_V1
+    } else if is_shared_memory_uri(path_bytes) {
+        OPEN_MODE_SHARED_MEMORY_V1
+    } else {
+        OPEN_MODE_FILE_V1
+    }
+}
+
+/// `file:<name>?mode=memory&cache=shared` (parameters in either order): a
+/// named in-memory database that every connection in the process opening
+/// the same name shares. Anything else is a path, including other `file:`
+/// URIs, which are opened without `SQLITE_OPEN_URI` and never parsed.
+fn is_shared_memory_uri(path_bytes: &[u8]) -> bool {
+    let Some(rest) = path_bytes.strip_prefix(b"file:") else {
+        return false;
+    };
+    let Some(q) = rest.iter().position(|&b| b == b'?') else {
+        return false;
+    };
+    let (name, query) = (&rest[..q], &rest[q + 1..]);
+    // No percent-escapes or separators, so the name cannot spell a path.
+    if name.is_empty()
+        || name
+            .iter()
+            .any(|b| matches!(b, b'/' | b'\\' | b'%' | b'#' | b':'))
+    {
+        return false;
+    }
+    let mut params: Vec<&[u8]> = query.split(|&b| b == b'&').collect();
+    params.sort_unstable();
+    params == [&b"cache=shared"[..], &b"mode=memory"[..]]
+}
+
+fn open_v1(pol: &Policy, req: &[u8], caps_raw: &[u8]) -> Vec<u8> {
     if !pol.enabled || !pol.sqlite_enabled {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
     }
 
     let caps = match parse_db_caps_v1(caps_raw) {
         Ok(c) => c,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_OPEN_V1, code, &[])),
+        Err(code) => return evdb_err(OP_OPEN_V1, code, &[]),
     };
 
     let (open_flags, path_bytes) = match parse_evso_open_req(req) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_OPEN_V1, code, &[])),
+        Err(code) => return evdb_err(OP_OPEN_V1, code, &[]),
     };
 
     if open_flags & !(OPEN_FLAG_READONLY_V1 | OPEN_FLAG_CREATE_V1) != 0 {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_BAD_REQ, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_BAD_REQ, &[]);
     }
     if (open_flags & OPEN_FLAG_CREATE_V1) != 0 && !pol.sqlite_allow_create {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
     }
     if pol.sqlite_readonly_only && (open_flags & OPEN_FLAG_READONLY_V1) == 0 {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
     }
 
-    let is_memory = path_bytes == b":memory:";
+    let mode = open_mode_v1(path_bytes);
+    let is_memory = mode != OPEN_MODE_FILE_V1;
     if is_memory && pol.sandboxed && !pol.sqlite_allow_in_memory {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
     }
 
     let path = match unsafe { bytes_to_utf8_path(path_bytes) } {
         Ok(p) => p,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_OPEN_V1, code, &[])),
+        Err(code) => return evdb_err(OP_OPEN_V1, code, &[]),
     };
 
-    if !is_memory && !is_sqlite_path_allowed(&path) {
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]));
+    if !is_memory && !is_sqlite_path_allowed(pol, &path) {
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
     }
 
     let cpath = match std::ffi::CString::new(path_bytes) {
         Ok(s) => s,
-        Err(_) => return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_BAD_REQ, &[])),
+        Err(_) => return evdb_err(OP_OPEN_V1, DB_ERR_BAD_REQ, &[]),
     };
 
     let mut db: *mut sqlite::sqlite3 = std::ptr::null_mut();
-    let flags = if (open_flags & OPEN_FLAG_READONLY_V1) != 0 {
+    let mut flags = if (open_flags & OPEN_FLAG_READONLY_V1) != 0 {
         sqlite::SQLITE_OPEN_READONLY
     } else if (open_flags & OPEN_FLAG_CREATE_V1) != 0 {
         sqlite::SQLITE_OPEN_READWRITE | sqlite::SQLITE_OPEN_CREATE
     } else {
         sqlite::SQLITE_OPEN_READWRITE
     };
+    if mode == OPEN_MODE_SHARED_MEMORY_V1 {
+        flags |= sqlite::SQLITE_OPEN_URI;
+        // The first connection to a name brings the database into being.
+        if (open_flags & OPEN_FLAG_READONLY_V1) == 0 {
+            flags |= sqlite::SQLITE_OPEN_CREATE;
+        }
+    }
 
     let rc = unsafe { sqlite::sqlite3_open_v2(cpath.as_ptr(), &mut db, flags, std::ptr::null()) };
     if rc != SQLITE_OK || db.is_null() {
@@ -366,7 +425,7 @@ pub extern "C" fn x07_ext_db_sqlite_open_v1(req: ev_bytes, caps: ev_bytes) -> ev
                 let _ = sqlite::sqlite3_close(db);
             }
         }
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_SQLITE_OPEN, &msg));
+        return evdb_err(OP_OPEN_V1, DB_ERR_SQLITE_OPEN, &msg);
     }
 
     let connect_timeout_ms = effective_connect_timeout_ms(pol.max_connect_timeout_ms, caps);
@@ -381,10 +440,17 @@ pub extern "C" fn x07_ext_db_sqlite_open_v1(req: ev_bytes, caps: ev_bytes) -> ev
         unsafe {
             let _ = sqlite::sqlite3_close(db);
         }
-        return alloc_return_bytes(&evdb_err(OP_OPEN_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_OPEN_V1, DB_ERR_TOO_LARGE, &[]);
     };
 
-    alloc_return_bytes(&evdb_ok(OP_OPEN_V1, &conn_id.to_le_bytes()))
+    evdb_ok(OP_OPEN_V1, &open_payload_v1(conn_id, mode))
+}
+
+fn open_payload_v1(conn_id: u32, mode: u8) -> [u8; 5] {
+    let mut out = [0u8; 5];
+    out[..4].copy_from_slice(&conn_id.to_le_bytes());
+    out[4] = mode;
+    out
 }
 
 #[no_mangle]
@@ -408,10 +474,117 @@ pub extern "C" fn x07_ext_db_sqlite_close_v1(req: ev_bytes, caps: ev_bytes) -> e
     alloc_return_bytes(&evdb_ok(OP_CLOSE_V1, &[]))
 }
 
+/// Opens a read-only in-memory connection holding the serialized database
+/// `db_bytes` (the bytes of a `.sqlite` file), so a run can query a known
+/// database without touching the filesystem. Gated like an `:memory:` open;
+/// the blob is capped by `max_sql_bytes`.
+#[no_mangle]
+pub extern "C" fn x07_ext_db_sqlite_open_from_bytes_v1(
+    db_bytes: ev_bytes,
+    caps: ev_bytes,
+) -> ev_bytes {
+    let db_bytes = unsafe { bytes_as_slice(db_bytes) };
+    let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&open_from_bytes_v1(policy(), db_bytes, caps_raw))
+}
+
+fn open_from_bytes_v1(pol: &Policy, db_bytes: &[u8], caps_raw: &[u8]) -> Vec<u8> {
+    if !pol.enabled || !pol.sqlite_enabled {
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
+    }
+    if pol.sandboxed && !pol.sqlite_allow_in_memory {
+        return evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]);
+    }
+    if let Err(code) = parse_db_caps_v1(caps_raw) {
+        return evdb_err(OP_OPEN_V1, code, &[]);
+    }
+    if db_bytes.len() > pol.max_sql_bytes as usize {
+        return evdb_err(OP_OPEN_V1, DB_ERR_TOO_LARGE, &[]);
+    }
+
+    let mut db: *mut sqlite::sqlite3 = std::ptr::null_mut();
+    let rc = unsafe {
+        sqlite::sqlite3_open_v2(
+            c":memory:".as_ptr(),
+            &mut db,
+            sqlite::SQLITE_OPEN_READWRITE,
+            std::ptr::null(),
+        )
+    };
+    if rc != SQLITE_OK || db.is_null() {
+        let msg = unsafe { sqlite_last_errmsg(db) };
+        if !db.is_null() {
+            unsafe {
+                let _ = sqlite::sqlite3_close(db);
+            }
+        }
+        return evdb_err(OP_OPEN_V1, DB_ERR_SQLITE_OPEN, &msg);
+    }
+
+    // sqlite owns the copy (FREEONCLOSE) and refuses writes to it (READONLY).
+    let rc = unsafe {
+        let buf = sqlite::sqlite3_malloc64(db_bytes.len().max(1) as u64) as *mut u8;
+        if buf.is_null() {
+            sqlite::SQLITE_NOMEM
+        } else {
+            std::ptr::copy_nonoverlapping(db_bytes.as_ptr(), buf, db_bytes.len());
+            sqlite::sqlite3_deserialize(
+                db,
+                c"main".as_ptr(),
+                buf,
+                db_bytes.len() as i64,
+                db_bytes.len() as i64,
+                sqlite::SQLITE_DESERIALIZE_FREEONCLOSE | sqlite::SQLITE_DESERIALIZE_READONLY,
+            )
+        }
+    };
+    // Deserializing does not look at the bytes; reading the schema does.
+    let rc = if rc == SQLITE_OK {
+        unsafe {
+            sqlite::sqlite3_exec(
+                db,
+                c"SELECT 1 FROM sqlite_schema LIMIT 1".as_ptr(),
+                None,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        }
+    } else {
+        rc
+    };
+    if rc != SQLITE_OK {
+        let msg = unsafe { sqlite_last_errmsg(db) };
+        unsafe {
+            let _ = sqlite::sqlite3_close(db);
+        }
+        return evdb_err(OP_OPEN_V1, DB_ERR_SQLITE_OPEN, &msg);
+    }
+
+    let Some(conn_id) = open_slot(db, pol) else {
+        unsafe {
+            let _ = sqlite::sqlite3_close(db);
+        }
+        return evdb_err(OP_OPEN_V1, DB_ERR_TOO_LARGE, &[]);
+    };
+
+    evdb_ok(OP_OPEN_V1, &open_payload_v1(conn_id, OPEN_MODE_MEMORY_V1))
+}
+
+fn sqlite_storage_class_name(t: c_int) -> Option<&'static [u8]> {
+    match t {
+        sqlite::SQLITE_INTEGER => Some(b"INTEGER"),
+        sqlite::SQLITE_FLOAT => Some(b"REAL"),
+        sqlite::SQLITE_TEXT => Some(b"TEXT"),
+        sqlite::SQLITE_BLOB => Some(b"BLOB"),
+        _ => None,
+    }
+}
+
 unsafe fn query_rows_doc(
     stmt: *mut sqlite::sqlite3_stmt,
     _db: *mut sqlite::sqlite3,
     max_rows: u32,
+    with_col_types: bool,
 ) -> Result<Vec<u8>, u32> {
     let col_count = sqlite::sqlite3_column_count(stmt);
     if col_count < 0 {
@@ -431,6 +604,20 @@ unsafe fn query_rows_doc(
 
     let cols_value = dm_value_seq(&cols.iter().map(|s| dm_value_string(s)).collect::<Vec<_>>());
 
+    // Declared types where the column has one; expressions and views without a
+    // declaration fall back to the storage class of the first non-null cell.
+    let mut col_types: Vec<Option<Vec<u8>>> = Vec::with_capacity(col_count);
+    if with_col_types {
+        for i in 0..col_count {
+            let decl = sqlite::sqlite3_column_decltype(stmt, i as c_int);
+            if decl.is_null() {
+                col_types.push(None);
+            } else {
+                col_types.push(Some(CStr::from_ptr(decl).to_bytes().to_vec()));
+            }
+        }
+    }
+
     let mut rows: Vec<Vec<u8>> = Vec::new();
     loop {
         let rc = sqlite::sqlite3_step(stmt);
@@ -448,6 +635,9 @@ unsafe fn query_rows_doc(
         let mut cells: Vec<Vec<u8>> = Vec::with_capacity(col_count);
         for i in 0..col_count {
             let t = sqlite::sqlite3_column_type(stmt, i as c_int);
+            if let Some(slot @ None) = col_types.get_mut(i) {
+                *slot = sqlite_storage_class_name(t).map(<[u8]>::to_vec);
+            }
             let cell = match t {
                 sqlite::SQLITE_NULL => dm_value_null(),
                 sqlite::SQLITE_INTEGER => {
@@ -488,21 +678,56 @@ unsafe fn query_rows_doc(
     }
 
     let rows_value = dm_value_seq(&rows);
-    let map_value = dm_value_map(vec![
+    let mut entries = vec![
         (b"cols".to_vec(), cols_value),
         (b"rows".to_vec(), rows_value),
-    ])?;
+    ];
+    if with_col_types {
+        let types = col_types
+            .iter()
+            .map(|t| dm_value_string(t.as_deref().unwrap_or(b"unknown")))
+            .collect::<Vec<_>>();
+        entries.push((b"col_types".to_vec(), dm_value_seq(&types)));
+    }
+    let map_value = dm_value_map(entries)?;
     Ok(dm_doc_ok(&map_value))
 }
 
+fn stats() -> dbcore::DbStatsV1 {
+    let pol = policy();
+    dbcore::DbStatsV1 {
+        live_conns: dbcore::live_conn_count(conns()),
+        max_live_conns: pol.max_live_conns,
+        queries_used: QUERIES.load(Ordering::Relaxed),
+        max_queries: pol.max_queries,
+        op_latency: OP_LATENCY.snapshot(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn x07_ext_db_sqlite_stats_v1() -> ev_bytes {
+    alloc_return_bytes(&stats().to_dm_doc())
+}
+
+/// Copies the query/exec/cmd wall-time histogram (bucket bounds in
+/// [`dbcore::LATENCY_BUCKET_LABELS`]) into `out`, which must hold
+/// [`dbcore::LATENCY_BUCKETS`] counters, and returns the bucket count.
+#[no_mangle]
+pub unsafe extern "C" fn x07_ext_db_sqlite_latency_hist_v1(out: *mut u64) -> u32 {
+    dbcore::copy_latency_hist(&OP_LATENCY, out)
+}
+
 #[no_mangle]
 pub extern "C" fn x07_ext_db_sqlite_query_v1(req: ev_bytes, caps: ev_bytes) -> ev_bytes {
     let req = unsafe { bytes_as_slice(req) };
     let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&dbcore::timed_op(&OP_LATENCY, || query_v1(req, caps_raw)))
+}
 
+fn query_v1(req: &[u8], caps_raw: &[u8]) -> Vec<u8> {
     let pol = policy();
     if !pol.enabled || !pol.sqlite_enabled {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_POLICY_DENIED, &[]);
     }
     if let Err(out) = count_query_or_deny(pol, OP_QUERY_V1) {
         return out;
@@ -510,24 +735,24 @@ pub extern "C" fn x07_ext_db_sqlite_query_v1(req: ev_bytes, caps: ev_bytes) -> e
 
     let caps = match parse_db_caps_v1(caps_raw) {
         Ok(c) => c,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_QUERY_V1, code, &[])),
+        Err(code) => return evdb_err(OP_QUERY_V1, code, &[]),
     };
 
-    let sql_req = match parse_evsq_req(req, b"X7SQ") {
+    let sql_req = match parse_evsq_req(req, b"X7SQ", QUERY_FLAG_COL_TYPES_V1) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_QUERY_V1, code, &[])),
+        Err(code) => return evdb_err(OP_QUERY_V1, code, &[]),
     };
     let conn_id = sql_req.conn_id;
-    let _flags = sql_req.flags;
+    let with_col_types = (sql_req.flags & QUERY_FLAG_COL_TYPES_V1) != 0;
     let sql = sql_req.sql;
     let params = sql_req.params;
 
     if sql.len() > pol.max_sql_bytes as usize {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]);
     }
 
     let Some(db) = get_conn(conn_id) else {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, &[]);
     };
 
     let timeout_ms = effective_query_timeout_ms(pol.max_query_timeout_ms, caps);
@@ -540,7 +765,7 @@ pub extern "C" fn x07_ext_db_sqlite_query_v1(req: ev_bytes, caps: ev_bytes) -> e
 
     let sql_c = match std::ffi::CString::new(sql) {
         Ok(s) => s,
-        Err(_) => return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_BAD_REQ, &[])),
+        Err(_) => return evdb_err(OP_QUERY_V1, DB_ERR_BAD_REQ, &[]),
     };
 
     let mut stmt: *mut sqlite::sqlite3_stmt = std::ptr::null_mut();
@@ -554,7 +779,7 @@ pub extern "C" fn x07_ext_db_sqlite_query_v1(req: ev_bytes, caps: ev_bytes) -> e
                 let _ = sqlite::sqlite3_finalize(stmt);
             }
         }
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_SQLITE_PREP, &msg));
+        return evdb_err(OP_QUERY_V1, DB_ERR_SQLITE_PREP, &msg);
     }
 
     let bind_res = unsafe { bind_params(stmt, params) };
@@ -562,35 +787,38 @@ pub extern "C" fn x07_ext_db_sqlite_query_v1(req: ev_bytes, caps: ev_bytes) -> e
         unsafe {
             let _ = sqlite::sqlite3_finalize(stmt);
         }
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_BAD_REQ, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_BAD_REQ, &[]);
     }
 
     let max_rows = effective_max(pol.max_rows, caps.max_rows);
-    let doc = unsafe { query_rows_doc(stmt, db, max_rows) };
+    let doc = unsafe { query_rows_doc(stmt, db, max_rows, with_col_types) };
     unsafe {
         let _ = sqlite::sqlite3_finalize(stmt);
     }
     let doc = match doc {
         Ok(d) => d,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_QUERY_V1, code, &[])),
+        Err(code) => return evdb_err(OP_QUERY_V1, code, &[]),
     };
 
     let max_resp = effective_max(pol.max_resp_bytes, caps.max_resp_bytes);
     if max_resp != 0 && doc.len() > max_resp as usize {
-        return alloc_return_bytes(&evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_QUERY_V1, DB_ERR_TOO_LARGE, &[]);
     }
 
-    alloc_return_bytes(&evdb_ok(OP_QUERY_V1, &doc))
+    evdb_ok(OP_QUERY_V1, &doc)
 }
 
 #[no_mangle]
 pub extern "C" fn x07_ext_db_sqlite_exec_v1(req: ev_bytes, caps: ev_bytes) -> ev_bytes {
     let req = unsafe { bytes_as_slice(req) };
     let caps_raw = unsafe { bytes_as_slice(caps) };
+    alloc_return_bytes(&dbcore::timed_op(&OP_LATENCY, || exec_v1(req, caps_raw)))
+}
 
+fn exec_v1(req: &[u8], caps_raw: &[u8]) -> Vec<u8> {
     let pol = policy();
     if !pol.enabled || !pol.sqlite_enabled {
-        return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_POLICY_DENIED, &[]));
+        return evdb_err(OP_EXEC_V1, DB_ERR_POLICY_DENIED, &[]);
     }
     if let Err(out) = count_query_or_deny(pol, OP_EXEC_V1) {
         return out;
@@ -598,24 +826,23 @@ pub extern "C" fn x07_ext_db_sqlite_exec_v1(req: ev_bytes, caps: ev_bytes) -> ev
 
     let caps = match parse_db_caps_v1(caps_raw) {
         Ok(c) => c,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_EXEC_V1, code, &[])),
+        Err(code) => return evdb_err(OP_EXEC_V1, code, &[]),
     };
 
-    let sql_req = match parse_evsq_req(req, b"X7SE") {
+    let sql_req = match parse_evsq_req(req, b"X7SE", 0) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_EXEC_V1, code, &[])),
+        Err(code) => return evdb_err(OP_EXEC_V1, code, &[]),
     };
     let conn_id = sql_req.conn_id;
-    let _flags = sql_req.flags;
     let sql = sql_req.sql;
     let params = sql_req.params;
 
     if sql.len() > pol.max_sql_bytes as usize {
-        return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_EXEC_V1, DB_ERR_TOO_LARGE, &[]);
     }
 
     let Some(db) = get_conn(conn_id) else {
-        return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_BAD_CONN, &[]));
+        return evdb_err(OP_EXEC_V1, DB_ERR_BAD_CONN, &[]);
     };
 
     let timeout_ms = effective_query_timeout_ms(pol.max_query_timeout_ms, caps);
@@ -628,7 +855,7 @@ pub extern "C" fn x07_ext_db_sqlite_exec_v1(req: ev_bytes, caps: ev_bytes) -> ev
 
     let sql_c = match std::ffi::CString::new(sql) {
         Ok(s) => s,
-        Err(_) => return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_BAD_REQ, &[])),
+        Err(_) => return evdb_err(OP_EXEC_V1, DB_ERR_BAD_REQ, &[]),
     };
 
     let mut stmt: *mut sqlite::sqlite3_stmt = std::ptr::null_mut();
@@ -642,7 +869,7 @@ pub extern "C" fn x07_ext_db_sqlite_exec_v1(req: ev_bytes, caps: ev_bytes) -> ev
                 let _ = sqlite::sqlite3_finalize(stmt);
             }
         }
-        return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_SQLITE_PREP, &msg));
+        return evdb_err(OP_EXEC_V1, DB_ERR_SQLITE_PREP, &msg);
     }
 
     let bind_res = unsafe { bind_params(stmt, params) };
@@ -650,7 +877,7 @@ pub extern "C" fn x07_ext_db_sqlite_exec_v1(req: ev_bytes, caps: ev_bytes) -> ev
         unsafe {
             let _ = sqlite::sqlite3_finalize(stmt);
         }
-        return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_BAD_REQ, &[]));
+        return evdb_err(OP_EXEC_V1, DB_ERR_BAD_REQ, &[]);
     }
 
     loop {
@@ -664,7 +891,7 @@ pub extern "C" fn x07_ext_db_sqlite_exec_v1(req: ev_bytes, caps: ev_bytes) -> ev
         unsafe {
             let _ = sqlite::sqlite3_finalize(stmt);
         }
-        return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_SQLITE_STEP, &[]));
+        return evdb_err(OP_EXEC_V1, DB_ERR_SQLITE_STEP, &[]);
     }
 
     unsafe {
@@ -688,14 +915,429 @@ pub extern "C" fn x07_ext_db_sqlite_exec_v1(req: ev_bytes, caps: ev_bytes) -> ev
 
     let map_value = match dm_value_map(entries) {
         Ok(v) => v,
-        Err(code) => return alloc_return_bytes(&evdb_err(OP_EXEC_V1, code, &[])),
+        Err(code) => return evdb_err(OP_EXEC_V1, code, &[]),
     };
     let doc = dm_doc_ok(&map_value);
 
     let max_resp = effective_max(pol.max_resp_bytes, caps.max_resp_bytes);
     if max_resp != 0 && doc.len() > max_resp as usize {
-        return alloc_return_bytes(&evdb_err(OP_EXEC_V1, DB_ERR_TOO_LARGE, &[]));
+        return evdb_err(OP_EXEC_V1, DB_ERR_TOO_LARGE, &[]);
+    }
+
+    evdb_ok(OP_EXEC_V1, &doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    dbcore::ev_test_stubs!();
+
+    unsafe fn exec(db: *mut sqlite::sqlite3, sql: &str) {
+        let sql = std::ffi::CString::new(sql).unwrap();
+        let rc = sqlite::sqlite3_exec(
+            db,
+            sql.as_ptr(),
+            None,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+        assert_eq!(
+            rc,
+            SQLITE_OK,
+            "{:?}",
+            String::from_utf8_lossy(&sqlite_last_errmsg(db))
+        );
+    }
+
+    unsafe fn query(db: *mut sqlite::sqlite3, sql: &str, with_col_types: bool) -> Vec<u8> {
+        let sql = std::ffi::CString::new(sql).unwrap();
+        let mut stmt: *mut sqlite::sqlite3_stmt = std::ptr::null_mut();
+        let rc = sqlite::sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, std::ptr::null_mut());
+        assert_eq!(rc, SQLITE_OK);
+        let doc = query_rows_doc(stmt, db, 0, with_col_types);
+        sqlite::sqlite3_finalize(stmt);
+        doc.expect("rows doc")
+    }
+
+    fn strings(items: &[&[u8]]) -> Vec<u8> {
+        dm_value_seq(&items.iter().map(|s| dm_value_string(s)).collect::<Vec<_>>())
     }
 
-    alloc_return_bytes(&evdb_ok(OP_EXEC_V1, &doc))
+    #[test]
+    fn col_types_follow_decltype_then_first_non_null_cell() {
+        unsafe {
+            let mut db: *mut sqlite::sqlite3 = std::ptr::null_mut();
+            let path = std::ffi::CString::new(":memory:").unwrap();
+            assert_eq!(sqlite::sqlite3_open(path.as_ptr(), &mut db), SQLITE_OK);
+            exec(db, "CREATE TABLE t (i INTEGER, r REAL, s TEXT, b BLOB, n)");
+            exec(db, "INSERT INTO t VALUES (NULL, NULL, NULL, NULL, NULL)");
+            exec(db, "INSERT INTO t VALUES (7, 1.5, 'x', x'00ff', NULL)");
+
+            let sql = "SELECT i, r, s, b, n, i + 1 AS e FROM t ORDER BY rowid";
+            let cols = strings(&[b"i", b"r", b"s", b"b", b"n", b"e"]);
+            let null = dm_value_null();
+            let rows = dm_value_seq(&[
+                dm_value_seq(&[
+                    null.clone(),
+                    null.clone(),
+                    null.clone(),
+                    null.clone(),
+                    null.clone(),
+                    null.clone(),
+                ]),
+                dm_value_seq(&[
+                    dm_value_number_ascii(b"7"),
+                    dm_value_number_ascii(b"1.5"),
+                    dm_value_string(b"x"),
+                    dm_value_string(&[0x00, 0xff]),
+                    null.clone(),
+                    dm_value_number_ascii(b"8"),
+                ]),
+            ]);
+
+            let plain = dm_value_map(vec![
+                (b"cols".to_vec(), cols.clone()),
+                (b"rows".to_vec(), rows.clone()),
+            ])
+            .unwrap();
+            assert_eq!(query(db, sql, false), dm_doc_ok(&plain));
+
+            let typed = dm_value_map(vec![
+                (b"cols".to_vec(), cols),
+                (b"rows".to_vec(), rows),
+                (
+                    b"col_types".to_vec(),
+                    strings(&[
+                        b"INTEGER", b"REAL", b"TEXT", b"BLOB", b"unknown", b"INTEGER",
+                    ]),
+                ),
+            ])
+            .unwrap();
+            assert_eq!(query(db, sql, true), dm_doc_ok(&typed));
+
+            sqlite::sqlite3_close(db);
+        }
+    }
+
+    fn test_policy() -> Policy {
+        Policy {
+            sandboxed: true,
+            enabled: true,
+            sqlite_enabled: true,
+            sqlite_readonly_only: true,
+            sqlite_allow_create: false,
+            sqlite_allow_in_memory: true,
+            sqlite_allow_paths: Vec::new(),
+            max_live_conns: 8,
+            max_queries: 0,
+            max_connect_timeout_ms: 30_000,
+            max_query_timeout_ms: 60_000,
+            max_rows: 10_000,
+            max_resp_bytes: 32 * 1024 * 1024,
+            max_sql_bytes: 1024 * 1024,
+        }
+    }
+
+    fn caps() -> Vec<u8> {
+        let mut out = b"X7DC".to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&[0; 16]);
+        out
+    }
+
+    fn as_ev_bytes(b: &[u8]) -> ev_bytes {
+        ev_bytes {
+            ptr: b.as_ptr() as *mut u8,
+            len: b.len() as u32,
+        }
+    }
+
+    fn sql_req(magic: &[u8; 4], conn_id: u32, sql: &str) -> Vec<u8> {
+        let mut req = magic.to_vec();
+        for v in [1, conn_id, 0, sql.len() as u32] {
+            req.extend_from_slice(&v.to_le_bytes());
+        }
+        req.extend_from_slice(sql.as_bytes());
+        req.extend_from_slice(&0u32.to_le_bytes());
+        req
+    }
+
+    fn call(f: extern "C" fn(ev_bytes, ev_bytes) -> ev_bytes, req: &[u8], caps: &[u8]) -> Vec<u8> {
+        let out = f(as_ev_bytes(req), as_ev_bytes(caps));
+        unsafe { bytes_as_slice(out) }.to_vec()
+    }
+
+    fn serialized_db() -> Vec<u8> {
+        unsafe {
+            let mut db: *mut sqlite::sqlite3 = std::ptr::null_mut();
+            let path = std::ffi::CString::new(":memory:").unwrap();
+            assert_eq!(sqlite::sqlite3_open(path.as_ptr(), &mut db), SQLITE_OK);
+            exec(db, "CREATE TABLE kv (k TEXT, v INTEGER)");
+            exec(db, "INSERT INTO kv VALUES ('a', 1), ('b', 2)");
+            let mut size: i64 = 0;
+            let ptr = sqlite::sqlite3_serialize(db, c"main".as_ptr(), &mut size, 0);
+            assert!(!ptr.is_null());
+            let bytes = std::slice::from_raw_parts(ptr, size as usize).to_vec();
+            sqlite::sqlite3_free(ptr as *mut std::ffi::c_void);
+            sqlite::sqlite3_close(db);
+            bytes
+        }
+    }
+
+    #[test]
+    fn open_from_bytes_queries_a_read_only_copy() {
+        let db_bytes = serialized_db();
+        let out = open_from_bytes_v1(&test_policy(), &db_bytes, &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+        let conn_id = read_u32_le(&out, 20).unwrap();
+
+        let out = call(
+            x07_ext_db_sqlite_query_v1,
+            &sql_req(b"X7SQ", conn_id, "SELECT k, v FROM kv ORDER BY k"),
+            &caps(),
+        );
+        let rows = dm_value_seq(&[
+            dm_value_seq(&[dm_value_string(b"a"), dm_value_number_ascii(b"1")]),
+            dm_value_seq(&[dm_value_string(b"b"), dm_value_number_ascii(b"2")]),
+        ]);
+        let expected = dm_value_map(vec![
+            (b"cols".to_vec(), strings(&[b"k", b"v"])),
+            (b"rows".to_vec(), rows),
+        ])
+        .unwrap();
+        assert_eq!(out, evdb_ok(OP_QUERY_V1, &dm_doc_ok(&expected)));
+
+        let out = call(
+            x07_ext_db_sqlite_exec_v1,
+            &sql_req(b"X7SE", conn_id, "INSERT INTO kv VALUES ('c', 3)"),
+            &caps(),
+        );
+        assert_eq!(read_u32_le(&out, 8), Some(0), "write must fail: {out:?}");
+
+        let mut close = b"X7SC".to_vec();
+        close.extend_from_slice(&1u32.to_le_bytes());
+        close.extend_from_slice(&conn_id.to_le_bytes());
+        let out = call(x07_ext_db_sqlite_close_v1, &close, &caps());
+        assert_eq!(out, evdb_ok(OP_CLOSE_V1, &[]));
+    }
+
+    #[test]
+    fn open_from_bytes_enforces_policy_and_size() {
+        let db_bytes = serialized_db();
+
+        let mut pol = test_policy();
+        pol.sqlite_allow_in_memory = false;
+        let out = open_from_bytes_v1(&pol, &db_bytes, &caps());
+        assert_eq!(out, evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]));
+
+        let mut pol = test_policy();
+        pol.max_sql_bytes = db_bytes.len() as u32 - 1;
+        let out = open_from_bytes_v1(&pol, &db_bytes, &caps());
+        assert_eq!(out, evdb_err(OP_OPEN_V1, DB_ERR_TOO_LARGE, &[]));
+
+        let out = open_from_bytes_v1(&test_policy(), b"not a database", &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(0), "garbage opened: {out:?}");
+        assert_eq!(read_u32_le(&out, 16), Some(DB_ERR_SQLITE_OPEN));
+    }
+
+    #[test]
+    fn unknown_sql_flag_bits_are_rejected() {
+        let with_flags = |magic: &[u8; 4], flags: u32| {
+            let mut req = sql_req(magic, 1, "SELECT 1");
+            req[12..16].copy_from_slice(&flags.to_le_bytes());
+            req
+        };
+        let req = with_flags(b"X7SQ", QUERY_FLAG_COL_TYPES_V1);
+        let parsed = parse_evsq_req(&req, b"X7SQ", QUERY_FLAG_COL_TYPES_V1).unwrap();
+        assert_eq!(parsed.flags, QUERY_FLAG_COL_TYPES_V1);
+
+        let req = with_flags(b"X7SQ", QUERY_FLAG_COL_TYPES_V1 | 1 << 7);
+        assert_eq!(
+            parse_evsq_req(&req, b"X7SQ", QUERY_FLAG_COL_TYPES_V1).err(),
+            Some(DB_ERR_BAD_REQ)
+        );
+
+        let req = with_flags(b"X7SE", QUERY_FLAG_COL_TYPES_V1);
+        assert_eq!(parse_evsq_req(&req, b"X7SE", 0).err(), Some(DB_ERR_BAD_REQ));
+    }
+
+    fn open_req(flags: u32, path: &str) -> Vec<u8> {
+        let mut req = b"X7SO".to_vec();
+        for v in [1, flags, path.len() as u32] {
+            req.extend_from_slice(&v.to_le_bytes());
+        }
+        req.extend_from_slice(path.as_bytes());
+        req
+    }
+
+    fn writable_policy() -> Policy {
+        Policy {
+            sqlite_readonly_only: false,
+            ..test_policy()
+        }
+    }
+
+    #[test]
+    fn shared_cache_memory_connections_see_each_others_writes() {
+        let uri = format!(
+            "file:x07_shared_{}?cache=shared&mode=memory",
+            std::process::id()
+        );
+        let pol = writable_policy();
+        let writer = open_v1(&pol, &open_req(0, &uri), &caps());
+        assert_eq!(read_u32_le(&writer, 8), Some(1), "open failed: {writer:?}");
+        assert_eq!(writer[24], OPEN_MODE_SHARED_MEMORY_V1);
+        let writer = read_u32_le(&writer, 20).unwrap();
+        let reader = open_v1(&pol, &open_req(0, &uri), &caps());
+        assert_eq!(reader[24], OPEN_MODE_SHARED_MEMORY_V1);
+        let reader = read_u32_le(&reader, 20).unwrap();
+
+        for sql in ["CREATE TABLE t (v INTEGER)", "INSERT INTO t VALUES (42)"] {
+            let out = call(
+                x07_ext_db_sqlite_exec_v1,
+                &sql_req(b"X7SE", writer, sql),
+                &caps(),
+            );
+            assert_eq!(read_u32_le(&out, 8), Some(1), "{sql}: {out:?}");
+        }
+        let out = call(
+            x07_ext_db_sqlite_query_v1,
+            &sql_req(b"X7SQ", reader, "SELECT v FROM t"),
+            &caps(),
+        );
+        let expected = dm_value_map(vec![
+            (b"cols".to_vec(), strings(&[b"v"])),
+            (
+                b"rows".to_vec(),
+                dm_value_seq(&[dm_value_seq(&[dm_value_number_ascii(b"42")])]),
+            ),
+        ])
+        .unwrap();
+        assert_eq!(out, evdb_ok(OP_QUERY_V1, &dm_doc_ok(&expected)));
+
+        // A private `:memory:` connection does not see the shared database.
+        let private = open_v1(&pol, &open_req(0, ":memory:"), &caps());
+        assert_eq!(private[24], OPEN_MODE_MEMORY_V1);
+        let private = read_u32_le(&private, 20).unwrap();
+        let out = call(
+            x07_ext_db_sqlite_query_v1,
+            &sql_req(b"X7SQ", private, "SELECT v FROM t"),
+            &caps(),
+        );
+        assert_eq!(read_u32_le(&out, 8), Some(0), "{out:?}");
+
+        for conn_id in [writer, reader, private] {
+            assert!(close_conn(conn_id).is_ok());
+        }
+    }
+
+    #[test]
+    fn shared_cache_uri_is_memory_for_policy_purposes() {
+        let uri = "file:x07_denied?mode=memory&cache=shared";
+        let mut pol = writable_policy();
+        pol.sqlite_allow_in_memory = false;
+        assert_eq!(
+            open_v1(&pol, &open_req(0, uri), &caps()),
+            evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[])
+        );
+
+        assert!(is_shared_memory_uri(uri.as_bytes()));
+        for path in [
+            "file:x07?mode=memory",
+            "file:x07?mode=memory&cache=shared&vfs=unix",
+            "file:/tmp/x07?mode=memory&cache=shared",
+            "file:%2e%2e?mode=memory&cache=shared",
+            "file:?mode=memory&cache=shared",
+            "x07?mode=memory&cache=shared",
+        ] {
+            assert!(!is_shared_memory_uri(path.as_bytes()), "{path}");
+            // Still a path, so a sandboxed open needs allow_paths.
+            assert_eq!(
+                open_v1(&writable_policy(), &open_req(0, path), &caps()),
+                evdb_err(OP_OPEN_V1, DB_ERR_POLICY_DENIED, &[]),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn latency_buckets_split_at_decade_bounds() {
+        use std::time::Duration;
+        let cases = [
+            (Duration::ZERO, 0),
+            (Duration::from_micros(999), 0),
+            (Duration::from_millis(1), 1),
+            (Duration::from_micros(9_999), 1),
+            (Duration::from_millis(10), 2),
+            (Duration::from_millis(99), 2),
+            (Duration::from_millis(100), 3),
+            (Duration::from_millis(999), 3),
+            (Duration::from_secs(1), 4),
+            (Duration::from_secs(3600), 4),
+        ];
+        for (elapsed, bucket) in cases {
+            assert_eq!(dbcore::latency_bucket(elapsed), bucket, "{elapsed:?}");
+        }
+
+        let hist = dbcore::OpLatency::new();
+        hist.record(Duration::from_micros(10));
+        hist.record(Duration::from_millis(250));
+        hist.record(Duration::from_millis(300));
+        assert_eq!(hist.snapshot(), [1, 0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn elapsed_ms_is_appended_to_error_details_only() {
+        use std::time::Duration;
+        let elapsed = Duration::from_millis(42);
+        assert_eq!(
+            dbcore::with_elapsed_ms(evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, &[]), elapsed),
+            evdb_err(OP_QUERY_V1, DB_ERR_BAD_CONN, b"elapsed_ms=42")
+        );
+        assert_eq!(
+            dbcore::with_elapsed_ms(
+                evdb_err(OP_EXEC_V1, DB_ERR_SQLITE_PREP, b"no such table"),
+                elapsed
+            ),
+            evdb_err(
+                OP_EXEC_V1,
+                DB_ERR_SQLITE_PREP,
+                b"no such table; elapsed_ms=42"
+            )
+        );
+        let ok = evdb_ok(OP_QUERY_V1, b"doc");
+        assert_eq!(dbcore::with_elapsed_ms(ok.clone(), elapsed), ok);
+    }
+
+    #[test]
+    fn queries_land_in_the_latency_histogram() {
+        let before: u64 = stats().op_latency.iter().sum();
+        let out = open_v1(&writable_policy(), &open_req(0, ":memory:"), &caps());
+        assert_eq!(read_u32_le(&out, 8), Some(1), "open failed: {out:?}");
+        let conn_id = read_u32_le(&out, 20).unwrap();
+
+        for _ in 0..3 {
+            let out = call(
+                x07_ext_db_sqlite_query_v1,
+                &sql_req(b"X7SQ", conn_id, "SELECT 1"),
+                &caps(),
+            );
+            assert_eq!(read_u32_le(&out, 8), Some(1), "{out:?}");
+        }
+        let out = call(
+            x07_ext_db_sqlite_query_v1,
+            &sql_req(b"X7SQ", conn_id, "SELECT * FROM missing"),
+            &caps(),
+        );
+        let msg_len = read_u32_le(&out, 20).unwrap() as usize;
+        let msg = String::from_utf8_lossy(&out[24..24 + msg_len]).into_owned();
+        assert!(msg.contains("; elapsed_ms="), "{msg}");
+
+        let mut hist = [0u64; dbcore::LATENCY_BUCKETS];
+        let n = unsafe { x07_ext_db_sqlite_latency_hist_v1(hist.as_mut_ptr()) };
+        assert_eq!(n as usize, dbcore::LATENCY_BUCKETS);
+        assert!(hist.iter().sum::<u64>() >= before + 4, "{hist:?}");
+        assert!(close_conn(conn_id).is_ok());
+    }
 }
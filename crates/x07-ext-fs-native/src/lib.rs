@@ -3,19 +3,22 @@
 
 use globset::{Glob, GlobMatcher};
 use once_cell::sync::OnceCell;
+use std::collections::HashSet;
 use std::fs::OpenOptions;
 use std::io::{self, Read as _, Write as _};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
-use std::time::UNIX_EPOCH;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use walkdir::WalkDir;
 use x07_ext_os_native_core::{
     bytes_to_utf8, cap_allow_hidden, cap_allow_symlinks, cap_atomic_write, cap_create_parents,
-    cap_overwrite, effective_max, enforce_read_path, enforce_write_path, map_io_err,
-    open_atomic_tmp_best_effort, parse_caps_v1, policy, FS_ERR_ALREADY_EXISTS, FS_ERR_BAD_HANDLE,
-    FS_ERR_BAD_PATH, FS_ERR_DEPTH_EXCEEDED, FS_ERR_IO, FS_ERR_IS_DIR, FS_ERR_NOT_DIR,
+    cap_overwrite, create_dir_all_with_mode, effective_max, enforce_read_path,
+    enforce_read_path_with, enforce_write_path, enforce_write_path_with, map_io_err,
+    open_atomic_tmp_best_effort, open_with_file_mode, parse_caps_v1, policy, CapsV1, Policy,
+    UnicodeNormalize, FS_ERR_ALREADY_EXISTS, FS_ERR_BAD_HANDLE, FS_ERR_BAD_PATH,
+    FS_ERR_DEPTH_EXCEEDED, FS_ERR_IO, FS_ERR_IS_DIR, FS_ERR_LOCK_TIMEOUT, FS_ERR_NOT_DIR,
     FS_ERR_NOT_FOUND, FS_ERR_POLICY_DENY, FS_ERR_SYMLINK_DENIED, FS_ERR_TOO_LARGE,
-    FS_ERR_TOO_MANY_ENTRIES, FS_ERR_UNSUPPORTED,
+    FS_ERR_TOO_MANY_ENTRIES, FS_ERR_TOO_MANY_HANDLES, FS_ERR_UNSUPPORTED,
 };
 
 #[repr(C)]
@@ -79,6 +82,19 @@ fn writers() -> &'static Mutex<Vec<Option<WriterHandleV1>>> {
     WRITERS.get_or_init(|| Mutex::new(Vec::new()))
 }
 
+/// Fails with `FS_ERR_TOO_MANY_HANDLES` once `max_open` writers hold an open
+/// file. Closed handles (`file: None`) and dropped slots don't count.
+fn check_writer_capacity(table: &[Option<WriterHandleV1>], max_open: u32) -> Result<(), i32> {
+    let open = table
+        .iter()
+        .filter(|slot| slot.as_ref().is_some_and(|w| w.file.is_some()))
+        .count();
+    if open >= max_open as usize {
+        return Err(FS_ERR_TOO_MANY_HANDLES);
+    }
+    Ok(())
+}
+
 fn handle_idx(handle: i32) -> Option<usize> {
     if handle <= 0 {
         None
@@ -125,6 +141,46 @@ fn readers() -> &'static Mutex<Vec<Option<ReaderHandleV1>>> {
     READERS.get_or_init(|| Mutex::new(Vec::new()))
 }
 
+// -------------------------
+// Advisory lock handles (FS v1)
+// -------------------------
+
+/// Held advisory lock on `<target>.x07_lock`. The OS lock (flock on Unix,
+/// LockFileEx on Windows) lives on `file`, so dropping the handle, or the
+/// process exiting, releases it. Lockfiles are never removed.
+#[derive(Debug)]
+struct LockHandleV1 {
+    file: std::fs::File,
+}
+
+static LOCKS: OnceCell<Mutex<Vec<Option<LockHandleV1>>>> = OnceCell::new();
+
+fn locks() -> &'static Mutex<Vec<Option<LockHandleV1>>> {
+    LOCKS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+const LOCK_POLL_MS: u64 = 10;
+
+fn lockfile_path(target: &Path) -> Result<PathBuf, i32> {
+    let Some(name) = target.file_name().and_then(|s| s.to_str()) else {
+        return Err(FS_ERR_BAD_PATH);
+    };
+    Ok(target.with_file_name(format!("{name}.x07_lock")))
+}
+
+fn try_lock_file(file: &std::fs::File, exclusive: bool) -> Result<bool, i32> {
+    let res = if exclusive {
+        file.try_lock()
+    } else {
+        file.try_lock_shared()
+    };
+    match res {
+        Ok(()) => Ok(true),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(false),
+        Err(std::fs::TryLockError::Error(e)) => Err(map_io_err(&e)),
+    }
+}
+
 // -------------------------
 // Result helpers
 // -------------------------
@@ -177,6 +233,7 @@ unsafe fn alloc_bytes(len: u32) -> ev_bytes {
 
 fn join_lines_sorted(mut lines: Vec<String>) -> Vec<u8> {
     lines.sort(); // UTF-8 string order
+    lines.dedup(); // names that only differed before Unicode normalization
     let mut out = String::new();
     if lines.is_empty() {
         out.push('\n');
@@ -294,7 +351,7 @@ pub extern "C" fn x07_ext_fs_write_all_v1(
 
         if cap_create_parents(caps) {
             if let Some(parent) = pb.parent() {
-                if let Err(e) = std::fs::create_dir_all(parent) {
+                if let Err(e) = create_dir_all_with_mode(parent, pol) {
                     return err_i32(map_io_err(&e));
                 }
             }
@@ -317,7 +374,13 @@ pub extern "C" fn x07_ext_fs_write_all_v1(
             return write_atomic_best_effort(&pb, data_bytes, cap_overwrite(caps));
         }
 
-        if let Err(e) = std::fs::write(&pb, data_bytes) {
+        let written = open_with_file_mode(
+            OpenOptions::new().write(true).create(true).truncate(true),
+            &pb,
+            pol,
+        )
+        .and_then(|mut f| f.write_all(data_bytes));
+        if let Err(e) = written {
             return err_i32(map_io_err(&e));
         }
         ok_i32(data_bytes.len() as i32)
@@ -364,7 +427,7 @@ pub extern "C" fn x07_ext_fs_append_all_v1(
 
         if cap_create_parents(caps) {
             if let Some(parent) = pb.parent() {
-                if let Err(e) = std::fs::create_dir_all(parent) {
+                if let Err(e) = create_dir_all_with_mode(parent, pol) {
                     return err_i32(map_io_err(&e));
                 }
             }
@@ -380,10 +443,11 @@ pub extern "C" fn x07_ext_fs_append_all_v1(
             Err(e) => return err_i32(map_io_err(&e)),
         }
 
-        let mut f = match OpenOptions::new().create(true).append(true).open(&pb) {
-            Ok(f) => f,
-            Err(e) => return err_i32(map_io_err(&e)),
-        };
+        let mut f =
+            match open_with_file_mode(OpenOptions::new().create(true).append(true), &pb, pol) {
+                Ok(f) => f,
+                Err(e) => return err_i32(map_io_err(&e)),
+            };
         if let Err(e) = f.write_all(data_bytes) {
             return err_i32(map_io_err(&e));
         }
@@ -420,9 +484,18 @@ pub extern "C" fn x07_ext_fs_stream_open_write_v1(path: ev_bytes, caps: ev_bytes
 
         let max_write = effective_max(pol.max_write_bytes, caps.max_write_bytes);
 
+        // Held until the handle is inserted so the capacity check can't race,
+        // and checked before any filesystem side effect.
+        let Ok(mut table) = writers().lock() else {
+            return err_i32(FS_ERR_IO);
+        };
+        if let Err(code) = check_writer_capacity(&table, pol.max_open_writers) {
+            return err_i32(code);
+        }
+
         if cap_create_parents(caps) {
             if let Some(parent) = pb.parent() {
-                if let Err(e) = std::fs::create_dir_all(parent) {
+                if let Err(e) = create_dir_all_with_mode(parent, pol) {
                     return err_i32(map_io_err(&e));
                 }
             }
@@ -436,19 +509,16 @@ pub extern "C" fn x07_ext_fs_stream_open_write_v1(path: ev_bytes, caps: ev_bytes
                 Err(code) => return err_i32(code),
             };
 
-            let handle = match writers().lock() {
-                Ok(mut table) => handle_insert(
-                    &mut table,
-                    WriterHandleV1 {
-                        file: Some(f),
-                        final_path: pb,
-                        tmp_path: Some(tmp),
-                        max_write_bytes: max_write,
-                        written: 0,
-                    },
-                ),
-                Err(_) => Err(FS_ERR_IO),
-            };
+            let handle = handle_insert(
+                &mut table,
+                WriterHandleV1 {
+                    file: Some(f),
+                    final_path: pb,
+                    tmp_path: Some(tmp),
+                    max_write_bytes: max_write,
+                    written: 0,
+                },
+            );
 
             return match handle {
                 Ok(h) => ok_i32(h),
@@ -476,37 +546,30 @@ pub extern "C" fn x07_ext_fs_stream_open_write_v1(path: ev_bytes, caps: ev_bytes
             }
         }
 
-        let open = if overwrite {
-            std::fs::OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(&pb)
+        let mut opts = OpenOptions::new();
+        opts.write(true);
+        if overwrite {
+            opts.create(true).truncate(true);
         } else {
-            std::fs::OpenOptions::new()
-                .write(true)
-                .create_new(true)
-                .open(&pb)
-        };
+            opts.create_new(true);
+        }
+        let open = open_with_file_mode(&mut opts, &pb, pol);
 
         let f = match open {
             Ok(f) => f,
             Err(e) => return err_i32(map_io_err(&e)),
         };
 
-        let handle = match writers().lock() {
-            Ok(mut table) => handle_insert(
-                &mut table,
-                WriterHandleV1 {
-                    file: Some(f),
-                    final_path: pb,
-                    tmp_path: None,
-                    max_write_bytes: max_write,
-                    written: 0,
-                },
-            ),
-            Err(_) => Err(FS_ERR_IO),
-        };
+        let handle = handle_insert(
+            &mut table,
+            WriterHandleV1 {
+                file: Some(f),
+                final_path: pb,
+                tmp_path: None,
+                max_write_bytes: max_write,
+                written: 0,
+            },
+        );
 
         match handle {
             Ok(h) => ok_i32(h),
@@ -608,6 +671,115 @@ pub extern "C" fn x07_ext_fs_stream_drop_v1(writer_handle: i32) -> i32 {
     .unwrap_or(1)
 }
 
+/// Takes an advisory lock for `path` (shared when `exclusive_flag == 0`),
+/// retrying for up to `wait_ms` (`<= 0` tries once). The lock is held on
+/// `<path>.x07_lock`, created if missing; `path` itself need not exist but
+/// must be writable under policy.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_lock_acquire_v1(
+    path: ev_bytes,
+    exclusive_flag: i32,
+    wait_ms: i32,
+    caps: ev_bytes,
+) -> ev_result_i32 {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_i32(code),
+        };
+
+        let pol = policy();
+        if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+            return err_i32(FS_ERR_SYMLINK_DENIED);
+        }
+
+        let path_bytes = bytes_as_slice(path);
+        let pb = match enforce_write_path(caps, path_bytes) {
+            Ok(p) => p,
+            Err(code) => return err_i32(code),
+        };
+        let lock_path = match lockfile_path(&pb) {
+            Ok(p) => p,
+            Err(code) => return err_i32(code),
+        };
+
+        let file = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+        {
+            Ok(f) => f,
+            Err(e) => return err_i32(map_io_err(&e)),
+        };
+
+        let exclusive = exclusive_flag != 0;
+        let deadline = Instant::now() + Duration::from_millis(wait_ms.max(0) as u64);
+        loop {
+            match try_lock_file(&file, exclusive) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(code) => return err_i32(code),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return err_i32(FS_ERR_LOCK_TIMEOUT);
+            }
+            std::thread::sleep((deadline - now).min(Duration::from_millis(LOCK_POLL_MS)));
+        }
+
+        let handle = match locks().lock() {
+            Ok(mut table) => handle_insert(&mut table, LockHandleV1 { file }),
+            Err(_) => Err(FS_ERR_IO),
+        };
+        match handle {
+            Ok(h) => ok_i32(h),
+            Err(code) => err_i32(code),
+        }
+    })
+    .unwrap_or_else(|_| err_i32(FS_ERR_IO))
+}
+
+/// Releases a lock handle and frees its slot. Releasing an already released
+/// handle is a no-op.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_lock_release_v1(lock_handle: i32) -> ev_result_i32 {
+    std::panic::catch_unwind(|| {
+        let Ok(mut table) = locks().lock() else {
+            return err_i32(FS_ERR_IO);
+        };
+        let Some(idx) = handle_idx(lock_handle) else {
+            return err_i32(FS_ERR_BAD_HANDLE);
+        };
+        let Some(slot) = table.get_mut(idx) else {
+            return err_i32(FS_ERR_BAD_HANDLE);
+        };
+        if let Some(l) = slot.take() {
+            let _ = l.file.unlock();
+        }
+        ok_i32(1)
+    })
+    .unwrap_or_else(|_| err_i32(FS_ERR_IO))
+}
+
+/// Releases every held lock; for runtime teardown. Returns how many were held.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_lock_release_all_v1() -> i32 {
+    std::panic::catch_unwind(|| {
+        let Ok(mut table) = locks().lock() else {
+            return 0;
+        };
+        let mut n: i32 = 0;
+        for l in table.iter_mut().filter_map(Option::take) {
+            let _ = l.file.unlock();
+            n = n.saturating_add(1);
+        }
+        n
+    })
+    .unwrap_or(0)
+}
+
 fn write_atomic_best_effort(path: &Path, data: &[u8], overwrite: bool) -> ev_result_i32 {
     let Some(parent) = path.parent() else {
         return err_i32(FS_ERR_BAD_PATH);
@@ -623,11 +795,11 @@ fn write_atomic_best_effort(path: &Path, data: &[u8], overwrite: bool) -> ev_res
     let mut counter: u32 = 0;
     let tmp_path = loop {
         let candidate = parent.join(format!("{name}.x07_tmp_{counter}"));
-        match std::fs::OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(&candidate)
-        {
+        match open_with_file_mode(
+            OpenOptions::new().write(true).create_new(true),
+            &candidate,
+            policy(),
+        ) {
             Ok(mut f) => {
                 if let Err(e) = f.write_all(data) {
                     let _ = std::fs::remove_file(&candidate);
@@ -874,7 +1046,7 @@ pub extern "C" fn x07_ext_fs_mkdirs_v1(path: ev_bytes, caps: ev_bytes) -> ev_res
             Ok(p) => p,
             Err(code) => return err_i32(code),
         };
-        match std::fs::create_dir_all(&pb) {
+        match create_dir_all_with_mode(&pb, pol) {
             Ok(()) => ok_i32(1),
             Err(e) => err_i32(map_io_err(&e)),
         }
@@ -1011,58 +1183,265 @@ pub extern "C" fn x07_ext_fs_list_dir_sorted_text_v1(
             Ok(caps) => caps,
             Err(code) => return err_bytes(code),
         };
+        match list_dir_sorted_text(policy(), caps, bytes_as_slice(path)) {
+            Ok(out) => ok_bytes_vec(out),
+            Err(code) => err_bytes(code),
+        }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
 
-        let pol = policy();
-        if !pol.allow_walk {
-            return err_bytes(FS_ERR_POLICY_DENY);
+/// Body of `list_dir_sorted_text_v1`.
+fn list_dir_sorted_text(pol: &Policy, caps: CapsV1, path_bytes: &[u8]) -> Result<Vec<u8>, i32> {
+    if !pol.allow_walk {
+        return Err(FS_ERR_POLICY_DENY);
+    }
+    if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
+    }
+
+    let pb = enforce_read_path_with(pol, caps, path_bytes)?;
+
+    let md = std::fs::metadata(&pb).map_err(|e| map_io_err(&e))?;
+    if !md.is_dir() {
+        return Err(FS_ERR_NOT_DIR);
+    }
+
+    let max = effective_max(pol.max_entries, caps.max_entries) as usize;
+    let mut names: Vec<String> = Vec::new();
+
+    let rd = std::fs::read_dir(&pb).map_err(|e| map_io_err(&e))?;
+    for ent in rd {
+        let ent = ent.map_err(|e| map_io_err(&e))?;
+        let Ok(name) = ent.file_name().into_string() else {
+            continue;
+        };
+        if pol.deny_hidden && name.starts_with('.') && !cap_allow_hidden(caps) {
+            continue;
         }
-        if cap_allow_symlinks(caps) && !pol.allow_symlinks {
-            return err_bytes(FS_ERR_SYMLINK_DENIED);
+        if pol.denies_read(&pb.join(&name)) {
+            continue;
+        }
+        names.push(pol.normalize_name(&name).into_owned());
+        if names.len() > max {
+            return Err(FS_ERR_TOO_MANY_ENTRIES);
         }
+    }
 
-        let path_bytes = bytes_as_slice(path);
-        let pb = match enforce_read_path(caps, path_bytes) {
-            Ok(p) => p,
-            Err(code) => return err_bytes(code),
-        };
+    Ok(join_lines_sorted(names))
+}
 
-        let md = match std::fs::metadata(&pb) {
-            Ok(m) => m,
-            Err(e) => return err_bytes(map_io_err(&e)),
+// -------------------------
+// Paged directory listing cache
+// -------------------------
+
+const LIST_DIR_CACHE_CAP: usize = 8;
+
+/// Sorted, hidden-filtered listing of one directory as of `mtime`.
+#[derive(Debug)]
+struct DirListingV1 {
+    path: PathBuf,
+    mtime: SystemTime,
+    include_hidden: bool,
+    unicode_normalize: UnicodeNormalize,
+    names: Arc<Vec<String>>,
+    /// Entries left out because their name is not UTF-8.
+    skipped_non_utf8: u32,
+}
+
+/// Most recently used first; bounded by `LIST_DIR_CACHE_CAP`.
+static LIST_DIR_CACHE: OnceCell<Mutex<Vec<DirListingV1>>> = OnceCell::new();
+
+fn list_dir_cache() -> &'static Mutex<Vec<DirListingV1>> {
+    LIST_DIR_CACHE.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Returns the sorted names in `dir` and how many non-UTF-8 names were
+/// skipped, reusing the cached listing while the directory's mtime is
+/// unchanged. Names are in the policy's Unicode form.
+fn sorted_dir_names(
+    pol: &Policy,
+    dir: &Path,
+    mtime: SystemTime,
+    include_hidden: bool,
+) -> Result<(Arc<Vec<String>>, u32), i32> {
+    {
+        let mut cache = list_dir_cache().lock().map_err(|_| FS_ERR_IO)?;
+        if let Some(idx) = cache.iter().position(|e| {
+            e.path == dir
+                && e.include_hidden == include_hidden
+                && e.unicode_normalize == pol.unicode_normalize
+        }) {
+            let ent = cache.remove(idx);
+            if ent.mtime == mtime {
+                let hit = (ent.names.clone(), ent.skipped_non_utf8);
+                cache.insert(0, ent);
+                return Ok(hit);
+            }
+        }
+    }
+
+    let rd = std::fs::read_dir(dir).map_err(|e| map_io_err(&e))?;
+    let mut names: Vec<String> = Vec::new();
+    let mut skipped_non_utf8: u32 = 0;
+    for ent in rd {
+        let ent = ent.map_err(|e| map_io_err(&e))?;
+        let Ok(name) = ent.file_name().into_string() else {
+            skipped_non_utf8 = skipped_non_utf8.saturating_add(1);
+            continue;
         };
-        if !md.is_dir() {
-            return err_bytes(FS_ERR_NOT_DIR);
+        if !include_hidden && name.starts_with('.') {
+            continue;
         }
+        names.push(pol.normalize_name(&name).into_owned());
+    }
+    // UTF-8 string order, same as list_dir_sorted_text_v1.
+    names.sort();
+    names.dedup();
+    let names = Arc::new(names);
+
+    let mut cache = list_dir_cache().lock().map_err(|_| FS_ERR_IO)?;
+    cache.retain(|e| {
+        !(e.path == dir
+            && e.include_hidden == include_hidden
+            && e.unicode_normalize == pol.unicode_normalize)
+    });
+    cache.insert(
+        0,
+        DirListingV1 {
+            path: dir.to_path_buf(),
+            mtime,
+            include_hidden,
+            unicode_normalize: pol.unicode_normalize,
+            names: names.clone(),
+            skipped_non_utf8,
+        },
+    );
+    cache.truncate(LIST_DIR_CACHE_CAP);
+    Ok((names, skipped_non_utf8))
+}
 
-        let max = effective_max(pol.max_entries, caps.max_entries) as usize;
-        let mut names: Vec<String> = Vec::new();
+/// One page of the sorted listing: the names strictly after
+/// `start_after_name` (empty = from the start), one per line, followed by a
+/// single marker byte (0x01 if more entries remain, else 0x00). `page_size <= 0`
+/// or above the effective `max_entries` is clamped to `max_entries`.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_list_dir_page_v1(
+    path: ev_bytes,
+    start_after_name: ev_bytes,
+    page_size: i32,
+    caps: ev_bytes,
+) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
+        };
+        match list_dir_page(
+            policy(),
+            caps,
+            bytes_as_slice(path),
+            bytes_as_slice(start_after_name),
+            page_size,
+        ) {
+            Ok(page) => ok_bytes_vec(page.lines),
+            Err(code) => err_bytes(code),
+        }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
 
-        let rd = match std::fs::read_dir(&pb) {
-            Ok(r) => r,
-            Err(e) => return err_bytes(map_io_err(&e)),
+/// `list_dir_page_v1` with the directory's count of skipped non-UTF-8 names
+/// appended after the marker byte as `skipped_non_utf8 u32_le`.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_list_dir_page_meta_v1(
+    path: ev_bytes,
+    start_after_name: ev_bytes,
+    page_size: i32,
+    caps: ev_bytes,
+) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
         };
-        for ent in rd {
-            let ent = match ent {
-                Ok(e) => e,
-                Err(e) => return err_bytes(map_io_err(&e)),
-            };
-            let Ok(name) = ent.file_name().into_string() else {
-                continue;
-            };
-            if pol.deny_hidden && name.starts_with('.') && !cap_allow_hidden(caps) {
-                continue;
-            }
-            names.push(name);
-            if names.len() > max {
-                return err_bytes(FS_ERR_TOO_MANY_ENTRIES);
+        match list_dir_page(
+            policy(),
+            caps,
+            bytes_as_slice(path),
+            bytes_as_slice(start_after_name),
+            page_size,
+        ) {
+            Ok(page) => {
+                let mut out = page.lines;
+                out.extend_from_slice(&page.skipped_non_utf8.to_le_bytes());
+                ok_bytes_vec(out)
             }
+            Err(code) => err_bytes(code),
         }
-
-        ok_bytes_vec(join_lines_sorted(names))
     })
     .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
 }
 
+struct DirPageV1 {
+    /// Names and the trailing has-more marker, as `list_dir_page_v1` returns them.
+    lines: Vec<u8>,
+    skipped_non_utf8: u32,
+}
+
+/// Body of `list_dir_page_v1` / `list_dir_page_meta_v1`.
+fn list_dir_page(
+    pol: &Policy,
+    caps: CapsV1,
+    path_bytes: &[u8],
+    start_after_name: &[u8],
+    page_size: i32,
+) -> Result<DirPageV1, i32> {
+    if !pol.allow_walk {
+        return Err(FS_ERR_POLICY_DENY);
+    }
+    if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
+    }
+
+    let pb = enforce_read_path_with(pol, caps, path_bytes)?;
+    let start_after = bytes_to_utf8(start_after_name)?;
+    let start_after = pol.normalize_name(start_after);
+
+    let md = std::fs::metadata(&pb).map_err(|e| map_io_err(&e))?;
+    if !md.is_dir() {
+        return Err(FS_ERR_NOT_DIR);
+    }
+    let mtime = md.modified().map_err(|e| map_io_err(&e))?;
+
+    let max = effective_max(pol.max_entries, caps.max_entries) as usize;
+    let page_size = match usize::try_from(page_size) {
+        Ok(n) if n > 0 => n.min(max),
+        _ => max,
+    };
+
+    let include_hidden = !pol.deny_hidden || cap_allow_hidden(caps);
+    let (names, skipped_non_utf8) = sorted_dir_names(pol, &pb, mtime, include_hidden)?;
+
+    // Filtered per call: the cache holds the raw listing, denies are policy.
+    let names: Vec<&String> = names
+        .iter()
+        .filter(|n| !pol.denies_read(&pb.join(n)))
+        .collect();
+    let start = names.partition_point(|n| n.as_str() <= start_after.as_ref());
+    let end = start.saturating_add(page_size).min(names.len());
+    let mut lines: Vec<u8> = Vec::new();
+    for name in &names[start..end] {
+        lines.extend_from_slice(name.as_bytes());
+        lines.push(b'\n');
+    }
+    lines.push(u8::from(end < names.len()));
+    Ok(DirPageV1 {
+        lines,
+        skipped_non_utf8,
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn x07_ext_fs_walk_glob_sorted_text_v1(
     root: ev_bytes,
@@ -1074,90 +1453,455 @@ pub extern "C" fn x07_ext_fs_walk_glob_sorted_text_v1(
             Ok(caps) => caps,
             Err(code) => return err_bytes(code),
         };
-
-        let pol = policy();
-        if !pol.allow_walk || !pol.allow_glob {
-            return err_bytes(FS_ERR_POLICY_DENY);
+        match walk_glob_sorted_text(policy(), caps, bytes_as_slice(root), bytes_as_slice(glob)) {
+            Ok(out) => ok_bytes_vec(out),
+            Err(code) => err_bytes(code),
         }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
 
-        let root_b = bytes_as_slice(root);
-        let root_pb = match enforce_read_path(caps, root_b) {
-            Ok(p) => p,
-            Err(code) => return err_bytes(code),
-        };
+/// Body of `walk_glob_sorted_text_v1`. Files covered by a deny-read glob are
+/// dropped from the listing rather than failing the walk.
+fn walk_glob_sorted_text(
+    pol: &Policy,
+    caps: CapsV1,
+    root_b: &[u8],
+    glob_b: &[u8],
+) -> Result<Vec<u8>, i32> {
+    if !pol.allow_walk || !pol.allow_glob {
+        return Err(FS_ERR_POLICY_DENY);
+    }
 
-        let md = match std::fs::metadata(&root_pb) {
-            Ok(m) => m,
-            Err(e) => return err_bytes(map_io_err(&e)),
-        };
-        if !md.is_dir() {
-            return err_bytes(FS_ERR_NOT_DIR);
-        }
+    let root_pb = enforce_read_path_with(pol, caps, root_b)?;
 
-        let glob_b = bytes_as_slice(glob);
-        let glob_s = match bytes_to_utf8(glob_b) {
-            Ok(s) => s,
-            Err(code) => return err_bytes(code),
-        };
-        let matcher = match build_glob_matcher(glob_s) {
-            Ok(m) => m,
-            Err(code) => return err_bytes(code),
-        };
+    let md = std::fs::metadata(&root_pb).map_err(|e| map_io_err(&e))?;
+    if !md.is_dir() {
+        return Err(FS_ERR_NOT_DIR);
+    }
 
-        let follow_links = cap_allow_symlinks(caps) && pol.allow_symlinks;
-        if cap_allow_symlinks(caps) && !pol.allow_symlinks {
-            return err_bytes(FS_ERR_SYMLINK_DENIED);
-        }
+    let glob_s = bytes_to_utf8(glob_b)?;
+    let matcher = build_glob_matcher(glob_s)?;
 
-        let max_entries = effective_max(pol.max_entries, caps.max_entries) as usize;
-        let max_depth = effective_max(pol.max_depth, caps.max_depth) as usize;
+    let follow_links = cap_allow_symlinks(caps) && pol.allow_symlinks;
+    if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
+    }
 
-        let walker = WalkDir::new(&root_pb)
-            .follow_links(follow_links)
-            .max_depth(max_depth.saturating_add(1));
+    let max_entries = effective_max(pol.max_entries, caps.max_entries) as usize;
+    let max_depth = effective_max(pol.max_depth, caps.max_depth) as usize;
 
-        let mut out: Vec<String> = Vec::new();
+    let walker = WalkDir::new(&root_pb)
+        .follow_links(follow_links)
+        .max_depth(max_depth.saturating_add(1));
 
-        for ent in walker {
-            let ent = match ent {
-                Ok(e) => e,
-                Err(_) => return err_bytes(FS_ERR_IO),
-            };
-            if ent.depth() > max_depth {
-                return err_bytes(FS_ERR_DEPTH_EXCEEDED);
-            }
-            if ent.file_type().is_dir() {
-                continue;
-            }
-            let rel = match ent.path().strip_prefix(&root_pb) {
-                Ok(r) => r,
-                Err(_) => continue,
-            };
-            let Some(rel_s) = rel.to_str() else {
-                continue;
-            };
-            let rel_s = rel_s.replace('\\', "/");
-            if pol.deny_hidden
-                && !cap_allow_hidden(caps)
-                && rel_s.split('/').any(|s| s.starts_with('.'))
-            {
-                continue;
-            }
-            if matcher.is_match(rel_s.as_str()) {
-                out.push(rel_s);
-                if out.len() > max_entries {
-                    return err_bytes(FS_ERR_TOO_MANY_ENTRIES);
-                }
+    let mut out: Vec<String> = Vec::new();
+
+    for ent in walker {
+        let ent = ent.map_err(|_| FS_ERR_IO)?;
+        if ent.depth() > max_depth {
+            return Err(FS_ERR_DEPTH_EXCEEDED);
+        }
+        if ent.file_type().is_dir() {
+            continue;
+        }
+        let rel = match ent.path().strip_prefix(&root_pb) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        let Some(rel_s) = rel.to_str() else {
+            continue;
+        };
+        let rel_s = pol.normalize_name(&rel_s.replace('\\', "/")).into_owned();
+        if pol.deny_hidden
+            && !cap_allow_hidden(caps)
+            && rel_s.split('/').any(|s| s.starts_with('.'))
+        {
+            continue;
+        }
+        if pol.denies_read(ent.path()) {
+            continue;
+        }
+        if matcher.is_match(rel_s.as_str()) {
+            out.push(rel_s);
+            if out.len() > max_entries {
+                return Err(FS_ERR_TOO_MANY_ENTRIES);
             }
         }
+    }
 
-        ok_bytes_vec(join_lines_sorted(out))
-    })
-    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+    Ok(join_lines_sorted(out))
 }
 
+/// Recursive size of the tree under `root` (root itself not counted), as a
+/// 17-byte record: `total_bytes u64_le`, `file_count u32_le`,
+/// `dir_count u32_le`, `truncated u8`. Hitting the effective
+/// max_entries/max_depth sets `truncated` instead of failing. Hidden entries are
+/// skipped unless allowed; symlinks are never counted, and are followed only
+/// when caps and policy allow it and the target stays under `root`.
 #[no_mangle]
-pub extern "C" fn x07_ext_fs_stat_v1(path: ev_bytes, caps: ev_bytes) -> ev_result_bytes {
+pub extern "C" fn x07_ext_fs_du_v1(root: ev_bytes, caps: ev_bytes) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
+        };
+        match disk_usage(policy(), caps, bytes_as_slice(root)) {
+            Ok(out) => ok_bytes_vec(out),
+            Err(code) => err_bytes(code),
+        }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
+
+/// Body of `du_v1`. Each file or dir is counted once per `(dev, ino)`, so hard
+/// links and followed links back into the tree do not inflate the totals.
+fn disk_usage(pol: &Policy, caps: CapsV1, root_b: &[u8]) -> Result<Vec<u8>, i32> {
+    if !pol.allow_walk {
+        return Err(FS_ERR_POLICY_DENY);
+    }
+    if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
+    }
+    let follow_links = cap_allow_symlinks(caps) && pol.allow_symlinks;
+
+    let root_pb = enforce_read_path_with(pol, caps, root_b)?;
+    let md = std::fs::metadata(&root_pb).map_err(|e| map_io_err(&e))?;
+    if !md.is_dir() {
+        return Err(FS_ERR_NOT_DIR);
+    }
+    let root_canon = std::fs::canonicalize(&root_pb).map_err(|e| map_io_err(&e))?;
+
+    let max_entries = effective_max(pol.max_entries, caps.max_entries) as u64;
+    let max_depth = effective_max(pol.max_depth, caps.max_depth) as usize;
+    let skip_hidden = pol.deny_hidden && !cap_allow_hidden(caps);
+
+    let mut total_bytes: u64 = 0;
+    let mut file_count: u32 = 0;
+    let mut dir_count: u32 = 0;
+    let mut truncated = false;
+    let mut seen: HashSet<(u64, u64)> = file_id(&md).into_iter().collect();
+
+    let mut walker = WalkDir::new(&root_pb)
+        .follow_links(follow_links)
+        .min_depth(1)
+        .max_depth(max_depth.saturating_add(1))
+        .into_iter()
+        .filter_entry(|e| {
+            let hidden = skip_hidden && e.file_name().to_string_lossy().starts_with('.');
+            !hidden && !pol.denies_read(e.path())
+        });
+    while let Some(ent) = walker.next() {
+        let ent = match ent {
+            Ok(e) => e,
+            Err(e) => match e.io_error().map(io::Error::kind) {
+                // Entries removed mid-walk, or link loops, are skipped.
+                Some(io::ErrorKind::NotFound) | None => continue,
+                Some(_) => return Err(FS_ERR_IO),
+            },
+        };
+        if ent.depth() > max_depth {
+            // Only reached one level past the bound, so nothing below it.
+            truncated = true;
+            continue;
+        }
+        if ent.path_is_symlink() {
+            let inside = follow_links
+                && std::fs::canonicalize(ent.path()).is_ok_and(|t| t.starts_with(&root_canon));
+            if !inside {
+                if ent.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+        }
+        let ft = ent.file_type();
+        let md = match ent.metadata() {
+            Ok(md) => md,
+            Err(_) => continue,
+        };
+        if file_id(&md).is_some_and(|id| !seen.insert(id)) {
+            if ft.is_dir() {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+        if u64::from(file_count) + u64::from(dir_count) >= max_entries {
+            truncated = true;
+            break;
+        }
+
+        if ft.is_dir() {
+            dir_count += 1;
+        } else if ft.is_file() {
+            file_count += 1;
+            total_bytes = total_bytes.saturating_add(md.len());
+        }
+    }
+
+    let mut out = Vec::with_capacity(17);
+    out.extend_from_slice(&total_bytes.to_le_bytes());
+    out.extend_from_slice(&file_count.to_le_bytes());
+    out.extend_from_slice(&dir_count.to_le_bytes());
+    out.push(u8::from(truncated));
+    Ok(out)
+}
+
+#[cfg(unix)]
+fn file_id(md: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt as _;
+    Some((md.dev(), md.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_md: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Outcome of [`copy_tree`]. A copy that stopped part way still reports what
+/// it did, plus the error and the path (relative to `src`) it stopped at.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CopyTreeV1 {
+    files_copied: u32,
+    bytes_copied: u64,
+    dirs_created: u32,
+    links_copied: u32,
+    links_skipped: u32,
+    failure: Option<(i32, String)>,
+}
+
+impl CopyTreeV1 {
+    fn fail(mut self, code: i32, rel: &Path) -> Self {
+        let rel = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.failure = Some((code, rel));
+        self
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let (code, rel) = match &self.failure {
+            Some((code, rel)) => (*code as u32, rel.as_bytes()),
+            None => (0, &[][..]),
+        };
+        let mut out = Vec::with_capacity(32 + rel.len());
+        out.extend_from_slice(&self.files_copied.to_le_bytes());
+        out.extend_from_slice(&self.bytes_copied.to_le_bytes());
+        out.extend_from_slice(&self.dirs_created.to_le_bytes());
+        out.extend_from_slice(&self.links_copied.to_le_bytes());
+        out.extend_from_slice(&self.links_skipped.to_le_bytes());
+        out.extend_from_slice(&code.to_le_bytes());
+        out.extend_from_slice(&(rel.len() as u32).to_le_bytes());
+        out.extend_from_slice(rel);
+        out
+    }
+}
+
+/// Copies the tree under `src` to `dst`. Errors before anything is written
+/// come back as `Err`; once copying has started, a failure stops the walk
+/// and is recorded in the returned [`CopyTreeV1`].
+fn copy_tree(pol: &Policy, caps: CapsV1, src: &[u8], dst: &[u8]) -> Result<CopyTreeV1, i32> {
+    if !pol.allow_walk || !pol.allow_mkdir {
+        return Err(FS_ERR_POLICY_DENY);
+    }
+    if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
+    }
+    let follow_links = cap_allow_symlinks(caps) && pol.allow_symlinks;
+    let overwrite = cap_overwrite(caps);
+
+    let src_pb = enforce_read_path_with(pol, caps, src)?;
+    let dst_pb = enforce_write_path_with(pol, caps, dst)?;
+    let src_md = std::fs::metadata(&src_pb).map_err(|e| map_io_err(&e))?;
+    if !src_md.is_dir() {
+        return Err(FS_ERR_NOT_DIR);
+    }
+    let src_canon = std::fs::canonicalize(&src_pb).map_err(|e| map_io_err(&e))?;
+
+    match std::fs::symlink_metadata(&dst_pb) {
+        Ok(m) if m.is_dir() && overwrite => {}
+        Ok(m) if m.file_type().is_symlink() => return Err(FS_ERR_SYMLINK_DENIED),
+        Ok(m) if m.is_dir() => return Err(FS_ERR_ALREADY_EXISTS),
+        Ok(_) => return Err(FS_ERR_NOT_DIR),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let parent = dst_pb.parent().filter(|p| !p.as_os_str().is_empty());
+            if let Some(parent) = parent {
+                if cap_create_parents(caps) {
+                    create_dir_all_with_mode(parent, pol).map_err(|e| map_io_err(&e))?;
+                } else if !parent.is_dir() {
+                    return Err(FS_ERR_NOT_FOUND);
+                }
+            }
+        }
+        Err(e) => return Err(map_io_err(&e)),
+    }
+    // Copying a tree onto or into itself would never finish.
+    let dst_parent = dst_pb
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let dst_canon = std::fs::canonicalize(dst_parent)
+        .map_err(|e| map_io_err(&e))?
+        .join(dst_pb.file_name().ok_or(FS_ERR_BAD_PATH)?);
+    if dst_canon.starts_with(&src_canon) {
+        return Err(FS_ERR_BAD_PATH);
+    }
+
+    let mut out = CopyTreeV1::default();
+    if std::fs::symlink_metadata(&dst_pb).is_err() {
+        if let Err(e) = create_dir_all_with_mode(&dst_pb, pol) {
+            return Err(map_io_err(&e));
+        }
+        out.dirs_created += 1;
+    }
+
+    let max_entries = effective_max(pol.max_entries, caps.max_entries) as u64;
+    let max_depth = effective_max(pol.max_depth, caps.max_depth) as usize;
+    let max_bytes = effective_max(pol.max_write_bytes, caps.max_write_bytes) as u64;
+    let skip_hidden = pol.deny_hidden && !cap_allow_hidden(caps);
+
+    let mut entries: u64 = 0;
+    let mut walker = WalkDir::new(&src_pb)
+        .follow_links(follow_links)
+        .min_depth(1)
+        .max_depth(max_depth.saturating_add(1))
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let hidden = skip_hidden && e.file_name().to_string_lossy().starts_with('.');
+            !hidden && !pol.denies_read(e.path())
+        });
+    while let Some(ent) = walker.next() {
+        let ent = match ent {
+            Ok(e) => e,
+            Err(e) => {
+                let rel = e.path().and_then(|p| p.strip_prefix(&src_pb).ok());
+                let code = e.io_error().map_or(FS_ERR_IO, map_io_err);
+                return Ok(out.fail(code, rel.unwrap_or(Path::new(""))));
+            }
+        };
+        let rel = ent.path().strip_prefix(&src_pb).unwrap_or(ent.path());
+        if ent.depth() > max_depth {
+            return Ok(out.fail(FS_ERR_DEPTH_EXCEEDED, rel));
+        }
+        entries += 1;
+        if entries > max_entries {
+            return Ok(out.fail(FS_ERR_TOO_MANY_ENTRIES, rel));
+        }
+        let target = dst_pb.join(rel);
+        if pol.denies_write(&target) {
+            return Ok(out.fail(FS_ERR_POLICY_DENY, rel));
+        }
+
+        // Links are followed only into the source tree; any other link is
+        // copied as a link.
+        let as_link = ent.path_is_symlink()
+            && !(follow_links
+                && std::fs::canonicalize(ent.path()).is_ok_and(|t| t.starts_with(&src_canon)));
+        let ft = ent.file_type();
+        if as_link && ft.is_dir() {
+            walker.skip_current_dir();
+        }
+
+        let existing = std::fs::symlink_metadata(&target).ok();
+        if let Some(m) = &existing {
+            if !overwrite {
+                return Ok(out.fail(FS_ERR_ALREADY_EXISTS, rel));
+            }
+            let res = if ft.is_dir() && !as_link {
+                if m.is_dir() {
+                    continue;
+                }
+                Err(FS_ERR_NOT_DIR)
+            } else if m.is_dir() {
+                Err(FS_ERR_IS_DIR)
+            } else {
+                // Never write through a link that happens to sit in `dst`.
+                std::fs::remove_file(&target).map_err(|e| map_io_err(&e))
+            };
+            if let Err(code) = res {
+                return Ok(out.fail(code, rel));
+            }
+        }
+
+        if as_link {
+            match copy_symlink(ent.path(), &target) {
+                Ok(true) => out.links_copied += 1,
+                Ok(false) => out.links_skipped += 1,
+                Err(e) => return Ok(out.fail(map_io_err(&e), rel)),
+            }
+        } else if ft.is_dir() {
+            if let Err(e) = create_dir_all_with_mode(&target, pol) {
+                return Ok(out.fail(map_io_err(&e), rel));
+            }
+            out.dirs_created += 1;
+        } else if ft.is_file() {
+            let len = match ent.metadata() {
+                Ok(m) => m.len(),
+                Err(_) => return Ok(out.fail(FS_ERR_IO, rel)),
+            };
+            if out.bytes_copied.saturating_add(len) > max_bytes {
+                return Ok(out.fail(FS_ERR_TOO_LARGE, rel));
+            }
+            let copied = std::fs::File::open(ent.path()).and_then(|mut from| {
+                let mut to = open_with_file_mode(
+                    OpenOptions::new().write(true).create_new(true),
+                    &target,
+                    pol,
+                )?;
+                io::copy(&mut (&mut from).take(len), &mut to)
+            });
+            match copied {
+                Ok(n) => {
+                    out.files_copied += 1;
+                    out.bytes_copied += n;
+                }
+                Err(e) => return Ok(out.fail(map_io_err(&e), rel)),
+            }
+        } else {
+            return Ok(out.fail(FS_ERR_UNSUPPORTED, rel));
+        }
+    }
+    Ok(out)
+}
+
+/// Recreates the link at `from` at `to` with the same target text. Returns
+/// `false` where links cannot be created portably (Windows).
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<bool> {
+    let target = std::fs::read_link(from)?;
+    std::os::unix::fs::symlink(target, to)?;
+    Ok(true)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(_from: &Path, _to: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_copy_tree_v1(
+    src: ev_bytes,
+    dst: ev_bytes,
+    caps: ev_bytes,
+) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
+        };
+        match copy_tree(policy(), caps, bytes_as_slice(src), bytes_as_slice(dst)) {
+            Ok(out) => ok_bytes_vec(out.encode()),
+            Err(code) => err_bytes(code),
+        }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
+
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_stat_v1(path: ev_bytes, caps: ev_bytes) -> ev_result_bytes {
     std::panic::catch_unwind(|| unsafe {
         let caps = match parse_caps_v1(bytes_as_slice(caps)) {
             Ok(caps) => caps,
@@ -1220,129 +1964,580 @@ pub extern "C" fn x07_ext_fs_stat_v1(path: ev_bytes, caps: ev_bytes) -> ev_resul
     .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use x07_ext_os_native_core::{CAP_ATOMIC_WRITE, CAP_CREATE_PARENTS, CAP_OVERWRITE};
+/// Sets a file's modification time to `secs.nanos` since the Unix epoch, so
+/// deterministic worlds can pin mtimes. Gated like a write to `path`.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_set_mtime_v1(
+    path: ev_bytes,
+    secs: u64,
+    nanos: u32,
+    caps: ev_bytes,
+) -> ev_result_i32 {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_i32(code),
+        };
 
-    #[no_mangle]
-    extern "C" fn ev_bytes_alloc(len: u32) -> ev_bytes {
-        let mut v = vec![0u8; len as usize];
-        let ptr = v.as_mut_ptr();
-        std::mem::forget(v);
-        ev_bytes { ptr, len }
-    }
+        let pol = policy();
+        let follow_symlinks = cap_allow_symlinks(caps);
+        if follow_symlinks && !pol.allow_symlinks {
+            return err_i32(FS_ERR_SYMLINK_DENIED);
+        }
+        if nanos >= 1_000_000_000 {
+            return err_i32(FS_ERR_TOO_LARGE);
+        }
 
-    #[no_mangle]
-    extern "C" fn ev_trap(code: i32) -> ! {
-        panic!("ev_trap({code})")
-    }
+        let path_bytes = bytes_as_slice(path);
+        let pb = match enforce_write_path(caps, path_bytes) {
+            Ok(p) => p,
+            Err(code) => return err_i32(code),
+        };
 
-    fn caps_v1(max_write_bytes: u32, flags: u32) -> Vec<u8> {
-        let mut out = Vec::with_capacity(24);
-        out.extend_from_slice(&1u32.to_le_bytes());
-        out.extend_from_slice(&0u32.to_le_bytes()); // max_read_bytes
-        out.extend_from_slice(&max_write_bytes.to_le_bytes());
-        out.extend_from_slice(&0u32.to_le_bytes()); // max_entries
-        out.extend_from_slice(&0u32.to_le_bytes()); // max_depth
-        out.extend_from_slice(&flags.to_le_bytes());
-        out
-    }
+        let md = match std::fs::symlink_metadata(&pb) {
+            Ok(m) => m,
+            Err(e) => return err_i32(map_io_err(&e)),
+        };
+        if md.file_type().is_symlink() && !follow_symlinks {
+            return err_i32(FS_ERR_SYMLINK_DENIED);
+        }
+        if md.is_dir() {
+            return err_i32(FS_ERR_IS_DIR);
+        }
 
-    fn to_ev_bytes(b: &[u8]) -> ev_bytes {
-        ev_bytes {
-            ptr: b.as_ptr() as *mut u8,
-            len: b.len() as u32,
+        let Some(mtime) = UNIX_EPOCH.checked_add(Duration::new(secs, nanos)) else {
+            return err_i32(FS_ERR_TOO_LARGE);
+        };
+        let f = match OpenOptions::new().write(true).open(&pb) {
+            Ok(f) => f,
+            Err(e) => return err_i32(map_io_err(&e)),
+        };
+        // `futimens`/`SetFileTime`; platforms without it report Unsupported.
+        match f.set_modified(mtime) {
+            Ok(()) => ok_i32(1),
+            Err(e) => err_i32(map_io_err(&e)),
         }
-    }
+    })
+    .unwrap_or_else(|_| err_i32(FS_ERR_IO))
+}
 
-    fn ok_i32(res: ev_result_i32) -> i32 {
-        assert_eq!(res.tag, 1, "expected ok, got err={}", unsafe {
-            res.payload.err
-        });
-        unsafe { res.payload.ok as i32 }
-    }
+/// Bits [`x07_ext_fs_set_mode_v1`] applies.
+const SET_MODE_PERM_BITS: u32 = 0o777;
+/// setuid, setgid and sticky: denied even when chmod is allowed.
+const SET_MODE_SPECIAL_BITS: u32 = 0o7000;
 
-    fn err_i32(res: ev_result_i32) -> i32 {
-        assert_eq!(res.tag, 0, "expected err");
-        unsafe { res.payload.err as i32 }
-    }
+/// Windows has no permission bits, only a readonly attribute: a mode with no
+/// write bit makes the file readonly.
+#[cfg(any(windows, test))]
+fn mode_is_readonly(mode: u32) -> bool {
+    mode & 0o222 == 0
+}
 
-    fn ok_bytes(res: ev_result_bytes) -> Vec<u8> {
-        assert_eq!(res.tag, 1, "expected ok, got err={}", unsafe {
-            res.payload.err
-        });
-        let out = unsafe { res.payload.ok };
-        unsafe { std::slice::from_raw_parts(out.ptr, out.len as usize).to_vec() }
+fn set_mode(pol: &Policy, caps: CapsV1, path_bytes: &[u8], mode: u32) -> Result<(), i32> {
+    if !pol.allow_chmod || mode & SET_MODE_SPECIAL_BITS != 0 {
+        return Err(FS_ERR_POLICY_DENY);
     }
-
-    fn err_bytes(res: ev_result_bytes) -> i32 {
-        assert_eq!(res.tag, 0, "expected err");
-        unsafe { res.payload.err as i32 }
+    if mode & !(SET_MODE_PERM_BITS | SET_MODE_SPECIAL_BITS) != 0 {
+        return Err(FS_ERR_UNSUPPORTED);
+    }
+    let follow_symlinks = cap_allow_symlinks(caps);
+    if follow_symlinks && !pol.allow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
     }
 
-    fn caps_read_v1(max_read_bytes: u32, flags: u32) -> Vec<u8> {
-        let mut out = Vec::with_capacity(24);
-        out.extend_from_slice(&1u32.to_le_bytes());
-        out.extend_from_slice(&max_read_bytes.to_le_bytes());
-        out.extend_from_slice(&0u32.to_le_bytes()); // max_write_bytes
-        out.extend_from_slice(&0u32.to_le_bytes()); // max_entries
-        out.extend_from_slice(&0u32.to_le_bytes()); // max_depth
-        out.extend_from_slice(&flags.to_le_bytes());
-        out
+    let pb = enforce_write_path_with(pol, caps, path_bytes)?;
+    let md = std::fs::symlink_metadata(&pb).map_err(|e| map_io_err(&e))?;
+    if md.file_type().is_symlink() && !follow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
     }
 
-    #[test]
-    fn fs_stream_writer_handle_v1_smoke() {
-        std::env::set_var("X07_OS_SANDBOXED", "0");
-        std::env::set_var("X07_OS_FS", "1");
-        std::env::set_var("X07_OS_FS_ALLOW_MKDIR", "1");
-        std::env::set_var("X07_OS_FS_ALLOW_RENAME", "1");
-        std::env::set_var("X07_OS_FS_MAX_WRITE_BYTES", "1000000");
+    apply_mode(&pb, mode).map_err(|e| map_io_err(&e))
+}
 
-        let root = format!("target/x07_ext_fs_stream_test_{}", std::process::id());
-        let _ = std::fs::remove_dir_all(&root);
-        std::fs::create_dir_all(&root).expect("create test dir");
+#[cfg(unix)]
+fn apply_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt as _;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
 
-        // Non-atomic writer, max_write_bytes enforced cumulatively.
-        let out_path = format!("{root}/out.txt");
-        let caps = caps_v1(8, CAP_CREATE_PARENTS | CAP_OVERWRITE);
-        let h = ok_i32(x07_ext_fs_stream_open_write_v1(
-            to_ev_bytes(out_path.as_bytes()),
-            to_ev_bytes(&caps),
-        ));
-        assert!(h > 0);
-        assert_eq!(
-            ok_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"abc"))),
-            3
-        );
-        assert_eq!(
-            ok_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"def"))),
-            3
-        );
-        assert_eq!(
-            ok_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"gh"))),
-            2
-        );
-        assert_eq!(
-            err_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"i"))),
-            FS_ERR_TOO_LARGE
-        );
-        assert_eq!(ok_i32(x07_ext_fs_stream_close_v1(h)), 1);
-        assert_eq!(x07_ext_fs_stream_drop_v1(h), 1);
+#[cfg(windows)]
+fn apply_mode(path: &Path, mode: u32) -> io::Result<()> {
+    let mut perms = std::fs::metadata(path)?.permissions();
+    perms.set_readonly(mode_is_readonly(mode));
+    std::fs::set_permissions(path, perms)
+}
 
-        let got = std::fs::read(&out_path).expect("read out.txt");
-        assert_eq!(got, b"abcdefgh");
+#[cfg(not(any(unix, windows)))]
+fn apply_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
 
-        // Atomic writer commits on close.
-        let atomic_path = format!("{root}/atomic.txt");
-        let caps_atomic = caps_v1(1024, CAP_CREATE_PARENTS | CAP_OVERWRITE | CAP_ATOMIC_WRITE);
-        let h2 = ok_i32(x07_ext_fs_stream_open_write_v1(
-            to_ev_bytes(atomic_path.as_bytes()),
-            to_ev_bytes(&caps_atomic),
-        ));
-        assert_eq!(
-            ok_i32(x07_ext_fs_stream_write_all_v1(h2, to_ev_bytes(b"hi"))),
+/// Sets `path`'s permission bits to `mode` (`0o777` bits only; on Windows
+/// only the write bits matter, via the readonly attribute), so guests can
+/// mark staged files executable. Needs `allow_chmod` and a write to `path`.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_set_mode_v1(
+    path: ev_bytes,
+    mode: u32,
+    caps: ev_bytes,
+) -> ev_result_i32 {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_i32(code),
+        };
+        match set_mode(policy(), caps, bytes_as_slice(path), mode) {
+            Ok(()) => ok_i32(1),
+            Err(code) => err_i32(code),
+        }
+    })
+    .unwrap_or_else(|_| err_i32(FS_ERR_IO))
+}
+
+// -------------------------
+// Change polling (FsStatV2)
+// -------------------------
+
+/// `baseline_mtime_ns` for "the file did not exist"; real mtimes are clamped
+/// below it.
+const WAIT_BASELINE_MISSING: u64 = u64::MAX;
+const WAIT_POLL_MIN_MS: u64 = 1;
+const WAIT_POLL_MAX_MS: u64 = 50;
+
+const WAIT_OUTCOME_TIMEOUT: u32 = 0;
+const WAIT_OUTCOME_CHANGED: u32 = 1;
+const WAIT_OUTCOME_APPEARED: u32 = 2;
+const WAIT_OUTCOME_REMOVED: u32 = 3;
+
+/// `FsStatV2`: `kind` as in `FsStatV1`, 64-bit size and nanosecond mtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct StatV2 {
+    kind: u32,
+    size: u64,
+    mtime_ns: u64,
+}
+
+impl StatV2 {
+    const MISSING: StatV2 = StatV2 {
+        kind: 0,
+        size: 0,
+        mtime_ns: 0,
+    };
+
+    fn encode_into(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&2u32.to_le_bytes()); // version
+        out.extend_from_slice(&self.kind.to_le_bytes());
+        out.extend_from_slice(&self.size.to_le_bytes());
+        out.extend_from_slice(&self.mtime_ns.to_le_bytes());
+    }
+}
+
+fn stat_v2(path: &Path) -> Result<StatV2, i32> {
+    let md = match std::fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(StatV2::MISSING),
+        Err(e) => return Err(map_io_err(&e)),
+    };
+    let ft = md.file_type();
+    let kind: u32 = if ft.is_file() {
+        1
+    } else if ft.is_dir() {
+        2
+    } else if ft.is_symlink() {
+        3
+    } else {
+        4
+    };
+    let mtime_ns = md
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| {
+            u64::try_from(d.as_nanos())
+                .unwrap_or(u64::MAX)
+                .min(WAIT_BASELINE_MISSING - 1)
+        })
+        .unwrap_or(0);
+    Ok(StatV2 {
+        kind,
+        size: if ft.is_file() { md.len() } else { 0 },
+        mtime_ns,
+    })
+}
+
+fn wait_outcome(baseline_mtime_ns: u64, baseline_size: u64, cur: StatV2) -> Option<u32> {
+    let was_missing = baseline_mtime_ns == WAIT_BASELINE_MISSING;
+    match (was_missing, cur.kind == 0) {
+        (true, true) => None,
+        (true, false) => Some(WAIT_OUTCOME_APPEARED),
+        (false, true) => Some(WAIT_OUTCOME_REMOVED),
+        (false, false) => (cur.mtime_ns != baseline_mtime_ns || cur.size != baseline_size)
+            .then_some(WAIT_OUTCOME_CHANGED),
+    }
+}
+
+/// Like [`x07_ext_fs_stat_v1`], but returns a 24-byte `FsStatV2` with a u64
+/// size and nanosecond mtime, the baseline [`x07_ext_fs_wait_change_v1`] takes.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_stat_v2(path: ev_bytes, caps: ev_bytes) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
+        };
+
+        let pol = policy();
+        if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+            return err_bytes(FS_ERR_SYMLINK_DENIED);
+        }
+
+        let pb = match enforce_read_path(caps, bytes_as_slice(path)) {
+            Ok(p) => p,
+            Err(code) => return err_bytes(code),
+        };
+        match stat_v2(&pb) {
+            Ok(st) => {
+                let mut out = Vec::with_capacity(24);
+                st.encode_into(&mut out);
+                ok_bytes_vec(out)
+            }
+            Err(code) => err_bytes(code),
+        }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
+
+const STAT_MANY_VERSION: u32 = 1;
+
+/// Splits a `stat_many_v1` request: `version u32_le` (1), `count u32_le`,
+/// then `count` times `len u32_le` + path bytes, with nothing after.
+fn parse_stat_many_request(blob: &[u8], max_entries: usize) -> Result<Vec<&[u8]>, i32> {
+    let read_u32 = |at: usize| -> Result<u32, i32> {
+        let b = blob.get(at..at + 4).ok_or(FS_ERR_BAD_PATH)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if read_u32(0)? != STAT_MANY_VERSION {
+        return Err(FS_ERR_UNSUPPORTED);
+    }
+    let count = read_u32(4)? as usize;
+    if count > max_entries {
+        return Err(FS_ERR_TOO_MANY_ENTRIES);
+    }
+    let mut paths = Vec::with_capacity(count);
+    let mut at = 8;
+    for _ in 0..count {
+        let len = read_u32(at)? as usize;
+        at += 4;
+        let path = blob
+            .get(at..at.checked_add(len).ok_or(FS_ERR_BAD_PATH)?)
+            .ok_or(FS_ERR_BAD_PATH)?;
+        paths.push(path);
+        at += len;
+    }
+    if at != blob.len() {
+        return Err(FS_ERR_BAD_PATH);
+    }
+    Ok(paths)
+}
+
+/// One `err_code u32_le` + `FsStatV2` record per path, in request order. A
+/// path that fails policy or IO gets its error code and a missing stat; it
+/// does not fail the batch.
+fn stat_many(pol: &Policy, caps: CapsV1, blob: &[u8]) -> Result<Vec<u8>, i32> {
+    if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+        return Err(FS_ERR_SYMLINK_DENIED);
+    }
+    let max_entries = effective_max(pol.max_entries, caps.max_entries) as usize;
+    let paths = parse_stat_many_request(blob, max_entries)?;
+
+    let mut out = Vec::with_capacity(8 + paths.len() * 28);
+    out.extend_from_slice(&STAT_MANY_VERSION.to_le_bytes());
+    out.extend_from_slice(&(paths.len() as u32).to_le_bytes());
+    for path in paths {
+        let (code, st) = match enforce_read_path_with(pol, caps, path).and_then(|p| stat_v2(&p)) {
+            Ok(st) => (0, st),
+            Err(code) => (code, StatV2::MISSING),
+        };
+        out.extend_from_slice(&(code as u32).to_le_bytes());
+        st.encode_into(&mut out);
+    }
+    Ok(out)
+}
+
+/// [`x07_ext_fs_stat_v2`] for a batch of paths, parsing caps and policy once.
+/// The batch is capped at the effective `max_entries`.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_stat_many_v1(paths: ev_bytes, caps: ev_bytes) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
+        };
+        match stat_many(policy(), caps, bytes_as_slice(paths)) {
+            Ok(out) => ok_bytes_vec(out),
+            Err(code) => err_bytes(code),
+        }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
+
+/// Polls `path` until it differs from the baseline (mtime or size changed,
+/// it appeared, or it was removed) or `timeout_ms` runs out, sleeping with a
+/// short backoff in between. Pass `baseline_mtime_ns == u64::MAX` to wait for
+/// a missing file to appear. The timeout is clamped to `max_wait_ms`.
+/// Returns `outcome u32_le` followed by the `FsStatV2` seen last.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_wait_change_v1(
+    path: ev_bytes,
+    baseline_mtime_ns: u64,
+    baseline_size: u64,
+    timeout_ms: u32,
+    caps: ev_bytes,
+) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
+        };
+
+        let pol = policy();
+        if cap_allow_symlinks(caps) && !pol.allow_symlinks {
+            return err_bytes(FS_ERR_SYMLINK_DENIED);
+        }
+
+        let pb = match enforce_read_path(caps, bytes_as_slice(path)) {
+            Ok(p) => p,
+            Err(code) => return err_bytes(code),
+        };
+
+        let deadline =
+            Instant::now() + Duration::from_millis(timeout_ms.min(pol.max_wait_ms) as u64);
+        let mut backoff = Duration::from_millis(WAIT_POLL_MIN_MS);
+        loop {
+            let cur = match stat_v2(&pb) {
+                Ok(st) => st,
+                Err(code) => return err_bytes(code),
+            };
+            let outcome = wait_outcome(baseline_mtime_ns, baseline_size, cur);
+            let now = Instant::now();
+            if outcome.is_some() || now >= deadline {
+                let mut out = Vec::with_capacity(28);
+                out.extend_from_slice(&outcome.unwrap_or(WAIT_OUTCOME_TIMEOUT).to_le_bytes());
+                cur.encode_into(&mut out);
+                return ok_bytes_vec(out);
+            }
+            std::thread::sleep(backoff.min(deadline - now));
+            backoff = (backoff * 2).min(Duration::from_millis(WAIT_POLL_MAX_MS));
+        }
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
+
+const POLICY_INFO_SANDBOXED: u32 = 1 << 0;
+const POLICY_INFO_ENABLED: u32 = 1 << 1;
+const POLICY_INFO_DENY_HIDDEN: u32 = 1 << 2;
+
+const POLICY_OP_SYMLINKS: u32 = 1 << 0;
+const POLICY_OP_MKDIR: u32 = 1 << 1;
+const POLICY_OP_REMOVE: u32 = 1 << 2;
+const POLICY_OP_RENAME: u32 = 1 << 3;
+const POLICY_OP_WALK: u32 = 1 << 4;
+const POLICY_OP_GLOB: u32 = 1 << 5;
+const POLICY_OP_CHMOD: u32 = 1 << 6;
+
+/// Encodes `FsPolicyInfoV1` (see docs/fs/fs-v1.md): a 36-byte header of
+/// u32_le fields, then, outside the sandbox only, the read and write roots as
+/// `u32_le len` + UTF-8 (lossy) bytes each.
+fn policy_info_v1(pol: &Policy, caps: CapsV1) -> Vec<u8> {
+    let mut flags = 0;
+    for (on, bit) in [
+        (pol.sandboxed, POLICY_INFO_SANDBOXED),
+        (pol.enabled, POLICY_INFO_ENABLED),
+        (pol.deny_hidden, POLICY_INFO_DENY_HIDDEN),
+    ] {
+        if on {
+            flags |= bit;
+        }
+    }
+    let mut ops = 0;
+    for (on, bit) in [
+        (pol.allow_symlinks, POLICY_OP_SYMLINKS),
+        (pol.allow_mkdir, POLICY_OP_MKDIR),
+        (pol.allow_remove, POLICY_OP_REMOVE),
+        (pol.allow_rename, POLICY_OP_RENAME),
+        (pol.allow_walk, POLICY_OP_WALK),
+        (pol.allow_glob, POLICY_OP_GLOB),
+        (pol.allow_chmod, POLICY_OP_CHMOD),
+    ] {
+        if on {
+            ops |= bit;
+        }
+    }
+
+    let mut out = Vec::with_capacity(36);
+    for v in [
+        1u32, // version
+        flags,
+        ops,
+        effective_max(pol.max_read_bytes, caps.max_read_bytes),
+        effective_max(pol.max_write_bytes, caps.max_write_bytes),
+        effective_max(pol.max_entries, caps.max_entries),
+        effective_max(pol.max_depth, caps.max_depth),
+        pol.read_roots.len().min(u32::MAX as usize) as u32,
+        pol.write_roots.len().min(u32::MAX as usize) as u32,
+    ] {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    // Root paths would expose the host layout to a sandboxed guest.
+    if !pol.sandboxed {
+        for root in pol.read_roots.iter().chain(&pol.write_roots) {
+            let root = root.to_string_lossy();
+            out.extend_from_slice(&(root.len() as u32).to_le_bytes());
+            out.extend_from_slice(root.as_bytes());
+        }
+    }
+    out
+}
+
+/// Returns an `FsPolicyInfoV1` summary of the effective fs policy combined
+/// with `caps`, so guests can check what is allowed instead of probing.
+#[no_mangle]
+pub extern "C" fn x07_ext_fs_policy_info_v1(caps: ev_bytes) -> ev_result_bytes {
+    std::panic::catch_unwind(|| unsafe {
+        let caps = match parse_caps_v1(bytes_as_slice(caps)) {
+            Ok(caps) => caps,
+            Err(code) => return err_bytes(code),
+        };
+        ok_bytes_vec(policy_info_v1(policy(), caps))
+    })
+    .unwrap_or_else(|_| err_bytes(FS_ERR_IO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x07_ext_os_native_core::{
+        CAP_ALLOW_SYMLINKS, CAP_ATOMIC_WRITE, CAP_CREATE_PARENTS, CAP_OVERWRITE,
+    };
+
+    #[no_mangle]
+    extern "C" fn ev_bytes_alloc(len: u32) -> ev_bytes {
+        let mut v = vec![0u8; len as usize];
+        let ptr = v.as_mut_ptr();
+        std::mem::forget(v);
+        ev_bytes { ptr, len }
+    }
+
+    #[no_mangle]
+    extern "C" fn ev_trap(code: i32) -> ! {
+        panic!("ev_trap({code})")
+    }
+
+    fn caps_v1(max_write_bytes: u32, flags: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(24);
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_read_bytes
+        out.extend_from_slice(&max_write_bytes.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_entries
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_depth
+        out.extend_from_slice(&flags.to_le_bytes());
+        out
+    }
+
+    fn to_ev_bytes(b: &[u8]) -> ev_bytes {
+        ev_bytes {
+            ptr: b.as_ptr() as *mut u8,
+            len: b.len() as u32,
+        }
+    }
+
+    fn ok_i32(res: ev_result_i32) -> i32 {
+        assert_eq!(res.tag, 1, "expected ok, got err={}", unsafe {
+            res.payload.err
+        });
+        unsafe { res.payload.ok as i32 }
+    }
+
+    fn err_i32(res: ev_result_i32) -> i32 {
+        assert_eq!(res.tag, 0, "expected err");
+        unsafe { res.payload.err as i32 }
+    }
+
+    fn ok_bytes(res: ev_result_bytes) -> Vec<u8> {
+        assert_eq!(res.tag, 1, "expected ok, got err={}", unsafe {
+            res.payload.err
+        });
+        let out = unsafe { res.payload.ok };
+        unsafe { std::slice::from_raw_parts(out.ptr, out.len as usize).to_vec() }
+    }
+
+    fn err_bytes(res: ev_result_bytes) -> i32 {
+        assert_eq!(res.tag, 0, "expected err");
+        unsafe { res.payload.err as i32 }
+    }
+
+    fn caps_read_v1(max_read_bytes: u32, flags: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(24);
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&max_read_bytes.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_write_bytes
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_entries
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_depth
+        out.extend_from_slice(&flags.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn fs_stream_writer_handle_v1_smoke() {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+        std::env::set_var("X07_OS_FS_ALLOW_MKDIR", "1");
+        std::env::set_var("X07_OS_FS_ALLOW_RENAME", "1");
+        std::env::set_var("X07_OS_FS_MAX_WRITE_BYTES", "1000000");
+
+        let root = format!("target/x07_ext_fs_stream_test_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+
+        // Non-atomic writer, max_write_bytes enforced cumulatively.
+        let out_path = format!("{root}/out.txt");
+        let caps = caps_v1(8, CAP_CREATE_PARENTS | CAP_OVERWRITE);
+        let h = ok_i32(x07_ext_fs_stream_open_write_v1(
+            to_ev_bytes(out_path.as_bytes()),
+            to_ev_bytes(&caps),
+        ));
+        assert!(h > 0);
+        assert_eq!(
+            ok_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"abc"))),
+            3
+        );
+        assert_eq!(
+            ok_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"def"))),
+            3
+        );
+        assert_eq!(
+            ok_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"gh"))),
+            2
+        );
+        assert_eq!(
+            err_i32(x07_ext_fs_stream_write_all_v1(h, to_ev_bytes(b"i"))),
+            FS_ERR_TOO_LARGE
+        );
+        assert_eq!(ok_i32(x07_ext_fs_stream_close_v1(h)), 1);
+        assert_eq!(x07_ext_fs_stream_drop_v1(h), 1);
+
+        let got = std::fs::read(&out_path).expect("read out.txt");
+        assert_eq!(got, b"abcdefgh");
+
+        // Atomic writer commits on close.
+        let atomic_path = format!("{root}/atomic.txt");
+        let caps_atomic = caps_v1(1024, CAP_CREATE_PARENTS | CAP_OVERWRITE | CAP_ATOMIC_WRITE);
+        let h2 = ok_i32(x07_ext_fs_stream_open_write_v1(
+            to_ev_bytes(atomic_path.as_bytes()),
+            to_ev_bytes(&caps_atomic),
+        ));
+        assert_eq!(
+            ok_i32(x07_ext_fs_stream_write_all_v1(h2, to_ev_bytes(b"hi"))),
             2
         );
         assert_eq!(ok_i32(x07_ext_fs_stream_close_v1(h2)), 1);
@@ -1373,193 +2568,1541 @@ mod tests {
 
         // Invalid handle errors.
         assert_eq!(
-            err_i32(x07_ext_fs_stream_write_all_v1(123, to_ev_bytes(b"z"))),
-            FS_ERR_BAD_HANDLE
+            err_i32(x07_ext_fs_stream_write_all_v1(123, to_ev_bytes(b"z"))),
+            FS_ERR_BAD_HANDLE
+        );
+        assert_eq!(err_i32(x07_ext_fs_stream_close_v1(123)), FS_ERR_BAD_HANDLE);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fs_stream_writer_capacity_counts_only_open_handles() {
+        let root = format!("target/x07_ext_fs_writer_cap_test_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+
+        let open_writer = |name: &str| WriterHandleV1 {
+            file: Some(std::fs::File::create(format!("{root}/{name}")).expect("create")),
+            final_path: PathBuf::from(format!("{root}/{name}")),
+            tmp_path: None,
+            max_write_bytes: 16,
+            written: 0,
+        };
+        let open = |table: &mut Vec<Option<WriterHandleV1>>, name: &str| {
+            check_writer_capacity(table, 2)?;
+            handle_insert(table, open_writer(name))
+        };
+
+        let mut table: Vec<Option<WriterHandleV1>> = Vec::new();
+        let h1 = open(&mut table, "a.txt").expect("first writer");
+        let h2 = open(&mut table, "b.txt").expect("second writer");
+        assert_eq!(
+            open(&mut table, "c.txt").expect_err("over the limit"),
+            FS_ERR_TOO_MANY_HANDLES
+        );
+
+        // Closing keeps the slot (close is idempotent) but frees capacity.
+        table[handle_idx(h1).unwrap()].as_mut().unwrap().file = None;
+        let h3 = open(&mut table, "c.txt").expect("writer after close");
+        assert_eq!(h3, 3, "closed slot is not reused");
+        assert_eq!(
+            open(&mut table, "d.txt").expect_err("over the limit again"),
+            FS_ERR_TOO_MANY_HANDLES
+        );
+
+        // Dropping frees the slot itself.
+        table[handle_idx(h2).unwrap()] = None;
+        assert_eq!(open(&mut table, "d.txt").expect("writer after drop"), h2);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fs_stream_reader_handle_v1_smoke() {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+        std::env::set_var("X07_OS_FS_MAX_READ_BYTES", "1000000");
+
+        let root = format!("target/x07_ext_fs_stream_read_test_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+
+        let in_path = format!("{root}/in.txt");
+        std::fs::write(&in_path, b"abcdefgh").expect("write in.txt");
+
+        let caps = caps_read_v1(8, 0);
+        let h = ok_i32(x07_ext_fs_stream_open_read_v1(
+            to_ev_bytes(in_path.as_bytes()),
+            to_ev_bytes(&caps),
+        ));
+        assert!(h > 0);
+
+        assert_eq!(
+            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
+            b"abc".to_vec()
+        );
+        assert_eq!(
+            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
+            b"def".to_vec()
+        );
+        assert_eq!(
+            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
+            b"gh".to_vec()
+        );
+        assert_eq!(
+            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
+            Vec::<u8>::new()
+        );
+        assert_eq!(ok_i32(x07_ext_fs_stream_close_read_v1(h)), 1);
+        assert_eq!(x07_ext_fs_stream_drop_read_v1(h), 1);
+
+        // read_into variant (no allocations).
+        let h2 = ok_i32(x07_ext_fs_stream_open_read_v1(
+            to_ev_bytes(in_path.as_bytes()),
+            to_ev_bytes(&caps),
+        ));
+        let mut tmp = vec![0u8; 3];
+        assert_eq!(
+            ok_i32(unsafe {
+                x07_ext_fs_stream_read_into_v1(h2, tmp.as_mut_ptr(), tmp.len() as u32)
+            }),
+            3
+        );
+        assert_eq!(tmp, b"abc");
+        assert_eq!(ok_i32(x07_ext_fs_stream_close_read_v1(h2)), 1);
+        assert_eq!(x07_ext_fs_stream_drop_read_v1(h2), 1);
+
+        // Too-large files are rejected at open.
+        let too_big_path = format!("{root}/too_big.txt");
+        std::fs::write(&too_big_path, b"abcdefghi").expect("write too_big.txt");
+        assert_eq!(
+            err_i32(x07_ext_fs_stream_open_read_v1(
+                to_ev_bytes(too_big_path.as_bytes()),
+                to_ev_bytes(&caps),
+            )),
+            FS_ERR_TOO_LARGE
+        );
+
+        // Invalid handle errors.
+        assert_eq!(
+            err_bytes(x07_ext_fs_stream_read_some_v1(123, 1)),
+            FS_ERR_BAD_HANDLE
+        );
+        assert_eq!(
+            err_i32(x07_ext_fs_stream_close_read_v1(123)),
+            FS_ERR_BAD_HANDLE
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fs_append_all_v1_smoke() {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+        std::env::set_var("X07_OS_FS_ALLOW_MKDIR", "1");
+        std::env::set_var("X07_OS_FS_MAX_WRITE_BYTES", "1000000");
+
+        let root = format!("target/x07_ext_fs_append_test_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+
+        let out_path = format!("{root}/out.txt");
+        let caps = caps_v1(1024, CAP_CREATE_PARENTS);
+
+        assert_eq!(
+            ok_i32(x07_ext_fs_append_all_v1(
+                to_ev_bytes(out_path.as_bytes()),
+                to_ev_bytes(b"abc"),
+                to_ev_bytes(&caps),
+            )),
+            3
+        );
+        assert_eq!(
+            ok_i32(x07_ext_fs_append_all_v1(
+                to_ev_bytes(out_path.as_bytes()),
+                to_ev_bytes(b"def"),
+                to_ev_bytes(&caps),
+            )),
+            3
+        );
+        let got = std::fs::read(&out_path).expect("read out.txt");
+        assert_eq!(got, b"abcdef");
+
+        // max_write_bytes enforced per call.
+        let caps_small = caps_v1(2, CAP_CREATE_PARENTS);
+        assert_eq!(
+            err_i32(x07_ext_fs_append_all_v1(
+                to_ev_bytes(out_path.as_bytes()),
+                to_ev_bytes(b"xyz"),
+                to_ev_bytes(&caps_small),
+            )),
+            FS_ERR_TOO_LARGE
+        );
+
+        // Atomic write is not supported for append.
+        let caps_atomic = caps_v1(1024, CAP_CREATE_PARENTS | CAP_ATOMIC_WRITE);
+        assert_eq!(
+            err_i32(x07_ext_fs_append_all_v1(
+                to_ev_bytes(out_path.as_bytes()),
+                to_ev_bytes(b"z"),
+                to_ev_bytes(&caps_atomic),
+            )),
+            FS_ERR_UNSUPPORTED
+        );
+
+        // Directory paths are rejected.
+        let dir_path = format!("{root}/dir");
+        std::fs::create_dir_all(&dir_path).expect("create dir");
+        assert_eq!(
+            err_i32(x07_ext_fs_append_all_v1(
+                to_ev_bytes(dir_path.as_bytes()),
+                to_ev_bytes(b"x"),
+                to_ev_bytes(&caps),
+            )),
+            FS_ERR_IS_DIR
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fs_created_files_and_dirs_get_configured_modes() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+        std::env::set_var("X07_OS_FS_ALLOW_MKDIR", "1");
+        std::env::set_var("X07_OS_FS_MAX_WRITE_BYTES", "1000000");
+        std::env::set_var("X07_OS_FS_FILE_MODE", "0640");
+        std::env::set_var("X07_OS_FS_DIR_MODE", "0750");
+        // The policy is loaded once per process, possibly by an earlier test.
+        let pol = policy();
+        let mode = |p: &str| std::fs::metadata(p).unwrap().permissions().mode() & 0o7777;
+
+        let root = format!("target/x07_ext_fs_modes_test_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+
+        let caps = caps_v1(1024, CAP_CREATE_PARENTS);
+        let out_path = format!("{root}/sub/out.txt");
+        assert_eq!(
+            ok_i32(x07_ext_fs_write_all_v1(
+                to_ev_bytes(out_path.as_bytes()),
+                to_ev_bytes(b"abc"),
+                to_ev_bytes(&caps),
+            )),
+            3
+        );
+        assert_eq!(mode(out_path.as_str()), pol.file_mode);
+        assert_eq!(mode(format!("{root}/sub").as_str()), pol.dir_mode);
+
+        let dir_path = format!("{root}/a/b");
+        assert_eq!(
+            ok_i32(x07_ext_fs_mkdirs_v1(
+                to_ev_bytes(dir_path.as_bytes()),
+                to_ev_bytes(&caps),
+            )),
+            1
+        );
+        assert_eq!(mode(dir_path.as_str()), pol.dir_mode);
+        assert_eq!(mode(format!("{root}/a").as_str()), pol.dir_mode);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fs_set_mode_v1_sets_permission_bits_and_refuses_special_bits() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+        std::env::set_var("X07_OS_FS_ALLOW_CHMOD", "1");
+        let mode = |p: &str| std::fs::metadata(p).unwrap().permissions().mode() & 0o7777;
+
+        let root = format!("target/x07_ext_fs_set_mode_test_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+        let path = format!("{root}/tool.sh");
+        std::fs::write(&path, b"#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let caps = caps_v1(0, 0);
+        let set =
+            |m: u32| x07_ext_fs_set_mode_v1(to_ev_bytes(path.as_bytes()), m, to_ev_bytes(&caps));
+
+        assert_eq!(ok_i32(set(0o755)), 1);
+        assert_eq!(mode(&path), 0o755);
+
+        for special in [0o4755, 0o2755, 0o1755] {
+            assert_eq!(err_i32(set(special)), FS_ERR_POLICY_DENY, "{special:o}");
+        }
+        assert_eq!(err_i32(set(0o10755)), FS_ERR_UNSUPPORTED);
+        assert_eq!(mode(&path), 0o755);
+
+        assert_eq!(
+            err_i32(x07_ext_fs_set_mode_v1(
+                to_ev_bytes(format!("{root}/missing").as_bytes()),
+                0o755,
+                to_ev_bytes(&caps),
+            )),
+            FS_ERR_NOT_FOUND
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fs_set_mode_v1_needs_allow_chmod_and_a_write_root() {
+        let base =
+            std::env::temp_dir().join(format!("x07_ext_fs_set_mode_pol_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("out")).unwrap();
+        std::fs::write(base.join("out").join("f"), b"x").unwrap();
+        std::fs::write(base.join("ro"), b"x").unwrap();
+        let base = std::fs::canonicalize(&base).unwrap();
+        let out = base.join("out");
+        let caps = parse_caps_v1(&caps_v1(0, 0)).unwrap();
+        let allowed = Policy {
+            write_roots: vec![out.clone()],
+            allow_chmod: true,
+            ..read_only_policy(&base)
+        };
+        let denied = Policy {
+            allow_chmod: false,
+            ..allowed.clone()
+        };
+        let f = out.join("f");
+        let f = f.to_str().unwrap().as_bytes();
+
+        assert_eq!(set_mode(&denied, caps, f, 0o644), Err(FS_ERR_POLICY_DENY));
+        assert_eq!(set_mode(&allowed, caps, f, 0o644), Ok(()));
+        let ro = base.join("ro");
+        assert_eq!(
+            set_mode(&allowed, caps, ro.to_str().unwrap().as_bytes(), 0o644),
+            Err(FS_ERR_POLICY_DENY)
+        );
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn fs_set_mode_v1_readonly_follows_the_write_bits() {
+        assert!(mode_is_readonly(0o444));
+        assert!(mode_is_readonly(0o555));
+        assert!(!mode_is_readonly(0o644));
+        assert!(!mode_is_readonly(0o400 | 0o020));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn fs_set_mode_v1_maps_write_bits_to_readonly_on_windows() {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+        std::env::set_var("X07_OS_FS_ALLOW_CHMOD", "1");
+        let root = format!("target/x07_ext_fs_set_mode_win_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+        let path = format!("{root}/f.txt");
+        std::fs::write(&path, b"x").unwrap();
+        let caps = caps_v1(0, 0);
+        let readonly = || std::fs::metadata(&path).unwrap().permissions().readonly();
+        let set = |m: u32| {
+            ok_i32(x07_ext_fs_set_mode_v1(
+                to_ev_bytes(path.as_bytes()),
+                m,
+                to_ev_bytes(&caps),
+            ))
+        };
+
+        assert_eq!(set(0o444), 1);
+        assert!(readonly());
+        assert_eq!(set(0o644), 1);
+        assert!(!readonly());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fs_read_write_v1_accept_absolute_paths_in_run_os() {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+        std::env::set_var("X07_OS_FS_ALLOW_MKDIR", "1");
+        std::env::set_var("X07_OS_FS_MAX_WRITE_BYTES", "1000000");
+
+        let root = std::env::temp_dir().join(format!("x07_ext_fs_abs_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let out_path = root.join("nested").join("out.txt");
+        let out_path_s = out_path.to_str().expect("utf8 temp path");
+        let caps = caps_v1(1024, CAP_CREATE_PARENTS | CAP_OVERWRITE);
+
+        assert_eq!(
+            ok_i32(x07_ext_fs_write_all_v1(
+                to_ev_bytes(out_path_s.as_bytes()),
+                to_ev_bytes(b"ok"),
+                to_ev_bytes(&caps),
+            )),
+            2
+        );
+        assert_eq!(std::fs::read(&out_path).expect("read out.txt"), b"ok");
+
+        let read_back = ok_bytes(x07_ext_fs_read_all_v1(
+            to_ev_bytes(out_path_s.as_bytes()),
+            to_ev_bytes(&caps),
+        ));
+        assert_eq!(read_back, b"ok");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    fn caps_entries_v1(max_entries: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(24);
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_read_bytes
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_write_bytes
+        out.extend_from_slice(&max_entries.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_depth
+        out.extend_from_slice(&0u32.to_le_bytes()); // flags
+        out
+    }
+
+    fn list_page(dir: &str, start_after: &str, page_size: i32, caps: &[u8]) -> (Vec<String>, bool) {
+        let mut out = ok_bytes(x07_ext_fs_list_dir_page_v1(
+            to_ev_bytes(dir.as_bytes()),
+            to_ev_bytes(start_after.as_bytes()),
+            page_size,
+            to_ev_bytes(caps),
+        ));
+        let has_more = match out.pop() {
+            Some(1) => true,
+            Some(0) => false,
+            other => panic!("bad has_more marker {other:?}"),
+        };
+        let text = String::from_utf8(out).expect("utf8 page");
+        (text.lines().map(str::to_string).collect(), has_more)
+    }
+
+    fn list_page_test_dir(name: &str, n: usize) -> String {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+
+        let root = format!("target/x07_ext_fs_{name}_{}", std::process::id());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+        for i in 0..n {
+            std::fs::write(format!("{root}/f{i:03}"), b"").expect("write entry");
+        }
+        root
+    }
+
+    #[test]
+    fn fs_list_dir_page_v1_pages_are_stable_and_complete() {
+        let root = list_page_test_dir("page_stable", 25);
+        let caps = caps_entries_v1(10);
+
+        let mut seen: Vec<String> = Vec::new();
+        let mut cursor = String::new();
+        let mut pages = 0;
+        loop {
+            // page_size above max_entries is clamped to 10.
+            let (names, has_more) = list_page(&root, &cursor, 1000, &caps);
+            pages += 1;
+            assert!(names.len() <= 10, "{names:?}");
+            if let Some(last) = names.last() {
+                cursor = last.clone();
+            }
+            seen.extend(names);
+            if !has_more {
+                break;
+            }
+        }
+        assert_eq!(pages, 3);
+        let want: Vec<String> = (0..25).map(|i| format!("f{i:03}")).collect();
+        assert_eq!(seen, want);
+
+        // The final page is short and reports no more entries; paging past the
+        // end yields an empty page.
+        let (names, has_more) = list_page(&root, "f019", 10, &caps);
+        assert_eq!(names.len(), 5);
+        assert!(!has_more);
+        let (names, has_more) = list_page(&root, "f024", 10, &caps);
+        assert!(names.is_empty());
+        assert!(!has_more);
+
+        // A cursor that is not itself an entry still resumes after it.
+        let (names, has_more) = list_page(&root, "f004x", 2, &caps);
+        assert_eq!(names, vec!["f005".to_string(), "f006".to_string()]);
+        assert!(has_more);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fs_list_dir_page_v1_cache_invalidates_on_mtime_change() {
+        let root = list_page_test_dir("page_mtime", 3);
+        let caps = caps_entries_v1(0);
+
+        let (names, has_more) = list_page(&root, "", 0, &caps);
+        assert_eq!(names, vec!["f000", "f001", "f002"]);
+        assert!(!has_more);
+
+        std::fs::write(format!("{root}/f003"), b"").expect("write entry");
+        // Force a distinct mtime in case the filesystem clock is coarse.
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::open(&root)
+            .and_then(|d| d.set_modified(later))
+            .expect("bump dir mtime");
+
+        let (names, _) = list_page(&root, "f001", 0, &caps);
+        assert_eq!(names, vec!["f002", "f003"]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    // The lock table is process-wide; tests that take locks in-process
+    // (and call release_all) hold this so they cannot see each other's handles.
+    static LOCK_TABLE: Mutex<()> = Mutex::new(());
+
+    fn lock_test_dir(name: &str) -> PathBuf {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+
+        let root = std::env::temp_dir().join(format!("x07_ext_fs_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+        root
+    }
+
+    fn lock_acquire(path: &str, exclusive: bool, wait_ms: i32) -> ev_result_i32 {
+        let caps = caps_v1(0, 0);
+        x07_ext_fs_lock_acquire_v1(
+            to_ev_bytes(path.as_bytes()),
+            i32::from(exclusive),
+            wait_ms,
+            to_ev_bytes(&caps),
+        )
+    }
+
+    #[test]
+    fn fs_lock_v1_handles_in_process() {
+        let _table = LOCK_TABLE.lock().unwrap_or_else(|e| e.into_inner());
+        let root = lock_test_dir("lock_handles");
+        let target = root.join("state.json");
+        let target_s = target.to_str().expect("utf8 temp path");
+
+        let h1 = ok_i32(lock_acquire(target_s, true, 0));
+        assert!(root.join("state.json.x07_lock").exists());
+        assert!(!target.exists());
+        assert_eq!(
+            err_i32(lock_acquire(target_s, true, 30)),
+            FS_ERR_LOCK_TIMEOUT
+        );
+        assert_eq!(
+            err_i32(lock_acquire(target_s, false, 0)),
+            FS_ERR_LOCK_TIMEOUT
+        );
+
+        // Release is idempotent and frees the slot for reuse.
+        assert_eq!(ok_i32(x07_ext_fs_lock_release_v1(h1)), 1);
+        assert_eq!(ok_i32(x07_ext_fs_lock_release_v1(h1)), 1);
+        assert_eq!(err_i32(x07_ext_fs_lock_release_v1(0)), FS_ERR_BAD_HANDLE);
+        assert_eq!(err_i32(x07_ext_fs_lock_release_v1(9999)), FS_ERR_BAD_HANDLE);
+
+        // Shared locks coexist and block exclusive ones.
+        let s1 = ok_i32(lock_acquire(target_s, false, 0));
+        assert_eq!(s1, h1);
+        let s2 = ok_i32(lock_acquire(target_s, false, 0));
+        assert_eq!(
+            err_i32(lock_acquire(target_s, true, 0)),
+            FS_ERR_LOCK_TIMEOUT
+        );
+
+        assert_eq!(x07_ext_fs_lock_release_all_v1(), 2);
+        let h3 = ok_i32(lock_acquire(target_s, true, 0));
+        assert_eq!(ok_i32(x07_ext_fs_lock_release_v1(h3)), 1);
+        assert_eq!(ok_i32(x07_ext_fs_lock_release_v1(s2)), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    const LOCK_CHILD_ENV: &str = "X07_EXT_FS_LOCK_CHILD";
+
+    /// Child half of `fs_lock_v1_contends_across_processes`; a no-op unless
+    /// spawned with `LOCK_CHILD_ENV` set to `<path>|<wait_ms>|<hold_ms>|<release>`.
+    #[test]
+    #[ignore]
+    fn fs_lock_v1_child() {
+        let Ok(spec) = std::env::var(LOCK_CHILD_ENV) else {
+            return;
+        };
+        let parts: Vec<&str> = spec.split('|').collect();
+        let [path, wait_ms, hold_ms, release] = parts[..] else {
+            panic!("bad {LOCK_CHILD_ENV}: {spec}");
+        };
+
+        let res = lock_acquire(path, true, wait_ms.parse().unwrap());
+        if res.tag != 1 {
+            println!("x07-lock:err:{}", unsafe { res.payload.err });
+            return;
+        }
+        println!("x07-lock:acquired");
+        std::thread::sleep(Duration::from_millis(hold_ms.parse().unwrap()));
+        if release == "1" {
+            ok_i32(x07_ext_fs_lock_release_v1(unsafe { res.payload.ok } as i32));
+        }
+        println!("x07-lock:done");
+    }
+
+    fn spawn_lock_child(
+        path: &str,
+        wait_ms: i32,
+        hold_ms: u64,
+        release: bool,
+        env: &[(&str, &str)],
+    ) -> std::process::Child {
+        std::process::Command::new(std::env::current_exe().expect("current_exe"))
+            .args([
+                "--exact",
+                "tests::fs_lock_v1_child",
+                "--ignored",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(
+                LOCK_CHILD_ENV,
+                format!("{path}|{wait_ms}|{hold_ms}|{}", u8::from(release)),
+            )
+            .envs(env.iter().copied())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .expect("spawn lock child")
+    }
+
+    fn lock_child_markers(child: std::process::Child) -> Vec<String> {
+        let out = child.wait_with_output().expect("wait lock child");
+        assert!(out.status.success(), "lock child failed: {:?}", out.status);
+        String::from_utf8_lossy(&out.stdout)
+            .lines()
+            // libtest may print `test ... ` on the same line first.
+            .filter_map(|l| l.split_once("x07-lock:"))
+            .map(|(_, marker)| marker.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn fs_lock_v1_contends_across_processes() {
+        use std::io::BufRead as _;
+
+        let _table = LOCK_TABLE.lock().unwrap_or_else(|e| e.into_inner());
+        let root = lock_test_dir("lock_procs");
+        let target = root.join("shared.db");
+        let target_s = target.to_str().expect("utf8 temp path");
+
+        // Holder: takes the lock, keeps it for a while, releases it.
+        let mut holder = spawn_lock_child(target_s, 0, 600, true, &[]);
+        let mut holder_out = io::BufReader::new(holder.stdout.take().expect("holder stdout"));
+        let mut line = String::new();
+        while !line.contains("x07-lock:acquired") {
+            line.clear();
+            assert!(
+                holder_out.read_line(&mut line).expect("read holder") > 0,
+                "holder exited before acquiring"
+            );
+        }
+
+        // An impatient contender times out; a patient one gets the lock once
+        // the holder lets go.
+        let impatient = spawn_lock_child(target_s, 50, 0, true, &[]);
+        let patient = spawn_lock_child(target_s, 10_000, 0, true, &[]);
+        assert_eq!(
+            lock_child_markers(impatient),
+            vec![format!("err:{FS_ERR_LOCK_TIMEOUT}")]
+        );
+        assert_eq!(lock_child_markers(patient), vec!["acquired", "done"]);
+        assert!(holder.wait().expect("wait holder").success());
+
+        // A handle leaked by an exiting process does not keep the lock.
+        let leaker = spawn_lock_child(target_s, 0, 0, false, &[]);
+        assert_eq!(lock_child_markers(leaker), vec!["acquired", "done"]);
+        let h = ok_i32(lock_acquire(target_s, true, 0));
+        assert_eq!(ok_i32(x07_ext_fs_lock_release_v1(h)), 1);
+
+        // Sandboxed: only paths under the write roots can be locked.
+        let allowed = root.join("allowed");
+        std::fs::create_dir_all(&allowed).expect("create allowed dir");
+        let allowed_s = allowed.to_str().expect("utf8 temp path");
+        let sandbox = [
+            ("X07_OS_SANDBOXED", "1"),
+            ("X07_OS_FS", "1"),
+            ("X07_OS_FS_WRITE_ROOTS", allowed_s),
+        ];
+        let outside = spawn_lock_child(target_s, 0, 0, true, &sandbox);
+        assert_eq!(
+            lock_child_markers(outside),
+            vec![format!("err:{FS_ERR_POLICY_DENY}")]
+        );
+        let inside_s = format!("{allowed_s}/state");
+        let inside = spawn_lock_child(&inside_s, 0, 0, true, &sandbox);
+        assert_eq!(lock_child_markers(inside), vec!["acquired", "done"]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fs_set_mtime_v1_round_trips_through_stat() {
+        std::env::set_var("X07_OS_SANDBOXED", "0");
+        std::env::set_var("X07_OS_FS", "1");
+
+        let root =
+            std::env::temp_dir().join(format!("x07_ext_fs_mtime_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+        let file = root.join("out.txt");
+        std::fs::write(&file, b"x").expect("write out.txt");
+        let file_s = file.to_str().expect("utf8 temp path");
+        let caps = caps_v1(0, 0);
+
+        assert_eq!(
+            ok_i32(x07_ext_fs_set_mtime_v1(
+                to_ev_bytes(file_s.as_bytes()),
+                1_000_000_000,
+                123_000_000,
+                to_ev_bytes(&caps),
+            )),
+            1
+        );
+        let stat = ok_bytes(x07_ext_fs_stat_v1(
+            to_ev_bytes(file_s.as_bytes()),
+            to_ev_bytes(&caps),
+        ));
+        assert_eq!(&stat[12..16], &1_000_000_000u32.to_le_bytes());
+        let mtime = std::fs::metadata(&file)
+            .and_then(|m| m.modified())
+            .expect("mtime");
+        assert_eq!(
+            mtime.duration_since(UNIX_EPOCH).unwrap(),
+            Duration::new(1_000_000_000, 123_000_000)
+        );
+
+        assert_eq!(
+            err_i32(x07_ext_fs_set_mtime_v1(
+                to_ev_bytes(file_s.as_bytes()),
+                0,
+                1_000_000_000,
+                to_ev_bytes(&caps),
+            )),
+            FS_ERR_TOO_LARGE
+        );
+        let root_s = root.to_str().expect("utf8 temp path");
+        assert_eq!(
+            err_i32(x07_ext_fs_set_mtime_v1(
+                to_ev_bytes(root_s.as_bytes()),
+                0,
+                0,
+                to_ev_bytes(&caps),
+            )),
+            FS_ERR_IS_DIR
+        );
+        let missing = format!("{root_s}/missing.txt");
+        assert_eq!(
+            err_i32(x07_ext_fs_set_mtime_v1(
+                to_ev_bytes(missing.as_bytes()),
+                0,
+                0,
+                to_ev_bytes(&caps),
+            )),
+            FS_ERR_NOT_FOUND
         );
-        assert_eq!(err_i32(x07_ext_fs_stream_close_v1(123)), FS_ERR_BAD_HANDLE);
+
+        #[cfg(unix)]
+        {
+            let link = format!("{root_s}/link.txt");
+            std::os::unix::fs::symlink(&file, &link).expect("symlink");
+            assert_eq!(
+                err_i32(x07_ext_fs_set_mtime_v1(
+                    to_ev_bytes(link.as_bytes()),
+                    0,
+                    0,
+                    to_ev_bytes(&caps),
+                )),
+                FS_ERR_SYMLINK_DENIED
+            );
+        }
 
         let _ = std::fs::remove_dir_all(&root);
     }
 
+    fn caps_bounds_v1(max_entries: u32, max_depth: u32, flags: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(24);
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_read_bytes
+        out.extend_from_slice(&0u32.to_le_bytes()); // max_write_bytes
+        out.extend_from_slice(&max_entries.to_le_bytes());
+        out.extend_from_slice(&max_depth.to_le_bytes());
+        out.extend_from_slice(&flags.to_le_bytes());
+        out
+    }
+
+    fn du(root: &str, caps: &[u8]) -> (u64, u32, u32, bool) {
+        let out = ok_bytes(x07_ext_fs_du_v1(
+            to_ev_bytes(root.as_bytes()),
+            to_ev_bytes(caps),
+        ));
+        assert_eq!(out.len(), 17);
+        (
+            u64::from_le_bytes(out[0..8].try_into().unwrap()),
+            u32::from_le_bytes(out[8..12].try_into().unwrap()),
+            u32::from_le_bytes(out[12..16].try_into().unwrap()),
+            out[16] == 1,
+        )
+    }
+
     #[test]
-    fn fs_stream_reader_handle_v1_smoke() {
+    fn fs_du_v1_counts_nested_tree_within_bounds() {
         std::env::set_var("X07_OS_SANDBOXED", "0");
         std::env::set_var("X07_OS_FS", "1");
-        std::env::set_var("X07_OS_FS_MAX_READ_BYTES", "1000000");
 
-        let root = format!("target/x07_ext_fs_stream_read_test_{}", std::process::id());
-        let _ = std::fs::remove_dir_all(&root);
-        std::fs::create_dir_all(&root).expect("create test dir");
+        let base = std::env::temp_dir().join(format!("x07_ext_fs_du_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        let root = base.join("cache");
+        std::fs::create_dir_all(root.join("a/b")).expect("create tree");
+        std::fs::write(root.join("top.bin"), [0u8; 10]).expect("write");
+        std::fs::write(root.join("a/mid.bin"), [0u8; 20]).expect("write");
+        std::fs::write(root.join("a/b/deep.bin"), [0u8; 30]).expect("write");
+        let root_s = root.to_str().expect("utf8 temp path");
+
+        // 3 files + 2 dirs, 60 bytes.
+        assert_eq!(du(root_s, &caps_bounds_v1(0, 0, 0)), (60, 3, 2, false));
+
+        // Depth bound: `a/b/deep.bin` is at depth 3.
+        assert_eq!(du(root_s, &caps_bounds_v1(0, 2, 0)), (30, 2, 2, true));
+        // Entry bound.
+        let (_, files, dirs, truncated) = du(root_s, &caps_bounds_v1(2, 0, 0));
+        assert_eq!(files + dirs, 2);
+        assert!(truncated);
+
+        #[cfg(unix)]
+        {
+            // Links are never counted, and links leaving the root are never
+            // followed even when symlinks are allowed.
+            let outside = base.join("outside");
+            std::fs::create_dir_all(&outside).expect("create outside");
+            std::fs::write(outside.join("big.bin"), [0u8; 1000]).expect("write");
+            std::os::unix::fs::symlink(&outside, root.join("out_dir")).expect("symlink");
+            std::os::unix::fs::symlink(outside.join("big.bin"), root.join("out_file"))
+                .expect("symlink");
+            assert_eq!(du(root_s, &caps_bounds_v1(0, 0, 0)), (60, 3, 2, false));
+            assert_eq!(
+                du(
+                    root_s,
+                    &caps_bounds_v1(0, 0, x07_ext_os_native_core::CAP_ALLOW_SYMLINKS)
+                ),
+                (60, 3, 2, false)
+            );
+
+            // A hard link and a followed link back into the tree reach the
+            // same files and dirs; each is counted once.
+            std::fs::hard_link(root.join("top.bin"), root.join("top_again.bin"))
+                .expect("hard link");
+            std::os::unix::fs::symlink(root.join("a"), root.join("a_again")).expect("symlink");
+            assert_eq!(du(root_s, &caps_bounds_v1(0, 0, 0)), (60, 3, 2, false));
+            assert_eq!(
+                du(
+                    root_s,
+                    &caps_bounds_v1(0, 0, x07_ext_os_native_core::CAP_ALLOW_SYMLINKS)
+                ),
+                (60, 3, 2, false)
+            );
+        }
 
-        let in_path = format!("{root}/in.txt");
-        std::fs::write(&in_path, b"abcdefgh").expect("write in.txt");
+        let _ = std::fs::remove_dir_all(&base);
+    }
 
-        let caps = caps_read_v1(8, 0);
-        let h = ok_i32(x07_ext_fs_stream_open_read_v1(
-            to_ev_bytes(in_path.as_bytes()),
-            to_ev_bytes(&caps),
-        ));
-        assert!(h > 0);
+    fn read_only_policy(root: &Path) -> Policy {
+        Policy {
+            sandboxed: true,
+            enabled: true,
+            deny_hidden: true,
+            read_roots: vec![root.to_path_buf()],
+            write_roots: Vec::new(),
+            allow_symlinks: false,
+            allow_mkdir: false,
+            allow_remove: false,
+            allow_rename: false,
+            allow_walk: true,
+            allow_glob: true,
+            allow_chmod: false,
+            max_read_bytes: 1024,
+            max_write_bytes: 1024,
+            max_entries: 100,
+            max_depth: 8,
+            max_path_bytes: 4096,
+            max_path_components: 255,
+            max_open_writers: 8,
+            max_wait_ms: 1000,
+            deny_read_globs: globset::GlobSet::empty(),
+            deny_write_globs: globset::GlobSet::empty(),
+            deny_globs_error: None,
+            unicode_normalize: UnicodeNormalize::Off,
+            file_mode: x07_ext_os_native_core::DEFAULT_FILE_MODE,
+            dir_mode: x07_ext_os_native_core::DEFAULT_DIR_MODE,
+        }
+    }
+
+    fn copy_tree_fixture(name: &str) -> (PathBuf, Policy) {
+        let base =
+            std::env::temp_dir().join(format!("x07_ext_fs_copy_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        let src = base.join("templates").join("site");
+        std::fs::create_dir_all(src.join("sub").join("deeper")).unwrap();
+        std::fs::write(src.join("a.txt"), b"alpha").unwrap();
+        std::fs::write(src.join("sub").join("b.txt"), b"bravo!").unwrap();
+        std::fs::write(src.join("sub").join("deeper").join("c.txt"), b"c").unwrap();
+        std::fs::create_dir_all(base.join("work")).unwrap();
+        let base = std::fs::canonicalize(&base).unwrap();
+        let pol = Policy {
+            write_roots: vec![base.join("work")],
+            allow_mkdir: true,
+            ..read_only_policy(&base)
+        };
+        (base, pol)
+    }
+
+    fn copy_caps(max_entries: u32, max_depth: u32, max_write_bytes: u32, flags: u32) -> CapsV1 {
+        CapsV1 {
+            max_read_bytes: 0,
+            max_write_bytes,
+            max_entries,
+            max_depth,
+            flags,
+        }
+    }
+
+    fn path_bytes(p: &Path) -> &[u8] {
+        p.to_str().unwrap().as_bytes()
+    }
+
+    #[test]
+    fn fs_copy_tree_v1_copies_nested_trees() {
+        let (base, pol) = copy_tree_fixture("nested");
+        let src = base.join("templates").join("site");
+        let dst = base.join("work").join("sites").join("site-1");
 
+        let caps = copy_caps(0, 0, 0, 0);
         assert_eq!(
-            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
-            b"abc".to_vec()
+            copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)),
+            Err(FS_ERR_NOT_FOUND),
+            "missing parent without CAP_CREATE_PARENTS"
         );
+
+        let caps = copy_caps(0, 0, 0, CAP_CREATE_PARENTS);
+        let out = copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)).unwrap();
         assert_eq!(
-            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
-            b"def".to_vec()
+            out,
+            CopyTreeV1 {
+                files_copied: 3,
+                bytes_copied: 12,
+                dirs_created: 3,
+                ..CopyTreeV1::default()
+            }
         );
+        assert_eq!(std::fs::read(dst.join("a.txt")).unwrap(), b"alpha");
         assert_eq!(
-            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
-            b"gh".to_vec()
+            std::fs::read(dst.join("sub").join("deeper").join("c.txt")).unwrap(),
+            b"c"
         );
+
+        // The source must be readable and the destination writable.
+        let into_src = src.join("copy");
         assert_eq!(
-            ok_bytes(x07_ext_fs_stream_read_some_v1(h, 3)),
-            Vec::<u8>::new()
+            copy_tree(&pol, caps, path_bytes(&src), path_bytes(&into_src)),
+            Err(FS_ERR_POLICY_DENY)
         );
-        assert_eq!(ok_i32(x07_ext_fs_stream_close_read_v1(h)), 1);
-        assert_eq!(x07_ext_fs_stream_drop_read_v1(h), 1);
-
-        // read_into variant (no allocations).
-        let h2 = ok_i32(x07_ext_fs_stream_open_read_v1(
-            to_ev_bytes(in_path.as_bytes()),
-            to_ev_bytes(&caps),
-        ));
-        let mut tmp = vec![0u8; 3];
+        let open = Policy {
+            write_roots: vec![base.clone()],
+            ..pol.clone()
+        };
         assert_eq!(
-            ok_i32(unsafe {
-                x07_ext_fs_stream_read_into_v1(h2, tmp.as_mut_ptr(), tmp.len() as u32)
-            }),
-            3
+            copy_tree(&open, caps, path_bytes(&src), path_bytes(&into_src)),
+            Err(FS_ERR_BAD_PATH)
         );
-        assert_eq!(tmp, b"abc");
-        assert_eq!(ok_i32(x07_ext_fs_stream_close_read_v1(h2)), 1);
-        assert_eq!(x07_ext_fs_stream_drop_read_v1(h2), 1);
-
-        // Too-large files are rejected at open.
-        let too_big_path = format!("{root}/too_big.txt");
-        std::fs::write(&too_big_path, b"abcdefghi").expect("write too_big.txt");
+        let no_mkdir = Policy {
+            allow_mkdir: false,
+            ..pol.clone()
+        };
+        let dst2 = base.join("work").join("site-2");
         assert_eq!(
-            err_i32(x07_ext_fs_stream_open_read_v1(
-                to_ev_bytes(too_big_path.as_bytes()),
-                to_ev_bytes(&caps),
-            )),
-            FS_ERR_TOO_LARGE
+            copy_tree(&no_mkdir, caps, path_bytes(&src), path_bytes(&dst2)),
+            Err(FS_ERR_POLICY_DENY)
         );
 
-        // Invalid handle errors.
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn fs_copy_tree_v1_overwrites_only_with_cap_overwrite() {
+        let (base, pol) = copy_tree_fixture("overwrite");
+        let src = base.join("templates").join("site");
+        let dst = base.join("work").join("site-1");
+        let caps = copy_caps(0, 0, 0, 0);
+        copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)).unwrap();
+
+        std::fs::write(src.join("a.txt"), b"ALPHA").unwrap();
+        std::fs::write(dst.join("local.txt"), b"kept").unwrap();
         assert_eq!(
-            err_bytes(x07_ext_fs_stream_read_some_v1(123, 1)),
-            FS_ERR_BAD_HANDLE
+            copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)),
+            Err(FS_ERR_ALREADY_EXISTS)
         );
+        assert_eq!(std::fs::read(dst.join("a.txt")).unwrap(), b"alpha");
+
+        let caps = copy_caps(0, 0, 0, CAP_OVERWRITE);
+        let out = copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)).unwrap();
         assert_eq!(
-            err_i32(x07_ext_fs_stream_close_read_v1(123)),
-            FS_ERR_BAD_HANDLE
+            (out.files_copied, out.dirs_created, out.failure),
+            (3, 0, None)
         );
+        assert_eq!(std::fs::read(dst.join("a.txt")).unwrap(), b"ALPHA");
+        assert_eq!(std::fs::read(dst.join("local.txt")).unwrap(), b"kept");
 
-        let _ = std::fs::remove_dir_all(&root);
+        // A file where the source has a dir stops the copy at that path.
+        std::fs::remove_dir_all(dst.join("sub")).unwrap();
+        std::fs::write(dst.join("sub"), b"in the way").unwrap();
+        let out = copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)).unwrap();
+        assert_eq!(out.failure, Some((FS_ERR_NOT_DIR, "sub".to_string())));
+        assert_eq!(out.files_copied, 1);
+
+        let _ = std::fs::remove_dir_all(&base);
     }
 
     #[test]
-    fn fs_append_all_v1_smoke() {
-        std::env::set_var("X07_OS_SANDBOXED", "0");
-        std::env::set_var("X07_OS_FS", "1");
-        std::env::set_var("X07_OS_FS_ALLOW_MKDIR", "1");
-        std::env::set_var("X07_OS_FS_MAX_WRITE_BYTES", "1000000");
-
-        let root = format!("target/x07_ext_fs_append_test_{}", std::process::id());
-        let _ = std::fs::remove_dir_all(&root);
-        std::fs::create_dir_all(&root).expect("create test dir");
+    fn fs_copy_tree_v1_stops_at_the_entry_that_breaks_a_bound() {
+        let (base, pol) = copy_tree_fixture("bounds");
+        let src = base.join("templates").join("site");
+        let work = base.join("work");
+
+        // Sorted walk: a.txt, sub, sub/b.txt, sub/deeper, sub/deeper/c.txt.
+        let dst = work.join("entries");
+        let out = copy_tree(
+            &pol,
+            copy_caps(3, 0, 0, 0),
+            path_bytes(&src),
+            path_bytes(&dst),
+        )
+        .unwrap();
+        assert_eq!(
+            out.failure,
+            Some((FS_ERR_TOO_MANY_ENTRIES, "sub/deeper".to_string()))
+        );
+        assert_eq!((out.files_copied, out.bytes_copied), (2, 11));
+        assert!(dst.join("sub").join("b.txt").is_file());
+        assert!(!dst.join("sub").join("deeper").exists());
+
+        let dst = work.join("depth");
+        let out = copy_tree(
+            &pol,
+            copy_caps(0, 1, 0, 0),
+            path_bytes(&src),
+            path_bytes(&dst),
+        )
+        .unwrap();
+        assert_eq!(
+            out.failure,
+            Some((FS_ERR_DEPTH_EXCEEDED, "sub/b.txt".to_string()))
+        );
 
-        let out_path = format!("{root}/out.txt");
-        let caps = caps_v1(1024, CAP_CREATE_PARENTS);
+        let dst = work.join("bytes");
+        let out = copy_tree(
+            &pol,
+            copy_caps(0, 0, 8, 0),
+            path_bytes(&src),
+            path_bytes(&dst),
+        )
+        .unwrap();
+        assert_eq!(
+            out.failure,
+            Some((FS_ERR_TOO_LARGE, "sub/b.txt".to_string()))
+        );
+        assert_eq!(out.bytes_copied, 5);
+        assert!(!dst.join("sub").join("b.txt").exists());
+
+        let encoded = out.encode();
+        assert_eq!(encoded.len(), 32 + "sub/b.txt".len());
+        assert_eq!(&encoded[0..4], &1u32.to_le_bytes());
+        assert_eq!(&encoded[4..12], &5u64.to_le_bytes());
+        assert_eq!(&encoded[24..28], &(FS_ERR_TOO_LARGE as u32).to_le_bytes());
+        assert_eq!(&encoded[28..32], &9u32.to_le_bytes());
+        assert_eq!(&encoded[32..], b"sub/b.txt");
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
 
+    #[cfg(unix)]
+    #[test]
+    fn fs_copy_tree_v1_symlink_matrix() {
+        use std::os::unix::fs::symlink;
+
+        let (base, pol) = copy_tree_fixture("symlinks");
+        let src = base.join("templates").join("site");
+        std::fs::write(base.join("outside.txt"), b"secret").unwrap();
+        symlink("sub", src.join("link_dir")).unwrap();
+        symlink("a.txt", src.join("link_file")).unwrap();
+        symlink(base.join("outside.txt"), src.join("link_out")).unwrap();
+        let links = ["link_dir", "link_file", "link_out"];
+
+        // Not following: every link is recreated as a link.
+        let dst = base.join("work").join("as-links");
+        let out = copy_tree(
+            &pol,
+            copy_caps(0, 0, 0, 0),
+            path_bytes(&src),
+            path_bytes(&dst),
+        )
+        .unwrap();
         assert_eq!(
-            ok_i32(x07_ext_fs_append_all_v1(
-                to_ev_bytes(out_path.as_bytes()),
-                to_ev_bytes(b"abc"),
-                to_ev_bytes(&caps),
-            )),
-            3
+            (out.files_copied, out.links_copied, out.failure),
+            (3, 3, None)
         );
+        for link in links {
+            assert_eq!(
+                std::fs::read_link(dst.join(link)).unwrap(),
+                std::fs::read_link(src.join(link)).unwrap()
+            );
+        }
+
+        // The cap needs the policy's allow_symlinks too.
+        let caps = copy_caps(0, 0, 0, CAP_ALLOW_SYMLINKS);
+        let dst = base.join("work").join("followed");
         assert_eq!(
-            ok_i32(x07_ext_fs_append_all_v1(
-                to_ev_bytes(out_path.as_bytes()),
-                to_ev_bytes(b"def"),
-                to_ev_bytes(&caps),
-            )),
-            3
+            copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)),
+            Err(FS_ERR_SYMLINK_DENIED)
         );
-        let got = std::fs::read(&out_path).expect("read out.txt");
-        assert_eq!(got, b"abcdef");
 
-        // max_write_bytes enforced per call.
-        let caps_small = caps_v1(2, CAP_CREATE_PARENTS);
+        // Following: links into the tree become copies, others stay links.
+        let follow = Policy {
+            allow_symlinks: true,
+            ..pol.clone()
+        };
+        let out = copy_tree(&follow, caps, path_bytes(&src), path_bytes(&dst)).unwrap();
         assert_eq!(
-            err_i32(x07_ext_fs_append_all_v1(
-                to_ev_bytes(out_path.as_bytes()),
-                to_ev_bytes(b"xyz"),
-                to_ev_bytes(&caps_small),
-            )),
-            FS_ERR_TOO_LARGE
+            (out.files_copied, out.links_copied, out.failure),
+            (6, 1, None)
         );
+        assert!(dst.join("link_dir").join("b.txt").is_file());
+        assert!(!dst.join("link_file").is_symlink());
+        assert_eq!(std::fs::read(dst.join("link_file")).unwrap(), b"alpha");
+        assert!(dst.join("link_out").is_symlink());
+
+        // A link already in the destination is replaced, never written through.
+        let caps = copy_caps(0, 0, 0, CAP_OVERWRITE);
+        let dst = base.join("work").join("as-links");
+        std::fs::remove_file(dst.join("a.txt")).unwrap();
+        symlink(base.join("outside.txt"), dst.join("a.txt")).unwrap();
+        copy_tree(&pol, caps, path_bytes(&src), path_bytes(&dst)).unwrap();
+        assert!(!dst.join("a.txt").is_symlink());
+        assert_eq!(std::fs::read(base.join("outside.txt")).unwrap(), b"secret");
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
 
-        // Atomic write is not supported for append.
-        let caps_atomic = caps_v1(1024, CAP_CREATE_PARENTS | CAP_ATOMIC_WRITE);
+    fn policy_info_u32s(info: &[u8]) -> Vec<u32> {
+        info[..36]
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn fs_policy_info_v1_hides_roots_when_sandboxed() {
+        let root = Path::new("/srv/fixtures");
+        let caps = parse_caps_v1(&caps_v1(0, 0)).unwrap();
+        let pol = Policy {
+            write_roots: vec![root.join("out")],
+            ..read_only_policy(root)
+        };
+        let info = policy_info_v1(&pol, caps);
+        assert_eq!(info.len(), 36);
         assert_eq!(
-            err_i32(x07_ext_fs_append_all_v1(
-                to_ev_bytes(out_path.as_bytes()),
-                to_ev_bytes(b"z"),
-                to_ev_bytes(&caps_atomic),
-            )),
-            FS_ERR_UNSUPPORTED
+            policy_info_u32s(&info),
+            vec![
+                1,
+                POLICY_INFO_SANDBOXED | POLICY_INFO_ENABLED | POLICY_INFO_DENY_HIDDEN,
+                POLICY_OP_WALK | POLICY_OP_GLOB,
+                1024,
+                1024,
+                100,
+                8,
+                1,
+                1,
+            ]
         );
 
-        // Directory paths are rejected.
-        let dir_path = format!("{root}/dir");
-        std::fs::create_dir_all(&dir_path).expect("create dir");
+        let open = Policy {
+            sandboxed: false,
+            deny_hidden: false,
+            allow_mkdir: true,
+            allow_chmod: true,
+            ..pol
+        };
+        let info = policy_info_v1(&open, caps);
+        let u = policy_info_u32s(&info);
+        assert_eq!(u[1], POLICY_INFO_ENABLED);
         assert_eq!(
-            err_i32(x07_ext_fs_append_all_v1(
-                to_ev_bytes(dir_path.as_bytes()),
-                to_ev_bytes(b"x"),
-                to_ev_bytes(&caps),
-            )),
-            FS_ERR_IS_DIR
+            u[2],
+            POLICY_OP_MKDIR | POLICY_OP_WALK | POLICY_OP_GLOB | POLICY_OP_CHMOD
         );
+        let mut roots = Vec::new();
+        let mut rest = &info[36..];
+        while !rest.is_empty() {
+            let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            roots.push(String::from_utf8(rest[4..4 + len].to_vec()).unwrap());
+            rest = &rest[4 + len..];
+        }
+        assert_eq!(
+            roots,
+            vec![
+                root.to_string_lossy().into_owned(),
+                root.join("out").to_string_lossy().into_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn fs_policy_info_v1_combines_caps_with_policy_maxima() {
+        let pol = read_only_policy(Path::new("/srv/fixtures"));
+        let mut caps = parse_caps_v1(&caps_v1(0, 0)).unwrap();
+        caps.max_read_bytes = 4096; // above the policy: policy wins
+        caps.max_write_bytes = 10; // below the policy: caps win
+        caps.max_entries = 0; // 0 means "use the policy"
+        caps.max_depth = 3;
+        let u = policy_info_u32s(&policy_info_v1(&pol, caps));
+        assert_eq!(&u[3..7], &[1024, 10, 100, 3]);
+    }
+
+    #[test]
+    fn fs_walk_glob_v1_filters_deny_read_globs() {
+        let root =
+            std::env::temp_dir().join(format!("x07_ext_fs_walk_deny_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for rel in [
+            "data/a.txt",
+            "data/server.key",
+            "data/secrets/token",
+            "data/sub/b.txt",
+        ] {
+            let p = root.join(rel);
+            std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+            std::fs::write(p, b"x").unwrap();
+        }
+        let root = std::fs::canonicalize(&root).unwrap();
+        let mut deny = globset::GlobSetBuilder::new();
+        deny.add(Glob::new("data/secrets/**").unwrap());
+        deny.add(Glob::new("*.key").unwrap());
+        let pol = Policy {
+            deny_read_globs: deny.build().unwrap(),
+            ..read_only_policy(&root)
+        };
+        let caps = parse_caps_v1(&caps_read_v1(0, 0)).unwrap();
+        let data = root.join("data");
+
+        let out = walk_glob_sorted_text(&pol, caps, data.to_str().unwrap().as_bytes(), b"**")
+            .expect("walk ok");
+        assert_eq!(String::from_utf8(out).unwrap(), "a.txt\nsub/b.txt\n");
+
+        // A glob that only matches denied files yields an empty listing.
+        let out = walk_glob_sorted_text(&pol, caps, data.to_str().unwrap().as_bytes(), b"*.key")
+            .expect("walk ok");
+        assert_eq!(out, b"\n");
 
         let _ = std::fs::remove_dir_all(&root);
     }
 
+    #[cfg(target_os = "linux")]
     #[test]
-    fn fs_read_write_v1_accept_absolute_paths_in_run_os() {
+    fn fs_listings_v1_nfc_policy_merges_normalization_forms() {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        const NFC: &str = "caf\u{e9}";
+        const NFD: &str = "cafe\u{301}";
+
+        let root = std::env::temp_dir().join(format!("x07_ext_fs_unicode_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        // ext4 keeps names byte-for-byte, so both forms coexist on disk.
+        std::fs::create_dir_all(root.join("d").join(NFD)).unwrap();
+        std::fs::write(root.join("d").join(NFC), b"nfc").unwrap();
+        std::fs::write(root.join("d").join(NFD).join("x.txt"), b"x").unwrap();
+        std::fs::write(
+            root.join("d").join(std::ffi::OsStr::from_bytes(b"bad\xff")),
+            b"?",
+        )
+        .unwrap();
+        let root = std::fs::canonicalize(&root).unwrap();
+        let caps = parse_caps_v1(&caps_read_v1(0, 0)).unwrap();
+        let dir = root.join("d");
+        let dir_b = dir.to_str().unwrap().as_bytes();
+
+        let off = read_only_policy(&root);
+        let nfc = Policy {
+            unicode_normalize: UnicodeNormalize::Nfc,
+            ..read_only_policy(&root)
+        };
+
+        // Off: both spellings listed in byte order ('e' < 0xC3).
+        let out = list_dir_sorted_text(&off, caps, dir_b).expect("list ok");
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{NFD}\n{NFC}\n"));
+
+        // Nfc: one name, whichever form is on disk.
+        let out = list_dir_sorted_text(&nfc, caps, dir_b).expect("list ok");
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{NFC}\n"));
+
+        let page = list_dir_page(&nfc, caps, dir_b, NFD.as_bytes(), 10).expect("page ok");
+        assert_eq!(page.lines, b"\x00", "NFD cursor is normalized too");
+        assert_eq!(page.skipped_non_utf8, 1);
+        let page = list_dir_page(&off, caps, dir_b, b"", 10).expect("page ok");
+        assert_eq!(page.lines, format!("{NFD}\n{NFC}\n\x00").into_bytes());
+        assert_eq!(page.skipped_non_utf8, 1);
+
+        let out = walk_glob_sorted_text(&nfc, caps, dir_b, b"**/*.txt").expect("walk ok");
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{NFC}/x.txt\n"));
+        let out = walk_glob_sorted_text(&off, caps, dir_b, b"**/*.txt").expect("walk ok");
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{NFD}/x.txt\n"));
+
+        // Lookups go through the NFC spelling: an NFD argument reaches the NFC file.
+        let nfd_path = dir.join(NFD);
+        let got = enforce_read_path_with(&nfc, caps, nfd_path.to_str().unwrap().as_bytes())
+            .expect("read path ok");
+        assert_eq!(std::fs::read(got).unwrap(), b"nfc");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    fn wait_change(path: &str, mtime_ns: u64, size: u64, timeout_ms: u32) -> (u32, Vec<u8>) {
+        let out = ok_bytes(x07_ext_fs_wait_change_v1(
+            to_ev_bytes(path.as_bytes()),
+            mtime_ns,
+            size,
+            timeout_ms,
+            to_ev_bytes(&caps_v1(0, 0)),
+        ));
+        assert_eq!(out.len(), 28);
+        assert_eq!(&out[4..8], &2u32.to_le_bytes());
+        (
+            u32::from_le_bytes(out[0..4].try_into().unwrap()),
+            out[4..].to_vec(),
+        )
+    }
+
+    fn stat2(path: &str) -> (u32, u64, u64) {
+        let st = ok_bytes(x07_ext_fs_stat_v2(
+            to_ev_bytes(path.as_bytes()),
+            to_ev_bytes(&caps_v1(0, 0)),
+        ));
+        assert_eq!(st.len(), 24);
+        (
+            u32::from_le_bytes(st[4..8].try_into().unwrap()),
+            u64::from_le_bytes(st[8..16].try_into().unwrap()),
+            u64::from_le_bytes(st[16..24].try_into().unwrap()),
+        )
+    }
+
+    #[test]
+    fn fs_wait_change_v1_sees_write_timeout_appear_and_remove() {
         std::env::set_var("X07_OS_SANDBOXED", "0");
         std::env::set_var("X07_OS_FS", "1");
-        std::env::set_var("X07_OS_FS_ALLOW_MKDIR", "1");
-        std::env::set_var("X07_OS_FS_MAX_WRITE_BYTES", "1000000");
 
-        let root = std::env::temp_dir().join(format!("x07_ext_fs_abs_test_{}", std::process::id()));
+        let root =
+            std::env::temp_dir().join(format!("x07_ext_fs_wait_test_{}", std::process::id()));
         let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create test dir");
+        let file = root.join("work.txt");
+        let file_s = file.to_str().expect("utf8 temp path").to_string();
+
+        // Nothing there yet and nothing shows up: times out with kind 0.
+        let start = Instant::now();
+        let (outcome, st) = wait_change(&file_s, WAIT_BASELINE_MISSING, 0, 30);
+        assert_eq!(outcome, WAIT_OUTCOME_TIMEOUT);
+        assert_eq!(&st[4..8], &0u32.to_le_bytes());
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        let writer = {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(40));
+                std::fs::write(&file, b"job-1").expect("write work item");
+            })
+        };
+        let (outcome, st) = wait_change(&file_s, WAIT_BASELINE_MISSING, 0, 5_000);
+        writer.join().unwrap();
+        assert_eq!(outcome, WAIT_OUTCOME_APPEARED);
+        assert_eq!(&st[4..8], &1u32.to_le_bytes());
+
+        // Unchanged file: times out and reports the same stat.
+        let (kind, size, mtime_ns) = stat2(&file_s);
+        assert_eq!((kind, size), (1, 5));
+        let (outcome, st) = wait_change(&file_s, mtime_ns, size, 30);
+        assert_eq!(outcome, WAIT_OUTCOME_TIMEOUT);
+        assert_eq!(u64::from_le_bytes(st[8..16].try_into().unwrap()), size);
+        assert_eq!(u64::from_le_bytes(st[16..24].try_into().unwrap()), mtime_ns);
+
+        let writer = {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(40));
+                use std::io::Write as _;
+                let mut f = OpenOptions::new().append(true).open(&file).unwrap();
+                f.write_all(b",job-2").expect("append work item");
+            })
+        };
+        let (outcome, st) = wait_change(&file_s, mtime_ns, size, 5_000);
+        writer.join().unwrap();
+        assert_eq!(outcome, WAIT_OUTCOME_CHANGED);
+        assert_eq!(u64::from_le_bytes(st[8..16].try_into().unwrap()), 11);
+
+        let (_, size, mtime_ns) = stat2(&file_s);
+        let remover = {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(40));
+                std::fs::remove_file(&file).expect("remove work item");
+            })
+        };
+        let (outcome, st) = wait_change(&file_s, mtime_ns, size, 5_000);
+        remover.join().unwrap();
+        assert_eq!(outcome, WAIT_OUTCOME_REMOVED);
+        assert_eq!(&st[4..8], &0u32.to_le_bytes());
 
-        let out_path = root.join("nested").join("out.txt");
-        let out_path_s = out_path.to_str().expect("utf8 temp path");
-        let caps = caps_v1(1024, CAP_CREATE_PARENTS | CAP_OVERWRITE);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    fn stat_many_request(paths: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&(paths.len() as u32).to_le_bytes());
+        for p in paths {
+            out.extend_from_slice(&(p.len() as u32).to_le_bytes());
+            out.extend_from_slice(p);
+        }
+        out
+    }
 
+    /// `(err_code, kind, size)` per entry.
+    fn stat_many_entries(out: &[u8]) -> Vec<(i32, u32, u64)> {
+        assert_eq!(&out[0..4], &1u32.to_le_bytes());
+        let count = u32::from_le_bytes(out[4..8].try_into().unwrap()) as usize;
+        assert_eq!(out.len(), 8 + count * 28);
+        out[8..]
+            .chunks(28)
+            .map(|e| {
+                assert_eq!(&e[4..8], &2u32.to_le_bytes());
+                (
+                    u32::from_le_bytes(e[0..4].try_into().unwrap()) as i32,
+                    u32::from_le_bytes(e[8..12].try_into().unwrap()),
+                    u64::from_le_bytes(e[12..20].try_into().unwrap()),
+                )
+            })
+            .collect()
+    }
+
+    fn stat_many_fixture(name: &str) -> (PathBuf, PathBuf) {
+        let base = std::env::temp_dir().join(format!(
+            "x07_ext_fs_stat_many_{name}_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&base);
+        let root = base.join("root");
+        std::fs::create_dir_all(root.join("sub")).expect("create root");
+        std::fs::create_dir_all(base.join("outside")).expect("create outside");
+        std::fs::write(root.join("a.txt"), b"hello").expect("write");
+        std::fs::write(root.join(".secret"), b"x").expect("write");
+        std::fs::write(base.join("outside/b.txt"), b"bye").expect("write");
+        (base.canonicalize().unwrap(), root.canonicalize().unwrap())
+    }
+
+    #[test]
+    fn stat_many_reports_each_entry_in_request_order() {
+        let (base, root) = stat_many_fixture("mixed");
+        let pol = read_only_policy(&root);
+        let caps = copy_caps(0, 0, 0, 0);
+
+        let file = root.join("a.txt");
+        let missing = root.join("nope.txt");
+        let hidden = root.join(".secret");
+        let outside = base.join("outside/b.txt");
+        let dir = root.join("sub");
+        let req = stat_many_request(&[
+            path_bytes(&file),
+            path_bytes(&missing),
+            path_bytes(&hidden),
+            path_bytes(&outside),
+            path_bytes(&dir),
+            path_bytes(&file),
+        ]);
+        let out = stat_many(&pol, caps, &req).expect("stat_many");
         assert_eq!(
-            ok_i32(x07_ext_fs_write_all_v1(
-                to_ev_bytes(out_path_s.as_bytes()),
-                to_ev_bytes(b"ok"),
-                to_ev_bytes(&caps),
-            )),
-            2
+            stat_many_entries(&out),
+            vec![
+                (0, 1, 5),
+                (0, 0, 0),
+                (FS_ERR_POLICY_DENY, 0, 0),
+                (FS_ERR_POLICY_DENY, 0, 0),
+                (0, 2, 0),
+                (0, 1, 5),
+            ]
         );
-        assert_eq!(std::fs::read(&out_path).expect("read out.txt"), b"ok");
 
-        let read_back = ok_bytes(x07_ext_fs_read_all_v1(
-            to_ev_bytes(out_path_s.as_bytes()),
-            to_ev_bytes(&caps),
-        ));
-        assert_eq!(read_back, b"ok");
+        // Hidden entries are allowed per entry once caps allow them.
+        let caps = copy_caps(0, 0, 0, x07_ext_os_native_core::CAP_ALLOW_HIDDEN);
+        let out = stat_many(&pol, caps, &stat_many_request(&[path_bytes(&hidden)])).unwrap();
+        assert_eq!(stat_many_entries(&out), vec![(0, 1, 1)]);
 
-        let _ = std::fs::remove_dir_all(&root);
+        // An empty batch is fine.
+        let out = stat_many(&pol, caps, &stat_many_request(&[])).unwrap();
+        assert!(stat_many_entries(&out).is_empty());
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn stat_many_rejects_oversized_or_malformed_batches() {
+        let (base, root) = stat_many_fixture("bounds");
+        let pol = read_only_policy(&root);
+        let file = root.join("a.txt");
+
+        // The effective max_entries: caps narrow the policy's 100.
+        let three = stat_many_request(&[path_bytes(&file); 3]);
+        assert!(stat_many(&pol, copy_caps(3, 0, 0, 0), &three).is_ok());
+        assert_eq!(
+            stat_many(&pol, copy_caps(2, 0, 0, 0), &three),
+            Err(FS_ERR_TOO_MANY_ENTRIES)
+        );
+        let many = stat_many_request(&[path_bytes(&file); 101]);
+        assert_eq!(
+            stat_many(&pol, copy_caps(0, 0, 0, 0), &many),
+            Err(FS_ERR_TOO_MANY_ENTRIES)
+        );
+
+        let caps = copy_caps(0, 0, 0, 0);
+        let mut trailing = stat_many_request(&[path_bytes(&file)]);
+        trailing.push(0);
+        assert_eq!(stat_many(&pol, caps, &trailing), Err(FS_ERR_BAD_PATH));
+        let mut short = stat_many_request(&[path_bytes(&file)]);
+        short.pop();
+        assert_eq!(stat_many(&pol, caps, &short), Err(FS_ERR_BAD_PATH));
+        let mut v2 = stat_many_request(&[]);
+        v2[0] = 2;
+        assert_eq!(stat_many(&pol, caps, &v2), Err(FS_ERR_UNSUPPORTED));
+        assert_eq!(
+            stat_many(
+                &pol,
+                copy_caps(0, 0, 0, x07_ext_os_native_core::CAP_ALLOW_SYMLINKS),
+                &stat_many_request(&[path_bytes(&file)])
+            ),
+            Err(FS_ERR_SYMLINK_DENIED)
+        );
+
+        let _ = std::fs::remove_dir_all(&base);
     }
 }
@@ -1,7 +1,10 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use once_cell::sync::OnceCell;
+use std::borrow::Cow;
 use std::fs;
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization as _};
 
 // -------------------------
 // Error code space (OS/FS v1)
@@ -24,6 +27,9 @@ pub const FS_ERR_TOO_MANY_ENTRIES: i32 = 60017;
 pub const FS_ERR_DEPTH_EXCEEDED: i32 = 60018;
 pub const FS_ERR_SYMLINK_DENIED: i32 = 60019;
 pub const FS_ERR_UNSUPPORTED: i32 = 60020;
+pub const FS_ERR_LOCK_TIMEOUT: i32 = 60021;
+pub const FS_ERR_TOO_MANY_HANDLES: i32 = 60022;
+pub const FS_ERR_BAD_POLICY: i32 = 60023;
 
 // -------------------------
 // Caps decoding (FsCapsV1)
@@ -113,13 +119,55 @@ pub struct Policy {
     pub allow_rename: bool,
     pub allow_walk: bool,
     pub allow_glob: bool,
+    /// `x07_ext_fs_set_mode_v1`; setuid/setgid/sticky are refused regardless.
+    pub allow_chmod: bool,
 
     pub max_read_bytes: u32,
     pub max_write_bytes: u32,
     pub max_entries: u32,
     pub max_depth: u32,
+    pub max_path_bytes: u32,
+    pub max_path_components: u32,
+    /// Cap on writer handles that still hold an open file.
+    pub max_open_writers: u32,
+    /// Upper bound on how long one blocking wait (e.g. `wait_change`) may last.
+    pub max_wait_ms: u32,
+
+    /// Root-relative patterns carved out of `read_roots` / `write_roots`.
+    pub deny_read_globs: GlobSet,
+    pub deny_write_globs: GlobSet,
+    /// Why a deny-glob env var failed to compile. While set, every enforced
+    /// path fails with `FS_ERR_BAD_POLICY` instead of running without the denies.
+    pub deny_globs_error: Option<String>,
+
+    /// Unicode form applied to incoming paths and to listed names.
+    pub unicode_normalize: UnicodeNormalize,
+
+    /// Permission bits of files the natives create (unix only), applied
+    /// regardless of the process umask.
+    pub file_mode: u32,
+    /// Permission bits of directories the natives create (unix only).
+    pub dir_mode: u32,
 }
 
+/// `X07_OS_FS_UNICODE_NORMALIZE`. With `Nfc`, path arguments are NFC-normalized
+/// before resolution and list/walk results are NFC-normalized before sorting,
+/// so NFC and NFD spellings of a name behave the same on every host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnicodeNormalize {
+    /// Names are passed through byte-for-byte.
+    #[default]
+    Off,
+    Nfc,
+}
+
+pub const DEFAULT_MAX_PATH_BYTES: u32 = 4096;
+pub const DEFAULT_MAX_PATH_COMPONENTS: u32 = 255;
+pub const DEFAULT_MAX_OPEN_WRITERS: u32 = 256;
+pub const DEFAULT_MAX_WAIT_MS: u32 = 60_000;
+pub const DEFAULT_FILE_MODE: u32 = 0o644;
+pub const DEFAULT_DIR_MODE: u32 = 0o755;
+
 static POLICY: OnceCell<Policy> = OnceCell::new();
 
 fn env_bool(name: &str, default: bool) -> bool {
@@ -141,6 +189,27 @@ fn env_u32_nonzero(name: &str, default: u32) -> u32 {
         .unwrap_or(default)
 }
 
+/// Octal permission bits (`644`, `0644` or `0o644`). Unparsable values and
+/// values above `0o7777` keep the default.
+fn env_mode(name: &str, default: u32) -> u32 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| {
+            let v = v.trim();
+            u32::from_str_radix(v.strip_prefix("0o").unwrap_or(v), 8).ok()
+        })
+        .filter(|&m| m <= 0o7777)
+        .unwrap_or(default)
+}
+
+/// `nfc` (any case) enables NFC; anything else leaves names untouched.
+fn env_unicode_normalize(name: &str) -> UnicodeNormalize {
+    match std::env::var(name) {
+        Ok(v) if v.trim().eq_ignore_ascii_case("nfc") => UnicodeNormalize::Nfc,
+        _ => UnicodeNormalize::Off,
+    }
+}
+
 pub fn canonicalize_best_effort(p: &Path) -> PathBuf {
     if p.is_absolute() {
         return p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
@@ -162,6 +231,20 @@ fn env_roots(name: &str) -> Vec<PathBuf> {
         .collect()
 }
 
+/// Compiles a `;`-separated glob list. Patterns match the path relative to
+/// the root that admitted it, always `/`-separated; `*` also crosses `/`.
+fn env_globs(name: &str) -> Result<GlobSet, String> {
+    compile_globs(&std::env::var(name).unwrap_or_default()).map_err(|e| format!("{name}: {e}"))
+}
+
+fn compile_globs(list: &str) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pat in list.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        builder.add(Glob::new(pat)?);
+    }
+    builder.build()
+}
+
 fn load_policy() -> Policy {
     let sandboxed = env_bool("X07_OS_SANDBOXED", false);
     let enabled = env_bool("X07_OS_FS", !sandboxed);
@@ -170,6 +253,16 @@ fn load_policy() -> Policy {
     let read_roots = env_roots("X07_OS_FS_READ_ROOTS");
     let write_roots = env_roots("X07_OS_FS_WRITE_ROOTS");
 
+    let mut deny_globs_error = None;
+    let mut deny_globs = |name: &str| {
+        env_globs(name).unwrap_or_else(|err| {
+            deny_globs_error.get_or_insert(err);
+            GlobSet::empty()
+        })
+    };
+    let deny_read_globs = deny_globs("X07_OS_FS_DENY_READ_GLOBS");
+    let deny_write_globs = deny_globs("X07_OS_FS_DENY_WRITE_GLOBS");
+
     Policy {
         sandboxed,
         enabled,
@@ -182,10 +275,60 @@ fn load_policy() -> Policy {
         allow_rename: env_bool("X07_OS_FS_ALLOW_RENAME", !sandboxed),
         allow_walk: env_bool("X07_OS_FS_ALLOW_WALK", !sandboxed),
         allow_glob: env_bool("X07_OS_FS_ALLOW_GLOB", !sandboxed),
+        allow_chmod: env_bool("X07_OS_FS_ALLOW_CHMOD", !sandboxed),
         max_read_bytes: env_u32_nonzero("X07_OS_FS_MAX_READ_BYTES", 16 * 1024 * 1024),
         max_write_bytes: env_u32_nonzero("X07_OS_FS_MAX_WRITE_BYTES", 16 * 1024 * 1024),
         max_entries: env_u32_nonzero("X07_OS_FS_MAX_ENTRIES", 10_000),
         max_depth: env_u32_nonzero("X07_OS_FS_MAX_DEPTH", 64),
+        max_path_bytes: env_u32_nonzero("X07_OS_FS_MAX_PATH_BYTES", DEFAULT_MAX_PATH_BYTES),
+        max_path_components: env_u32_nonzero(
+            "X07_OS_FS_MAX_PATH_COMPONENTS",
+            DEFAULT_MAX_PATH_COMPONENTS,
+        ),
+        max_open_writers: env_u32_nonzero("X07_OS_FS_MAX_OPEN_WRITERS", DEFAULT_MAX_OPEN_WRITERS),
+        max_wait_ms: env_u32_nonzero("X07_OS_FS_MAX_WAIT_MS", DEFAULT_MAX_WAIT_MS),
+        deny_read_globs,
+        deny_write_globs,
+        deny_globs_error,
+        unicode_normalize: env_unicode_normalize("X07_OS_FS_UNICODE_NORMALIZE"),
+        file_mode: env_mode("X07_OS_FS_FILE_MODE", DEFAULT_FILE_MODE),
+        dir_mode: env_mode("X07_OS_FS_DIR_MODE", DEFAULT_DIR_MODE),
+    }
+}
+
+impl Policy {
+    /// Whether a deny-read glob covers `abs`, an already root-confined path.
+    /// Always false outside the sandbox, where there are no roots to be
+    /// relative to.
+    pub fn denies_read(&self, abs: &Path) -> bool {
+        self.sandboxed && is_denied_by_globs(abs, &self.read_roots, &self.deny_read_globs)
+    }
+
+    /// Write-side counterpart of [`Policy::denies_read`].
+    pub fn denies_write(&self, abs: &Path) -> bool {
+        self.sandboxed && is_denied_by_globs(abs, &self.write_roots, &self.deny_write_globs)
+    }
+
+    /// `name` in the policy's Unicode form; borrowed when already in it.
+    pub fn normalize_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.unicode_normalize {
+            UnicodeNormalize::Nfc if is_nfc_quick(name.chars()) != IsNormalized::Yes => {
+                Cow::Owned(name.nfc().collect())
+            }
+            _ => Cow::Borrowed(name),
+        }
+    }
+
+    /// Byte-level [`Policy::normalize_name`] for path arguments. Non-UTF-8
+    /// input is returned as is and rejected later by path parsing.
+    pub fn normalize_path_bytes<'a>(&self, path: &'a [u8]) -> Cow<'a, [u8]> {
+        match std::str::from_utf8(path) {
+            Ok(s) => match self.normalize_name(s) {
+                Cow::Owned(n) => Cow::Owned(n.into_bytes()),
+                Cow::Borrowed(_) => Cow::Borrowed(path),
+            },
+            Err(_) => Cow::Borrowed(path),
+        }
     }
 }
 
@@ -202,6 +345,20 @@ pub fn bytes_to_utf8(b: &[u8]) -> Result<&str, i32> {
 }
 
 pub fn parse_safe_path_v1(input: &[u8]) -> Result<(PathBuf, bool), i32> {
+    let pol = policy();
+    parse_safe_path_limited_v1(input, pol.max_path_bytes, pol.max_path_components)
+}
+
+/// `parse_safe_path_v1` with explicit limits: `input` may be at most `max_bytes`
+/// long and name at most `max_components` segments (`.` segments excluded).
+pub fn parse_safe_path_limited_v1(
+    input: &[u8],
+    max_bytes: u32,
+    max_components: u32,
+) -> Result<(PathBuf, bool), i32> {
+    if input.len() > max_bytes as usize {
+        return Err(FS_ERR_BAD_PATH);
+    }
     let s = bytes_to_utf8(input)?;
     if s.is_empty() {
         return Err(FS_ERR_BAD_PATH);
@@ -231,6 +388,9 @@ pub fn parse_safe_path_v1(input: &[u8]) -> Result<(PathBuf, bool), i32> {
         if raw.starts_with('.') {
             hidden = true;
         }
+        if segs.len() >= max_components as usize {
+            return Err(FS_ERR_BAD_PATH);
+        }
         segs.push(raw);
     }
     if segs.is_empty() {
@@ -272,13 +432,68 @@ fn is_allowed_by_roots(abs_path: &Path, roots: &[PathBuf]) -> bool {
     roots.iter().any(|r| abs_path.starts_with(r))
 }
 
+/// Matches `abs_path` relative to every root containing it; a hit under any
+/// of them denies.
+fn is_denied_by_globs(abs_path: &Path, roots: &[PathBuf], globs: &GlobSet) -> bool {
+    if globs.is_empty() {
+        return false;
+    }
+    roots.iter().any(|r| {
+        abs_path
+            .strip_prefix(r)
+            .ok()
+            .and_then(Path::to_str)
+            .is_some_and(|rel| !rel.is_empty() && globs.is_match(rel.replace('\\', "/")))
+    })
+}
+
+/// Control dirs the host runner stages into the run cwd (fs latency index,
+/// rr cassettes and index, kv seed). Writes into them are always denied, so a
+/// program cannot rewrite its own deterministic inputs mid-run.
+pub const RESERVED_CONTROL_DIRS: &[&str] = &[".x07_fs", ".x07_rr", ".x07_kv"];
+
+fn starts_with_control_dir(rel: &Path) -> bool {
+    matches!(
+        rel.components().next(),
+        Some(Component::Normal(first)) if RESERVED_CONTROL_DIRS.iter().any(|d| first == *d)
+    )
+}
+
+/// `path` is in a [`RESERVED_CONTROL_DIRS`] entry of the cwd: its first
+/// component is one, or, once resolved against the cwd with symlinks
+/// followed, it lands under one.
+fn targets_control_dir(path: &Path) -> bool {
+    if path.is_relative() && starts_with_control_dir(path) {
+        return true;
+    }
+    let Ok(cwd) = std::env::current_dir() else {
+        return false;
+    };
+    let cwd = canonicalize_best_effort(&cwd);
+    let abs = canonicalize_existing_prefix(&canonicalize_best_effort(path));
+    abs.strip_prefix(&cwd).is_ok_and(starts_with_control_dir)
+}
+
 pub fn enforce_read_path(caps: CapsV1, path_bytes: &[u8]) -> Result<PathBuf, i32> {
-    let pol = policy();
+    enforce_read_path_with(policy(), caps, path_bytes)
+}
+
+/// [`enforce_read_path`] against an explicit policy.
+pub fn enforce_read_path_with(
+    pol: &Policy,
+    caps: CapsV1,
+    path_bytes: &[u8],
+) -> Result<PathBuf, i32> {
     if !pol.enabled {
         return Err(FS_ERR_DISABLED);
     }
+    if pol.deny_globs_error.is_some() {
+        return Err(FS_ERR_BAD_POLICY);
+    }
 
-    let (path, hidden) = parse_safe_path_v1(path_bytes)?;
+    let path_bytes = pol.normalize_path_bytes(path_bytes);
+    let (path, hidden) =
+        parse_safe_path_limited_v1(&path_bytes, pol.max_path_bytes, pol.max_path_components)?;
     if pol.deny_hidden && hidden && !cap_allow_hidden(caps) {
         return Err(FS_ERR_POLICY_DENY);
     }
@@ -291,22 +506,38 @@ pub fn enforce_read_path(caps: CapsV1, path_bytes: &[u8]) -> Result<PathBuf, i32
     }
 
     let abs = canonicalize_existing_prefix(&canonicalize_best_effort(&path));
-    if !is_allowed_by_roots(&abs, &pol.read_roots) {
+    if !is_allowed_by_roots(&abs, &pol.read_roots) || pol.denies_read(&abs) {
         return Err(FS_ERR_POLICY_DENY);
     }
     Ok(abs)
 }
 
 pub fn enforce_write_path(caps: CapsV1, path_bytes: &[u8]) -> Result<PathBuf, i32> {
-    let pol = policy();
+    enforce_write_path_with(policy(), caps, path_bytes)
+}
+
+/// [`enforce_write_path`] against an explicit policy.
+pub fn enforce_write_path_with(
+    pol: &Policy,
+    caps: CapsV1,
+    path_bytes: &[u8],
+) -> Result<PathBuf, i32> {
     if !pol.enabled {
         return Err(FS_ERR_DISABLED);
     }
+    if pol.deny_globs_error.is_some() {
+        return Err(FS_ERR_BAD_POLICY);
+    }
 
-    let (path, hidden) = parse_safe_path_v1(path_bytes)?;
+    let path_bytes = pol.normalize_path_bytes(path_bytes);
+    let (path, hidden) =
+        parse_safe_path_limited_v1(&path_bytes, pol.max_path_bytes, pol.max_path_components)?;
     if pol.deny_hidden && hidden && !cap_allow_hidden(caps) {
         return Err(FS_ERR_POLICY_DENY);
     }
+    if targets_control_dir(&path) {
+        return Err(FS_ERR_POLICY_DENY);
+    }
 
     if !pol.sandboxed {
         return Ok(path);
@@ -316,7 +547,7 @@ pub fn enforce_write_path(caps: CapsV1, path_bytes: &[u8]) -> Result<PathBuf, i3
     }
 
     let abs = canonicalize_existing_prefix(&canonicalize_best_effort(&path));
-    if !is_allowed_by_roots(&abs, &pol.write_roots) {
+    if !is_allowed_by_roots(&abs, &pol.write_roots) || pol.denies_write(&abs) {
         return Err(FS_ERR_POLICY_DENY);
     }
     Ok(abs)
@@ -336,6 +567,58 @@ pub fn map_io_err(e: &io::Error) -> i32 {
     }
 }
 
+/// Opens `path` with `opts` plus [`Policy::file_mode`] as the create mode.
+/// A file this call created is then set to exactly that mode, since the
+/// create mode alone is still narrowed by the umask.
+pub fn open_with_file_mode(
+    opts: &mut fs::OpenOptions,
+    path: &Path,
+    pol: &Policy,
+) -> io::Result<fs::File> {
+    let existed = fs::symlink_metadata(path).is_ok();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt as _, PermissionsExt as _};
+        let f = opts.mode(pol.file_mode).open(path)?;
+        if !existed {
+            f.set_permissions(fs::Permissions::from_mode(pol.file_mode))?;
+        }
+        Ok(f)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (existed, pol);
+        opts.open(path)
+    }
+}
+
+/// `create_dir_all` that sets every directory it created to
+/// [`Policy::dir_mode`]. Directories that already existed keep their mode.
+pub fn create_dir_all_with_mode(path: &Path, pol: &Policy) -> io::Result<()> {
+    let mut created = Vec::new();
+    let mut cur = Some(path);
+    while let Some(p) = cur {
+        if p.as_os_str().is_empty() || fs::symlink_metadata(p).is_ok() {
+            break;
+        }
+        created.push(p);
+        cur = p.parent();
+    }
+    fs::create_dir_all(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        // Deepest first, so a mode without search permission on a parent
+        // cannot lock us out of its children.
+        for dir in created {
+            fs::set_permissions(dir, fs::Permissions::from_mode(pol.dir_mode))?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (created, pol);
+    Ok(())
+}
+
 pub fn open_atomic_tmp_best_effort(
     path: &Path,
     overwrite: bool,
@@ -365,11 +648,11 @@ pub fn open_atomic_tmp_best_effort(
     let mut counter: u32 = 0;
     loop {
         let candidate = parent.join(format!("{name}.x07_tmp_{counter}"));
-        match fs::OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(&candidate)
-        {
+        match open_with_file_mode(
+            fs::OpenOptions::new().write(true).create_new(true),
+            &candidate,
+            policy(),
+        ) {
             Ok(f) => return Ok((f, candidate)),
             Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
                 counter = counter.wrapping_add(1);
@@ -379,3 +662,297 @@ pub fn open_atomic_tmp_best_effort(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_byte_limit_is_inclusive() {
+        let at = "a".repeat(16);
+        assert!(parse_safe_path_limited_v1(at.as_bytes(), 16, 255).is_ok());
+        let over = "a".repeat(17);
+        assert_eq!(
+            parse_safe_path_limited_v1(over.as_bytes(), 16, 255).unwrap_err(),
+            FS_ERR_BAD_PATH
+        );
+    }
+
+    #[test]
+    fn path_component_limit_is_inclusive() {
+        let at = ["d"; 4].join("/");
+        assert!(parse_safe_path_limited_v1(at.as_bytes(), 4096, 4).is_ok());
+        let over = ["d"; 5].join("/");
+        assert_eq!(
+            parse_safe_path_limited_v1(over.as_bytes(), 4096, 4).unwrap_err(),
+            FS_ERR_BAD_PATH
+        );
+        // `.` segments are dropped and do not count.
+        assert!(parse_safe_path_limited_v1(b"./d/./d/d/d", 4096, 4).is_ok());
+    }
+
+    #[test]
+    fn default_limits_accept_boundary_paths() {
+        let max_bytes = "a".repeat(DEFAULT_MAX_PATH_BYTES as usize);
+        assert!(parse_safe_path_limited_v1(
+            max_bytes.as_bytes(),
+            DEFAULT_MAX_PATH_BYTES,
+            DEFAULT_MAX_PATH_COMPONENTS
+        )
+        .is_ok());
+        let max_components = vec!["d"; DEFAULT_MAX_PATH_COMPONENTS as usize].join("/");
+        assert!(parse_safe_path_limited_v1(
+            max_components.as_bytes(),
+            DEFAULT_MAX_PATH_BYTES,
+            DEFAULT_MAX_PATH_COMPONENTS
+        )
+        .is_ok());
+        let too_many = vec!["d"; DEFAULT_MAX_PATH_COMPONENTS as usize + 1].join("/");
+        assert_eq!(
+            parse_safe_path_limited_v1(
+                too_many.as_bytes(),
+                DEFAULT_MAX_PATH_BYTES,
+                DEFAULT_MAX_PATH_COMPONENTS
+            )
+            .unwrap_err(),
+            FS_ERR_BAD_PATH
+        );
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("x07_os_core_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("data/secrets")).unwrap();
+        fs::write(dir.join("data/a.txt"), b"a").unwrap();
+        fs::write(dir.join("data/server.key"), b"k").unwrap();
+        fs::write(dir.join("data/secrets/token"), b"t").unwrap();
+        canonicalize_best_effort(&dir)
+    }
+
+    fn sandboxed_policy(root: &Path, deny_read: &str, deny_write: &str) -> Policy {
+        Policy {
+            sandboxed: true,
+            enabled: true,
+            deny_hidden: true,
+            read_roots: vec![root.to_path_buf()],
+            write_roots: vec![root.to_path_buf()],
+            allow_symlinks: false,
+            allow_mkdir: true,
+            allow_remove: true,
+            allow_rename: true,
+            allow_walk: true,
+            allow_glob: true,
+            allow_chmod: false,
+            max_read_bytes: 1024,
+            max_write_bytes: 1024,
+            max_entries: 100,
+            max_depth: 8,
+            max_path_bytes: DEFAULT_MAX_PATH_BYTES,
+            max_path_components: DEFAULT_MAX_PATH_COMPONENTS,
+            max_open_writers: DEFAULT_MAX_OPEN_WRITERS,
+            max_wait_ms: DEFAULT_MAX_WAIT_MS,
+            deny_read_globs: compile_globs(deny_read).unwrap(),
+            deny_write_globs: compile_globs(deny_write).unwrap(),
+            deny_globs_error: None,
+            unicode_normalize: UnicodeNormalize::Off,
+            file_mode: DEFAULT_FILE_MODE,
+            dir_mode: DEFAULT_DIR_MODE,
+        }
+    }
+
+    fn caps() -> CapsV1 {
+        CapsV1 {
+            max_read_bytes: 0,
+            max_write_bytes: 0,
+            max_entries: 0,
+            max_depth: 0,
+            flags: 0,
+        }
+    }
+
+    fn path_bytes(root: &Path, rel: &str) -> Vec<u8> {
+        root.join(rel).to_str().unwrap().as_bytes().to_vec()
+    }
+
+    #[test]
+    fn deny_read_globs_carve_out_of_read_roots() {
+        let root = temp_root("deny_read");
+        let pol = sandboxed_policy(&root, "data/secrets/**; *.key", "");
+
+        assert!(enforce_read_path_with(&pol, caps(), &path_bytes(&root, "data/a.txt")).is_ok());
+        for denied in ["data/secrets/token", "data/server.key"] {
+            assert_eq!(
+                enforce_read_path_with(&pol, caps(), &path_bytes(&root, denied)).unwrap_err(),
+                FS_ERR_POLICY_DENY,
+                "{denied}"
+            );
+        }
+        // Read denies do not leak into writes.
+        assert!(
+            enforce_write_path_with(&pol, caps(), &path_bytes(&root, "data/server.key")).is_ok()
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn deny_write_globs_cover_paths_not_yet_created() {
+        let root = temp_root("deny_write");
+        let pol = sandboxed_policy(&root, "", "data/secrets/**");
+
+        assert!(enforce_write_path_with(&pol, caps(), &path_bytes(&root, "data/b.txt")).is_ok());
+        assert_eq!(
+            enforce_write_path_with(&pol, caps(), &path_bytes(&root, "data/secrets/new/x"))
+                .unwrap_err(),
+            FS_ERR_POLICY_DENY
+        );
+        assert!(
+            enforce_read_path_with(&pol, caps(), &path_bytes(&root, "data/secrets/token")).is_ok()
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn bad_deny_glob_fails_every_call() {
+        let root = temp_root("deny_bad");
+        let err = compile_globs("ok/*; data/[unclosed").unwrap_err();
+        let pol = Policy {
+            deny_globs_error: Some(err.to_string()),
+            ..sandboxed_policy(&root, "", "")
+        };
+        assert_eq!(
+            enforce_read_path_with(&pol, caps(), &path_bytes(&root, "data/a.txt")).unwrap_err(),
+            FS_ERR_BAD_POLICY
+        );
+        assert_eq!(
+            enforce_write_path_with(&pol, caps(), &path_bytes(&root, "data/a.txt")).unwrap_err(),
+            FS_ERR_BAD_POLICY
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn nfc_policy_resolves_nfd_arguments_to_nfc_paths() {
+        let root = temp_root("unicode_nfc");
+        let nfc = "caf\u{e9}.txt";
+        let nfd = "cafe\u{301}.txt";
+        let off = sandboxed_policy(&root, "", "");
+        let pol = Policy {
+            unicode_normalize: UnicodeNormalize::Nfc,
+            ..sandboxed_policy(&root, "", "")
+        };
+
+        assert_eq!(pol.normalize_name(nfd), nfc);
+        assert!(matches!(pol.normalize_name(nfc), Cow::Borrowed(_)));
+        assert!(matches!(off.normalize_name(nfd), Cow::Borrowed(_)));
+        assert_eq!(
+            pol.normalize_path_bytes(b"\xff/a").as_ref(),
+            b"\xff/a",
+            "non-UTF-8 input is left for path parsing to reject"
+        );
+
+        let got = enforce_read_path_with(&pol, caps(), &path_bytes(&root, nfd)).unwrap();
+        assert_eq!(got, root.join(nfc));
+        let got = enforce_write_path_with(&off, caps(), &path_bytes(&root, nfd)).unwrap();
+        assert_eq!(got, root.join(nfd));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn writes_into_control_dirs_are_denied_under_any_root() {
+        let cwd = canonicalize_best_effort(&std::env::current_dir().unwrap());
+        let sandboxed = sandboxed_policy(&cwd, "", "");
+        let open = Policy {
+            sandboxed: false,
+            ..sandboxed_policy(&cwd, "", "")
+        };
+        let hidden_ok = CapsV1 {
+            flags: CAP_ALLOW_HIDDEN,
+            ..caps()
+        };
+
+        for pol in [&sandboxed, &open] {
+            for denied in [
+                b".x07_rr/index.evrr".to_vec(),
+                b"./.x07_kv/seed.evkv".to_vec(),
+                b".x07_fs".to_vec(),
+                path_bytes(&cwd, ".x07_rr/index.evrr"),
+            ] {
+                assert_eq!(
+                    enforce_write_path_with(pol, hidden_ok, &denied).unwrap_err(),
+                    FS_ERR_POLICY_DENY,
+                    "{}",
+                    String::from_utf8_lossy(&denied)
+                );
+            }
+            // Only the cwd's own control dirs are reserved.
+            for allowed in [".x07_rr_backup/index.evrr", "data/.x07_rr/index.evrr"] {
+                assert!(
+                    enforce_write_path_with(pol, hidden_ok, allowed.as_bytes()).is_ok(),
+                    "{allowed}"
+                );
+            }
+            // Reads are unaffected.
+            assert!(enforce_read_path_with(pol, hidden_ok, b".x07_rr/index.evrr").is_ok());
+        }
+    }
+
+    #[test]
+    fn env_mode_parses_octal() {
+        for (v, want) in [
+            ("600", 0o600),
+            ("0640", 0o640),
+            ("0o2775", 0o2775),
+            (" 755 ", 0o755),
+            ("", 0o644),
+            ("rw-r--r--", 0o644),
+            ("888", 0o644),
+            ("17777", 0o644),
+        ] {
+            std::env::set_var("X07_OS_CORE_TEST_MODE", v);
+            assert_eq!(env_mode("X07_OS_CORE_TEST_MODE", 0o644), want, "{v:?}");
+        }
+        std::env::remove_var("X07_OS_CORE_TEST_MODE");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn created_files_and_dirs_get_policy_mode_regardless_of_umask() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let root = temp_root("modes");
+        // Bits a typical 022 umask would strip from the create mode.
+        let pol = Policy {
+            file_mode: 0o666,
+            dir_mode: 0o777,
+            ..sandboxed_policy(&root, "", "")
+        };
+        let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o7777;
+
+        let existing = root.join("data");
+        fs::set_permissions(&existing, fs::Permissions::from_mode(0o700)).unwrap();
+        let nested = existing.join("x/y");
+        create_dir_all_with_mode(&nested, &pol).unwrap();
+        assert_eq!(mode(&nested), 0o777);
+        assert_eq!(mode(&existing.join("x")), 0o777);
+        assert_eq!(mode(&existing), 0o700, "existing dirs keep their mode");
+
+        let file = nested.join("out.bin");
+        open_with_file_mode(
+            fs::OpenOptions::new().write(true).create_new(true),
+            &file,
+            &pol,
+        )
+        .unwrap();
+        assert_eq!(mode(&file), 0o666);
+
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+        open_with_file_mode(
+            fs::OpenOptions::new().write(true).truncate(true),
+            &file,
+            &pol,
+        )
+        .unwrap();
+        assert_eq!(mode(&file), 0o600, "overwritten files keep their mode");
+        let _ = fs::remove_dir_all(&root);
+    }
+}
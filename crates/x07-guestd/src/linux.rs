@@ -22,6 +22,7 @@ const EXIT_MOUNT_FAILED: i32 = 121;
 const EXIT_VSOCK_FAILED: i32 = 122;
 const EXIT_EXEC_FAILED: i32 = 123;
 const EXIT_INTERNAL_ERROR: i32 = 124;
+const EXIT_WORKLOAD_OOM: i32 = 125;
 
 const FLAG_REPORT_COMPLETE: u32 = 0x0000_0001;
 const FLAG_STDOUT_TRUNCATED: u32 = 0x0000_0002;
@@ -33,7 +34,12 @@ const FLAG_GUEST_FAILURE_WRITTEN: u32 = 0x0000_0020;
 const ENV_GUESTD_WORKDIR: &str = "X07_GUESTD_WORKDIR";
 const CMDLINE_RUN_ID_KEY: &str = "x07.run_id";
 const POLICY_PATH: &str = "/x07/in/policy.json";
+/// Touched by the host's reaper ahead of the deadline; forwarded to the
+/// workload as SIGTERM.
+const DEADLINE_WARNING_PATH: &str = "/x07/in/deadline_warning";
 const NFT_TABLE_NAME: &str = "x07";
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const WORKLOAD_CGROUP: &str = "/sys/fs/cgroup/x07-workload";
 
 #[derive(Debug, Deserialize)]
 struct PolicyLite {
@@ -67,6 +73,10 @@ struct GuestRequest {
     expect: GuestExpect,
     #[serde(default)]
     limits: GuestLimits,
+    /// Write `/x07/out/guest_clock.json` at exec start so the host can
+    /// estimate clock skew.
+    #[serde(default)]
+    report_clock: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -90,6 +100,11 @@ struct GuestExpect {
     stdout_schema_version: String,
     #[serde(default = "default_true")]
     stdout_is_single_json_object: bool,
+    /// Redirect the workload's stdout into this file under `/x07/out`
+    /// instead of streaming it, so a large report is not cut at
+    /// `stdout_max_bytes`. The host then sees empty stdout.
+    #[serde(default)]
+    report_file: Option<String>,
 }
 
 fn default_expect_schema_version() -> String {
@@ -106,6 +121,10 @@ struct GuestLimits {
     wall_ms: Option<u64>,
     stdout_max_bytes: Option<u64>,
     stderr_max_bytes: Option<u64>,
+    /// `memory.max` of the workload cgroup.
+    workload_mem_bytes: Option<u64>,
+    /// `pids.max` of the workload cgroup.
+    workload_pids_max: Option<u32>,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -324,6 +343,19 @@ fn try_main() -> Result<i32> {
         return Ok(EXIT_INTERNAL_ERROR);
     }
 
+    let cgroup_procs = match setup_workload_cgroup(&req.limits) {
+        Ok(v) => v,
+        Err(err) => {
+            let _ = writeln!(
+                &mut std::io::stderr(),
+                "workload cgroup setup failed: {err:#}"
+            );
+            let flags = FLAG_CONTRACT_ERROR | FLAG_METRICS_PRESENT;
+            let _ = write_ctrl_record(&mut ctrl, EXIT_INTERNAL_ERROR, flags, start, 0, 0);
+            return Ok(EXIT_INTERNAL_ERROR);
+        }
+    };
+
     let mut stdout_sock = unsafe { std::fs::File::from_raw_fd(stdout_fd) };
     let mut stderr_sock = unsafe { std::fs::File::from_raw_fd(stderr_fd) };
 
@@ -333,9 +365,25 @@ fn try_main() -> Result<i32> {
     let env = req.exec.env.clone();
     let workdir = env.get(ENV_GUESTD_WORKDIR).map(PathBuf::from);
 
+    let report_file = match req.expect.report_file.as_deref() {
+        Some(path) => match create_report_file(Path::new(path)) {
+            Ok(f) => Some(f),
+            Err(err) => {
+                let flags = FLAG_CONTRACT_ERROR | FLAG_METRICS_PRESENT;
+                let _ = write_ctrl_record(&mut ctrl, EXIT_INTERNAL_ERROR, flags, start, 0, 0);
+                let _ = writeln!(&mut std::io::stderr(), "report_file setup failed: {err:#}");
+                return Ok(EXIT_INTERNAL_ERROR);
+            }
+        },
+        None => None,
+    };
+
     let mut cmd = std::process::Command::new(&req.exec.path);
     cmd.stdin(std::process::Stdio::null());
-    cmd.stdout(std::process::Stdio::piped());
+    match report_file {
+        Some(f) => cmd.stdout(f),
+        None => cmd.stdout(std::process::Stdio::piped()),
+    };
     cmd.stderr(std::process::Stdio::piped());
     cmd.env_clear();
     for (k, v) in &env {
@@ -356,6 +404,27 @@ fn try_main() -> Result<i32> {
         cmd.args(&req.exec.argv[1..]);
     }
 
+    if let Some(procs) = cgroup_procs.as_ref() {
+        use std::os::unix::io::AsRawFd as _;
+        let procs_fd = procs.as_raw_fd();
+        unsafe {
+            // Writing "0" to cgroup.procs moves the writer, i.e. the child
+            // before it execs.
+            cmd.pre_exec(move || {
+                if libc::write(procs_fd, b"0".as_ptr() as *const libc::c_void, 1) != 1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    if req.report_clock {
+        if let Err(err) = write_guest_clock(&req.run_id) {
+            let _ = writeln!(&mut std::io::stderr(), "guest_clock write failed: {err:#}");
+        }
+    }
+
     let mut child = match cmd.spawn() {
         Ok(c) => c,
         Err(err) => {
@@ -366,17 +435,29 @@ fn try_main() -> Result<i32> {
         }
     };
 
-    let child_stdout = child.stdout.take().context("take child stdout")?;
+    // `None` when stdout goes to `expect.report_file`.
+    let child_stdout = child.stdout.take();
     let child_stderr = child.stderr.take().context("take child stderr")?;
 
     let stdout_thread = std::thread::spawn(move || -> std::io::Result<StreamStats> {
-        stream_pipe_to_vsock(child_stdout, &mut stdout_sock, stdout_cap, true)
+        match child_stdout {
+            Some(r) => stream_pipe_to_vsock(r, &mut stdout_sock, stdout_cap, true),
+            None => Ok(StreamStats::empty()),
+        }
     });
     let stderr_thread = std::thread::spawn(move || -> std::io::Result<StreamStats> {
         stream_pipe_to_vsock(child_stderr, &mut stderr_sock, stderr_cap, false)
     });
 
+    let workload_done = std::sync::Arc::new(std::sync::Mutex::new(false));
+    let warn_thread = spawn_deadline_warning_watch(child.id(), workload_done.clone());
+
+    // Wait without reaping so the pid stays ours while the watcher may still
+    // signal it; only reap once the watcher can no longer see `done == false`.
+    wait_exited_no_reap(child.id()).context("wait child")?;
+    *workload_done.lock().unwrap_or_else(|e| e.into_inner()) = true;
     let exit_status = child.wait().context("wait child")?;
+    let _ = warn_thread.join();
     let stdout_stats = stdout_thread
         .join()
         .unwrap_or_else(|_| Ok(StreamStats::empty()))?;
@@ -392,6 +473,14 @@ fn try_main() -> Result<i32> {
             exit_code = 128 + sig;
         }
     }
+    if cgroup_procs.is_some() {
+        let events = std::fs::read_to_string(Path::new(WORKLOAD_CGROUP).join("memory.events"))
+            .unwrap_or_default();
+        if oom_kill_count(&events) > 0 {
+            let _ = writeln!(&mut std::io::stderr(), "workload_oom: killed by memory.max");
+            exit_code = EXIT_WORKLOAD_OOM;
+        }
+    }
 
     let mut flags: u32 = 0;
     if stdout_stats.truncated {
@@ -401,12 +490,17 @@ fn try_main() -> Result<i32> {
         flags |= FLAG_STDERR_TRUNCATED;
     }
 
-    if !stdout_stats.truncated
+    let report_ok = match req.expect.report_file.as_deref() {
+        Some(path) => std::fs::read(path)
+            .is_ok_and(|bytes| is_expected_report_json_object(&bytes).unwrap_or(false)),
+        None => {
+            !stdout_stats.truncated
+                && is_expected_report_json_object(&stdout_stats.captured_stdout).unwrap_or(false)
+        }
+    };
+    if report_ok
         && req.expect.stdout_is_single_json_object
         && req.expect.stdout_schema_version == X07_OS_RUNNER_REPORT_SCHEMA_VERSION
-        && stdout_stats
-            .is_expected_report_json_object()
-            .unwrap_or(false)
     {
         flags |= FLAG_REPORT_COMPLETE;
     }
@@ -425,6 +519,55 @@ fn try_main() -> Result<i32> {
     Ok(exit_code)
 }
 
+/// Polls for [`DEADLINE_WARNING_PATH`] until the workload exits and sends it
+/// one SIGTERM when the file shows up. `done` is held across the check and
+/// the signal, and is only set before the child is reaped, so the pid can't
+/// have been reused by the time it is signalled.
+fn spawn_deadline_warning_watch(
+    pid: u32,
+    done: std::sync::Arc<std::sync::Mutex<bool>>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || loop {
+        {
+            let done = done.lock().unwrap_or_else(|e| e.into_inner());
+            if *done {
+                return;
+            }
+            if Path::new(DEADLINE_WARNING_PATH).exists() {
+                if let Ok(pid) = i32::try_from(pid) {
+                    unsafe {
+                        libc::kill(pid, libc::SIGTERM);
+                    }
+                }
+                return;
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    })
+}
+
+/// Blocks until `pid` has exited, leaving it a zombie for `Child::wait` to reap.
+fn wait_exited_no_reap(pid: u32) -> std::io::Result<()> {
+    loop {
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        let rc = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if rc == 0 {
+            return Ok(());
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
 fn validate_request(req: &GuestRequest) -> Result<()> {
     if req.schema_version != "x07.guest.request@1" {
         anyhow::bail!("unsupported schema_version {:?}", req.schema_version);
@@ -445,10 +588,33 @@ fn validate_request(req: &GuestRequest) -> Result<()> {
             anyhow::bail!("mount guest_path must be absolute: {:?}", m.guest_path);
         }
     }
+    if let Some(path) = req.expect.report_file.as_deref() {
+        validate_report_file(path)?;
+    }
+
+    Ok(())
+}
 
+fn validate_report_file(path: &str) -> Result<()> {
+    let Some(rel) = path.strip_prefix("/x07/out/") else {
+        anyhow::bail!("expect.report_file must be under /x07/out, got {path:?}");
+    };
+    if rel
+        .split('/')
+        .any(|c| c.is_empty() || c == "." || c == "..")
+    {
+        anyhow::bail!("expect.report_file must be a normalized path, got {path:?}");
+    }
     Ok(())
 }
 
+fn create_report_file(path: &Path) -> Result<std::fs::File> {
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent)?;
+    }
+    std::fs::File::create(path).with_context(|| format!("create {}", path.display()))
+}
+
 fn validate_mount_tag(tag: &str) -> Result<()> {
     let Some(num) = tag.strip_prefix("x07m") else {
         anyhow::bail!("mount tag must start with x07m, got {tag:?}");
@@ -708,6 +874,51 @@ fn read_resolv_nameservers() -> Result<Vec<IpAddr>> {
     Ok(out)
 }
 
+/// Creates the workload cgroup when the request sets any workload limit and
+/// returns its `cgroup.procs`, opened for the child to join.
+fn setup_workload_cgroup(limits: &GuestLimits) -> Result<Option<std::fs::File>> {
+    if limits.workload_mem_bytes.is_none() && limits.workload_pids_max.is_none() {
+        return Ok(None);
+    }
+    let root = Path::new(CGROUP_ROOT);
+    if !root.join("cgroup.controllers").is_file() {
+        ensure_dir(root)?;
+        mount_fs("cgroup2", CGROUP_ROOT, "cgroup2", 0, None).context("mount cgroup2")?;
+    }
+    write_cgroup_file(root, "cgroup.subtree_control", "+memory +pids")?;
+
+    let cg = Path::new(WORKLOAD_CGROUP);
+    ensure_dir(cg)?;
+    if let Some(mem) = limits.workload_mem_bytes {
+        write_cgroup_file(cg, "memory.max", &mem.to_string())?;
+        // Best-effort: the guest kernel may be built without swap.
+        let _ = write_cgroup_file(cg, "memory.swap.max", "0");
+    }
+    if let Some(pids) = limits.workload_pids_max {
+        write_cgroup_file(cg, "pids.max", &pids.to_string())?;
+    }
+
+    let procs = std::fs::OpenOptions::new()
+        .write(true)
+        .open(cg.join("cgroup.procs"))
+        .context("open workload cgroup.procs")?;
+    Ok(Some(procs))
+}
+
+fn write_cgroup_file(dir: &Path, name: &str, value: &str) -> Result<()> {
+    let path = dir.join(name);
+    std::fs::write(&path, value).with_context(|| format!("write {}", path.display()))
+}
+
+/// `oom_kill` counter from a cgroup v2 `memory.events` file.
+fn oom_kill_count(events: &str) -> u64 {
+    events
+        .lines()
+        .find_map(|l| l.strip_prefix("oom_kill "))
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0)
+}
+
 fn ensure_dir(path: &Path) -> Result<()> {
     std::fs::create_dir_all(path).with_context(|| format!("create dir {}", path.display()))?;
     Ok(())
@@ -717,6 +928,48 @@ fn ensure_dir(path: &Path) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn oom_kill_count_reads_memory_events() {
+        let events = "low 0\nhigh 0\nmax 12\noom 1\noom_kill 1\noom_group_kill 0\n";
+        assert_eq!(oom_kill_count(events), 1);
+        assert_eq!(oom_kill_count("low 0\noom 0\noom_kill 0\n"), 0);
+        assert_eq!(oom_kill_count(""), 0);
+    }
+
+    #[test]
+    fn request_limits_accept_workload_fields() {
+        let limits: GuestLimits = serde_json::from_str(
+            r#"{"wall_ms":1000,"workload_mem_bytes":436207616,"workload_pids_max":1024}"#,
+        )
+        .unwrap();
+        assert_eq!(limits.workload_mem_bytes, Some(436_207_616));
+        assert_eq!(limits.workload_pids_max, Some(1024));
+        let old: GuestLimits = serde_json::from_str(r#"{"wall_ms":1000}"#).unwrap();
+        assert!(old.workload_mem_bytes.is_none() && old.workload_pids_max.is_none());
+    }
+
+    #[test]
+    fn report_file_must_be_a_normalized_path_under_x07_out() {
+        validate_report_file("/x07/out/report.json").unwrap();
+        validate_report_file("/x07/out/reports/solve.json").unwrap();
+        for bad in [
+            "/x07/out",
+            "/x07/out/",
+            "/x07/in/report.json",
+            "x07/out/report.json",
+            "/x07/out/../in/report.json",
+            "/x07/out/./report.json",
+            "/x07/out//report.json",
+        ] {
+            assert!(validate_report_file(bad).is_err(), "{bad}");
+        }
+
+        let expect: GuestExpect =
+            serde_json::from_str(r#"{"report_file":"/x07/out/report.json"}"#).unwrap();
+        assert_eq!(expect.report_file.as_deref(), Some("/x07/out/report.json"));
+        assert!(expect.stdout_is_single_json_object);
+    }
+
     #[test]
     fn build_nft_script_net_disabled_is_default_drop() {
         let net = PolicyNet {
@@ -888,16 +1141,17 @@ impl StreamStats {
             captured_stdout: Vec::new(),
         }
     }
+}
 
-    fn is_expected_report_json_object(&self) -> Result<bool> {
-        let s = std::str::from_utf8(&self.captured_stdout).context("stdout not utf-8")?;
-        let v: serde_json::Value = serde_json::from_str(s).context("stdout not JSON")?;
-        let obj = v.as_object().context("stdout not a JSON object")?;
-        Ok(obj
-            .get("schema_version")
-            .and_then(|v| v.as_str())
-            .is_some_and(|sv| sv == X07_OS_RUNNER_REPORT_SCHEMA_VERSION))
-    }
+/// Checks captured stdout, or the `expect.report_file` contents.
+fn is_expected_report_json_object(bytes: &[u8]) -> Result<bool> {
+    let s = std::str::from_utf8(bytes).context("report not utf-8")?;
+    let v: serde_json::Value = serde_json::from_str(s).context("report not JSON")?;
+    let obj = v.as_object().context("report not a JSON object")?;
+    Ok(obj
+        .get("schema_version")
+        .and_then(|v| v.as_str())
+        .is_some_and(|sv| sv == X07_OS_RUNNER_REPORT_SCHEMA_VERSION))
 }
 
 fn stream_pipe_to_vsock<R: Read>(
@@ -1028,6 +1282,30 @@ fn write_guest_failure(spec: GuestFailureWriteSpec<'_>) -> Result<bool> {
     Ok(true)
 }
 
+#[derive(Debug, serde::Serialize)]
+struct GuestClock<'a> {
+    schema_version: &'static str,
+    run_id: &'a str,
+    exec_start_unix_ms: u64,
+}
+
+fn write_guest_clock(run_id: &str) -> Result<()> {
+    let clock = GuestClock {
+        schema_version: "x07.guest.clock@0.1.0",
+        run_id,
+        exec_start_unix_ms: now_unix_ms()?,
+    };
+
+    let out_path = Path::new("/x07/out/guest_clock.json");
+    let tmp_path = Path::new("/x07/out/guest_clock.json.tmp");
+
+    let mut bytes = serde_json::to_vec(&clock)?;
+    bytes.push(b'\n');
+    std::fs::write(tmp_path, &bytes).context("write guest_clock.json.tmp")?;
+    std::fs::rename(tmp_path, out_path).context("rename guest_clock.json")?;
+    Ok(())
+}
+
 fn fsync_path(path: &Path) -> Result<()> {
     use std::os::unix::io::AsRawFd as _;
     let f = std::fs::OpenOptions::new().read(true).open(path)?;
@@ -0,0 +1,451 @@
+//! Retention for `compiled_out` directories.
+//!
+//! [`crate::compile_program_with_options`] writes an [`ArtifactMeta`] sidecar
+//! (`<artifact>.x07-artifact.json`) next to every exe it copies out. [`apply`]
+//! uses those sidecars to prune a directory by age, per-program history and
+//! total size. Files without a sidecar are treated as legacy artifacts: only
+//! the age rule (by mtime) ever removes them.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const ARTIFACT_META_SCHEMA_VERSION: &str = "x07.artifact.meta@0.1.0";
+/// Appended to the artifact's file name to get its sidecar path.
+pub const ARTIFACT_SIDECAR_SUFFIX: &str = ".x07-artifact.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactMeta {
+    pub schema_version: String,
+    /// sha256 of the program bytes the artifact was compiled from.
+    pub program_sha256: String,
+    pub world: String,
+    pub created_unix_ms: u64,
+    pub native_cache_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Once the other rules ran, remove the oldest sidecar'd artifacts until
+    /// the directory (sidecars included) fits. Legacy artifacts still count
+    /// towards the total.
+    pub max_total_bytes: Option<u64>,
+    /// Remove artifacts created (or, for legacy ones, modified) longer ago.
+    pub max_age: Option<Duration>,
+    /// Keep only the newest N artifacts per `program_sha256`.
+    pub keep_latest_per_program: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionReason {
+    /// No rule applied.
+    Kept,
+    /// Over `max_total_bytes`, but legacy artifacts are only removed by age.
+    KeptLegacy,
+    MaxAge,
+    /// A newer artifact of the same program pushed it past `keep_latest_per_program`.
+    KeepLatestPerProgram,
+    MaxTotalBytes,
+    /// A sidecar whose artifact is gone.
+    OrphanSidecar,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionEntry {
+    pub path: PathBuf,
+    /// Artifact plus sidecar.
+    pub bytes: u64,
+    pub created_unix_ms: u64,
+    /// `None` for legacy artifacts and orphan sidecars.
+    pub program_sha256: Option<String>,
+    pub reason: RetentionReason,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub kept: Vec<RetentionEntry>,
+    pub removed: Vec<RetentionEntry>,
+    pub kept_bytes: u64,
+    pub removed_bytes: u64,
+}
+
+pub fn sidecar_path(artifact: &Path) -> PathBuf {
+    let mut name = artifact.file_name().unwrap_or_default().to_os_string();
+    name.push(ARTIFACT_SIDECAR_SUFFIX);
+    artifact.with_file_name(name)
+}
+
+pub fn write_sidecar(artifact: &Path, meta: &ArtifactMeta) -> Result<()> {
+    let path = sidecar_path(artifact);
+    let bytes = serde_json::to_vec_pretty(meta).context("serialize artifact meta")?;
+    crate::write_file_atomic(&path, &bytes)
+        .with_context(|| format!("write artifact meta: {}", path.display()))
+}
+
+/// `None` when the sidecar is missing or unreadable.
+pub fn read_sidecar(artifact: &Path) -> Option<ArtifactMeta> {
+    let bytes = std::fs::read(sidecar_path(artifact)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+pub(crate) fn now_unix_ms() -> u64 {
+    unix_ms(SystemTime::now())
+}
+
+fn unix_ms(t: SystemTime) -> u64 {
+    t.duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Applies `policy` to the artifacts directly inside `dir` and removes what
+/// it rejects.
+pub fn apply(dir: &Path, policy: &RetentionPolicy) -> Result<RetentionReport> {
+    apply_at(dir, policy, now_unix_ms())
+}
+
+struct Candidate {
+    path: PathBuf,
+    sidecar: Option<PathBuf>,
+    bytes: u64,
+    created_unix_ms: u64,
+    program_sha256: Option<String>,
+    reason: RetentionReason,
+}
+
+impl Candidate {
+    fn entry(&self) -> RetentionEntry {
+        RetentionEntry {
+            path: self.path.clone(),
+            bytes: self.bytes,
+            created_unix_ms: self.created_unix_ms,
+            program_sha256: self.program_sha256.clone(),
+            reason: self.reason,
+        }
+    }
+
+    fn removed(&self) -> bool {
+        !matches!(
+            self.reason,
+            RetentionReason::Kept | RetentionReason::KeptLegacy
+        )
+    }
+}
+
+fn apply_at(dir: &Path, policy: &RetentionPolicy, now_unix_ms: u64) -> Result<RetentionReport> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("read dir: {}", dir.display()))? {
+        let entry = entry.with_context(|| format!("read dir entry: {}", dir.display()))?;
+        let meta = entry
+            .metadata()
+            .with_context(|| format!("stat: {}", entry.path().display()))?;
+        if meta.is_file() {
+            files.push((entry.path(), meta));
+        }
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut candidates = Vec::new();
+    for (path, meta) in &files {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(artifact_name) = name.strip_suffix(ARTIFACT_SIDECAR_SUFFIX) {
+            if !path.with_file_name(artifact_name).is_file() {
+                candidates.push(Candidate {
+                    path: path.clone(),
+                    sidecar: None,
+                    bytes: meta.len(),
+                    created_unix_ms: meta.modified().map(unix_ms).unwrap_or(0),
+                    program_sha256: None,
+                    reason: RetentionReason::OrphanSidecar,
+                });
+            }
+            continue;
+        }
+        let sidecar = sidecar_path(path);
+        let sidecar_bytes = std::fs::metadata(&sidecar).map(|m| m.len()).unwrap_or(0);
+        let (created_unix_ms, program_sha256) = match read_sidecar(path) {
+            Some(m) => (m.created_unix_ms, Some(m.program_sha256)),
+            None => (meta.modified().map(unix_ms).unwrap_or(0), None),
+        };
+        candidates.push(Candidate {
+            path: path.clone(),
+            sidecar: sidecar.is_file().then_some(sidecar),
+            bytes: meta.len() + sidecar_bytes,
+            created_unix_ms,
+            program_sha256,
+            reason: RetentionReason::Kept,
+        });
+    }
+
+    if let Some(max_age) = policy.max_age {
+        let cutoff = now_unix_ms.saturating_sub(max_age.as_millis() as u64);
+        for c in candidates.iter_mut().filter(|c| !c.removed()) {
+            if c.created_unix_ms < cutoff {
+                c.reason = RetentionReason::MaxAge;
+            }
+        }
+    }
+
+    // Newest first; ties broken by path so runs are reproducible.
+    let mut by_age: Vec<usize> = (0..candidates.len()).collect();
+    by_age.sort_by(|&a, &b| {
+        candidates[b]
+            .created_unix_ms
+            .cmp(&candidates[a].created_unix_ms)
+            .then_with(|| candidates[a].path.cmp(&candidates[b].path))
+    });
+
+    if let Some(keep) = policy.keep_latest_per_program {
+        let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+        for &i in &by_age {
+            let c = &mut candidates[i];
+            let Some(program) = c.program_sha256.clone() else {
+                continue;
+            };
+            if c.removed() {
+                continue;
+            }
+            let n = seen.entry(program).or_insert(0);
+            *n += 1;
+            if *n > keep {
+                c.reason = RetentionReason::KeepLatestPerProgram;
+            }
+        }
+    }
+
+    if let Some(max_total) = policy.max_total_bytes {
+        let mut total: u64 = candidates
+            .iter()
+            .filter(|c| !c.removed())
+            .map(|c| c.bytes)
+            .sum();
+        for &i in by_age.iter().rev() {
+            if total <= max_total {
+                break;
+            }
+            let c = &mut candidates[i];
+            if c.removed() {
+                continue;
+            }
+            if c.program_sha256.is_none() {
+                c.reason = RetentionReason::KeptLegacy;
+                continue;
+            }
+            c.reason = RetentionReason::MaxTotalBytes;
+            total -= c.bytes;
+        }
+    }
+
+    let mut report = RetentionReport::default();
+    for c in &candidates {
+        if !c.removed() {
+            report.kept_bytes += c.bytes;
+            report.kept.push(c.entry());
+            continue;
+        }
+        std::fs::remove_file(&c.path)
+            .with_context(|| format!("remove artifact: {}", c.path.display()))?;
+        if let Some(sidecar) = &c.sidecar {
+            std::fs::remove_file(sidecar)
+                .with_context(|| format!("remove artifact meta: {}", sidecar.display()))?;
+        }
+        report.removed_bytes += c.bytes;
+        report.removed.push(c.entry());
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+    const NOW: u64 = 1_800_000_000_000;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "x07_artifacts_{name}_{}_{}",
+            std::process::id(),
+            now_unix_ms()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn artifact(dir: &Path, name: &str, program: &str, created_unix_ms: u64, size: usize) {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![0u8; size]).unwrap();
+        write_sidecar(
+            &path,
+            &ArtifactMeta {
+                schema_version: ARTIFACT_META_SCHEMA_VERSION.to_string(),
+                program_sha256: program.to_string(),
+                world: "solve-pure".to_string(),
+                created_unix_ms,
+                native_cache_key: Some("k".repeat(64)),
+            },
+        )
+        .unwrap();
+    }
+
+    fn legacy(dir: &Path, name: &str, modified_unix_ms: u64, size: usize) {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![0u8; size]).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::UNIX_EPOCH + Duration::from_millis(modified_unix_ms))
+            .unwrap();
+    }
+
+    fn names(entries: &[RetentionEntry]) -> Vec<(String, RetentionReason)> {
+        entries
+            .iter()
+            .map(|e| {
+                (
+                    e.path.file_name().unwrap().to_string_lossy().into_owned(),
+                    e.reason,
+                )
+            })
+            .collect()
+    }
+
+    fn on_disk(dir: &Path) -> Vec<String> {
+        let mut out: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn max_age_removes_old_artifacts_and_legacy() {
+        let dir = make_temp_dir("age");
+        artifact(&dir, "old", "p1", NOW - 10 * DAY_MS, 8);
+        artifact(&dir, "new", "p1", NOW - DAY_MS, 8);
+        legacy(&dir, "legacy-old", NOW - 10 * DAY_MS, 8);
+        legacy(&dir, "legacy-new", NOW - DAY_MS, 8);
+
+        let policy = RetentionPolicy {
+            max_age: Some(Duration::from_millis(7 * DAY_MS)),
+            ..Default::default()
+        };
+        let report = apply_at(&dir, &policy, NOW).unwrap();
+        assert_eq!(
+            names(&report.removed),
+            vec![
+                ("legacy-old".to_string(), RetentionReason::MaxAge),
+                ("old".to_string(), RetentionReason::MaxAge),
+            ]
+        );
+        assert_eq!(
+            on_disk(&dir),
+            vec![
+                "legacy-new".to_string(),
+                "new".to_string(),
+                format!("new{ARTIFACT_SIDECAR_SUFFIX}"),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keep_latest_per_program_ignores_legacy() {
+        let dir = make_temp_dir("latest");
+        artifact(&dir, "a1", "p1", NOW - 3, 8);
+        artifact(&dir, "a2", "p1", NOW - 2, 8);
+        artifact(&dir, "a3", "p1", NOW - 1, 8);
+        artifact(&dir, "b1", "p2", NOW - 5, 8);
+        legacy(&dir, "legacy", NOW - 10 * DAY_MS, 8);
+
+        let policy = RetentionPolicy {
+            keep_latest_per_program: Some(2),
+            ..Default::default()
+        };
+        let report = apply_at(&dir, &policy, NOW).unwrap();
+        assert_eq!(
+            names(&report.removed),
+            vec![("a1".to_string(), RetentionReason::KeepLatestPerProgram)]
+        );
+        assert_eq!(
+            names(&report.kept),
+            vec![
+                ("a2".to_string(), RetentionReason::Kept),
+                ("a3".to_string(), RetentionReason::Kept),
+                ("b1".to_string(), RetentionReason::Kept),
+                ("legacy".to_string(), RetentionReason::Kept),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn max_total_bytes_removes_oldest_but_not_legacy() {
+        let dir = make_temp_dir("total");
+        artifact(&dir, "a", "p1", NOW - 30, 1000);
+        artifact(&dir, "b", "p2", NOW - 20, 1000);
+        artifact(&dir, "c", "p3", NOW - 10, 1000);
+        legacy(&dir, "legacy", NOW - 10 * DAY_MS, 1000);
+
+        let sidecar = std::fs::metadata(sidecar_path(&dir.join("a")))
+            .unwrap()
+            .len();
+        // Room for the legacy file and two sidecar'd artifacts.
+        let policy = RetentionPolicy {
+            max_total_bytes: Some(1000 + 2 * (1000 + sidecar)),
+            ..Default::default()
+        };
+        let report = apply_at(&dir, &policy, NOW).unwrap();
+        assert_eq!(
+            names(&report.removed),
+            vec![("a".to_string(), RetentionReason::MaxTotalBytes)]
+        );
+        assert_eq!(report.removed_bytes, 1000 + sidecar);
+        assert_eq!(report.kept_bytes, 1000 + 2 * (1000 + sidecar));
+        assert!(!sidecar_path(&dir.join("a")).exists());
+
+        // Even a zero budget leaves legacy artifacts alone.
+        let report = apply_at(
+            &dir,
+            &RetentionPolicy {
+                max_total_bytes: Some(0),
+                ..Default::default()
+            },
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(
+            names(&report.kept),
+            vec![("legacy".to_string(), RetentionReason::KeptLegacy)]
+        );
+        assert_eq!(on_disk(&dir), vec!["legacy".to_string()]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn orphan_sidecars_are_removed_and_report_serializes() {
+        let dir = make_temp_dir("orphan");
+        artifact(&dir, "gone", "p1", NOW, 8);
+        std::fs::remove_file(dir.join("gone")).unwrap();
+
+        let report = apply_at(&dir, &RetentionPolicy::default(), NOW).unwrap();
+        assert_eq!(
+            names(&report.removed),
+            vec![(
+                format!("gone{ARTIFACT_SIDECAR_SUFFIX}"),
+                RetentionReason::OrphanSidecar
+            )]
+        );
+        assert!(on_disk(&dir).is_empty());
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["removed"][0]["reason"], "orphan_sidecar");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,312 @@
+//! Budget checks over [`RunnerResult`] metrics, for tests and CI gates.
+//!
+//! ```json
+//! { "max_fuel": 2000000, "max_rr_request_calls": 3, "forbid_traps": true }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::RunnerResult;
+
+/// Optional bounds on a run's metrics; unset fields are not checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricAssertions {
+    pub max_fuel: Option<u64>,
+    /// Bounds `heap_used`.
+    pub max_heap: Option<u64>,
+    pub max_fs_read_file_calls: Option<u64>,
+    /// Bounds `rr_next_calls` (one per replayed request).
+    pub max_rr_request_calls: Option<u64>,
+    pub max_kv_set_calls: Option<u64>,
+    /// Required `sched_stats.sched_trace_hash`.
+    pub sched_trace_hash: Option<String>,
+    pub forbid_traps: bool,
+}
+
+impl MetricAssertions {
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes).context("parse metric assertions json")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// Name of the checked [`RunnerResult`] metric, e.g. `fuel_used`.
+    pub metric: &'static str,
+    #[serde(flatten)]
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ViolationKind {
+    OverLimit {
+        actual: u64,
+        limit: u64,
+    },
+    /// A bound was set but the run did not report the metric.
+    Missing,
+    Mismatch {
+        actual: String,
+        expected: String,
+    },
+    Trapped {
+        trap: String,
+    },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let metric = self.metric;
+        match &self.kind {
+            ViolationKind::OverLimit { actual, limit } => {
+                write!(f, "{metric}: {actual} exceeds limit {limit}")
+            }
+            ViolationKind::Missing => {
+                write!(f, "{metric}: not reported by the run, cannot check bound")
+            }
+            ViolationKind::Mismatch { actual, expected } => {
+                write!(f, "{metric}: got {actual:?}, expected {expected:?}")
+            }
+            ViolationKind::Trapped { trap } => write!(f, "{metric}: run trapped: {trap}"),
+        }
+    }
+}
+
+/// Returns every violated bound in `spec`, in field order; empty means pass.
+pub fn check(result: &RunnerResult, spec: &MetricAssertions) -> Vec<Violation> {
+    let mut out = Vec::new();
+    let bounds = [
+        ("fuel_used", result.fuel_used, spec.max_fuel),
+        ("heap_used", result.heap_used, spec.max_heap),
+        (
+            "fs_read_file_calls",
+            result.fs_read_file_calls,
+            spec.max_fs_read_file_calls,
+        ),
+        (
+            "rr_next_calls",
+            result.rr_next_calls,
+            spec.max_rr_request_calls,
+        ),
+        ("kv_set_calls", result.kv_set_calls, spec.max_kv_set_calls),
+    ];
+    for (metric, actual, limit) in bounds {
+        let Some(limit) = limit else {
+            continue;
+        };
+        match actual {
+            None => out.push(Violation {
+                metric,
+                kind: ViolationKind::Missing,
+            }),
+            Some(actual) if actual > limit => out.push(Violation {
+                metric,
+                kind: ViolationKind::OverLimit { actual, limit },
+            }),
+            Some(_) => {}
+        }
+    }
+
+    if let Some(expected) = &spec.sched_trace_hash {
+        let metric = "sched_trace_hash";
+        match &result.sched_stats {
+            None => out.push(Violation {
+                metric,
+                kind: ViolationKind::Missing,
+            }),
+            Some(stats) if &stats.sched_trace_hash != expected => out.push(Violation {
+                metric,
+                kind: ViolationKind::Mismatch {
+                    actual: stats.sched_trace_hash.clone(),
+                    expected: expected.clone(),
+                },
+            }),
+            Some(_) => {}
+        }
+    }
+
+    if spec.forbid_traps {
+        if let Some(trap) = &result.trap {
+            out.push(Violation {
+                metric: "trap",
+                kind: ViolationKind::Trapped { trap: trap.clone() },
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchedStats;
+
+    fn result() -> RunnerResult {
+        RunnerResult {
+            ok: true,
+            exit_status: 0,
+            solve_output: Vec::new(),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            fuel_used: Some(1_000),
+            heap_used: Some(4_096),
+            fs_read_file_calls: Some(2),
+            fs_list_dir_calls: Some(0),
+            rr_open_calls: Some(1),
+            rr_close_calls: Some(1),
+            rr_stats_calls: Some(0),
+            rr_next_calls: Some(3),
+            rr_next_miss_calls: Some(0),
+            rr_append_calls: Some(0),
+            kv_get_calls: Some(0),
+            kv_set_calls: Some(1),
+            sched_stats: Some(SchedStats {
+                sched_trace_hash: "0xabc".to_string(),
+                ..SchedStats::default()
+            }),
+            mem_stats: None,
+            debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
+            trap: None,
+            run_dir_bytes: None,
+            env_fingerprint: String::new(),
+            fixture_digest: None,
+            fixture_overlays: Vec::new(),
+            sandbox_degradations: Vec::new(),
+            sandbox_caps: crate::SandboxCaps::default(),
+            solve_seed: None,
+            staging_error: None,
+            output_contract_violation: None,
+        }
+    }
+
+    fn at_limits() -> MetricAssertions {
+        MetricAssertions {
+            max_fuel: Some(1_000),
+            max_heap: Some(4_096),
+            max_fs_read_file_calls: Some(2),
+            max_rr_request_calls: Some(3),
+            max_kv_set_calls: Some(1),
+            sched_trace_hash: Some("0xabc".to_string()),
+            forbid_traps: true,
+        }
+    }
+
+    #[test]
+    fn all_bounds_at_limit_pass() {
+        assert_eq!(check(&result(), &at_limits()), Vec::new());
+        assert_eq!(check(&result(), &MetricAssertions::default()), Vec::new());
+    }
+
+    #[test]
+    fn each_numeric_bound_reports_actual_and_limit() {
+        let mut res = result();
+        res.fuel_used = Some(1_001);
+        res.heap_used = Some(5_000);
+        res.fs_read_file_calls = Some(3);
+        res.rr_next_calls = Some(4);
+        res.kv_set_calls = Some(2);
+
+        let got = check(&res, &at_limits());
+        let summary: Vec<(&str, ViolationKind)> =
+            got.iter().map(|v| (v.metric, v.kind.clone())).collect();
+        let over = |actual, limit| ViolationKind::OverLimit { actual, limit };
+        assert_eq!(
+            summary,
+            vec![
+                ("fuel_used", over(1_001, 1_000)),
+                ("heap_used", over(5_000, 4_096)),
+                ("fs_read_file_calls", over(3, 2)),
+                ("rr_next_calls", over(4, 3)),
+                ("kv_set_calls", over(2, 1)),
+            ]
+        );
+        assert_eq!(got[0].to_string(), "fuel_used: 1001 exceeds limit 1000");
+    }
+
+    #[test]
+    fn sched_hash_and_traps() {
+        let mut res = result();
+        res.sched_stats.as_mut().unwrap().sched_trace_hash = "0xdef".to_string();
+        res.trap = Some("fuel exhausted".to_string());
+
+        let got = check(&res, &at_limits());
+        assert_eq!(got.len(), 2);
+        assert_eq!(
+            got[0].to_string(),
+            r#"sched_trace_hash: got "0xdef", expected "0xabc""#
+        );
+        assert_eq!(got[1].to_string(), "trap: run trapped: fuel exhausted");
+
+        let lenient = MetricAssertions {
+            forbid_traps: false,
+            ..at_limits()
+        };
+        assert_eq!(check(&res, &lenient).len(), 1);
+    }
+
+    #[test]
+    fn bound_on_missing_metric_is_a_distinct_violation() {
+        let mut res = result();
+        res.fuel_used = None;
+        res.sched_stats = None;
+
+        let got = check(&res, &at_limits());
+        assert_eq!(
+            got,
+            vec![
+                Violation {
+                    metric: "fuel_used",
+                    kind: ViolationKind::Missing,
+                },
+                Violation {
+                    metric: "sched_trace_hash",
+                    kind: ViolationKind::Missing,
+                },
+            ]
+        );
+        assert!(got[0].to_string().contains("not reported"), "{}", got[0]);
+
+        // Unbounded metrics may be missing.
+        assert_eq!(check(&res, &MetricAssertions::default()), Vec::new());
+    }
+
+    #[test]
+    fn from_json_loads_budgets_and_rejects_typos() {
+        let spec = MetricAssertions::from_json(
+            br#"{ "max_fuel": 2000000, "max_rr_request_calls": 3, "forbid_traps": true }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            spec,
+            MetricAssertions {
+                max_fuel: Some(2_000_000),
+                max_rr_request_calls: Some(3),
+                forbid_traps: true,
+                ..MetricAssertions::default()
+            }
+        );
+        assert!(MetricAssertions::from_json(br#"{ "max_fuell": 1 }"#).is_err());
+
+        let v = Violation {
+            metric: "fuel_used",
+            kind: ViolationKind::OverLimit {
+                actual: 2,
+                limit: 1,
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&v).unwrap(),
+            serde_json::json!({
+                "metric": "fuel_used",
+                "reason": "over_limit",
+                "actual": 2,
+                "limit": 1
+            })
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Build manifest written next to a compiled bundle.
+//!
+//! Pins what went into the exe: the native cache entry it came from, the cc
+//! that built it, the `-D` defines, the staged native backends and digests of
+//! the combined C and the exe. Nothing time- or run-dependent is recorded, so
+//! rebuilding the same bundle with the same toolchain yields the same file.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x07_contracts::X07_BUNDLE_MANIFEST_SCHEMA_VERSION;
+
+use crate::{
+    cache_dir, hex_lower, read_native_cache_meta, write_file_atomic, BundleCompileOutput,
+    CompilerResult,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub schema_version: String,
+    pub lang_id: String,
+    pub native_cache_key: String,
+    /// sha256 of the compile options; see [`crate::CompilerResult::options_fingerprint`].
+    pub options_fingerprint: String,
+    pub cc_path: String,
+    /// First line of `cc --version`.
+    pub cc_version: String,
+    /// `-D` flags passed to cc, in argv order.
+    pub defines: Vec<String>,
+    pub native_backends: Vec<BundleManifestBackend>,
+    pub combined_c_sha256: String,
+    pub exe_sha256: String,
+    pub exe_size: u64,
+}
+
+/// A staged backend file linked into the exe, pinned by content rather than
+/// by mtime.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifestBackend {
+    pub backend_id: String,
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// Builds the [`BundleManifest`] for a successful [`crate::compile_bundle_exe`].
+///
+/// cc details come from the native cache entry's meta, i.e. the cc that
+/// actually produced the exe, even when this compile was a cache hit.
+pub fn bundle_manifest(out: &BundleCompileOutput) -> Result<BundleManifest> {
+    manifest_for_compile(&out.compile, &out.combined_c)
+}
+
+/// [`bundle_manifest`] for any successful compile, given the C source cc
+/// built. It is recorded as `combined_c_sha256`.
+pub(crate) fn manifest_for_compile(
+    compile: &CompilerResult,
+    c_source: &str,
+) -> Result<BundleManifest> {
+    if !compile.ok {
+        anyhow::bail!("bundle manifest needs a successful compile");
+    }
+    let key = compile
+        .native_cache_key
+        .as_ref()
+        .context("bundle compile has no native cache key")?;
+    let exe = compile
+        .compiled_exe
+        .as_ref()
+        .context("bundle compile has no exe")?;
+    let meta = read_native_cache_meta(&cache_dir()?.join(key))?;
+
+    let native_backends = compile
+        .native_backend_resolutions
+        .iter()
+        .map(|r| {
+            Ok(BundleManifestBackend {
+                backend_id: r.backend_id.clone(),
+                path: r.path.clone(),
+                sha256: file_sha256(&r.path)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(BundleManifest {
+        schema_version: X07_BUNDLE_MANIFEST_SCHEMA_VERSION.to_string(),
+        lang_id: compile.lang_id.clone(),
+        native_cache_key: key.clone(),
+        options_fingerprint: compile.options_fingerprint.clone(),
+        cc_path: meta.cc,
+        cc_version: meta.cc_version,
+        defines: meta
+            .cc_args
+            .into_iter()
+            .filter(|a| a.starts_with("-D"))
+            .collect(),
+        native_backends,
+        combined_c_sha256: hex_lower(&Sha256::digest(c_source.as_bytes())),
+        exe_sha256: file_sha256(exe)?,
+        exe_size: std::fs::metadata(exe)
+            .with_context(|| format!("stat bundle exe: {}", exe.display()))?
+            .len(),
+    })
+}
+
+/// Writes [`bundle_manifest`] for `out` to `path` as pretty JSON.
+pub fn write_bundle_manifest(out: &BundleCompileOutput, path: &Path) -> Result<()> {
+    write_manifest(&bundle_manifest(out)?, path)
+}
+
+pub(crate) fn write_manifest(manifest: &BundleManifest, path: &Path) -> Result<()> {
+    let mut bytes = serde_json::to_vec_pretty(manifest).context("serialize bundle manifest")?;
+    bytes.push(b'\n');
+    write_file_atomic(path, &bytes)
+        .with_context(|| format!("write bundle manifest: {}", path.display()))
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut f = std::fs::File::open(path)
+        .with_context(|| format!("open for digest: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut f, &mut hasher).with_context(|| format!("digest {}", path.display()))?;
+    Ok(hex_lower(&hasher.finalize()))
+}
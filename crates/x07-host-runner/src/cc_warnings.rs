@@ -0,0 +1,192 @@
+//! Warning lines in C compiler stderr, grouped by the `-W` flag that enabled
+//! them.
+//!
+//! Both gcc (`file.c:1:2: warning: msg [-Wfoo]`) and clang (the same, plus
+//! `clang: warning: msg [-Wfoo]` for driver warnings) end a warning with its
+//! flag in brackets. Lines are decoded lossily, so stray non-UTF-8 bytes in
+//! paths or messages do not hide the warnings around them.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Flag recorded for a warning that names no `-W` option.
+pub const UNFLAGGED_WARNING: &str = "unflagged";
+
+/// `-Wno-*` flags appended after `-Werror` when
+/// [`crate::NativeToolchainConfig::warnings_as_errors`] is set: categories
+/// the generated C is known to trigger harmlessly. All of them are accepted
+/// by both gcc and clang.
+pub const CC_WARNINGS_ALLOWLIST: &[&str] = &[
+    "-Wno-unused-function",
+    "-Wno-unused-variable",
+    "-Wno-unused-but-set-variable",
+    "-Wno-unused-parameter",
+    "-Wno-unused-label",
+];
+
+/// Cap on [`CcWarning::sample_message`].
+const SAMPLE_MAX_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CcWarning {
+    /// E.g. `-Wunused-variable`; [`UNFLAGGED_WARNING`] when none was printed.
+    pub flag: String,
+    pub count: u64,
+    /// Message of the first warning with this flag, location and flag removed.
+    pub sample_message: String,
+}
+
+/// Warnings in `stderr`, one entry per flag, sorted by flag.
+pub fn parse_cc_warnings(stderr: &[u8]) -> Vec<CcWarning> {
+    let mut by_flag: BTreeMap<String, CcWarning> = BTreeMap::new();
+    for raw in stderr.split(|&b| b == b'\n') {
+        let line = strip_ansi(&String::from_utf8_lossy(raw));
+        let Some((flag, message)) = parse_warning_line(line.trim_end()) else {
+            continue;
+        };
+        by_flag
+            .entry(flag.clone())
+            .and_modify(|w| w.count += 1)
+            .or_insert_with(|| CcWarning {
+                flag,
+                count: 1,
+                sample_message: message.chars().take(SAMPLE_MAX_CHARS).collect(),
+            });
+    }
+    by_flag.into_values().collect()
+}
+
+fn parse_warning_line(line: &str) -> Option<(String, &str)> {
+    let rest = if let Some(rest) = line.strip_prefix("warning: ") {
+        rest
+    } else {
+        line.split_once(": warning: ")?.1
+    };
+
+    if let Some(open) = rest.rfind(" [") {
+        if let Some(inner) = rest[open + 2..].strip_suffix(']') {
+            // clang: `[-Wfoo]` or `[-Werror,-Wfoo]`; gcc: `[-Wfoo]` or `[-Wformat=]`.
+            let flag = inner
+                .split(',')
+                .map(str::trim)
+                .find(|f| f.starts_with("-W") && !f.starts_with("-Werror"));
+            if let Some(flag) = flag {
+                let flag = flag.strip_suffix('=').unwrap_or(flag);
+                return Some((flag.to_string(), rest[..open].trim()));
+            }
+        }
+    }
+    Some((UNFLAGGED_WARNING.to_string(), rest.trim()))
+}
+
+/// Drops SGR escapes (`ESC [ ... m`) in case colored diagnostics were forced.
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GCC_STDERR: &[u8] = b"/tmp/x07/solver_1_0.c: In function 'x07_solve':
+/tmp/x07/solver_1_0.c:120:9: warning: unused variable 'tmp' [-Wunused-variable]
+  120 |     int tmp = 0;
+      |         ^~~
+/tmp/x07/solver_1_0.c:140:20: warning: initialization of 'uint8_t *' from incompatible pointer type 'char *' [-Wincompatible-pointer-types]
+  140 |     uint8_t* p = s;
+      |                  ^
+/tmp/x07/solver_1_0.c:150:9: warning: unused variable 'len' [-Wunused-variable]
+/tmp/x07/solver_1_0.c:160:14: warning: format '%d' expects argument of type 'int' [-Wformat=]
+cc1: warning: command-line option '-Wfoo' is valid for C++ but not for C
+/tmp/x07/solver_1_0.c:170:1: note: declared here
+";
+
+    const CLANG_STDERR: &[u8] = b"/tmp/x07/solver_1_0.c:120:9: warning: unused variable 'tmp' [-Wunused-variable]
+  120 |     int tmp = 0;
+      |         ^~~
+/tmp/x07/solver_1_0.c:140:18: warning: incompatible pointer types initializing 'uint8_t *' with an expression of type 'char *' [-Wincompatible-pointer-types]
+/tmp/x07/solver_1_0.c:3:2: warning: x07 test [-W#warnings]
+clang: warning: argument unused during compilation: '-O2' [-Wunused-command-line-argument]
+2 warnings generated.
+";
+
+    fn flags(warnings: &[CcWarning]) -> Vec<(&str, u64)> {
+        warnings
+            .iter()
+            .map(|w| (w.flag.as_str(), w.count))
+            .collect()
+    }
+
+    #[test]
+    fn parses_gcc_warnings() {
+        let warnings = parse_cc_warnings(GCC_STDERR);
+        assert_eq!(
+            flags(&warnings),
+            vec![
+                ("-Wformat", 1),
+                ("-Wincompatible-pointer-types", 1),
+                ("-Wunused-variable", 2),
+                (UNFLAGGED_WARNING, 1),
+            ]
+        );
+        assert_eq!(warnings[2].sample_message, "unused variable 'tmp'");
+        assert_eq!(
+            warnings[3].sample_message,
+            "command-line option '-Wfoo' is valid for C++ but not for C"
+        );
+    }
+
+    #[test]
+    fn parses_clang_warnings() {
+        let warnings = parse_cc_warnings(CLANG_STDERR);
+        assert_eq!(
+            flags(&warnings),
+            vec![
+                ("-W#warnings", 1),
+                ("-Wincompatible-pointer-types", 1),
+                ("-Wunused-command-line-argument", 1),
+                ("-Wunused-variable", 1),
+            ]
+        );
+        assert_eq!(
+            warnings[2].sample_message,
+            "argument unused during compilation: '-O2'"
+        );
+    }
+
+    #[test]
+    fn tolerates_non_utf8_and_color() {
+        let mut stderr = b"/tmp/\xff\xfe/a.c:1:1: warning: bad \xc3 byte [-Wcomment]\n".to_vec();
+        stderr.extend_from_slice(
+            b"\x1b[01m\x1b[Ka.c:2:3:\x1b[m\x1b[K \x1b[01;35m\x1b[Kwarning: \x1b[m\x1b[Kunused variable 'x' [\x1b[01;35m\x1b[K-Wunused-variable\x1b[m\x1b[K]\n",
+        );
+        stderr.extend_from_slice(b"a.c:4:1: error: not a warning [-Werror,-Wcomment]\n");
+        let warnings = parse_cc_warnings(&stderr);
+        assert_eq!(
+            flags(&warnings),
+            vec![("-Wcomment", 1), ("-Wunused-variable", 1)]
+        );
+        assert_eq!(warnings[0].sample_message, "bad \u{fffd} byte");
+        assert_eq!(warnings[1].sample_message, "unused variable 'x'");
+    }
+
+    #[test]
+    fn no_warnings_in_clean_or_empty_output() {
+        assert!(parse_cc_warnings(b"").is_empty());
+        assert!(parse_cc_warnings(b"ld: note: something\n").is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! Self-contained compile archives for reproducible reporting.
+//!
+//! [`archive_compile`] compiles a program and writes everything needed to
+//! account for the exe later into one directory: the generated C, the exe, a
+//! report of the compile and the [`crate::BundleManifest`] pinning the
+//! toolchain. Nothing in the report is time-dependent, so archiving the same
+//! program twice with the same toolchain yields identical files.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use x07c::compile;
+
+use crate::bundle_manifest::{manifest_for_compile, write_manifest};
+use crate::{
+    compile_program_and_c, hex_lower, no_progress, place_compiled_exe, write_file_atomic,
+    CompilerResult, RunnerConfig,
+};
+
+pub const COMPILE_ARCHIVE_REPORT_SCHEMA_VERSION: &str = "x07.compile_archive.report@0.1.0";
+
+pub const ARCHIVE_C_FILE: &str = "solver.c";
+pub const ARCHIVE_EXE_FILE: &str = "solver";
+pub const ARCHIVE_REPORT_FILE: &str = "report.json";
+pub const ARCHIVE_MANIFEST_FILE: &str = "manifest.json";
+
+/// `report.json` of a compile archive.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompileArchiveReport {
+    pub schema_version: String,
+    pub ok: bool,
+    pub exit_status: i32,
+    pub lang_id: String,
+    pub options_fingerprint: String,
+    pub native_cache_key: Option<String>,
+    pub compile_error: Option<String>,
+    pub diagnostics: Vec<x07c::diagnostics::Diagnostic>,
+    pub fuel_used: Option<u64>,
+    pub c_source_size: usize,
+    /// sha256 of [`ARCHIVE_C_FILE`]; `None` when codegen failed and no C
+    /// was written.
+    pub c_sha256: Option<String>,
+    /// sha256 of [`ARCHIVE_EXE_FILE`]; `None` when the compile failed.
+    pub exe_sha256: Option<String>,
+    pub exe_size: Option<u64>,
+}
+
+/// Compiles `program` and writes the archive into `out_dir`, creating it:
+/// [`ARCHIVE_C_FILE`], [`ARCHIVE_EXE_FILE`], [`ARCHIVE_REPORT_FILE`] and
+/// [`ARCHIVE_MANIFEST_FILE`]. The returned result's `compiled_exe` points
+/// into `out_dir`.
+///
+/// A failed compile is archived too. The report records why it failed, and
+/// the C is kept when codegen succeeded. There is no exe or manifest, and the
+/// failed result is returned as `Ok`, as [`crate::compile_program`] does.
+///
+/// Build-dependent details stay out of the report: cc warnings, which are
+/// only seen on a native cache miss, and codegen timing.
+pub fn archive_compile(
+    program: &[u8],
+    config: &RunnerConfig,
+    options: &compile::CompileOptions,
+    out_dir: &Path,
+) -> Result<CompilerResult> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("create archive dir: {}", out_dir.display()))?;
+    let (mut compile, c_source) =
+        compile_program_and_c(program, config, None, options, &[], &no_progress)?;
+
+    if let Some(c_source) = &c_source {
+        let path = out_dir.join(ARCHIVE_C_FILE);
+        write_file_atomic(&path, c_source.as_bytes())
+            .with_context(|| format!("write archived C: {}", path.display()))?;
+    }
+
+    let mut exe_sha256 = None;
+    if let (Some(cached_exe), Some(c_source)) = (compile.compiled_exe.take(), &c_source) {
+        let exe = place_compiled_exe(cached_exe, Some(&out_dir.join(ARCHIVE_EXE_FILE)))?;
+        compile.compiled_exe_size = std::fs::metadata(&exe).map(|m| m.len()).ok();
+        compile.compiled_exe = Some(exe);
+        let manifest = manifest_for_compile(&compile, c_source)?;
+        exe_sha256 = Some(manifest.exe_sha256.clone());
+        write_manifest(&manifest, &out_dir.join(ARCHIVE_MANIFEST_FILE))?;
+    }
+
+    let report = CompileArchiveReport {
+        schema_version: COMPILE_ARCHIVE_REPORT_SCHEMA_VERSION.to_string(),
+        ok: compile.ok,
+        exit_status: compile.exit_status,
+        lang_id: compile.lang_id.clone(),
+        options_fingerprint: compile.options_fingerprint.clone(),
+        native_cache_key: compile.native_cache_key.clone(),
+        compile_error: compile.compile_error.clone(),
+        diagnostics: compile.compile_diagnostics.clone(),
+        fuel_used: compile.fuel_used,
+        c_source_size: compile.c_source_size,
+        c_sha256: c_source
+            .as_ref()
+            .map(|c| hex_lower(&Sha256::digest(c.as_bytes()))),
+        exe_sha256,
+        exe_size: compile.compiled_exe_size,
+    };
+    let mut bytes = serde_json::to_vec_pretty(&report).context("serialize archive report")?;
+    bytes.push(b'\n');
+    let path = out_dir.join(ARCHIVE_REPORT_FILE);
+    write_file_atomic(&path, &bytes)
+        .with_context(|| format!("write archive report: {}", path.display()))?;
+
+    Ok(compile)
+}
@@ -0,0 +1,169 @@
+//! Program-bytes-keyed memo in front of the native cache.
+//!
+//! The native cache is keyed on the emitted C, so every lookup still pays for
+//! x07-to-C codegen. This memo maps the x07 program bytes plus everything that
+//! shapes the exe straight to the cached `solver`, skipping codegen on a hit.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x07c::language;
+use x07c::native::NativeRequires;
+use x07c::options_fingerprint::CompileOptionsFingerprint;
+
+use crate::native_backends::{file_mtime_unix_ms, plan_native_link_argv};
+use crate::{
+    cache_dir, compile_options_for_config, compile_program_impl, hash_cc_toolchain, hex_lower,
+    no_progress, place_compiled_exe, workspace_root, CompilerResult, NativeBackendResolution,
+    RunnerConfig,
+};
+
+pub const COMPILE_MEMO_SCHEMA_VERSION: &str = "x07.compile_memo@0.2.0";
+
+const COMPILE_MEMO_DIR: &str = "memo";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MemoEntry {
+    schema_version: String,
+    /// The native cache `solver` the program compiled to.
+    exe_path: PathBuf,
+    c_source_size: usize,
+    native_requires: NativeRequires,
+    native_backend_resolutions: Vec<NativeBackendResolution>,
+    /// sha256 of the [`crate::NativeLinkPlan::cache_salt`] the exe was built
+    /// with, so a native backend manifest edit drops the hit.
+    native_cache_salt_sha256: String,
+    native_cache_key: Option<String>,
+    fuel_used: Option<u64>,
+}
+
+/// Like [`crate::compile_program`], but an earlier successful compile of the
+/// same program bytes under the same options, runner limits and C toolchain
+/// reuses its exe without running codegen (`memo_hit`, `codegen_ms == 0`).
+///
+/// A hit is dropped when the cached exe is gone, a linked native backend
+/// file changed mtime since it was recorded, or the manifest entry of a
+/// required backend changed.
+pub fn compile_program_memoized(
+    program: &[u8],
+    config: &RunnerConfig,
+    compiled_out: Option<&Path>,
+) -> Result<CompilerResult> {
+    let compile_options = compile_options_for_config(config)?;
+    let options_fingerprint = CompileOptionsFingerprint::compute(&compile_options)
+        .context("fingerprint compile options")?
+        .to_string();
+    let memo_dir = cache_dir()?.join(COMPILE_MEMO_DIR);
+    let memo_path = memo_dir.join(format!(
+        "{}.json",
+        memo_key(program, config, &options_fingerprint)?
+    ));
+
+    if let Some(entry) = read_fresh_entry(&memo_path) {
+        let exe = place_compiled_exe(entry.exe_path, compiled_out)?;
+        let exe_size = std::fs::metadata(&exe).map(|m| m.len()).ok();
+        return Ok(CompilerResult {
+            ok: true,
+            exit_status: 0,
+            lang_id: language::LANG_ID.to_string(),
+            native_requires: entry.native_requires,
+            c_source_size: entry.c_source_size,
+            codegen_ms: 0,
+            memo_hit: true,
+            compiled_exe: Some(exe),
+            compiled_exe_size: exe_size,
+            compile_error: None,
+            compile_diagnostics: Vec::new(),
+            options_fingerprint,
+            native_backend_resolutions: entry.native_backend_resolutions,
+            cc_warnings: Vec::new(),
+            native_cache_key: entry.native_cache_key,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            fuel_used: entry.fuel_used,
+            trap: None,
+        });
+    }
+
+    let mut compile =
+        compile_program_impl(program, config, None, &compile_options, &[], &no_progress)?;
+    let Some(cached_exe) = compile.compiled_exe.take() else {
+        return Ok(compile);
+    };
+    let salt_sha256 = native_cache_salt_sha256(&compile.native_requires);
+    let entry = salt_sha256.map(|native_cache_salt_sha256| MemoEntry {
+        schema_version: COMPILE_MEMO_SCHEMA_VERSION.to_string(),
+        exe_path: cached_exe.clone(),
+        c_source_size: compile.c_source_size,
+        native_requires: compile.native_requires.clone(),
+        native_backend_resolutions: compile.native_backend_resolutions.clone(),
+        native_cache_salt_sha256,
+        native_cache_key: compile.native_cache_key.clone(),
+        fuel_used: compile.fuel_used,
+    });
+    // Best effort: a memo that fails to persist just means codegen next time.
+    if let Some(entry) = &entry {
+        let _ = write_entry(&memo_dir, &memo_path, entry);
+    }
+
+    let exe = place_compiled_exe(cached_exe, compiled_out)?;
+    compile.compiled_exe_size = std::fs::metadata(&exe).map(|m| m.len()).ok();
+    compile.compiled_exe = Some(exe);
+    Ok(compile)
+}
+
+fn memo_key(program: &[u8], config: &RunnerConfig, options_fingerprint: &str) -> Result<String> {
+    let mut h = Sha256::new();
+    h.update(b"x07-compile-memo-v1\0");
+    h.update(env!("CARGO_PKG_VERSION").as_bytes());
+    h.update(b"\0");
+    h.update(Sha256::digest(program));
+    h.update(options_fingerprint.as_bytes());
+    h.update(b"\0");
+    h.update(config.solve_fuel.to_le_bytes());
+    h.update((config.max_memory_bytes as u64).to_le_bytes());
+    h.update([config.debug_borrow_checks as u8]);
+    for s in &config.sanitizers {
+        h.update(s.as_str().as_bytes());
+        h.update(b"\0");
+    }
+    // Same toolchain inputs as the native cache key: a compiler upgrade or an
+    // edited `X07_CC_ARGS` file must not resolve to an exe built before it.
+    hash_cc_toolchain(&mut h)?;
+    Ok(hex_lower(&h.finalize()))
+}
+
+fn read_fresh_entry(path: &Path) -> Option<MemoEntry> {
+    let bytes = std::fs::read(path).ok()?;
+    let entry: MemoEntry = serde_json::from_slice(&bytes).ok()?;
+    if entry.schema_version != COMPILE_MEMO_SCHEMA_VERSION || !entry.exe_path.is_file() {
+        return None;
+    }
+    let backends_unchanged = entry
+        .native_backend_resolutions
+        .iter()
+        .all(|r| file_mtime_unix_ms(&r.path) == r.mtime_unix_ms);
+    let salt_unchanged = native_cache_salt_sha256(&entry.native_requires).as_deref()
+        == Some(entry.native_cache_salt_sha256.as_str());
+    (backends_unchanged && salt_unchanged).then_some(entry)
+}
+
+/// `None` when the link plan cannot be made; such a compile is not memoized.
+fn native_cache_salt_sha256(requires: &NativeRequires) -> Option<String> {
+    if requires.requires.is_empty() {
+        return Some(String::new());
+    }
+    let root = workspace_root().ok()?;
+    let plan = plan_native_link_argv(&root, requires).ok()?;
+    Some(hex_lower(&Sha256::digest(&plan.cache_salt)))
+}
+
+fn write_entry(memo_dir: &Path, memo_path: &Path, entry: &MemoEntry) -> Result<()> {
+    std::fs::create_dir_all(memo_dir)
+        .with_context(|| format!("create compile memo dir: {}", memo_dir.display()))?;
+    let bytes = serde_json::to_vec_pretty(entry).context("serialize compile memo entry")?;
+    crate::write_file_atomic(memo_path, &bytes)
+        .with_context(|| format!("write compile memo entry: {}", memo_path.display()))
+}
@@ -0,0 +1,214 @@
+//! Repeated-run determinism checks.
+//!
+//! Deterministic worlds must give the same `solve_output`, fuel, heap and
+//! scheduler trace for the same program and input. [`check`] compiles once,
+//! runs the exe several times and reports the first field that differs from
+//! run 0 as a [`Divergence`]. The children always run in their own run dir,
+//! so the host's cwd never reaches them; [`DeterminismOptions`] can instead
+//! give every run a different run dir token.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::golden::{self, GoldenDiff, GoldenFormat};
+use crate::{compile_program, hex_lower, run_artifact_file, RunnerConfig, RunnerResult};
+
+pub const DETERMINISM_REPORT_SCHEMA_VERSION: &str = "x07.host_runner.determinism@0.1.0";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeterminismOptions {
+    /// Give each run its own [`RunnerConfig::stable_run_dir_token`], which
+    /// changes the run dir path and `X07_RUN_DIR_TOKEN` between runs.
+    pub vary_run_dir_token: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeterminismField {
+    SolveOutput,
+    FuelUsed,
+    HeapUsed,
+    SchedTraceHash,
+}
+
+/// What one run produced, as compared across runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunObservation {
+    pub ok: bool,
+    pub exit_status: i32,
+    pub solve_output_len: usize,
+    pub solve_output_sha256: String,
+    pub fuel_used: Option<u64>,
+    pub heap_used: Option<u64>,
+    pub sched_trace_hash: Option<String>,
+    pub trap: Option<String>,
+}
+
+/// The first field in which `run` differs from run 0. Values are rendered
+/// as strings; `solve_output` as its sha256, with `output_diff` locating the
+/// first differing byte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub field: DeterminismField,
+    pub run: usize,
+    pub baseline: Option<String>,
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_diff: Option<GoldenDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeterminismReport {
+    pub schema_version: String,
+    pub runs: usize,
+    pub deterministic: bool,
+    pub observations: Vec<RunObservation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub divergence: Option<Divergence>,
+}
+
+/// Compiles `program` once and runs it `runs` times (at least 2) on `input`.
+/// Fails if the compile fails or a run cannot be started.
+pub fn check(
+    program: &[u8],
+    config: &RunnerConfig,
+    input: &[u8],
+    runs: usize,
+) -> Result<DeterminismReport> {
+    check_with_options(program, config, input, runs, DeterminismOptions::default())
+}
+
+pub fn check_with_options(
+    program: &[u8],
+    config: &RunnerConfig,
+    input: &[u8],
+    runs: usize,
+    options: DeterminismOptions,
+) -> Result<DeterminismReport> {
+    let compile = compile_program(program, config, None)?;
+    let Some(exe) = compile.compiled_exe.filter(|_| compile.ok) else {
+        anyhow::bail!(
+            "compile failed: {}",
+            compile.compile_error.as_deref().unwrap_or("unknown error")
+        );
+    };
+    check_artifact(config, &exe, input, runs, options)
+}
+
+/// [`check`] for an exe that is already built, e.g. by [`crate::compile_c_and_run`].
+pub fn check_artifact(
+    config: &RunnerConfig,
+    exe: &Path,
+    input: &[u8],
+    runs: usize,
+    options: DeterminismOptions,
+) -> Result<DeterminismReport> {
+    if runs < 2 {
+        anyhow::bail!("a determinism check needs at least 2 runs (got {runs})");
+    }
+
+    let token_base = format!(
+        "det-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0)
+    );
+    let mut baseline: Option<RunnerResult> = None;
+    let mut observations = Vec::with_capacity(runs);
+    let mut divergence = None;
+    for run in 0..runs {
+        let result = if options.vary_run_dir_token {
+            let mut cfg = config.clone();
+            cfg.stable_run_dir_token = Some(format!("{token_base}-{run}"));
+            run_artifact_file(&cfg, exe, input)?
+        } else {
+            run_artifact_file(config, exe, input)?
+        };
+        observations.push(observe(&result));
+        match &baseline {
+            None => baseline = Some(result),
+            Some(base) if divergence.is_none() => {
+                divergence = diverge(base, &observations[0], &result, &observations[run], run)?;
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(DeterminismReport {
+        schema_version: DETERMINISM_REPORT_SCHEMA_VERSION.to_string(),
+        runs,
+        deterministic: divergence.is_none(),
+        observations,
+        divergence,
+    })
+}
+
+fn observe(result: &RunnerResult) -> RunObservation {
+    RunObservation {
+        ok: result.ok,
+        exit_status: result.exit_status,
+        solve_output_len: result.solve_output.len(),
+        solve_output_sha256: hex_lower(&Sha256::digest(&result.solve_output)),
+        fuel_used: result.fuel_used,
+        heap_used: result.heap_used,
+        sched_trace_hash: result
+            .sched_stats
+            .as_ref()
+            .map(|s| s.sched_trace_hash.clone()),
+        trap: result.trap.clone(),
+    }
+}
+
+fn diverge(
+    base: &RunnerResult,
+    base_obs: &RunObservation,
+    result: &RunnerResult,
+    obs: &RunObservation,
+    run: usize,
+) -> Result<Option<Divergence>> {
+    let divergence = |field, baseline: Option<String>, value: Option<String>| Divergence {
+        field,
+        run,
+        baseline,
+        value,
+        output_diff: None,
+    };
+    let num = |v: Option<u64>| v.map(|v| v.to_string());
+
+    if base.solve_output != result.solve_output {
+        let mut d = divergence(
+            DeterminismField::SolveOutput,
+            Some(base_obs.solve_output_sha256.clone()),
+            Some(obs.solve_output_sha256.clone()),
+        );
+        d.output_diff = golden::compare(result, &base.solve_output, GoldenFormat::Bytes)?;
+        return Ok(Some(d));
+    }
+    if base_obs.fuel_used != obs.fuel_used {
+        return Ok(Some(divergence(
+            DeterminismField::FuelUsed,
+            num(base_obs.fuel_used),
+            num(obs.fuel_used),
+        )));
+    }
+    if base_obs.heap_used != obs.heap_used {
+        return Ok(Some(divergence(
+            DeterminismField::HeapUsed,
+            num(base_obs.heap_used),
+            num(obs.heap_used),
+        )));
+    }
+    if base_obs.sched_trace_hash != obs.sched_trace_hash {
+        return Ok(Some(divergence(
+            DeterminismField::SchedTraceHash,
+            base_obs.sched_trace_hash.clone(),
+            obs.sched_trace_hash.clone(),
+        )));
+    }
+    Ok(None)
+}
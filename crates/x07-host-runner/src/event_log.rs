@@ -0,0 +1,374 @@
+//! JSON-lines event log for long batches of runs.
+//!
+//! With [`crate::RunnerConfig::event_log`] set, every
+//! [`crate::compile_and_run`]-family call appends one JSON object per line:
+//! a `phase` event for each [`RunPhase`] it reaches, then one `summary` event
+//! for the whole call. Each line carries [`EVENT_LOG_SCHEMA_VERSION`] and a
+//! `run` id unique across processes, so lines from concurrent runs and
+//! concurrent runner processes can share one file and still be grouped.
+//!
+//! Files are opened with `O_APPEND` and every line goes out in a single
+//! write of at most [`EVENT_LOG_MAX_LINE_BYTES`], so lines from different
+//! processes never interleave. Long string fields are cut down and listed
+//! under `elided`. Logging is best effort: a failed write never fails a run.
+//!
+//! [`aggregate_event_log`] folds a log back into per-program statistics.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{hex_lower, CompileAndRunResult, RunPhase};
+
+pub const EVENT_LOG_SCHEMA_VERSION: &str = "x07.host_runner.event@0.1.0";
+
+/// Hard cap on one line, trailing newline included.
+pub const EVENT_LOG_MAX_LINE_BYTES: usize = 4096;
+
+/// String fields longer than this are truncated and listed under `elided`.
+const MAX_FIELD_BYTES: usize = 512;
+
+/// Keys every line keeps, even when everything else has to go.
+const CORE_KEYS: &[&str] = &["schema_version", "run", "ts_unix_ms", "event", "phase"];
+
+static NEXT_RUN: AtomicU64 = AtomicU64::new(0);
+
+/// Where [`crate::RunnerConfig::event_log`] writes; clones share the sink.
+#[derive(Clone)]
+pub struct EventLog(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl EventLog {
+    /// Appends to `path`, creating it if needed. Several runner processes
+    /// may open the same file.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open event log: {}", path.display()))?;
+        Ok(Self::from_writer(file))
+    }
+
+    /// Writes lines to `writer`, one `write_all` per line.
+    pub fn from_writer(writer: impl Write + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(writer))))
+    }
+
+    fn emit(&self, fields: Map<String, Value>) {
+        let line = encode_line(fields);
+        if let Ok(mut w) = self.0.lock() {
+            let _ = w.write_all(&line);
+        }
+    }
+
+    pub(crate) fn begin_run(&self) -> EventRun {
+        EventRun {
+            log: self.clone(),
+            run: format!(
+                "{}-{}",
+                std::process::id(),
+                NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+            ),
+            cache_hit: Mutex::new(None),
+        }
+    }
+}
+
+impl std::fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventLog(..)")
+    }
+}
+
+/// One call's worth of events.
+pub(crate) struct EventRun {
+    log: EventLog,
+    run: String,
+    cache_hit: Mutex<Option<bool>>,
+}
+
+impl EventRun {
+    fn emit(&self, event: &str, mut fields: Map<String, Value>) {
+        let ts_unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        fields.insert("schema_version".into(), json!(EVENT_LOG_SCHEMA_VERSION));
+        fields.insert("run".into(), json!(self.run));
+        fields.insert("ts_unix_ms".into(), json!(ts_unix_ms));
+        fields.insert("event".into(), json!(event));
+        self.log.emit(fields);
+    }
+
+    pub(crate) fn phase(&self, phase: &RunPhase) {
+        let mut fields = Map::new();
+        let name = match phase {
+            RunPhase::CompileStarted => "compile_started",
+            RunPhase::CSourceReady { bytes } => {
+                fields.insert("bytes".into(), json!(bytes));
+                "c_source_ready"
+            }
+            RunPhase::NativeLinkPlanned { backends } => {
+                fields.insert("backends".into(), json!(backends));
+                "native_link_planned"
+            }
+            RunPhase::CcStarted => "cc_started",
+            RunPhase::CcFinished { cache_hit } => {
+                if let Ok(mut slot) = self.cache_hit.lock() {
+                    *slot = Some(*cache_hit);
+                }
+                fields.insert("cache_hit".into(), json!(cache_hit));
+                "cc_finished"
+            }
+            RunPhase::FixturesStaged => "fixtures_staged",
+            RunPhase::ChildSpawned { pid } => {
+                fields.insert("pid".into(), json!(pid));
+                "child_spawned"
+            }
+            RunPhase::ChildExited { status } => {
+                fields.insert("status".into(), json!(status));
+                "child_exited"
+            }
+            RunPhase::Done => "done",
+        };
+        fields.insert("phase".into(), json!(name));
+        self.emit("phase", fields);
+    }
+
+    pub(crate) fn summary(&self, program: &[u8], res: &Result<CompileAndRunResult>) {
+        let mut fields = Map::new();
+        fields.insert(
+            "program_sha256".into(),
+            json!(hex_lower(&Sha256::digest(program))),
+        );
+        fields.insert(
+            "cache_hit".into(),
+            json!(self.cache_hit.lock().ok().and_then(|c| *c)),
+        );
+        match res {
+            Ok(r) => {
+                fields.insert("compile_ok".into(), json!(r.compile.ok));
+                fields.insert("cache_key".into(), json!(r.compile.native_cache_key));
+                fields.insert("compile_error".into(), json!(r.compile.compile_error));
+                let solve = r.solve.as_ref();
+                fields.insert("ok".into(), json!(solve.is_some_and(|s| s.ok)));
+                fields.insert("exit_status".into(), json!(solve.map(|s| s.exit_status)));
+                fields.insert("fuel_used".into(), json!(solve.and_then(|s| s.fuel_used)));
+                let trap = solve.and_then(|s| s.trap.as_deref());
+                fields.insert("trap".into(), json!(trap));
+                fields.insert("trap_kind".into(), json!(trap.map(trap_kind)));
+            }
+            Err(err) => {
+                fields.insert("ok".into(), json!(false));
+                fields.insert("error".into(), json!(format!("{err:#}")));
+            }
+        }
+        self.emit("summary", fields);
+    }
+}
+
+/// Short, stable class of a trap message: the text before its first `:`
+/// (so `"fuel exhausted: ..."` and `"fuel exhausted"` group together).
+pub fn trap_kind(trap: &str) -> &str {
+    trap.split(':').next().unwrap_or(trap).trim()
+}
+
+fn encode_line(mut fields: Map<String, Value>) -> Vec<u8> {
+    let mut elided = Vec::new();
+    for (key, value) in fields.iter_mut() {
+        if let Value::String(s) = value {
+            if s.len() > MAX_FIELD_BYTES {
+                let mut cut = MAX_FIELD_BYTES;
+                while !s.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                s.truncate(cut);
+                elided.push(key.clone());
+            }
+        }
+    }
+    let line = finish_line(&mut fields, &mut elided);
+    if line.len() <= EVENT_LOG_MAX_LINE_BYTES {
+        return line;
+    }
+
+    // Still too long: drop every non-core string and array outright.
+    for (key, value) in fields.iter_mut() {
+        if !CORE_KEYS.contains(&key.as_str()) && (value.is_string() || value.is_array()) {
+            *value = Value::Null;
+            if !elided.contains(key) {
+                elided.push(key.clone());
+            }
+        }
+    }
+    finish_line(&mut fields, &mut elided)
+}
+
+fn finish_line(fields: &mut Map<String, Value>, elided: &mut Vec<String>) -> Vec<u8> {
+    elided.sort();
+    if !elided.is_empty() {
+        fields.insert("elided".into(), json!(elided));
+    }
+    let mut line = serde_json::to_vec(&fields).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
+
+/// Per-program numbers from [`aggregate_event_log`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProgramStats {
+    pub runs: u64,
+    pub ok: u64,
+    pub compile_failures: u64,
+    /// Runs that failed before producing a result (`error` set).
+    pub errors: u64,
+    pub cache_hits: u64,
+    /// Count per [`trap_kind`].
+    pub traps: BTreeMap<String, u64>,
+    pub fuel_used_total: u64,
+    pub fuel_used_max: u64,
+}
+
+/// What [`aggregate_event_log`] found in a log.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EventLogStats {
+    /// Keyed by `program_sha256`.
+    pub programs: BTreeMap<String, ProgramStats>,
+    pub events: u64,
+    /// Lines that were not JSON objects of [`EVENT_LOG_SCHEMA_VERSION`].
+    pub malformed_lines: u64,
+}
+
+/// Folds `summary` events into per-program statistics. Other events are
+/// only counted; blank lines are skipped.
+pub fn aggregate_event_log(reader: impl BufRead) -> Result<EventLogStats> {
+    let mut stats = EventLogStats::default();
+    for line in reader.lines() {
+        let line = line.context("read event log")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Object(event))
+                if event.get("schema_version") == Some(&json!(EVENT_LOG_SCHEMA_VERSION)) =>
+            {
+                event
+            }
+            _ => {
+                stats.malformed_lines += 1;
+                continue;
+            }
+        };
+        stats.events += 1;
+        if event.get("event").and_then(Value::as_str) != Some("summary") {
+            continue;
+        }
+        let Some(program) = event.get("program_sha256").and_then(Value::as_str) else {
+            stats.malformed_lines += 1;
+            continue;
+        };
+        let flag = |key: &str| event.get(key).and_then(Value::as_bool);
+        let p = stats.programs.entry(program.to_string()).or_default();
+        p.runs += 1;
+        if flag("ok") == Some(true) {
+            p.ok += 1;
+        }
+        if flag("compile_ok") == Some(false) {
+            p.compile_failures += 1;
+        }
+        if event.get("error").is_some_and(|e| !e.is_null()) {
+            p.errors += 1;
+        }
+        if flag("cache_hit") == Some(true) {
+            p.cache_hits += 1;
+        }
+        if let Some(kind) = event.get("trap_kind").and_then(Value::as_str) {
+            *p.traps.entry(kind.to_string()).or_default() += 1;
+        }
+        if let Some(fuel) = event.get("fuel_used").and_then(Value::as_u64) {
+            p.fuel_used_total = p.fuel_used_total.saturating_add(fuel);
+            p.fuel_used_max = p.fuel_used_max.max(fuel);
+        }
+    }
+    Ok(stats)
+}
+
+/// [`aggregate_event_log`] over the file at `path`.
+pub fn read_event_log(path: &Path) -> Result<EventLogStats> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("open event log: {}", path.display()))?;
+    aggregate_event_log(std::io::BufReader::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_fields_are_elided_to_fit_one_line() {
+        let mut fields = Map::new();
+        fields.insert("event".into(), json!("summary"));
+        fields.insert("compile_error".into(), json!("é".repeat(2000)));
+        let line = encode_line(fields.clone());
+        assert!(line.len() <= EVENT_LOG_MAX_LINE_BYTES);
+        let v: Value = serde_json::from_slice(&line).unwrap();
+        assert_eq!(v["elided"], json!(["compile_error"]));
+        assert_eq!(v["compile_error"].as_str().unwrap().len(), MAX_FIELD_BYTES);
+
+        for i in 0..20 {
+            fields.insert(format!("f{i:02}"), json!("x".repeat(1000)));
+        }
+        let line = encode_line(fields);
+        assert!(line.len() <= EVENT_LOG_MAX_LINE_BYTES);
+        let v: Value = serde_json::from_slice(&line).unwrap();
+        assert_eq!(v["event"], json!("summary"));
+        assert!(v["f00"].is_null());
+    }
+
+    #[test]
+    fn concurrent_handles_never_interleave_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "x07_host_runner_event_log_unit_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let log = EventLog::open(&path).unwrap();
+                s.spawn(move || {
+                    let run = log.begin_run();
+                    for i in 0..100 {
+                        let mut fields = Map::new();
+                        fields.insert("filler".into(), json!(format!("{t}").repeat(300 + i)));
+                        run.emit("summary", fields);
+                    }
+                });
+            }
+        });
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 800);
+        for line in text.lines() {
+            let v: Value = serde_json::from_str(line).unwrap();
+            let filler = v["filler"].as_str().unwrap();
+            assert!(filler.bytes().all(|b| b == filler.as_bytes()[0]));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn trap_kind_is_the_text_before_the_first_colon() {
+        assert_eq!(trap_kind("fuel exhausted"), "fuel exhausted");
+        assert_eq!(
+            trap_kind("run dir quota exceeded: 70000 > 65536"),
+            "run dir quota exceeded"
+        );
+    }
+}
@@ -0,0 +1,506 @@
+//! Golden-output comparison of `solve_output` against expected bytes.
+//!
+//! [`compare`] returns `None` on a match, or a [`GoldenDiff`] that locates the
+//! first mismatch and carries a bounded excerpt of both sides, so huge outputs
+//! still produce readable failure messages and small run reports.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::RunnerResult;
+
+/// Set to `1` to let [`update`] rewrite golden files.
+pub const GOLDEN_UPDATE_ENV: &str = "X07_UPDATE_GOLDEN";
+
+/// Bytes shown on each side of the first differing byte in [`GoldenFormat::Bytes`].
+const BYTES_CONTEXT: usize = 16;
+/// Lines shown from the first differing line in [`GoldenFormat::Utf8Lines`].
+const LINES_CONTEXT: usize = 3;
+/// Cap on each rendered line / JSON value in an excerpt.
+const EXCERPT_MAX_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum GoldenFormat {
+    #[default]
+    Bytes,
+    /// Lossy UTF-8, compared line by line (`\n` separated).
+    Utf8Lines,
+    /// Both sides parsed as JSON and compared structurally; key order is ignored.
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GoldenDiff {
+    pub format: GoldenFormat,
+    pub expected_len: usize,
+    pub actual_len: usize,
+    #[serde(flatten)]
+    pub mismatch: Mismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mismatch {
+    /// `offset` is the first differing byte (or the shorter length when one
+    /// side is a prefix of the other); excerpts are lowercase hex starting at
+    /// `excerpt_start`.
+    Bytes {
+        offset: usize,
+        excerpt_start: usize,
+        expected_hex: String,
+        actual_hex: String,
+    },
+    /// `line` is 1-based; excerpts hold up to a few lines from there on.
+    Lines {
+        offset: usize,
+        line: usize,
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    /// `pointer` is an RFC 6901 JSON pointer; `None` means the value is
+    /// absent on that side.
+    Json {
+        pointer: String,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+    /// The actual output is not valid JSON.
+    InvalidJson { error: String },
+}
+
+impl std::fmt::Display for GoldenDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (expected_len, actual_len) = (self.expected_len, self.actual_len);
+        match &self.mismatch {
+            Mismatch::Bytes {
+                offset,
+                excerpt_start,
+                expected_hex,
+                actual_hex,
+            } => write!(
+                f,
+                "output differs at byte {offset} \
+                 (expected {expected_len} bytes, got {actual_len})\n  \
+                 expected[{excerpt_start}..]: {expected_hex}\n  \
+                 actual[{excerpt_start}..]:   {actual_hex}"
+            ),
+            Mismatch::Lines {
+                offset,
+                line,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "output differs at line {line} \
+                     (byte {offset}; expected {expected_len} bytes, got {actual_len})"
+                )?;
+                for l in expected {
+                    write!(f, "\n  - {l}")?;
+                }
+                for l in actual {
+                    write!(f, "\n  + {l}")?;
+                }
+                Ok(())
+            }
+            Mismatch::Json {
+                pointer,
+                expected,
+                actual,
+            } => {
+                let expected = expected.as_deref().unwrap_or("<missing>");
+                let actual = actual.as_deref().unwrap_or("<missing>");
+                write!(
+                    f,
+                    "json differs at {pointer:?}: expected {expected}, got {actual}"
+                )
+            }
+            Mismatch::InvalidJson { error } => {
+                write!(f, "output is not valid json ({actual_len} bytes): {error}")
+            }
+        }
+    }
+}
+
+/// Compares `result.solve_output` against `expected`.
+///
+/// Errors only when `expected` itself cannot be interpreted in `format` (a
+/// broken golden file); a malformed actual output is reported as a diff.
+pub fn compare(
+    result: &RunnerResult,
+    expected: &[u8],
+    format: GoldenFormat,
+) -> Result<Option<GoldenDiff>> {
+    let actual = result.solve_output.as_slice();
+    let mismatch = match format {
+        GoldenFormat::Bytes => compare_bytes(expected, actual),
+        GoldenFormat::Utf8Lines => compare_lines(expected, actual),
+        GoldenFormat::Json => compare_json(expected, actual)?,
+    };
+    Ok(mismatch.map(|mismatch| GoldenDiff {
+        format,
+        expected_len: expected.len(),
+        actual_len: actual.len(),
+        mismatch,
+    }))
+}
+
+/// Writes `result.solve_output` to `path` when [`GOLDEN_UPDATE_ENV`] is `1`.
+/// Returns whether the file was written.
+pub fn update(path: &Path, result: &RunnerResult) -> Result<bool> {
+    if std::env::var(GOLDEN_UPDATE_ENV).as_deref() != Ok("1") {
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("create golden dir: {}", parent.display()))?;
+    }
+    std::fs::write(path, &result.solve_output)
+        .with_context(|| format!("write golden file: {}", path.display()))?;
+    Ok(true)
+}
+
+pub(crate) fn first_diff(expected: &[u8], actual: &[u8]) -> Option<usize> {
+    match expected.iter().zip(actual).position(|(e, a)| e != a) {
+        Some(i) => Some(i),
+        None if expected.len() != actual.len() => Some(expected.len().min(actual.len())),
+        None => None,
+    }
+}
+
+fn compare_bytes(expected: &[u8], actual: &[u8]) -> Option<Mismatch> {
+    let offset = first_diff(expected, actual)?;
+    let excerpt_start = offset.saturating_sub(BYTES_CONTEXT);
+    let window = |b: &[u8]| {
+        let end = b.len().min(offset + BYTES_CONTEXT);
+        crate::hex_lower(b.get(excerpt_start..end).unwrap_or_default())
+    };
+    Some(Mismatch::Bytes {
+        offset,
+        excerpt_start,
+        expected_hex: window(expected),
+        actual_hex: window(actual),
+    })
+}
+
+fn compare_lines(expected: &[u8], actual: &[u8]) -> Option<Mismatch> {
+    let offset = first_diff(expected, actual)?;
+    let line = expected[..offset.min(expected.len())]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count();
+    let excerpt = |b: &[u8]| -> Vec<String> {
+        String::from_utf8_lossy(b)
+            .split('\n')
+            .skip(line)
+            .take(LINES_CONTEXT)
+            .map(truncate)
+            .collect()
+    };
+    Some(Mismatch::Lines {
+        offset,
+        line: line + 1,
+        expected: excerpt(expected),
+        actual: excerpt(actual),
+    })
+}
+
+fn compare_json(expected: &[u8], actual: &[u8]) -> Result<Option<Mismatch>> {
+    let expected: Value = serde_json::from_slice(expected).context("parse expected json")?;
+    let actual: Value = match serde_json::from_slice(actual) {
+        Ok(v) => v,
+        Err(err) => {
+            return Ok(Some(Mismatch::InvalidJson {
+                error: err.to_string(),
+            }));
+        }
+    };
+    let mut pointer = String::new();
+    Ok(
+        json_diff(&mut pointer, Some(&expected), Some(&actual)).map(|(e, a)| Mismatch::Json {
+            pointer,
+            expected: e.map(render_json),
+            actual: a.map(render_json),
+        }),
+    )
+}
+
+/// Depth-first search for the first differing value; objects are visited in
+/// sorted key order, so the result does not depend on key order. On a hit,
+/// `pointer` is left pointing at it.
+fn json_diff<'a>(
+    pointer: &mut String,
+    expected: Option<&'a Value>,
+    actual: Option<&'a Value>,
+) -> Option<(Option<&'a Value>, Option<&'a Value>)> {
+    let len = pointer.len();
+    match (expected, actual) {
+        (Some(Value::Object(e)), Some(Value::Object(a))) => {
+            let mut keys: Vec<&String> = e.keys().chain(a.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                if let Some(hit) = json_diff(pointer, e.get(key), a.get(key)) {
+                    return Some(hit);
+                }
+                pointer.truncate(len);
+            }
+            None
+        }
+        (Some(Value::Array(e)), Some(Value::Array(a))) => {
+            for i in 0..e.len().max(a.len()) {
+                pointer.push('/');
+                pointer.push_str(&i.to_string());
+                if let Some(hit) = json_diff(pointer, e.get(i), a.get(i)) {
+                    return Some(hit);
+                }
+                pointer.truncate(len);
+            }
+            None
+        }
+        (e, a) if e == a => None,
+        (e, a) => Some((e, a)),
+    }
+}
+
+fn render_json(v: &Value) -> String {
+    truncate(&v.to_string())
+}
+
+fn truncate(s: &str) -> String {
+    match s.char_indices().nth(EXCERPT_MAX_CHARS) {
+        Some((i, _)) => format!("{}... ({} bytes total)", &s[..i], s.len()),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(solve_output: &[u8]) -> RunnerResult {
+        RunnerResult {
+            ok: true,
+            exit_status: 0,
+            solve_output: solve_output.to_vec(),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            fuel_used: None,
+            heap_used: None,
+            fs_read_file_calls: None,
+            fs_list_dir_calls: None,
+            rr_open_calls: None,
+            rr_close_calls: None,
+            rr_stats_calls: None,
+            rr_next_calls: None,
+            rr_next_miss_calls: None,
+            rr_append_calls: None,
+            kv_get_calls: None,
+            kv_set_calls: None,
+            sched_stats: None,
+            mem_stats: None,
+            debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
+            trap: None,
+            run_dir_bytes: None,
+            env_fingerprint: String::new(),
+            fixture_digest: None,
+            fixture_overlays: Vec::new(),
+            sandbox_degradations: Vec::new(),
+            sandbox_caps: crate::SandboxCaps::default(),
+            solve_seed: None,
+            staging_error: None,
+            output_contract_violation: None,
+        }
+    }
+
+    #[test]
+    fn bytes_reports_offset_and_hex_window() {
+        let res = result(b"hello world");
+        assert_eq!(
+            compare(&res, b"hello world", GoldenFormat::Bytes).unwrap(),
+            None
+        );
+
+        let diff = compare(&res, b"hello World", GoldenFormat::Bytes)
+            .unwrap()
+            .expect("diff");
+        assert_eq!(diff.expected_len, 11);
+        assert_eq!(diff.actual_len, 11);
+        assert_eq!(
+            diff.mismatch,
+            Mismatch::Bytes {
+                offset: 6,
+                excerpt_start: 0,
+                expected_hex: crate::hex_lower(b"hello World"),
+                actual_hex: crate::hex_lower(b"hello world"),
+            }
+        );
+
+        // A strict prefix mismatches at the shorter length.
+        let diff = compare(&res, b"hello", GoldenFormat::Bytes)
+            .unwrap()
+            .expect("diff");
+        let Mismatch::Bytes { offset, .. } = diff.mismatch else {
+            panic!("{diff:?}");
+        };
+        assert_eq!(offset, 5);
+    }
+
+    #[test]
+    fn large_output_excerpts_are_bounded() {
+        let expected = vec![b'a'; 1 << 20];
+        let mut actual = expected.clone();
+        actual[500_000] = b'b';
+        let res = result(&actual);
+
+        let diff = compare(&res, &expected, GoldenFormat::Bytes)
+            .unwrap()
+            .expect("diff");
+        let Mismatch::Bytes {
+            offset,
+            excerpt_start,
+            expected_hex,
+            actual_hex,
+        } = &diff.mismatch
+        else {
+            panic!("{diff:?}");
+        };
+        assert_eq!(*offset, 500_000);
+        assert_eq!(*excerpt_start, 500_000 - BYTES_CONTEXT);
+        assert_eq!(expected_hex.len(), 4 * BYTES_CONTEXT);
+        assert_eq!(&actual_hex[2 * BYTES_CONTEXT..2 * BYTES_CONTEXT + 2], "62");
+        assert!(diff.to_string().len() < 512);
+
+        // One huge line is truncated rather than copied into the excerpt.
+        let diff = compare(&res, &expected, GoldenFormat::Utf8Lines)
+            .unwrap()
+            .expect("diff");
+        let Mismatch::Lines { line, expected, .. } = &diff.mismatch else {
+            panic!("{diff:?}");
+        };
+        assert_eq!(*line, 1);
+        assert_eq!(expected.len(), 1);
+        assert!(
+            expected[0].ends_with("... (1048576 bytes total)"),
+            "{}",
+            expected[0]
+        );
+        assert!(expected[0].len() < 256);
+    }
+
+    #[test]
+    fn utf8_lines_points_at_first_differing_line() {
+        let res = result(b"alpha\nbeta\ngamma\ndelta\nepsilon\n");
+        let diff = compare(
+            &res,
+            b"alpha\nbeta\nGAMMA\ndelta\nepsilon\n",
+            GoldenFormat::Utf8Lines,
+        )
+        .unwrap()
+        .expect("diff");
+        assert_eq!(
+            diff.mismatch,
+            Mismatch::Lines {
+                offset: 11,
+                line: 3,
+                expected: vec!["GAMMA".into(), "delta".into(), "epsilon".into()],
+                actual: vec!["gamma".into(), "delta".into(), "epsilon".into()],
+            }
+        );
+        assert!(
+            diff.to_string().starts_with("output differs at line 3"),
+            "{diff}"
+        );
+    }
+
+    #[test]
+    fn json_ignores_key_order_and_reports_pointer() {
+        let res = result(br#"{"b":[1,{"x/y":true}],"a":"s"}"#);
+        assert_eq!(
+            compare(
+                &res,
+                br#"{ "a": "s", "b": [1, {"x/y": true}] }"#,
+                GoldenFormat::Json
+            )
+            .unwrap(),
+            None
+        );
+
+        let diff = compare(
+            &res,
+            br#"{"a":"s","b":[1,{"x/y":false}]}"#,
+            GoldenFormat::Json,
+        )
+        .unwrap()
+        .expect("diff");
+        assert_eq!(
+            diff.mismatch,
+            Mismatch::Json {
+                pointer: "/b/1/x~1y".to_string(),
+                expected: Some("false".to_string()),
+                actual: Some("true".to_string()),
+            }
+        );
+
+        let diff = compare(
+            &res,
+            br#"{"a":"s","b":[1,{"x/y":true}],"c":null}"#,
+            GoldenFormat::Json,
+        )
+        .unwrap()
+        .expect("diff");
+        assert_eq!(
+            diff.mismatch,
+            Mismatch::Json {
+                pointer: "/c".to_string(),
+                expected: Some("null".to_string()),
+                actual: None,
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&diff).unwrap()["kind"],
+            serde_json::json!("json")
+        );
+    }
+
+    #[test]
+    fn json_invalid_sides() {
+        let res = result(b"{not json");
+        let diff = compare(&res, b"{}", GoldenFormat::Json)
+            .unwrap()
+            .expect("diff");
+        assert!(
+            matches!(diff.mismatch, Mismatch::InvalidJson { .. }),
+            "{diff:?}"
+        );
+
+        assert!(compare(&result(b"{}"), b"{oops", GoldenFormat::Json).is_err());
+    }
+
+    #[test]
+    fn update_requires_env_opt_in() {
+        let dir = std::env::temp_dir().join(format!("x07_golden_{}", std::process::id()));
+        let path = dir.join("nested").join("out.bin");
+        let res = result(b"blessed");
+
+        std::env::remove_var(GOLDEN_UPDATE_ENV);
+        assert!(!update(&path, &res).unwrap());
+        assert!(!path.exists());
+
+        std::env::set_var(GOLDEN_UPDATE_ENV, "1");
+        let wrote = update(&path, &res);
+        std::env::remove_var(GOLDEN_UPDATE_ENV);
+        assert!(wrote.unwrap());
+        assert_eq!(std::fs::read(&path).unwrap(), b"blessed");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
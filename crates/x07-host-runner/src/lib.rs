@@ -1,9 +1,10 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsStr;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
@@ -18,9 +19,49 @@ use x07c::compile;
 use x07c::language;
 #[cfg(target_os = "linux")]
 use x07c::native::BACKEND_ID_EXT_DB_SQLITE;
-
+use x07c::options_fingerprint::CompileOptionsFingerprint;
+
+pub mod artifacts;
+pub mod assertions;
+mod bundle_manifest;
+mod cc_warnings;
+mod compile_archive;
+mod compile_memo;
+pub mod determinism;
+mod event_log;
+pub mod golden;
 mod native_backends;
-pub use native_backends::plan_native_link_argv;
+mod output_contract;
+mod run_dir_token;
+mod run_slots;
+mod sandbox;
+mod staging;
+pub use bundle_manifest::{
+    bundle_manifest, write_bundle_manifest, BundleManifest, BundleManifestBackend,
+};
+pub use cc_warnings::{parse_cc_warnings, CcWarning, CC_WARNINGS_ALLOWLIST, UNFLAGGED_WARNING};
+pub use compile_archive::{
+    archive_compile, CompileArchiveReport, ARCHIVE_C_FILE, ARCHIVE_EXE_FILE, ARCHIVE_MANIFEST_FILE,
+    ARCHIVE_REPORT_FILE, COMPILE_ARCHIVE_REPORT_SCHEMA_VERSION,
+};
+pub use compile_memo::{compile_program_memoized, COMPILE_MEMO_SCHEMA_VERSION};
+pub use event_log::{
+    aggregate_event_log, read_event_log, trap_kind, EventLog, EventLogStats, ProgramStats,
+    EVENT_LOG_MAX_LINE_BYTES, EVENT_LOG_SCHEMA_VERSION,
+};
+pub use native_backends::{plan_native_link_argv, NativeBackendResolution, NativeLinkPlan};
+pub use output_contract::{
+    OutputContract, OutputContractVerdict, OutputContractViolation, OUTPUT_CONTRACT_TRAP,
+};
+pub use run_dir_token::{
+    validate_run_dir_token, RunDirTokenInUse, ENV_RUN_DIR_TOKEN, RUN_DIR_TOKEN_MAX_LEN,
+};
+pub use run_slots::{
+    current_runs, run_slot_limiter, run_slot_stats, set_max_concurrent_runs,
+    set_run_slot_acquire_timeout, RunSlot, RunSlotError, RunSlotLimiter, RunSlotStats,
+};
+pub use sandbox::{sandbox_capabilities, SandboxCaps, SandboxFeature, SandboxUnavailable};
+pub use staging::{FixtureOverlay, IndexKind, OverlayKind, StagingError, STAGING_FAILED_TRAP};
 
 const EXTERNAL_PACKAGES_LOCK_JSON: &str = include_str!("../../../locks/external-packages.lock");
 
@@ -97,6 +138,131 @@ fn merge_cc_args(existing: &str, flags: &[&str]) -> String {
     out.join(" ")
 }
 
+/// Sanitizer runtimes the native toolchain can build solvers with.
+///
+/// `Address` and `Thread` are mutually exclusive (the compiler rejects
+/// `-fsanitize=address,thread`); configs naming both fail before cc runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+pub enum Sanitizer {
+    Address,
+    Undefined,
+    Thread,
+}
+
+impl Sanitizer {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Undefined => "undefined",
+            Sanitizer::Thread => "thread",
+        }
+    }
+}
+
+/// How the runner treats a sandbox rlimit it cannot apply to the solver child
+/// (the runner's own hard limit is already below it).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+pub enum RlimitMode {
+    /// Fail the run with an error naming the limit.
+    #[default]
+    Enforce,
+    /// Skip the limit, record it in [`RunnerResult::sandbox_degradations`] and run anyway.
+    BestEffort,
+}
+
+fn normalized_sanitizers(sanitizers: &[Sanitizer]) -> Result<Vec<Sanitizer>> {
+    let mut out = sanitizers.to_vec();
+    out.sort();
+    out.dedup();
+    if out.contains(&Sanitizer::Address) && out.contains(&Sanitizer::Thread) {
+        anyhow::bail!("sanitizers address and thread are mutually exclusive");
+    }
+    Ok(out)
+}
+
+/// `-fsanitize=...` value for `sanitizers`, or `None` when no sanitizer is requested.
+pub fn sanitize_cc_flag(sanitizers: &[Sanitizer]) -> Result<Option<String>> {
+    let sanitizers = normalized_sanitizers(sanitizers)?;
+    if sanitizers.is_empty() {
+        return Ok(None);
+    }
+    let names: Vec<&str> = sanitizers.iter().map(|s| s.as_str()).collect();
+    Ok(Some(format!("-fsanitize={}", names.join(","))))
+}
+
+/// Variables set on every solver child after `env_clear()`, so host locale and
+/// timezone cannot leak into a run: `LC_ALL=C`, `TZ=UTC`.
+pub const PINNED_CHILD_ENV: &[(&str, &str)] = &[("LC_ALL", "C"), ("TZ", "UTC")];
+
+/// Env var carrying [`RunnerConfig::solve_seed`] to the solver child, where
+/// `prng.run_seed_u32` reads it. Solvers are built with `-DX07_SEED_ENV`
+/// naming this variable.
+pub const ENV_SOLVE_SEED: &str = "X07_SOLVE_SEED";
+
+/// Env var naming the fd the solver writes its metrics line to. On unix the
+/// runner sets it to [`METRICS_FD`] and passes a dedicated pipe there; the
+/// runtime falls back to stderr when it is unset.
+pub const ENV_METRICS_FD: &str = "X07_METRICS_FD";
+pub const METRICS_FD: i32 = 3;
+
+/// Full environment applied to the solver child, sorted by name.
+fn solver_child_env(config: &RunnerConfig) -> Vec<(String, String)> {
+    let mut env: std::collections::BTreeMap<String, String> = PINNED_CHILD_ENV
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    if let Ok(v) = std::env::var("X07_DEBUG_SCHED") {
+        env.insert("X07_DEBUG_SCHED".to_string(), v);
+    }
+    for (k, v) in sanitizer_runtime_env(&config.sanitizers) {
+        env.insert(k.to_string(), v.to_string());
+    }
+    if let Some(seed) = config.solve_seed {
+        env.insert(ENV_SOLVE_SEED.to_string(), seed.to_string());
+    }
+    if let Some(token) = &config.stable_run_dir_token {
+        env.insert(ENV_RUN_DIR_TOKEN.to_string(), token.clone());
+    }
+    if cfg!(unix) {
+        env.insert(ENV_METRICS_FD.to_string(), METRICS_FD.to_string());
+    }
+    env.into_iter().collect()
+}
+
+/// sha256 over `name=value\n` lines of the (sorted) applied env.
+fn env_fingerprint(env: &[(String, String)]) -> String {
+    let mut hasher = Sha256::new();
+    for (k, v) in env {
+        hasher.update(k.as_bytes());
+        hasher.update(b"=");
+        hasher.update(v.as_bytes());
+        hasher.update(b"\n");
+    }
+    hex_lower(&hasher.finalize())
+}
+
+/// Runtime options for sanitized solvers: fail fast on the first report and
+/// skip leak detection (the solver arena is intentionally never freed).
+fn sanitizer_runtime_env(sanitizers: &[Sanitizer]) -> Vec<(&'static str, &'static str)> {
+    let mut out = Vec::new();
+    for s in sanitizers {
+        let kv = match s {
+            Sanitizer::Address => (
+                "ASAN_OPTIONS",
+                "detect_leaks=0:halt_on_error=1:abort_on_error=0:disable_coredump=1",
+            ),
+            Sanitizer::Undefined => ("UBSAN_OPTIONS", "halt_on_error=1:print_stacktrace=1"),
+            Sanitizer::Thread => ("TSAN_OPTIONS", "halt_on_error=1"),
+        };
+        if !out.contains(&kv) {
+            out.push(kv);
+        }
+    }
+    out
+}
+
 #[derive(Debug, Clone)]
 pub struct RunnerConfig {
     /// Deterministic evaluation worlds only (`solve-*`).
@@ -107,11 +273,135 @@ pub struct RunnerConfig {
     pub fixture_rr_dir: Option<PathBuf>,
     pub fixture_kv_dir: Option<PathBuf>,
     pub fixture_kv_seed: Option<PathBuf>,
+    /// KV seed built in code (`solve-kv` / `solve-full` only). Written to
+    /// `.x07_kv/seed.evkv` in place of `fixture_kv_seed`; `fixture_kv_dir`
+    /// becomes optional.
+    ///
+    /// Entries carry no TTL. The solve-kv runtime never expires keys and
+    /// `seed.evkv` has no field for it, so an inline seed is limited to what
+    /// an `x07.kv.seed@0.1.0` file can express.
+    pub kv_seed_inline: Option<KvSeed>,
     pub solve_fuel: u64,
     pub max_memory_bytes: usize,
     pub max_output_bytes: usize,
     pub cpu_time_limit_seconds: u64,
     pub debug_borrow_checks: bool,
+    /// Build the solver with these sanitizers and set their runtime options on the child.
+    pub sanitizers: Vec<Sanitizer>,
+    /// Extra module search roots used by [`compile_program`]; each must be an existing directory.
+    pub module_roots: Vec<PathBuf>,
+    /// Cap on bytes the solver may add to its run dir (staged fixtures excluded);
+    /// exceeding it traps with `run dir quota exceeded`.
+    pub max_run_dir_bytes: Option<u64>,
+    /// Keep the raw framed stdout in [`RunnerResult::stdout`] after a successful
+    /// run. When false, successful runs keep only the decoded `solve_output`;
+    /// failed runs always keep raw stdout.
+    pub retain_raw_stdout: bool,
+    /// What to do when a sandbox rlimit cannot be applied to the solver child.
+    pub rlimit_mode: RlimitMode,
+    /// Seed exposed to the program as `prng.run_seed_u32`. `None` keeps the
+    /// runtime default of 0; either way the run stays deterministic.
+    pub solve_seed: Option<u64>,
+    /// Extra staging after the built-in fixtures are in place; see [`StageHook`].
+    pub stage_hook: Option<StageHook>,
+    /// Unix only: run the solver with a pseudo-tty as stdout and stderr, so
+    /// programs that check `isatty` take their interactive path. Both streams
+    /// are read merged from the pty master into [`RunnerResult::stdout`],
+    /// still capped by `max_output_bytes`. The tty rewrites bytes (`\n`
+    /// becomes `\r\n`), so the length-prefixed solve output is unreliable and
+    /// `solve_output` is left empty: this is for capturing human-facing
+    /// output, not for solve runs.
+    pub allocate_pty: bool,
+    /// Features the run must be able to enforce. When any is missing from the
+    /// run's effective [`SandboxCaps`], the run fails with
+    /// [`SandboxUnavailable`] before fixtures are staged.
+    pub require_sandbox: Vec<SandboxFeature>,
+    /// Name the run dir after this token (`<temp>/x07_run_tok_<token>`)
+    /// instead of the pid and a counter, so absolute paths the program sees
+    /// repeat across runs. The child gets it as [`ENV_RUN_DIR_TOKEN`].
+    /// Concurrent runs must use distinct tokens: a token whose dir is still
+    /// in use fails the run with [`RunDirTokenInUse`]. See
+    /// [`validate_run_dir_token`] for the allowed characters.
+    pub stable_run_dir_token: Option<String>,
+    /// Dirs copied over the staged fs fixture in order, each a safe relative
+    /// path under `fixture_fs_dir`. Later overlays win: files replace files
+    /// and dirs merge into dirs, while a file meeting a dir is a
+    /// [`StagingError::OverlayConflict`].
+    pub fixture_fs_overlays: Vec<PathBuf>,
+    /// `x07.kv.seed@0.1.0` JSON under `fixture_kv_dir` whose entries replace
+    /// the base seed's (`kv_seed_inline` or `fixture_kv_seed`) by key before
+    /// `seed.evkv` is written. The base's `default_latency_ticks` is kept.
+    pub fixture_kv_overlay_seed: Option<PathBuf>,
+    /// Append [`RunPhase`]s and a per-call summary here as JSON lines; see
+    /// [`EventLog`]. Used by the `compile_and_run*` entry points.
+    pub event_log: Option<EventLog>,
+    /// Check a successful run's `solve_output` against this; a mismatch is
+    /// recorded in [`RunnerResult::output_contract_violation`].
+    pub output_contract: Option<OutputContract>,
+    /// Also fail the run on a contract violation, with `trap` set to
+    /// [`OUTPUT_CONTRACT_TRAP`].
+    pub output_contract_strict: bool,
+}
+
+/// `solve-pure` with the `x07-host-runner` CLI's default budgets; every
+/// optional feature is off.
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            world: WorldId::SolvePure,
+            fixture_fs_dir: None,
+            fixture_fs_root: None,
+            fixture_fs_latency_index: None,
+            fixture_rr_dir: None,
+            fixture_kv_dir: None,
+            fixture_kv_seed: None,
+            kv_seed_inline: None,
+            solve_fuel: 50_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+            max_output_bytes: 1024 * 1024,
+            cpu_time_limit_seconds: 5,
+            debug_borrow_checks: false,
+            sanitizers: Vec::new(),
+            module_roots: Vec::new(),
+            max_run_dir_bytes: None,
+            retain_raw_stdout: true,
+            rlimit_mode: RlimitMode::Enforce,
+            solve_seed: None,
+            stage_hook: None,
+            allocate_pty: false,
+            require_sandbox: Vec::new(),
+            stable_run_dir_token: None,
+            fixture_fs_overlays: Vec::new(),
+            fixture_kv_overlay_seed: None,
+            event_log: None,
+            output_contract: None,
+            output_contract_strict: false,
+        }
+    }
+}
+
+/// Custom run dir staging for [`RunnerConfig::stage_hook`].
+///
+/// Called with the solver's run dir once the world's fixtures are staged and
+/// before the dir is made read-only, as part of sandbox prep in the runner
+/// process (not in the solver child). Whatever it creates is visible to the
+/// solver but read-only to it, so the hook must not rely on the solver
+/// writing there. An error fails the run.
+#[derive(Clone)]
+pub struct StageHook(Arc<StageHookFn>);
+
+type StageHookFn = dyn Fn(&Path) -> Result<()> + Send + Sync;
+
+impl StageHook {
+    pub fn new(hook: impl Fn(&Path) -> Result<()> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+}
+
+impl std::fmt::Debug for StageHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StageHook(..)")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -121,12 +411,27 @@ pub struct CompilerResult {
     pub lang_id: String,
     pub native_requires: x07c::native::NativeRequires,
     pub c_source_size: usize,
+    /// Wall time of the x07-to-C step; 0 when [`compile_program_memoized`]
+    /// skipped it.
+    pub codegen_ms: u64,
+    /// The exe came from the [`compile_program_memoized`] memo without
+    /// running codegen.
+    pub memo_hit: bool,
     pub compiled_exe: Option<PathBuf>,
     pub compiled_exe_size: Option<u64>,
     pub compile_error: Option<String>,
     /// Structured diagnostics for a failed compile (same shape as `x07 lint`),
     /// so run reports carry pointers/quickfixes instead of only a message string.
     pub compile_diagnostics: Vec<x07c::diagnostics::Diagnostic>,
+    /// sha256 of the canonical [`CompileOptionsFingerprint`] this compile used.
+    pub options_fingerprint: String,
+    /// Staged backend files linked into the exe, with their mtimes.
+    pub native_backend_resolutions: Vec<NativeBackendResolution>,
+    /// Warnings cc printed for a successful build, by flag. Empty when the
+    /// exe came from the native cache or the compile memo.
+    pub cc_warnings: Vec<CcWarning>,
+    /// [`native_cache_key`] of the exe; `None` when cc never ran.
+    pub native_cache_key: Option<String>,
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
     pub fuel_used: Option<u64>,
@@ -155,7 +460,35 @@ pub struct RunnerResult {
     pub sched_stats: Option<SchedStats>,
     pub mem_stats: Option<MemStats>,
     pub debug_stats: Option<DebugStats>,
+    /// Per-backend query/exec/cmd wall-time histograms from the ext-db
+    /// natives (see [`MetricsLine::db_latency_ms`]); `None` when no DB call ran.
+    pub db_latency_ms: Option<DbLatencyMs>,
     pub trap: Option<String>,
+    /// Tail of `stderr` (see [`trap_detail_from_stderr`]) when `trap` is set.
+    pub trap_detail: Option<String>,
+    /// Bytes the solver added to its run dir; set when `max_run_dir_bytes` is configured.
+    pub run_dir_bytes: Option<u64>,
+    /// sha256 of the environment applied to the solver child (see
+    /// [`PINNED_CHILD_ENV`]). Equal fingerprints mean equal child envs
+    /// regardless of the host's own locale/timezone.
+    pub env_fingerprint: String,
+    /// sha256 of the staged fixture tree (see [`fixture_tree_digest`]), taken
+    /// before the solver starts; `None` when nothing was staged (solve-pure).
+    pub fixture_digest: Option<String>,
+    /// The overlays staged on top of the base fixtures, in the order applied.
+    pub fixture_overlays: Vec<FixtureOverlay>,
+    /// Sandbox rlimits skipped under [`RlimitMode::BestEffort`], one line each.
+    pub sandbox_degradations: Vec<String>,
+    /// What the sandbox enforced for this run: [`sandbox_capabilities`]
+    /// minus any rlimit listed in `sandbox_degradations`.
+    pub sandbox_caps: SandboxCaps,
+    /// [`RunnerConfig::solve_seed`] the run used.
+    pub solve_seed: Option<u64>,
+    /// Why the fixtures could not be staged; the solver did not run and
+    /// `trap` is [`STAGING_FAILED_TRAP`].
+    pub staging_error: Option<StagingError>,
+    /// How `solve_output` broke [`RunnerConfig::output_contract`].
+    pub output_contract_violation: Option<OutputContractViolation>,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -173,10 +506,14 @@ pub struct MemStats {
     pub memcpy_bytes: u64,
 }
 
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DebugStats {
     pub borrow_violations: u64,
+    /// Where the first violations (at most 16) were detected, in detection
+    /// order: the line in the generated C source of the runtime call that
+    /// tripped the check.
+    pub borrow_violation_sites: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -202,6 +539,26 @@ pub struct CompileAndRunResult {
     pub solve: Option<RunnerResult>,
 }
 
+/// Progress points reported by [`compile_and_run_with_progress`].
+///
+/// Phases are observations only: callbacks receive owned values and cannot
+/// influence the run. Failure paths still report the phases reached so far,
+/// and every call ends with `Done`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunPhase {
+    CompileStarted,
+    CSourceReady { bytes: usize },
+    NativeLinkPlanned { backends: Vec<String> },
+    CcStarted,
+    CcFinished { cache_hit: bool },
+    FixturesStaged,
+    ChildSpawned { pid: u32 },
+    ChildExited { status: i32 },
+    Done,
+}
+
+fn no_progress(_: RunPhase) {}
+
 pub fn compile_options_for_world(
     world: WorldId,
     module_roots: Vec<PathBuf>,
@@ -223,7 +580,121 @@ pub fn compile_and_run(
     input: &[u8],
     compiled_out: Option<&Path>,
 ) -> Result<CompileAndRunResult> {
-    let compile = compile_program(program, config, compiled_out)?;
+    compile_and_run_input(program, config, SolveInput::Bytes(input), compiled_out)
+}
+
+/// [`compile_and_run`] with the input streamed from the file at
+/// `input_path`; see [`run_artifact_file_from`].
+pub fn compile_and_run_from(
+    program: &[u8],
+    config: &RunnerConfig,
+    input_path: &Path,
+    compiled_out: Option<&Path>,
+) -> Result<CompileAndRunResult> {
+    compile_and_run_input(program, config, SolveInput::File(input_path), compiled_out)
+}
+
+fn compile_and_run_input(
+    program: &[u8],
+    config: &RunnerConfig,
+    input: SolveInput<'_>,
+    compiled_out: Option<&Path>,
+) -> Result<CompileAndRunResult> {
+    let compile_options = compile_options_for_config(config)?;
+    compile_and_run_logged(
+        program,
+        config,
+        input,
+        compiled_out,
+        &compile_options,
+        &no_progress,
+    )
+}
+
+pub fn compile_and_run_with_options(
+    program: &[u8],
+    config: &RunnerConfig,
+    input: &[u8],
+    compiled_out: Option<&Path>,
+    compile_options: &compile::CompileOptions,
+) -> Result<CompileAndRunResult> {
+    compile_and_run_with_progress(
+        program,
+        config,
+        input,
+        compiled_out,
+        compile_options,
+        &no_progress,
+    )
+}
+
+/// Same as [`compile_and_run_with_options`], reporting [`RunPhase`]s to `progress`.
+pub fn compile_and_run_with_progress(
+    program: &[u8],
+    config: &RunnerConfig,
+    input: &[u8],
+    compiled_out: Option<&Path>,
+    compile_options: &compile::CompileOptions,
+    progress: &dyn Fn(RunPhase),
+) -> Result<CompileAndRunResult> {
+    compile_and_run_logged(
+        program,
+        config,
+        SolveInput::Bytes(input),
+        compiled_out,
+        compile_options,
+        progress,
+    )
+}
+
+/// Runs [`compile_and_run_impl`], ending with `Done`, and mirrors every phase
+/// plus a summary into [`RunnerConfig::event_log`].
+fn compile_and_run_logged(
+    program: &[u8],
+    config: &RunnerConfig,
+    input: SolveInput<'_>,
+    compiled_out: Option<&Path>,
+    compile_options: &compile::CompileOptions,
+    progress: &dyn Fn(RunPhase),
+) -> Result<CompileAndRunResult> {
+    let events = config.event_log.as_ref().map(EventLog::begin_run);
+    let report = |phase: RunPhase| {
+        if let Some(events) = &events {
+            events.phase(&phase);
+        }
+        progress(phase);
+    };
+    let res = compile_and_run_impl(
+        program,
+        config,
+        input,
+        compiled_out,
+        compile_options,
+        &report,
+    );
+    report(RunPhase::Done);
+    if let Some(events) = &events {
+        events.summary(program, &res);
+    }
+    res
+}
+
+fn compile_and_run_impl(
+    program: &[u8],
+    config: &RunnerConfig,
+    input: SolveInput<'_>,
+    compiled_out: Option<&Path>,
+    compile_options: &compile::CompileOptions,
+    progress: &dyn Fn(RunPhase),
+) -> Result<CompileAndRunResult> {
+    let compile = compile_program_impl(
+        program,
+        config,
+        compiled_out,
+        compile_options,
+        &[],
+        progress,
+    )?;
     if !compile.ok {
         return Ok(CompileAndRunResult {
             compile,
@@ -235,22 +706,38 @@ pub fn compile_and_run(
         anyhow::bail!("internal error: compile.ok but no compiled_exe");
     };
 
-    let solve = run_artifact_file(config, &exe, input)?;
+    let solve = run_artifact_file_impl(config, &exe, input, progress)?;
     Ok(CompileAndRunResult {
         compile,
         solve: Some(solve),
     })
 }
 
-pub fn compile_and_run_with_options(
-    program: &[u8],
+/// `lang_id` reported by [`compile_c_and_run`].
+pub const C_PASSTHROUGH_LANG_ID: &str = "c-passthrough";
+
+/// Toolchain knobs for [`compile_c_and_run`].
+#[derive(Debug, Clone, Default)]
+pub struct CPassthroughOverrides {
+    /// Appended after the world-derived cc flags.
+    pub extra_cc_args: Vec<String>,
+    /// Native backends to link, planned as for an x07 compile. `None` links
+    /// none.
+    pub native_requires: Option<x07c::native::NativeRequires>,
+}
+
+/// Builds `c_source` (e.g. a hand-edited kept `solver.c`) with the same
+/// toolchain, defines and native cache as [`compile_and_run`], skipping x07c,
+/// then runs it. `compile.lang_id` is [`C_PASSTHROUGH_LANG_ID`] and
+/// `compile.fuel_used` is `None`.
+pub fn compile_c_and_run(
+    c_source: &str,
     config: &RunnerConfig,
+    overrides: &CPassthroughOverrides,
     input: &[u8],
-    compiled_out: Option<&Path>,
-    compile_options: &compile::CompileOptions,
 ) -> Result<CompileAndRunResult> {
-    let compile =
-        compile_program_with_options(program, config, compiled_out, compile_options, &[])?;
+    let compile_options = compile_options_for_config(config)?;
+    let compile = compile_c_passthrough(c_source, config, &compile_options, overrides)?;
     if !compile.ok {
         return Ok(CompileAndRunResult {
             compile,
@@ -262,22 +749,113 @@ pub fn compile_and_run_with_options(
         anyhow::bail!("internal error: compile.ok but no compiled_exe");
     };
 
-    let solve = run_artifact_file(config, &exe, input)?;
+    let solve = run_artifact_file_impl(config, &exe, SolveInput::Bytes(input), &no_progress)?;
     Ok(CompileAndRunResult {
         compile,
         solve: Some(solve),
     })
 }
 
+fn compile_c_passthrough(
+    c_source: &str,
+    config: &RunnerConfig,
+    compile_options: &compile::CompileOptions,
+    overrides: &CPassthroughOverrides,
+) -> Result<CompilerResult> {
+    let options_fingerprint = CompileOptionsFingerprint::compute(compile_options)
+        .context("fingerprint compile options")?
+        .to_string();
+    let native_requires = overrides
+        .native_requires
+        .clone()
+        .unwrap_or_else(|| empty_native_requires(compile_options));
+    let mut result = CompilerResult {
+        ok: false,
+        exit_status: 1,
+        lang_id: C_PASSTHROUGH_LANG_ID.to_string(),
+        native_requires,
+        c_source_size: c_source.len(),
+        codegen_ms: 0,
+        memo_hit: false,
+        compiled_exe: None,
+        compiled_exe_size: None,
+        compile_error: None,
+        compile_diagnostics: Vec::new(),
+        options_fingerprint,
+        native_backend_resolutions: Vec::new(),
+        cc_warnings: Vec::new(),
+        native_cache_key: None,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        fuel_used: None,
+        trap: None,
+    };
+
+    let mut cc_args = overrides.extra_cc_args.clone();
+    let mut cache_salt = Vec::new();
+    if !result.native_requires.requires.is_empty() {
+        let root = workspace_root()?;
+        match native_backends::plan_native_link_argv(&root, &result.native_requires) {
+            Ok(plan) => {
+                cc_args.extend(plan.argv);
+                result.native_backend_resolutions = plan.resolutions;
+                cache_salt = plan.cache_salt;
+            }
+            Err(err) => {
+                result.compile_error = Some(format_native_backend_error(&err));
+                return Ok(result);
+            }
+        }
+    }
+    maybe_add_linux_libm_for_sqlite(&result.native_requires, &mut cc_args);
+
+    let tool = compile_c_to_exe(
+        c_source,
+        config,
+        compile_options,
+        &result.options_fingerprint,
+        &cc_args,
+        &cache_salt,
+    )?;
+    result.exit_status = tool.exit_status;
+    result.native_cache_key = Some(tool.cache_key);
+    if !tool.ok {
+        result.compile_error = Some(format!("C toolchain failed (exit={})", tool.exit_status));
+        result.stdout = tool.stdout;
+        result.stderr = tool.stderr;
+        return Ok(result);
+    }
+
+    let exe = tool
+        .exe_path
+        .context("internal error: toolchain ok but no exe")?;
+    result.ok = true;
+    result.compiled_exe_size = std::fs::metadata(&exe).map(|m| m.len()).ok();
+    result.compiled_exe = Some(exe);
+    result.cc_warnings = parse_cc_warnings(&tool.stderr);
+    result.stdout = tool.stdout;
+    result.stderr = tool.stderr;
+    Ok(result)
+}
+
 pub fn compile_program(
     program: &[u8],
     config: &RunnerConfig,
     compiled_out: Option<&Path>,
 ) -> Result<CompilerResult> {
-    let compile_options = compile_options_for_world(config.world, Vec::new())?;
+    let compile_options = compile_options_for_config(config)?;
     compile_program_with_options(program, config, compiled_out, &compile_options, &[])
 }
 
+fn compile_options_for_config(config: &RunnerConfig) -> Result<compile::CompileOptions> {
+    for root in &config.module_roots {
+        if !root.is_dir() {
+            anyhow::bail!("module root is not a directory: {}", root.display());
+        }
+    }
+    compile_options_for_world(config.world, config.module_roots.clone())
+}
+
 pub fn compile_program_with_options(
     program: &[u8],
     config: &RunnerConfig,
@@ -285,9 +863,55 @@ pub fn compile_program_with_options(
     compile_options: &compile::CompileOptions,
     extra_cc_args: &[String],
 ) -> Result<CompilerResult> {
-    let lang_id = language::LANG_ID.to_string();
+    compile_program_impl(
+        program,
+        config,
+        compiled_out,
+        compile_options,
+        extra_cc_args,
+        &no_progress,
+    )
+}
 
-    let compile_out = match compile::compile_program_to_c_with_meta(program, compile_options) {
+fn compile_program_impl(
+    program: &[u8],
+    config: &RunnerConfig,
+    compiled_out: Option<&Path>,
+    compile_options: &compile::CompileOptions,
+    extra_cc_args: &[String],
+    progress: &dyn Fn(RunPhase),
+) -> Result<CompilerResult> {
+    compile_program_and_c(
+        program,
+        config,
+        compiled_out,
+        compile_options,
+        extra_cc_args,
+        progress,
+    )
+    .map(|(result, _)| result)
+}
+
+/// [`compile_program_impl`], also returning the generated C source when
+/// codegen got that far.
+fn compile_program_and_c(
+    program: &[u8],
+    config: &RunnerConfig,
+    compiled_out: Option<&Path>,
+    compile_options: &compile::CompileOptions,
+    extra_cc_args: &[String],
+    progress: &dyn Fn(RunPhase),
+) -> Result<(CompilerResult, Option<String>)> {
+    let lang_id = language::LANG_ID.to_string();
+    let options_fingerprint = CompileOptionsFingerprint::compute(compile_options)
+        .context("fingerprint compile options")?
+        .to_string();
+
+    progress(RunPhase::CompileStarted);
+    let codegen_start = Instant::now();
+    let compile_out = compile::compile_program_to_c_with_meta(program, compile_options);
+    let codegen_ms = codegen_start.elapsed().as_millis() as u64;
+    let compile_out = match compile_out {
         Ok(out) => out,
         Err(err) => {
             let mut msg = format!("{:?}: {}", err.kind, err.message);
@@ -306,110 +930,188 @@ pub fn compile_program_with_options(
                 }
             }
             let compile_diagnostics = compile_failure_diagnostics(program, &err, compile_options);
-            return Ok(CompilerResult {
-                ok: false,
-                exit_status: 1,
-                lang_id,
-                native_requires: empty_native_requires(compile_options),
-                c_source_size: 0,
-                compiled_exe: None,
-                compiled_exe_size: None,
-                compile_error: Some(msg),
-                compile_diagnostics,
-                stdout: Vec::new(),
-                stderr: Vec::new(),
-                fuel_used: None,
-                trap: None,
-            });
+            return Ok((
+                CompilerResult {
+                    ok: false,
+                    exit_status: 1,
+                    lang_id,
+                    native_requires: empty_native_requires(compile_options),
+                    c_source_size: 0,
+                    codegen_ms,
+                    memo_hit: false,
+                    compiled_exe: None,
+                    compiled_exe_size: None,
+                    compile_error: Some(msg),
+                    compile_diagnostics,
+                    options_fingerprint,
+                    native_backend_resolutions: Vec::new(),
+                    cc_warnings: Vec::new(),
+                    native_cache_key: None,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                    fuel_used: None,
+                    trap: None,
+                },
+                None,
+            ));
         }
     };
 
     let c_source = compile_out.c_src;
     let compile_stats = compile_out.stats;
     let native_requires = compile_out.native_requires;
+    progress(RunPhase::CSourceReady {
+        bytes: c_source.len(),
+    });
 
     let mut cc_args = extra_cc_args.to_vec();
+    let mut native_backend_resolutions = Vec::new();
+    let mut cache_salt = Vec::new();
     if !native_requires.requires.is_empty() {
         let root = workspace_root()?;
-        if let Err(err) = native_backends::plan_native_link_argv(&root, &native_requires)
-            .map(|argv| cc_args.extend(argv))
+        if let Err(err) =
+            native_backends::plan_native_link_argv(&root, &native_requires).map(|plan| {
+                cc_args.extend(plan.argv);
+                native_backend_resolutions = plan.resolutions;
+                cache_salt = plan.cache_salt;
+            })
         {
-            return Ok(CompilerResult {
+            return Ok((
+                CompilerResult {
+                    ok: false,
+                    exit_status: 1,
+                    lang_id,
+                    native_requires,
+                    c_source_size: c_source.len(),
+                    codegen_ms,
+                    memo_hit: false,
+                    compiled_exe: None,
+                    compiled_exe_size: None,
+                    compile_error: Some(format_native_backend_error(&err)),
+                    compile_diagnostics: Vec::new(),
+                    options_fingerprint,
+                    native_backend_resolutions: Vec::new(),
+                    cc_warnings: Vec::new(),
+                    native_cache_key: None,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                    fuel_used: Some(compile_stats.fuel_used),
+                    trap: None,
+                },
+                Some(c_source),
+            ));
+        }
+    }
+    maybe_add_linux_libm_for_sqlite(&native_requires, &mut cc_args);
+    progress(RunPhase::NativeLinkPlanned {
+        backends: native_requires
+            .requires
+            .iter()
+            .map(|r| r.backend_id.clone())
+            .collect(),
+    });
+
+    progress(RunPhase::CcStarted);
+    let tool = compile_c_to_exe(
+        &c_source,
+        config,
+        compile_options,
+        &options_fingerprint,
+        &cc_args,
+        &cache_salt,
+    )?;
+    progress(RunPhase::CcFinished {
+        cache_hit: tool.cache_hit,
+    });
+    if !tool.ok {
+        return Ok((
+            CompilerResult {
                 ok: false,
-                exit_status: 1,
+                exit_status: tool.exit_status,
                 lang_id,
                 native_requires,
                 c_source_size: c_source.len(),
+                codegen_ms,
+                memo_hit: false,
                 compiled_exe: None,
                 compiled_exe_size: None,
-                compile_error: Some(format_native_backend_error(&err)),
+                compile_error: Some(format!("C toolchain failed (exit={})", tool.exit_status)),
                 compile_diagnostics: Vec::new(),
-                stdout: Vec::new(),
-                stderr: Vec::new(),
+                options_fingerprint,
+                native_backend_resolutions,
+                cc_warnings: Vec::new(),
+                native_cache_key: Some(tool.cache_key),
+                stdout: tool.stdout,
+                stderr: tool.stderr,
                 fuel_used: Some(compile_stats.fuel_used),
                 trap: None,
-            });
-        }
+            },
+            Some(c_source),
+        ));
     }
-    maybe_add_linux_libm_for_sqlite(&native_requires, &mut cc_args);
 
-    let tool = compile_c_to_exe(&c_source, config, compile_options, &cc_args)?;
-    if !tool.ok {
-        return Ok(CompilerResult {
-            ok: false,
-            exit_status: tool.exit_status,
+    let exe = tool
+        .exe_path
+        .context("internal error: toolchain ok but no exe")?;
+
+    let final_exe = place_compiled_exe(exe, compiled_out)?;
+    if compiled_out.is_some() {
+        artifacts::write_sidecar(
+            &final_exe,
+            &artifacts::ArtifactMeta {
+                schema_version: artifacts::ARTIFACT_META_SCHEMA_VERSION.to_string(),
+                program_sha256: hex_lower(&Sha256::digest(program)),
+                world: config.world.as_str().to_string(),
+                created_unix_ms: artifacts::now_unix_ms(),
+                native_cache_key: Some(tool.cache_key.clone()),
+            },
+        )?;
+    }
+    let exe_size = std::fs::metadata(&final_exe).map(|m| m.len()).ok();
+
+    Ok((
+        CompilerResult {
+            ok: true,
+            exit_status: 0,
             lang_id,
             native_requires,
             c_source_size: c_source.len(),
-            compiled_exe: None,
-            compiled_exe_size: None,
-            compile_error: Some(format!("C toolchain failed (exit={})", tool.exit_status)),
+            codegen_ms,
+            memo_hit: false,
+            compiled_exe: Some(final_exe),
+            compiled_exe_size: exe_size,
+            compile_error: None,
             compile_diagnostics: Vec::new(),
+            options_fingerprint,
+            native_backend_resolutions,
+            cc_warnings: parse_cc_warnings(&tool.stderr),
+            native_cache_key: Some(tool.cache_key),
             stdout: tool.stdout,
             stderr: tool.stderr,
             fuel_used: Some(compile_stats.fuel_used),
             trap: None,
-        });
-    }
-
-    let exe = tool
-        .exe_path
-        .context("internal error: toolchain ok but no exe")?;
+        },
+        Some(c_source),
+    ))
+}
 
-    let final_exe = if let Some(out_path) = compiled_out {
-        if let Some(parent) = out_path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("create dir: {}", parent.display()))?;
-        }
-        copy_executable_atomic(&exe, out_path).with_context(|| {
-            format!(
-                "copy compiled artifact from {} to {}",
-                exe.display(),
-                out_path.display()
-            )
-        })?;
-        out_path.to_path_buf()
-    } else {
-        exe
+/// Copies a cached exe to `compiled_out` when given; otherwise returns it as is.
+fn place_compiled_exe(exe: PathBuf, compiled_out: Option<&Path>) -> Result<PathBuf> {
+    let Some(out_path) = compiled_out else {
+        return Ok(exe);
     };
-
-    let exe_size = std::fs::metadata(&final_exe).map(|m| m.len()).ok();
-
-    Ok(CompilerResult {
-        ok: true,
-        exit_status: 0,
-        lang_id,
-        native_requires,
-        c_source_size: c_source.len(),
-        compiled_exe: Some(final_exe),
-        compiled_exe_size: exe_size,
-        compile_error: None,
-        compile_diagnostics: Vec::new(),
-        stdout: tool.stdout,
-        stderr: tool.stderr,
-        fuel_used: Some(compile_stats.fuel_used),
-        trap: None,
-    })
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("create dir: {}", parent.display()))?;
+    }
+    copy_executable_atomic(&exe, out_path).with_context(|| {
+        format!(
+            "copy compiled artifact from {} to {}",
+            exe.display(),
+            out_path.display()
+        )
+    })?;
+    Ok(out_path.to_path_buf())
 }
 
 fn copy_executable_atomic(src: &Path, dst: &Path) -> Result<()> {
@@ -471,6 +1173,48 @@ fn copy_executable_atomic(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
+fn write_file_atomic(dst: &Path, bytes: &[u8]) -> Result<()> {
+    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let parent = dst
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let pid = std::process::id();
+    let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let tmp_path = parent.join(format!(".x07_tmp_file_{pid}_{n}"));
+
+    let _ = std::fs::remove_file(&tmp_path);
+    let _cleanup_tmp = CleanupFile {
+        path: tmp_path.clone(),
+    };
+
+    let mut tmp_file = std::fs::OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(&tmp_path)
+        .with_context(|| format!("create tmp file: {}", tmp_path.display()))?;
+    tmp_file
+        .write_all(bytes)
+        .with_context(|| format!("write tmp file: {}", tmp_path.display()))?;
+    let _ = tmp_file.sync_all();
+    drop(tmp_file);
+
+    #[cfg(windows)]
+    if dst.exists() {
+        let _ = std::fs::remove_file(dst);
+    }
+    std::fs::rename(&tmp_path, dst).with_context(|| {
+        format!(
+            "rename file into place from {} to {}",
+            tmp_path.display(),
+            dst.display()
+        )
+    })?;
+
+    Ok(())
+}
+
 struct CleanupFile {
     path: PathBuf,
 }
@@ -710,12 +1454,132 @@ fn parse_semver(v: &str) -> Option<SemverKey> {
     })
 }
 
+/// Runs the solver exe at `artifact_path` on `input`. The child counts
+/// against [`set_max_concurrent_runs`]; when no slot frees up within
+/// [`set_run_slot_acquire_timeout`] this fails with a [`RunSlotError`].
 pub fn run_artifact_file(
     config: &RunnerConfig,
     artifact_path: &Path,
     input: &[u8],
 ) -> Result<RunnerResult> {
-    let out = run_child(artifact_path, input, config)?;
+    run_artifact_file_impl(
+        config,
+        artifact_path,
+        SolveInput::Bytes(input),
+        &no_progress,
+    )
+}
+
+/// [`run_artifact_file`] with the input read from `input_path`. The file is
+/// streamed to the child's stdin behind its length frame instead of being
+/// loaded into memory; it must fit the `u32` frame.
+pub fn run_artifact_file_from(
+    config: &RunnerConfig,
+    artifact_path: &Path,
+    input_path: &Path,
+) -> Result<RunnerResult> {
+    run_artifact_file_impl(
+        config,
+        artifact_path,
+        SolveInput::File(input_path),
+        &no_progress,
+    )
+}
+
+/// Solver input: bytes already in memory, or a file streamed to the child.
+#[derive(Debug, Clone, Copy)]
+enum SolveInput<'a> {
+    Bytes(&'a [u8]),
+    File(&'a Path),
+}
+
+/// Outcome of [`run_and_check`].
+#[derive(Debug, Clone)]
+pub struct RunCheck {
+    pub result: RunnerResult,
+    /// The run succeeded and `solve_output` equals the expected bytes.
+    pub matched: bool,
+    /// Offset of the first byte where `solve_output` and the expected bytes
+    /// differ; the shorter length when one is a prefix of the other. `None`
+    /// when the outputs are equal.
+    pub first_diff_offset: Option<usize>,
+}
+
+/// [`run_artifact_file`], then compares `solve_output` against `expected`
+/// for golden tests. The diff offset is only searched for on a mismatch.
+pub fn run_and_check(
+    config: &RunnerConfig,
+    artifact_path: &Path,
+    input: &[u8],
+    expected: &[u8],
+) -> Result<RunCheck> {
+    let result = run_artifact_file(config, artifact_path, input)?;
+    let first_diff_offset = golden::first_diff(expected, &result.solve_output);
+    Ok(RunCheck {
+        matched: result.ok && first_diff_offset.is_none(),
+        first_diff_offset,
+        result,
+    })
+}
+
+fn run_artifact_file_impl(
+    config: &RunnerConfig,
+    artifact_path: &Path,
+    input: SolveInput<'_>,
+    progress: &dyn Fn(RunPhase),
+) -> Result<RunnerResult> {
+    let checker = config
+        .output_contract
+        .as_ref()
+        .map(OutputContract::checker)
+        .transpose()?;
+    let mut res = run_artifact_file_raw(config, artifact_path, input, progress)?;
+    if res.trap.is_some() {
+        res.trap_detail = trap_detail_from_stderr(&res.stderr);
+    }
+    if let Some(checker) = checker.filter(|_| res.ok) {
+        res.output_contract_violation = checker.check(&res.solve_output);
+        if let Some(violation) = res
+            .output_contract_violation
+            .as_ref()
+            .filter(|_| config.output_contract_strict)
+        {
+            res.ok = false;
+            res.trap = Some(OUTPUT_CONTRACT_TRAP.to_string());
+            res.trap_detail = Some(violation.message.clone());
+        }
+    }
+    Ok(res)
+}
+
+fn run_artifact_file_raw(
+    config: &RunnerConfig,
+    artifact_path: &Path,
+    input: SolveInput<'_>,
+    progress: &dyn Fn(RunPhase),
+) -> Result<RunnerResult> {
+    let sandbox_caps = effective_sandbox_caps(config)?;
+    let missing = sandbox_caps.missing(&config.require_sandbox);
+    if !missing.is_empty() {
+        return Err(SandboxUnavailable {
+            missing,
+            caps: sandbox_caps,
+        }
+        .into());
+    }
+    let out = match run_child(artifact_path, input, config, progress) {
+        Ok(out) => out,
+        Err(err) => match err.downcast::<StagingError>() {
+            Ok(staging_error) => {
+                return Ok(staging_failed_result(config, sandbox_caps, staging_error))
+            }
+            Err(err) => return Err(err),
+        },
+    };
+    let env_fingerprint = out.env_fingerprint;
+    let fixture_digest = out.fixture_digest;
+    let fixture_overlays = out.fixture_overlays;
+    let sandbox_degradations = out.sandbox_degradations;
     let exit_status = out.exit_status;
     let stdout = out.stdout;
     let stderr = out.stderr;
@@ -742,7 +1606,55 @@ pub fn run_artifact_file(
             sched_stats: None,
             mem_stats: None,
             debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
             trap: Some("wall timeout".to_string()),
+            run_dir_bytes: out.run_dir_bytes,
+            env_fingerprint,
+            fixture_digest,
+            fixture_overlays,
+            sandbox_degradations,
+            sandbox_caps,
+            solve_seed: config.solve_seed,
+            staging_error: None,
+            output_contract_violation: None,
+        });
+    }
+
+    if out.run_dir_quota_exceeded {
+        return Ok(RunnerResult {
+            ok: false,
+            exit_status,
+            solve_output: Vec::new(),
+            stdout,
+            stderr,
+            fuel_used: None,
+            heap_used: None,
+            fs_read_file_calls: None,
+            fs_list_dir_calls: None,
+            rr_open_calls: None,
+            rr_close_calls: None,
+            rr_stats_calls: None,
+            rr_next_calls: None,
+            rr_next_miss_calls: None,
+            rr_append_calls: None,
+            kv_get_calls: None,
+            kv_set_calls: None,
+            sched_stats: None,
+            mem_stats: None,
+            debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
+            trap: Some(RUN_DIR_QUOTA_TRAP.to_string()),
+            run_dir_bytes: out.run_dir_bytes,
+            env_fingerprint,
+            fixture_digest,
+            fixture_overlays,
+            sandbox_degradations,
+            sandbox_caps,
+            solve_seed: config.solve_seed,
+            staging_error: None,
+            output_contract_violation: None,
         });
     }
 
@@ -768,7 +1680,18 @@ pub fn run_artifact_file(
             sched_stats: None,
             mem_stats: None,
             debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
             trap: Some("stderr exceeded cap".to_string()),
+            run_dir_bytes: out.run_dir_bytes,
+            env_fingerprint,
+            fixture_digest,
+            fixture_overlays,
+            sandbox_degradations,
+            sandbox_caps,
+            solve_seed: config.solve_seed,
+            staging_error: None,
+            output_contract_violation: None,
         });
     }
 
@@ -794,11 +1717,26 @@ pub fn run_artifact_file(
             sched_stats: None,
             mem_stats: None,
             debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
             trap: Some("stdout exceeded cap".to_string()),
+            run_dir_bytes: out.run_dir_bytes,
+            env_fingerprint,
+            fixture_digest,
+            fixture_overlays,
+            sandbox_degradations,
+            sandbox_caps,
+            solve_seed: config.solve_seed,
+            staging_error: None,
+            output_contract_violation: None,
         });
     }
 
-    let parse = parse_native_stdout(&stdout, config.max_output_bytes);
+    let parse = if config.allocate_pty {
+        Ok(Vec::new())
+    } else {
+        parse_native_stdout(&stdout, config.max_output_bytes)
+    };
 
     let (solve_output, mut trap) = match parse {
         Ok(bytes) => (
@@ -813,7 +1751,7 @@ pub fn run_artifact_file(
         ),
     };
 
-    let metrics = parse_metrics(&stderr);
+    let metrics = parse_metrics_fd(&out.metrics).or_else(|| parse_metrics(&stderr));
     if exit_status == 0 && metrics.is_none() && trap.is_none() {
         trap = Some("missing metrics json line on stderr".to_string());
     }
@@ -837,9 +1775,15 @@ pub fn run_artifact_file(
     let kv_set_calls = metrics.as_ref().and_then(|m| m.kv_set_calls);
     let sched_stats = metrics.as_ref().and_then(|m| m.sched_stats.clone());
     let mem_stats = metrics.as_ref().and_then(|m| m.mem_stats);
-    let debug_stats = metrics.as_ref().and_then(|m| m.debug_stats);
+    let debug_stats = metrics.as_ref().and_then(|m| m.debug_stats.clone());
+    let db_latency_ms = metrics.as_ref().and_then(|m| m.db_latency_ms.clone());
 
     let ok = exit_status == 0 && trap.is_none();
+    let stdout = if ok && !config.retain_raw_stdout && !config.allocate_pty {
+        Vec::new()
+    } else {
+        stdout
+    };
     Ok(RunnerResult {
         ok,
         exit_status,
@@ -861,10 +1805,65 @@ pub fn run_artifact_file(
         sched_stats,
         mem_stats,
         debug_stats,
+        db_latency_ms,
         trap,
+        trap_detail: None,
+        run_dir_bytes: out.run_dir_bytes,
+        env_fingerprint,
+        fixture_digest,
+        fixture_overlays,
+        sandbox_degradations,
+        sandbox_caps,
+        solve_seed: config.solve_seed,
+        staging_error: None,
+        output_contract_violation: None,
     })
 }
 
+fn staging_failed_result(
+    config: &RunnerConfig,
+    sandbox_caps: SandboxCaps,
+    staging_error: StagingError,
+) -> RunnerResult {
+    RunnerResult {
+        ok: false,
+        exit_status: 1,
+        solve_output: Vec::new(),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        fuel_used: None,
+        heap_used: None,
+        fs_read_file_calls: None,
+        fs_list_dir_calls: None,
+        rr_open_calls: None,
+        rr_close_calls: None,
+        rr_stats_calls: None,
+        rr_next_calls: None,
+        rr_next_miss_calls: None,
+        rr_append_calls: None,
+        kv_get_calls: None,
+        kv_set_calls: None,
+        sched_stats: None,
+        mem_stats: None,
+        debug_stats: None,
+        db_latency_ms: None,
+        trap_detail: None,
+        trap: Some(STAGING_FAILED_TRAP.to_string()),
+        run_dir_bytes: None,
+        env_fingerprint: env_fingerprint(&solver_child_env(config)),
+        fixture_digest: None,
+        fixture_overlays: Vec::new(),
+        sandbox_degradations: Vec::new(),
+        sandbox_caps,
+        solve_seed: config.solve_seed,
+        staging_error: Some(staging_error),
+        output_contract_violation: None,
+    }
+}
+
+/// Trap reported when the solver's run dir grows past `RunnerConfig.max_run_dir_bytes`.
+pub const RUN_DIR_QUOTA_TRAP: &str = "run dir quota exceeded";
+
 /// Maps well-known runtime trap messages to actionable hints surfaced in run
 /// reports next to `trap`.
 pub fn trap_help_for(trap: Option<&str>, solve_fuel: u64) -> Option<String> {
@@ -876,6 +1875,10 @@ pub fn trap_help_for(trap: Option<&str>, solve_fuel: u64) -> Option<String> {
             "a budget.scope_v1 fuel budget was exceeded; raise the scope budget or reduce work inside the scope"
                 .to_string(),
         ),
+        RUN_DIR_QUOTA_TRAP => Some(
+            "the solver wrote more than max_run_dir_bytes into its run dir; raise --max-run-dir-bytes or write less scratch data"
+                .to_string(),
+        ),
         "map_u32 full" => Some(
             "std.hash_map capacity exceeded; create the map with std.hash_map.with_capacity_u32(expected_len) sized for the input"
                 .to_string(),
@@ -901,6 +1904,33 @@ pub struct MetricsLine {
     pub sched_stats: Option<SchedStats>,
     pub mem_stats: Option<MemStats>,
     pub debug_stats: Option<DebugStats>,
+    /// `"sqlite"`/`"pg"`/`"redis"` to call counts in the buckets <1ms, <10ms,
+    /// <100ms, <1s and >=1s. Only backends with at least one call appear.
+    pub db_latency_ms: Option<DbLatencyMs>,
+}
+
+/// Backend name to wall-time bucket counts, as in [`MetricsLine::db_latency_ms`].
+pub type DbLatencyMs = BTreeMap<String, Vec<u64>>;
+
+impl MetricsLine {
+    fn has_any_metric(&self) -> bool {
+        self.fuel_used.is_some()
+            || self.heap_used.is_some()
+            || self.fs_read_file_calls.is_some()
+            || self.fs_list_dir_calls.is_some()
+            || self.rr_open_calls.is_some()
+            || self.rr_close_calls.is_some()
+            || self.rr_stats_calls.is_some()
+            || self.rr_next_calls.is_some()
+            || self.rr_next_miss_calls.is_some()
+            || self.rr_append_calls.is_some()
+            || self.kv_get_calls.is_some()
+            || self.kv_set_calls.is_some()
+            || self.sched_stats.is_some()
+            || self.mem_stats.is_some()
+            || self.debug_stats.is_some()
+            || self.db_latency_ms.is_some()
+    }
 }
 
 pub fn parse_metrics(stderr: &[u8]) -> Option<MetricsLine> {
@@ -911,22 +1941,7 @@ pub fn parse_metrics(stderr: &[u8]) -> Option<MetricsLine> {
             continue;
         }
         if let Ok(m) = serde_json::from_str::<MetricsLine>(line) {
-            if m.fuel_used.is_some()
-                || m.heap_used.is_some()
-                || m.fs_read_file_calls.is_some()
-                || m.fs_list_dir_calls.is_some()
-                || m.rr_open_calls.is_some()
-                || m.rr_close_calls.is_some()
-                || m.rr_stats_calls.is_some()
-                || m.rr_next_calls.is_some()
-                || m.rr_next_miss_calls.is_some()
-                || m.rr_append_calls.is_some()
-                || m.kv_get_calls.is_some()
-                || m.kv_set_calls.is_some()
-                || m.sched_stats.is_some()
-                || m.mem_stats.is_some()
-                || m.debug_stats.is_some()
-            {
+            if m.has_any_metric() {
                 return Some(m);
             }
         }
@@ -934,6 +1949,67 @@ pub fn parse_metrics(stderr: &[u8]) -> Option<MetricsLine> {
     None
 }
 
+/// Metrics written to [`METRICS_FD`]: the whole stream is one JSON object.
+/// `None` when the stream is empty (artifacts built before the metrics fd
+/// existed) or not a metrics object.
+pub fn parse_metrics_fd(bytes: &[u8]) -> Option<MetricsLine> {
+    let text = std::str::from_utf8(bytes).ok()?.trim();
+    if text.is_empty() {
+        return None;
+    }
+    serde_json::from_str::<MetricsLine>(text)
+        .ok()
+        .filter(MetricsLine::has_any_metric)
+}
+
+/// Cap on [`trap_detail_from_stderr`] output, in bytes.
+pub const TRAP_DETAIL_MAX_BYTES: usize = 2048;
+
+/// The last [`TRAP_DETAIL_MAX_BYTES`] of `stderr` as lossy UTF-8, for showing
+/// next to a trap. Metrics JSON lines are dropped and runs of identical lines
+/// collapse to one `<line> [repeated N times]`. `None` when nothing is left.
+pub fn trap_detail_from_stderr(stderr: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(stderr);
+    let mut lines: Vec<(&str, usize)> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('{') && trimmed.ends_with('}') {
+            continue;
+        }
+        match lines.last_mut() {
+            Some((prev, n)) if *prev == line => *n += 1,
+            _ => lines.push((line, 1)),
+        }
+    }
+
+    let mut out = String::new();
+    for (line, n) in lines {
+        out.push_str(line);
+        if n > 1 {
+            out.push_str(&format!(" [repeated {n} times]"));
+        }
+        out.push('\n');
+    }
+    let out = out.trim();
+    if out.is_empty() {
+        return None;
+    }
+    if out.len() <= TRAP_DETAIL_MAX_BYTES {
+        return Some(out.to_string());
+    }
+    let mut start = out.len() - TRAP_DETAIL_MAX_BYTES;
+    while !out.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = &out[start..];
+    // Drop the partial first line unless it is the only one.
+    let tail = match tail.find('\n') {
+        Some(i) if i + 1 < tail.len() => &tail[i + 1..],
+        _ => tail,
+    };
+    Some(tail.to_string())
+}
+
 pub fn parse_trap_stderr(stderr: &[u8]) -> Option<String> {
     let text = String::from_utf8_lossy(stderr);
     for line in text.lines().rev() {
@@ -991,6 +2067,15 @@ fn cache_dir() -> Result<PathBuf> {
     Ok(dir)
 }
 
+fn native_cache_root(config: &NativeToolchainConfig) -> Result<PathBuf> {
+    let Some(dir) = &config.cache_dir_override else {
+        return cache_dir();
+    };
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("create native cache dir: {}", dir.display()))?;
+    Ok(dir.clone())
+}
+
 fn workspace_root() -> Result<PathBuf> {
     if let Some(override_dir) = std::env::var_os("X07_WORKSPACE_ROOT") {
         let dir = PathBuf::from(override_dir);
@@ -1091,12 +2176,39 @@ fn native_backend_missing_hint(backend_id: &str) -> Option<&'static str> {
 pub struct NativeToolchainConfig {
     pub world_tag: String,
     pub fuel_init: u64,
+    /// Enforced heap limit (`X07_MEM_CAP`).
     pub mem_cap_bytes: usize,
+    /// Arena the bundle wrapper allocates (`X07_ARENA_CAP`); defaults to
+    /// `mem_cap_bytes` and must not be smaller.
+    pub arena_cap_bytes: Option<usize>,
     pub debug_borrow_checks: bool,
     pub enable_fs: bool,
     pub enable_rr: bool,
     pub enable_kv: bool,
     pub extra_cc_args: Vec<String>,
+    /// Extra `-DKEY=VALUE` defines, e.g. runtime feature flags. See
+    /// [`extra_define_flags`] for what is accepted.
+    pub extra_defines: Vec<(String, String)>,
+    /// Appends `-Werror` and then [`CC_WARNINGS_ALLOWLIST`] after all other
+    /// cc args.
+    pub warnings_as_errors: bool,
+    /// Appended as a single `-fsanitize=...` flag (cc compiles and links in one step).
+    pub sanitizers: Vec<Sanitizer>,
+    /// [`CompileOptionsFingerprint`] of the options that produced `c_source`;
+    /// mixed into the cache key and recorded in the cache meta.
+    pub options_fingerprint: Option<String>,
+    /// Opaque bytes mixed into the cache key. [`compile_program_with_options`]
+    /// sets it to [`NativeLinkPlan::cache_salt`] so native backend manifest
+    /// edits invalidate cached exes.
+    pub cache_salt: Vec<u8>,
+    /// Native cache root for this call, instead of `X07_NATIVE_CACHE_DIR` (or
+    /// its default). Not part of the cache key.
+    pub cache_dir_override: Option<PathBuf>,
+    /// Compile into a fresh temp dir, neither reading nor writing the native
+    /// cache. [`ToolchainOutput::cache_key`] is still computed. The dir (exe
+    /// and any kept C source) is removed when the last clone of the returned
+    /// [`ToolchainOutput`] is dropped.
+    pub bypass_cache: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -1106,6 +2218,22 @@ pub struct ToolchainOutput {
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
     pub exe_path: Option<PathBuf>,
+    /// The executable was already present in the native cache; cc did not run.
+    pub cache_hit: bool,
+    /// Native cache entry (see [`native_cache_key`]) the exe lives in.
+    pub cache_key: String,
+    /// Owns the [`NativeToolchainConfig::bypass_cache`] build dir.
+    scratch_dir: Option<Arc<ScratchDir>>,
+}
+
+/// Removes its dir on drop.
+#[derive(Debug)]
+struct ScratchDir(PathBuf);
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1213,6 +2341,10 @@ pub fn emit_native_cli_wrapper_c(opts: &NativeCliWrapperOpts) -> String {
 #include <string.h>
 #include <sys/resource.h>
 
+#ifndef X07_ARENA_CAP
+#define X07_ARENA_CAP X07_MEM_CAP
+#endif
+
 static void x07_setenv(const char* k, const char* v, int overwrite) {{
   setenv(k, v, overwrite);
 }}
@@ -1278,15 +2410,17 @@ int main(int argc, char** argv) {{
     off += (uint32_t)n;
   }}
 
-  uint32_t arena_cap = (uint32_t)(X07_MEM_CAP);
-  uint8_t* arena = (uint8_t*)calloc(1, (size_t)arena_cap);
+  // The arena may be reserved larger than the enforced heap cap.
+  size_t arena_cap = (size_t)(X07_ARENA_CAP);
+  uint32_t mem_cap = (uint32_t)(X07_MEM_CAP);
+  uint8_t* arena = (uint8_t*)calloc(1, arena_cap);
   if (!arena) {{
     fprintf(stderr, "x07 bundle: arena calloc failed\\n");
     free(in);
     return 2;
   }}
 
-  bytes_t out = x07_solve_v2(arena, arena_cap, in, in_len);
+  bytes_t out = x07_solve_v2(arena, mem_cap, in, in_len);
   free(in);
 
   if ({max_output_bytes}u && out.len > {max_output_bytes}u) {{
@@ -1309,20 +2443,39 @@ int main(int argc, char** argv) {{
     )
 }
 
+/// Builds a standalone CLI executable at `compiled_out`. When `bundle_c_out`
+/// is set, a successful build also writes `combined_c` there (atomically,
+/// independent of `X07_KEEP_C`); its parent dir is created up front so an
+/// unusable path fails before any compile work.
 pub fn compile_bundle_exe(
     program: &[u8],
     compile_options: &compile::CompileOptions,
     toolchain: &NativeToolchainConfig,
     compiled_out: &Path,
     wrapper: &NativeCliWrapperOpts,
+    bundle_c_out: Option<&Path>,
 ) -> Result<BundleCompileOutput> {
     let lang_id = language::LANG_ID.to_string();
 
+    if let Some(parent) = bundle_c_out
+        .and_then(Path::parent)
+        .filter(|p| !p.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("create bundle C output dir: {}", parent.display()))?;
+    }
+
     let mut compile_options = compile_options.clone();
     compile_options.emit_main = false;
     compile_options.freestanding = false;
-
-    let compile_out = match compile::compile_program_to_c_with_meta(program, &compile_options) {
+    let options_fingerprint = CompileOptionsFingerprint::compute(&compile_options)
+        .context("fingerprint compile options")?
+        .to_string();
+
+    let codegen_start = Instant::now();
+    let compile_out = compile::compile_program_to_c_with_meta(program, &compile_options);
+    let codegen_ms = codegen_start.elapsed().as_millis() as u64;
+    let compile_out = match compile_out {
         Ok(out) => out,
         Err(err) => {
             let mut msg = format!("{:?}: {}", err.kind, err.message);
@@ -1348,10 +2501,16 @@ pub fn compile_bundle_exe(
                     lang_id,
                     native_requires: empty_native_requires(&compile_options),
                     c_source_size: 0,
+                    codegen_ms,
+                    memo_hit: false,
                     compiled_exe: None,
                     compiled_exe_size: None,
                     compile_error: Some(msg),
                     compile_diagnostics,
+                    options_fingerprint,
+                    native_backend_resolutions: Vec::new(),
+                    cc_warnings: Vec::new(),
+                    native_cache_key: None,
                     stdout: Vec::new(),
                     stderr: Vec::new(),
                     fuel_used: None,
@@ -1369,10 +2528,16 @@ pub fn compile_bundle_exe(
     let native_requires = compile_out.native_requires;
 
     let mut cc_args = toolchain.extra_cc_args.clone();
+    let mut native_backend_resolutions = Vec::new();
+    let mut cache_salt = toolchain.cache_salt.clone();
     if !native_requires.requires.is_empty() {
         let root = workspace_root()?;
-        if let Err(err) = native_backends::plan_native_link_argv(&root, &native_requires)
-            .map(|argv| cc_args.extend(argv))
+        if let Err(err) =
+            native_backends::plan_native_link_argv(&root, &native_requires).map(|plan| {
+                cc_args.extend(plan.argv);
+                native_backend_resolutions = plan.resolutions;
+                cache_salt.extend(plan.cache_salt);
+            })
         {
             return Ok(BundleCompileOutput {
                 compile: CompilerResult {
@@ -1381,10 +2546,16 @@ pub fn compile_bundle_exe(
                     lang_id,
                     native_requires,
                     c_source_size: freestanding_c.len(),
+                    codegen_ms,
+                    memo_hit: false,
                     compiled_exe: None,
                     compiled_exe_size: None,
                     compile_error: Some(format_native_backend_error(&err)),
                     compile_diagnostics: Vec::new(),
+                    options_fingerprint,
+                    native_backend_resolutions: Vec::new(),
+                    cc_warnings: Vec::new(),
+                    native_cache_key: None,
                     stdout: Vec::new(),
                     stderr: Vec::new(),
                     fuel_used: Some(compile_stats.fuel_used),
@@ -1403,6 +2574,8 @@ pub fn compile_bundle_exe(
 
     let mut toolchain = toolchain.clone();
     toolchain.extra_cc_args = cc_args;
+    toolchain.options_fingerprint = Some(options_fingerprint.clone());
+    toolchain.cache_salt = cache_salt;
 
     let tool = compile_c_to_exe_with_config(&combined_c, &toolchain)?;
     if !tool.ok {
@@ -1413,10 +2586,16 @@ pub fn compile_bundle_exe(
                 lang_id,
                 native_requires,
                 c_source_size: combined_c.len(),
+                codegen_ms,
+                memo_hit: false,
                 compiled_exe: None,
                 compiled_exe_size: None,
                 compile_error: Some(format!("C toolchain failed (exit={})", tool.exit_status)),
                 compile_diagnostics: Vec::new(),
+                options_fingerprint,
+                native_backend_resolutions,
+                cc_warnings: Vec::new(),
+                native_cache_key: Some(tool.cache_key),
                 stdout: tool.stdout,
                 stderr: tool.stderr,
                 fuel_used: Some(compile_stats.fuel_used),
@@ -1446,6 +2625,11 @@ pub fn compile_bundle_exe(
 
     let exe_size = std::fs::metadata(compiled_out).map(|m| m.len()).ok();
 
+    if let Some(c_out) = bundle_c_out {
+        write_file_atomic(c_out, combined_c.as_bytes())
+            .with_context(|| format!("write bundle C source: {}", c_out.display()))?;
+    }
+
     Ok(BundleCompileOutput {
         compile: CompilerResult {
             ok: true,
@@ -1453,10 +2637,16 @@ pub fn compile_bundle_exe(
             lang_id,
             native_requires,
             c_source_size: combined_c.len(),
+            codegen_ms,
+            memo_hit: false,
             compiled_exe: Some(compiled_out.to_path_buf()),
             compiled_exe_size: exe_size,
             compile_error: None,
             compile_diagnostics: Vec::new(),
+            options_fingerprint,
+            native_backend_resolutions,
+            cc_warnings: parse_cc_warnings(&tool.stderr),
+            native_cache_key: Some(tool.cache_key),
             stdout: tool.stdout,
             stderr: tool.stderr,
             fuel_used: Some(compile_stats.fuel_used),
@@ -1468,44 +2658,89 @@ pub fn compile_bundle_exe(
     })
 }
 
-pub fn compile_c_to_exe_with_config(
-    c_source: &str,
-    config: &NativeToolchainConfig,
-) -> Result<ToolchainOutput> {
-    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+pub const NATIVE_CACHE_META_FILE: &str = "meta.json";
+pub const NATIVE_CACHE_META_SCHEMA_VERSION: &str = "x07.native_cache_meta@0.1.0";
+
+/// Provenance written next to each cached `solver` (`<cache>/<key>/meta.json`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NativeCacheMeta {
+    pub schema_version: String,
+    pub cc: String,
+    pub cc_version: String,
+    pub cc_args: Vec<String>,
+    pub world_tag: String,
+    pub created_unix_ms: u64,
+    pub c_source_sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options_fingerprint: Option<String>,
+}
+
+pub fn read_native_cache_meta(entry_dir: &Path) -> Result<NativeCacheMeta> {
+    let path = entry_dir.join(NATIVE_CACHE_META_FILE);
+    let bytes = std::fs::read(&path)
+        .with_context(|| format!("read native cache meta: {}", path.display()))?;
+    serde_json::from_slice(&bytes)
+        .with_context(|| format!("parse native cache meta: {}", path.display()))
+}
+
+/// Installs `tmp` at `dst` unless another writer got there first.
+///
+/// Uses a hard link as an atomic create-new so concurrent writers racing on the
+/// same cache key never replace each other's file; `tmp` is always removed.
+/// Returns `true` when this call installed `dst`.
+fn persist_file_once(tmp: &Path, dst: &Path) -> Result<bool> {
+    let _cleanup_tmp = CleanupFile {
+        path: tmp.to_path_buf(),
+    };
+    match std::fs::hard_link(tmp, dst) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(_) if dst.exists() => Ok(false),
+        // Filesystems without hard links: fall back to rename, which is still atomic
+        // per file but lets the last writer win.
+        Err(_) => match std::fs::rename(tmp, dst) {
+            Ok(()) => Ok(true),
+            Err(_) if dst.exists() => Ok(false),
+            Err(err) => {
+                Err(err).with_context(|| format!("persist {} to {}", tmp.display(), dst.display()))
+            }
+        },
+    }
+}
 
+/// Native cache key (the entry dir name under [`cache_dir`]) that
+/// [`compile_c_to_exe_with_config`] uses for `c_source` under `config`.
+///
+/// Covers the C source, `cc --version`, the toolchain config, `X07_CC_ARGS`
+/// and the contents of any file named in `X07_CC_ARGS` or `extra_cc_args`,
+/// so it can be computed without compiling to check or share cache entries.
+pub fn native_cache_key(c_source: &str, config: &NativeToolchainConfig) -> Result<String> {
     let cc = std::env::var_os("X07_CC").unwrap_or_else(|| OsStr::new("cc").to_os_string());
-    let cc_args = std::env::var("X07_CC_ARGS").unwrap_or_default();
-    let keep_c = std::env::var("X07_KEEP_C")
-        .map(|v| {
-            let v = v.trim().to_ascii_lowercase();
-            !(v.is_empty() || v == "0" || v == "false" || v == "no" || v == "off")
-        })
-        .unwrap_or(false);
+    native_cache_key_with_cc_version(c_source, config, &cc_version_output(&cc))
+}
 
+fn cc_version_output(cc: &OsStr) -> Vec<u8> {
     let mut cc_version = Vec::new();
-    if let Ok(out) = Command::new(&cc).arg("--version").output() {
+    if let Ok(out) = Command::new(cc).arg("--version").output() {
         cc_version.extend_from_slice(&out.stdout);
         cc_version.extend_from_slice(&out.stderr);
     }
+    cc_version
+}
 
-    let mut hasher = Sha256::new();
-    hasher.update(b"x07-native-cache-v2\0");
-    hasher.update(c_source.as_bytes());
-    hasher.update(b"\0");
-    hasher.update(&cc_version);
-    hasher.update(b"\0");
-    hasher.update(config.world_tag.as_bytes());
+/// Hashes the `cc --version` output, `X07_CC_ARGS` and the contents of the
+/// files it names: the toolchain inputs that change an exe built from the
+/// same C source.
+pub(crate) fn hash_cc_toolchain(hasher: &mut Sha256) -> Result<()> {
+    let cc = std::env::var_os("X07_CC").unwrap_or_else(|| OsStr::new("cc").to_os_string());
+    hasher.update(cc.to_string_lossy().as_bytes());
     hasher.update(b"\0");
-    hasher.update(config.fuel_init.to_le_bytes());
-    hasher.update(config.mem_cap_bytes.to_le_bytes());
-    hasher.update([config.debug_borrow_checks as u8]);
-    hasher.update([
-        config.enable_fs as u8,
-        config.enable_rr as u8,
-        config.enable_kv as u8,
-    ]);
+    hasher.update(cc_version_output(&cc));
     hasher.update(b"\0");
+    hash_cc_args(hasher, &std::env::var("X07_CC_ARGS").unwrap_or_default())
+}
+
+fn hash_cc_args(hasher: &mut Sha256, cc_args: &str) -> Result<()> {
     hasher.update(cc_args.trim().as_bytes());
     hasher.update(b"\0");
     for a in cc_args.split_whitespace() {
@@ -1527,6 +2762,67 @@ pub fn compile_c_to_exe_with_config(
             hasher.update(b"\0");
         }
     }
+    Ok(())
+}
+
+fn native_cache_key_with_cc_version(
+    c_source: &str,
+    config: &NativeToolchainConfig,
+    cc_version: &[u8],
+) -> Result<String> {
+    let cc_args = std::env::var("X07_CC_ARGS").unwrap_or_default();
+    let sanitize_flag = sanitize_cc_flag(&config.sanitizers)?;
+    let arena_cap_bytes = native_arena_cap_bytes(config)?;
+    let define_flags = extra_define_flags(&config.extra_defines)?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"x07-native-cache-v2\0");
+    hasher.update(c_source.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(cc_version);
+    hasher.update(b"\0");
+    hasher.update(config.world_tag.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(config.fuel_init.to_le_bytes());
+    hasher.update(config.mem_cap_bytes.to_le_bytes());
+    hasher.update(arena_cap_bytes.to_le_bytes());
+    hasher.update([config.debug_borrow_checks as u8]);
+    hasher.update([
+        config.enable_fs as u8,
+        config.enable_rr as u8,
+        config.enable_kv as u8,
+    ]);
+    hasher.update(b"\0");
+    if let Some(flag) = &sanitize_flag {
+        hasher.update(b"sanitize\0");
+        hasher.update(flag.as_bytes());
+        hasher.update(b"\0");
+    }
+    if !define_flags.is_empty() {
+        hasher.update(b"defines\0");
+        for flag in &define_flags {
+            hasher.update(flag.as_bytes());
+            hasher.update(b"\0");
+        }
+    }
+    if config.warnings_as_errors {
+        hasher.update(b"werror\0");
+        for flag in CC_WARNINGS_ALLOWLIST {
+            hasher.update(flag.as_bytes());
+            hasher.update(b"\0");
+        }
+    }
+    if let Some(fp) = &config.options_fingerprint {
+        hasher.update(b"options\0");
+        hasher.update(fp.as_bytes());
+        hasher.update(b"\0");
+    }
+    if !config.cache_salt.is_empty() {
+        hasher.update(b"salt\0");
+        hasher.update((config.cache_salt.len() as u64).to_le_bytes());
+        hasher.update(&config.cache_salt);
+    }
+    hash_cc_args(&mut hasher, &cc_args)?;
     for a in &config.extra_cc_args {
         hasher.update(a.as_bytes());
         hasher.update(b"\0");
@@ -1549,26 +2845,118 @@ pub fn compile_c_to_exe_with_config(
             hasher.update(b"\0");
         }
     }
-    let key = hex_lower(&hasher.finalize());
+    Ok(hex_lower(&hasher.finalize()))
+}
+
+fn native_arena_cap_bytes(config: &NativeToolchainConfig) -> Result<usize> {
+    // The runtime addresses its heap with `uint32_t`; a larger cap would be
+    // truncated in the generated C.
+    if config.mem_cap_bytes > u32::MAX as usize {
+        anyhow::bail!(
+            "mem_cap_bytes ({}) must be <= {}",
+            config.mem_cap_bytes,
+            u32::MAX
+        );
+    }
+    let arena_cap_bytes = config.arena_cap_bytes.unwrap_or(config.mem_cap_bytes);
+    if arena_cap_bytes > u32::MAX as usize {
+        anyhow::bail!(
+            "arena_cap_bytes ({arena_cap_bytes}) must be <= {}",
+            u32::MAX
+        );
+    }
+    if arena_cap_bytes < config.mem_cap_bytes {
+        anyhow::bail!(
+            "arena_cap_bytes ({arena_cap_bytes}) must be >= mem_cap_bytes ({})",
+            config.mem_cap_bytes
+        );
+    }
+    Ok(arena_cap_bytes)
+}
+
+/// Validates `defines` and renders them as `-DKEY=VALUE` flags, in order.
+///
+/// Keys must match `[A-Z_][A-Z0-9_]*` and must not start with `X07_`, which is
+/// reserved for the defines the runner emits itself. Values must not contain
+/// whitespace, control characters or shell metacharacters.
+pub fn extra_define_flags(defines: &[(String, String)]) -> Result<Vec<String>> {
+    const VALUE_FORBIDDEN: &[char] = &[
+        '\\', '\'', '"', '`', '$', ';', '&', '|', '<', '>', '(', ')', '{', '}', '[', ']', '*', '?',
+        '!', '#', '~',
+    ];
+
+    let mut flags = Vec::with_capacity(defines.len());
+    for (key, value) in defines {
+        let mut chars = key.chars();
+        let key_ok = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_uppercase() || c == '_')
+            && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        if !key_ok {
+            anyhow::bail!("invalid define name {key:?}: expected [A-Z_][A-Z0-9_]*");
+        }
+        if key.starts_with("X07_") {
+            anyhow::bail!("define {key} is reserved: X07_* defines are set by the runner");
+        }
+        if let Some(c) = value
+            .chars()
+            .find(|c| c.is_whitespace() || c.is_control() || VALUE_FORBIDDEN.contains(c))
+        {
+            anyhow::bail!("invalid value for define {key}: {c:?} is not allowed");
+        }
+        flags.push(format!("-D{key}={value}"));
+    }
+    Ok(flags)
+}
+
+pub fn compile_c_to_exe_with_config(
+    c_source: &str,
+    config: &NativeToolchainConfig,
+) -> Result<ToolchainOutput> {
+    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let cc = std::env::var_os("X07_CC").unwrap_or_else(|| OsStr::new("cc").to_os_string());
+    let cc_args = std::env::var("X07_CC_ARGS").unwrap_or_default();
+    let keep_c = std::env::var("X07_KEEP_C")
+        .map(|v| {
+            let v = v.trim().to_ascii_lowercase();
+            !(v.is_empty() || v == "0" || v == "false" || v == "no" || v == "off")
+        })
+        .unwrap_or(false);
+    let sanitize_flag = sanitize_cc_flag(&config.sanitizers)?;
+    let arena_cap_bytes = native_arena_cap_bytes(config)?;
+    let define_flags = extra_define_flags(&config.extra_defines)?;
 
-    let dir = cache_dir()?.join(&key);
+    let cc_version = cc_version_output(&cc);
+    let key = native_cache_key_with_cc_version(c_source, config, &cc_version)?;
+
+    let dir = if config.bypass_cache {
+        let pid = std::process::id();
+        let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("x07-native-nocache-{pid}-{n}"));
+        // A dead process with the same pid may have left it behind.
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    } else {
+        native_cache_root(config)?.join(&key)
+    };
     std::fs::create_dir_all(&dir)
         .with_context(|| format!("create cache dir: {}", dir.display()))?;
+    let scratch_dir = config
+        .bypass_cache
+        .then(|| Arc::new(ScratchDir(dir.clone())));
 
     let exe_path = dir.join("solver");
     let keep_c_path = dir.join("solver.c");
 
-    if exe_path.exists() {
+    if !config.bypass_cache && exe_path.exists() {
         if keep_c && !keep_c_path.exists() {
             let pid = std::process::id();
             let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
             let tmp_src_path = dir.join(format!("solver_{pid}_{n}.c"));
-            if std::fs::write(&tmp_src_path, c_source.as_bytes()).is_ok()
-                && std::fs::rename(&tmp_src_path, &keep_c_path).is_err()
-            {
-                if !keep_c_path.exists() {
-                    let _ = std::fs::copy(&tmp_src_path, &keep_c_path);
-                }
+            if std::fs::write(&tmp_src_path, c_source.as_bytes()).is_ok() {
+                let _ = persist_file_once(&tmp_src_path, &keep_c_path);
+            } else {
                 let _ = std::fs::remove_file(&tmp_src_path);
             }
         }
@@ -1578,6 +2966,9 @@ pub fn compile_c_to_exe_with_config(
             stdout: Vec::new(),
             stderr: Vec::new(),
             exe_path: Some(exe_path),
+            cache_hit: true,
+            cache_key: key,
+            scratch_dir: None,
         });
     }
 
@@ -1603,6 +2994,7 @@ pub fn compile_c_to_exe_with_config(
     }
     cmd.arg(format!("-DX07_FUEL_INIT={}ULL", config.fuel_init));
     cmd.arg(format!("-DX07_MEM_CAP={}u", config.mem_cap_bytes));
+    cmd.arg(format!("-DX07_ARENA_CAP={arena_cap_bytes}u"));
     if config.debug_borrow_checks {
         cmd.arg("-DX07_DEBUG_BORROW=1");
     }
@@ -1618,6 +3010,15 @@ pub fn compile_c_to_exe_with_config(
         "-DX07_ENABLE_KV={}",
         if config.enable_kv { 1 } else { 0 }
     ));
+    cmd.arg(format!("-DX07_SEED_ENV=\"{ENV_SOLVE_SEED}\""));
+    for flag in &define_flags {
+        cmd.arg(flag);
+    }
+    if let Some(flag) = &sanitize_flag {
+        cmd.arg(flag);
+        cmd.arg("-fno-omit-frame-pointer");
+        cmd.arg("-g");
+    }
 
     cmd.arg(&tmp_src_path);
     cmd.arg("-o");
@@ -1630,6 +3031,10 @@ pub fn compile_c_to_exe_with_config(
     for a in &config.extra_cc_args {
         cmd.arg(a);
     }
+    if config.warnings_as_errors {
+        cmd.arg("-Werror");
+        cmd.args(CC_WARNINGS_ALLOWLIST);
+    }
 
     let cmd_program = cmd.get_program().to_string_lossy().to_string();
 
@@ -1670,34 +3075,49 @@ pub fn compile_c_to_exe_with_config(
     }
 
     if keep_c {
-        if !keep_c_path.exists() {
-            if std::fs::rename(&tmp_src_path, &keep_c_path).is_err() {
-                if !keep_c_path.exists() {
-                    let _ = std::fs::copy(&tmp_src_path, &keep_c_path);
-                }
-                let _ = std::fs::remove_file(&tmp_src_path);
-            }
-        } else {
-            let _ = std::fs::remove_file(&tmp_src_path);
-        }
+        persist_file_once(&tmp_src_path, &keep_c_path)
+            .with_context(|| format!("persist C source: {}", keep_c_path.display()))?;
     } else {
         let _ = std::fs::remove_file(&tmp_src_path);
     }
 
     let final_exe_path = if ok {
-        match std::fs::rename(&tmp_exe_path, &exe_path) {
-            Ok(()) => exe_path.clone(),
-            Err(_) if exe_path.exists() => {
-                let _ = std::fs::remove_file(&tmp_exe_path);
-                exe_path.clone()
-            }
-            Err(err) => {
-                let copy = std::fs::copy(&tmp_exe_path, &exe_path);
-                let _ = std::fs::remove_file(&tmp_exe_path);
-                copy.with_context(|| format!("finalize compiled artifact: {err}"))?;
-                exe_path.clone()
-            }
-        }
+        persist_file_once(&tmp_exe_path, &exe_path)
+            .with_context(|| format!("finalize compiled artifact: {}", exe_path.display()))?;
+        let meta = NativeCacheMeta {
+            schema_version: NATIVE_CACHE_META_SCHEMA_VERSION.to_string(),
+            cc: cmd_program.clone(),
+            cc_version: String::from_utf8_lossy(&cc_version)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            cc_args: cmd
+                .get_args()
+                .filter(|a| {
+                    *a != OsStr::new("-o")
+                        && *a != tmp_src_path.as_os_str()
+                        && *a != tmp_exe_path.as_os_str()
+                })
+                .map(|a| a.to_string_lossy().to_string())
+                .collect(),
+            world_tag: config.world_tag.clone(),
+            created_unix_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            c_source_sha256: hex_lower(&Sha256::digest(c_source.as_bytes())),
+            options_fingerprint: config.options_fingerprint.clone(),
+        };
+        let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp_meta_path = dir.join(format!("meta_{pid}_{n}.json"));
+        let meta_bytes = serde_json::to_vec_pretty(&meta).context("serialize native cache meta")?;
+        std::fs::write(&tmp_meta_path, meta_bytes)
+            .with_context(|| format!("write native cache meta: {}", tmp_meta_path.display()))?;
+        persist_file_once(&tmp_meta_path, &dir.join(NATIVE_CACHE_META_FILE))
+            .context("persist native cache meta")?;
+        exe_path.clone()
     } else {
         let _ = std::fs::remove_file(&tmp_exe_path);
         exe_path.clone()
@@ -1709,6 +3129,9 @@ pub fn compile_c_to_exe_with_config(
         stdout: out.stdout,
         stderr,
         exe_path: ok.then_some(final_exe_path),
+        cache_hit: false,
+        cache_key: key,
+        scratch_dir,
     })
 }
 
@@ -1762,29 +3185,82 @@ mod tests {
         maybe_add_linux_libm_for_sqlite(&native_requires, &mut cc_args);
         assert!(cc_args.last().is_some_and(|a| a == "-lm"));
     }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_stages_fixture_files_readonly_and_cleans_them_up() {
+        let fixture = make_temp_dir("readonly_fixture");
+        std::fs::create_dir_all(fixture.join("nested")).unwrap();
+        std::fs::write(fixture.join("a.txt"), b"a").unwrap();
+        std::fs::write(fixture.join("nested/b.txt"), b"b").unwrap();
+
+        let config = RunnerConfig {
+            world: WorldId::SolveFs,
+            fixture_fs_dir: Some(fixture.clone()),
+            solve_fuel: 1,
+            max_memory_bytes: 1 << 20,
+            max_output_bytes: 1 << 20,
+            cpu_time_limit_seconds: 1,
+            require_sandbox: vec![SandboxFeature::ReadonlyFixtures],
+            ..Default::default()
+        };
+        let tmp = TempDir::new("x07_readonly_test").unwrap();
+        let run_dir = tmp.path().to_path_buf();
+        setup_run_dir(&tmp, &config).unwrap();
+
+        for rel in ["a.txt", "nested/b.txt"] {
+            let path = run_dir.join(rel);
+            assert!(std::fs::metadata(&path).unwrap().permissions().readonly());
+            assert!(std::fs::write(&path, b"changed").is_err(), "{rel}");
+            assert!(std::fs::remove_file(&path).is_err(), "{rel}");
+        }
+        // The dir itself stays writable; only staged files are protected.
+        std::fs::write(run_dir.join("out.txt"), b"new").unwrap();
+
+        let caps = effective_sandbox_caps(&config).unwrap();
+        assert!(caps.readonly_fixtures && caps.process_group_kill);
+        assert!(caps.missing(&config.require_sandbox).is_empty());
+
+        drop(tmp);
+        assert!(!run_dir.exists());
+        let _ = std::fs::remove_dir_all(&fixture);
+    }
 }
 
 fn compile_c_to_exe(
     c_source: &str,
     config: &RunnerConfig,
     options: &compile::CompileOptions,
+    options_fingerprint: &str,
     extra_cc_args: &[String],
+    cache_salt: &[u8],
 ) -> Result<ToolchainOutput> {
     let toolchain = NativeToolchainConfig {
         world_tag: options.world.as_str().to_string(),
         fuel_init: config.solve_fuel,
         mem_cap_bytes: config.max_memory_bytes,
+        arena_cap_bytes: None,
         debug_borrow_checks: config.debug_borrow_checks,
         enable_fs: options.enable_fs,
         enable_rr: options.enable_rr,
         enable_kv: options.enable_kv,
         extra_cc_args: extra_cc_args.to_vec(),
+        extra_defines: Vec::new(),
+        warnings_as_errors: false,
+        sanitizers: config.sanitizers.clone(),
+        options_fingerprint: Some(options_fingerprint.to_string()),
+        cache_salt: cache_salt.to_vec(),
+        cache_dir_override: None,
+        bypass_cache: false,
     };
     compile_c_to_exe_with_config(c_source, &toolchain)
 }
 
 struct TempDir {
     path: PathBuf,
+    /// Released after the dir is removed, so the next run with the token
+    /// finds it gone.
+    _token_lease: Option<run_dir_token::RunDirTokenLease>,
 }
 
 impl TempDir {
@@ -1797,7 +3273,12 @@ impl TempDir {
             let n = COUNTER.fetch_add(1, Ordering::Relaxed);
             let path = base.join(format!("{prefix}_{pid}_{n}"));
             match std::fs::create_dir(&path) {
-                Ok(()) => return Ok(Self { path }),
+                Ok(()) => {
+                    return Ok(Self {
+                        path,
+                        _token_lease: None,
+                    })
+                }
                 Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
                 Err(err) => {
                     return Err(err).with_context(|| format!("create temp dir: {}", path.display()))
@@ -1807,6 +3288,25 @@ impl TempDir {
         anyhow::bail!("failed to create unique temp dir under {}", base.display())
     }
 
+    /// The run dir for [`RunnerConfig::stable_run_dir_token`]. Fails rather
+    /// than picking another name when the token or its dir is taken.
+    fn for_token(token: &str) -> Result<Self> {
+        let lease = run_dir_token::RunDirTokenLease::acquire(token)?;
+        let path = run_dir_token::run_dir_for_token(token);
+        match std::fs::create_dir(&path) {
+            Ok(()) => Ok(Self {
+                path,
+                _token_lease: Some(lease),
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Err(RunDirTokenInUse {
+                token: token.to_string(),
+                path,
+            }
+            .into()),
+            Err(err) => Err(err).with_context(|| format!("create run dir: {}", path.display())),
+        }
+    }
+
     fn path(&self) -> &Path {
         &self.path
     }
@@ -1814,178 +3314,201 @@ impl TempDir {
 
 impl Drop for TempDir {
     fn drop(&mut self) {
+        #[cfg(windows)]
+        let _ = set_readonly_recursive(&self.path, false);
         let _ = std::fs::remove_dir_all(&self.path);
     }
 }
 
+/// Fixture failures come back as a bare [`StagingError`] (no added context)
+/// so [`run_artifact_file`] can downcast it into the result.
 fn setup_run_dir(tmp: &TempDir, config: &RunnerConfig) -> Result<()> {
+    if config.kv_seed_inline.is_some()
+        && !matches!(config.world, WorldId::SolveKv | WorldId::SolveFull)
+    {
+        anyhow::bail!(
+            "kv_seed_inline is only supported for solve-kv and solve-full, got {}",
+            config.world.as_str()
+        );
+    }
     match config.world {
-        WorldId::SolvePure => Ok(()),
-        WorldId::SolveFs => {
-            let fixture = config
-                .fixture_fs_dir
-                .as_ref()
-                .context("missing fixture_fs_dir for solve-fs")?;
-            let fs_root = config
-                .fixture_fs_root
-                .as_deref()
-                .unwrap_or_else(|| Path::new(""));
-            ensure_safe_rel_path(fs_root)?;
-            let fs_src = fixture.join(fs_root);
-            copy_dir_contents(&fs_src, tmp.path())
-                .with_context(|| format!("copy fixture dir: {}", fs_src.display()))?;
-
-            if let Some(latency_index) = config.fixture_fs_latency_index.as_deref() {
-                ensure_safe_rel_path(latency_index)?;
-                let src = fixture.join(latency_index);
-                let dst = tmp.path().join(".x07_fs").join("latency.evfslat");
-                write_fs_latency_evfslat(&src, &dst)
-                    .with_context(|| format!("generate fs latency index from {}", src.display()))?;
-            }
-            #[cfg(unix)]
-            make_readonly_recursive(tmp.path())?;
-            Ok(())
-        }
-        WorldId::SolveRr => {
-            let fixture = config
-                .fixture_rr_dir
-                .as_ref()
-                .context("missing fixture_rr_dir for solve-rr")?;
-            let rr_dir = tmp.path().join(".x07_rr");
-            std::fs::create_dir(&rr_dir)
-                .with_context(|| format!("create rr fixture dir: {}", rr_dir.display()))?;
-            copy_dir_contents(fixture, &rr_dir)
-                .with_context(|| format!("copy rr fixture dir: {}", fixture.display()))?;
-            #[cfg(unix)]
-            make_readonly_recursive(tmp.path())?;
-            Ok(())
-        }
-        WorldId::SolveKv => {
-            let fixture = config
-                .fixture_kv_dir
-                .as_ref()
-                .context("missing fixture_kv_dir for solve-kv")?;
-            let kv_dir = tmp.path().join(".x07_kv");
-            std::fs::create_dir(&kv_dir)
-                .with_context(|| format!("create kv fixture dir: {}", kv_dir.display()))?;
-            copy_dir_contents(fixture, &kv_dir)
-                .with_context(|| format!("copy kv fixture dir: {}", fixture.display()))?;
-
-            let seed_evkv = kv_dir.join("seed.evkv");
-            if !seed_evkv.is_file() {
-                let seed_json = config
-                    .fixture_kv_seed
-                    .as_deref()
-                    .context("missing fixture_kv_seed for solve-kv (seed.evkv not present)")?;
-                ensure_safe_rel_path(seed_json)?;
-                let src = fixture.join(seed_json);
-                let latency_dst = kv_dir.join("latency.evkvlat");
-                write_kv_seed_evkv_and_latency(&src, &seed_evkv, &latency_dst)
-                    .with_context(|| format!("generate kv seed from {}", src.display()))?;
-            }
-            #[cfg(unix)]
-            make_readonly_recursive(tmp.path())?;
-            Ok(())
-        }
+        WorldId::SolvePure => {}
+        WorldId::SolveFs => stage_fs_fixture(tmp, config)?,
+        WorldId::SolveRr => stage_rr_fixture(tmp, config)?,
+        WorldId::SolveKv => stage_kv_fixture(tmp, config)?,
         WorldId::SolveFull => {
-            let fs_fixture = config
-                .fixture_fs_dir
-                .as_ref()
-                .context("missing fixture_fs_dir for solve-full")?;
-            let fs_root = config
-                .fixture_fs_root
-                .as_deref()
-                .unwrap_or_else(|| Path::new(""));
-            ensure_safe_rel_path(fs_root)?;
-            let fs_src = fs_fixture.join(fs_root);
-            copy_dir_contents(&fs_src, tmp.path())
-                .with_context(|| format!("copy fixture dir: {}", fs_src.display()))?;
-
-            if let Some(latency_index) = config.fixture_fs_latency_index.as_deref() {
-                ensure_safe_rel_path(latency_index)?;
-                let src = fs_fixture.join(latency_index);
-                let dst = tmp.path().join(".x07_fs").join("latency.evfslat");
-                write_fs_latency_evfslat(&src, &dst)
-                    .with_context(|| format!("generate fs latency index from {}", src.display()))?;
-            }
-
-            let rr_fixture = config
-                .fixture_rr_dir
-                .as_ref()
-                .context("missing fixture_rr_dir for solve-full")?;
-            let rr_dir = tmp.path().join(".x07_rr");
-            std::fs::create_dir(&rr_dir)
-                .with_context(|| format!("create rr fixture dir: {}", rr_dir.display()))?;
-            copy_dir_contents(rr_fixture, &rr_dir)
-                .with_context(|| format!("copy rr fixture dir: {}", rr_fixture.display()))?;
-
-            let kv_fixture = config
-                .fixture_kv_dir
-                .as_ref()
-                .context("missing fixture_kv_dir for solve-full")?;
-            let kv_dir = tmp.path().join(".x07_kv");
-            std::fs::create_dir(&kv_dir)
-                .with_context(|| format!("create kv fixture dir: {}", kv_dir.display()))?;
-            copy_dir_contents(kv_fixture, &kv_dir)
-                .with_context(|| format!("copy kv fixture dir: {}", kv_fixture.display()))?;
-
-            let seed_evkv = kv_dir.join("seed.evkv");
-            if !seed_evkv.is_file() {
-                let seed_json = config
-                    .fixture_kv_seed
-                    .as_deref()
-                    .context("missing fixture_kv_seed for solve-full (seed.evkv not present)")?;
-                ensure_safe_rel_path(seed_json)?;
-                let src = kv_fixture.join(seed_json);
-                let latency_dst = kv_dir.join("latency.evkvlat");
-                write_kv_seed_evkv_and_latency(&src, &seed_evkv, &latency_dst)
-                    .with_context(|| format!("generate kv seed from {}", src.display()))?;
-            }
-
-            #[cfg(unix)]
-            make_readonly_recursive(tmp.path())?;
-            Ok(())
+            stage_fs_fixture(tmp, config)?;
+            stage_rr_fixture(tmp, config)?;
+            stage_kv_fixture(tmp, config)?;
         }
         other => anyhow::bail!(
             "x07-host-runner supports only deterministic solve worlds, got {}",
             other.as_str()
         ),
     }
+    if let Some(hook) = &config.stage_hook {
+        (hook.0)(tmp.path()).context("stage_hook")?;
+    }
+    if config.world == WorldId::SolvePure {
+        return Ok(());
+    }
+    #[cfg(any(unix, windows))]
+    make_readonly_recursive(tmp.path())?;
+    Ok(())
 }
 
-fn copy_dir_contents(src_dir: &Path, dst_dir: &Path) -> Result<()> {
-    for entry in
-        std::fs::read_dir(src_dir).with_context(|| format!("read_dir: {}", src_dir.display()))?
-    {
-        let entry = entry.context("read_dir entry")?;
-        let file_type = entry.file_type().context("file_type")?;
-        let src_path = entry.path();
-        let dst_path = dst_dir.join(entry.file_name());
-        copy_tree(&src_path, &dst_path, &file_type)?;
+/// Copies `fixture_fs_dir/fixture_fs_root` into the run dir and writes
+/// `.x07_fs/latency.evfslat` when `fixture_fs_latency_index` is set.
+fn stage_fs_fixture(tmp: &TempDir, config: &RunnerConfig) -> Result<(), StagingError> {
+    let fixture = config
+        .fixture_fs_dir
+        .as_ref()
+        .filter(|d| d.is_dir())
+        .ok_or_else(|| StagingError::missing("fixture_fs_dir"))?;
+    let fs_root = config
+        .fixture_fs_root
+        .as_deref()
+        .unwrap_or_else(|| Path::new(""));
+    staging::safe_rel_path(fs_root)?;
+    let fs_src = fixture.join(fs_root);
+    if !fs_src.is_dir() {
+        return Err(StagingError::missing("fixture_fs_root"));
+    }
+    staging::copy_dir_contents(&fs_src, tmp.path())?;
+    for overlay in &config.fixture_fs_overlays {
+        staging::safe_rel_path(overlay)?;
+        let src = fixture.join(overlay);
+        if !src.is_dir() {
+            return Err(StagingError::missing("fixture_fs_overlays"));
+        }
+        staging::overlay_dir_contents(&src, tmp.path(), overlay)?;
+    }
+
+    if let Some(latency_index) = config.fixture_fs_latency_index.as_deref() {
+        staging::safe_rel_path(latency_index)?;
+        let src = fixture.join(latency_index);
+        let dst = tmp.path().join(".x07_fs").join("latency.evfslat");
+        write_fs_latency_evfslat(&src, &dst)
+            .map_err(|e| StagingError::index_failed(IndexKind::FsLatency, Some(&src), &e))?;
     }
     Ok(())
 }
 
-fn copy_tree(src: &Path, dst: &Path, src_type: &std::fs::FileType) -> Result<()> {
-    if src_type.is_dir() {
-        std::fs::create_dir(dst).with_context(|| format!("create_dir: {}", dst.display()))?;
-        for entry in
-            std::fs::read_dir(src).with_context(|| format!("read_dir: {}", src.display()))?
-        {
-            let entry = entry.context("read_dir entry")?;
-            let file_type = entry.file_type().context("file_type")?;
-            let child_src = entry.path();
-            let child_dst = dst.join(entry.file_name());
-            copy_tree(&child_src, &child_dst, &file_type)?;
+/// Copies `fixture_rr_dir` into `.x07_rr`.
+fn stage_rr_fixture(tmp: &TempDir, config: &RunnerConfig) -> Result<(), StagingError> {
+    let fixture = config
+        .fixture_rr_dir
+        .as_ref()
+        .filter(|d| d.is_dir())
+        .ok_or_else(|| StagingError::missing("fixture_rr_dir"))?;
+    let rr_dir = tmp.path().join(".x07_rr");
+    std::fs::create_dir(&rr_dir).map_err(|e| StagingError::copy_failed(fixture, &rr_dir, &e))?;
+    staging::copy_dir_contents(fixture, &rr_dir)
+}
+
+/// Populates `.x07_kv` for solve-kv / solve-full: copies `fixture_kv_dir` (if
+/// any), then writes `seed.evkv` + `latency.evkvlat` from `kv_seed_inline`, or
+/// from `fixture_kv_seed` when the fixture has no `seed.evkv`, with
+/// `fixture_kv_overlay_seed` applied on top.
+fn stage_kv_fixture(tmp: &TempDir, config: &RunnerConfig) -> Result<(), StagingError> {
+    let kv_dir = tmp.path().join(".x07_kv");
+    let fixture = config.fixture_kv_dir.as_ref();
+    if fixture.is_some_and(|d| !d.is_dir()) {
+        return Err(StagingError::missing("fixture_kv_dir"));
+    }
+    std::fs::create_dir(&kv_dir)
+        .map_err(|e| StagingError::copy_failed(fixture.unwrap_or(&kv_dir), &kv_dir, &e))?;
+    let seed_evkv = kv_dir.join("seed.evkv");
+    let latency_dst = kv_dir.join("latency.evkvlat");
+    let overlay = kv_overlay_seed(config)?;
+
+    if let Some(seed) = &config.kv_seed_inline {
+        if let Some(fixture) = fixture {
+            staging::copy_dir_contents(fixture, &kv_dir)?;
         }
-        return Ok(());
+        let mut seed = seed.clone();
+        if let Some((_, overlay)) = &overlay {
+            seed.apply_overlay(overlay);
+        }
+        return write_kv_seed_bins(&seed, &seed_evkv, &latency_dst)
+            .map_err(|e| StagingError::index_failed(IndexKind::KvSeed, None, &e));
     }
-    if src_type.is_file() {
-        std::fs::copy(src, dst)
-            .with_context(|| format!("copy file from {} to {}", src.display(), dst.display()))?;
+
+    let fixture = fixture.ok_or_else(|| StagingError::missing("fixture_kv_dir"))?;
+    staging::copy_dir_contents(fixture, &kv_dir)?;
+    if seed_evkv.is_file() {
+        if let Some((src, _)) = &overlay {
+            let err = anyhow::anyhow!(
+                "fixture_kv_overlay_seed needs a JSON or inline base seed, but fixture_kv_dir has a prebuilt seed.evkv"
+            );
+            return Err(StagingError::index_failed(
+                IndexKind::KvSeed,
+                Some(src),
+                &err,
+            ));
+        }
         return Ok(());
     }
-    anyhow::bail!("unsupported fixture entry type: {}", src.display());
+    let seed_json = config
+        .fixture_kv_seed
+        .as_deref()
+        .ok_or_else(|| StagingError::missing("fixture_kv_seed"))?;
+    staging::safe_rel_path(seed_json)?;
+    let src = fixture.join(seed_json);
+    let mut seed = read_kv_seed_json(&src)
+        .map_err(|e| StagingError::index_failed(IndexKind::KvSeed, Some(&src), &e))?;
+    if let Some((_, overlay)) = &overlay {
+        seed.apply_overlay(overlay);
+    }
+    write_kv_seed_bins(&seed, &seed_evkv, &latency_dst)
+        .map_err(|e| StagingError::index_failed(IndexKind::KvSeed, Some(&src), &e))
+}
+
+/// `fixture_kv_overlay_seed`, resolved under `fixture_kv_dir` and parsed.
+fn kv_overlay_seed(config: &RunnerConfig) -> Result<Option<(PathBuf, KvSeed)>, StagingError> {
+    let Some(rel) = config.fixture_kv_overlay_seed.as_deref() else {
+        return Ok(None);
+    };
+    let fixture = config
+        .fixture_kv_dir
+        .as_ref()
+        .ok_or_else(|| StagingError::missing("fixture_kv_dir"))?;
+    staging::safe_rel_path(rel)?;
+    let src = fixture.join(rel);
+    let seed = read_kv_seed_json(&src)
+        .map_err(|e| StagingError::index_failed(IndexKind::KvSeed, Some(&src), &e))?;
+    Ok(Some((src, seed)))
+}
+
+/// Digests of the overlays [`setup_run_dir`] staged for `config.world`.
+fn applied_fixture_overlays(config: &RunnerConfig) -> Result<Vec<FixtureOverlay>> {
+    let mut out = Vec::new();
+    if matches!(config.world, WorldId::SolveFs | WorldId::SolveFull) {
+        if let Some(fixture) = &config.fixture_fs_dir {
+            for overlay in &config.fixture_fs_overlays {
+                out.push(FixtureOverlay {
+                    kind: OverlayKind::Fs,
+                    path: overlay.clone(),
+                    sha256: fixture_tree_digest(&fixture.join(overlay))?,
+                });
+            }
+        }
+    }
+    if matches!(config.world, WorldId::SolveKv | WorldId::SolveFull) {
+        if let (Some(fixture), Some(overlay)) =
+            (&config.fixture_kv_dir, &config.fixture_kv_overlay_seed)
+        {
+            let src = fixture.join(overlay);
+            let bytes = std::fs::read(&src)
+                .with_context(|| format!("read kv overlay seed: {}", src.display()))?;
+            out.push(FixtureOverlay {
+                kind: OverlayKind::Kv,
+                path: overlay.clone(),
+                sha256: Some(hex_lower(&Sha256::digest(&bytes))),
+            });
+        }
+    }
+    Ok(out)
 }
 
 pub fn ensure_safe_rel_path(rel: &Path) -> Result<()> {
@@ -2063,17 +3586,33 @@ struct KvSeedEntryJsonV1 {
     latency_ticks: u64,
 }
 
-struct KvSeedEntryDecoded {
-    key: Vec<u8>,
-    value: Vec<u8>,
-    latency_ticks: u32,
+/// In-memory `/x07_kv` seed, equivalent to an `x07.kv.seed@0.1.0` JSON file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KvSeed {
+    /// Latency for keys without an entry.
+    pub default_latency_ticks: u32,
+    /// Any order; entries are sorted by key when encoded.
+    pub entries: Vec<KvSeedEntry>,
 }
 
-fn write_kv_seed_evkv_and_latency(
-    src_json: &Path,
-    seed_dst: &Path,
-    latency_dst: &Path,
-) -> Result<()> {
+impl KvSeed {
+    /// Replaces every entry whose key `overlay` also has with `overlay`'s
+    /// entries; `default_latency_ticks` stays.
+    pub fn apply_overlay(&mut self, overlay: &KvSeed) {
+        let keys: BTreeSet<&[u8]> = overlay.entries.iter().map(|e| e.key.as_slice()).collect();
+        self.entries.retain(|e| !keys.contains(e.key.as_slice()));
+        self.entries.extend(overlay.entries.iter().cloned());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvSeedEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub latency_ticks: u32,
+}
+
+fn read_kv_seed_json(src_json: &Path) -> Result<KvSeed> {
     let obj = serde_json::from_slice::<KvSeedJsonV1>(
         &std::fs::read(src_json)
             .with_context(|| format!("read kv seed json: {}", src_json.display()))?,
@@ -2087,7 +3626,7 @@ fn write_kv_seed_evkv_and_latency(
         .context("kv seed default_latency_ticks out of u32 range")?;
 
     let b64 = base64::engine::general_purpose::STANDARD;
-    let mut decoded: Vec<KvSeedEntryDecoded> = Vec::with_capacity(obj.entries.len());
+    let mut decoded: Vec<KvSeedEntry> = Vec::with_capacity(obj.entries.len());
     for e in obj.entries {
         let key = b64
             .decode(e.key_b64.as_bytes())
@@ -2097,13 +3636,22 @@ fn write_kv_seed_evkv_and_latency(
             .with_context(|| format!("decode kv seed value_b64: {}", e.value_b64))?;
         let latency_ticks =
             u32::try_from(e.latency_ticks).context("kv seed latency_ticks out of u32 range")?;
-        decoded.push(KvSeedEntryDecoded {
+        decoded.push(KvSeedEntry {
             key,
             value,
             latency_ticks,
         });
     }
 
+    Ok(KvSeed {
+        default_latency_ticks: default_ticks,
+        entries: decoded,
+    })
+}
+
+fn write_kv_seed_bins(kv_seed: &KvSeed, seed_dst: &Path, latency_dst: &Path) -> Result<()> {
+    let default_ticks = kv_seed.default_latency_ticks;
+    let mut decoded: Vec<&KvSeedEntry> = kv_seed.entries.iter().collect();
     decoded.sort_by(|a, b| a.key.as_slice().cmp(b.key.as_slice()));
 
     let count = u32::try_from(decoded.len()).context("kv seed too many entries")?;
@@ -2173,82 +3721,247 @@ fn make_readonly_recursive(path: &Path) -> Result<()> {
     anyhow::bail!("unsupported fixture entry type: {}", path.display());
 }
 
-#[cfg(unix)]
-fn apply_rlimits(config: &RunnerConfig) -> std::io::Result<()> {
-    unsafe {
-        let cpu = libc::rlimit {
-            rlim_cur: config.cpu_time_limit_seconds as libc::rlim_t,
-            rlim_max: config.cpu_time_limit_seconds as libc::rlim_t,
-        };
-        if libc::setrlimit(libc::RLIMIT_CPU, &cpu) != 0 {
-            return Err(std::io::Error::last_os_error());
+/// Sets the readonly attribute (`SetFileAttributesW`, via
+/// `Permissions::set_readonly`) on every staged file. Windows ignores the
+/// attribute on directories, so the solver can still create new files in the
+/// run dir, but it cannot modify or delete staged fixtures.
+#[cfg(windows)]
+fn make_readonly_recursive(path: &Path) -> Result<()> {
+    set_readonly_recursive(path, true)
+}
+
+#[cfg(windows)]
+fn set_readonly_recursive(path: &Path, readonly: bool) -> Result<()> {
+    let md =
+        std::fs::symlink_metadata(path).with_context(|| format!("metadata: {}", path.display()))?;
+    let ft = md.file_type();
+    if ft.is_dir() {
+        for entry in
+            std::fs::read_dir(path).with_context(|| format!("read_dir: {}", path.display()))?
+        {
+            let entry = entry.context("read_dir entry")?;
+            set_readonly_recursive(&entry.path(), readonly)?;
         }
+        return Ok(());
+    }
+    if ft.is_file() {
+        let mut perms = md.permissions();
+        perms.set_readonly(readonly);
+        std::fs::set_permissions(path, perms)
+            .with_context(|| format!("set readonly attribute: {}", path.display()))?;
+        return Ok(());
+    }
+    anyhow::bail!("unsupported fixture entry type: {}", path.display());
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy)]
+struct RlimitTarget {
+    name: &'static str,
+    resource: RlimitResource,
+    value: libc::rlim_t,
+}
+
+#[cfg(unix)]
+fn rlimit_targets(config: &RunnerConfig) -> [RlimitTarget; 4] {
+    [
+        RlimitTarget {
+            name: "RLIMIT_CPU",
+            resource: libc::RLIMIT_CPU,
+            value: config.cpu_time_limit_seconds as libc::rlim_t,
+        },
+        RlimitTarget {
+            name: "RLIMIT_FSIZE",
+            resource: libc::RLIMIT_FSIZE,
+            value: 0,
+        },
+        RlimitTarget {
+            name: "RLIMIT_NOFILE",
+            resource: libc::RLIMIT_NOFILE,
+            value: 32,
+        },
+        RlimitTarget {
+            name: "RLIMIT_CORE",
+            resource: libc::RLIMIT_CORE,
+            value: 0,
+        },
+    ]
+}
 
-        let fsize = libc::rlimit {
+/// Checks each sandbox rlimit against the runner's own hard limit, which the
+/// child inherits. The runner only ever lowers limits, so a hard limit already
+/// below the target cannot be applied: [`RlimitMode::Enforce`] fails naming
+/// it, [`RlimitMode::BestEffort`] drops it from the returned set and records
+/// a degradation instead.
+#[cfg(unix)]
+fn plan_rlimits(config: &RunnerConfig) -> Result<(Vec<RlimitTarget>, Vec<String>)> {
+    let mut apply = Vec::new();
+    let mut degradations = Vec::new();
+    for target in rlimit_targets(config) {
+        let mut cur = libc::rlimit {
             rlim_cur: 0,
             rlim_max: 0,
         };
-        if libc::setrlimit(libc::RLIMIT_FSIZE, &fsize) != 0 {
-            return Err(std::io::Error::last_os_error());
+        if unsafe { libc::getrlimit(target.resource, &mut cur) } != 0 {
+            let err = std::io::Error::last_os_error();
+            anyhow::bail!("getrlimit {}: {err}", target.name);
         }
-
-        let nofile = libc::rlimit {
-            rlim_cur: 32,
-            rlim_max: 32,
-        };
-        if libc::setrlimit(libc::RLIMIT_NOFILE, &nofile) != 0 {
-            return Err(std::io::Error::last_os_error());
+        if cur.rlim_max == libc::RLIM_INFINITY || cur.rlim_max >= target.value {
+            apply.push(target);
+            continue;
+        }
+        match config.rlimit_mode {
+            RlimitMode::Enforce => anyhow::bail!(
+                "cannot apply {}={}: the runner's hard limit is {} (rlimit mode best-effort skips it)",
+                target.name,
+                target.value,
+                cur.rlim_max
+            ),
+            RlimitMode::BestEffort => degradations.push(format!(
+                "{}: hard limit {} is below the sandbox limit {}; left unchanged",
+                target.name, cur.rlim_max, target.value
+            )),
         }
+    }
+    Ok((apply, degradations))
+}
 
-        let core = libc::rlimit {
-            rlim_cur: 0,
-            rlim_max: 0,
+/// [`sandbox_capabilities`] narrowed to what this run can apply: under
+/// [`RlimitMode::BestEffort`], a skipped `RLIMIT_CPU` or `RLIMIT_FSIZE` drops
+/// the matching feature. Under [`RlimitMode::Enforce`] a skipped limit is an
+/// error here, as it is in [`run_child`].
+fn effective_sandbox_caps(config: &RunnerConfig) -> Result<SandboxCaps> {
+    #[allow(unused_mut)]
+    let mut caps = sandbox_capabilities();
+    #[cfg(unix)]
+    {
+        let (apply, _) = plan_rlimits(config)?;
+        let applied = |name: &str| apply.iter().any(|t| t.name == name);
+        caps.cpu_limit &= applied("RLIMIT_CPU");
+        caps.fsize_limit &= applied("RLIMIT_FSIZE");
+    }
+    #[cfg(not(unix))]
+    let _ = config;
+    Ok(caps)
+}
+
+#[cfg(unix)]
+fn apply_rlimits(targets: &[RlimitTarget]) -> std::io::Result<()> {
+    for target in targets {
+        let lim = libc::rlimit {
+            rlim_cur: target.value,
+            rlim_max: target.value,
         };
-        if libc::setrlimit(libc::RLIMIT_CORE, &core) != 0 {
+        if unsafe { libc::setrlimit(target.resource, &lim) } != 0 {
             return Err(std::io::Error::last_os_error());
         }
     }
     Ok(())
 }
 
-fn run_child(artifact_path: &Path, input: &[u8], config: &RunnerConfig) -> Result<ChildOutput> {
-    let tmp = TempDir::new("x07_run").context("create tempdir")?;
+fn run_child(
+    artifact_path: &Path,
+    input: SolveInput<'_>,
+    config: &RunnerConfig,
+    progress: &dyn Fn(RunPhase),
+) -> Result<ChildOutput> {
+    let stdin_source = StdinSource::open(input)?;
+    let tmp = match &config.stable_run_dir_token {
+        Some(token) => TempDir::for_token(token)?,
+        None => TempDir::new("x07_run").context("create tempdir")?,
+    };
     let artifact_abs = std::fs::canonicalize(artifact_path)
         .with_context(|| format!("canonicalize artifact path: {}", artifact_path.display()))?;
 
     setup_run_dir(&tmp, config)?;
+    let fixture_digest = fixture_tree_digest(tmp.path())?;
+    let fixture_overlays = applied_fixture_overlays(config)?;
+    progress(RunPhase::FixturesStaged);
+    let run_dir_quota = config.max_run_dir_bytes.map(|limit| RunDirQuota {
+        dir: tmp.path(),
+        baseline: dir_size_bytes(tmp.path()),
+        limit,
+    });
+
+    #[cfg(unix)]
+    let (rlimits, sandbox_degradations) = plan_rlimits(config)?;
+    #[cfg(not(unix))]
+    let sandbox_degradations = Vec::new();
 
+    let child_env = solver_child_env(config);
+    #[cfg(unix)]
+    let (metrics_read, metrics_write) = std::io::pipe().context("create metrics pipe")?;
+    #[cfg(unix)]
+    let pty = if config.allocate_pty {
+        Some(open_pty().context("allocate pty")?)
+    } else {
+        None
+    };
+    #[cfg(not(unix))]
+    if config.allocate_pty {
+        anyhow::bail!("allocate_pty is only supported on unix");
+    }
+    // Held until this returns, after the child has been reaped.
+    let _run_slot = run_slots::acquire_run_slot()?;
     let mut child = {
         let mut cmd = Command::new(&artifact_abs);
         cmd.stdin(Stdio::piped());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
-        cmd.env_clear();
-        if let Ok(v) = std::env::var("X07_DEBUG_SCHED") {
-            cmd.env("X07_DEBUG_SCHED", v);
+        #[cfg(unix)]
+        if let Some((_, slave)) = &pty {
+            cmd.stdout(Stdio::from(slave.try_clone().context("dup pty slave")?));
+            cmd.stderr(Stdio::from(slave.try_clone().context("dup pty slave")?));
         }
+        cmd.env_clear();
+        cmd.envs(child_env.iter().map(|(k, v)| (k, v)));
         cmd.current_dir(tmp.path());
 
         #[cfg(unix)]
         {
+            use std::os::fd::AsRawFd as _;
             use std::os::unix::process::CommandExt as _;
-            let cfg = config.clone();
+            // Own process group, so a kill reaches whatever the solver spawned.
+            cmd.process_group(0);
+            let metrics_fd = metrics_write.as_raw_fd();
             unsafe {
-                cmd.pre_exec(move || apply_rlimits(&cfg));
+                cmd.pre_exec(move || {
+                    attach_metrics_fd(metrics_fd)?;
+                    apply_rlimits(&rlimits)
+                });
             }
         }
 
         cmd.spawn()
             .with_context(|| format!("spawn artifact: {}", artifact_path.display()))?
     };
+    // Only the child may hold the write end, so the reader sees EOF on exit.
+    #[cfg(unix)]
+    drop(metrics_write);
+    // Same for the pty slave; the master then reads EIO once the child exits.
+    #[cfg(unix)]
+    let pty_master = pty.map(|(master, _slave)| PtyMaster(std::fs::File::from(master)));
+    #[cfg(not(unix))]
+    let pty_master: Option<std::convert::Infallible> = None;
+
+    progress(RunPhase::ChildSpawned { pid: child.id() });
 
     let mut stdin = child.stdin.take().context("take stdin")?;
-    let stdout = child.stdout.take().context("take stdout")?;
-    let stderr = child.stderr.take().context("take stderr")?;
+    let stdout: Box<dyn Read + Send> = match pty_master {
+        #[cfg(unix)]
+        Some(master) => Box::new(master),
+        _ => Box::new(child.stdout.take().context("take stdout")?),
+    };
+    // Merged into the pty when there is one.
+    let stderr = child.stderr.take();
 
-    let input_vec = encode_len_prefixed(input);
     let stdin_thread = std::thread::spawn(move || -> std::io::Result<()> {
-        stdin.write_all(&input_vec)?;
+        stdin_source.write_to(&mut stdin)?;
         stdin.flush()?;
         drop(stdin);
         Ok(())
@@ -2263,10 +3976,19 @@ fn run_child(artifact_path: &Path, input: &[u8], config: &RunnerConfig) -> Resul
 
     let stderr_cap = 256usize * 1024;
     let stderr_thread = std::thread::spawn(move || -> std::io::Result<(Vec<u8>, bool)> {
-        read_to_end_capped(stderr, stderr_cap)
+        match stderr {
+            Some(stderr) => read_to_end_capped(stderr, stderr_cap),
+            None => Ok((Vec::new(), false)),
+        }
+    });
+
+    #[cfg(unix)]
+    let metrics_thread = std::thread::spawn(move || -> std::io::Result<(Vec<u8>, bool)> {
+        read_to_end_capped(metrics_read, METRICS_FD_CAP_BYTES)
     });
 
-    let (status, timed_out) = wait_child_with_wall_timeout(&mut child, config)?;
+    let (status, timed_out, mut run_dir_quota_exceeded) =
+        wait_child_with_wall_timeout(&mut child, config, run_dir_quota.as_ref())?;
     let _ = stdin_thread.join();
     let (stdout_bytes, stdout_truncated) = stdout_thread
         .join()
@@ -2274,6 +3996,12 @@ fn run_child(artifact_path: &Path, input: &[u8], config: &RunnerConfig) -> Resul
     let (stderr_bytes, stderr_truncated) = stderr_thread
         .join()
         .unwrap_or_else(|_| Ok((Vec::new(), false)))?;
+    #[cfg(unix)]
+    let (metrics, _) = metrics_thread
+        .join()
+        .unwrap_or_else(|_| Ok((Vec::new(), false)))?;
+    #[cfg(not(unix))]
+    let metrics = Vec::new();
 
     #[cfg(unix)]
     let exit_signal = {
@@ -2287,38 +4015,291 @@ fn run_child(artifact_path: &Path, input: &[u8], config: &RunnerConfig) -> Resul
         Some(code) => code,
         None => exit_signal.map(|s| 128 + s).unwrap_or(1),
     };
+    progress(RunPhase::ChildExited {
+        status: exit_status,
+    });
+    let run_dir_bytes = run_dir_quota.as_ref().map(RunDirQuota::used_bytes);
+    if let (Some(q), Some(used)) = (run_dir_quota.as_ref(), run_dir_bytes) {
+        run_dir_quota_exceeded |= used > q.limit;
+    }
     Ok(ChildOutput {
+        env_fingerprint: env_fingerprint(&child_env),
+        fixture_digest,
+        fixture_overlays,
         exit_status,
         exit_signal,
         timed_out,
+        run_dir_quota_exceeded,
+        run_dir_bytes,
         stdout: stdout_bytes,
         stderr: stderr_bytes,
         stdout_truncated,
         stderr_truncated,
+        sandbox_degradations,
+        metrics,
     })
 }
 
+#[cfg(unix)]
+fn open_pty() -> std::io::Result<(std::os::fd::OwnedFd, std::os::fd::OwnedFd)> {
+    use std::os::fd::FromRawFd as _;
+
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+    let rc = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    unsafe {
+        Ok((
+            std::os::fd::OwnedFd::from_raw_fd(master),
+            std::os::fd::OwnedFd::from_raw_fd(slave),
+        ))
+    }
+}
+
+/// Reads the pty master, treating the EIO Linux returns once every slave fd
+/// is closed as end of output.
+#[cfg(unix)]
+struct PtyMaster(std::fs::File);
+
+#[cfg(unix)]
+impl Read for PtyMaster {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.read(buf) {
+            Err(err) if err.raw_os_error() == Some(libc::EIO) => Ok(0),
+            res => res,
+        }
+    }
+}
+
+/// What the stdin thread writes to the child: the length-prefixed input.
+enum StdinSource {
+    Framed(Vec<u8>),
+    /// Written as the `len` prefix, then `len` bytes copied from `file`.
+    File {
+        file: std::fs::File,
+        len: u32,
+        path: PathBuf,
+    },
+}
+
+impl StdinSource {
+    /// Opens and size-checks a file input up front, so an unusable file
+    /// fails the run before anything is staged or spawned.
+    fn open(input: SolveInput<'_>) -> Result<Self> {
+        let path = match input {
+            SolveInput::Bytes(bytes) => return Ok(StdinSource::Framed(encode_len_prefixed(bytes))),
+            SolveInput::File(path) => path,
+        };
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("open input file: {}", path.display()))?;
+        let size = file
+            .metadata()
+            .with_context(|| format!("stat input file: {}", path.display()))?
+            .len();
+        let Ok(len) = u32::try_from(size) else {
+            anyhow::bail!(
+                "input file {} is {size} bytes; the input frame holds at most {} bytes",
+                path.display(),
+                u32::MAX
+            );
+        };
+        Ok(StdinSource::File {
+            file,
+            len,
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn write_to<W: Write>(self, w: &mut W) -> std::io::Result<()> {
+        match self {
+            StdinSource::Framed(bytes) => w.write_all(&bytes),
+            StdinSource::File { file, len, path } => {
+                w.write_all(&len.to_le_bytes())?;
+                let copied = std::io::copy(&mut file.take(u64::from(len)), w)?;
+                if copied != u64::from(len) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "input file {} shrank to {copied} of {len} bytes while streaming",
+                            path.display()
+                        ),
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Cap on what is read from [`METRICS_FD`]; a metrics line is ~1 KiB.
+#[cfg(unix)]
+const METRICS_FD_CAP_BYTES: usize = 64 * 1024;
+
+/// Runs in the child before exec: moves the metrics pipe to [`METRICS_FD`].
+#[cfg(unix)]
+fn attach_metrics_fd(fd: libc::c_int) -> std::io::Result<()> {
+    if fd == METRICS_FD {
+        // dup2 onto itself keeps close-on-exec set.
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, 0) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    } else if unsafe { libc::dup2(fd, METRICS_FD) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+struct RunDirQuota<'a> {
+    dir: &'a Path,
+    baseline: u64,
+    limit: u64,
+}
+
+impl RunDirQuota<'_> {
+    fn used_bytes(&self) -> u64 {
+        dir_size_bytes(self.dir).saturating_sub(self.baseline)
+    }
+}
+
+/// Size of a directory tree, counting each entry (directories included) by the
+/// larger of its length and its allocated blocks so that piles of empty files
+/// still add up. Entries that vanish mid-walk are skipped.
+fn dir_size_bytes(path: &Path) -> u64 {
+    let mut total: u64 = 0;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(p) = stack.pop() {
+        let Ok(md) = std::fs::symlink_metadata(&p) else {
+            continue;
+        };
+        #[cfg(unix)]
+        let allocated = {
+            use std::os::unix::fs::MetadataExt as _;
+            md.blocks().saturating_mul(512)
+        };
+        #[cfg(not(unix))]
+        let allocated = 0u64;
+        total = total.saturating_add(md.len().max(allocated));
+        if md.is_dir() {
+            if let Ok(rd) = std::fs::read_dir(&p) {
+                stack.extend(rd.flatten().map(|e| e.path()));
+            }
+        }
+    }
+    total
+}
+
+/// sha256 over the regular files under `root`, sorted by `/`-joined relative
+/// path. Each file contributes `path\0`, its length as u64 LE, then its bytes,
+/// so the digest does not depend on directory iteration order, mtimes or
+/// permissions. Returns `None` for an empty tree.
+pub fn fixture_tree_digest(root: &Path) -> Result<Option<String>> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    let mut stack = vec![(String::new(), root.to_path_buf())];
+    while let Some((rel, dir)) = stack.pop() {
+        for entry in
+            std::fs::read_dir(&dir).with_context(|| format!("read_dir: {}", dir.display()))?
+        {
+            let entry = entry.context("read_dir entry")?;
+            let file_type = entry.file_type().context("file_type")?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let child_rel = if rel.is_empty() {
+                name.into_owned()
+            } else {
+                format!("{rel}/{name}")
+            };
+            if file_type.is_dir() {
+                stack.push((child_rel, entry.path()));
+            } else if file_type.is_file() {
+                files.push((child_rel, entry.path()));
+            }
+        }
+    }
+    if files.is_empty() {
+        return Ok(None);
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for (rel, path) in &files {
+        let mut f = std::fs::File::open(path)
+            .with_context(|| format!("open fixture file: {}", path.display()))?;
+        let len = f
+            .metadata()
+            .with_context(|| format!("stat fixture file: {}", path.display()))?
+            .len();
+        hasher.update(rel.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(len.to_le_bytes());
+        std::io::copy(&mut f, &mut hasher)
+            .with_context(|| format!("hash fixture file: {}", path.display()))?;
+    }
+    Ok(Some(hex_lower(&hasher.finalize())))
+}
+
+/// Waits for the child, killing it on wall timeout or when the run dir quota is
+/// exceeded. Returns `(status, timed_out, run_dir_quota_exceeded)`.
 fn wait_child_with_wall_timeout(
     child: &mut std::process::Child,
     config: &RunnerConfig,
-) -> Result<(std::process::ExitStatus, bool)> {
+    run_dir_quota: Option<&RunDirQuota<'_>>,
+) -> Result<(std::process::ExitStatus, bool, bool)> {
+    const QUOTA_POLL_EVERY: u32 = 20;
+
     let wall_limit = Duration::from_secs(config.cpu_time_limit_seconds.saturating_add(1));
     let start = Instant::now();
     let deadline = start.checked_add(wall_limit);
+    let mut polls: u32 = 0;
 
     loop {
         if let Some(status) = child.try_wait().context("try_wait child")? {
-            return Ok((status, false));
+            return Ok((status, false, false));
         }
         if deadline.is_some_and(|d| Instant::now() >= d) {
-            let _ = child.kill();
+            kill_child_tree(child);
             let status = child.wait().context("wait child after kill")?;
-            return Ok((status, true));
+            return Ok((status, true, false));
+        }
+        polls = polls.wrapping_add(1);
+        if let Some(q) = run_dir_quota {
+            if polls.is_multiple_of(QUOTA_POLL_EVERY) && q.used_bytes() > q.limit {
+                kill_child_tree(child);
+                let status = child.wait().context("wait child after kill")?;
+                return Ok((status, false, true));
+            }
         }
         std::thread::sleep(Duration::from_millis(5));
     }
 }
 
+/// Kills the child and its descendants (see
+/// [`SandboxFeature::ProcessGroupKill`]); the caller still reaps the child.
+fn kill_child_tree(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        // The child leads its own group (`process_group(0)` at spawn), and
+        // it is not reaped yet, so the group id cannot have been reused.
+        if let Ok(pgid) = libc::pid_t::try_from(child.id()) {
+            unsafe { libc::killpg(pgid, libc::SIGKILL) };
+        }
+        let _ = child.kill();
+    }
+    #[cfg(windows)]
+    sandbox::kill_process_tree(child);
+    #[cfg(not(any(unix, windows)))]
+    let _ = child.kill();
+}
+
 pub fn encode_len_prefixed(payload: &[u8]) -> Vec<u8> {
     let len: u32 = payload.len().try_into().unwrap_or(u32::MAX);
     let mut out = Vec::with_capacity(4 + payload.len());
@@ -2365,11 +4346,19 @@ fn hex_lower(bytes: &[u8]) -> String {
 }
 
 struct ChildOutput {
+    env_fingerprint: String,
+    fixture_digest: Option<String>,
+    fixture_overlays: Vec<FixtureOverlay>,
     exit_status: i32,
     exit_signal: Option<i32>,
     timed_out: bool,
+    run_dir_quota_exceeded: bool,
+    run_dir_bytes: Option<u64>,
     stdout: Vec<u8>,
     stderr: Vec<u8>,
     stdout_truncated: bool,
     stderr_truncated: bool,
+    sandbox_degradations: Vec<String>,
+    /// Everything the solver wrote to [`METRICS_FD`].
+    metrics: Vec<u8>,
 }
@@ -4,8 +4,10 @@ use anyhow::{Context, Result};
 use base64::Engine;
 use clap::Parser;
 use x07_contracts::X07_HOST_RUNNER_REPORT_SCHEMA_VERSION;
+use x07_host_runner::golden::{self, GoldenDiff, GoldenFormat};
 use x07_host_runner::{
-    apply_cc_profile, compile_program_with_options, run_artifact_file, CcProfile, RunnerConfig,
+    apply_cc_profile, compile_program_with_options, run_artifact_file, CcProfile, OutputContract,
+    OutputContractVerdict, RlimitMode, RunnerConfig, RunnerResult, Sanitizer, StagingError,
 };
 use x07_worlds::WorldId;
 use x07c::project;
@@ -51,6 +53,11 @@ struct Cli {
     #[arg(long)]
     fixture_fs_latency_index: Option<PathBuf>,
 
+    /// Dir under `--fixture-fs-dir` copied over the staged fs fixture;
+    /// repeatable, later overlays win.
+    #[arg(long, value_name = "PATH")]
+    fixture_fs_overlay: Vec<PathBuf>,
+
     #[arg(long)]
     fixture_rr_dir: Option<PathBuf>,
 
@@ -60,9 +67,22 @@ struct Cli {
     #[arg(long)]
     fixture_kv_seed: Option<PathBuf>,
 
+    /// Seed JSON under `--fixture-kv-dir` whose entries replace the base
+    /// seed's by key.
+    #[arg(long, value_name = "PATH")]
+    fixture_kv_overlay_seed: Option<PathBuf>,
+
     #[arg(long)]
     input: Option<PathBuf>,
 
+    /// Compare solve_output against this golden file; a mismatch fails the run
+    /// and is reported as `solve.golden_diff`.
+    #[arg(long, value_name = "PATH")]
+    expect_output: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = GoldenFormat::Bytes)]
+    expect_format: GoldenFormat,
+
     #[arg(long, default_value_t = 50_000_000)]
     solve_fuel: u64,
 
@@ -72,12 +92,47 @@ struct Cli {
     #[arg(long)]
     max_output_bytes: Option<usize>,
 
+    /// Trap if the solver adds more than this many bytes to its run dir.
+    #[arg(long)]
+    max_run_dir_bytes: Option<u64>,
+
     #[arg(long, default_value_t = 5)]
     cpu_time_limit_seconds: u64,
 
     #[arg(long)]
     debug_borrow_checks: bool,
 
+    /// Build the solver with a sanitizer (repeatable; address and thread are mutually exclusive).
+    #[arg(long, value_enum)]
+    sanitize: Vec<Sanitizer>,
+
+    /// What to do when a sandbox rlimit cannot be applied: fail the run, or
+    /// skip it and list it under `sandbox_degradations` in the report.
+    #[arg(long, value_enum, default_value_t = RlimitMode::Enforce)]
+    rlimit_mode: RlimitMode,
+
+    /// Per-run seed the program reads via `prng.run_seed_u32`; recorded as
+    /// `solve_seed` in the report.
+    #[arg(long, value_name = "U64")]
+    solve_seed: Option<u64>,
+
+    /// Check solve_output's shape after a successful run; the verdict is
+    /// reported as `output_contract`.
+    #[arg(long, value_enum)]
+    output_contract: Option<OutputContractKind>,
+
+    /// JSON Schema the output must match, for `--output-contract json`.
+    #[arg(long, value_name = "PATH")]
+    output_schema: Option<PathBuf>,
+
+    /// Record size for `--output-contract framed`.
+    #[arg(long, value_name = "BYTES")]
+    output_record_size: Option<usize>,
+
+    /// Fail the run when the output breaks its contract.
+    #[arg(long)]
+    output_contract_strict: bool,
+
     #[arg(long)]
     compiled_out: Option<PathBuf>,
 
@@ -220,15 +275,25 @@ fn try_main() -> Result<std::process::ExitCode> {
                 max_output_bytes: cli.max_output_bytes.unwrap_or(1024 * 1024),
                 cpu_time_limit_seconds: cli.cpu_time_limit_seconds,
                 debug_borrow_checks: cli.debug_borrow_checks,
+                sanitizers: cli.sanitize.clone(),
+                max_run_dir_bytes: cli.max_run_dir_bytes,
+                rlimit_mode: cli.rlimit_mode,
+                solve_seed: cli.solve_seed,
+                fixture_fs_overlays: cli.fixture_fs_overlay.clone(),
+                fixture_kv_overlay_seed: cli.fixture_kv_overlay_seed.clone(),
+                output_contract: output_contract(&cli)?,
+                output_contract_strict: cli.output_contract_strict,
+                ..Default::default()
             };
 
             let result = x07_host_runner::run_artifact_file(&config, artifact, &input)?;
-            let exit_code: u8 = if result.ok && result.exit_status == 0 {
+            let golden_diff = golden_diff(&cli, &result)?;
+            let exit_code: u8 = if result.ok && result.exit_status == 0 && golden_diff.is_none() {
                 0
             } else {
                 1
             };
-            let json = serde_json::json!({
+            let mut json = serde_json::json!({
                 "schema_version": X07_HOST_RUNNER_REPORT_SCHEMA_VERSION,
                 "mode": "solve",
                 "ok": result.ok,
@@ -254,7 +319,28 @@ fn try_main() -> Result<std::process::ExitCode> {
                 "debug_stats": result.debug_stats,
                 "trap": result.trap,
                 "trap_help": x07_host_runner::trap_help_for(result.trap.as_deref(), config.solve_fuel),
+                "trap_detail": result.trap_detail,
+                "run_dir_bytes": result.run_dir_bytes,
+                "sandbox_caps": result.sandbox_caps,
             });
+            if let Some(diff) = &golden_diff {
+                json["golden_diff"] = serde_json::to_value(diff)?;
+            }
+            if !result.sandbox_degradations.is_empty() {
+                json["sandbox_degradations"] = serde_json::json!(result.sandbox_degradations);
+            }
+            if let Some(seed) = result.solve_seed {
+                json["solve_seed"] = serde_json::json!(seed);
+            }
+            if !result.fixture_overlays.is_empty() {
+                json["fixture_overlays"] = serde_json::to_value(&result.fixture_overlays)?;
+            }
+            if let Some(contract) = output_contract_json(&config, &result)? {
+                json["output_contract"] = contract;
+            }
+            if let Some(err) = &result.staging_error {
+                json["staging_error"] = staging_error_json(err)?;
+            }
             println!("{}", serde_json::to_string_pretty(&json)?);
 
             Ok(std::process::ExitCode::from(exit_code))
@@ -314,6 +400,16 @@ fn try_main() -> Result<std::process::ExitCode> {
                 max_output_bytes: cli.max_output_bytes.unwrap_or(1024 * 1024),
                 cpu_time_limit_seconds: cli.cpu_time_limit_seconds,
                 debug_borrow_checks: cli.debug_borrow_checks,
+                sanitizers: cli.sanitize.clone(),
+                module_roots: cli.module_root.clone(),
+                max_run_dir_bytes: cli.max_run_dir_bytes,
+                rlimit_mode: cli.rlimit_mode,
+                solve_seed: cli.solve_seed,
+                fixture_fs_overlays: cli.fixture_fs_overlay.clone(),
+                fixture_kv_overlay_seed: cli.fixture_kv_overlay_seed.clone(),
+                output_contract: output_contract(&cli)?,
+                output_contract_strict: cli.output_contract_strict,
+                ..Default::default()
             };
 
             if !program_path
@@ -363,7 +459,11 @@ fn try_main() -> Result<std::process::ExitCode> {
                 &compile_options,
             )?;
 
-            let solve_json = match &result.solve {
+            let golden_diff = match &result.solve {
+                Some(solve) => golden_diff(&cli, solve)?,
+                None => None,
+            };
+            let mut solve_json = match &result.solve {
                 Some(solve) => serde_json::json!({
                     "ok": solve.ok,
                     "exit_status": solve.exit_status,
@@ -387,16 +487,41 @@ fn try_main() -> Result<std::process::ExitCode> {
                     "debug_stats": solve.debug_stats,
                     "trap": solve.trap,
                     "trap_help": x07_host_runner::trap_help_for(solve.trap.as_deref(), config.solve_fuel),
+                    "trap_detail": solve.trap_detail,
+                    "run_dir_bytes": solve.run_dir_bytes,
+                    "sandbox_caps": solve.sandbox_caps,
                 }),
                 None => serde_json::Value::Null,
             };
+            if let Some(diff) = &golden_diff {
+                solve_json["golden_diff"] = serde_json::to_value(diff)?;
+            }
+            if let Some(solve) = result.solve.as_ref() {
+                if !solve.sandbox_degradations.is_empty() {
+                    solve_json["sandbox_degradations"] =
+                        serde_json::json!(solve.sandbox_degradations);
+                }
+                if let Some(seed) = solve.solve_seed {
+                    solve_json["solve_seed"] = serde_json::json!(seed);
+                }
+                if !solve.fixture_overlays.is_empty() {
+                    solve_json["fixture_overlays"] = serde_json::to_value(&solve.fixture_overlays)?;
+                }
+                if let Some(contract) = output_contract_json(&config, solve)? {
+                    solve_json["output_contract"] = contract;
+                }
+                if let Some(err) = &solve.staging_error {
+                    solve_json["staging_error"] = staging_error_json(err)?;
+                }
+            }
 
             let ok = result.compile.ok
                 && result
                     .solve
                     .as_ref()
                     .map(|s| s.ok && s.exit_status == 0)
-                    .unwrap_or(false);
+                    .unwrap_or(false)
+                && golden_diff.is_none();
             let exit_code: u8 = if ok { 0 } else { 1 };
             let json = serde_json::json!({
                 "schema_version": X07_HOST_RUNNER_REPORT_SCHEMA_VERSION,
@@ -468,6 +593,15 @@ fn try_main() -> Result<std::process::ExitCode> {
                 max_output_bytes: cli.max_output_bytes.unwrap_or(1024 * 1024),
                 cpu_time_limit_seconds: cli.cpu_time_limit_seconds,
                 debug_borrow_checks: cli.debug_borrow_checks,
+                sanitizers: cli.sanitize.clone(),
+                max_run_dir_bytes: cli.max_run_dir_bytes,
+                rlimit_mode: cli.rlimit_mode,
+                solve_seed: cli.solve_seed,
+                fixture_fs_overlays: cli.fixture_fs_overlay.clone(),
+                fixture_kv_overlay_seed: cli.fixture_kv_overlay_seed.clone(),
+                output_contract: output_contract(&cli)?,
+                output_contract_strict: cli.output_contract_strict,
+                ..Default::default()
             };
 
             let lock_path = project::default_lockfile_path(project_path, &manifest);
@@ -545,10 +679,11 @@ fn try_main() -> Result<std::process::ExitCode> {
                 .clone()
                 .context("internal error: compile.ok but no compiled_exe")?;
             let solve = run_artifact_file(&config, &exe, &input)?;
+            let golden_diff = golden_diff(&cli, &solve)?;
 
-            let ok = compile.ok && solve.ok && solve.exit_status == 0;
+            let ok = compile.ok && solve.ok && solve.exit_status == 0 && golden_diff.is_none();
             let exit_code: u8 = if ok { 0 } else { 1 };
-            let json = serde_json::json!({
+            let mut json = serde_json::json!({
                 "schema_version": X07_HOST_RUNNER_REPORT_SCHEMA_VERSION,
                 "mode": "project-compile-run",
                 "exit_code": exit_code,
@@ -576,8 +711,30 @@ fn try_main() -> Result<std::process::ExitCode> {
                     "debug_stats": solve.debug_stats,
                     "trap": solve.trap,
                     "trap_help": x07_host_runner::trap_help_for(solve.trap.as_deref(), config.solve_fuel),
+                    "trap_detail": solve.trap_detail,
+                    "run_dir_bytes": solve.run_dir_bytes,
+                    "sandbox_caps": solve.sandbox_caps,
                 },
             });
+            if let Some(diff) = &golden_diff {
+                json["solve"]["golden_diff"] = serde_json::to_value(diff)?;
+            }
+            if !solve.sandbox_degradations.is_empty() {
+                json["solve"]["sandbox_degradations"] =
+                    serde_json::json!(solve.sandbox_degradations);
+            }
+            if let Some(seed) = solve.solve_seed {
+                json["solve"]["solve_seed"] = serde_json::json!(seed);
+            }
+            if !solve.fixture_overlays.is_empty() {
+                json["solve"]["fixture_overlays"] = serde_json::to_value(&solve.fixture_overlays)?;
+            }
+            if let Some(contract) = output_contract_json(&config, &solve)? {
+                json["solve"]["output_contract"] = contract;
+            }
+            if let Some(err) = &solve.staging_error {
+                json["solve"]["staging_error"] = staging_error_json(err)?;
+            }
             println!("{}", serde_json::to_string_pretty(&json)?);
 
             Ok(std::process::ExitCode::from(exit_code))
@@ -585,6 +742,87 @@ fn try_main() -> Result<std::process::ExitCode> {
     }
 }
 
+/// The typed staging error plus its human-readable `message`.
+fn staging_error_json(err: &StagingError) -> Result<serde_json::Value> {
+    let mut v = serde_json::to_value(err)?;
+    v["message"] = serde_json::json!(err.to_string());
+    Ok(v)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputContractKind {
+    Json,
+    Utf8,
+    Framed,
+}
+
+fn output_contract(cli: &Cli) -> Result<Option<OutputContract>> {
+    let Some(kind) = cli.output_contract else {
+        if cli.output_schema.is_some() || cli.output_record_size.is_some() {
+            anyhow::bail!("--output-schema and --output-record-size need --output-contract");
+        }
+        return Ok(None);
+    };
+    if cli.output_schema.is_some() && kind != OutputContractKind::Json {
+        anyhow::bail!("--output-schema needs --output-contract json");
+    }
+    if cli.output_record_size.is_some() && kind != OutputContractKind::Framed {
+        anyhow::bail!("--output-record-size needs --output-contract framed");
+    }
+    Ok(Some(match kind {
+        OutputContractKind::Json => {
+            let schema = match &cli.output_schema {
+                Some(path) => {
+                    let bytes = std::fs::read(path)
+                        .with_context(|| format!("read output schema: {}", path.display()))?;
+                    Some(
+                        serde_json::from_slice(&bytes)
+                            .with_context(|| format!("parse output schema: {}", path.display()))?,
+                    )
+                }
+                None => None,
+            };
+            OutputContract::Json { schema }
+        }
+        OutputContractKind::Utf8 => OutputContract::Utf8,
+        OutputContractKind::Framed => OutputContract::Framed {
+            record_size: cli
+                .output_record_size
+                .context("--output-contract framed needs --output-record-size")?,
+        },
+    }))
+}
+
+/// The report's `output_contract` verdict, when the run had a contract.
+fn output_contract_json(
+    config: &RunnerConfig,
+    solve: &RunnerResult,
+) -> Result<Option<serde_json::Value>> {
+    let Some(contract) = &config.output_contract else {
+        return Ok(None);
+    };
+    let mut v = serde_json::json!({
+        "kind": contract.kind(),
+        "strict": config.output_contract_strict,
+        "verdict": OutputContractVerdict::of(solve),
+    });
+    if let Some(violation) = &solve.output_contract_violation {
+        v["violation"] = serde_json::to_value(violation)?;
+    }
+    Ok(Some(v))
+}
+
+/// Compares `solve` against `--expect-output`, if set.
+fn golden_diff(cli: &Cli, solve: &RunnerResult) -> Result<Option<GoldenDiff>> {
+    let Some(path) = &cli.expect_output else {
+        return Ok(None);
+    };
+    let expected =
+        std::fs::read(path).with_context(|| format!("read expected output: {}", path.display()))?;
+    golden::compare(solve, &expected, cli.expect_format)
+        .with_context(|| format!("expected output: {}", path.display()))
+}
+
 fn compiler_json(
     compile: &x07_host_runner::CompilerResult,
     b64: &base64::engine::general_purpose::GeneralPurpose,
@@ -599,6 +837,8 @@ fn compiler_json(
         "compiled_exe": compile.compiled_exe.as_ref().map(|p| p.display().to_string()),
         "compiled_exe_size": compile.compiled_exe_size,
         "compile_error": compile.compile_error,
+        "options_fingerprint": compile.options_fingerprint,
+        "native_cache_key": compile.native_cache_key,
         "stdout_b64": b64.encode(&compile.stdout),
         "stderr_b64": b64.encode(&compile.stderr),
         "fuel_used": compile.fuel_used,
@@ -609,5 +849,15 @@ fn compiler_json(
             out["diagnostics"] = diags;
         }
     }
+    if !compile.native_backend_resolutions.is_empty() {
+        if let Ok(resolutions) = serde_json::to_value(&compile.native_backend_resolutions) {
+            out["native_backend_resolutions"] = resolutions;
+        }
+    }
+    if !compile.cc_warnings.is_empty() {
+        if let Ok(warnings) = serde_json::to_value(&compile.cc_warnings) {
+            out["cc_warnings"] = warnings;
+        }
+    }
     out
 }
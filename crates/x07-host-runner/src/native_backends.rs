@@ -2,7 +2,7 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use x07_contracts::NATIVE_BACKENDS_SCHEMA_VERSION;
 use x07c::native::NativeRequires;
@@ -43,6 +43,28 @@ pub struct LinkSpec {
     pub whole_archive: bool,
 }
 
+/// Link argv for a set of native requires, plus the staged backend files it
+/// points at.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NativeLinkPlan {
+    pub argv: Vec<String>,
+    pub resolutions: Vec<NativeBackendResolution>,
+    /// Canonical JSON of the requires plus the manifest entries of the
+    /// backends they name; goes into [`crate::NativeToolchainConfig::cache_salt`]
+    /// so a manifest edit that leaves every linked file alone still misses
+    /// the native cache. Empty when nothing is required.
+    pub cache_salt: Vec<u8>,
+}
+
+/// One staged backend file resolved for linking. `mtime_unix_ms` is when the
+/// file was last written, so a stale `.a` shows up next to a fresh build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NativeBackendResolution {
+    pub backend_id: String,
+    pub path: PathBuf,
+    pub mtime_unix_ms: Option<u64>,
+}
+
 #[derive(Debug, Copy, Clone)]
 enum HostPlatform {
     Linux,
@@ -111,9 +133,9 @@ fn split_linux_link_args(args: &[String], backend_id: &str) -> Result<(Vec<Strin
 pub fn plan_native_link_argv(
     toolchain_root: &Path,
     requires: &NativeRequires,
-) -> Result<Vec<String>> {
+) -> Result<NativeLinkPlan> {
     if requires.requires.is_empty() {
-        return Ok(Vec::new());
+        return Ok(NativeLinkPlan::default());
     }
 
     let platform = host_platform()?;
@@ -128,6 +150,13 @@ pub fn plan_native_link_argv(
                 manifest_path.display()
             )
         })?;
+    let manifest_value: serde_json::Value =
+        serde_json::from_str(&manifest_text).with_context(|| {
+            format!(
+                "parse native backends manifest: {}",
+                manifest_path.display()
+            )
+        })?;
     if manifest.schema_version != NATIVE_BACKENDS_SCHEMA_VERSION {
         anyhow::bail!(
             "native backends manifest schema_version mismatch: expected {} got {}",
@@ -150,6 +179,7 @@ pub fn plan_native_link_argv(
     let mut libs: Vec<String> = Vec::new();
     let mut seen_libs: BTreeSet<String> = BTreeSet::new();
     let mut tail_args_linux: Vec<String> = Vec::new();
+    let mut resolutions: Vec<NativeBackendResolution> = Vec::new();
 
     for req in &reqs {
         let backend = backends
@@ -232,6 +262,11 @@ pub fn plan_native_link_argv(
             if seen_libs.insert(s.clone()) {
                 libs.push(s);
             }
+            resolutions.push(NativeBackendResolution {
+                backend_id: req.backend_id.clone(),
+                mtime_unix_ms: file_mtime_unix_ms(&full),
+                path: full,
+            });
         }
     }
 
@@ -249,7 +284,32 @@ pub fn plan_native_link_argv(
         }
     }
 
-    Ok(out)
+    let backend_entries: Vec<&serde_json::Value> = reqs
+        .iter()
+        .filter_map(|req| {
+            manifest_value["backends"]
+                .as_array()?
+                .iter()
+                .find(|b| b["backend_id"] == req.backend_id.as_str())
+        })
+        .collect();
+    let cache_salt = serde_json::to_vec(&serde_json::json!({
+        "native_requires": requires,
+        "backends": backend_entries,
+    }))
+    .context("serialize native cache salt")?;
+
+    Ok(NativeLinkPlan {
+        argv: out,
+        resolutions,
+        cache_salt,
+    })
+}
+
+pub(crate) fn file_mtime_unix_ms(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_millis() as u64)
 }
 
 fn host_platform() -> Result<HostPlatform> {
@@ -0,0 +1,154 @@
+//! Declared shapes for `solve_output`.
+//!
+//! With [`RunnerConfig::output_contract`](crate::RunnerConfig::output_contract)
+//! set, a successful run's `solve_output` is checked against the contract and
+//! a mismatch is recorded in
+//! [`RunnerResult::output_contract_violation`](crate::RunnerResult::output_contract_violation).
+//! Under `output_contract_strict` it also fails the run with
+//! [`OUTPUT_CONTRACT_TRAP`].
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::RunnerResult;
+
+/// `trap` of a run failed by a strict output contract.
+pub const OUTPUT_CONTRACT_TRAP: &str = "output contract violation";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutputContract {
+    /// One JSON document, valid against `schema` when given.
+    Json {
+        schema: Option<serde_json::Value>,
+    },
+    Utf8,
+    /// A list of fixed-size records: the length is a multiple of `record_size`.
+    Framed {
+        record_size: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutputContractViolation {
+    /// `json`, `utf8` or `framed`.
+    pub kind: String,
+    pub message: String,
+    /// Byte offset into `solve_output` where the output went wrong, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    /// JSON pointer to the value that failed the schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_path: Option<String>,
+}
+
+/// What the run report says about the contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputContractVerdict {
+    Pass,
+    Fail,
+    /// The run failed before its output could be checked.
+    Skipped,
+}
+
+impl OutputContract {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OutputContract::Json { .. } => "json",
+            OutputContract::Utf8 => "utf8",
+            OutputContract::Framed { .. } => "framed",
+        }
+    }
+
+    /// Checks `output`. Fails only when the contract itself is unusable: an
+    /// invalid schema or a zero `record_size`.
+    pub fn check(&self, output: &[u8]) -> Result<Option<OutputContractViolation>> {
+        Ok(self.checker()?.check(output))
+    }
+
+    pub(crate) fn checker(&self) -> Result<OutputChecker<'_>> {
+        let validator = match self {
+            OutputContract::Json {
+                schema: Some(schema),
+            } => Some(jsonschema::validator_for(schema).context("build output contract schema")?),
+            OutputContract::Framed { record_size: 0 } => {
+                anyhow::bail!("output contract record_size must be > 0")
+            }
+            _ => None,
+        };
+        Ok(OutputChecker {
+            contract: self,
+            validator,
+        })
+    }
+}
+
+/// An [`OutputContract`] with its schema compiled, built before the run so a
+/// bad contract fails without running the solver.
+pub(crate) struct OutputChecker<'a> {
+    contract: &'a OutputContract,
+    validator: Option<jsonschema::Validator>,
+}
+
+impl OutputChecker<'_> {
+    pub(crate) fn check(&self, output: &[u8]) -> Option<OutputContractViolation> {
+        let violation = |message: String, offset, instance_path| OutputContractViolation {
+            kind: self.contract.kind().to_string(),
+            message,
+            offset,
+            instance_path,
+        };
+        match self.contract {
+            OutputContract::Json { .. } => {
+                let doc: serde_json::Value = match serde_json::from_slice(output) {
+                    Ok(doc) => doc,
+                    Err(err) => {
+                        return Some(violation(
+                            format!("solve_output is not valid JSON: {err}"),
+                            None,
+                            None,
+                        ))
+                    }
+                };
+                let err = self.validator.as_ref()?.iter_errors(&doc).next()?;
+                Some(violation(
+                    format!("solve_output does not match the schema: {err}"),
+                    None,
+                    Some(err.instance_path().to_string()),
+                ))
+            }
+            OutputContract::Utf8 => std::str::from_utf8(output).err().map(|err| {
+                violation(
+                    format!("solve_output is not valid UTF-8: {err}"),
+                    Some(err.valid_up_to()),
+                    None,
+                )
+            }),
+            OutputContract::Framed { record_size } => {
+                let partial = output.len() % record_size;
+                (partial != 0).then(|| {
+                    violation(
+                        format!(
+                            "solve_output is {} bytes, not a multiple of the {record_size}-byte record size",
+                            output.len()
+                        ),
+                        Some(output.len() - partial),
+                        None,
+                    )
+                })
+            }
+        }
+    }
+}
+
+impl OutputContractVerdict {
+    /// The verdict for a run made with an output contract set.
+    pub fn of(result: &RunnerResult) -> Self {
+        match (&result.output_contract_violation, result.ok) {
+            (Some(_), _) => OutputContractVerdict::Fail,
+            (None, true) => OutputContractVerdict::Pass,
+            (None, false) => OutputContractVerdict::Skipped,
+        }
+    }
+}
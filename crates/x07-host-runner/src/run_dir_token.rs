@@ -0,0 +1,144 @@
+//! Stable run dir names for [`crate::RunnerConfig::stable_run_dir_token`].
+//!
+//! A run with a token gets its run dir at `<temp>/x07_run_tok_<token>`
+//! instead of a pid- and counter-derived name, so absolute paths a program
+//! observes are the same on every run. Two runs with one token would share
+//! that dir, so a process-wide registry refuses a token that is still held
+//! with [`RunDirTokenInUse`]. A dir left on disk by a run in another process
+//! (or a crashed one) is refused the same way rather than renamed around.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::Result;
+
+/// Env var carrying [`crate::RunnerConfig::stable_run_dir_token`] to the
+/// solver child.
+pub const ENV_RUN_DIR_TOKEN: &str = "X07_RUN_DIR_TOKEN";
+
+pub const RUN_DIR_TOKEN_MAX_LEN: usize = 64;
+
+const RUN_DIR_TOKEN_PREFIX: &str = "x07_run_tok_";
+
+/// The token's run dir is taken, by a live run in this process or by a
+/// directory already on disk. Returned as the run's `Err` so callers can
+/// downcast it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunDirTokenInUse {
+    pub token: String,
+    pub path: PathBuf,
+}
+
+impl std::fmt::Display for RunDirTokenInUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "run dir token {:?} is in use ({}); concurrent runs need distinct tokens",
+            self.token,
+            self.path.display()
+        )
+    }
+}
+
+impl std::error::Error for RunDirTokenInUse {}
+
+/// Tokens are used as a path component: 1 to [`RUN_DIR_TOKEN_MAX_LEN`]
+/// bytes of `[A-Za-z0-9._-]`, not `.` or `..`.
+pub fn validate_run_dir_token(token: &str) -> Result<()> {
+    if token.is_empty() || token.len() > RUN_DIR_TOKEN_MAX_LEN {
+        anyhow::bail!(
+            "run dir token must be 1..={RUN_DIR_TOKEN_MAX_LEN} bytes (got {})",
+            token.len()
+        );
+    }
+    if token == "." || token == ".." {
+        anyhow::bail!("run dir token must not be {token:?}");
+    }
+    if let Some(c) = token
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+    {
+        anyhow::bail!("run dir token {token:?} has invalid character {c:?}");
+    }
+    Ok(())
+}
+
+pub(crate) fn run_dir_for_token(token: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{RUN_DIR_TOKEN_PREFIX}{token}"))
+}
+
+fn active_tokens() -> &'static Mutex<BTreeSet<String>> {
+    static ACTIVE: OnceLock<Mutex<BTreeSet<String>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| Mutex::new(BTreeSet::new()))
+}
+
+/// Holds a token in the registry until dropped.
+#[derive(Debug)]
+pub(crate) struct RunDirTokenLease {
+    token: String,
+}
+
+impl RunDirTokenLease {
+    pub(crate) fn acquire(token: &str) -> Result<Self> {
+        validate_run_dir_token(token)?;
+        let mut active = active_tokens()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !active.insert(token.to_string()) {
+            return Err(RunDirTokenInUse {
+                token: token.to_string(),
+                path: run_dir_for_token(token),
+            }
+            .into());
+        }
+        Ok(Self {
+            token: token.to_string(),
+        })
+    }
+}
+
+impl Drop for RunDirTokenLease {
+    fn drop(&mut self) {
+        active_tokens()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_must_be_a_single_safe_path_component() {
+        for ok in ["a", "case-1.v2_x", &"t".repeat(RUN_DIR_TOKEN_MAX_LEN)] {
+            validate_run_dir_token(ok).unwrap();
+        }
+        for bad in [
+            "",
+            ".",
+            "..",
+            "a/b",
+            "a\\b",
+            "a b",
+            "é",
+            &"t".repeat(RUN_DIR_TOKEN_MAX_LEN + 1),
+        ] {
+            assert!(validate_run_dir_token(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn a_token_is_held_until_its_lease_drops() {
+        let token = format!("lease-{}", std::process::id());
+        let lease = RunDirTokenLease::acquire(&token).unwrap();
+        let err = RunDirTokenLease::acquire(&token).unwrap_err();
+        let err = err.downcast::<RunDirTokenInUse>().unwrap();
+        assert_eq!(err.token, token);
+        assert_eq!(err.path, run_dir_for_token(&token));
+        drop(lease);
+        drop(RunDirTokenLease::acquire(&token).unwrap());
+    }
+}
@@ -0,0 +1,279 @@
+//! Process-wide cap on concurrently running solver children.
+//!
+//! [`crate::run_artifact_file`] takes a slot from the global
+//! [`RunSlotLimiter`] right before spawning the child and gives it back once
+//! the child is reaped, so an embedding service cannot fork more children
+//! than [`set_max_concurrent_runs`] allows. Waiters are served in arrival
+//! order; with [`set_run_slot_acquire_timeout`] a run that waits too long
+//! fails with [`RunSlotError::WouldBlock`] instead of queueing forever.
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunSlotError {
+    /// No slot freed up within the acquire timeout.
+    WouldBlock {
+        max_concurrent_runs: usize,
+        waited_ms: u64,
+    },
+}
+
+impl std::fmt::Display for RunSlotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunSlotError::WouldBlock {
+                max_concurrent_runs,
+                waited_ms,
+            } => write!(
+                f,
+                "all {max_concurrent_runs} run slots busy; gave up after {waited_ms} ms"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RunSlotError {}
+
+/// Counters for metrics; see [`run_slot_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RunSlotStats {
+    pub max_concurrent_runs: usize,
+    pub current_runs: usize,
+    /// Most runs ever held at once.
+    pub high_water_runs: usize,
+    pub waiting: usize,
+    pub acquired_total: u64,
+    pub would_block_total: u64,
+}
+
+#[derive(Debug)]
+struct SlotState {
+    max: usize,
+    active: usize,
+    high_water: usize,
+    next_ticket: u64,
+    /// Tickets of blocked acquirers, oldest first.
+    queue: VecDeque<u64>,
+    acquired_total: u64,
+    would_block_total: u64,
+}
+
+/// FIFO counting semaphore handing out [`RunSlot`] guards.
+#[derive(Debug)]
+pub struct RunSlotLimiter {
+    state: Mutex<SlotState>,
+    freed: Condvar,
+}
+
+impl RunSlotLimiter {
+    /// `max` is clamped to at least 1.
+    pub fn new(max: usize) -> Self {
+        Self {
+            state: Mutex::new(SlotState {
+                max: max.max(1),
+                active: 0,
+                high_water: 0,
+                next_ticket: 0,
+                queue: VecDeque::new(),
+                acquired_total: 0,
+                would_block_total: 0,
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SlotState> {
+        // Slot bookkeeping stays consistent even if a holder panicked.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Raising the limit wakes waiters right away; lowering it lets running
+    /// children finish and only holds back new ones.
+    pub fn set_max(&self, max: usize) {
+        self.lock().max = max.max(1);
+        self.freed.notify_all();
+    }
+
+    /// Waits for a slot behind every earlier waiter. `None` waits as long as
+    /// it takes.
+    pub fn acquire(&self, timeout: Option<Duration>) -> Result<RunSlot<'_>, RunSlotError> {
+        let start = Instant::now();
+        let mut st = self.lock();
+        if st.queue.is_empty() && st.active < st.max {
+            return Ok(self.grant(&mut st));
+        }
+
+        let ticket = st.next_ticket;
+        st.next_ticket += 1;
+        st.queue.push_back(ticket);
+        loop {
+            if st.queue.front() == Some(&ticket) && st.active < st.max {
+                st.queue.pop_front();
+                let slot = self.grant(&mut st);
+                // The next waiter may fit too (e.g. after `set_max`).
+                self.freed.notify_all();
+                return Ok(slot);
+            }
+            st = match timeout {
+                None => self.freed.wait(st).unwrap_or_else(|e| e.into_inner()),
+                Some(timeout) => {
+                    let Some(left) = timeout.checked_sub(start.elapsed()) else {
+                        st.queue.retain(|&t| t != ticket);
+                        st.would_block_total += 1;
+                        // Whoever queued behind us may be first now.
+                        self.freed.notify_all();
+                        return Err(RunSlotError::WouldBlock {
+                            max_concurrent_runs: st.max,
+                            waited_ms: start.elapsed().as_millis().try_into().unwrap_or(u64::MAX),
+                        });
+                    };
+                    self.freed
+                        .wait_timeout(st, left)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+
+    fn grant(&self, st: &mut SlotState) -> RunSlot<'_> {
+        st.active += 1;
+        st.high_water = st.high_water.max(st.active);
+        st.acquired_total += 1;
+        RunSlot { limiter: self }
+    }
+
+    pub fn stats(&self) -> RunSlotStats {
+        let st = self.lock();
+        RunSlotStats {
+            max_concurrent_runs: st.max,
+            current_runs: st.active,
+            high_water_runs: st.high_water,
+            waiting: st.queue.len(),
+            acquired_total: st.acquired_total,
+            would_block_total: st.would_block_total,
+        }
+    }
+}
+
+/// A held slot; dropping it (including while unwinding) frees the slot.
+#[derive(Debug)]
+pub struct RunSlot<'a> {
+    limiter: &'a RunSlotLimiter,
+}
+
+impl Drop for RunSlot<'_> {
+    fn drop(&mut self) {
+        self.limiter.lock().active -= 1;
+        self.limiter.freed.notify_all();
+    }
+}
+
+static RUN_SLOTS: OnceLock<RunSlotLimiter> = OnceLock::new();
+static ACQUIRE_TIMEOUT: Mutex<Option<Duration>> = Mutex::new(None);
+
+/// The limiter [`crate::run_artifact_file`] draws from. Starts with one slot
+/// per available CPU.
+pub fn run_slot_limiter() -> &'static RunSlotLimiter {
+    RUN_SLOTS.get_or_init(|| {
+        RunSlotLimiter::new(std::thread::available_parallelism().map_or(1, |n| n.get()))
+    })
+}
+
+/// Caps concurrently running solver children process-wide (at least 1).
+pub fn set_max_concurrent_runs(n: usize) {
+    run_slot_limiter().set_max(n);
+}
+
+/// How long a run waits for a slot before failing with
+/// [`RunSlotError::WouldBlock`]. `None` (the default) waits indefinitely.
+pub fn set_run_slot_acquire_timeout(timeout: Option<Duration>) {
+    *ACQUIRE_TIMEOUT.lock().unwrap_or_else(|e| e.into_inner()) = timeout;
+}
+
+pub(crate) fn acquire_run_slot() -> Result<RunSlot<'static>, RunSlotError> {
+    let timeout = *ACQUIRE_TIMEOUT.lock().unwrap_or_else(|e| e.into_inner());
+    run_slot_limiter().acquire(timeout)
+}
+
+/// Solver children running right now.
+pub fn current_runs() -> usize {
+    run_slot_limiter().stats().current_runs
+}
+
+pub fn run_slot_stats() -> RunSlotStats {
+    run_slot_limiter().stats()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn times_out_with_would_block_and_frees_on_drop() {
+        let limiter = RunSlotLimiter::new(1);
+        let held = limiter.acquire(None).unwrap();
+        let err = limiter
+            .acquire(Some(Duration::from_millis(20)))
+            .unwrap_err();
+        let RunSlotError::WouldBlock {
+            max_concurrent_runs,
+            waited_ms,
+        } = err;
+        assert_eq!(max_concurrent_runs, 1);
+        assert!(waited_ms >= 20);
+        assert_eq!(limiter.stats().waiting, 0);
+
+        drop(held);
+        let _slot = limiter.acquire(Some(Duration::from_millis(20))).unwrap();
+        let stats = limiter.stats();
+        assert_eq!(stats.current_runs, 1);
+        assert_eq!(stats.acquired_total, 2);
+        assert_eq!(stats.would_block_total, 1);
+    }
+
+    #[test]
+    fn slot_is_released_when_holder_panics() {
+        let limiter = Arc::new(RunSlotLimiter::new(1));
+        let l = Arc::clone(&limiter);
+        let res = std::thread::spawn(move || {
+            let _slot = l.acquire(None).unwrap();
+            panic!("solver setup blew up");
+        })
+        .join();
+        assert!(res.is_err());
+        assert_eq!(limiter.stats().current_runs, 0);
+        assert!(limiter.acquire(Some(Duration::ZERO)).is_ok());
+    }
+
+    #[test]
+    fn waiters_are_served_in_arrival_order() {
+        let limiter = Arc::new(RunSlotLimiter::new(1));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = limiter.acquire(None).unwrap();
+
+        let mut threads = Vec::new();
+        for i in 0..4 {
+            let (l, order) = (Arc::clone(&limiter), Arc::clone(&order));
+            threads.push(std::thread::spawn(move || {
+                let _slot = l.acquire(None).unwrap();
+                order.lock().unwrap().push(i);
+            }));
+            // Queue the waiters one at a time so arrival order is fixed.
+            while limiter.stats().waiting != i + 1 {
+                std::thread::yield_now();
+            }
+        }
+        drop(held);
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2, 3]);
+        assert_eq!(limiter.stats().high_water_runs, 1);
+    }
+}
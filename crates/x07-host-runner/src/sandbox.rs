@@ -0,0 +1,354 @@
+//! What the runner can enforce on the solver child on this host.
+//!
+//! [`sandbox_capabilities`] reports the platform's caps. Each run reports its
+//! effective caps in [`crate::RunnerResult::sandbox_caps`], which can be
+//! narrower: under [`crate::RlimitMode::BestEffort`], an rlimit the runner
+//! cannot apply is dropped. [`crate::RunnerConfig::require_sandbox`] turns a
+//! missing feature into a [`SandboxUnavailable`] error before anything is
+//! staged or spawned.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxFeature {
+    /// CPU time limit (`RLIMIT_CPU`). A wall-clock timeout applies either way.
+    CpuLimit,
+    /// Heap cap. It is compiled into the artifact (`X07_MEM_CAP`), so every
+    /// platform has it.
+    MemoryLimit,
+    /// Zero-size file limit (`RLIMIT_FSIZE`). Writes outside the run dir
+    /// fail.
+    FsizeLimit,
+    /// Staged fixtures are read-only to the solver. Unix uses file modes.
+    /// Windows sets the readonly attribute on files only, because Windows
+    /// ignores that attribute on directories.
+    ReadonlyFixtures,
+    /// A timeout or quota kill also reaches the processes the solver spawned.
+    /// Unix uses a process group. Windows walks the process tree.
+    ProcessGroupKill,
+}
+
+impl SandboxFeature {
+    pub const ALL: [SandboxFeature; 5] = [
+        SandboxFeature::CpuLimit,
+        SandboxFeature::MemoryLimit,
+        SandboxFeature::FsizeLimit,
+        SandboxFeature::ReadonlyFixtures,
+        SandboxFeature::ProcessGroupKill,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SandboxFeature::CpuLimit => "cpu_limit",
+            SandboxFeature::MemoryLimit => "memory_limit",
+            SandboxFeature::FsizeLimit => "fsize_limit",
+            SandboxFeature::ReadonlyFixtures => "readonly_fixtures",
+            SandboxFeature::ProcessGroupKill => "process_group_kill",
+        }
+    }
+}
+
+impl std::fmt::Display for SandboxFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxCaps {
+    pub cpu_limit: bool,
+    pub memory_limit: bool,
+    pub fsize_limit: bool,
+    pub readonly_fixtures: bool,
+    pub process_group_kill: bool,
+}
+
+impl SandboxCaps {
+    pub fn has(&self, feature: SandboxFeature) -> bool {
+        match feature {
+            SandboxFeature::CpuLimit => self.cpu_limit,
+            SandboxFeature::MemoryLimit => self.memory_limit,
+            SandboxFeature::FsizeLimit => self.fsize_limit,
+            SandboxFeature::ReadonlyFixtures => self.readonly_fixtures,
+            SandboxFeature::ProcessGroupKill => self.process_group_kill,
+        }
+    }
+
+    /// `required` features these caps lack, sorted and deduplicated.
+    pub fn missing(&self, required: &[SandboxFeature]) -> Vec<SandboxFeature> {
+        let mut out: Vec<SandboxFeature> =
+            required.iter().copied().filter(|f| !self.has(*f)).collect();
+        out.sort();
+        out.dedup();
+        out
+    }
+}
+
+/// What the current platform can enforce, before any per-run degradation.
+pub fn sandbox_capabilities() -> SandboxCaps {
+    SandboxCaps {
+        cpu_limit: cfg!(unix),
+        memory_limit: true,
+        fsize_limit: cfg!(unix),
+        readonly_fixtures: cfg!(any(unix, windows)),
+        process_group_kill: cfg!(any(unix, windows)),
+    }
+}
+
+/// A [`crate::RunnerConfig::require_sandbox`] feature is not available for
+/// this run. Returned as the run's `Err` so callers can downcast it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxUnavailable {
+    pub missing: Vec<SandboxFeature>,
+    pub caps: SandboxCaps,
+}
+
+impl std::fmt::Display for SandboxUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.missing.iter().map(|m| m.as_str()).collect();
+        write!(
+            f,
+            "required sandbox features unavailable: {}",
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for SandboxUnavailable {}
+
+/// Best-effort kill of `root` and every process descended from it, for
+/// timeout and quota kills on Windows. The tree is read from one toolhelp
+/// snapshot taken before anything is killed, so parent links still point
+/// at live processes. A descendant whose parent had already exited before the
+/// snapshot is no longer linked to `root` and survives.
+///
+/// Windows reuses pids, and a snapshot entry keeps the pid of a parent that
+/// may since have exited. A parent-to-child link is only followed when the
+/// child was created after the parent, and each descendant is killed through
+/// the handle opened while walking the tree, never by pid.
+#[cfg(windows)]
+pub(crate) fn kill_process_tree(child: &mut std::process::Child) {
+    let descendants = win::descendants_of(child.id());
+    let _ = child.kill();
+    for process in &descendants {
+        process.terminate();
+    }
+}
+
+#[cfg(windows)]
+mod win {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    type Handle = isize;
+    const INVALID_HANDLE_VALUE: Handle = -1;
+    const TH32CS_SNAPPROCESS: u32 = 0x0000_0002;
+    const PROCESS_TERMINATE: u32 = 0x0001;
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+
+    #[repr(C)]
+    struct ProcessEntry32W {
+        dw_size: u32,
+        cnt_usage: u32,
+        th32_process_id: u32,
+        th32_default_heap_id: usize,
+        th32_module_id: u32,
+        cnt_threads: u32,
+        th32_parent_process_id: u32,
+        pc_pri_class_base: i32,
+        dw_flags: u32,
+        sz_exe_file: [u16; 260],
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateToolhelp32Snapshot(flags: u32, pid: u32) -> Handle;
+        fn Process32FirstW(snapshot: Handle, entry: *mut ProcessEntry32W) -> i32;
+        fn Process32NextW(snapshot: Handle, entry: *mut ProcessEntry32W) -> i32;
+        fn OpenProcess(access: u32, inherit: i32, pid: u32) -> Handle;
+        fn TerminateProcess(process: Handle, exit_code: u32) -> i32;
+        fn GetProcessTimes(
+            process: Handle,
+            creation: *mut FileTime,
+            exit: *mut FileTime,
+            kernel: *mut FileTime,
+            user: *mut FileTime,
+        ) -> i32;
+        fn CloseHandle(handle: Handle) -> i32;
+    }
+
+    /// `(pid, parent_pid)` for every process in a fresh snapshot.
+    fn process_list() -> Vec<(u32, u32)> {
+        let mut out = Vec::new();
+        let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+        if snapshot == INVALID_HANDLE_VALUE {
+            return out;
+        }
+        let mut entry = ProcessEntry32W {
+            dw_size: std::mem::size_of::<ProcessEntry32W>() as u32,
+            cnt_usage: 0,
+            th32_process_id: 0,
+            th32_default_heap_id: 0,
+            th32_module_id: 0,
+            cnt_threads: 0,
+            th32_parent_process_id: 0,
+            pc_pri_class_base: 0,
+            dw_flags: 0,
+            sz_exe_file: [0; 260],
+        };
+        let mut ok = unsafe { Process32FirstW(snapshot, &mut entry) };
+        while ok != 0 {
+            out.push((entry.th32_process_id, entry.th32_parent_process_id));
+            ok = unsafe { Process32NextW(snapshot, &mut entry) };
+        }
+        unsafe { CloseHandle(snapshot) };
+        out
+    }
+
+    /// An open process handle. Holding it keeps the pid from being reused.
+    pub(super) struct Process {
+        handle: Handle,
+        /// Creation time, in 100ns ticks since 1601.
+        created: u64,
+    }
+
+    impl Process {
+        fn open(pid: u32) -> Option<Self> {
+            let handle = unsafe {
+                OpenProcess(
+                    PROCESS_TERMINATE | PROCESS_QUERY_LIMITED_INFORMATION,
+                    0,
+                    pid,
+                )
+            };
+            if handle == 0 {
+                return None;
+            }
+            let mut creation = FileTime::default();
+            let mut exit = FileTime::default();
+            let mut kernel = FileTime::default();
+            let mut user = FileTime::default();
+            let ok = unsafe {
+                GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user)
+            };
+            if ok == 0 {
+                unsafe { CloseHandle(handle) };
+                return None;
+            }
+            Some(Self {
+                handle,
+                created: (u64::from(creation.high) << 32) | u64::from(creation.low),
+            })
+        }
+
+        pub(super) fn terminate(&self) {
+            unsafe { TerminateProcess(self.handle, 1) };
+        }
+    }
+
+    impl Drop for Process {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.handle) };
+        }
+    }
+
+    /// Descendants of `root`, parents before children.
+    pub(super) fn descendants_of(root: u32) -> Vec<Process> {
+        let Some(root_process) = Process::open(root) else {
+            return Vec::new();
+        };
+        let mut children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for (pid, ppid) in process_list() {
+            // pid 0 lists itself as its own parent.
+            if pid != ppid {
+                children.entry(ppid).or_default().push(pid);
+            }
+        }
+        let mut seen = BTreeSet::from([root]);
+        let mut out = Vec::new();
+        let mut queue = vec![(root, root_process.created)];
+        while let Some((pid, parent_created)) = queue.pop() {
+            for &c in children.get(&pid).map(Vec::as_slice).unwrap_or(&[]) {
+                if !seen.insert(c) {
+                    continue;
+                }
+                let Some(process) = Process::open(c) else {
+                    continue;
+                };
+                // Older than `pid`: its real parent exited and `pid` was
+                // reused, so it is not part of this tree.
+                if process.created < parent_created {
+                    continue;
+                }
+                queue.push((c, process.created));
+                out.push(process);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_lists_each_absent_feature_once_in_order() {
+        let caps = SandboxCaps {
+            memory_limit: true,
+            readonly_fixtures: true,
+            ..SandboxCaps::default()
+        };
+        assert_eq!(
+            caps.missing(&[
+                SandboxFeature::ProcessGroupKill,
+                SandboxFeature::MemoryLimit,
+                SandboxFeature::CpuLimit,
+                SandboxFeature::ProcessGroupKill,
+            ]),
+            vec![SandboxFeature::CpuLimit, SandboxFeature::ProcessGroupKill]
+        );
+        assert!(caps.missing(&[]).is_empty());
+        assert_eq!(
+            SandboxUnavailable {
+                missing: vec![SandboxFeature::CpuLimit, SandboxFeature::FsizeLimit],
+                caps,
+            }
+            .to_string(),
+            "required sandbox features unavailable: cpu_limit, fsize_limit"
+        );
+    }
+
+    #[test]
+    fn feature_names_match_the_caps_fields() {
+        let json = serde_json::to_value(SandboxCaps::default()).unwrap();
+        for f in SandboxFeature::ALL {
+            assert!(json.get(f.as_str()).is_some(), "{f}");
+            assert_eq!(serde_json::to_value(f).unwrap(), f.as_str());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_reports_every_feature() {
+        assert!(sandbox_capabilities()
+            .missing(&SandboxFeature::ALL)
+            .is_empty());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_reports_no_rlimits() {
+        assert_eq!(
+            sandbox_capabilities().missing(&SandboxFeature::ALL),
+            vec![SandboxFeature::CpuLimit, SandboxFeature::FsizeLimit]
+        );
+    }
+}
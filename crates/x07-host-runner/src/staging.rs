@@ -0,0 +1,270 @@
+//! Typed failures from staging fixtures into the solver's run dir.
+//!
+//! Fixture problems (a missing dir, an unsafe path, a file that cannot be
+//! copied, a malformed latency index) come back as a [`StagingError`] in
+//! [`crate::RunnerResult::staging_error`] rather than as an `Err` from the
+//! run, so callers can tell "the fixtures are broken" from "the runner is
+//! broken". The `Display` impl keeps the human-readable message.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+/// Trap reported alongside [`crate::RunnerResult::staging_error`].
+pub const STAGING_FAILED_TRAP: &str = "fixture staging failed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexKind {
+    /// `.x07_fs/latency.evfslat` from `fixture_fs_latency_index`.
+    FsLatency,
+    /// `.x07_kv/seed.evkv` + `latency.evkvlat` from `fixture_kv_seed` or
+    /// `kv_seed_inline`.
+    KvSeed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayKind {
+    /// A dir from `fixture_fs_overlays`.
+    Fs,
+    /// `fixture_kv_overlay_seed`.
+    Kv,
+}
+
+/// An overlay staged on top of the base fixtures, as recorded in
+/// [`crate::RunnerResult::fixture_overlays`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FixtureOverlay {
+    pub kind: OverlayKind,
+    /// As configured, relative to its fixture dir.
+    pub path: PathBuf,
+    /// [`crate::fixture_tree_digest`] of an fs overlay (`None` when it holds
+    /// no files), or the sha256 of a kv overlay seed file.
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum StagingError {
+    /// `which` names the config field (`fixture_fs_dir`, `fixture_fs_root`,
+    /// `fixture_kv_seed`, ...) that is unset or points at nothing.
+    MissingFixtureDir {
+        which: String,
+    },
+    UnsafeRelPath {
+        path: PathBuf,
+    },
+    /// `errno` is the OS error code, when there was one.
+    CopyFailed {
+        src: PathBuf,
+        dst: PathBuf,
+        errno: Option<i32>,
+    },
+    /// `src` is `None` for `kv_seed_inline`.
+    IndexGenerationFailed {
+        kind: IndexKind,
+        src: Option<PathBuf>,
+        reason: String,
+    },
+    /// Fixtures may only hold regular files and dirs.
+    UnsupportedEntryType {
+        path: PathBuf,
+    },
+    /// `overlay` has a file where the staged fixture has a dir, or the other
+    /// way round. `path` is relative to the run dir.
+    OverlayConflict {
+        overlay: PathBuf,
+        path: PathBuf,
+    },
+}
+
+impl StagingError {
+    pub(crate) fn missing(which: &str) -> Self {
+        StagingError::MissingFixtureDir {
+            which: which.to_string(),
+        }
+    }
+
+    pub(crate) fn copy_failed(src: &Path, dst: &Path, err: &std::io::Error) -> Self {
+        StagingError::CopyFailed {
+            src: src.to_path_buf(),
+            dst: dst.to_path_buf(),
+            errno: err.raw_os_error(),
+        }
+    }
+
+    pub(crate) fn index_failed(kind: IndexKind, src: Option<&Path>, err: &anyhow::Error) -> Self {
+        StagingError::IndexGenerationFailed {
+            kind,
+            src: src.map(Path::to_path_buf),
+            reason: format!("{err:#}"),
+        }
+    }
+}
+
+impl std::fmt::Display for StagingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StagingError::MissingFixtureDir { which } => write!(f, "missing fixture: {which}"),
+            StagingError::UnsafeRelPath { path } => {
+                write!(f, "expected safe relative path, got {}", path.display())
+            }
+            StagingError::CopyFailed { src, dst, errno } => {
+                write!(f, "copy fixture {} to {}", src.display(), dst.display())?;
+                match errno {
+                    Some(code) => write!(f, ": {}", std::io::Error::from_raw_os_error(*code)),
+                    None => Ok(()),
+                }
+            }
+            StagingError::IndexGenerationFailed { kind, src, reason } => {
+                let kind = match kind {
+                    IndexKind::FsLatency => "fs latency",
+                    IndexKind::KvSeed => "kv seed",
+                };
+                match src {
+                    Some(src) => write!(f, "generate {kind} index from {}", src.display())?,
+                    None => write!(f, "generate {kind} index from kv_seed_inline")?,
+                }
+                write!(f, ": {reason}")
+            }
+            StagingError::UnsupportedEntryType { path } => {
+                write!(f, "unsupported fixture entry type: {}", path.display())
+            }
+            StagingError::OverlayConflict { overlay, path } => write!(
+                f,
+                "fixture overlay {} conflicts with the staged entry type at {}",
+                overlay.display(),
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StagingError {}
+
+/// [`crate::ensure_safe_rel_path`] as a [`StagingError`].
+pub(crate) fn safe_rel_path(rel: &Path) -> Result<(), StagingError> {
+    crate::ensure_safe_rel_path(rel).map_err(|_| StagingError::UnsafeRelPath {
+        path: rel.to_path_buf(),
+    })
+}
+
+pub(crate) fn copy_dir_contents(src_dir: &Path, dst_dir: &Path) -> Result<(), StagingError> {
+    let entries =
+        std::fs::read_dir(src_dir).map_err(|e| StagingError::copy_failed(src_dir, dst_dir, &e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| StagingError::copy_failed(src_dir, dst_dir, &e))?;
+        let src_path = entry.path();
+        let dst_path = dst_dir.join(entry.file_name());
+        let file_type = entry
+            .file_type()
+            .map_err(|e| StagingError::copy_failed(&src_path, &dst_path, &e))?;
+        copy_tree(&src_path, &dst_path, &file_type)?;
+    }
+    Ok(())
+}
+
+/// Copies `src_dir` over the already staged `dst_dir`: new entries are
+/// copied, files replace files and dirs merge into dirs. `overlay` is the
+/// configured overlay path, for errors.
+pub(crate) fn overlay_dir_contents(
+    src_dir: &Path,
+    dst_dir: &Path,
+    overlay: &Path,
+) -> Result<(), StagingError> {
+    overlay_tree(src_dir, dst_dir, overlay, Path::new(""))
+}
+
+fn overlay_tree(
+    src_dir: &Path,
+    dst_dir: &Path,
+    overlay: &Path,
+    rel: &Path,
+) -> Result<(), StagingError> {
+    let entries =
+        std::fs::read_dir(src_dir).map_err(|e| StagingError::copy_failed(src_dir, dst_dir, &e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| StagingError::copy_failed(src_dir, dst_dir, &e))?;
+        let src_path = entry.path();
+        let dst_path = dst_dir.join(entry.file_name());
+        let rel = rel.join(entry.file_name());
+        let src_type = entry
+            .file_type()
+            .map_err(|e| StagingError::copy_failed(&src_path, &dst_path, &e))?;
+        if !src_type.is_dir() && !src_type.is_file() {
+            return Err(StagingError::UnsupportedEntryType { path: src_path });
+        }
+        let dst_type = match std::fs::symlink_metadata(&dst_path) {
+            Ok(md) => md.file_type(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                copy_tree(&src_path, &dst_path, &src_type)?;
+                continue;
+            }
+            Err(e) => return Err(StagingError::copy_failed(&src_path, &dst_path, &e)),
+        };
+        if src_type.is_dir() && dst_type.is_dir() {
+            overlay_tree(&src_path, &dst_path, overlay, &rel)?;
+        } else if src_type.is_file() && dst_type.is_file() {
+            std::fs::copy(&src_path, &dst_path)
+                .map_err(|e| StagingError::copy_failed(&src_path, &dst_path, &e))?;
+        } else {
+            return Err(StagingError::OverlayConflict {
+                overlay: overlay.to_path_buf(),
+                path: rel,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn copy_tree(src: &Path, dst: &Path, src_type: &std::fs::FileType) -> Result<(), StagingError> {
+    if src_type.is_dir() {
+        std::fs::create_dir(dst).map_err(|e| StagingError::copy_failed(src, dst, &e))?;
+        return copy_dir_contents(src, dst);
+    }
+    if src_type.is_file() {
+        std::fs::copy(src, dst).map_err(|e| StagingError::copy_failed(src, dst, &e))?;
+        return Ok(());
+    }
+    Err(StagingError::UnsupportedEntryType {
+        path: src.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_with_code_tag() {
+        let err = StagingError::CopyFailed {
+            src: PathBuf::from("fx/a.bin"),
+            dst: PathBuf::from("run/a.bin"),
+            errno: Some(13),
+        };
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({
+                "code": "copy_failed",
+                "src": "fx/a.bin",
+                "dst": "run/a.bin",
+                "errno": 13,
+            })
+        );
+        assert!(err
+            .to_string()
+            .starts_with("copy fixture fx/a.bin to run/a.bin: "));
+
+        let err = StagingError::IndexGenerationFailed {
+            kind: IndexKind::KvSeed,
+            src: None,
+            reason: "kv seed too many entries".to_string(),
+        };
+        assert_eq!(serde_json::to_value(&err).unwrap()["kind"], "kv_seed");
+        assert_eq!(
+            err.to_string(),
+            "generate kv seed index from kv_seed_inline: kv seed too many entries"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use x07_host_runner::{
+    compile_c_and_run, CPassthroughOverrides, RunnerConfig, C_PASSTHROUGH_LANG_ID,
+};
+use x07_worlds::WorldId;
+
+fn config() -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        ..Default::default()
+    }
+}
+
+// Input arrives framed like the output (u32 LE length prefix), so echoing
+// stdin verbatim is a valid solver. Reports fixed metrics on stderr.
+const ECHO_SOLVER_C: &str = r#"
+#include <stdio.h>
+
+int main(void) {
+  static unsigned char buf[4096];
+  size_t n = fread(buf, 1, sizeof(buf), stdin);
+  fwrite(buf, 1, n, stdout);
+  fflush(stdout);
+  fprintf(stderr, "{\"fuel_used\":%llu,\"heap_used\":64}\n",
+          (unsigned long long)X07_FUEL_INIT / 1000ULL);
+  return 0;
+}
+"#;
+
+#[test]
+fn hand_written_c_runs_through_the_runner() {
+    let cfg = config();
+    let res = compile_c_and_run(
+        ECHO_SOLVER_C,
+        &cfg,
+        &CPassthroughOverrides::default(),
+        b"hello",
+    )
+    .expect("compile_c_and_run");
+    assert!(
+        res.compile.ok,
+        "compile_error={:?}",
+        res.compile.compile_error
+    );
+    assert_eq!(res.compile.lang_id, C_PASSTHROUGH_LANG_ID);
+    assert!(res.compile.native_requires.requires.is_empty());
+    assert_eq!(res.compile.fuel_used, None);
+    assert!(res.compile.native_cache_key.is_some());
+
+    let solve = res.solve.expect("solve result");
+    assert!(solve.ok, "trap={:?}", solve.trap);
+    assert_eq!(solve.solve_output, b"hello");
+    // The world defines reach cc, and the metrics line is parsed as usual.
+    assert_eq!(solve.fuel_used, Some(10_000));
+    assert_eq!(solve.heap_used, Some(64));
+}
+
+#[test]
+fn cc_failure_is_reported_without_running() {
+    let cfg = config();
+    let res = compile_c_and_run(
+        "int main(void) { return not_declared; }\n",
+        &cfg,
+        &CPassthroughOverrides::default(),
+        b"",
+    )
+    .expect("compile_c_and_run");
+    assert!(!res.compile.ok);
+    assert_eq!(res.compile.lang_id, C_PASSTHROUGH_LANG_ID);
+    assert!(res.solve.is_none());
+    assert!(res
+        .compile
+        .compile_error
+        .as_deref()
+        .is_some_and(|e| e.starts_with("C toolchain failed")));
+}
+
+#[test]
+fn trap_detail_carries_the_abort_message() {
+    let cfg = config();
+    let src = r#"
+#include <stdio.h>
+#include <stdlib.h>
+
+int main(void) {
+  for (int i = 0; i < 100; i++) fputs("check failed: len > 0\n", stderr);
+  fputs("solver: invariant broken, aborting\n", stderr);
+  fflush(stderr);
+  abort();
+}
+"#;
+    let res = compile_c_and_run(src, &cfg, &CPassthroughOverrides::default(), b"")
+        .expect("compile_c_and_run");
+    assert!(
+        res.compile.ok,
+        "compile_error={:?}",
+        res.compile.compile_error
+    );
+    let solve = res.solve.expect("solve result");
+    assert!(!solve.ok);
+    assert!(solve.trap.is_some());
+    assert_eq!(
+        solve.trap_detail.as_deref(),
+        Some("check failed: len > 0 [repeated 100 times]\nsolver: invariant broken, aborting")
+    );
+    // Raw stderr is left as the child wrote it.
+    assert!(solve
+        .stderr
+        .starts_with(b"check failed: len > 0\ncheck failed"));
+}
+
+#[test]
+fn trap_detail_is_unset_without_a_trap() {
+    let res = compile_c_and_run(
+        ECHO_SOLVER_C,
+        &config(),
+        &CPassthroughOverrides::default(),
+        b"x",
+    )
+    .expect("compile_c_and_run");
+    let solve = res.solve.expect("solve result");
+    assert!(solve.ok);
+    assert_eq!(solve.trap_detail, None);
+}
@@ -3,6 +3,8 @@ use std::process::Command;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use serde_json::json;
+use x07_host_runner::{compile_and_run, compile_program, RunnerConfig};
+use x07_worlds::WorldId;
 
 mod x07_program;
 
@@ -81,6 +83,12 @@ fn cli_module_root_allows_importing_user_modules() {
             .and_then(|ok| ok.as_bool()),
         Some(true)
     );
+    let fingerprint = v
+        .get("compile")
+        .and_then(|c| c.get("options_fingerprint"))
+        .and_then(|fp| fp.as_str())
+        .expect("compile.options_fingerprint");
+    assert_eq!(fingerprint.len(), 64, "fingerprint={fingerprint}");
 
     let bad_out = Command::new(bin)
         .arg("--program")
@@ -108,3 +116,103 @@ fn cli_module_root_allows_importing_user_modules() {
 
     let _ = std::fs::remove_dir_all(&dir);
 }
+
+fn config(module_roots: Vec<PathBuf>) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        module_roots,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn runner_config_module_roots_resolve_user_modules() {
+    let dir = temp_dir("x07_host_runner_config_module_root");
+    let module_root = dir.join("module_root");
+    let module_path = module_root.join("ext").join("foo.x07.json");
+    std::fs::create_dir_all(module_path.parent().unwrap()).expect("create module dir");
+
+    let module = module_file(
+        "ext.foo",
+        vec![
+            x07_program::export(&["ext.foo.answer"]),
+            x07_program::defn("ext.foo.answer", &[], "i32", json!(7)),
+        ],
+    );
+    std::fs::write(&module_path, module).expect("write module");
+
+    let program = x07_program::entry(
+        &["ext.foo"],
+        json!(["codec.write_u32_le", ["ext.foo.answer"]]),
+    );
+
+    let res = compile_and_run(&program, &config(vec![module_root]), b"", None).expect("runner ok");
+    assert!(
+        res.compile.ok,
+        "compile_error={:?}",
+        res.compile.compile_error
+    );
+    let solve = res.solve.expect("solve result");
+    assert!(solve.ok, "trap={:?}", solve.trap);
+    assert_eq!(solve.solve_output, 7u32.to_le_bytes().to_vec());
+
+    let res = compile_and_run(&program, &config(Vec::new()), b"", None).expect("runner ok");
+    assert!(!res.compile.ok, "expected ext.foo to be unresolved");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn runner_config_rejects_missing_module_root() {
+    let dir = temp_dir("x07_host_runner_config_module_root_missing");
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let err = compile_and_run(&program, &config(vec![dir.join("nope")]), b"", None)
+        .expect_err("missing module root must be rejected");
+    assert!(
+        format!("{err:#}").contains("module root is not a directory"),
+        "err={err:#}"
+    );
+}
+
+#[test]
+fn options_fingerprint_tracks_module_content_not_root_path() {
+    let dir = temp_dir("x07_host_runner_options_fingerprint");
+    let write_root = |name: &str, answer: i64| {
+        let root = dir.join(name);
+        let module_path = root.join("ext").join("foo.x07.json");
+        std::fs::create_dir_all(module_path.parent().unwrap()).expect("create module dir");
+        let module = module_file(
+            "ext.foo",
+            vec![
+                x07_program::export(&["ext.foo.answer"]),
+                x07_program::defn("ext.foo.answer", &[], "i32", json!(answer)),
+            ],
+        );
+        std::fs::write(&module_path, module).expect("write module");
+        root
+    };
+    let root_a = write_root("root_a", 7);
+    let root_b = write_root("root_b", 7);
+    let root_c = write_root("root_c", 8);
+
+    let program = x07_program::entry(
+        &["ext.foo"],
+        json!(["codec.write_u32_le", ["ext.foo.answer"]]),
+    );
+    let fingerprint = |root: PathBuf| {
+        let res = compile_program(&program, &config(vec![root]), None).expect("compile");
+        assert!(res.ok, "compile_error={:?}", res.compile_error);
+        res.options_fingerprint
+    };
+
+    let a = fingerprint(root_a);
+    assert_eq!(a.len(), 64, "fingerprint={a}");
+    assert_eq!(a, fingerprint(root_b), "same content under another path");
+    assert_ne!(a, fingerprint(root_c), "edited module content");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
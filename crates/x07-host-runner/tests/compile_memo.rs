@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use x07_host_runner::{compile_program_memoized, run_artifact_file, RunnerConfig};
+use x07_worlds::WorldId;
+
+mod x07_program;
+
+fn config() -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        ..Default::default()
+    }
+}
+
+fn memo_entries(cache: &Path) -> usize {
+    std::fs::read_dir(cache.join("memo"))
+        .map(|rd| rd.count())
+        .unwrap_or(0)
+}
+
+// Single test in this binary: it owns `X07_NATIVE_CACHE_DIR`.
+#[test]
+fn identical_program_skips_codegen_on_second_compile() {
+    let cache: PathBuf = std::env::temp_dir().join(format!(
+        "x07_compile_memo_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&cache).unwrap();
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &cache);
+
+    let cfg = config();
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+
+    let first = compile_program_memoized(&program, &cfg, None).expect("compile ok");
+    assert!(first.ok, "compile_error={:?}", first.compile_error);
+    assert!(!first.memo_hit);
+    assert_eq!(memo_entries(&cache), 1);
+
+    let out = cache.join("out/solver");
+    let second = compile_program_memoized(&program, &cfg, Some(&out)).expect("compile ok");
+    assert!(second.ok, "compile_error={:?}", second.compile_error);
+    assert!(second.memo_hit);
+    assert_eq!(second.codegen_ms, 0);
+    assert_eq!(second.compiled_exe.as_deref(), Some(out.as_path()));
+    assert_eq!(second.c_source_size, first.c_source_size);
+    assert_eq!(second.fuel_used, first.fuel_used);
+    assert_eq!(second.options_fingerprint, first.options_fingerprint);
+
+    let res = run_artifact_file(&cfg, &out, b"memo").expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert_eq!(res.solve_output, b"memo");
+
+    // Runner limits are baked into the exe, so they key the memo too.
+    let other = RunnerConfig {
+        solve_fuel: cfg.solve_fuel + 1,
+        ..config()
+    };
+    let third = compile_program_memoized(&program, &other, None).expect("compile ok");
+    assert!(third.ok, "compile_error={:?}", third.compile_error);
+    assert!(!third.memo_hit);
+    assert_eq!(memo_entries(&cache), 2);
+
+    // Editing a file named in `X07_CC_ARGS` changes the exe, so it misses.
+    let header = cache.join("extra.h");
+    std::fs::write(&header, "/* v1 */\n").unwrap();
+    std::env::set_var("X07_CC_ARGS", format!("-include {}", header.display()));
+    let with_header = compile_program_memoized(&program, &cfg, None).expect("compile ok");
+    assert!(!with_header.memo_hit);
+    let again = compile_program_memoized(&program, &cfg, None).expect("compile ok");
+    assert!(again.memo_hit);
+    std::fs::write(&header, "/* v2 */\n").unwrap();
+    let edited = compile_program_memoized(&program, &cfg, None).expect("compile ok");
+    assert!(edited.ok, "compile_error={:?}", edited.compile_error);
+    assert!(!edited.memo_hit);
+    std::env::remove_var("X07_CC_ARGS");
+
+    // A memo whose exe was evicted from the native cache is not trusted.
+    std::fs::remove_file(first.compiled_exe.as_deref().expect("cached exe")).unwrap();
+    let fourth = compile_program_memoized(&program, &cfg, None).expect("compile ok");
+    assert!(fourth.ok, "compile_error={:?}", fourth.compile_error);
+    assert!(!fourth.memo_hit);
+    assert!(fourth.compiled_exe.as_deref().is_some_and(Path::is_file));
+
+    std::env::remove_var("X07_NATIVE_CACHE_DIR");
+    let _ = std::fs::remove_dir_all(&cache);
+}
@@ -7,17 +7,11 @@ mod x07_program;
 fn pure_cfg() -> RunnerConfig {
     RunnerConfig {
         world: WorldId::SolvePure,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 5_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 20,
-        debug_borrow_checks: false,
+        ..Default::default()
     }
 }
 
@@ -0,0 +1,97 @@
+use serde_json::json;
+use x07_host_runner::determinism::{self, DeterminismField, DeterminismOptions};
+use x07_host_runner::{compile_c_and_run, CPassthroughOverrides, RunnerConfig};
+use x07_worlds::WorldId;
+
+mod x07_program;
+
+fn config() -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        ..Default::default()
+    }
+}
+
+// Hand-written C outside any eval world: the output is the wall clock.
+const CLOCK_SOLVER_C: &str = r#"
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+
+int main(void) {
+  struct timespec ts;
+  char buf[64];
+  unsigned char len[4];
+  timespec_get(&ts, TIME_UTC);
+  int n = snprintf(buf, sizeof(buf), "%lld.%09ld", (long long)ts.tv_sec, (long)ts.tv_nsec);
+  for (int i = 0; i < 4; i++) len[i] = (unsigned char)((unsigned)n >> (8 * i));
+  fwrite(len, 1, 4, stdout);
+  fwrite(buf, 1, (size_t)n, stdout);
+  fflush(stdout);
+  fprintf(stderr, "{\"fuel_used\":1,\"heap_used\":64}\n");
+  return 0;
+}
+"#;
+
+#[test]
+fn deterministic_program_is_all_equal() {
+    let cfg = config();
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+
+    for options in [
+        DeterminismOptions::default(),
+        DeterminismOptions {
+            vary_run_dir_token: true,
+        },
+    ] {
+        let report = determinism::check_with_options(&program, &cfg, b"same input", 4, options)
+            .expect("determinism check");
+        assert!(report.deterministic, "{report:?}");
+        assert_eq!(report.runs, 4);
+        assert_eq!(report.observations.len(), 4);
+        assert!(report.divergence.is_none());
+        assert!(report
+            .observations
+            .iter()
+            .all(|o| o.ok && *o == report.observations[0]));
+        assert!(report.observations[0].fuel_used.is_some());
+    }
+
+    assert!(determinism::check(&program, &cfg, b"x", 1).is_err());
+}
+
+#[test]
+fn clock_dependent_program_diverges_on_solve_output() {
+    let cfg = config();
+    let built = compile_c_and_run(CLOCK_SOLVER_C, &cfg, &CPassthroughOverrides::default(), b"")
+        .expect("compile_c_and_run");
+    assert!(built.compile.ok, "{:?}", built.compile.compile_error);
+    let exe = built.compile.compiled_exe.expect("compiled exe");
+
+    let report = determinism::check_artifact(&cfg, &exe, b"", 3, DeterminismOptions::default())
+        .expect("determinism check");
+    assert!(!report.deterministic);
+    assert_eq!(report.observations.len(), 3);
+
+    let divergence = report.divergence.as_ref().expect("divergence");
+    assert_eq!(divergence.field, DeterminismField::SolveOutput);
+    assert_eq!(divergence.run, 1);
+    assert_eq!(
+        divergence.baseline.as_deref(),
+        Some(report.observations[0].solve_output_sha256.as_str())
+    );
+    assert_eq!(
+        divergence.value.as_deref(),
+        Some(report.observations[1].solve_output_sha256.as_str())
+    );
+    assert!(divergence.output_diff.is_some());
+
+    let doc = serde_json::to_value(&report).expect("serialize report");
+    assert_eq!(doc["schema_version"], "x07.host_runner.determinism@0.1.0");
+    assert_eq!(doc["divergence"]["field"], "solve_output");
+    assert_eq!(doc["divergence"]["output_diff"]["kind"], "bytes");
+}
@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+use x07_host_runner::{
+    aggregate_event_log, compile_and_run, read_event_log, EventLog, RunnerConfig,
+    EVENT_LOG_MAX_LINE_BYTES, EVENT_LOG_SCHEMA_VERSION,
+};
+use x07_worlds::WorldId;
+
+mod x07_program;
+
+fn config() -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        ..Default::default()
+    }
+}
+
+fn log_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "x07_host_runner_event_log_{name}_{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn concurrent_runs_append_whole_lines_to_one_file() {
+    let path = log_path("concurrent");
+    let ok_program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let bad_program = x07_program::entry(&[], json!(["no.such.fn", "input"]));
+
+    std::thread::scope(|s| {
+        for t in 0..4 {
+            let (path, program) = (
+                &path,
+                if t % 2 == 0 {
+                    &ok_program
+                } else {
+                    &bad_program
+                },
+            );
+            s.spawn(move || {
+                // A handle per thread, as separate runner processes would have.
+                let mut cfg = config();
+                cfg.event_log = Some(EventLog::open(path).expect("open log"));
+                for _ in 0..2 {
+                    compile_and_run(program, &cfg, b"abc", None).expect("runner ok");
+                }
+            });
+        }
+    });
+
+    let text = std::fs::read_to_string(&path).expect("read log");
+    let mut runs = std::collections::BTreeSet::new();
+    for line in text.lines() {
+        assert!(line.len() < EVENT_LOG_MAX_LINE_BYTES, "line too long");
+        let v: Value = serde_json::from_str(line).expect("well-formed line");
+        assert_eq!(v["schema_version"], json!(EVENT_LOG_SCHEMA_VERSION));
+        runs.insert(v["run"].as_str().unwrap().to_string());
+    }
+    assert_eq!(runs.len(), 8);
+
+    let stats = read_event_log(&path).expect("aggregate");
+    assert_eq!(stats.malformed_lines, 0);
+    assert_eq!(stats.programs.len(), 2);
+    let (ok, bad) = stats.programs.values().fold((0, 0), |(ok, bad), p| {
+        assert_eq!(p.runs, 4);
+        (ok + p.ok, bad + p.compile_failures)
+    });
+    assert_eq!((ok, bad), (4, 4));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn aggregator_folds_summaries_per_program() {
+    let line = |v: Value| {
+        let mut v = v;
+        v["schema_version"] = json!(EVENT_LOG_SCHEMA_VERSION);
+        v.to_string()
+    };
+    let log = [
+        line(json!({"event": "phase", "phase": "compile_started", "run": "1-0"})),
+        line(
+            json!({"event": "summary", "run": "1-0", "program_sha256": "aa",
+            "compile_ok": true, "ok": true, "cache_hit": false, "fuel_used": 100}),
+        ),
+        line(
+            json!({"event": "summary", "run": "1-1", "program_sha256": "aa",
+            "compile_ok": true, "ok": false, "cache_hit": true, "fuel_used": 300,
+            "trap": "fuel exhausted", "trap_kind": "fuel exhausted"}),
+        ),
+        line(
+            json!({"event": "summary", "run": "2-0", "program_sha256": "bb",
+            "compile_ok": false, "ok": false, "cache_hit": null}),
+        ),
+        line(
+            json!({"event": "summary", "run": "2-1", "program_sha256": "bb",
+            "ok": false, "error": "spawn failed"}),
+        ),
+        String::new(),
+        "{\"event\":\"summary\"".to_string(),
+        json!({"schema_version": "other@0.1.0", "event": "summary"}).to_string(),
+    ]
+    .join("\n");
+
+    let stats = aggregate_event_log(log.as_bytes()).expect("aggregate");
+    assert_eq!(stats.events, 5);
+    assert_eq!(stats.malformed_lines, 2);
+
+    let aa = &stats.programs["aa"];
+    assert_eq!((aa.runs, aa.ok, aa.cache_hits), (2, 1, 1));
+    assert_eq!((aa.fuel_used_total, aa.fuel_used_max), (400, 300));
+    assert_eq!(aa.traps.get("fuel exhausted"), Some(&1));
+
+    let bb = &stats.programs["bb"];
+    assert_eq!(
+        (bb.runs, bb.ok, bb.compile_failures, bb.errors),
+        (2, 0, 1, 1)
+    );
+    assert!(bb.traps.is_empty());
+}
@@ -3,8 +3,10 @@ use std::process::Command;
 
 use serde_json::json;
 use x07_host_runner::{
-    compile_bundle_exe, compile_options_for_world, compile_program, run_artifact_file,
-    NativeCliWrapperOpts, NativeToolchainConfig, RunnerConfig,
+    archive_compile, compile_bundle_exe, compile_options_for_world, compile_program,
+    run_artifact_file, write_bundle_manifest, BundleManifest, NativeCliWrapperOpts,
+    NativeToolchainConfig, RunnerConfig, ARCHIVE_C_FILE, ARCHIVE_EXE_FILE, ARCHIVE_MANIFEST_FILE,
+    ARCHIVE_REPORT_FILE,
 };
 use x07_worlds::WorldId;
 
@@ -13,17 +15,11 @@ mod x07_program;
 fn config() -> RunnerConfig {
     RunnerConfig {
         world: WorldId::SolvePure,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 20,
-        debug_borrow_checks: false,
+        ..Default::default()
     }
 }
 
@@ -80,11 +76,19 @@ fn bundle_wrapper_returns_set_exit_code() {
         world_tag: compile_options.world.as_str().to_string(),
         fuel_init: cfg.solve_fuel,
         mem_cap_bytes: cfg.max_memory_bytes,
+        arena_cap_bytes: None,
         debug_borrow_checks: cfg.debug_borrow_checks,
         enable_fs: compile_options.enable_fs,
         enable_rr: compile_options.enable_rr,
         enable_kv: compile_options.enable_kv,
         extra_cc_args: Vec::new(),
+        extra_defines: Vec::new(),
+        warnings_as_errors: false,
+        sanitizers: Vec::new(),
+        options_fingerprint: None,
+        cache_salt: Vec::new(),
+        cache_dir_override: None,
+        bypass_cache: false,
     };
 
     let dir = make_temp_dir("bundle");
@@ -102,6 +106,7 @@ fn bundle_wrapper_returns_set_exit_code() {
         &toolchain,
         &exe_path,
         &wrapper,
+        None,
     )
     .expect("compile bundle ok");
     assert!(
@@ -116,3 +121,313 @@ fn bundle_wrapper_returns_set_exit_code() {
 
     let _ = std::fs::remove_dir_all(&dir);
 }
+
+#[test]
+fn bundle_manifest_exe_digest_matches_exe() {
+    use sha2::{Digest, Sha256};
+
+    let cfg = config();
+    let program = x07_program::entry(&[], json!(["bytes.lit", "ok"]));
+
+    let compile_options =
+        compile_options_for_world(cfg.world, Vec::new()).expect("compile options");
+    let toolchain = NativeToolchainConfig {
+        world_tag: compile_options.world.as_str().to_string(),
+        fuel_init: cfg.solve_fuel,
+        mem_cap_bytes: cfg.max_memory_bytes,
+        arena_cap_bytes: None,
+        debug_borrow_checks: cfg.debug_borrow_checks,
+        enable_fs: compile_options.enable_fs,
+        enable_rr: compile_options.enable_rr,
+        enable_kv: compile_options.enable_kv,
+        extra_cc_args: Vec::new(),
+        extra_defines: vec![("BUNDLE_MANIFEST_TEST".to_string(), "1".to_string())],
+        warnings_as_errors: false,
+        sanitizers: Vec::new(),
+        options_fingerprint: None,
+        cache_salt: Vec::new(),
+        cache_dir_override: None,
+        bypass_cache: false,
+    };
+
+    let dir = make_temp_dir("bundle-manifest");
+    let exe_path = dir.join(if cfg!(windows) { "app.exe" } else { "app" });
+    let wrapper = NativeCliWrapperOpts {
+        argv0: "app".to_string(),
+        env: Vec::new(),
+        max_output_bytes: Some(1024 * 1024),
+        cpu_time_limit_seconds: Some(20),
+    };
+
+    let out = compile_bundle_exe(
+        program.as_slice(),
+        &compile_options,
+        &toolchain,
+        &exe_path,
+        &wrapper,
+        None,
+    )
+    .expect("compile bundle ok");
+    assert!(
+        out.compile.ok,
+        "compile_error={:?}",
+        out.compile.compile_error
+    );
+
+    let manifest_path = dir.join("app.manifest.json");
+    write_bundle_manifest(&out, &manifest_path).expect("write manifest");
+    let first = std::fs::read(&manifest_path).expect("read manifest");
+    let manifest: BundleManifest = serde_json::from_slice(&first).expect("parse manifest");
+
+    let exe_bytes = std::fs::read(&exe_path).expect("read exe");
+    let exe_sha256: String = Sha256::digest(&exe_bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    assert_eq!(manifest.exe_sha256, exe_sha256);
+    assert_eq!(manifest.exe_size, exe_bytes.len() as u64);
+    assert_eq!(
+        Some(&manifest.native_cache_key),
+        out.compile.native_cache_key.as_ref()
+    );
+    assert!(
+        manifest
+            .defines
+            .iter()
+            .any(|d| d == "-DBUNDLE_MANIFEST_TEST=1"),
+        "defines={:?}",
+        manifest.defines
+    );
+
+    write_bundle_manifest(&out, &manifest_path).expect("rewrite manifest");
+    let second = std::fs::read(&manifest_path).expect("reread manifest");
+    assert_eq!(first, second, "manifest must be deterministic");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn archive_compile_writes_consistent_archive() {
+    use sha2::{Digest, Sha256};
+
+    let sha256_hex = |bytes: &[u8]| -> String {
+        Sha256::digest(bytes)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    };
+
+    let cfg = config();
+    let program = x07_program::entry(&[], json!(["bytes.lit", "archived"]));
+    let compile_options =
+        compile_options_for_world(cfg.world, Vec::new()).expect("compile options");
+
+    let dir = make_temp_dir("archive");
+    let out_dir = dir.join("archive");
+    let compile =
+        archive_compile(program.as_slice(), &cfg, &compile_options, &out_dir).expect("archive");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    assert_eq!(compile.compiled_exe, Some(out_dir.join(ARCHIVE_EXE_FILE)));
+
+    let c_bytes = std::fs::read(out_dir.join(ARCHIVE_C_FILE)).expect("read solver.c");
+    let exe_bytes = std::fs::read(out_dir.join(ARCHIVE_EXE_FILE)).expect("read solver");
+    let report_bytes = std::fs::read(out_dir.join(ARCHIVE_REPORT_FILE)).expect("read report");
+    let report: serde_json::Value = serde_json::from_slice(&report_bytes).expect("parse report");
+    let manifest: BundleManifest = serde_json::from_slice(
+        &std::fs::read(out_dir.join(ARCHIVE_MANIFEST_FILE)).expect("read manifest"),
+    )
+    .expect("parse manifest");
+
+    let c_sha256 = sha256_hex(&c_bytes);
+    let exe_sha256 = sha256_hex(&exe_bytes);
+    assert_eq!(report["ok"], json!(true));
+    assert_eq!(report["c_sha256"], json!(c_sha256));
+    assert_eq!(report["c_source_size"], json!(c_bytes.len()));
+    assert_eq!(report["exe_sha256"], json!(exe_sha256));
+    assert_eq!(report["exe_size"], json!(exe_bytes.len()));
+    assert_eq!(
+        report["native_cache_key"],
+        json!(manifest.native_cache_key.clone())
+    );
+    assert_eq!(manifest.combined_c_sha256, c_sha256);
+    assert_eq!(manifest.exe_sha256, exe_sha256);
+    assert_eq!(manifest.exe_size, exe_bytes.len() as u64);
+
+    // A second archive of the same program, now a native cache hit, is
+    // byte-identical.
+    let again = dir.join("again");
+    archive_compile(program.as_slice(), &cfg, &compile_options, &again).expect("archive again");
+    for name in [ARCHIVE_REPORT_FILE, ARCHIVE_MANIFEST_FILE, ARCHIVE_C_FILE] {
+        assert_eq!(
+            std::fs::read(out_dir.join(name)).unwrap(),
+            std::fs::read(again.join(name)).unwrap(),
+            "{name}"
+        );
+    }
+
+    let out = run_artifact_file(&cfg, &out_dir.join(ARCHIVE_EXE_FILE), b"").expect("run");
+    assert!(out.ok, "trap={:?}", out.trap);
+    assert_eq!(out.solve_output, b"archived");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn bundle_wrapper_reserves_arena_above_mem_cap() {
+    let cfg = config();
+    let program = x07_program::entry(
+        &[],
+        json!([
+            "begin",
+            ["process.set_exit_code_v1", 7],
+            ["bytes.lit", "ok"]
+        ]),
+    );
+
+    let compile_options =
+        compile_options_for_world(cfg.world, Vec::new()).expect("compile options");
+    let toolchain = NativeToolchainConfig {
+        world_tag: compile_options.world.as_str().to_string(),
+        fuel_init: cfg.solve_fuel,
+        mem_cap_bytes: cfg.max_memory_bytes,
+        arena_cap_bytes: Some(cfg.max_memory_bytes * 2),
+        debug_borrow_checks: cfg.debug_borrow_checks,
+        enable_fs: compile_options.enable_fs,
+        enable_rr: compile_options.enable_rr,
+        enable_kv: compile_options.enable_kv,
+        extra_cc_args: Vec::new(),
+        extra_defines: Vec::new(),
+        warnings_as_errors: false,
+        sanitizers: Vec::new(),
+        options_fingerprint: None,
+        cache_salt: Vec::new(),
+        cache_dir_override: None,
+        bypass_cache: false,
+    };
+
+    let dir = make_temp_dir("bundle-arena");
+    let exe_path = dir.join(if cfg!(windows) { "app.exe" } else { "app" });
+    let wrapper = NativeCliWrapperOpts {
+        argv0: "app".to_string(),
+        env: Vec::new(),
+        max_output_bytes: Some(1024 * 1024),
+        cpu_time_limit_seconds: Some(20),
+    };
+
+    let out = compile_bundle_exe(
+        program.as_slice(),
+        &compile_options,
+        &toolchain,
+        &exe_path,
+        &wrapper,
+        None,
+    )
+    .expect("compile bundle ok");
+    assert!(
+        out.compile.ok,
+        "compile_error={:?}",
+        out.compile.compile_error
+    );
+    assert!(out
+        .wrapper_c
+        .contains("x07_solve_v2(arena, mem_cap, in, in_len)"));
+
+    let run = Command::new(&exe_path).output().expect("run bundle exe");
+    assert_eq!(run.status.code(), Some(7));
+    assert_eq!(run.stdout, b"ok");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn bundle_c_out_writes_standalone_c() {
+    let cfg = config();
+    let program = x07_program::entry(
+        &[],
+        json!([
+            "begin",
+            ["process.set_exit_code_v1", 7],
+            ["bytes.lit", "ok"]
+        ]),
+    );
+
+    let compile_options =
+        compile_options_for_world(cfg.world, Vec::new()).expect("compile options");
+    let toolchain = NativeToolchainConfig {
+        world_tag: compile_options.world.as_str().to_string(),
+        fuel_init: cfg.solve_fuel,
+        mem_cap_bytes: cfg.max_memory_bytes,
+        arena_cap_bytes: None,
+        debug_borrow_checks: cfg.debug_borrow_checks,
+        enable_fs: compile_options.enable_fs,
+        enable_rr: compile_options.enable_rr,
+        enable_kv: compile_options.enable_kv,
+        extra_cc_args: Vec::new(),
+        extra_defines: Vec::new(),
+        warnings_as_errors: false,
+        sanitizers: Vec::new(),
+        options_fingerprint: None,
+        cache_salt: Vec::new(),
+        cache_dir_override: None,
+        bypass_cache: false,
+    };
+
+    let dir = make_temp_dir("bundle-c-out");
+    let exe_path = dir.join(if cfg!(windows) { "app.exe" } else { "app" });
+    let c_path = dir.join("dist").join("app.c");
+    let wrapper = NativeCliWrapperOpts {
+        argv0: "app".to_string(),
+        env: Vec::new(),
+        max_output_bytes: Some(1024 * 1024),
+        cpu_time_limit_seconds: Some(20),
+    };
+
+    let out = compile_bundle_exe(
+        program.as_slice(),
+        &compile_options,
+        &toolchain,
+        &exe_path,
+        &wrapper,
+        Some(&c_path),
+    )
+    .expect("compile bundle ok");
+    assert!(
+        out.compile.ok,
+        "compile_error={:?}",
+        out.compile.compile_error
+    );
+    let written = std::fs::read_to_string(&c_path).expect("read bundle C");
+    assert_eq!(written, out.combined_c);
+
+    // The persisted C builds on its own with the emitted defaults.
+    let standalone_exe = dir.join(if cfg!(windows) {
+        "standalone.exe"
+    } else {
+        "standalone"
+    });
+    let cc = std::env::var_os("X07_CC").unwrap_or_else(|| "cc".into());
+    let mut cmd = Command::new(cc);
+    cmd.arg("-std=c11");
+    if cfg!(target_os = "linux") {
+        cmd.arg("-D_GNU_SOURCE").arg("-D_DEFAULT_SOURCE");
+    }
+    let cc_out = cmd
+        .arg(&c_path)
+        .arg("-o")
+        .arg(&standalone_exe)
+        .output()
+        .expect("invoke cc");
+    assert!(
+        cc_out.status.success(),
+        "cc stderr:\n{}",
+        String::from_utf8_lossy(&cc_out.stderr)
+    );
+
+    let run = Command::new(&standalone_exe)
+        .output()
+        .expect("run standalone exe");
+    assert_eq!(run.status.code(), Some(7));
+    assert_eq!(run.stdout, b"ok");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
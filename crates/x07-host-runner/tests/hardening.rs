@@ -2,13 +2,17 @@ use std::ffi::{OsStr, OsString};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 
 use serde_json::json;
-use x07_host_runner::{compile_program, run_artifact_file, RunnerConfig};
+use x07_host_runner::{compile_program, run_artifact_file, RunnerConfig, RUN_DIR_QUOTA_TRAP};
 use x07_worlds::WorldId;
 
 mod x07_program;
 
+// Tests below that set host env vars hold this while they do.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
 fn create_temp_dir(prefix: &str) -> PathBuf {
     static COUNTER: AtomicU64 = AtomicU64::new(0);
     let base = std::env::temp_dir();
@@ -65,17 +69,11 @@ fn compile_c_artifact(source: &str) -> (PathBuf, PathBuf) {
 fn base_config() -> RunnerConfig {
     RunnerConfig {
         world: WorldId::SolvePure,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     }
 }
 
@@ -117,6 +115,35 @@ fn success_without_metrics_is_failure() {
     rm_rf(&dir);
 }
 
+#[test]
+fn failed_run_keeps_raw_stdout_without_retention() {
+    let (dir, exe) = compile_c_artifact(
+        r#"
+          #include <stdio.h>
+
+          int main(void) {
+            const unsigned char buf[6] = {2, 0, 0, 0, 'o', 'k'};
+            fwrite(buf, 1, sizeof(buf), stdout);
+            fflush(stdout);
+            return 0;
+          }
+        "#,
+    );
+
+    let mut cfg = base_config();
+    cfg.retain_raw_stdout = false;
+
+    let res = run_artifact_file(&cfg, &exe, b"ignored").expect("runner ok");
+    assert!(!res.ok);
+    assert_eq!(res.solve_output, b"ok");
+    assert_eq!(
+        res.stdout, b"\x02\x00\x00\x00ok",
+        "failures keep raw stdout"
+    );
+
+    rm_rf(&dir);
+}
+
 #[test]
 fn fake_metrics_json_is_rejected() {
     let (dir, exe) = compile_c_artifact(
@@ -157,6 +184,70 @@ fn fake_metrics_json_is_rejected() {
     rm_rf(&dir);
 }
 
+#[cfg(unix)]
+#[test]
+fn metrics_fd_takes_precedence_over_stderr() {
+    let (dir, exe) = compile_c_artifact(
+        r#"
+          #define _POSIX_C_SOURCE 200809L
+          #include <stdint.h>
+          #include <stdio.h>
+          #include <stdlib.h>
+          #include <string.h>
+          #include <unistd.h>
+
+          int main(void) {
+            uint8_t buf[7] = {3, 0, 0, 0, 'a', 'b', 'c'};
+            const char* fd_env = getenv("X07_METRICS_FD");
+            const char* line = "{\"fuel_used\":7}\n";
+            fwrite(buf, 1, sizeof(buf), stdout);
+            fflush(stdout);
+            fputs("{\"fuel_used\":1}\n{\"fuel_used\":\n", stderr);
+            fflush(stderr);
+            if (!fd_env) return 2;
+            if (write(atoi(fd_env), line, strlen(line)) != (ssize_t)strlen(line)) return 3;
+            return 0;
+          }
+        "#,
+    );
+
+    let cfg = base_config();
+
+    let res = run_artifact_file(&cfg, &exe, b"ignored").expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert_eq!(res.solve_output, b"abc");
+    assert_eq!(res.fuel_used, Some(7));
+
+    rm_rf(&dir);
+}
+
+#[test]
+fn metrics_fall_back_to_stderr_when_fd_is_unused() {
+    let (dir, exe) = compile_c_artifact(
+        r#"
+          #include <stdint.h>
+          #include <stdio.h>
+
+          int main(void) {
+            uint8_t buf[7] = {3, 0, 0, 0, 'a', 'b', 'c'};
+            fwrite(buf, 1, sizeof(buf), stdout);
+            fputs("{\"fuel_used\":9}\n", stderr);
+            fflush(stdout);
+            fflush(stderr);
+            return 0;
+          }
+        "#,
+    );
+
+    let cfg = base_config();
+
+    let res = run_artifact_file(&cfg, &exe, b"ignored").expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert_eq!(res.fuel_used, Some(9));
+
+    rm_rf(&dir);
+}
+
 #[test]
 fn wall_timeout_kills_blocked_process() {
     let (dir, exe) = compile_c_artifact(
@@ -182,6 +273,94 @@ fn wall_timeout_kills_blocked_process() {
     rm_rf(&dir);
 }
 
+const MANY_FILES_WRITER: &str = r#"
+  #include <fcntl.h>
+  #include <stdint.h>
+  #include <stdio.h>
+  #include <unistd.h>
+
+  int main(void) {
+    char name[32];
+    for (int i = 0; i < N_FILES; i++) {
+      snprintf(name, sizeof(name), "f%05d", i);
+      int fd = open(name, O_CREAT | O_WRONLY, 0644);
+      if (fd >= 0) close(fd);
+    }
+    while (SLEEP_FOREVER) {
+      sleep(1);
+    }
+    uint8_t buf[4] = {0, 0, 0, 0};
+    fwrite(buf, 1, sizeof(buf), stdout);
+    fflush(stdout);
+    fputs("{\"fuel_used\":1}\n", stderr);
+    fflush(stderr);
+    return 0;
+  }
+"#;
+
+fn many_files_writer(n_files: u32, sleep_forever: bool) -> String {
+    format!(
+        "#define N_FILES {n_files}\n#define SLEEP_FOREVER {}\n{MANY_FILES_WRITER}",
+        u8::from(sleep_forever)
+    )
+}
+
+#[test]
+fn run_dir_quota_traps_on_many_small_files() {
+    let (dir, exe) = compile_c_artifact(&many_files_writer(4000, false));
+
+    let mut cfg = base_config();
+    cfg.max_run_dir_bytes = Some(16 * 1024);
+
+    let res = run_artifact_file(&cfg, &exe, b"ignored").expect("runner ok");
+    assert!(!res.ok);
+    assert_eq!(res.trap.as_deref(), Some(RUN_DIR_QUOTA_TRAP));
+    assert!(
+        res.run_dir_bytes.is_some_and(|n| n > 16 * 1024),
+        "run_dir_bytes={:?}",
+        res.run_dir_bytes
+    );
+
+    rm_rf(&dir);
+}
+
+#[test]
+fn run_dir_quota_kills_long_running_writer() {
+    let (dir, exe) = compile_c_artifact(&many_files_writer(4000, true));
+
+    let mut cfg = base_config();
+    cfg.max_run_dir_bytes = Some(16 * 1024);
+
+    let start = std::time::Instant::now();
+    let res = run_artifact_file(&cfg, &exe, b"ignored").expect("runner ok");
+    assert!(!res.ok);
+    assert_eq!(res.trap.as_deref(), Some(RUN_DIR_QUOTA_TRAP));
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(cfg.cpu_time_limit_seconds),
+        "quota should stop the child before the wall timeout"
+    );
+
+    rm_rf(&dir);
+}
+
+#[test]
+fn run_dir_quota_reports_usage_within_limit() {
+    let (dir, exe) = compile_c_artifact(&many_files_writer(1, false));
+
+    let mut cfg = base_config();
+    cfg.max_run_dir_bytes = Some(1024 * 1024);
+
+    let res = run_artifact_file(&cfg, &exe, b"ignored").expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert!(res.run_dir_bytes.is_some_and(|n| n <= 1024 * 1024));
+
+    let res = run_artifact_file(&base_config(), &exe, b"ignored").expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert_eq!(res.run_dir_bytes, None);
+
+    rm_rf(&dir);
+}
+
 #[test]
 fn stdout_cap_does_not_hang() {
     let (dir, exe) = compile_c_artifact(
@@ -246,16 +425,11 @@ fn fs_read_rejects_reserved_x07_dirs() {
     let cfg = RunnerConfig {
         world: WorldId::SolveFs,
         fixture_fs_dir: Some(fixture.clone()),
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry(
@@ -317,3 +491,43 @@ fn terminated_by_signal_is_reported_as_trap() {
 
     rm_rf(&dir);
 }
+
+#[test]
+fn child_env_is_pinned_regardless_of_host_tz() {
+    let (dir, exe) = compile_c_artifact(
+        r#"
+          #include <stdio.h>
+          #include <stdlib.h>
+
+          int main(void) {
+            const char* tz = getenv("TZ");
+            const char* lc = getenv("LC_ALL");
+            fprintf(stderr, "TZ=%s LC_ALL=%s\n", tz ? tz : "-", lc ? lc : "-");
+            fflush(stderr);
+            return 1;
+          }
+        "#,
+    );
+
+    let cfg = base_config();
+    let (first, second) = {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let host_tz = std::env::var_os("TZ");
+        std::env::set_var("TZ", "America/New_York");
+        let first = run_artifact_file(&cfg, &exe, b"ignored");
+        std::env::set_var("TZ", "Asia/Tokyo");
+        let second = run_artifact_file(&cfg, &exe, b"ignored");
+        match host_tz {
+            Some(tz) => std::env::set_var("TZ", tz),
+            None => std::env::remove_var("TZ"),
+        }
+        (first.expect("runner ok"), second.expect("runner ok"))
+    };
+
+    assert_eq!(first.trap.as_deref(), Some("TZ=UTC LC_ALL=C"));
+    assert_eq!(second.trap.as_deref(), Some("TZ=UTC LC_ALL=C"));
+    assert_eq!(first.env_fingerprint.len(), 64);
+    assert_eq!(first.env_fingerprint, second.env_fingerprint);
+
+    rm_rf(&dir);
+}
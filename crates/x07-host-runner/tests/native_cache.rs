@@ -0,0 +1,391 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use x07_host_runner::{
+    compile_c_to_exe_with_config, extra_define_flags, native_cache_key, parse_cc_warnings,
+    read_native_cache_meta, NativeToolchainConfig, NATIVE_CACHE_META_FILE,
+};
+
+// Tests below point `X07_NATIVE_CACHE_DIR` at their own dir; serialize them.
+static CACHE_DIR_ENV: Mutex<()> = Mutex::new(());
+
+fn create_temp_dir(prefix: &str) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let base = std::env::temp_dir();
+    let pid = std::process::id();
+    for _ in 0..10_000 {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = base.join(format!("{prefix}_{pid}_{n}"));
+        if std::fs::create_dir(&path).is_ok() {
+            return path;
+        }
+    }
+    panic!("failed to create temp dir under {}", base.display());
+}
+
+fn toolchain() -> NativeToolchainConfig {
+    NativeToolchainConfig {
+        world_tag: "solve-pure".to_string(),
+        fuel_init: 1,
+        mem_cap_bytes: 1024 * 1024,
+        arena_cap_bytes: None,
+        debug_borrow_checks: false,
+        enable_fs: false,
+        enable_rr: false,
+        enable_kv: false,
+        extra_cc_args: Vec::new(),
+        extra_defines: Vec::new(),
+        warnings_as_errors: false,
+        sanitizers: Vec::new(),
+        options_fingerprint: None,
+        cache_salt: Vec::new(),
+        cache_dir_override: None,
+        bypass_cache: false,
+    }
+}
+
+fn entry_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .expect("read cache entry")
+        .map(|e| e.expect("entry").file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn concurrent_identical_compiles_leave_one_cache_entry() {
+    let _env = CACHE_DIR_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = create_temp_dir("x07_native_cache_race");
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &cache);
+    std::env::set_var("X07_KEEP_C", "1");
+
+    let src = "int main(void) { return 0; }\n/* native cache race */\n";
+    let handles: Vec<_> = (0..16)
+        .map(|_| {
+            std::thread::spawn(move || {
+                compile_c_to_exe_with_config(src, &toolchain()).expect("compile c")
+            })
+        })
+        .collect();
+    for h in handles {
+        let out = h.join().expect("thread");
+        assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+    }
+
+    let entries: Vec<PathBuf> = std::fs::read_dir(&cache)
+        .expect("read cache")
+        .map(|e| e.expect("entry").path())
+        .collect();
+    assert_eq!(entries.len(), 1, "entries={entries:?}");
+    assert_eq!(
+        entry_names(&entries[0]),
+        vec![
+            NATIVE_CACHE_META_FILE.to_string(),
+            "solver".to_string(),
+            "solver.c".to_string()
+        ]
+    );
+
+    let meta = read_native_cache_meta(&entries[0]).expect("meta");
+    assert_eq!(meta.world_tag, "solve-pure");
+    assert_eq!(meta.c_source_sha256.len(), 64);
+    assert!(meta.cc_args.iter().any(|a| a == "-std=c11"));
+
+    let _ = std::fs::remove_dir_all(&cache);
+}
+
+#[test]
+fn options_fingerprint_splits_cache_entries() {
+    let _env = CACHE_DIR_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = create_temp_dir("x07_native_cache_options_fp");
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &cache);
+
+    let src = "int main(void) { return 0; }\n/* options fingerprint */\n";
+    let compile_with = |fp: &str| {
+        let mut cfg = toolchain();
+        cfg.options_fingerprint = Some(fp.to_string());
+        let out = compile_c_to_exe_with_config(src, &cfg).expect("compile c");
+        assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+        out.exe_path.expect("exe").parent().unwrap().to_path_buf()
+    };
+
+    let a = compile_with(&"a".repeat(64));
+    let b = compile_with(&"b".repeat(64));
+    assert_ne!(
+        a, b,
+        "same C source, different options must not share an entry"
+    );
+    assert_eq!(compile_with(&"a".repeat(64)), a);
+
+    let meta = read_native_cache_meta(&b).expect("meta");
+    assert_eq!(meta.options_fingerprint, Some("b".repeat(64)));
+
+    let _ = std::fs::remove_dir_all(&cache);
+}
+
+#[test]
+fn native_cache_key_matches_compile_entry_and_is_stable() {
+    let _env = CACHE_DIR_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = create_temp_dir("x07_native_cache_key");
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &cache);
+
+    let src = "int main(void) { return 0; }\n/* cache key */\n";
+    let key = native_cache_key(src, &toolchain()).expect("cache key");
+    let out = compile_c_to_exe_with_config(src, &toolchain()).expect("compile c");
+    assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+    let entry = out.exe_path.expect("exe").parent().unwrap().to_path_buf();
+    assert_eq!(entry.file_name().unwrap().to_string_lossy(), key);
+
+    // A cc that cannot run contributes an empty `--version`, pinning the key.
+    std::env::set_var("X07_CC", cache.join("no-such-cc"));
+    std::env::remove_var("X07_CC_ARGS");
+    let pinned = native_cache_key(src, &toolchain()).expect("cache key");
+    std::env::remove_var("X07_CC");
+    assert_eq!(
+        pinned,
+        "2d8a5e98784a10f800d5f85b189301f5081d8a66c8f318d7d2eff13a82b6729c"
+    );
+
+    let _ = std::fs::remove_dir_all(&cache);
+}
+
+#[test]
+fn arena_cap_below_mem_cap_is_rejected() {
+    let mut cfg = toolchain();
+    cfg.arena_cap_bytes = Some(cfg.mem_cap_bytes - 1);
+    let err = compile_c_to_exe_with_config("int main(void) { return 0; }\n", &cfg)
+        .expect_err("arena smaller than mem cap");
+    assert!(
+        format!("{err:#}").contains("must be >= mem_cap_bytes"),
+        "{err:#}"
+    );
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn caps_above_u32_are_rejected() {
+    let mut cfg = toolchain();
+    cfg.arena_cap_bytes = Some(u32::MAX as usize + 1);
+    let err = compile_c_to_exe_with_config("int main(void) { return 0; }\n", &cfg)
+        .expect_err("arena above u32");
+    assert!(
+        format!("{err:#}").contains("arena_cap_bytes (4294967296) must be <="),
+        "{err:#}"
+    );
+    assert!(native_cache_key("", &cfg).is_err());
+
+    let mut cfg = toolchain();
+    cfg.mem_cap_bytes = u32::MAX as usize + 1;
+    cfg.arena_cap_bytes = Some(u32::MAX as usize + 1);
+    let err = compile_c_to_exe_with_config("int main(void) { return 0; }\n", &cfg)
+        .expect_err("mem cap above u32");
+    assert!(
+        format!("{err:#}").contains("mem_cap_bytes (4294967296) must be <="),
+        "{err:#}"
+    );
+}
+
+fn defines(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn extra_defines_are_validated() {
+    assert_eq!(
+        extra_define_flags(&defines(&[("EXTRA_ASSERTS", "1"), ("_MODE", "-0x1f.5")])).unwrap(),
+        vec![
+            "-DEXTRA_ASSERTS=1".to_string(),
+            "-D_MODE=-0x1f.5".to_string()
+        ]
+    );
+    assert_eq!(
+        extra_define_flags(&defines(&[("EMPTY", "")])).unwrap(),
+        vec!["-DEMPTY=".to_string()]
+    );
+
+    for key in ["", "lower", "9LIVES", "A-B", "A B", "É"] {
+        let err = extra_define_flags(&defines(&[(key, "1")])).expect_err(key);
+        assert!(
+            format!("{err:#}").contains("invalid define name"),
+            "{err:#}"
+        );
+    }
+    for key in ["X07_MEM_CAP", "X07_ENABLE_FS", "X07_ANYTHING"] {
+        let err = extra_define_flags(&defines(&[(key, "1")])).expect_err(key);
+        assert!(format!("{err:#}").contains("reserved"), "{err:#}");
+    }
+    for value in ["a b", "1;rm", "$(x)", "`x`", "a|b", "\"q\"", "x\ny", "a\\b"] {
+        let err = extra_define_flags(&defines(&[("FLAG", value)])).expect_err(value);
+        assert!(format!("{err:#}").contains("is not allowed"), "{err:#}");
+    }
+
+    // compile rejects them before invoking cc.
+    let mut cfg = toolchain();
+    cfg.extra_defines = defines(&[("X07_FUEL_INIT", "1")]);
+    compile_c_to_exe_with_config("int main(void) { return 0; }\n", &cfg)
+        .expect_err("reserved define");
+}
+
+#[test]
+fn extra_defines_reach_cc_and_split_cache_entries() {
+    let _env = CACHE_DIR_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = create_temp_dir("x07_native_cache_defines");
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &cache);
+
+    let src =
+        "#if EXTRA_ASSERTS != 3\n#error EXTRA_ASSERTS\n#endif\nint main(void) { return 0; }\n";
+    let mut cfg = toolchain();
+    cfg.extra_defines = defines(&[("EXTRA_ASSERTS", "3")]);
+    let key = native_cache_key(src, &cfg).expect("cache key");
+    assert_ne!(key, native_cache_key(src, &toolchain()).expect("cache key"));
+
+    let out = compile_c_to_exe_with_config(src, &cfg).expect("compile c");
+    assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+    let entry = out.exe_path.expect("exe").parent().unwrap().to_path_buf();
+    assert_eq!(entry.file_name().unwrap().to_string_lossy(), key);
+
+    cfg.extra_defines = defines(&[("EXTRA_ASSERTS", "4")]);
+    let out = compile_c_to_exe_with_config(src, &cfg).expect("compile c");
+    assert!(
+        !out.ok,
+        "a different value must miss the cache and recompile"
+    );
+
+    let _ = std::fs::remove_dir_all(&cache);
+}
+
+#[test]
+fn cc_warnings_are_captured_and_werror_fails_the_build() {
+    let _env = CACHE_DIR_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = create_temp_dir("x07_native_cache_warnings");
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &cache);
+
+    let src = "#warning \"x07 test\"\nint main(void) { return 0; }\n";
+    let out = compile_c_to_exe_with_config(src, &toolchain()).expect("compile c");
+    assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+    let warnings = parse_cc_warnings(&out.stderr);
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    // gcc reports `-Wcpp`, clang `-W#warnings`.
+    assert!(
+        ["-Wcpp", "-W#warnings"].contains(&warnings[0].flag.as_str()),
+        "{warnings:?}"
+    );
+    assert_eq!(warnings[0].count, 1);
+    assert!(warnings[0].sample_message.contains("x07 test"));
+
+    let mut strict = toolchain();
+    strict.warnings_as_errors = true;
+    let out = compile_c_to_exe_with_config(src, &strict).expect("compile c");
+    assert!(!out.ok, "-Werror must turn the warning into an error");
+
+    // Allowlisted categories stay warnings even when `-Wall` enables them.
+    let src = "int main(void) { int unused = 0; return 0; }\n";
+    strict.extra_cc_args = vec!["-Wall".to_string()];
+    let out = compile_c_to_exe_with_config(src, &strict).expect("compile c");
+    assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+    assert!(parse_cc_warnings(&out.stderr).is_empty());
+
+    let _ = std::fs::remove_dir_all(&cache);
+}
+
+#[test]
+fn cache_dir_override_is_per_call() {
+    let _env = CACHE_DIR_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let shared = create_temp_dir("x07_native_cache_shared");
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &shared);
+
+    let src = "int main(void) { return 0; }\n/* per-call cache dir */\n";
+    let key = native_cache_key(src, &toolchain()).expect("cache key");
+    let dirs = [
+        create_temp_dir("x07_native_cache_override_a"),
+        create_temp_dir("x07_native_cache_override_b"),
+    ];
+    let handles: Vec<_> = dirs
+        .iter()
+        .cloned()
+        .map(|dir| {
+            std::thread::spawn(move || {
+                let mut cfg = toolchain();
+                cfg.cache_dir_override = Some(dir.clone());
+                for i in 0..4 {
+                    let out = compile_c_to_exe_with_config(src, &cfg).expect("compile c");
+                    assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+                    assert_eq!(out.cache_hit, i > 0);
+                    assert_eq!(
+                        out.exe_path.expect("exe"),
+                        dir.join(&out.cache_key).join("solver")
+                    );
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().expect("thread");
+    }
+
+    for dir in &dirs {
+        assert_eq!(entry_names(dir), vec![key.clone()]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    assert!(entry_names(&shared).is_empty());
+    let _ = std::fs::remove_dir_all(&shared);
+}
+
+#[test]
+fn bypass_cache_leaves_the_shared_cache_untouched() {
+    let _env = CACHE_DIR_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let shared = create_temp_dir("x07_native_cache_bypass");
+    std::env::set_var("X07_NATIVE_CACHE_DIR", &shared);
+
+    let src = "int main(void) { return 7; }\n/* bypass cache */\n";
+    let mut cfg = toolchain();
+    cfg.bypass_cache = true;
+    let compile_bypassed = || {
+        let out = compile_c_to_exe_with_config(src, &cfg).expect("compile c");
+        assert!(out.ok, "stderr={}", String::from_utf8_lossy(&out.stderr));
+        assert!(!out.cache_hit);
+        assert_eq!(
+            out.cache_key,
+            native_cache_key(src, &toolchain()).expect("cache key")
+        );
+        let exe = out.exe_path.clone().expect("exe");
+        assert!(!exe.starts_with(&shared), "exe={}", exe.display());
+        let status = std::process::Command::new(&exe).status().expect("run exe");
+        assert_eq!(status.code(), Some(7));
+        out
+    };
+
+    let first = compile_bypassed();
+    assert!(entry_names(&shared).is_empty());
+
+    let cached = compile_c_to_exe_with_config(src, &toolchain()).expect("compile c");
+    assert!(cached.ok && !cached.cache_hit);
+    let before = entry_names(&shared.join(&cached.cache_key));
+
+    let second = compile_bypassed();
+    assert_ne!(first.exe_path, second.exe_path);
+    assert_eq!(entry_names(&shared), vec![cached.cache_key.clone()]);
+    assert_eq!(entry_names(&shared.join(&cached.cache_key)), before);
+
+    // The throwaway dir lives exactly as long as the output (and its clones).
+    for out in [first, second] {
+        let dir = out
+            .exe_path
+            .as_deref()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        let clone = out.clone();
+        drop(out);
+        assert!(dir.is_dir(), "dir={}", dir.display());
+        drop(clone);
+        assert!(!dir.exists(), "dir={}", dir.display());
+    }
+    let _ = std::fs::remove_dir_all(&shared);
+}
@@ -2,7 +2,7 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use x07_contracts::{NATIVE_BACKENDS_SCHEMA_VERSION, NATIVE_REQUIRES_SCHEMA_VERSION};
-use x07_host_runner::plan_native_link_argv;
+use x07_host_runner::{native_cache_key, plan_native_link_argv, NativeToolchainConfig};
 use x07c::native::{NativeBackendReq, NativeRequires};
 
 static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
@@ -104,7 +104,9 @@ fn native_link_argv_linux_exact() {
     let dir = temp_dir("x07_native_link_linux");
     write_fixture_toolchain_root(&dir);
 
-    let argv = plan_native_link_argv(&dir, &requires_doc()).expect("plan argv");
+    let argv = plan_native_link_argv(&dir, &requires_doc())
+        .expect("plan argv")
+        .argv;
 
     let expected = vec![
         "-pthread".to_string(),
@@ -132,7 +134,9 @@ fn native_link_argv_macos_exact() {
     let dir = temp_dir("x07_native_link_macos");
     write_fixture_toolchain_root(&dir);
 
-    let argv = plan_native_link_argv(&dir, &requires_doc()).expect("plan argv");
+    let argv = plan_native_link_argv(&dir, &requires_doc())
+        .expect("plan argv")
+        .argv;
 
     let expected = vec![
         dir.join("deps/x07/libx07_ext_net.a")
@@ -150,3 +154,98 @@ fn native_link_argv_macos_exact() {
 
     let _ = std::fs::remove_dir_all(&dir);
 }
+
+#[test]
+fn native_link_plan_records_backend_resolutions() {
+    let dir = temp_dir("x07_native_link_resolutions");
+    write_fixture_toolchain_root(&dir);
+
+    let plan = plan_native_link_argv(&dir, &requires_doc()).expect("plan argv");
+
+    let got: Vec<(&str, PathBuf)> = plan
+        .resolutions
+        .iter()
+        .map(|r| (r.backend_id.as_str(), r.path.clone()))
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            ("x07.ext.net", dir.join("deps/x07/libx07_ext_net.a")),
+            ("x07.ext.regex", dir.join("deps/x07/libx07_ext_regex.a")),
+            ("x07.ext.sqlite3", dir.join("deps/x07/libx07_ext_sqlite3.a")),
+        ]
+    );
+    for r in &plan.resolutions {
+        assert!(
+            r.mtime_unix_ms.is_some_and(|ms| ms > 0),
+            "missing mtime for {}",
+            r.backend_id
+        );
+        assert!(plan.argv.contains(&r.path.to_string_lossy().to_string()));
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+fn cache_key_for(root: &Path, requires: &NativeRequires) -> String {
+    let plan = plan_native_link_argv(root, requires).expect("plan argv");
+    let toolchain = NativeToolchainConfig {
+        world_tag: "solve-pure".to_string(),
+        fuel_init: 1,
+        mem_cap_bytes: 1024 * 1024,
+        arena_cap_bytes: None,
+        debug_borrow_checks: false,
+        enable_fs: false,
+        enable_rr: false,
+        enable_kv: false,
+        extra_cc_args: plan.argv,
+        extra_defines: Vec::new(),
+        warnings_as_errors: false,
+        sanitizers: Vec::new(),
+        options_fingerprint: None,
+        cache_salt: plan.cache_salt,
+        cache_dir_override: None,
+        bypass_cache: false,
+    };
+    native_cache_key("int main(void) { return 0; }\n", &toolchain).expect("cache key")
+}
+
+#[test]
+fn manifest_edits_to_required_backends_change_the_cache_key() {
+    let dir = temp_dir("x07_native_link_cache_salt");
+    write_fixture_toolchain_root(&dir);
+    let requires = NativeRequires {
+        requires: vec![NativeBackendReq {
+            backend_id: "x07.ext.regex".to_string(),
+            abi_major: 1,
+            features: Vec::new(),
+        }],
+        ..requires_doc()
+    };
+    let manifest_path = dir.join("deps/x07/native_backends.json");
+    let key = cache_key_for(&dir, &requires);
+
+    // x07.ext.net is not required: editing it must keep the cached exe.
+    let unrelated = MANIFEST_JSON.replacen(
+        r#""args": ["-pthread"]"#,
+        r#""args": ["-pthread", "-ldl"]"#,
+        1,
+    );
+    write_bytes(&manifest_path, unrelated.as_bytes());
+    assert_eq!(cache_key_for(&dir, &requires), key);
+
+    // Only the other platform's link spec changes, so the host link argv and
+    // the linked files stay the same; the key must still move.
+    let needle =
+        r#""macos": { "kind": "static", "files": ["deps/x07/libx07_ext_regex.a"], "args": []"#;
+    assert!(unrelated.contains(needle));
+    let related = unrelated.replacen(
+        needle,
+        &needle.replace(r#""args": []"#, r#""args": ["-DX07_REGEX_JIT=0"]"#),
+        1,
+    );
+    write_bytes(&manifest_path, related.as_bytes());
+    assert_ne!(cache_key_for(&dir, &requires), key);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use serde_json::json;
+use x07_host_runner::{
+    compile_program, run_artifact_file, OutputContract, OutputContractVerdict, RunnerConfig,
+    OUTPUT_CONTRACT_TRAP,
+};
+use x07_worlds::WorldId;
+
+mod x07_program;
+
+fn config(contract: Option<OutputContract>, strict: bool) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        output_contract: contract,
+        output_contract_strict: strict,
+        ..Default::default()
+    }
+}
+
+/// An exe whose solve_output is its input.
+fn echo_exe() -> PathBuf {
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let compile = compile_program(&program, &config(None, false), None).expect("compile");
+    assert!(compile.ok, "{:?}", compile.compile_error);
+    compile.compiled_exe.expect("compiled exe")
+}
+
+fn violation_of(contract: &OutputContract, output: &[u8]) -> Option<(String, Option<usize>)> {
+    contract
+        .check(output)
+        .expect("usable contract")
+        .map(|v| (v.kind, v.offset))
+}
+
+#[test]
+fn json_contract_checks_syntax_and_schema() {
+    let contract = OutputContract::Json {
+        schema: Some(json!({
+            "type": "object",
+            "required": ["a"],
+            "properties": { "a": { "type": "integer" } }
+        })),
+    };
+    assert_eq!(contract.check(br#"{"a": 1}"#).unwrap(), None);
+
+    let bad_type = contract
+        .check(br#"{"a": "x"}"#)
+        .unwrap()
+        .expect("violation");
+    assert_eq!(bad_type.kind, "json");
+    assert_eq!(bad_type.instance_path.as_deref(), Some("/a"));
+
+    let not_json = contract.check(b"{").unwrap().expect("violation");
+    assert!(not_json.message.contains("not valid JSON"), "{not_json:?}");
+    assert_eq!(not_json.instance_path, None);
+
+    // Without a schema any JSON document passes.
+    let any = OutputContract::Json { schema: None };
+    assert_eq!(any.check(b"[1, 2]").unwrap(), None);
+    assert!(any.check(b"[1, 2").unwrap().is_some());
+
+    assert!(OutputContract::Json {
+        schema: Some(json!({ "type": 5 }))
+    }
+    .check(b"{}")
+    .is_err());
+}
+
+#[test]
+fn utf8_and_framed_contracts_locate_the_bad_byte() {
+    assert_eq!(
+        violation_of(&OutputContract::Utf8, "héllo".as_bytes()),
+        None
+    );
+    assert_eq!(
+        violation_of(&OutputContract::Utf8, b"ab\xffcd"),
+        Some(("utf8".to_string(), Some(2)))
+    );
+
+    let framed = OutputContract::Framed { record_size: 4 };
+    assert_eq!(violation_of(&framed, b""), None);
+    assert_eq!(violation_of(&framed, b"aaaabbbb"), None);
+    assert_eq!(
+        violation_of(&framed, b"aaaabbbbcc"),
+        Some(("framed".to_string(), Some(8)))
+    );
+    assert!(OutputContract::Framed { record_size: 0 }
+        .check(b"")
+        .is_err());
+}
+
+#[test]
+fn runs_record_violations_and_fail_only_when_strict() {
+    let exe = echo_exe();
+    let framed = || Some(OutputContract::Framed { record_size: 4 });
+
+    let pass = run_artifact_file(&config(framed(), true), &exe, b"aaaabbbb").unwrap();
+    assert!(pass.ok);
+    assert_eq!(pass.output_contract_violation, None);
+    assert_eq!(
+        OutputContractVerdict::of(&pass),
+        OutputContractVerdict::Pass
+    );
+
+    let lenient = run_artifact_file(&config(framed(), false), &exe, b"aaaab").unwrap();
+    assert!(lenient.ok, "a non-strict violation keeps the run ok");
+    assert_eq!(lenient.trap, None);
+    let violation = lenient
+        .output_contract_violation
+        .clone()
+        .expect("violation");
+    assert_eq!(
+        (violation.kind.as_str(), violation.offset),
+        ("framed", Some(4))
+    );
+    assert_eq!(
+        OutputContractVerdict::of(&lenient),
+        OutputContractVerdict::Fail
+    );
+
+    let strict = run_artifact_file(&config(framed(), true), &exe, b"aaaab").unwrap();
+    assert!(!strict.ok);
+    assert_eq!(strict.trap.as_deref(), Some(OUTPUT_CONTRACT_TRAP));
+    assert_eq!(
+        strict.trap_detail.as_deref(),
+        Some(violation.message.as_str())
+    );
+    assert_eq!(strict.solve_output, b"aaaab");
+    assert_eq!(
+        OutputContractVerdict::of(&strict),
+        OutputContractVerdict::Fail
+    );
+
+    // A bad contract fails before the solver runs.
+    let err = run_artifact_file(
+        &config(Some(OutputContract::Framed { record_size: 0 }), false),
+        &exe,
+        b"",
+    )
+    .unwrap_err();
+    assert!(format!("{err:#}").contains("record_size"), "{err:#}");
+}
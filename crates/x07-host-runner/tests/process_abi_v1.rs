@@ -9,17 +9,11 @@ mod x07_program;
 fn config() -> RunnerConfig {
     RunnerConfig {
         world: WorldId::SolvePure,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 20,
-        debug_borrow_checks: false,
+        ..Default::default()
     }
 }
 
@@ -346,3 +340,83 @@ fn result_bytes_v1_decode_helpers_work_for_err_and_ok_docs() {
         assert_eq!(res.solve_output, b"ok", "decode failed for {name}");
     }
 }
+
+#[cfg(unix)]
+#[test]
+fn background_child_does_not_hold_metrics_fd_open() {
+    let cfg = config();
+    // The shell leaves a detached grandchild behind, out of reach of the
+    // runtime's kill-on-exit; the runner must still see EOF on the metrics
+    // pipe as soon as the solver exits.
+    let program = x07_program::entry(
+        &[
+            "std.os.process",
+            "std.os.process.caps_v1",
+            "std.os.process.req_v1",
+        ],
+        json!([
+            "begin",
+            [
+                "let",
+                "reqb",
+                ["std.os.process.req_v1.new", ["bytes.lit", "/bin/sh"]]
+            ],
+            [
+                "set",
+                "reqb",
+                ["std.os.process.req_v1.arg", "reqb", ["bytes.lit", "-c"]]
+            ],
+            [
+                "set",
+                "reqb",
+                [
+                    "std.os.process.req_v1.arg",
+                    "reqb",
+                    ["bytes.lit", "setsid sleep 10 </dev/null >/dev/null 2>&1 &"]
+                ]
+            ],
+            ["let", "req", ["std.os.process.req_v1.finish", "reqb"]],
+            [
+                "let",
+                "caps",
+                ["std.os.process.caps_v1.finish", 1024, 1024, 0, 0]
+            ],
+            [
+                "let",
+                "h",
+                ["std.os.process.spawn_capture_v1", "req", "caps"]
+            ],
+            ["let", "doc", ["os.process.join_capture_v1", "h"]],
+            ["std.os.process.drop_v1", "h"],
+            ["bytes.lit", "ok"]
+        ]),
+    );
+
+    let compile = compile_program_with_options(
+        program.as_slice(),
+        &cfg,
+        None,
+        &compile_options(WorldId::RunOs),
+        &[],
+    )
+    .expect("compile ok");
+    assert!(
+        compile.ok,
+        "compile_error={:?}\nstdout:\n{}\nstderr:\n{}",
+        compile.compile_error,
+        String::from_utf8_lossy(&compile.stdout),
+        String::from_utf8_lossy(&compile.stderr)
+    );
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let started = std::time::Instant::now();
+    let res = run_artifact_file(&cfg, &exe, b"").expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert_eq!(res.solve_output, b"ok");
+    assert!(res.fuel_used.is_some(), "metrics line missing");
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(5),
+        "runner waited on the background child: {:?}",
+        started.elapsed()
+    );
+}
@@ -0,0 +1,61 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::PathBuf;
+
+use x07_host_runner::{run_artifact_file, RunnerConfig};
+use x07_worlds::WorldId;
+
+fn config(allocate_pty: bool) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        allocate_pty,
+        ..Default::default()
+    }
+}
+
+fn make_temp_dir(prefix: &str) -> PathBuf {
+    let base = std::env::temp_dir();
+    let pid = std::process::id();
+    for n in 0..10_000u32 {
+        let p = base.join(format!("x07-pty-{prefix}-{pid}-{n}"));
+        if std::fs::create_dir(&p).is_ok() {
+            return p;
+        }
+    }
+    panic!("failed to create temp dir under {}", base.display());
+}
+
+#[test]
+fn isatty_program_takes_the_tty_branch_with_allocate_pty() {
+    let dir = make_temp_dir("isatty");
+    let artifact = dir.join("isatty.sh");
+    std::fs::write(
+        &artifact,
+        "#!/bin/sh\nif [ -t 1 ]; then echo stdout-tty; else echo stdout-pipe; fi\n\
+         if [ -t 2 ]; then echo stderr-tty >&2; else echo stderr-pipe >&2; fi\n",
+    )
+    .expect("write artifact");
+    std::fs::set_permissions(&artifact, std::fs::Permissions::from_mode(0o755))
+        .expect("chmod artifact");
+
+    let tty = run_artifact_file(&config(true), &artifact, b"").expect("run with pty");
+    let tty_out = String::from_utf8_lossy(&tty.stdout);
+    assert!(tty_out.contains("stdout-tty"), "stdout={tty_out:?}");
+    // stderr shares the pty and arrives merged into stdout.
+    assert!(tty_out.contains("stderr-tty"), "stdout={tty_out:?}");
+    assert!(tty.stderr.is_empty());
+    // The tty turns `\n` into `\r\n`; no framed solve output is decoded.
+    assert!(tty_out.contains("\r\n"), "stdout={tty_out:?}");
+    assert!(tty.solve_output.is_empty());
+
+    let piped = run_artifact_file(&config(false), &artifact, b"").expect("run with pipes");
+    assert_eq!(String::from_utf8_lossy(&piped.stdout), "stdout-pipe\n");
+    assert_eq!(String::from_utf8_lossy(&piped.stderr), "stderr-pipe\n");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,98 @@
+#![cfg(unix)]
+
+use serde_json::json;
+use x07_host_runner::{compile_program, run_artifact_file, RlimitMode, RunnerConfig};
+use x07_worlds::WorldId;
+
+mod x07_program;
+
+const CHILD_ENV: &str = "X07_TEST_RLIMITS_CHILD";
+
+fn config(rlimit_mode: RlimitMode) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        rlimit_mode,
+        ..Default::default()
+    }
+}
+
+// Runs in a re-spawned test process so lowering its own RLIMIT_NOFILE does
+// not leak into the other tests.
+#[test]
+#[ignore]
+fn rlimits_child() {
+    if std::env::var_os(CHILD_ENV).is_none() {
+        return;
+    }
+
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let compile = compile_program(&program, &config(RlimitMode::Enforce), None).expect("compile");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    // Below the runner's NOFILE=32, so the limit can no longer be applied.
+    let lim = libc::rlimit {
+        rlim_cur: 24,
+        rlim_max: 24,
+    };
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &lim) }, 0);
+
+    match run_artifact_file(&config(RlimitMode::Enforce), &exe, b"x") {
+        Ok(res) => println!("x07-rlimits:enforce-ran:{}", res.ok),
+        Err(err) => println!("x07-rlimits:enforce-err:{err:#}"),
+    }
+
+    let res = run_artifact_file(&config(RlimitMode::BestEffort), &exe, b"x").expect("runner ok");
+    println!("x07-rlimits:best-effort-ok:{}", res.ok);
+    for d in &res.sandbox_degradations {
+        println!("x07-rlimits:degraded:{d}");
+    }
+}
+
+#[test]
+fn unraisable_nofile_fails_enforce_and_degrades_best_effort() {
+    let out = std::process::Command::new(std::env::current_exe().expect("current_exe"))
+        .args([
+            "--exact",
+            "rlimits_child",
+            "--ignored",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env(CHILD_ENV, "1")
+        .output()
+        .expect("spawn rlimits child");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        out.status.success(),
+        "rlimits child failed: {:?}\n{stdout}\n{}",
+        out.status,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let markers: Vec<&str> = stdout
+        .lines()
+        // libtest may print `test ... ` on the same line first.
+        .filter_map(|l| l.split_once("x07-rlimits:"))
+        .map(|(_, marker)| marker)
+        .collect();
+
+    let enforce = markers
+        .iter()
+        .find(|m| m.starts_with("enforce-"))
+        .expect("enforce marker");
+    assert!(
+        enforce.starts_with("enforce-err:") && enforce.contains("RLIMIT_NOFILE=32"),
+        "{enforce}"
+    );
+    assert!(markers.contains(&"best-effort-ok:true"), "{markers:?}");
+    let degraded: Vec<&&str> = markers
+        .iter()
+        .filter(|m| m.starts_with("degraded:"))
+        .collect();
+    assert_eq!(degraded.len(), 1, "{degraded:?}");
+    assert!(degraded[0].contains("RLIMIT_NOFILE"), "{degraded:?}");
+}
@@ -0,0 +1,93 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::{Path, PathBuf};
+
+use x07_host_runner::{run_artifact_file, RunDirTokenInUse, RunnerConfig};
+use x07_worlds::WorldId;
+
+fn config(token: &str) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        stable_run_dir_token: Some(token.to_string()),
+        ..Default::default()
+    }
+}
+
+fn make_temp_dir(prefix: &str) -> PathBuf {
+    let base = std::env::temp_dir();
+    let pid = std::process::id();
+    for n in 0..10_000u32 {
+        let p = base.join(format!("x07-run-dir-token-{prefix}-{pid}-{n}"));
+        if std::fs::create_dir(&p).is_ok() {
+            return p;
+        }
+    }
+    panic!("failed to create temp dir under {}", base.display());
+}
+
+fn write_script(path: &Path, body: &str) {
+    std::fs::write(path, format!("#!/bin/sh\n{body}")).expect("write artifact");
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).expect("chmod artifact");
+}
+
+#[test]
+fn sequential_runs_with_one_token_see_the_same_cwd() {
+    let dir = make_temp_dir("seq");
+    let artifact = dir.join("pwd.sh");
+    write_script(&artifact, "pwd\necho \"$X07_RUN_DIR_TOKEN\"\n");
+    let token = format!("seq-{}", std::process::id());
+
+    let first = run_artifact_file(&config(&token), &artifact, b"").expect("first run");
+    let second = run_artifact_file(&config(&token), &artifact, b"").expect("second run");
+    assert_eq!(first.stdout, second.stdout);
+    let out = String::from_utf8_lossy(&first.stdout);
+    assert!(
+        out.contains(&format!("x07_run_tok_{token}\n{token}\n")),
+        "stdout={out:?}"
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn concurrent_reuse_of_a_token_errors() {
+    let dir = make_temp_dir("concurrent");
+    let started = dir.join("started");
+    let release = dir.join("release");
+    let artifact = dir.join("wait.sh");
+    write_script(
+        &artifact,
+        &format!(
+            "touch '{}'\nwhile [ ! -f '{}' ]; do sleep 0.05; done\n",
+            started.display(),
+            release.display()
+        ),
+    );
+    let token = format!("concurrent-{}", std::process::id());
+
+    let holder = {
+        let (token, artifact) = (token.clone(), artifact.clone());
+        std::thread::spawn(move || run_artifact_file(&config(&token), &artifact, b""))
+    };
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !started.exists() {
+        assert!(std::time::Instant::now() < deadline, "holder never started");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+
+    let err = run_artifact_file(&config(&token), &artifact, b"").expect_err("token is held");
+    let err = err.downcast::<RunDirTokenInUse>().expect("typed error");
+    assert_eq!(err.token, token);
+
+    std::fs::write(&release, b"").expect("release holder");
+    holder.join().unwrap().expect("holder run");
+    // Free again once the holder is done; `release` lets this run finish.
+    run_artifact_file(&config(&token), &artifact, b"").expect("run after release");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
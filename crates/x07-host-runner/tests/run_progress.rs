@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+
+use serde_json::json;
+use x07_host_runner::{
+    compile_and_run_with_progress, compile_options_for_world, RunPhase, RunnerConfig,
+};
+use x07_worlds::WorldId;
+
+mod x07_program;
+
+fn config() -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        ..Default::default()
+    }
+}
+
+fn phase_name(p: &RunPhase) -> &'static str {
+    match p {
+        RunPhase::CompileStarted => "compile_started",
+        RunPhase::CSourceReady { .. } => "c_source_ready",
+        RunPhase::NativeLinkPlanned { .. } => "native_link_planned",
+        RunPhase::CcStarted => "cc_started",
+        RunPhase::CcFinished { .. } => "cc_finished",
+        RunPhase::FixturesStaged => "fixtures_staged",
+        RunPhase::ChildSpawned { .. } => "child_spawned",
+        RunPhase::ChildExited { .. } => "child_exited",
+        RunPhase::Done => "done",
+    }
+}
+
+fn record(cfg: &RunnerConfig, program: &[u8]) -> Vec<RunPhase> {
+    let phases = RefCell::new(Vec::new());
+    let options = compile_options_for_world(cfg.world, Vec::new()).expect("compile options");
+    let _ = compile_and_run_with_progress(program, cfg, b"abc", None, &options, &|p| {
+        phases.borrow_mut().push(p)
+    })
+    .expect("runner ok");
+    phases.into_inner()
+}
+
+#[test]
+fn success_reports_full_phase_sequence() {
+    let cfg = config();
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let phases = record(&cfg, program.as_slice());
+    let names: Vec<&str> = phases.iter().map(phase_name).collect();
+    assert_eq!(
+        names,
+        vec![
+            "compile_started",
+            "c_source_ready",
+            "native_link_planned",
+            "cc_started",
+            "cc_finished",
+            "fixtures_staged",
+            "child_spawned",
+            "child_exited",
+            "done",
+        ]
+    );
+    assert!(matches!(phases[1], RunPhase::CSourceReady { bytes } if bytes > 0));
+    assert_eq!(phases[7], RunPhase::ChildExited { status: 0 });
+}
+
+#[test]
+fn compile_failure_stops_after_compile_started() {
+    let cfg = config();
+    let program = x07_program::entry(&[], json!(["no.such.fn", "input"]));
+    let names: Vec<&str> = record(&cfg, program.as_slice())
+        .iter()
+        .map(phase_name)
+        .collect();
+    assert_eq!(names, vec!["compile_started", "done"]);
+}
+
+#[test]
+fn runtime_trap_still_reports_child_exit() {
+    let mut cfg = config();
+    cfg.solve_fuel = 0;
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let phases = record(&cfg, program.as_slice());
+    let n = phases.len();
+    assert_eq!(phases[n - 1], RunPhase::Done);
+    assert!(
+        matches!(phases[n - 2], RunPhase::ChildExited { status } if status != 0),
+        "phases={phases:?}"
+    );
+}
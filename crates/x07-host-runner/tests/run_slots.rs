@@ -0,0 +1,130 @@
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use x07_host_runner::{
+    run_artifact_file, run_slot_stats, set_max_concurrent_runs, set_run_slot_acquire_timeout,
+    RunSlotError, RunnerConfig,
+};
+use x07_worlds::WorldId;
+
+fn config() -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        ..Default::default()
+    }
+}
+
+fn make_temp_dir(prefix: &str) -> PathBuf {
+    let base = std::env::temp_dir();
+    let pid = std::process::id();
+    for n in 0..10_000u32 {
+        let p = base.join(format!("x07-run-slots-{prefix}-{pid}-{n}"));
+        if std::fs::create_dir(&p).is_ok() {
+            return p;
+        }
+    }
+    panic!("failed to create temp dir under {}", base.display());
+}
+
+/// Stand-in solver: registers itself in `live/`, records how many solvers
+/// were live at that point as `seen/<pid>-<count>`, sleeps, then
+/// deregisters. Only dirs are created since `RLIMIT_FSIZE` is 0.
+fn sleepy_artifact(dir: &Path, sleep_s: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    for sub in ["live", "seen"] {
+        std::fs::create_dir(dir.join(sub)).expect("create marker dir");
+    }
+    let script = format!(
+        "#!/bin/sh\n\
+         PATH=/usr/bin:/bin\n\
+         cd {dir}\n\
+         mkdir live/$$\n\
+         mkdir seen/$$-$(ls live | wc -l | tr -d ' ')\n\
+         sleep {sleep_s}\n\
+         rmdir live/$$\n",
+        dir = dir.display(),
+    );
+    let path = dir.join("solver.sh");
+    std::fs::write(&path, script).expect("write artifact");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+    path
+}
+
+fn live_counts_seen(dir: &Path) -> Vec<usize> {
+    std::fs::read_dir(dir.join("seen"))
+        .expect("read seen dir")
+        .map(|e| {
+            let name = e.expect("seen entry").file_name();
+            let name = name.to_str().expect("utf-8 marker");
+            name.rsplit_once('-').unwrap().1.parse().unwrap()
+        })
+        .collect()
+}
+
+// The limiter is process-global, so everything touching it runs in one test.
+#[test]
+fn concurrent_runs_never_exceed_the_limit() {
+    const LIMIT: usize = 2;
+    const RUNS: usize = 8;
+    const SLEEP: Duration = Duration::from_millis(300);
+
+    let dir = make_temp_dir("limit");
+    let artifact = sleepy_artifact(&dir, "0.3");
+    set_max_concurrent_runs(LIMIT);
+
+    let cfg = config();
+    let start = Instant::now();
+    std::thread::scope(|s| {
+        for _ in 0..RUNS {
+            s.spawn(|| run_artifact_file(&cfg, &artifact, b"").expect("run artifact"));
+        }
+    });
+    let elapsed = start.elapsed();
+
+    let live_counts = live_counts_seen(&dir);
+    assert_eq!(live_counts.len(), RUNS);
+    assert!(
+        live_counts.iter().all(|&n| n <= LIMIT),
+        "live counts {live_counts:?}"
+    );
+
+    // RUNS / LIMIT waves of SLEEP each; well under running them one by one.
+    let waves = (RUNS / LIMIT) as u32;
+    assert!(elapsed >= SLEEP * waves, "elapsed {elapsed:?}");
+    assert!(elapsed < SLEEP * RUNS as u32, "elapsed {elapsed:?}");
+
+    let stats = run_slot_stats();
+    assert_eq!(stats.current_runs, 0);
+    assert_eq!(stats.high_water_runs, LIMIT);
+    assert_eq!(stats.acquired_total, RUNS as u64);
+
+    // With every slot busy, a bounded wait fails instead of queueing.
+    set_max_concurrent_runs(1);
+    set_run_slot_acquire_timeout(Some(Duration::from_millis(50)));
+    std::thread::scope(|s| {
+        let busy = s.spawn(|| run_artifact_file(&cfg, &artifact, b""));
+        while run_slot_stats().current_runs == 0 {
+            std::thread::yield_now();
+        }
+        let err = run_artifact_file(&cfg, &artifact, b"").expect_err("all slots busy");
+        match err.downcast_ref::<RunSlotError>() {
+            Some(RunSlotError::WouldBlock {
+                max_concurrent_runs,
+                ..
+            }) => assert_eq!(*max_concurrent_runs, 1),
+            None => panic!("expected RunSlotError, got {err:#}"),
+        }
+        busy.join().unwrap().expect("busy run");
+    });
+    assert_eq!(run_slot_stats().would_block_total, 1);
+    set_run_slot_acquire_timeout(None);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use x07_host_runner::{run_artifact_file, sandbox_capabilities, RunnerConfig, SandboxFeature};
+use x07_worlds::WorldId;
+
+fn config(require_sandbox: Vec<SandboxFeature>) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 1,
+        require_sandbox,
+        ..Default::default()
+    }
+}
+
+fn make_temp_dir(prefix: &str) -> PathBuf {
+    let base = std::env::temp_dir();
+    let pid = std::process::id();
+    for n in 0..10_000u32 {
+        let p = base.join(format!("x07-sandbox-{prefix}-{pid}-{n}"));
+        if std::fs::create_dir(&p).is_ok() {
+            return p;
+        }
+    }
+    panic!("failed to create temp dir under {}", base.display());
+}
+
+#[cfg(unix)]
+fn pid_alive(pid: i32) -> bool {
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+#[cfg(unix)]
+#[test]
+fn timeout_kills_the_solvers_children() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let dir = make_temp_dir("pgroup");
+    let artifact = dir.join("spawns.sh");
+    std::fs::write(&artifact, "#!/bin/sh\nsleep 30 &\necho $!\nwait\n").expect("write artifact");
+    std::fs::set_permissions(&artifact, std::fs::Permissions::from_mode(0o755))
+        .expect("chmod artifact");
+
+    let start = std::time::Instant::now();
+    let res = run_artifact_file(
+        &config(vec![SandboxFeature::ProcessGroupKill]),
+        &artifact,
+        b"",
+    )
+    .expect("run");
+    // The orphaned `sleep` would otherwise hold stdout open for 30s.
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    assert_eq!(res.trap.as_deref(), Some("wall timeout"));
+    assert_eq!(res.sandbox_caps, sandbox_capabilities());
+
+    let pid: i32 = String::from_utf8_lossy(&res.stdout)
+        .trim()
+        .parse()
+        .expect("grandchild pid");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while pid_alive(pid) && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    assert!(!pid_alive(pid), "grandchild {pid} survived the timeout");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(windows)]
+#[test]
+fn windows_reports_missing_rlimits_and_fails_fast_when_required() {
+    use x07_host_runner::SandboxUnavailable;
+
+    let caps = sandbox_capabilities();
+    assert!(!caps.cpu_limit);
+    assert!(!caps.fsize_limit);
+    assert!(caps.memory_limit);
+    assert!(caps.readonly_fixtures);
+    assert!(caps.process_group_kill);
+
+    // Rejected before the artifact is looked at.
+    let dir = make_temp_dir("require");
+    let err = run_artifact_file(
+        &config(vec![SandboxFeature::FsizeLimit, SandboxFeature::CpuLimit]),
+        &dir.join("missing.exe"),
+        b"",
+    )
+    .expect_err("cpu and fsize limits are unavailable on windows");
+    let err = err.downcast::<SandboxUnavailable>().expect("typed error");
+    assert_eq!(
+        err.missing,
+        vec![SandboxFeature::CpuLimit, SandboxFeature::FsizeLimit]
+    );
+    assert_eq!(err.caps, caps);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,72 @@
+use serde_json::json;
+use x07_host_runner::{
+    compile_program, parse_metrics, run_artifact_file, sanitize_cc_flag, RunnerConfig, Sanitizer,
+};
+use x07_worlds::WorldId;
+
+mod x07_program;
+
+fn config(sanitizers: Vec<Sanitizer>) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolvePure,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 20,
+        sanitizers,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sanitize_flag_is_sorted_and_deduplicated() {
+    assert_eq!(sanitize_cc_flag(&[]).unwrap(), None);
+    assert_eq!(
+        sanitize_cc_flag(&[
+            Sanitizer::Undefined,
+            Sanitizer::Address,
+            Sanitizer::Undefined
+        ])
+        .unwrap()
+        .as_deref(),
+        Some("-fsanitize=address,undefined")
+    );
+}
+
+#[test]
+fn address_and_thread_are_mutually_exclusive() {
+    let err = sanitize_cc_flag(&[Sanitizer::Address, Sanitizer::Thread]).unwrap_err();
+    assert!(err.to_string().contains("mutually exclusive"), "{err:#}");
+
+    let cfg = config(vec![Sanitizer::Thread, Sanitizer::Address]);
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    assert!(compile_program(program.as_slice(), &cfg, None).is_err());
+}
+
+#[test]
+fn metrics_line_survives_sanitizer_noise() {
+    let stderr = b"{\"fuel_used\":11}\n\
+==4242==WARNING: AddressSanitizer failed to allocate 0x1000 bytes\n\
+prog.c:10:5: runtime error: signed integer overflow\n";
+    let metrics = parse_metrics(stderr).expect("metrics must parse");
+    assert_eq!(metrics.fuel_used, Some(11));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ubsan_build_runs_clean_program() {
+    let cfg = config(vec![Sanitizer::Undefined]);
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let res = run_artifact_file(&cfg, &exe, b"sanitized").expect("runner ok");
+    assert!(
+        res.ok,
+        "trap={:?}\nstderr={:?}",
+        res.trap,
+        String::from_utf8_lossy(&res.stderr)
+    );
+    assert_eq!(res.solve_output, b"sanitized");
+}
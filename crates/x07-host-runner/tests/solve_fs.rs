@@ -2,7 +2,10 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use serde_json::json;
-use x07_host_runner::{compile_program, run_artifact_file, RunnerConfig};
+use x07_host_runner::{
+    compile_program, run_artifact_file, IndexKind, OverlayKind, RunnerConfig, StageHook,
+    StagingError, STAGING_FAILED_TRAP,
+};
 use x07_worlds::WorldId;
 
 mod x07_program;
@@ -33,16 +36,11 @@ fn solve_fs_can_read_fixture_file() {
     let cfg = RunnerConfig {
         world: WorldId::SolveFs,
         fixture_fs_dir: Some(fixture.clone()),
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry(&[], json!(["fs.read", ["bytes.lit", "config.bin"]]));
@@ -62,6 +60,55 @@ fn solve_fs_can_read_fixture_file() {
     rm_rf(&fixture);
 }
 
+#[test]
+fn stage_hook_files_are_visible_to_the_solver() {
+    let fixture = create_temp_dir("x07_fixture");
+    std::fs::write(fixture.join("config.bin"), b"\x01").expect("write fixture file");
+
+    let cfg = RunnerConfig {
+        world: WorldId::SolveFs,
+        fixture_fs_dir: Some(fixture.clone()),
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 5,
+        stage_hook: Some(StageHook::new(|run_dir| {
+            // Built-in staging has already run.
+            anyhow::ensure!(run_dir.join("config.bin").is_file());
+            std::fs::create_dir(run_dir.join("gen"))?;
+            std::fs::write(run_dir.join("gen").join("extra.bin"), b"from hook")?;
+            Ok(())
+        })),
+        ..Default::default()
+    };
+
+    let program = x07_program::entry(&[], json!(["fs.read", ["bytes.lit", "gen/extra.bin"]]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let res = run_artifact_file(&cfg, &exe, b"").expect("runner ok");
+    assert!(
+        res.ok,
+        "trap={:?}\nstderr={:?}",
+        res.trap,
+        String::from_utf8_lossy(&res.stderr)
+    );
+    assert_eq!(res.solve_output, b"from hook");
+
+    let failing = RunnerConfig {
+        stage_hook: Some(StageHook::new(|_| anyhow::bail!("no FIFO support here"))),
+        ..cfg
+    };
+    let err = run_artifact_file(&failing, &exe, b"").expect_err("hook error fails the run");
+    assert!(
+        format!("{err:#}").contains("no FIFO support here"),
+        "{err:#}"
+    );
+
+    rm_rf(&fixture);
+}
+
 #[test]
 fn solve_fs_rejects_absolute_paths() {
     let fixture = create_temp_dir("x07_fixture");
@@ -70,16 +117,11 @@ fn solve_fs_rejects_absolute_paths() {
     let cfg = RunnerConfig {
         world: WorldId::SolveFs,
         fixture_fs_dir: Some(fixture.clone()),
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry(&[], json!(["fs.read", ["bytes.lit", "/etc/passwd"]]));
@@ -104,16 +146,11 @@ fn solve_fs_can_list_dir_sorted() {
     let cfg = RunnerConfig {
         world: WorldId::SolveFs,
         fixture_fs_dir: Some(fixture.clone()),
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry(&[], json!(["fs.list_dir", ["bytes.lit", "cfg"]]));
@@ -143,16 +180,11 @@ fn solve_fs_list_dir_rejects_absolute_paths() {
     let cfg = RunnerConfig {
         world: WorldId::SolveFs,
         fixture_fs_dir: Some(fixture.clone()),
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry(&[], json!(["fs.list_dir", ["bytes.lit", "/etc"]]));
@@ -175,16 +207,11 @@ fn builtin_fs_module_can_be_imported() {
     let cfg = RunnerConfig {
         world: WorldId::SolveFs,
         fixture_fs_dir: Some(fixture.clone()),
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry(
@@ -206,3 +233,240 @@ fn builtin_fs_module_can_be_imported() {
 
     rm_rf(&fixture);
 }
+
+#[test]
+fn identical_fixtures_yield_the_same_fixture_digest() {
+    fn write_fixture(files: &[(&str, &[u8])]) -> PathBuf {
+        let fixture = create_temp_dir("x07_fixture");
+        std::fs::create_dir(fixture.join("cfg")).expect("create cfg dir");
+        for (rel, bytes) in files {
+            std::fs::write(fixture.join(rel), bytes).expect("write fixture file");
+        }
+        fixture
+    }
+
+    fn cfg_for(fixture: &Path) -> RunnerConfig {
+        RunnerConfig {
+            world: WorldId::SolveFs,
+            fixture_fs_dir: Some(fixture.to_path_buf()),
+            solve_fuel: 10_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+            max_output_bytes: 1024 * 1024,
+            cpu_time_limit_seconds: 5,
+            ..Default::default()
+        }
+    }
+
+    fn run_with(exe: &Path, fixture: &Path) -> Option<String> {
+        let res = run_artifact_file(&cfg_for(fixture), exe, b"").expect("runner ok");
+        assert!(res.ok, "trap={:?}", res.trap);
+        res.fixture_digest
+    }
+
+    // Same content, written in a different order.
+    let a = write_fixture(&[
+        ("cfg/a.txt", b"a"),
+        ("cfg/b.txt", b"b"),
+        ("top.bin", b"\x00"),
+    ]);
+    let b = write_fixture(&[
+        ("top.bin", b"\x00"),
+        ("cfg/b.txt", b"b"),
+        ("cfg/a.txt", b"a"),
+    ]);
+    let changed = write_fixture(&[
+        ("cfg/a.txt", b"a"),
+        ("cfg/b.txt", b"B"),
+        ("top.bin", b"\x00"),
+    ]);
+
+    let cfg = cfg_for(&a);
+    let program = x07_program::entry(&[], json!(["fs.read", ["bytes.lit", "top.bin"]]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let digest_a = run_with(&exe, &a).expect("fixture digest");
+    assert_eq!(digest_a.len(), 64);
+    assert_eq!(run_with(&exe, &b), Some(digest_a.clone()));
+    assert_ne!(run_with(&exe, &changed), Some(digest_a));
+
+    rm_rf(&a);
+    rm_rf(&b);
+    rm_rf(&changed);
+}
+
+fn staging_cfg(fixture: &Path) -> RunnerConfig {
+    RunnerConfig {
+        world: WorldId::SolveFs,
+        fixture_fs_dir: Some(fixture.to_path_buf()),
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 5,
+        ..Default::default()
+    }
+}
+
+/// Staging fails before the artifact is spawned, so any existing file will do.
+fn staging_error_for(cfg: &RunnerConfig) -> StagingError {
+    let artifact = std::env::current_exe().expect("current exe");
+    let res = run_artifact_file(cfg, &artifact, b"").expect("runner ok");
+    assert!(!res.ok);
+    assert_eq!(res.trap.as_deref(), Some(STAGING_FAILED_TRAP));
+    res.staging_error.expect("staging error")
+}
+
+#[test]
+fn missing_fixture_dir_is_a_staging_error() {
+    let fixture = create_temp_dir("x07_fixture");
+    let missing = fixture.join("nope");
+    assert_eq!(
+        staging_error_for(&staging_cfg(&missing)),
+        StagingError::MissingFixtureDir {
+            which: "fixture_fs_dir".to_string()
+        }
+    );
+    rm_rf(&fixture);
+}
+
+#[test]
+fn absolute_fixture_fs_root_is_a_staging_error() {
+    let fixture = create_temp_dir("x07_fixture");
+    let mut cfg = staging_cfg(&fixture);
+    cfg.fixture_fs_root = Some(PathBuf::from("/etc"));
+    assert_eq!(
+        staging_error_for(&cfg),
+        StagingError::UnsafeRelPath {
+            path: PathBuf::from("/etc")
+        }
+    );
+    rm_rf(&fixture);
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_fixture_file_is_a_copy_failure() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let fixture = create_temp_dir("x07_fixture");
+    let file = fixture.join("secret.bin");
+    std::fs::write(&file, b"x").expect("write fixture file");
+    std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o000)).expect("chmod");
+    if std::fs::read(&file).is_ok() {
+        // Running privileged: permissions are not enforced.
+        rm_rf(&fixture);
+        return;
+    }
+
+    match staging_error_for(&staging_cfg(&fixture)) {
+        StagingError::CopyFailed { src, errno, .. } => {
+            assert_eq!(src, file);
+            assert_eq!(errno, Some(libc::EACCES));
+        }
+        other => panic!("expected CopyFailed, got {other:?}"),
+    }
+    rm_rf(&fixture);
+}
+
+#[test]
+fn bad_latency_json_is_an_index_generation_failure() {
+    let fixture = create_temp_dir("x07_fixture");
+    std::fs::write(fixture.join("latency.json"), b"{not json").expect("write latency index");
+    let mut cfg = staging_cfg(&fixture);
+    cfg.fixture_fs_latency_index = Some(PathBuf::from("latency.json"));
+
+    match staging_error_for(&cfg) {
+        StagingError::IndexGenerationFailed { kind, src, reason } => {
+            assert_eq!(kind, IndexKind::FsLatency);
+            assert_eq!(src, Some(fixture.join("latency.json")));
+            assert!(reason.contains("parse fs latency json"), "{reason}");
+        }
+        other => panic!("expected IndexGenerationFailed, got {other:?}"),
+    }
+    rm_rf(&fixture);
+}
+
+fn write_files(root: &Path, files: &[(&str, &[u8])]) {
+    for (rel, bytes) in files {
+        let path = root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("create fixture dir");
+        std::fs::write(path, bytes).expect("write fixture file");
+    }
+}
+
+#[test]
+fn fs_overlays_replace_and_add_files_in_order() {
+    let fixture = create_temp_dir("x07_fixture");
+    write_files(
+        &fixture,
+        &[
+            ("base/cfg.txt", b"base"),
+            ("base/keep.txt", b"keep"),
+            ("case-a/cfg.txt", b"a"),
+            ("case-b/cfg.txt", b"b"),
+            ("case-b/new/added.txt", b"added"),
+        ],
+    );
+    let mut cfg = staging_cfg(&fixture);
+    cfg.fixture_fs_root = Some(PathBuf::from("base"));
+    cfg.fixture_fs_overlays = vec![PathBuf::from("case-a"), PathBuf::from("case-b")];
+
+    let program = x07_program::entry(&[], json!(["fs.read", ["view.to_bytes", "input"]]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    for (path, want) in [
+        ("cfg.txt", &b"b"[..]),
+        ("keep.txt", b"keep"),
+        ("new/added.txt", b"added"),
+    ] {
+        let res = run_artifact_file(&cfg, &exe, path.as_bytes()).expect("runner ok");
+        assert!(
+            res.ok,
+            "{path}: trap={:?}\nstderr={:?}",
+            res.trap,
+            String::from_utf8_lossy(&res.stderr)
+        );
+        assert_eq!(res.solve_output, want, "{path}");
+
+        let overlays = &res.fixture_overlays;
+        assert_eq!(overlays.len(), 2);
+        assert!(overlays.iter().all(|o| o.kind == OverlayKind::Fs));
+        assert_eq!(overlays[0].path, PathBuf::from("case-a"));
+        assert_eq!(overlays[1].path, PathBuf::from("case-b"));
+        assert_eq!(overlays[0].sha256.as_deref().map(str::len), Some(64));
+        assert_ne!(overlays[0].sha256, overlays[1].sha256);
+    }
+
+    rm_rf(&fixture);
+}
+
+#[test]
+fn fs_overlay_file_over_dir_is_a_staging_error() {
+    let fixture = create_temp_dir("x07_fixture");
+    write_files(
+        &fixture,
+        &[("base/cfg/a.txt", b"a"), ("case/cfg", b"not a dir")],
+    );
+    let mut cfg = staging_cfg(&fixture);
+    cfg.fixture_fs_root = Some(PathBuf::from("base"));
+    cfg.fixture_fs_overlays = vec![PathBuf::from("case")];
+    assert_eq!(
+        staging_error_for(&cfg),
+        StagingError::OverlayConflict {
+            overlay: PathBuf::from("case"),
+            path: PathBuf::from("cfg"),
+        }
+    );
+
+    cfg.fixture_fs_overlays = vec![PathBuf::from("../case")];
+    assert_eq!(
+        staging_error_for(&cfg),
+        StagingError::UnsafeRelPath {
+            path: PathBuf::from("../case")
+        }
+    );
+    rm_rf(&fixture);
+}
@@ -1,6 +1,9 @@
+use base64::Engine as _;
 use serde_json::json;
 use std::path::PathBuf;
-use x07_host_runner::{compile_program, run_artifact_file, RunnerConfig};
+use x07_host_runner::{
+    compile_program, run_artifact_file, KvSeed, KvSeedEntry, OverlayKind, RunnerConfig,
+};
 use x07_worlds::WorldId;
 
 mod x07_program;
@@ -12,17 +15,13 @@ fn solve_kv_reads_from_seeded_store() {
 
     let cfg = RunnerConfig {
         world: WorldId::SolveKv,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
         fixture_kv_dir: Some(fixture),
         fixture_kv_seed: Some(PathBuf::from("seed.json")),
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry(
@@ -45,3 +44,120 @@ fn solve_kv_reads_from_seeded_store() {
     assert_eq!(res.kv_get_calls, Some(1));
     assert_eq!(res.kv_set_calls, Some(0));
 }
+
+fn inline_seed_config(world: WorldId) -> RunnerConfig {
+    RunnerConfig {
+        world,
+        solve_fuel: 10_000_000,
+        max_memory_bytes: 64 * 1024 * 1024,
+        max_output_bytes: 1024 * 1024,
+        cpu_time_limit_seconds: 5,
+        kv_seed_inline: Some(KvSeed {
+            default_latency_ticks: 1,
+            entries: vec![
+                KvSeedEntry {
+                    key: b"zeta".to_vec(),
+                    value: b"last".to_vec(),
+                    latency_ticks: 1,
+                },
+                KvSeedEntry {
+                    key: b"alpha".to_vec(),
+                    value: b"first".to_vec(),
+                    latency_ticks: 3,
+                },
+            ],
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn solve_kv_inline_seed_needs_no_fixture_files() {
+    let cfg = inline_seed_config(WorldId::SolveKv);
+    let program = x07_program::entry(
+        &["std.kv"],
+        json!(["std.kv.get", ["view.to_bytes", "input"]]),
+    );
+
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    for (key, want) in [(&b"alpha"[..], &b"first"[..]), (b"zeta", b"last")] {
+        let res = run_artifact_file(&cfg, &exe, key).expect("runner ok");
+        assert!(
+            res.ok,
+            "trap={:?}\nstderr={:?}",
+            res.trap,
+            String::from_utf8_lossy(&res.stderr)
+        );
+        assert_eq!(res.solve_output, want);
+    }
+}
+
+#[test]
+fn inline_kv_seed_is_rejected_outside_kv_worlds() {
+    let cfg = inline_seed_config(WorldId::SolvePure);
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let err = run_artifact_file(&cfg, &exe, b"").expect_err("solve-pure rejects kv seed");
+    assert!(
+        format!("{err:#}").contains("kv_seed_inline is only supported for solve-kv and solve-full"),
+        "{err:#}"
+    );
+}
+
+#[test]
+fn kv_overlay_seed_replaces_base_entries_by_key() {
+    let fixture = std::env::temp_dir().join(format!("x07_kv_overlay_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&fixture);
+    std::fs::create_dir_all(&fixture).expect("create fixture dir");
+    let b64 = |b: &[u8]| base64::engine::general_purpose::STANDARD.encode(b);
+    let overlay = json!({
+        "format": "x07.kv.seed@0.1.0",
+        "default_latency_ticks": 9,
+        "entries": [
+            { "key_b64": b64(b"alpha"), "value_b64": b64(b"over"), "latency_ticks": 1 },
+            { "key_b64": b64(b"beta"), "value_b64": b64(b"added"), "latency_ticks": 1 },
+        ],
+    });
+    std::fs::write(
+        fixture.join("overlay.json"),
+        serde_json::to_vec(&overlay).unwrap(),
+    )
+    .expect("write overlay seed");
+
+    let mut cfg = inline_seed_config(WorldId::SolveKv);
+    cfg.fixture_kv_dir = Some(fixture.clone());
+    cfg.fixture_kv_overlay_seed = Some(PathBuf::from("overlay.json"));
+    let program = x07_program::entry(
+        &["std.kv"],
+        json!(["std.kv.get", ["view.to_bytes", "input"]]),
+    );
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    for (key, want) in [
+        (&b"alpha"[..], &b"over"[..]),
+        (b"beta", b"added"),
+        (b"zeta", b"last"),
+    ] {
+        let res = run_artifact_file(&cfg, &exe, key).expect("runner ok");
+        assert!(
+            res.ok,
+            "trap={:?}\nstderr={:?}",
+            res.trap,
+            String::from_utf8_lossy(&res.stderr)
+        );
+        assert_eq!(res.solve_output, want);
+        assert_eq!(res.fixture_overlays.len(), 1);
+        assert_eq!(res.fixture_overlays[0].kind, OverlayKind::Kv);
+        assert_eq!(res.fixture_overlays[0].path, PathBuf::from("overlay.json"));
+    }
+
+    let _ = std::fs::remove_dir_all(&fixture);
+}
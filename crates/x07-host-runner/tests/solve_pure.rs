@@ -1,5 +1,8 @@
 use serde_json::json;
-use x07_host_runner::{compile_program, run_artifact_file, RunnerConfig};
+use x07_host_runner::{
+    compile_options_for_world, compile_program, run_and_check, run_artifact_file,
+    run_artifact_file_from, RunnerConfig,
+};
 use x07_worlds::WorldId;
 
 mod x07_program;
@@ -7,17 +10,11 @@ mod x07_program;
 fn config() -> RunnerConfig {
     RunnerConfig {
         world: WorldId::SolvePure,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 20,
-        debug_borrow_checks: false,
+        ..Default::default()
     }
 }
 
@@ -60,6 +57,85 @@ fn solve_pure_echoes_bytes() {
     assert!(res.fuel_used.is_some());
 }
 
+#[test]
+fn input_from_file_matches_input_from_slice() {
+    let cfg = config();
+
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let input: Vec<u8> = (0..200_000u32).map(|i| (i * 31 % 251) as u8).collect();
+    let input_path =
+        std::env::temp_dir().join(format!("x07_solve_pure_input_{}.bin", std::process::id()));
+    std::fs::write(&input_path, &input).expect("write input file");
+
+    let from_slice = run_artifact_file(&cfg, &exe, &input).expect("runner ok");
+    let from_file = run_artifact_file_from(&cfg, &exe, &input_path).expect("runner ok");
+    assert!(
+        from_file.ok,
+        "trap={:?}\nstderr={:?}",
+        from_file.trap,
+        String::from_utf8_lossy(&from_file.stderr)
+    );
+    assert_eq!(from_file.solve_output, input);
+    assert_eq!(from_file.solve_output, from_slice.solve_output);
+    assert_eq!(from_file.fuel_used, from_slice.fuel_used);
+
+    std::fs::remove_file(&input_path).expect("remove input file");
+    let err = run_artifact_file_from(&cfg, &exe, &input_path).expect_err("missing input file");
+    assert!(format!("{err:#}").contains("open input file"), "{err:#}");
+}
+
+#[test]
+fn run_and_check_reports_first_diff_offset() {
+    let cfg = config();
+
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let check = run_and_check(&cfg, &exe, b"golden", b"golden").expect("runner ok");
+    assert!(check.matched, "trap={:?}", check.result.trap);
+    assert_eq!(check.first_diff_offset, None);
+    assert_eq!(check.result.solve_output, b"golden");
+
+    for (expected, offset) in [
+        (&b"goLden"[..], 2),
+        (&b"gold"[..], 4),
+        (&b"golden!"[..], 6),
+        (&b""[..], 0),
+    ] {
+        let check = run_and_check(&cfg, &exe, b"golden", expected).expect("runner ok");
+        assert!(check.result.ok, "trap={:?}", check.result.trap);
+        assert!(!check.matched, "{expected:?}");
+        assert_eq!(check.first_diff_offset, Some(offset), "{expected:?}");
+    }
+}
+
+#[test]
+fn raw_stdout_is_dropped_on_success_when_not_retained() {
+    let mut cfg = config();
+
+    let program = x07_program::entry(&[], json!(["view.to_bytes", "input"]));
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let input = vec![7u8; 64 * 1024];
+    let res = run_artifact_file(&cfg, &exe, &input).expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert_eq!(res.stdout.len(), 4 + input.len());
+
+    cfg.retain_raw_stdout = false;
+    let res = run_artifact_file(&cfg, &exe, &input).expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    assert_eq!(res.solve_output, input);
+    assert!(res.stdout.is_empty());
+}
+
 #[test]
 fn solve_pure_find_sub_returns_substring_index() {
     let cfg = config();
@@ -351,6 +427,7 @@ fn debug_borrow_checks_detect_stale_bytes_after_view_as_bytes() {
 
     let dbg = res.debug_stats.expect("debug_stats");
     assert_eq!(dbg.borrow_violations, 0, "debug_stats={dbg:?}");
+    assert!(dbg.borrow_violation_sites.is_empty(), "debug_stats={dbg:?}");
 }
 
 #[test]
@@ -382,6 +459,62 @@ fn debug_borrow_checks_detect_stale_output_bytes() {
     assert_eq!(dbg.borrow_violations, 0, "debug_stats={dbg:?}");
 }
 
+#[test]
+fn debug_borrow_checks_report_violation_site_in_generated_c() {
+    let mut cfg = config();
+    cfg.debug_borrow_checks = true;
+
+    // The task keeps a view into `h`, which is dropped when the inner block
+    // ends; the read inside the task is a use-after-drop the static checks
+    // do not see.
+    let program = x07_program::entry_with_decls(
+        &[],
+        vec![x07_program::defasync(
+            "main.first",
+            &[("v", "bytes_view")],
+            "bytes",
+            json!(["bytes1", ["view.get_u8", "v", 0]]),
+        )],
+        json!([
+            "begin",
+            [
+                "let",
+                "t",
+                [
+                    "begin",
+                    ["let", "h", ["vec_u8.with_capacity", 1]],
+                    ["set", "h", ["vec_u8.push", "h", 7]],
+                    ["main.first", ["vec_u8.as_view", "h"]]
+                ]
+            ],
+            ["await", "t"]
+        ]),
+    );
+
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let res = run_artifact_file(&cfg, &exe, b"").expect("runner ok");
+    assert!(res.ok, "trap={:?}", res.trap);
+    let dbg = res.debug_stats.expect("debug_stats");
+    assert_eq!(dbg.borrow_violations, 1, "debug_stats={dbg:?}");
+
+    let opts = compile_options_for_world(WorldId::SolvePure, Vec::new()).expect("options");
+    let c_src = x07c::compile::compile_program_to_c(program.as_slice(), &opts).expect("emit C");
+    let call_lines: Vec<u32> = c_src
+        .lines()
+        .zip(1u32..)
+        .filter(|(l, _)| l.contains("= rt_view_get_u8(ctx, "))
+        .map(|(_, n)| n)
+        .collect();
+    assert_eq!(call_lines.len(), 1, "view.get_u8 calls at {call_lines:?}");
+    assert_eq!(
+        dbg.borrow_violation_sites, call_lines,
+        "debug_stats={dbg:?}"
+    );
+}
+
 #[test]
 fn using_fs_module_in_solve_pure_fails() {
     let cfg = config();
@@ -405,3 +538,58 @@ fn using_fs_module_in_solve_pure_fails() {
         "compile_error={msg:?}"
     );
 }
+
+#[test]
+fn solve_seed_is_visible_and_deterministic_per_seed() {
+    let cfg = config();
+
+    // Four LCG steps from the run seed, as decimal text.
+    let program = x07_program::entry(
+        &[],
+        json!([
+            "begin",
+            ["let", "s", ["prng.run_seed_u32"]],
+            ["for", "i", 0, 4, ["set", "s", ["prng.lcg_next_u32", "s"]]],
+            ["fmt.u32_to_dec", "s"]
+        ]),
+    );
+    let compile = compile_program(program.as_slice(), &cfg, None).expect("compile ok");
+    assert!(compile.ok, "compile_error={:?}", compile.compile_error);
+    let exe = compile.compiled_exe.expect("compiled exe");
+
+    let run = |seed: Option<u64>| {
+        let mut cfg = config();
+        cfg.solve_seed = seed;
+        let res = run_artifact_file(&cfg, &exe, b"").expect("runner ok");
+        assert!(
+            res.ok,
+            "trap={:?}\nstderr={:?}",
+            res.trap,
+            String::from_utf8_lossy(&res.stderr)
+        );
+        assert_eq!(res.solve_seed, seed);
+        let trace = res.sched_stats.map(|s| s.sched_trace_hash);
+        (res.solve_output, trace)
+    };
+
+    let lcg4 = |mut s: u32| {
+        for _ in 0..4 {
+            s = s.wrapping_mul(1103515245).wrapping_add(12345);
+        }
+        s.to_string().into_bytes()
+    };
+
+    // No seed keeps the runtime default of 0.
+    assert_eq!(run(None).0, lcg4(0));
+
+    let a1 = run(Some(42));
+    let a2 = run(Some(42));
+    assert_eq!(a1, a2);
+    assert_eq!(a1.0, lcg4(42));
+
+    // The high half is folded in.
+    let b = run(Some((7u64 << 32) | 42));
+    assert_eq!(b, run(Some((7u64 << 32) | 42)));
+    assert_eq!(b.0, lcg4(42 ^ 7));
+    assert_ne!(a1.0, b.0);
+}
@@ -14,17 +14,12 @@ fn solve_rr_fetches_response_fixture() {
 
     let cfg = RunnerConfig {
         world: WorldId::SolveRr,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
         fixture_rr_dir: Some(fixture.clone()),
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry_with_decls(
@@ -116,17 +111,12 @@ fn solve_rr_miss_increments_metric() {
 
     let cfg = RunnerConfig {
         world: WorldId::SolveRr,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
         fixture_rr_dir: Some(fixture.clone()),
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 10_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds: 5,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let program = x07_program::entry_with_decls(
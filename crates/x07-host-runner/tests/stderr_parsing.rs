@@ -1,4 +1,6 @@
-use x07_host_runner::{parse_metrics, parse_trap_stderr};
+use x07_host_runner::{
+    parse_metrics, parse_trap_stderr, trap_detail_from_stderr, TRAP_DETAIL_MAX_BYTES,
+};
 
 #[test]
 fn parse_trap_stderr_handles_non_utf8() {
@@ -15,3 +17,65 @@ fn parse_metrics_handles_non_utf8() {
     let metrics = parse_metrics(stderr).expect("metrics must parse");
     assert_eq!(metrics.fuel_used, Some(7));
 }
+
+#[test]
+fn parse_metrics_accepts_debug_stats_without_sites() {
+    // Runtimes that predate the site list still parse.
+    let stderr = b"{\"debug_stats\":{\"borrow_violations\":2}}\n";
+    let dbg = parse_metrics(stderr).unwrap().debug_stats.unwrap();
+    assert_eq!(dbg.borrow_violations, 2);
+    assert!(dbg.borrow_violation_sites.is_empty());
+}
+
+#[test]
+fn parse_metrics_reads_db_latency_histograms() {
+    let stderr =
+        b"{\"fuel_used\":9,\"db_latency_ms\":{\"sqlite\":[3,1,0,0,0],\"redis\":[0,2,0,0,1]}}\n";
+    let lat = parse_metrics(stderr)
+        .expect("metrics must parse")
+        .db_latency_ms
+        .expect("db_latency_ms");
+    assert_eq!(lat["sqlite"], vec![3, 1, 0, 0, 0]);
+    assert_eq!(lat["redis"], vec![0, 2, 0, 0, 1]);
+    assert!(!lat.contains_key("pg"));
+
+    let stderr = b"{\"fuel_used\":9}\n";
+    assert!(parse_metrics(stderr).unwrap().db_latency_ms.is_none());
+}
+
+#[test]
+fn trap_detail_drops_the_metrics_line() {
+    assert_eq!(trap_detail_from_stderr(b"{\"fuel_used\":9}\n"), None);
+    assert_eq!(
+        trap_detail_from_stderr(b"boom\n{\"fuel_used\":9}\n").as_deref(),
+        Some("boom")
+    );
+}
+
+#[test]
+fn trap_detail_collapses_repeated_lines() {
+    let mut stderr = b"start\n".to_vec();
+    for _ in 0..500 {
+        stderr.extend_from_slice(b"assertion failed: x > 0\n");
+    }
+    stderr.extend_from_slice(b"aborting\n");
+    assert_eq!(
+        trap_detail_from_stderr(&stderr).as_deref(),
+        Some("start\nassertion failed: x > 0 [repeated 500 times]\naborting")
+    );
+}
+
+#[test]
+fn trap_detail_keeps_the_tail() {
+    let mut stderr = Vec::new();
+    for i in 0..1000 {
+        stderr.extend_from_slice(format!("line {i}\n").as_bytes());
+    }
+    let detail = trap_detail_from_stderr(&stderr).unwrap();
+    assert!(detail.len() <= TRAP_DETAIL_MAX_BYTES);
+    assert!(detail.ends_with("line 999"));
+    assert!(
+        detail.starts_with("line "),
+        "partial first line kept: {detail:?}"
+    );
+}
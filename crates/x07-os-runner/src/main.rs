@@ -14,7 +14,7 @@ use x07_contracts::RUN_OS_POLICY_SCHEMA_VERSION;
 use x07_contracts::{X07_OS_RUNNER_REPORT_SCHEMA_VERSION, X07_RUNTIME_ATTEST_SCHEMA_VERSION};
 use x07_host_runner::{
     apply_cc_profile, compile_program_with_options, CcProfile, CompilerResult, RunnerConfig,
-    RunnerResult,
+    RunnerResult, SandboxCaps,
 };
 use x07_runner_common::sandbox_backend::{
     resolve_sandbox_backend, EffectiveSandboxBackend, SandboxBackend,
@@ -22,8 +22,8 @@ use x07_runner_common::sandbox_backend::{
 use x07_runner_common::{auto_ffi, os_env, os_paths};
 use x07_vm::{
     copy_dir_recursive, default_cleanup_ms, default_grace_ms, firecracker_ctr_config_from_env,
-    resolve_sibling_or_path as resolve_sibling_or_path_vm, resolve_vm_backend, LimitsSpec,
-    MountSpec, NetworkMode, RunSpec, VmBackend,
+    resolve_sibling_or_path as resolve_sibling_or_path_vm, resolve_vm_backend_with_record,
+    LimitsSpec, MountKind, MountSpec, NetworkMode, RunSpec, VmBackend,
 };
 use x07_worlds::WorldId;
 
@@ -113,6 +113,10 @@ struct Cli {
 
     #[arg(long, value_name = "PATH")]
     attest_runtime: Option<PathBuf>,
+
+    /// Digest read-only VM mounts before launch and record them in the report.
+    #[arg(long)]
+    vm_digest_mounts: bool,
 }
 
 fn main() -> std::process::ExitCode {
@@ -327,8 +331,10 @@ fn try_main() -> Result<std::process::ExitCode> {
                 "sched_stats": solve.sched_stats,
                 "mem_stats": solve.mem_stats,
                 "debug_stats": solve.debug_stats,
+                "db_latency_ms": solve.db_latency_ms,
                 "trap": solve.trap,
                 "trap_help": x07_host_runner::trap_help_for(solve.trap.as_deref(), cli.solve_fuel),
+                "trap_detail": solve.trap_detail,
             });
             attach_runtime_fields(
                 &mut json,
@@ -767,6 +773,9 @@ fn extract_runner_result_from_run_os_report_json(
             .with_context(|| format!("missing required key in run-os report: {key:?}"))?;
         out.insert(key.to_string(), v.clone());
     }
+    if let Some(v) = run_os_report.get("db_latency_ms") {
+        out.insert("db_latency_ms".to_string(), v.clone());
+    }
 
     Ok(serde_json::Value::Object(out))
 }
@@ -816,7 +825,11 @@ fn run_vm(
     }
     let policy = policy.context("internal error: run-os-sandboxed policy missing")?;
 
-    let backend = resolve_vm_backend()?;
+    let resolution = resolve_vm_backend_with_record()?;
+    for warning in &resolution.warnings {
+        eprintln!("warning: {warning}");
+    }
+    let backend = resolution.backend;
 
     let guest_image = if backend == VmBackend::Vz {
         std::env::var(x07_vm::ENV_VZ_GUEST_BUNDLE).unwrap_or_default()
@@ -852,8 +865,8 @@ fn run_vm(
 
     let state_root = x07_vm::default_vm_state_root()?;
 
-    let build_run_id = format!("{run_id_base}-build");
-    let run_run_id = format!("{run_id_base}-run");
+    let build_run_id = x07_vm::RunId::parse(&format!("{run_id_base}-build"))?;
+    let run_run_id = x07_vm::RunId::parse(&format!("{run_id_base}-run"))?;
 
     let policy_path = cli
         .policy
@@ -982,11 +995,13 @@ fn run_vm(
             host_path: build_job_in.clone(),
             guest_path: PathBuf::from("/x07/in"),
             readonly: true,
+            kind: MountKind::Bind,
         },
         MountSpec {
             host_path: build_job_out.clone(),
             guest_path: PathBuf::from("/x07/out"),
             readonly: false,
+            kind: MountKind::Bind,
         },
     ];
 
@@ -1058,6 +1073,10 @@ fn run_vm(
         max_stdout_bytes: 32 * 1024 * 1024,
         max_stderr_bytes: 32 * 1024 * 1024,
         network: NetworkMode::None,
+        persist_output: false,
+        persist_output_dir: None,
+        warn_before_deadline_ms: None,
+        shm_size_bytes: None,
     };
 
     let build_spec = RunSpec {
@@ -1070,6 +1089,9 @@ fn run_vm(
         mounts: build_mounts,
         workdir: Some(PathBuf::from("/opt/x07")),
         limits: build_limits,
+        digest_mounts: false,
+        dry_run: false,
+        report_file: None,
     };
 
     let reaper_bin = resolve_sibling_or_path_vm("x07-vm-reaper");
@@ -1082,8 +1104,12 @@ fn run_vm(
             created_unix_ms: build_created_unix_ms,
             deadline_unix_ms: overall_deadline_unix_ms,
             firecracker_cfg: firecracker_cfg.as_ref(),
+            spawn_reaper: true,
         },
     )?;
+    for warning in &build_out.warnings {
+        eprintln!("warning: {warning}");
+    }
 
     if !build_out.stderr.is_empty() {
         let _ = std::io::Write::write_all(&mut std::io::stderr(), &build_out.stderr);
@@ -1221,11 +1247,13 @@ fn run_vm(
             host_path: run_job_in.clone(),
             guest_path: PathBuf::from("/x07/in"),
             readonly: true,
+            kind: MountKind::Bind,
         },
         MountSpec {
             host_path: run_job_out.clone(),
             guest_path: PathBuf::from("/x07/out"),
             readonly: false,
+            kind: MountKind::Bind,
         },
     ];
     x07_vm::append_root_mounts(
@@ -1280,6 +1308,10 @@ fn run_vm(
         max_stdout_bytes: 32 * 1024 * 1024,
         max_stderr_bytes: 32 * 1024 * 1024,
         network: run_network_mode,
+        persist_output: false,
+        persist_output_dir: None,
+        warn_before_deadline_ms: None,
+        shm_size_bytes: None,
     };
 
     let run_spec = RunSpec {
@@ -1292,6 +1324,9 @@ fn run_vm(
         mounts: run_mounts,
         workdir: Some(PathBuf::from("/opt/x07")),
         limits: run_limits,
+        digest_mounts: cli.vm_digest_mounts,
+        dry_run: false,
+        report_file: None,
     };
 
     let run_out = x07_vm::run_vm_job(
@@ -1303,8 +1338,12 @@ fn run_vm(
             created_unix_ms: run_created_unix_ms,
             deadline_unix_ms: overall_deadline_unix_ms,
             firecracker_cfg: firecracker_cfg.as_ref(),
+            spawn_reaper: true,
         },
     )?;
+    for warning in &run_out.warnings {
+        eprintln!("warning: {warning}");
+    }
 
     if !run_out.stderr.is_empty() {
         let _ = std::io::Write::write_all(&mut std::io::stderr(), &run_out.stderr);
@@ -1315,38 +1354,50 @@ fn run_vm(
         run_report_bytes.push(b'\n');
     }
 
-    let solve = match serde_json::from_slice::<serde_json::Value>(&run_report_bytes) {
-        Ok(run_report_json) => {
-            if run_report_json
-                .get("schema_version")
-                .and_then(|v| v.as_str())
-                != Some(X07_OS_RUNNER_REPORT_SCHEMA_VERSION)
-            {
-                synthesize_vm_solve_failure_runner_result(
-                    run_out.exit_status,
-                    &run_report_bytes,
-                    &run_out.stderr,
-                    "runner report schema_version mismatch".to_string(),
-                )
-            } else {
-                extract_runner_result_from_run_os_report_json(&run_report_json).unwrap_or_else(
-                    |err| {
-                        synthesize_vm_solve_failure_runner_result(
-                            run_out.exit_status,
-                            &run_report_bytes,
-                            &run_out.stderr,
-                            format!("invalid run-os runner report: {err}"),
-                        )
-                    },
-                )
-            }
-        }
-        Err(err) => synthesize_vm_solve_failure_runner_result(
+    let solve = match &run_out.guest_report {
+        Some(Err(err)) => synthesize_vm_solve_failure_runner_result(
             run_out.exit_status,
             &run_report_bytes,
             &run_out.stderr,
-            format!("invalid run-os runner report JSON: {err}"),
+            if err.is_truncation() {
+                format!("run-os runner report {err}")
+            } else {
+                format!("invalid run-os runner report: {err}")
+            },
         ),
+        _ => match serde_json::from_slice::<serde_json::Value>(&run_report_bytes) {
+            Ok(run_report_json) => {
+                if run_report_json
+                    .get("schema_version")
+                    .and_then(|v| v.as_str())
+                    != Some(X07_OS_RUNNER_REPORT_SCHEMA_VERSION)
+                {
+                    synthesize_vm_solve_failure_runner_result(
+                        run_out.exit_status,
+                        &run_report_bytes,
+                        &run_out.stderr,
+                        "runner report schema_version mismatch".to_string(),
+                    )
+                } else {
+                    extract_runner_result_from_run_os_report_json(&run_report_json).unwrap_or_else(
+                        |err| {
+                            synthesize_vm_solve_failure_runner_result(
+                                run_out.exit_status,
+                                &run_report_bytes,
+                                &run_out.stderr,
+                                format!("invalid run-os runner report: {err}"),
+                            )
+                        },
+                    )
+                }
+            }
+            Err(err) => synthesize_vm_solve_failure_runner_result(
+                run_out.exit_status,
+                &run_report_bytes,
+                &run_out.stderr,
+                format!("invalid run-os runner report JSON: {err}"),
+            ),
+        },
     };
 
     let compile = build_report_json
@@ -1404,6 +1455,12 @@ fn run_vm(
         "solve": solve,
     });
     attach_runtime_fields(&mut combined, Some("vm"), runtime_attestation.as_ref());
+    if let Some(digests) = &run_out.mount_digests {
+        combined["vm_mount_digests"] = serde_json::to_value(digests)?;
+    }
+    if let Some(signal) = run_out.exit_signal {
+        combined["vm_exit_signal"] = serde_json::json!(signal);
+    }
     println!("{}", serde_json::to_string_pretty(&combined)?);
     Ok(std::process::ExitCode::from(exit_code))
 }
@@ -1459,6 +1516,8 @@ fn compiler_json(
         "compiled_exe": compile.compiled_exe.as_ref().map(|p| p.display().to_string()),
         "compiled_exe_size": compile.compiled_exe_size,
         "compile_error": compile.compile_error,
+        "options_fingerprint": compile.options_fingerprint,
+        "native_cache_key": compile.native_cache_key,
         "stdout_b64": b64.encode(&compile.stdout),
         "stderr_b64": b64.encode(&compile.stderr),
         "fuel_used": compile.fuel_used,
@@ -1469,6 +1528,16 @@ fn compiler_json(
             out["diagnostics"] = diags;
         }
     }
+    if !compile.native_backend_resolutions.is_empty() {
+        if let Ok(resolutions) = serde_json::to_value(&compile.native_backend_resolutions) {
+            out["native_backend_resolutions"] = resolutions;
+        }
+    }
+    if !compile.cc_warnings.is_empty() {
+        if let Ok(warnings) = serde_json::to_value(&compile.cc_warnings) {
+            out["cc_warnings"] = warnings;
+        }
+    }
     out
 }
 
@@ -1498,8 +1567,10 @@ fn runner_json(
         "sched_stats": solve.sched_stats,
         "mem_stats": solve.mem_stats,
         "debug_stats": solve.debug_stats,
+        "db_latency_ms": solve.db_latency_ms,
         "trap": solve.trap,
         "trap_help": x07_host_runner::trap_help_for(solve.trap.as_deref(), solve_fuel),
+        "trap_detail": solve.trap_detail,
     })
 }
 
@@ -1793,17 +1864,12 @@ fn write_runtime_attestation(
 fn compile_runner_config(cli: &Cli, max_output_bytes: usize) -> RunnerConfig {
     RunnerConfig {
         world: x07_worlds::WorldId::SolvePure,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: cli.solve_fuel,
         max_memory_bytes: cli.max_memory_bytes,
         max_output_bytes,
         cpu_time_limit_seconds: cli.cpu_time_limit_seconds,
         debug_borrow_checks: cli.debug_borrow_checks,
+        ..Default::default()
     }
 }
 
@@ -2112,6 +2178,14 @@ fn run_child(inv: &RunInvocation<'_>) -> Result<ChildOutput> {
 }
 
 fn run_os_artifact(inv: &RunInvocation<'_>) -> Result<RunnerResult> {
+    let mut res = run_os_artifact_raw(inv)?;
+    if res.trap.is_some() {
+        res.trap_detail = x07_host_runner::trap_detail_from_stderr(&res.stderr);
+    }
+    Ok(res)
+}
+
+fn run_os_artifact_raw(inv: &RunInvocation<'_>) -> Result<RunnerResult> {
     let out = run_child(inv)?;
 
     if out.timed_out {
@@ -2136,7 +2210,18 @@ fn run_os_artifact(inv: &RunInvocation<'_>) -> Result<RunnerResult> {
             sched_stats: None,
             mem_stats: None,
             debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
             trap: Some("timed out".to_string()),
+            run_dir_bytes: None,
+            env_fingerprint: String::new(),
+            fixture_digest: None,
+            fixture_overlays: Vec::new(),
+            sandbox_degradations: Vec::new(),
+            sandbox_caps: SandboxCaps::default(),
+            solve_seed: None,
+            staging_error: None,
+            output_contract_violation: None,
         });
     }
 
@@ -2162,7 +2247,18 @@ fn run_os_artifact(inv: &RunInvocation<'_>) -> Result<RunnerResult> {
             sched_stats: None,
             mem_stats: None,
             debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
             trap: Some("stderr exceeded cap".to_string()),
+            run_dir_bytes: None,
+            env_fingerprint: String::new(),
+            fixture_digest: None,
+            fixture_overlays: Vec::new(),
+            sandbox_degradations: Vec::new(),
+            sandbox_caps: SandboxCaps::default(),
+            solve_seed: None,
+            staging_error: None,
+            output_contract_violation: None,
         });
     }
 
@@ -2188,7 +2284,18 @@ fn run_os_artifact(inv: &RunInvocation<'_>) -> Result<RunnerResult> {
             sched_stats: None,
             mem_stats: None,
             debug_stats: None,
+            db_latency_ms: None,
+            trap_detail: None,
             trap: Some("stdout exceeded cap".to_string()),
+            run_dir_bytes: None,
+            env_fingerprint: String::new(),
+            fixture_digest: None,
+            fixture_overlays: Vec::new(),
+            sandbox_degradations: Vec::new(),
+            sandbox_caps: SandboxCaps::default(),
+            solve_seed: None,
+            staging_error: None,
+            output_contract_violation: None,
         });
     }
 
@@ -2231,7 +2338,8 @@ fn run_os_artifact(inv: &RunInvocation<'_>) -> Result<RunnerResult> {
     let kv_set_calls = metrics.as_ref().and_then(|m| m.kv_set_calls);
     let sched_stats = metrics.as_ref().and_then(|m| m.sched_stats.clone());
     let mem_stats = metrics.as_ref().and_then(|m| m.mem_stats);
-    let debug_stats = metrics.as_ref().and_then(|m| m.debug_stats);
+    let debug_stats = metrics.as_ref().and_then(|m| m.debug_stats.clone());
+    let db_latency_ms = metrics.as_ref().and_then(|m| m.db_latency_ms.clone());
 
     let ok = out.exit_status == 0 && trap.is_none();
     Ok(RunnerResult {
@@ -2255,7 +2363,18 @@ fn run_os_artifact(inv: &RunInvocation<'_>) -> Result<RunnerResult> {
         sched_stats,
         mem_stats,
         debug_stats,
+        db_latency_ms,
         trap,
+        trap_detail: None,
+        run_dir_bytes: None,
+        env_fingerprint: String::new(),
+        fixture_digest: None,
+        fixture_overlays: Vec::new(),
+        sandbox_degradations: Vec::new(),
+        sandbox_caps: SandboxCaps::default(),
+        solve_seed: None,
+        staging_error: None,
+        output_contract_violation: None,
     })
 }
 
@@ -2763,17 +2882,11 @@ mod tests {
     fn base_runner_config(max_output_bytes: usize) -> RunnerConfig {
         RunnerConfig {
             world: x07_worlds::WorldId::SolvePure,
-            fixture_fs_dir: None,
-            fixture_fs_root: None,
-            fixture_fs_latency_index: None,
-            fixture_rr_dir: None,
-            fixture_kv_dir: None,
-            fixture_kv_seed: None,
             solve_fuel: 10_000_000,
             max_memory_bytes: 64 * 1024 * 1024,
             max_output_bytes,
             cpu_time_limit_seconds: 5,
-            debug_borrow_checks: false,
+            ..Default::default()
         }
     }
 
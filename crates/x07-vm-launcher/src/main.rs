@@ -6,8 +6,8 @@ use serde::Deserialize;
 use x07_runner_common::os_policy;
 use x07_vm::{
     default_cleanup_ms, default_grace_ms, firecracker_ctr_config_from_env, resolve_sibling_or_path,
-    resolve_vm_backend, run_vm_job_passthrough, LimitsSpec, MountSpec, NetworkMode, RunSpec,
-    VmBackend, VmJobRunParams, ENV_VZ_GUEST_BUNDLE,
+    resolve_vm_backend_with_record, run_vm_job_passthrough, LimitsSpec, MountKind, MountSpec,
+    NetworkMode, RunSpec, VmBackend, VmJobRunParams, ENV_VZ_GUEST_BUNDLE,
 };
 
 #[derive(Debug, Clone, Deserialize)]
@@ -76,7 +76,11 @@ fn try_main() -> Result<std::process::ExitCode> {
         None => manifest_backend,
     };
     std::env::set_var(x07_vm::ENV_VM_BACKEND, backend.to_string());
-    let backend = resolve_vm_backend()?;
+    let resolution = resolve_vm_backend_with_record()?;
+    for warning in &resolution.warnings {
+        eprintln!("warning: {warning}");
+    }
+    let backend = resolution.backend;
 
     let guest_image_override = std::env::var("X07_VM_GUEST_IMAGE").ok();
     let guest_image = if backend == VmBackend::Vz {
@@ -102,7 +106,7 @@ fn try_main() -> Result<std::process::ExitCode> {
     let created_unix_ms = now_unix_ms()?;
     let run_id = {
         let pid = std::process::id();
-        format!("{created_unix_ms}-{pid}")
+        x07_vm::RunId::parse(&format!("{created_unix_ms}-{pid}"))?
     };
 
     let wall_ms = policy.limits.wall_ms.max(1);
@@ -127,16 +131,19 @@ fn try_main() -> Result<std::process::ExitCode> {
             host_path: job_in.clone(),
             guest_path: PathBuf::from("/x07/in"),
             readonly: true,
+            kind: MountKind::Bind,
         },
         MountSpec {
             host_path: job_out.clone(),
             guest_path: PathBuf::from("/x07/out"),
             readonly: false,
+            kind: MountKind::Bind,
         },
         MountSpec {
             host_path: sidecar.clone(),
             guest_path: PathBuf::from("/x07/bundle"),
             readonly: true,
+            kind: MountKind::Bind,
         },
     ];
 
@@ -195,6 +202,10 @@ fn try_main() -> Result<std::process::ExitCode> {
         max_stdout_bytes: 64 * 1024 * 1024,
         max_stderr_bytes: 64 * 1024 * 1024,
         network: network_mode,
+        persist_output: false,
+        persist_output_dir: None,
+        warn_before_deadline_ms: None,
+        shm_size_bytes: None,
     };
 
     let spec = RunSpec {
@@ -207,6 +218,9 @@ fn try_main() -> Result<std::process::ExitCode> {
         mounts,
         workdir: Some(PathBuf::from(&manifest.workdir)),
         limits,
+        digest_mounts: false,
+        dry_run: false,
+        report_file: None,
     };
 
     let reaper_bin = resolve_reaper(&exe, &sidecar);
@@ -220,8 +234,12 @@ fn try_main() -> Result<std::process::ExitCode> {
             created_unix_ms,
             deadline_unix_ms,
             firecracker_cfg: firecracker_cfg.as_ref(),
+            spawn_reaper: true,
         },
     )?;
+    for warning in &out.warnings {
+        eprintln!("warning: {warning}");
+    }
 
     Ok(std::process::ExitCode::from(
         out.exit_status.clamp(0, 255) as u8
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use x07_vm::{enforce_kill_plan_for_job, KillResult, VmJob};
+use x07_vm::{enforce_kill_plan_for_job, read_job_file, KillResult, VmJob};
 
 #[derive(Parser)]
 #[command(name = "x07-vm-reaper")]
@@ -46,6 +46,10 @@ fn try_main() -> Result<()> {
     if res == KillResult::CompletedBeforeDeadline || done_marker.is_file() {
         return Ok(());
     }
+    // `cancel_job` owns the kill for a cancelled job.
+    if read_job_file(&cli.job).is_ok_and(|j| j.cancelled) {
+        return Ok(());
+    }
 
     let _ = std::fs::write(reaped_marker, b"reaped\n");
     Ok(())
@@ -0,0 +1,203 @@
+use std::process::{Command, Stdio};
+
+use crate::VmBackend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BackendVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl BackendVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl std::fmt::Display for BackendVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Returned (inside `anyhow::Error`) by preflight when a backend CLI is older
+/// than x07 supports; callers can `downcast_ref::<VersionTooOld>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionTooOld {
+    pub backend: VmBackend,
+    pub found: BackendVersion,
+    pub required: BackendVersion,
+}
+
+impl std::fmt::Display for VersionTooOld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} version {} is too old (x07 requires >= {})",
+            self.backend, self.found, self.required
+        )
+    }
+}
+
+impl std::error::Error for VersionTooOld {}
+
+/// `(minimum supported, newest tested major)` for backends driven through a
+/// versioned CLI. `vz` and `firecracker-ctr` are not version-checked.
+pub fn backend_version_requirements(backend: VmBackend) -> Option<(BackendVersion, u32)> {
+    match backend {
+        VmBackend::Docker => Some((BackendVersion::new(20, 10, 0), 28)),
+        VmBackend::Podman => Some((BackendVersion::new(4, 0, 0), 5)),
+        VmBackend::AppleContainer => Some((BackendVersion::new(0, 1, 0), 0)),
+        VmBackend::Vz | VmBackend::FirecrackerCtr => None,
+    }
+}
+
+/// Extracts the first `MAJOR.MINOR[.PATCH]` token from `--version` output,
+/// e.g. `Docker version 24.0.7, build afdd53b` or
+/// `container CLI version 0.5.0 (build: release)`.
+pub fn parse_backend_version(output: &str) -> Option<BackendVersion> {
+    output.split_whitespace().find_map(|token| {
+        let token = token.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '.');
+        let token = token.strip_prefix('v').unwrap_or(token);
+        let core = token.split(['-', '+']).next()?;
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = match parts.next() {
+            Some(p) => p.parse().ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(BackendVersion::new(major, minor, patch))
+    })
+}
+
+/// Compares `--version` output against the support table. Too-old versions
+/// are an error; untested or unparseable ones yield a warning message.
+pub fn check_backend_version(
+    backend: VmBackend,
+    version_output: &str,
+) -> Result<Option<String>, VersionTooOld> {
+    let Some((required, tested_major)) = backend_version_requirements(backend) else {
+        return Ok(None);
+    };
+    let Some(found) = parse_backend_version(version_output) else {
+        return Ok(Some(format!(
+            "could not parse {backend} version from {:?}",
+            version_output.trim()
+        )));
+    };
+    if found < required {
+        return Err(VersionTooOld {
+            backend,
+            found,
+            required,
+        });
+    }
+    if found.major > tested_major {
+        return Ok(Some(format!(
+            "{backend} version {found} is newer than tested (<= {tested_major}.x)"
+        )));
+    }
+    Ok(None)
+}
+
+pub(crate) fn backend_version_command(backend: VmBackend) -> Option<Command> {
+    let bin = match backend {
+        VmBackend::Docker => "docker",
+        VmBackend::Podman => "podman",
+        VmBackend::AppleContainer => "container",
+        VmBackend::Vz | VmBackend::FirecrackerCtr => return None,
+    };
+    let mut cmd = Command::new(bin);
+    cmd.arg("--version");
+    cmd.stdin(Stdio::null());
+    Some(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_real_version_strings() {
+        assert_eq!(
+            parse_backend_version("Docker version 24.0.7, build afdd53b"),
+            Some(BackendVersion::new(24, 0, 7))
+        );
+        assert_eq!(
+            parse_backend_version("podman version 4.9.3\n"),
+            Some(BackendVersion::new(4, 9, 3))
+        );
+        assert_eq!(
+            parse_backend_version("container CLI version 0.5.0 (build: release, commit: 1f2e3d4)"),
+            Some(BackendVersion::new(0, 5, 0))
+        );
+        assert_eq!(
+            parse_backend_version("Docker version 27.3.1-rd, build 7bc1d23"),
+            Some(BackendVersion::new(27, 3, 1))
+        );
+        assert_eq!(
+            parse_backend_version("ctr github.com/containerd/containerd v1.7.2"),
+            Some(BackendVersion::new(1, 7, 2))
+        );
+        assert_eq!(
+            parse_backend_version("Docker version 1.13, build 092cba3"),
+            Some(BackendVersion::new(1, 13, 0))
+        );
+    }
+
+    #[test]
+    fn rejects_unversioned_output() {
+        assert_eq!(parse_backend_version(""), None);
+        assert_eq!(parse_backend_version("docker: command not found"), None);
+        assert_eq!(parse_backend_version("build 1.2.3.4"), None);
+        assert_eq!(parse_backend_version("version x.y.z"), None);
+    }
+
+    #[test]
+    fn check_classifies_too_old_untested_and_ok() {
+        let err = check_backend_version(
+            VmBackend::Docker,
+            "Docker version 17.06.0-ce, build 02c1d87",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            VersionTooOld {
+                backend: VmBackend::Docker,
+                found: BackendVersion::new(17, 6, 0),
+                required: BackendVersion::new(20, 10, 0),
+            }
+        );
+        assert!(err.to_string().contains("17.6.0"), "{err}");
+        assert!(err.to_string().contains(">= 20.10.0"), "{err}");
+
+        assert_eq!(
+            check_backend_version(VmBackend::Docker, "Docker version 24.0.7, build afdd53b"),
+            Ok(None)
+        );
+
+        let warning = check_backend_version(VmBackend::Podman, "podman version 9.0.0")
+            .unwrap()
+            .expect("untested warning");
+        assert!(warning.contains("newer than tested"), "{warning}");
+
+        let warning = check_backend_version(VmBackend::Podman, "podman version ???")
+            .unwrap()
+            .expect("unparseable warning");
+        assert!(warning.contains("could not parse"), "{warning}");
+
+        assert_eq!(
+            check_backend_version(VmBackend::FirecrackerCtr, "anything"),
+            Ok(None)
+        );
+    }
+}
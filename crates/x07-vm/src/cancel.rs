@@ -0,0 +1,266 @@
+//! Cancelling an in-flight job from its persisted [`VmJob`].
+//!
+//! The kill sequence runs right away instead of at the job deadline. The job
+//! file records the cancel, and the done marker is written afterwards so the
+//! job's reaper exits without killing a second time.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enforce_kill_plan_for_job, read_job_file, touch_done_marker, write_job_file, KillResult, VmJob,
+};
+
+/// Written to [`VmJob::cancellation`] once a cancel's kill sequence finished.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobCancellation {
+    pub initiator: String,
+    pub cancelled_unix_ms: u64,
+    pub kill_result: KillResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// The job was still queued in a [`crate::JobPool`] and never started.
+    NotStarted,
+    /// The job finished on its own first; nothing was killed.
+    AlreadyFinished,
+    /// The kill sequence ran, for this call or an earlier cancel of the job.
+    Cancelled(JobCancellation),
+}
+
+/// Cancels the job whose `job.json` is at `job_file`: soft stop now, hard kill
+/// after `grace_ms`, then cleanup, via [`enforce_kill_plan_for_job`].
+///
+/// Cancelling a job that already finished or was already cancelled changes
+/// nothing and returns that earlier outcome.
+pub fn cancel_job(job_file: &Path, grace_ms: u64, initiator: &str) -> Result<CancelOutcome> {
+    cancel_job_with(job_file, grace_ms, initiator, enforce_kill_plan_for_job)
+}
+
+fn cancel_job_with<F>(
+    job_file: &Path,
+    grace_ms: u64,
+    initiator: &str,
+    kill: F,
+) -> Result<CancelOutcome>
+where
+    F: FnOnce(&VmJob, &Path, &Path) -> Result<KillResult>,
+{
+    let state_dir = job_file
+        .parent()
+        .context("job file has no parent directory")?;
+    let done_marker = state_dir.join("done");
+
+    let mut job = read_job_file(job_file)?;
+    if let Some(prior) = job.cancellation {
+        return Ok(CancelOutcome::Cancelled(prior));
+    }
+    if job.cancelled {
+        anyhow::bail!("job {} is already being cancelled", job.run_id);
+    }
+    if done_marker.is_file() {
+        return Ok(CancelOutcome::AlreadyFinished);
+    }
+
+    job.cancelled = true;
+    write_job_file(job_file, &job)?;
+
+    let now = now_unix_ms()?;
+    let immediate = VmJob {
+        created_unix_ms: now,
+        deadline_unix_ms: now.saturating_add(grace_ms),
+        grace_ms,
+        ..job.clone()
+    };
+    let kill_result = match kill(&immediate, state_dir, &done_marker) {
+        Ok(res) => res,
+        Err(err) => {
+            // Leave the job cancellable again.
+            let mut job = read_job_file(job_file).unwrap_or(job);
+            job.cancelled = false;
+            let _ = write_job_file(job_file, &job);
+            return Err(err.context(format!("cancel job {}", immediate.run_id)));
+        }
+    };
+    touch_done_marker(&done_marker)?;
+
+    // The runner may have written its timeline back in the meantime.
+    let mut job = read_job_file(job_file).unwrap_or(job);
+    let cancellation = JobCancellation {
+        initiator: initiator.to_string(),
+        cancelled_unix_ms: now,
+        kill_result,
+    };
+    job.cancelled = true;
+    job.cancellation = Some(cancellation.clone());
+    write_job_file(job_file, &job)?;
+    Ok(CancelOutcome::Cancelled(cancellation))
+}
+
+/// Carries a cancel already recorded in `job_file` over to `job`, so a runner
+/// writing its copy back does not erase it.
+pub(crate) fn keep_recorded_cancellation(job_file: &Path, job: &mut VmJob) {
+    if let Ok(on_disk) = read_job_file(job_file) {
+        job.cancelled |= on_disk.cancelled;
+        if job.cancellation.is_none() {
+            job.cancellation = on_disk.cancellation;
+        }
+    }
+}
+
+fn now_unix_ms() -> Result<u64> {
+    let d = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system time before unix epoch")?;
+    Ok(d.as_millis().try_into().unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RunId, VmBackend, VM_JOB_SCHEMA_VERSION};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn state_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "x07_vm_cancel_{name}_{}_{}",
+            std::process::id(),
+            now_unix_ms().unwrap()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_running_job(dir: &Path) -> PathBuf {
+        let now = now_unix_ms().unwrap();
+        let job = VmJob {
+            schema_version: VM_JOB_SCHEMA_VERSION.to_string(),
+            run_id: RunId::parse("cancel-me").unwrap(),
+            backend: VmBackend::Docker,
+            container_id: "x07-cancel-me".to_string(),
+            pid: None,
+            created_unix_ms: now,
+            deadline_unix_ms: now + 3_600_000,
+            grace_ms: 1_000,
+            cleanup_ms: 1_000,
+            ctr: None,
+            timeline: None,
+            mount_digests: None,
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: None,
+            warned: false,
+            warned_unix_ms: None,
+        };
+        let job_file = dir.join("job.json");
+        write_job_file(&job_file, &job).unwrap();
+        job_file
+    }
+
+    #[test]
+    fn cancel_runs_kill_once_and_records_outcome() {
+        let dir = state_dir("mid_run");
+        let job_file = write_running_job(&dir);
+        let kills = Arc::new(AtomicUsize::new(0));
+
+        // Stand-in for the long-running container: it only exits once killed.
+        let container_kills = Arc::clone(&kills);
+        let container = std::thread::spawn(move || {
+            while container_kills.load(Ordering::SeqCst) == 0 {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        });
+
+        let kill = |job: &VmJob, _state_dir: &Path, done: &Path| {
+            assert!(!done.is_file());
+            assert_eq!(job.deadline_unix_ms - job.created_unix_ms, 250);
+            let on_disk = read_job_file(&dir.join("job.json")).unwrap();
+            assert!(on_disk.cancelled && on_disk.cancellation.is_none());
+            kills.fetch_add(1, Ordering::SeqCst);
+            Ok(KillResult::KilledAtHardDeadline)
+        };
+        let out = cancel_job_with(&job_file, 250, "test-suite", kill).unwrap();
+        container.join().unwrap();
+
+        let CancelOutcome::Cancelled(c) = &out else {
+            panic!("expected cancelled, got {out:?}");
+        };
+        assert_eq!(c.initiator, "test-suite");
+        assert_eq!(c.kill_result, KillResult::KilledAtHardDeadline);
+        assert!(dir.join("done").is_file());
+        let recorded = read_job_file(&job_file).unwrap();
+        assert!(recorded.cancelled);
+        assert_eq!(recorded.cancellation.as_ref(), Some(c));
+
+        // A second cancel reports the first outcome without killing again.
+        let again = cancel_job_with(
+            &job_file,
+            250,
+            "someone-else",
+            |_: &VmJob, _: &Path, _: &Path| {
+                kills.fetch_add(1, Ordering::SeqCst);
+                Ok(KillResult::CleanupTimeout)
+            },
+        )
+        .unwrap();
+        assert_eq!(again, out);
+        assert_eq!(kills.load(Ordering::SeqCst), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cancel_after_finish_is_a_no_op() {
+        let dir = state_dir("finished");
+        let job_file = write_running_job(&dir);
+        touch_done_marker(&dir.join("done")).unwrap();
+        let before = std::fs::read(&job_file).unwrap();
+
+        let out = cancel_job_with(
+            &job_file,
+            250,
+            "test-suite",
+            |_: &VmJob, _: &Path, _: &Path| panic!("finished job must not be killed"),
+        )
+        .unwrap();
+        assert_eq!(out, CancelOutcome::AlreadyFinished);
+        assert_eq!(std::fs::read(&job_file).unwrap(), before);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn runner_write_back_keeps_recorded_cancellation() {
+        let dir = state_dir("write_back");
+        let job_file = write_running_job(&dir);
+        let mut runner_copy = read_job_file(&job_file).unwrap();
+        cancel_job_with(
+            &job_file,
+            250,
+            "test-suite",
+            |_: &VmJob, _: &Path, _: &Path| Ok(KillResult::CompletedBeforeDeadline),
+        )
+        .unwrap();
+
+        keep_recorded_cancellation(&job_file, &mut runner_copy);
+        assert!(runner_copy.cancelled);
+        assert_eq!(
+            runner_copy.cancellation.map(|c| c.initiator).as_deref(),
+            Some("test-suite")
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,4 +1,10 @@
-use crate::VmBackend;
+use std::path::{Path, PathBuf};
+
+use crate::guest_paths::{guest_path_denylist_from_env, guest_path_issues};
+use crate::{
+    normalize_abs_path, MountKind, MountSpec, NetworkMode, RunSpec, VmBackend,
+    CONTAINER_ID_MAX_BYTES, VZ_JOB_IN_GUEST_PATH, VZ_JOB_OUT_GUEST_PATH, VZ_MAX_EXTRA_MOUNTS,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct VmCaps {
@@ -44,8 +50,291 @@ impl VmCaps {
     }
 }
 
+/// What x07-vm actually drives on each backend. Unsupported features are
+/// absent from the generated command/request, not silently emulated.
+/// Fields that overlap [`VmCaps`] are taken from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendCaps {
+    pub tmpfs_mounts: bool,
+    pub pids_limit: bool,
+    /// `NetworkMode::None` is enforced.
+    pub network_restriction: bool,
+    /// Passthrough runs forward the caller's stdin into the workload.
+    pub stdin: bool,
+    /// Commands can be exec'd into a running job.
+    pub exec: bool,
+    /// CPU/memory usage is reported back in `RunOutput`.
+    pub resource_usage: bool,
+    /// `LimitsSpec.shm_size_bytes` is applied (otherwise ignored with a warning).
+    pub shm_size: bool,
+    /// `MountKind::NamedVolume` mounts are supported.
+    pub named_volumes: bool,
+    /// Upper bound on `RunSpec.mounts`; `None` means unbounded.
+    pub max_mounts: Option<usize>,
+    /// Guest paths that must appear in `RunSpec.mounts`.
+    pub required_mounts: &'static [&'static str],
+    pub max_container_id_bytes: usize,
+}
+
+const VZ_REQUIRED_MOUNTS: &[&str] = &[VZ_JOB_IN_GUEST_PATH, VZ_JOB_OUT_GUEST_PATH];
+
+pub fn backend_capabilities(backend: VmBackend) -> BackendCaps {
+    let vm = VmCaps::for_backend(backend);
+    let base = BackendCaps {
+        tmpfs_mounts: false,
+        pids_limit: false,
+        network_restriction: vm.supports_network_none,
+        stdin: false,
+        exec: false,
+        resource_usage: false,
+        shm_size: false,
+        named_volumes: false,
+        max_mounts: None,
+        required_mounts: &[],
+        max_container_id_bytes: CONTAINER_ID_MAX_BYTES,
+    };
+    match backend {
+        // `docker_like_command` passes `-i` for passthrough, `--shm-size` and
+        // `type=volume` mounts.
+        VmBackend::Docker | VmBackend::Podman => BackendCaps {
+            stdin: true,
+            shm_size: true,
+            named_volumes: true,
+            ..base
+        },
+        // The vz helper caps the extra virtio-fs shares.
+        VmBackend::Vz => BackendCaps {
+            max_mounts: Some(VZ_REQUIRED_MOUNTS.len() + VZ_MAX_EXTRA_MOUNTS),
+            required_mounts: VZ_REQUIRED_MOUNTS,
+            ..base
+        },
+        VmBackend::AppleContainer | VmBackend::FirecrackerCtr => base,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatibility {
+    NetworkRestriction,
+    TooManyMounts {
+        got: usize,
+        max: usize,
+    },
+    MissingMount {
+        guest_path: &'static str,
+    },
+    ContainerIdTooLong {
+        len: usize,
+        max: usize,
+    },
+    NamedVolume {
+        name: String,
+    },
+    /// `mounts[index].guest_path` is relative.
+    GuestPathNotAbsolute {
+        index: usize,
+        guest_path: PathBuf,
+    },
+    /// `mounts[index].guest_path` normalizes onto a denylisted path; see
+    /// [`crate::ENV_VM_GUEST_PATH_DENYLIST`].
+    GuestPathDenied {
+        index: usize,
+        guest_path: PathBuf,
+        target: PathBuf,
+    },
+    DuplicateGuestPath {
+        first: usize,
+        first_guest_path: PathBuf,
+        second: usize,
+        second_guest_path: PathBuf,
+        target: PathBuf,
+    },
+    /// vz shares `/x07/in` and `/x07/out` whole; nothing can be mounted inside.
+    VzReservedGuestPath {
+        index: usize,
+        guest_path: PathBuf,
+        reserved: &'static str,
+    },
+    /// `report_file` is not a file path under a `/x07/out` bind mount.
+    ReportFile {
+        path: PathBuf,
+        reason: &'static str,
+    },
+}
+
+impl std::fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Incompatibility::NetworkRestriction => f.write_str("network=none is not supported"),
+            Incompatibility::TooManyMounts { got, max } => {
+                write!(f, "{got} mounts (max {max})")
+            }
+            Incompatibility::MissingMount { guest_path } => {
+                write!(f, "missing required mount {guest_path}")
+            }
+            Incompatibility::ContainerIdTooLong { len, max } => {
+                write!(f, "container id is {len} bytes (max {max})")
+            }
+            Incompatibility::NamedVolume { name } => {
+                write!(f, "named volume {name:?} is not supported")
+            }
+            Incompatibility::GuestPathNotAbsolute { index, guest_path } => {
+                write!(
+                    f,
+                    "mounts[{index}] guest path {} is not absolute",
+                    guest_path.display()
+                )
+            }
+            Incompatibility::GuestPathDenied {
+                index,
+                guest_path,
+                target,
+            } => write!(
+                f,
+                "mounts[{index}] guest path {} targets {}, which is denied",
+                guest_path.display(),
+                target.display()
+            ),
+            Incompatibility::DuplicateGuestPath {
+                first,
+                first_guest_path,
+                second,
+                second_guest_path,
+                target,
+            } => write!(
+                f,
+                "mounts[{first}] ({}) and mounts[{second}] ({}) both target {}",
+                first_guest_path.display(),
+                second_guest_path.display(),
+                target.display()
+            ),
+            Incompatibility::VzReservedGuestPath {
+                index,
+                guest_path,
+                reserved,
+            } => write!(
+                f,
+                "mounts[{index}] guest path {} is inside {reserved}, which vz reserves",
+                guest_path.display()
+            ),
+            Incompatibility::ReportFile { path, reason } => {
+                write!(f, "report_file {}: {reason}", path.display())
+            }
+        }
+    }
+}
+
+/// Every way a [`RunSpec`] exceeds what a backend supports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleSpec {
+    pub backend: VmBackend,
+    pub issues: Vec<Incompatibility>,
+}
+
+impl std::fmt::Display for IncompatibleSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "run spec is not supported by the {} backend: ",
+            self.backend
+        )?;
+        for (idx, issue) in self.issues.iter().enumerate() {
+            if idx != 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for IncompatibleSpec {}
+
+impl RunSpec {
+    /// Cross-checks the spec against [`backend_capabilities`], collecting
+    /// every incompatibility instead of stopping at the first. Mount guest
+    /// paths are checked after normalization, so `/x07/../proc` counts as
+    /// `/proc`.
+    pub fn validate_for(&self, backend: VmBackend) -> Result<(), IncompatibleSpec> {
+        let caps = backend_capabilities(backend);
+        let mut issues = Vec::new();
+
+        if self.limits.network == NetworkMode::None && !caps.network_restriction {
+            issues.push(Incompatibility::NetworkRestriction);
+        }
+        if let Some(max) = caps.max_mounts {
+            if self.mounts.len() > max {
+                issues.push(Incompatibility::TooManyMounts {
+                    got: self.mounts.len(),
+                    max,
+                });
+            }
+        }
+        if !caps.named_volumes {
+            for m in &self.mounts {
+                if let MountKind::NamedVolume { name } = &m.kind {
+                    issues.push(Incompatibility::NamedVolume { name: name.clone() });
+                }
+            }
+        }
+        issues.extend(guest_path_issues(
+            &self.mounts,
+            backend,
+            &guest_path_denylist_from_env(),
+        ));
+        for &guest_path in caps.required_mounts {
+            if !self.mounts.iter().any(|m| {
+                normalize_abs_path(&m.guest_path).is_ok_and(|p| p == Path::new(guest_path))
+            }) {
+                issues.push(Incompatibility::MissingMount { guest_path });
+            }
+        }
+        if let Some(path) = self.report_file.as_deref() {
+            if let Some(reason) = report_file_issue(path, &self.mounts) {
+                issues.push(Incompatibility::ReportFile {
+                    path: path.to_path_buf(),
+                    reason,
+                });
+            }
+        }
+        let len = "x07-".len() + self.run_id.as_str().len();
+        if len > caps.max_container_id_bytes {
+            issues.push(Incompatibility::ContainerIdTooLong {
+                len,
+                max: caps.max_container_id_bytes,
+            });
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(IncompatibleSpec { backend, issues })
+        }
+    }
+}
+
+/// The host reads the report back through the `/x07/out` mount, so that
+/// mount must be a host dir.
+fn report_file_issue(path: &Path, mounts: &[MountSpec]) -> Option<&'static str> {
+    let Ok(target) = normalize_abs_path(path) else {
+        return Some("must be absolute");
+    };
+    if target == Path::new(VZ_JOB_OUT_GUEST_PATH) || !target.starts_with(VZ_JOB_OUT_GUEST_PATH) {
+        return Some("must be a file under /x07/out");
+    }
+    let out = mounts.iter().find(|m| {
+        normalize_abs_path(&m.guest_path).is_ok_and(|p| p == Path::new(VZ_JOB_OUT_GUEST_PATH))
+    });
+    match out {
+        Some(m) if matches!(m.kind, MountKind::Bind) => None,
+        Some(_) => Some("/x07/out must be a bind mount to be read back"),
+        None => Some("needs a bind mount at /x07/out"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::path::PathBuf;
+
     use super::*;
 
     #[test]
@@ -55,4 +344,338 @@ mod tests {
         assert!(VmCaps::for_backend(VmBackend::Vz).supports_network_none);
         assert!(VmCaps::for_backend(VmBackend::Vz).supports_bind_mount_ro);
     }
+
+    fn spec(backend: VmBackend, mounts: &[&str]) -> RunSpec {
+        RunSpec {
+            run_id: crate::RunId::parse("caps").unwrap(),
+            backend,
+            image: "x07-test".to_string(),
+            image_digest: None,
+            argv: vec!["/bin/cat".to_string()],
+            env: Default::default(),
+            mounts: mounts
+                .iter()
+                .map(|p| crate::MountSpec {
+                    host_path: PathBuf::from("/tmp/x07-caps"),
+                    guest_path: PathBuf::from(p),
+                    readonly: true,
+                    kind: MountKind::Bind,
+                })
+                .collect(),
+            workdir: None,
+            limits: crate::LimitsSpec {
+                wall_ms: 1_000,
+                grace_ms: 100,
+                cleanup_ms: 100,
+                mem_bytes: Some(256 * 1024 * 1024),
+                vcpus: None,
+                max_stdout_bytes: 1024,
+                max_stderr_bytes: 1024,
+                network: NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+                shm_size_bytes: Some(64 * 1024 * 1024),
+            },
+            digest_mounts: false,
+            dry_run: false,
+            report_file: None,
+        }
+    }
+
+    fn extra_mounts(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("/data/{i}")).collect()
+    }
+
+    fn docker_args(spec: &RunSpec, interactive: bool) -> Vec<String> {
+        crate::docker_like_command(
+            "docker",
+            spec,
+            "x07-caps",
+            &Default::default(),
+            false,
+            interactive,
+        )
+        .expect("docker command")
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect()
+    }
+
+    #[test]
+    fn docker_matrix_matches_command_builder() {
+        let caps = backend_capabilities(VmBackend::Docker);
+        let many: Vec<String> = extra_mounts(200);
+        let many: Vec<&str> = many.iter().map(String::as_str).collect();
+        let spec = spec(VmBackend::Docker, &many);
+        spec.validate_for(VmBackend::Docker).unwrap();
+
+        let has = |args: &[String], flag: &str, value: &str| {
+            args.windows(2).any(|w| w[0] == flag && w[1] == value)
+        };
+        let captured = docker_args(&spec, false);
+        let passthrough = docker_args(&spec, true);
+        let cases: [(&str, bool, bool, bool); 5] = [
+            (
+                "network_restriction",
+                true,
+                caps.network_restriction,
+                has(&captured, "--network", "none"),
+            ),
+            (
+                "stdin",
+                true,
+                caps.stdin,
+                passthrough.iter().any(|a| a == "-i"),
+            ),
+            (
+                "shm_size",
+                true,
+                caps.shm_size,
+                has(&captured, "--shm-size", "64m"),
+            ),
+            (
+                "tmpfs_mounts",
+                false,
+                caps.tmpfs_mounts,
+                captured.iter().any(|a| a == "--tmpfs"),
+            ),
+            (
+                "pids_limit",
+                false,
+                caps.pids_limit,
+                captured.iter().any(|a| a == "--pids-limit"),
+            ),
+        ];
+        for (name, expected, claimed, observed) in cases {
+            assert_eq!(claimed, expected, "docker cap {name}");
+            assert_eq!(observed, expected, "docker flag for {name}");
+        }
+        assert_eq!(caps.max_mounts, None);
+        assert_eq!(captured.iter().filter(|a| *a == "--mount").count(), 200);
+    }
+
+    #[test]
+    fn capability_matrix_per_backend() {
+        let base = BackendCaps {
+            tmpfs_mounts: false,
+            pids_limit: false,
+            network_restriction: true,
+            stdin: false,
+            exec: false,
+            resource_usage: false,
+            shm_size: false,
+            named_volumes: false,
+            max_mounts: None,
+            required_mounts: &[],
+            max_container_id_bytes: CONTAINER_ID_MAX_BYTES,
+        };
+        let container = BackendCaps {
+            stdin: true,
+            shm_size: true,
+            named_volumes: true,
+            ..base
+        };
+        let vz = BackendCaps {
+            max_mounts: Some(2 + VZ_MAX_EXTRA_MOUNTS),
+            required_mounts: &["/x07/in", "/x07/out"],
+            ..base
+        };
+        for (backend, expected) in [
+            (VmBackend::Docker, container),
+            (VmBackend::Podman, container),
+            (VmBackend::Vz, vz),
+            (VmBackend::AppleContainer, base),
+            (VmBackend::FirecrackerCtr, base),
+        ] {
+            let caps = backend_capabilities(backend);
+            assert_eq!(caps, expected, "{backend:?}");
+            assert_eq!(
+                caps.network_restriction,
+                VmCaps::for_backend(backend).supports_network_none,
+                "{backend:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn vz_matrix_matches_mount_handling() {
+        let caps = backend_capabilities(VmBackend::Vz);
+        assert!(!caps.stdin && !caps.shm_size && caps.network_restriction);
+
+        let max_extra = caps.max_mounts.unwrap() - caps.required_mounts.len();
+        for (extra, ok) in [(max_extra, true), (max_extra + 1, false)] {
+            let extra = extra_mounts(extra);
+            let mut mounts: Vec<&str> = caps.required_mounts.to_vec();
+            mounts.extend(extra.iter().map(String::as_str));
+            let spec = spec(VmBackend::Vz, &mounts);
+            assert_eq!(
+                crate::vz_job_mounts(&spec).is_ok(),
+                ok,
+                "{} mounts",
+                mounts.len()
+            );
+            assert_eq!(
+                spec.validate_for(VmBackend::Vz).is_ok(),
+                ok,
+                "{} mounts",
+                mounts.len()
+            );
+        }
+
+        for &required in caps.required_mounts {
+            let spec = spec(
+                VmBackend::Vz,
+                &caps
+                    .required_mounts
+                    .iter()
+                    .copied()
+                    .filter(|p| *p != required)
+                    .collect::<Vec<_>>(),
+            );
+            assert!(crate::vz_job_mounts(&spec).is_err());
+            assert_eq!(
+                spec.validate_for(VmBackend::Vz).unwrap_err().issues,
+                vec![Incompatibility::MissingMount {
+                    guest_path: required
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn validate_for_reports_every_issue() {
+        let extra = extra_mounts(VZ_MAX_EXTRA_MOUNTS + 3);
+        let mounts: Vec<&str> = extra.iter().map(String::as_str).collect();
+        let err = spec(VmBackend::Docker, &mounts)
+            .validate_for(VmBackend::Vz)
+            .unwrap_err();
+        assert_eq!(
+            err.issues,
+            vec![
+                Incompatibility::TooManyMounts {
+                    got: VZ_MAX_EXTRA_MOUNTS + 3,
+                    max: VZ_MAX_EXTRA_MOUNTS + 2,
+                },
+                Incompatibility::MissingMount {
+                    guest_path: VZ_JOB_IN_GUEST_PATH
+                },
+                Incompatibility::MissingMount {
+                    guest_path: VZ_JOB_OUT_GUEST_PATH
+                },
+            ]
+        );
+        assert!(
+            err.to_string()
+                .starts_with("run spec is not supported by the vz backend: 67 mounts (max 66); "),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn valid_guest_paths_pass_through_unchanged() {
+        let mounts = ["/x07/in", "/x07/out", "/x07/bundle", "/work/./data"];
+        for backend in [VmBackend::Docker, VmBackend::Vz] {
+            let spec = spec(backend, &mounts);
+            spec.validate_for(backend).unwrap();
+            let got: Vec<&Path> = spec.mounts.iter().map(|m| m.guest_path.as_path()).collect();
+            let want: Vec<&Path> = mounts.iter().map(Path::new).collect();
+            assert_eq!(got, want, "{backend}");
+        }
+
+        let args = docker_args(&spec(VmBackend::Docker, &mounts), false);
+        for target in mounts {
+            assert!(
+                args.iter().any(|a| a.contains(&format!("target={target}"))),
+                "{target}: {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn report_file_must_sit_under_the_out_bind_mount() {
+        let mut spec = spec(VmBackend::Docker, &["/x07/in", "/x07/out"]);
+        spec.report_file = Some(PathBuf::from("/x07/out/report.json"));
+        spec.validate_for(VmBackend::Docker).unwrap();
+        let args = docker_args(&spec, false);
+        assert!(args.windows(2).any(|w| w[0] == "--env"
+            && w[1] == format!("{}=/x07/out/report.json", crate::ENV_GUEST_REPORT_FILE)));
+        assert_eq!(
+            crate::guest_report::report_file_host_path(&spec),
+            Some(PathBuf::from("/tmp/x07-caps/report.json"))
+        );
+
+        for (path, reason) in [
+            ("/x07/out", "must be a file under /x07/out"),
+            (
+                "/x07/out/../in/report.json",
+                "must be a file under /x07/out",
+            ),
+            ("x07/out/report.json", "must be absolute"),
+        ] {
+            spec.report_file = Some(PathBuf::from(path));
+            assert_eq!(
+                spec.validate_for(VmBackend::Docker).unwrap_err().issues,
+                vec![Incompatibility::ReportFile {
+                    path: PathBuf::from(path),
+                    reason
+                }]
+            );
+        }
+
+        spec.report_file = Some(PathBuf::from("/x07/out/report.json"));
+        spec.mounts[1].kind = MountKind::NamedVolume {
+            name: "out".to_string(),
+        };
+        assert_eq!(
+            spec.validate_for(VmBackend::Docker).unwrap_err().issues,
+            vec![Incompatibility::ReportFile {
+                path: PathBuf::from("/x07/out/report.json"),
+                reason: "/x07/out must be a bind mount to be read back"
+            }]
+        );
+        spec.mounts.pop();
+        assert_eq!(
+            spec.validate_for(VmBackend::Docker).unwrap_err().issues,
+            vec![Incompatibility::ReportFile {
+                path: PathBuf::from("/x07/out/report.json"),
+                reason: "needs a bind mount at /x07/out"
+            }]
+        );
+    }
+
+    #[test]
+    fn named_volumes_are_container_only() {
+        for backend in [
+            VmBackend::Docker,
+            VmBackend::Podman,
+            VmBackend::Vz,
+            VmBackend::AppleContainer,
+            VmBackend::FirecrackerCtr,
+        ] {
+            let caps = backend_capabilities(backend);
+            let mut mounts: Vec<&str> = caps.required_mounts.to_vec();
+            mounts.push("/x07/scratch");
+            let mut spec = spec(backend, &mounts);
+            spec.mounts.last_mut().unwrap().kind = MountKind::NamedVolume {
+                name: "scratch".to_string(),
+            };
+
+            let res = spec.validate_for(backend);
+            if caps.named_volumes {
+                res.unwrap();
+            } else {
+                assert_eq!(
+                    res.unwrap_err().issues,
+                    vec![Incompatibility::NamedVolume {
+                        name: "scratch".to_string()
+                    }],
+                    "{backend}"
+                );
+            }
+            if backend == VmBackend::Vz {
+                assert!(crate::vz_job_mounts(&spec).is_err());
+            }
+        }
+    }
 }
@@ -0,0 +1,281 @@
+//! Guest-vs-host clock skew, recorded in [`crate::RunOutput::guest_clock_skew_ms`].
+//!
+//! The host never sees the guest clock and its own clock at the same instant,
+//! only a guest reading taken somewhere inside a host-clock window. For `vz`
+//! the reading is x07-guestd's exec start ([`GUEST_CLOCK_FILE`]) and the
+//! window runs from the helper spawn to its exit. For `docker` / `podman`
+//! with [`ENV_VM_CLOCK_PROBE`] set, the reading is `date +%s%3N` through
+//! `exec` and the window brackets that call. The skew reported is the
+//! smallest one consistent with the reading, so a slow boot or exec never
+//! shows up as skew.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::control_cmd::run_control_command;
+use crate::{JobEventKind, JobTimeline, CONTROL_CMD_LIMITS};
+
+pub const ENV_VM_CLOCK_SKEW_WARN_MS: &str = "X07_VM_CLOCK_SKEW_WARN_MS";
+/// Skew above which a run is flagged when the env var is unset.
+pub const DEFAULT_CLOCK_SKEW_WARN_MS: u64 = 5_000;
+/// Set to `1` to probe the container clock for `docker` / `podman` runs.
+pub const ENV_VM_CLOCK_PROBE: &str = "X07_VM_CLOCK_PROBE";
+
+pub const GUEST_CLOCK_SCHEMA_VERSION: &str = "x07.guest.clock@0.1.0";
+/// Written by x07-guestd into `/x07/out` when the request sets `report_clock`.
+pub const GUEST_CLOCK_FILE: &str = "guest_clock.json";
+
+/// `X07_VM_CLOCK_SKEW_WARN_MS`, or [`DEFAULT_CLOCK_SKEW_WARN_MS`].
+pub fn clock_skew_warn_ms_from_env() -> u64 {
+    std::env::var(ENV_VM_CLOCK_SKEW_WARN_MS)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_CLOCK_SKEW_WARN_MS)
+}
+
+/// Whether `X07_VM_CLOCK_PROBE` is set to `1` or `true`.
+pub fn clock_probe_from_env() -> bool {
+    std::env::var(ENV_VM_CLOCK_PROBE)
+        .map(|v| matches!(v.trim(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestClockRecord {
+    pub schema_version: String,
+    pub run_id: String,
+    pub exec_start_unix_ms: u64,
+}
+
+/// Host-clock interval known to contain the moment of a guest reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockWindow {
+    pub lo_unix_ms: u64,
+    pub hi_unix_ms: u64,
+}
+
+impl ClockWindow {
+    /// From the first `from` event to the first `to` event of `timeline`.
+    pub fn from_timeline(
+        timeline: &JobTimeline,
+        from: JobEventKind,
+        to: JobEventKind,
+    ) -> Option<Self> {
+        Some(ClockWindow {
+            lo_unix_ms: timeline.start_unix_ms.saturating_add(timeline.first(from)?),
+            hi_unix_ms: timeline.start_unix_ms.saturating_add(timeline.first(to)?),
+        })
+    }
+}
+
+/// Guest clock minus host clock, in ms: zero when `guest_unix_ms` falls
+/// inside `window`, else the signed distance to the nearer end.
+pub fn guest_clock_skew_ms(guest_unix_ms: u64, window: ClockWindow) -> i64 {
+    let signed = |a: u64, b: u64| -> i64 {
+        if a >= b {
+            i64::try_from(a - b).unwrap_or(i64::MAX)
+        } else {
+            i64::try_from(b - a).map(|d| -d).unwrap_or(i64::MIN)
+        }
+    };
+    if guest_unix_ms < window.lo_unix_ms {
+        signed(guest_unix_ms, window.lo_unix_ms)
+    } else if guest_unix_ms > window.hi_unix_ms {
+        signed(guest_unix_ms, window.hi_unix_ms)
+    } else {
+        0
+    }
+}
+
+/// Whether `skew_ms` is large enough to flag. Such runs are warned about,
+/// never failed.
+pub fn skew_exceeds(skew_ms: i64, threshold_ms: u64) -> bool {
+    skew_ms.unsigned_abs() > threshold_ms
+}
+
+/// The [`crate::RunOutput::warnings`] entry for a run whose skew is flagged.
+pub(crate) fn skew_warning(skew_ms: i64, threshold_ms: u64) -> Option<String> {
+    skew_exceeds(skew_ms, threshold_ms).then(|| {
+        format!(
+            "guest clock is {skew_ms}ms off the host clock (threshold {threshold_ms}ms); guest timestamps in this run may be unreliable"
+        )
+    })
+}
+
+/// x07-guestd's exec start from `job_out`, if it wrote one for `run_id`.
+/// A record left over from an earlier run is ignored.
+pub(crate) fn read_guest_clock(job_out: &Path, run_id: &str) -> Option<u64> {
+    let bytes = std::fs::read(job_out.join(GUEST_CLOCK_FILE)).ok()?;
+    let rec: GuestClockRecord = serde_json::from_slice(&bytes).ok()?;
+    (rec.schema_version == GUEST_CLOCK_SCHEMA_VERSION && rec.run_id == run_id)
+        .then_some(rec.exec_start_unix_ms)
+}
+
+/// Parses `date +%s%3N` output. A `date` without `%N` support prints a
+/// literal `N` and is rejected.
+pub(crate) fn parse_date_ms(stdout: &[u8]) -> Option<u64> {
+    std::str::from_utf8(stdout).ok()?.trim().parse().ok()
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis().try_into().unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+/// Retries `<bin> exec <container> date +%s%3N` in the background until one
+/// succeeds or the job ends. The container only accepts `exec` while it is
+/// running, so a very short job may finish without a reading.
+pub(crate) struct ContainerClockProbe {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Option<i64>>,
+}
+
+impl ContainerClockProbe {
+    pub(crate) fn start(bin: &'static str, container_id: &str) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let container_id = container_id.to_string();
+        let handle = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let mut cmd = Command::new(bin);
+                    cmd.args(["exec", &container_id, "date", "+%s%3N"]);
+                    let lo_unix_ms = now_unix_ms();
+                    let out = run_control_command(cmd, CONTROL_CMD_LIMITS);
+                    let hi_unix_ms = now_unix_ms();
+                    if let Ok(out) = out {
+                        if out.exit_status == 0 {
+                            let window = ClockWindow {
+                                lo_unix_ms,
+                                hi_unix_ms,
+                            };
+                            return parse_date_ms(&out.stdout)
+                                .map(|guest| guest_clock_skew_ms(guest, window));
+                        }
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                None
+            })
+        };
+        Self { stop, handle }
+    }
+
+    /// Stops retrying and returns the skew, if a probe succeeded.
+    pub(crate) fn finish(self) -> Option<i64> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().ok().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JobEvent;
+
+    const WINDOW: ClockWindow = ClockWindow {
+        lo_unix_ms: 1_000_000,
+        hi_unix_ms: 1_003_000,
+    };
+
+    #[test]
+    fn skew_is_zero_inside_the_window_and_signed_outside() {
+        assert_eq!(guest_clock_skew_ms(1_000_000, WINDOW), 0);
+        assert_eq!(guest_clock_skew_ms(1_001_500, WINDOW), 0);
+        assert_eq!(guest_clock_skew_ms(1_003_000, WINDOW), 0);
+        assert_eq!(guest_clock_skew_ms(1_010_000, WINDOW), 7_000);
+        assert_eq!(guest_clock_skew_ms(994_000, WINDOW), -6_000);
+        assert_eq!(guest_clock_skew_ms(0, WINDOW), -1_000_000);
+        assert_eq!(guest_clock_skew_ms(u64::MAX, WINDOW), i64::MAX);
+    }
+
+    #[test]
+    fn threshold_is_exclusive_and_symmetric() {
+        assert!(!skew_exceeds(0, DEFAULT_CLOCK_SKEW_WARN_MS));
+        assert!(!skew_exceeds(5_000, DEFAULT_CLOCK_SKEW_WARN_MS));
+        assert!(!skew_exceeds(-5_000, DEFAULT_CLOCK_SKEW_WARN_MS));
+        assert!(skew_exceeds(5_001, DEFAULT_CLOCK_SKEW_WARN_MS));
+        assert!(skew_exceeds(-5_001, DEFAULT_CLOCK_SKEW_WARN_MS));
+        assert!(skew_exceeds(i64::MIN, i64::MAX as u64));
+        assert!(skew_exceeds(1, 0));
+    }
+
+    #[test]
+    fn flagged_skew_becomes_a_run_warning() {
+        assert_eq!(skew_warning(-5_000, DEFAULT_CLOCK_SKEW_WARN_MS), None);
+        let warning = skew_warning(-7_250, DEFAULT_CLOCK_SKEW_WARN_MS).unwrap();
+        assert!(
+            warning.starts_with("guest clock is -7250ms off the host clock (threshold 5000ms)"),
+            "{warning}"
+        );
+    }
+
+    #[test]
+    fn window_comes_from_the_timeline() {
+        let timeline = JobTimeline {
+            start_unix_ms: 1_000_000,
+            events: vec![
+                JobEvent {
+                    kind: JobEventKind::TaskStarted,
+                    at_ms: 20,
+                },
+                JobEvent {
+                    kind: JobEventKind::Exited,
+                    at_ms: 900,
+                },
+            ],
+        };
+        assert_eq!(
+            ClockWindow::from_timeline(&timeline, JobEventKind::TaskStarted, JobEventKind::Exited),
+            Some(ClockWindow {
+                lo_unix_ms: 1_000_020,
+                hi_unix_ms: 1_000_900,
+            })
+        );
+        assert_eq!(
+            ClockWindow::from_timeline(
+                &timeline,
+                JobEventKind::CreateStarted,
+                JobEventKind::Exited
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn guest_clock_record_must_match_the_run() {
+        let dir = std::env::temp_dir().join(format!("x07_vm_clock_skew_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(read_guest_clock(&dir, "run-a"), None);
+
+        let rec = GuestClockRecord {
+            schema_version: GUEST_CLOCK_SCHEMA_VERSION.to_string(),
+            run_id: "run-a".to_string(),
+            exec_start_unix_ms: 1_234,
+        };
+        std::fs::write(
+            dir.join(GUEST_CLOCK_FILE),
+            serde_json::to_vec(&rec).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(read_guest_clock(&dir, "run-a"), Some(1_234));
+        assert_eq!(read_guest_clock(&dir, "run-b"), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn date_output_parsing() {
+        assert_eq!(parse_date_ms(b"1700000000123\n"), Some(1_700_000_000_123));
+        assert_eq!(parse_date_ms(b"1700000000N\n"), None);
+        assert_eq!(parse_date_ms(b""), None);
+    }
+}
@@ -0,0 +1,486 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::{
+    firecracker_ctr_config_from_env, parse_apple_container_json_owned,
+    parse_ctr_container_info_json_owned, parse_docker_inspect_json_owned, resolve_job_file_label,
+    state_root_id, FirecrackerCtrConfig, OwnedContainer, VmBackend, X07_LABEL_BACKEND_KEY,
+    X07_LABEL_CREATED_UNIX_MS_KEY, X07_LABEL_DEADLINE_UNIX_MS_KEY, X07_LABEL_IMAGE_DIGEST_KEY,
+    X07_LABEL_RUN_ID_KEY,
+};
+
+/// Cap on containers inspected per backend in one listing.
+const MAX_LISTED_PER_BACKEND: usize = 512;
+
+/// Which backends [`list_owned_containers`] asks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendFilter {
+    /// Every container backend usable on this host; one that fails to list
+    /// is skipped.
+    All,
+    /// Only this backend; a failed listing is an error.
+    Only(VmBackend),
+}
+
+/// An x07-owned container with its labels decoded and paired with the job
+/// state it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListedContainer {
+    pub backend: VmBackend,
+    #[serde(flatten)]
+    pub container: OwnedContainer,
+    pub run_id: Option<String>,
+    pub deadline_unix_ms: Option<u64>,
+    pub created_unix_ms: Option<u64>,
+    /// Time since `created_unix_ms` when the listing was taken.
+    pub age_ms: Option<u64>,
+    /// `now >= deadline_unix_ms`.
+    pub expired: bool,
+    pub image_digest: Option<String>,
+    /// The `io.x07.backend` label, e.g. `vm.docker`.
+    pub backend_label: Option<String>,
+    /// Job file for this container under the state root: from the job-file
+    /// label when it names this state root, else `<state_root>/<run_id>/job.json`.
+    pub job_file: Option<PathBuf>,
+    pub job_file_exists: bool,
+    /// The job's `done` marker exists.
+    pub done: bool,
+}
+
+/// Lists the x07-owned containers the selected backends know about, each
+/// paired with its job state under `state_root`. Sorted by backend, then id.
+/// `vz` runs have no container runtime and are never listed.
+pub fn list_owned_containers(
+    state_root: &Path,
+    filter: BackendFilter,
+    firecracker_cfg: Option<&FirecrackerCtrConfig>,
+) -> Result<Vec<ListedContainer>> {
+    let backends: Vec<VmBackend> = match filter {
+        BackendFilter::All => vec![
+            VmBackend::AppleContainer,
+            VmBackend::Docker,
+            VmBackend::Podman,
+            VmBackend::FirecrackerCtr,
+        ],
+        BackendFilter::Only(b) => vec![b],
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system time before unix epoch")?
+        .as_millis()
+        .try_into()
+        .unwrap_or(u64::MAX);
+
+    let mut out = Vec::new();
+    for backend in backends {
+        let owned = match list_backend_owned(backend, firecracker_cfg) {
+            Ok(v) => v,
+            Err(_) if filter == BackendFilter::All => continue,
+            Err(err) => return Err(err),
+        };
+        out.extend(reconcile_with_state_root(state_root, backend, owned, now));
+    }
+    out.sort_by(|a, b| {
+        (a.backend.to_string(), &a.container.id).cmp(&(b.backend.to_string(), &b.container.id))
+    });
+    Ok(out)
+}
+
+fn list_backend_owned(
+    backend: VmBackend,
+    firecracker_cfg: Option<&FirecrackerCtrConfig>,
+) -> Result<Vec<OwnedContainer>> {
+    match backend {
+        VmBackend::AppleContainer => list_apple_owned(),
+        VmBackend::Docker => list_docker_like_owned("docker"),
+        VmBackend::Podman => list_docker_like_owned("podman"),
+        VmBackend::FirecrackerCtr => {
+            let cfg = firecracker_cfg
+                .cloned()
+                .unwrap_or_else(firecracker_ctr_config_from_env);
+            list_ctr_owned(&cfg)
+        }
+        VmBackend::Vz => Ok(Vec::new()),
+    }
+}
+
+/// `container list --all` on macOS; empty elsewhere.
+pub(crate) fn list_apple_owned() -> Result<Vec<OwnedContainer>> {
+    if !cfg!(target_os = "macos") {
+        return Ok(Vec::new());
+    }
+
+    let mut cmd = std::process::Command::new("container");
+    cmd.args(["list", "--all", "--format", "json"]);
+    let out = crate::run_control_command(cmd, crate::CONTROL_CMD_LIST_LIMITS)?;
+    if out.timed_out || out.exit_status != 0 {
+        anyhow::bail!("container list failed (exit={})", out.exit_status);
+    }
+    let s = String::from_utf8_lossy(&out.stdout);
+    parse_apple_container_json_owned(&s).map_err(anyhow::Error::new)
+}
+
+/// `ctr containers list -q` then `containers info` per id, on Linux; empty
+/// elsewhere. Ids whose info call fails are skipped.
+pub(crate) fn list_ctr_owned(cfg: &FirecrackerCtrConfig) -> Result<Vec<OwnedContainer>> {
+    if !cfg!(target_os = "linux") {
+        return Ok(Vec::new());
+    }
+
+    let mut cmd = std::process::Command::new(&cfg.bin);
+    cmd.args(crate::ctr_base_args(cfg));
+    cmd.arg("--timeout").arg("2s");
+    cmd.args(["containers", "list", "-q"]);
+    let out = crate::run_control_command(cmd, crate::CONTROL_CMD_LIST_LIMITS)?;
+    if out.timed_out || out.exit_status != 0 {
+        anyhow::bail!("ctr containers list failed (exit={})", out.exit_status);
+    }
+
+    let ids = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .take(MAX_LISTED_PER_BACKEND)
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+
+    let mut owned = Vec::new();
+    for id in ids {
+        let mut info_cmd = std::process::Command::new(&cfg.bin);
+        info_cmd.args(crate::ctr_base_args(cfg));
+        info_cmd.arg("--timeout").arg("2s");
+        info_cmd.args(["containers", "info"]);
+        info_cmd.arg(&id);
+        let info = crate::run_control_command(info_cmd, crate::CONTROL_CMD_LIST_LIMITS)?;
+        if info.timed_out || info.exit_status != 0 {
+            continue;
+        }
+        let s = String::from_utf8_lossy(&info.stdout);
+        if let Ok(Some(c)) = parse_ctr_container_info_json_owned(&s) {
+            owned.push(c);
+        }
+    }
+    Ok(owned)
+}
+
+/// `<bin> ps -a` filtered on the ownership label, then one `inspect` call.
+fn list_docker_like_owned(bin: &str) -> Result<Vec<OwnedContainer>> {
+    let mut cmd = std::process::Command::new(bin);
+    cmd.args(["ps", "--all", "--quiet", "--no-trunc", "--filter"]);
+    cmd.arg(format!(
+        "label={}={}",
+        crate::X07_LABEL_SCHEMA_KEY,
+        crate::X07_LABEL_SCHEMA_VALUE
+    ));
+    let out = crate::run_control_command(cmd, crate::CONTROL_CMD_LIST_LIMITS)
+        .with_context(|| format!("{bin} ps"))?;
+    if out.timed_out || out.exit_status != 0 {
+        anyhow::bail!("{bin} ps failed (exit={})", out.exit_status);
+    }
+
+    let ids = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .take(MAX_LISTED_PER_BACKEND)
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut inspect = std::process::Command::new(bin);
+    inspect.arg("inspect").args(&ids);
+    let out = crate::run_control_command(inspect, crate::CONTROL_CMD_LIST_LIMITS)
+        .with_context(|| format!("{bin} inspect"))?;
+    // Containers removed between `ps` and `inspect` make it exit non-zero
+    // while still printing the rest.
+    if out.timed_out || out.stdout.is_empty() {
+        anyhow::bail!("{bin} inspect failed (exit={})", out.exit_status);
+    }
+    let s = String::from_utf8_lossy(&out.stdout);
+    parse_docker_inspect_json_owned(&s).map_err(anyhow::Error::new)
+}
+
+/// Decodes the labels of `owned` and pairs each container with its job file
+/// and `done` marker under `state_root`.
+pub(crate) fn reconcile_with_state_root(
+    state_root: &Path,
+    backend: VmBackend,
+    owned: Vec<OwnedContainer>,
+    now_unix_ms: u64,
+) -> Vec<ListedContainer> {
+    let root_id = state_root_id(state_root);
+    owned
+        .into_iter()
+        .map(|c| {
+            let label = |k: &str| c.labels.get(k).cloned();
+            let label_u64 = |k: &str| c.labels.get(k).and_then(|v| v.parse::<u64>().ok());
+
+            let run_id = label(X07_LABEL_RUN_ID_KEY);
+            let deadline_unix_ms = label_u64(X07_LABEL_DEADLINE_UNIX_MS_KEY);
+            let created_unix_ms = label_u64(X07_LABEL_CREATED_UNIX_MS_KEY);
+
+            let labelled_job_file = match (&c.state_root_id, &c.job_file) {
+                (Some(id), Some(rel)) if *id == root_id => resolve_job_file_label(state_root, rel),
+                _ => None,
+            };
+            let job_file = labelled_job_file.or_else(|| {
+                run_id
+                    .as_deref()
+                    .and_then(|r| crate::RunId::parse(r).ok())
+                    .map(|r| state_root.join(r.as_str()).join("job.json"))
+            });
+            let job_file_exists = job_file.as_deref().is_some_and(Path::is_file);
+            let done = job_file
+                .as_deref()
+                .and_then(Path::parent)
+                .is_some_and(|d| d.join("done").is_file());
+
+            ListedContainer {
+                backend,
+                run_id,
+                deadline_unix_ms,
+                created_unix_ms,
+                age_ms: created_unix_ms.map(|t| now_unix_ms.saturating_sub(t)),
+                expired: deadline_unix_ms.is_some_and(|d| now_unix_ms >= d),
+                image_digest: label(X07_LABEL_IMAGE_DIGEST_KEY),
+                backend_label: label(X07_LABEL_BACKEND_KEY),
+                job_file,
+                job_file_exists,
+                done,
+                container: c,
+            }
+        })
+        .collect()
+}
+
+/// Renders `items` as a fixed-width text table for CLI output.
+pub fn to_table_string(items: &[ListedContainer]) -> String {
+    const HEADERS: [&str; 7] = [
+        "BACKEND", "ID", "RUN_ID", "STATUS", "AGE", "DEADLINE", "JOB",
+    ];
+
+    let rows: Vec<[String; 7]> = items
+        .iter()
+        .map(|c| {
+            let dash = || "-".to_string();
+            let job = if !c.job_file_exists {
+                "missing"
+            } else if c.done {
+                "done"
+            } else {
+                "active"
+            };
+            [
+                c.backend.to_string(),
+                c.container.id.clone(),
+                c.run_id.clone().unwrap_or_else(dash),
+                c.container.status.clone().unwrap_or_else(dash),
+                c.age_ms.map(format_age).unwrap_or_else(dash),
+                match (c.deadline_unix_ms, c.expired) {
+                    (Some(_), true) => "expired".to_string(),
+                    (Some(d), false) => d.to_string(),
+                    (None, _) => dash(),
+                },
+                job.to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    let mut push_row = |cells: &[&str]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(c, w)| format!("{c:<w$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    };
+    push_row(&HEADERS);
+    for row in &rows {
+        push_row(&row.each_ref().map(String::as_str));
+    }
+    out
+}
+
+fn format_age(ms: u64) -> String {
+    let s = ms / 1000;
+    if s < 60 {
+        format!("{s}s")
+    } else if s < 3600 {
+        format!("{}m{}s", s / 60, s % 60)
+    } else {
+        format!("{}h{}m", s / 3600, (s % 3600) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_ctr_container_info_json_owned, write_job_file, VmJob};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let p = std::env::temp_dir().join(format!("{name}_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&p);
+            std::fs::create_dir_all(&p).unwrap();
+            TempDir(p)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn job(run_id: &str, backend: VmBackend) -> VmJob {
+        VmJob {
+            schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
+            run_id: crate::RunId::parse(run_id).unwrap(),
+            backend,
+            container_id: format!("x07-{run_id}"),
+            pid: None,
+            created_unix_ms: 1_000,
+            deadline_unix_ms: 61_000,
+            grace_ms: 1,
+            cleanup_ms: 1,
+            ctr: None,
+            timeline: None,
+            mount_digests: None,
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: None,
+            warned: false,
+            warned_unix_ms: None,
+        }
+    }
+
+    #[test]
+    fn reconcile_pairs_containers_with_job_files() {
+        let tmp = TempDir::new("x07_vm_container_list");
+        let root = &tmp.0;
+        let root_id = state_root_id(root);
+
+        // r1: found through the job-file label; r2: through the run id, and
+        // done; r3: no job state at all.
+        write_job_file(
+            &root.join("custom").join("job.json"),
+            &job("r1", VmBackend::Docker),
+        )
+        .unwrap();
+        write_job_file(
+            &root.join("r2").join("job.json"),
+            &job("r2", VmBackend::Docker),
+        )
+        .unwrap();
+        std::fs::write(root.join("r2").join("done"), b"done\n").unwrap();
+
+        let input = format!(
+            r#"[
+              {{ "Name": "/x07-r1", "State": {{ "Status": "running" }},
+                 "Config": {{ "Labels": {{ "io.x07.schema": "1", "io.x07.run_id": "r1",
+                   "io.x07.created_unix_ms": "1000", "io.x07.deadline_unix_ms": "61000",
+                   "io.x07.backend": "vm.docker",
+                   "io.x07.state_root_id": "{root_id}", "io.x07.job_file": "custom/job.json" }} }} }},
+              {{ "Name": "/x07-r2", "State": {{ "Status": "exited" }},
+                 "Config": {{ "Labels": {{ "io.x07.schema": "1", "io.x07.run_id": "r2",
+                   "io.x07.deadline_unix_ms": "5000" }} }} }},
+              {{ "Name": "/x07-r3", "State": {{ "Status": "running" }},
+                 "Config": {{ "Labels": {{ "io.x07.schema": "1", "io.x07.run_id": "r3" }} }} }}
+            ]"#
+        );
+        let owned = parse_docker_inspect_json_owned(&input).unwrap();
+        let listed = reconcile_with_state_root(root, VmBackend::Docker, owned, 31_000);
+
+        assert_eq!(listed.len(), 3);
+        let r1 = &listed[0];
+        assert_eq!(r1.run_id.as_deref(), Some("r1"));
+        assert_eq!(
+            r1.job_file.as_deref(),
+            Some(root.join("custom/job.json").as_path())
+        );
+        assert!(r1.job_file_exists && !r1.done && !r1.expired);
+        assert_eq!(r1.age_ms, Some(30_000));
+        assert_eq!(r1.backend_label.as_deref(), Some("vm.docker"));
+
+        let r2 = &listed[1];
+        assert_eq!(
+            r2.job_file.as_deref(),
+            Some(root.join("r2/job.json").as_path())
+        );
+        assert!(r2.job_file_exists && r2.done && r2.expired);
+        assert_eq!(r2.age_ms, None);
+
+        let r3 = &listed[2];
+        assert!(!r3.job_file_exists && !r3.done);
+        assert_eq!(r3.deadline_unix_ms, None);
+
+        let table = to_table_string(&listed);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("BACKEND"));
+        assert!(lines[1].contains("x07-r1") && lines[1].ends_with("active"));
+        assert!(lines[2].contains("expired") && lines[2].ends_with("done"));
+        assert!(lines[3].ends_with("missing"));
+
+        let json = serde_json::to_value(&listed[0]).unwrap();
+        assert_eq!(json["backend"], "docker");
+        assert_eq!(json["id"], "x07-r1");
+        assert_eq!(json["status"], "running");
+        assert_eq!(json["job_file_exists"], true);
+    }
+
+    #[test]
+    fn reconcile_ignores_job_file_labels_from_another_state_root() {
+        let tmp = TempDir::new("x07_vm_container_list_foreign");
+        let root = &tmp.0;
+        write_job_file(
+            &root.join("r5").join("job.json"),
+            &job("r5", VmBackend::FirecrackerCtr),
+        )
+        .unwrap();
+
+        let info = r#"{ "id": "x07-r5", "labels": { "io.x07.schema": "1",
+            "io.x07.run_id": "r5", "io.x07.state_root_id": "sr-elsewhere",
+            "io.x07.job_file": "other/job.json" } }"#;
+        let owned = parse_ctr_container_info_json_owned(info).unwrap().unwrap();
+        let listed = reconcile_with_state_root(root, VmBackend::FirecrackerCtr, vec![owned], 0);
+        assert_eq!(
+            listed[0].job_file.as_deref(),
+            Some(root.join("r5/job.json").as_path())
+        );
+        assert!(listed[0].job_file_exists);
+    }
+
+    #[test]
+    fn apple_listing_reconciles() {
+        let tmp = TempDir::new("x07_vm_container_list_apple");
+        let input = r#"[ { "status": "stopped", "configuration": { "id": "x07-r7",
+            "labels": { "io.x07.schema": "1", "io.x07.run_id": "r7" } } } ]"#;
+        let owned = parse_apple_container_json_owned(input).unwrap();
+        let listed = reconcile_with_state_root(&tmp.0, VmBackend::AppleContainer, owned, 0);
+        assert_eq!(listed[0].container.status.as_deref(), Some("stopped"));
+        assert!(!listed[0].job_file_exists);
+    }
+}
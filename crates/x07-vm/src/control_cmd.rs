@@ -0,0 +1,77 @@
+use std::process::Command;
+
+use anyhow::Result;
+
+use crate::RunOutput;
+
+/// Timeout and output caps for the short backend CLI calls x07-vm makes
+/// around a job (stop, kill, rm, version, image inspect, sweep listings).
+/// The job itself is capped by [`crate::LimitsSpec`] instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlCmdLimits {
+    pub wall_ms: u64,
+    pub max_stdout_bytes: usize,
+    pub max_stderr_bytes: usize,
+}
+
+impl ControlCmdLimits {
+    pub const fn with_wall_ms(self, wall_ms: u64) -> Self {
+        ControlCmdLimits { wall_ms, ..self }
+    }
+}
+
+/// Stop/kill/cleanup, version and single-image inspect calls.
+pub const CONTROL_CMD_LIMITS: ControlCmdLimits = ControlCmdLimits {
+    wall_ms: 2_000,
+    max_stdout_bytes: 64 * 1024,
+    max_stderr_bytes: 64 * 1024,
+};
+
+/// Calls whose output grows with the number of containers or image layers
+/// (sweep listings, `ctr images info`, `container image inspect`).
+pub const CONTROL_CMD_LIST_LIMITS: ControlCmdLimits = ControlCmdLimits {
+    wall_ms: 2_000,
+    max_stdout_bytes: 256 * 1024,
+    max_stderr_bytes: 256 * 1024,
+};
+
+pub(crate) fn run_control_command(cmd: Command, limits: ControlCmdLimits) -> Result<RunOutput> {
+    crate::run_command_capped(
+        cmd,
+        limits.wall_ms,
+        limits.max_stdout_bytes,
+        limits.max_stderr_bytes,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn stderr_of(len: usize, limits: ControlCmdLimits) -> RunOutput {
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            &format!("head -c {len} /dev/zero | tr '\\0' e >&2; exit 1"),
+        ]);
+        run_control_command(cmd, limits.with_wall_ms(5_000)).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verbose_control_command_errors_are_kept_up_to_the_cap() {
+        for limits in [CONTROL_CMD_LIMITS, CONTROL_CMD_LIST_LIMITS] {
+            let cap = limits.max_stderr_bytes;
+
+            let out = stderr_of(cap, limits);
+            assert_eq!(out.exit_status, 1);
+            assert_eq!(out.stderr.len(), cap);
+            assert!(!out.stderr_truncated);
+
+            let out = stderr_of(cap + 4096, limits);
+            assert_eq!(out.stderr.len(), cap);
+            assert!(out.stderr_truncated);
+        }
+    }
+}
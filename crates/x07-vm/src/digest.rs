@@ -21,6 +21,27 @@ struct VzGuestBundleManifest {
     linux: VzGuestBundleLinux,
 }
 
+/// Returned (inside `anyhow::Error`) when a guest image or bundle does not hash
+/// to the expected digest; callers can `downcast_ref::<DigestMismatch>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestMismatch {
+    pub backend: VmBackend,
+    pub expected: String,
+    pub got: String,
+}
+
+impl std::fmt::Display for DigestMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "guest digest mismatch for {}: expected {:?}, got {:?}",
+            self.backend, self.expected, self.got
+        )
+    }
+}
+
+impl std::error::Error for DigestMismatch {}
+
 pub fn resolve_vm_guest_digest(
     backend: VmBackend,
     image_or_bundle: &str,
@@ -47,10 +68,25 @@ pub fn verify_vm_guest_digest(
     firecracker_cfg: Option<&FirecrackerCtrConfig>,
 ) -> Result<()> {
     let got = resolve_vm_guest_digest(backend, image_or_bundle, firecracker_cfg)?;
+    check_digest(backend, expected_digest, got)
+}
+
+pub(crate) fn verify_vz_guest_bundle_digest(
+    bundle_dir: &Path,
+    expected_digest: &str,
+) -> Result<()> {
+    let got = compute_vz_guest_bundle_digest(bundle_dir)?;
+    check_digest(VmBackend::Vz, expected_digest, got)
+}
+
+pub(crate) fn check_digest(backend: VmBackend, expected_digest: &str, got: String) -> Result<()> {
     if got != expected_digest {
-        anyhow::bail!(
-            "guest digest mismatch for {backend}: expected {expected_digest:?}, got {got:?}"
-        );
+        return Err(DigestMismatch {
+            backend,
+            expected: expected_digest.to_string(),
+            got,
+        }
+        .into());
     }
     Ok(())
 }
@@ -122,7 +158,7 @@ fn docker_like_repo_digest(bin: &str, image: &str) -> Result<String> {
         "{{json .RepoDigests}}",
         image,
     ]);
-    let out = crate::run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let out = crate::run_control_command(cmd, crate::CONTROL_CMD_LIMITS)
         .with_context(|| format!("{bin} image inspect RepoDigests {image}"))?;
     if out.timed_out || out.exit_status != 0 {
         anyhow::bail!("inspect failed");
@@ -142,7 +178,7 @@ fn docker_like_repo_digest(bin: &str, image: &str) -> Result<String> {
 fn docker_like_image_id(bin: &str, image: &str) -> Result<String> {
     let mut cmd = std::process::Command::new(bin);
     cmd.args(["image", "inspect", "--format", "{{.Id}}", image]);
-    let out = crate::run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let out = crate::run_control_command(cmd, crate::CONTROL_CMD_LIMITS)
         .with_context(|| format!("{bin} image inspect Id {image}"))?;
     if out.timed_out {
         anyhow::bail!("{bin} image inspect timed out");
@@ -155,7 +191,10 @@ fn docker_like_image_id(bin: &str, image: &str) -> Result<String> {
     normalize_sha256_digest(s.trim())
 }
 
-fn resolve_ctr_image_target_digest(cfg: &FirecrackerCtrConfig, image: &str) -> Result<String> {
+pub(crate) fn resolve_ctr_image_target_digest(
+    cfg: &FirecrackerCtrConfig,
+    image: &str,
+) -> Result<String> {
     let mut cmd = std::process::Command::new(&cfg.bin);
     cmd.args([
         "--address",
@@ -168,7 +207,7 @@ fn resolve_ctr_image_target_digest(cfg: &FirecrackerCtrConfig, image: &str) -> R
         "info",
         image,
     ]);
-    let out = crate::run_command_capped(cmd, 2_000, 256 * 1024, 256 * 1024)
+    let out = crate::run_control_command(cmd, crate::CONTROL_CMD_LIST_LIMITS)
         .with_context(|| format!("firecracker-ctr images info {image}"))?;
     if out.timed_out {
         anyhow::bail!("firecracker-ctr images info timed out");
@@ -221,7 +260,7 @@ fn resolve_apple_container_image_digest(image: &str) -> Result<String> {
         let mut cmd = std::process::Command::new("container");
         cmd.args([subcmd, "inspect", image]);
 
-        let out = crate::run_command_capped(cmd, 2_000, 256 * 1024, 256 * 1024)
+        let out = crate::run_control_command(cmd, crate::CONTROL_CMD_LIST_LIMITS)
             .with_context(|| format!("container {subcmd} inspect {image}"))?;
 
         if out.timed_out {
@@ -341,7 +380,7 @@ fn normalize_sha256_digest(raw: &str) -> Result<String> {
     Ok(s.to_string())
 }
 
-fn find_first_sha256_digest_in_text(s: &str) -> Option<String> {
+pub(crate) fn find_first_sha256_digest_in_text(s: &str) -> Option<String> {
     let needle = "sha256:";
     let start = s.find(needle)?;
     let rest = &s[start..];
@@ -363,6 +402,70 @@ fn find_first_sha256_digest_in_text(s: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
+
+    struct TempBundle {
+        path: PathBuf,
+    }
+
+    impl TempBundle {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("x07_vm_digest_{name}_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            let manifest = serde_json::json!({
+                "schema_version": "x07.vz.guest.bundle@0.1.0",
+                "linux": { "kernel": "vmlinux", "rootfs": "rootfs.img", "cmdline": "cmdline.txt" },
+            });
+            std::fs::write(
+                path.join("manifest.json"),
+                serde_json::to_vec(&manifest).unwrap(),
+            )
+            .unwrap();
+            std::fs::write(path.join("vmlinux"), b"kernel-bytes").unwrap();
+            std::fs::write(path.join("rootfs.img"), b"rootfs-bytes").unwrap();
+            std::fs::write(path.join("cmdline.txt"), b"console=hvc0").unwrap();
+            Self { path }
+        }
+    }
+
+    impl Drop for TempBundle {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    #[test]
+    fn vz_bundle_digest_changes_when_a_bundle_file_changes() {
+        let bundle = TempBundle::new("modified");
+        let before = compute_vz_guest_bundle_digest(&bundle.path).unwrap();
+        assert!(looks_like_sha256_digest(&before));
+        assert_eq!(
+            compute_vz_guest_bundle_digest(&bundle.path).unwrap(),
+            before
+        );
+        verify_vz_guest_bundle_digest(&bundle.path, &before).unwrap();
+
+        std::fs::write(bundle.path.join("rootfs.img"), b"rootfs-bytes-tampered").unwrap();
+        let after = compute_vz_guest_bundle_digest(&bundle.path).unwrap();
+        assert_ne!(after, before);
+
+        let err = verify_vz_guest_bundle_digest(&bundle.path, &before).unwrap_err();
+        let mismatch = err
+            .downcast_ref::<DigestMismatch>()
+            .expect("DigestMismatch error");
+        assert_eq!(mismatch.backend, VmBackend::Vz);
+        assert_eq!(mismatch.expected, before);
+        assert_eq!(mismatch.got, after);
+    }
+
+    #[test]
+    fn vz_bundle_digest_requires_referenced_files() {
+        let bundle = TempBundle::new("missing");
+        std::fs::remove_file(bundle.path.join("cmdline.txt")).unwrap();
+        assert!(compute_vz_guest_bundle_digest(&bundle.path).is_err());
+    }
 
     #[test]
     fn normalize_sha256_digest_rejects_non_hex() {
@@ -0,0 +1,563 @@
+//! Dry runs: the exact backend invocation a [`RunSpec`] turns into, built
+//! without running it.
+//!
+//! [`plan_run`] (or [`RunSpec::dry_run`]) goes through the same validation,
+//! label construction and mount translation as a real run, then returns the
+//! argv in a [`RunPlan`] instead of spawning it. For `vz` the plan also holds
+//! the guest request that would have been written to `/x07/in/request.json`.
+//! Env values whose names look secret are replaced with [`REDACTED`].
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::{
+    apple_container_command, docker_like_command, firecracker_ctr_command,
+    named_volume_create_command, named_volumes, run_vm_job, vz_helper_plan, FirecrackerCtrConfig,
+    GuestRequestJson, JobTimeline, RunOutput, RunSpec, VmBackend, VmJobRunParams,
+};
+
+/// Stands in for a redacted env value.
+pub const REDACTED: &str = "<redacted>";
+
+/// Env names containing any of these (case-insensitively) are redacted.
+const SECRET_NAME_PARTS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "PRIVATE_KEY",
+    "API_KEY",
+    "ACCESS_KEY",
+    "AUTH",
+];
+
+/// `value`, or [`REDACTED`] when `name` looks like it holds a secret.
+pub fn redact_env_value<'a>(name: &str, value: &'a str) -> &'a str {
+    let upper = name.to_ascii_uppercase();
+    if SECRET_NAME_PARTS.iter().any(|p| upper.contains(p)) {
+        REDACTED
+    } else {
+        value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunPlan {
+    pub backend: VmBackend,
+    pub container_id: String,
+    /// Commands run before `argv`: `volume create` for each named volume
+    /// (`docker` / `podman`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub setup_argvs: Vec<Vec<String>>,
+    /// Program followed by its arguments.
+    pub argv: Vec<String>,
+    /// `vz` only: the `request.json` x07-guestd would read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guest_request: Option<serde_json::Value>,
+}
+
+/// Plans `spec` as [`run_vm_job`] would run it, without running anything.
+pub fn plan_run(spec: &RunSpec, params: VmJobRunParams<'_>) -> Result<RunPlan> {
+    let mut spec = spec.clone();
+    spec.dry_run = true;
+    run_vm_job(&spec, params)?
+        .dry_run
+        .context("internal error: dry run returned no plan")
+}
+
+/// The invocation for `spec` on its backend. `interactive` matches a
+/// passthrough run, which forwards stdin.
+pub(crate) fn backend_run_plan(
+    spec: &RunSpec,
+    container_id: &str,
+    labels: &BTreeMap<String, String>,
+    firecracker_cfg: Option<&FirecrackerCtrConfig>,
+    state_dir: &Path,
+    interactive: bool,
+) -> Result<RunPlan> {
+    let mut setup = Vec::new();
+    let mut guest_request = None;
+    let cmd = match spec.backend {
+        VmBackend::Docker | VmBackend::Podman => {
+            let (bin, annotations) = if spec.backend == VmBackend::Docker {
+                ("docker", false)
+            } else {
+                ("podman", true)
+            };
+            for name in named_volumes(spec) {
+                setup.push(named_volume_create_command(bin, name, labels)?);
+            }
+            docker_like_command(bin, spec, container_id, labels, annotations, interactive)?
+        }
+        VmBackend::AppleContainer => apple_container_command(spec, container_id, labels)?,
+        VmBackend::FirecrackerCtr => {
+            let cfg = firecracker_cfg.context("internal error: firecracker cfg missing")?;
+            firecracker_ctr_command(spec, cfg, container_id, labels)?
+        }
+        VmBackend::Vz => {
+            let plan = vz_helper_plan(spec, state_dir)?;
+            guest_request = Some(plan.request);
+            plan.cmd
+        }
+    };
+    redacted_plan(spec, container_id, &setup, &cmd, guest_request.as_ref())
+}
+
+fn redacted_plan(
+    spec: &RunSpec,
+    container_id: &str,
+    setup: &[Command],
+    cmd: &Command,
+    guest_request: Option<&GuestRequestJson>,
+) -> Result<RunPlan> {
+    let guest_request = match guest_request {
+        Some(req) => {
+            let mut v = serde_json::to_value(req).context("serialize guest request")?;
+            if let Some(env) = v.pointer_mut("/exec/env").and_then(|e| e.as_object_mut()) {
+                for (k, val) in env.iter_mut() {
+                    if let Some(s) = val.as_str() {
+                        *val = serde_json::Value::String(redact_env_value(k, s).to_string());
+                    }
+                }
+            }
+            Some(v)
+        }
+        None => None,
+    };
+    Ok(RunPlan {
+        backend: spec.backend,
+        container_id: container_id.to_string(),
+        setup_argvs: setup.iter().map(command_argv).collect(),
+        argv: redact_env_args(spec, command_argv(cmd)),
+        guest_request,
+    })
+}
+
+fn command_argv(cmd: &Command) -> Vec<String> {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Redacts the `--env NAME=value` pairs built from `spec.env`. The workload's
+/// own argv is left alone, even where it happens to contain `--env`.
+fn redact_env_args(spec: &RunSpec, mut argv: Vec<String>) -> Vec<String> {
+    let secrets: BTreeMap<String, String> = spec
+        .env
+        .iter()
+        .filter(|(k, v)| redact_env_value(k, v) == REDACTED)
+        .map(|(k, v)| (format!("{k}={v}"), format!("{k}={REDACTED}")))
+        .collect();
+    let workload_start = argv.len().saturating_sub(spec.argv.len());
+    for i in 1..workload_start {
+        if argv[i - 1] == "--env" {
+            if let Some(redacted) = secrets.get(&argv[i]) {
+                argv[i] = redacted.clone();
+            }
+        }
+    }
+    argv
+}
+
+pub(crate) fn dry_run_output(plan: RunPlan) -> RunOutput {
+    RunOutput {
+        exit_status: 0,
+        exit_signal: None,
+        timed_out: false,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        stdout_truncated: false,
+        stderr_truncated: false,
+        guest_report: None,
+        timeline: JobTimeline::default(),
+        mount_digests: None,
+        persisted_output: None,
+        guest_clock_skew_ms: None,
+        dry_run: Some(plan),
+        warnings: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        vz_helper_plan_with, LimitsSpec, MountKind, MountSpec, NetworkMode, RunId,
+        X07_OS_RUNNER_REPORT_SCHEMA_VERSION,
+    };
+
+    const CONTAINER_ID: &str = "x07-golden-run";
+
+    fn spec(backend: VmBackend) -> RunSpec {
+        let bind = |host: &str, guest: &str, readonly: bool| MountSpec {
+            host_path: PathBuf::from(host),
+            guest_path: PathBuf::from(guest),
+            readonly,
+            kind: MountKind::Bind,
+        };
+        RunSpec {
+            run_id: RunId::parse("golden-run").unwrap(),
+            backend,
+            image: "x07-guest:golden".to_string(),
+            image_digest: None,
+            argv: vec![
+                "/usr/local/bin/x07-os-runner".to_string(),
+                "--env".to_string(),
+                "API_TOKEN=s3cr3t".to_string(),
+            ],
+            env: BTreeMap::from([
+                ("API_TOKEN".to_string(), "s3cr3t".to_string()),
+                ("LANG_MODE".to_string(), "plain".to_string()),
+            ]),
+            mounts: vec![
+                bind("/host/in", "/x07/in", true),
+                bind("/host/out", "/x07/out", false),
+            ],
+            workdir: Some(PathBuf::from("/x07/out")),
+            limits: LimitsSpec {
+                wall_ms: 5_000,
+                grace_ms: 500,
+                cleanup_ms: 1_000,
+                mem_bytes: Some(512 * 1024 * 1024),
+                shm_size_bytes: None,
+                vcpus: Some(2),
+                max_stdout_bytes: 1_024,
+                max_stderr_bytes: 1_024,
+                network: NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+            },
+            digest_mounts: false,
+            dry_run: true,
+            report_file: None,
+        }
+    }
+
+    fn labels() -> BTreeMap<String, String> {
+        BTreeMap::from([("io.x07.run_id".to_string(), "golden-run".to_string())])
+    }
+
+    fn plan(backend: VmBackend, cfg: Option<&FirecrackerCtrConfig>) -> RunPlan {
+        backend_run_plan(
+            &spec(backend),
+            CONTAINER_ID,
+            &labels(),
+            cfg,
+            Path::new("/state/golden-run"),
+            false,
+        )
+        .unwrap()
+    }
+
+    /// Shared tail of every container backend's argv.
+    fn env_mounts_and_workload(mounts: [&str; 2]) -> Vec<&str> {
+        let mut out = vec![
+            "--env",
+            "API_TOKEN=<redacted>",
+            "--env",
+            "LANG_MODE=plain",
+            "--mount",
+            mounts[0],
+            "--mount",
+            mounts[1],
+            "x07-guest:golden",
+        ];
+        out.extend(["/usr/local/bin/x07-os-runner", "--env", "API_TOKEN=s3cr3t"]);
+        out
+    }
+
+    const OCI_MOUNTS: [&str; 2] = [
+        "type=bind,source=/host/in,target=/x07/in,readonly",
+        "type=bind,source=/host/out,target=/x07/out",
+    ];
+
+    #[test]
+    fn secret_env_names_are_redacted() {
+        for name in [
+            "API_TOKEN",
+            "db_password",
+            "AWS_SECRET_ACCESS_KEY",
+            "GH_AUTH",
+        ] {
+            assert_eq!(redact_env_value(name, "v"), REDACTED, "{name}");
+        }
+        for name in ["PATH", "HOME", "LANG_MODE"] {
+            assert_eq!(redact_env_value(name, "v"), "v", "{name}");
+        }
+    }
+
+    #[test]
+    fn plan_run_neither_runs_nor_writes_job_state() {
+        let root = std::env::temp_dir().join(format!("x07_vm_dry_run_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let state_dir = root.join("jobs").join("golden-run");
+        let params = VmJobRunParams {
+            state_root: &root,
+            state_dir: &state_dir,
+            reaper_bin: Path::new("/nonexistent/x07-vm-reaper"),
+            created_unix_ms: 1_700_000_000_000,
+            deadline_unix_ms: 1_700_000_060_000,
+            firecracker_cfg: None,
+            spawn_reaper: true,
+        };
+        let plan = plan_run(&spec(VmBackend::Docker), params).unwrap();
+        assert_eq!(plan.argv[..2], ["docker", "run"]);
+        assert!(plan.argv.contains(&"API_TOKEN=<redacted>".to_string()));
+        assert!(!state_dir.exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn docker_golden() {
+        let plan = plan(VmBackend::Docker, None);
+        let mut want = vec![
+            "docker",
+            "run",
+            "--rm",
+            "--name",
+            CONTAINER_ID,
+            "--label",
+            "io.x07.run_id=golden-run",
+            "--memory",
+            "512m",
+            "--cpus",
+            "2",
+            "--network",
+            "none",
+            "--workdir",
+            "/x07/out",
+        ];
+        want.extend(env_mounts_and_workload(OCI_MOUNTS));
+        assert_eq!(plan.argv, want);
+        assert!(plan.setup_argvs.is_empty());
+        assert_eq!(plan.guest_request, None);
+    }
+
+    #[test]
+    fn podman_golden() {
+        let mut spec = spec(VmBackend::Podman);
+        spec.mounts.push(MountSpec {
+            host_path: PathBuf::new(),
+            guest_path: PathBuf::from("/x07/scratch"),
+            readonly: false,
+            kind: MountKind::NamedVolume {
+                name: "x07-scratch".to_string(),
+            },
+        });
+        let plan = backend_run_plan(
+            &spec,
+            CONTAINER_ID,
+            &labels(),
+            None,
+            Path::new("/state/golden-run"),
+            true,
+        )
+        .unwrap();
+        let want = [
+            "podman",
+            "run",
+            "--rm",
+            "--name",
+            CONTAINER_ID,
+            "-i",
+            "--label",
+            "io.x07.run_id=golden-run",
+            "--annotation",
+            "io.x07.run_id=golden-run",
+            "--memory",
+            "512m",
+            "--cpus",
+            "2",
+            "--network",
+            "none",
+            "--workdir",
+            "/x07/out",
+            "--env",
+            "API_TOKEN=<redacted>",
+            "--env",
+            "LANG_MODE=plain",
+            "--mount",
+            OCI_MOUNTS[0],
+            "--mount",
+            OCI_MOUNTS[1],
+            "--mount",
+            "type=volume,source=x07-scratch,target=/x07/scratch",
+            "x07-guest:golden",
+            "/usr/local/bin/x07-os-runner",
+            "--env",
+            "API_TOKEN=s3cr3t",
+        ];
+        assert_eq!(plan.argv, want);
+        assert_eq!(
+            plan.setup_argvs,
+            [[
+                "podman",
+                "volume",
+                "create",
+                "--ignore",
+                "--label",
+                "io.x07.run_id=golden-run",
+                "x07-scratch",
+            ]]
+        );
+    }
+
+    #[test]
+    fn apple_container_golden() {
+        let plan = plan(VmBackend::AppleContainer, None);
+        let mut want = vec![
+            "container",
+            "run",
+            "--name",
+            CONTAINER_ID,
+            "--rm",
+            "--label",
+            "io.x07.run_id=golden-run",
+            "--memory",
+            "536870912",
+            "--cpus",
+            "2",
+            "--network",
+            "none",
+            "--workdir",
+            "/x07/out",
+        ];
+        want.extend(env_mounts_and_workload(OCI_MOUNTS));
+        assert_eq!(plan.argv, want);
+    }
+
+    #[test]
+    fn firecracker_ctr_golden() {
+        let cfg = FirecrackerCtrConfig {
+            bin: "ctr".into(),
+            address: "/run/containerd/containerd.sock".to_string(),
+            namespace: "x07".to_string(),
+            runtime: "aws.firecracker".to_string(),
+            snapshotter: "devmapper".to_string(),
+        };
+        let plan = plan(VmBackend::FirecrackerCtr, Some(&cfg));
+        let want = [
+            "ctr",
+            "--address",
+            "/run/containerd/containerd.sock",
+            "--namespace",
+            "x07",
+            "run",
+            "--rm",
+            "--runtime",
+            "aws.firecracker",
+            "--snapshotter",
+            "devmapper",
+            "--label",
+            "io.x07.run_id=golden-run",
+            "--annotation",
+            "io.x07.run_id=golden-run",
+            "--memory-limit",
+            "536870912",
+            "--cpus",
+            "2",
+            "--cwd",
+            "/x07/out",
+            "--env",
+            "API_TOKEN=<redacted>",
+            "--env",
+            "LANG_MODE=plain",
+            "--mount",
+            "type=bind,src=/host/in,dst=/x07/in,options=rbind:ro",
+            "--mount",
+            "type=bind,src=/host/out,dst=/x07/out,options=rbind",
+            "x07-guest:golden",
+            CONTAINER_ID,
+            "/usr/local/bin/x07-os-runner",
+            "--env",
+            "API_TOKEN=s3cr3t",
+        ];
+        assert_eq!(plan.argv, want);
+    }
+
+    #[test]
+    fn vz_golden() {
+        let spec = spec(VmBackend::Vz);
+        let vz = vz_helper_plan_with(
+            &spec,
+            Path::new("/state/golden-run"),
+            Path::new("/opt/x07/guest.bundle"),
+            Path::new("/opt/x07/x07-vz-helper"),
+        )
+        .unwrap();
+        let plan = redacted_plan(&spec, CONTAINER_ID, &[], &vz.cmd, Some(&vz.request)).unwrap();
+        assert_eq!(
+            plan.argv,
+            [
+                "/opt/x07/x07-vz-helper",
+                "run",
+                "--run-id",
+                "golden-run",
+                "--bundle",
+                "/opt/x07/guest.bundle",
+                "--state-dir",
+                "/state/golden-run",
+                "--mem-bytes",
+                "536870912",
+                "--cpus",
+                "2",
+                "--net",
+                "none",
+                "--wall-ms",
+                "5000",
+                "--grace-ms",
+                "500",
+                "--share",
+                "x07in",
+                "/host/in",
+                "ro",
+                "--share",
+                "x07out",
+                "/host/out",
+                "rw",
+            ]
+        );
+        assert_eq!(vz.job_in, Path::new("/host/in"));
+        assert_eq!(
+            plan.guest_request,
+            Some(serde_json::json!({
+                "schema_version": "x07.guest.request@1",
+                "run_id": "golden-run",
+                "exec": {
+                    "path": "/usr/local/bin/x07-os-runner",
+                    "argv": ["/usr/local/bin/x07-os-runner", "--env", "API_TOKEN=s3cr3t"],
+                    "env": {
+                        "API_TOKEN": REDACTED,
+                        "HOME": "/root",
+                        "LANG_MODE": "plain",
+                        "LC_ALL": "C",
+                        "PATH": "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin",
+                        "X07_GUESTD_WORKDIR": "/x07/out",
+                    },
+                },
+                "expect": {
+                    "stdout_schema_version": X07_OS_RUNNER_REPORT_SCHEMA_VERSION,
+                    "stdout_is_single_json_object": true,
+                },
+                "limits": {
+                    "wall_ms": 5_000,
+                    "stdout_max_bytes": 1_024,
+                    "stderr_max_bytes": 1_024,
+                    "workload_mem_bytes": 436_207_616,
+                    "workload_pids_max": 1_024,
+                },
+                "report_clock": true,
+            }))
+        );
+    }
+}
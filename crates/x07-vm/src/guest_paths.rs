@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::caps::Incompatibility;
+use crate::{
+    normalize_abs_path, MountSpec, VmBackend, VZ_JOB_IN_GUEST_PATH, VZ_JOB_OUT_GUEST_PATH,
+};
+
+/// Comma-separated absolute guest paths no mount may target. Replaces
+/// [`DEFAULT_GUEST_PATH_DENYLIST`] when set and non-empty.
+pub const ENV_VM_GUEST_PATH_DENYLIST: &str = "X07_VM_GUEST_PATH_DENYLIST";
+pub const DEFAULT_GUEST_PATH_DENYLIST: &[&str] = &["/", "/proc", "/sys", "/dev"];
+
+/// `X07_VM_GUEST_PATH_DENYLIST`, or [`DEFAULT_GUEST_PATH_DENYLIST`].
+/// Entries that are not absolute are ignored.
+pub fn guest_path_denylist_from_env() -> Vec<PathBuf> {
+    let from_env: Vec<PathBuf> = std::env::var(ENV_VM_GUEST_PATH_DENYLIST)
+        .ok()
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter_map(|p| normalize_abs_path(Path::new(p)).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    if from_env.is_empty() {
+        DEFAULT_GUEST_PATH_DENYLIST
+            .iter()
+            .map(PathBuf::from)
+            .collect()
+    } else {
+        from_env
+    }
+}
+
+/// Checks every mount's `guest_path` after [`normalize_abs_path`]: it must be
+/// absolute, must not land on a `denylist` entry and must not collide with
+/// another mount's target. On vz, `/x07/in` and `/x07/out` are shared as
+/// whole directories, so nothing else may be mounted inside them.
+pub(crate) fn guest_path_issues(
+    mounts: &[MountSpec],
+    backend: VmBackend,
+    denylist: &[PathBuf],
+) -> Vec<Incompatibility> {
+    let mut issues = Vec::new();
+    let mut seen: BTreeMap<PathBuf, usize> = BTreeMap::new();
+    for (index, m) in mounts.iter().enumerate() {
+        let Ok(target) = normalize_abs_path(&m.guest_path) else {
+            issues.push(Incompatibility::GuestPathNotAbsolute {
+                index,
+                guest_path: m.guest_path.clone(),
+            });
+            continue;
+        };
+        if denylist.contains(&target) {
+            issues.push(Incompatibility::GuestPathDenied {
+                index,
+                guest_path: m.guest_path.clone(),
+                target: target.clone(),
+            });
+        }
+        if backend == VmBackend::Vz {
+            for reserved in [VZ_JOB_IN_GUEST_PATH, VZ_JOB_OUT_GUEST_PATH] {
+                if target != Path::new(reserved) && target.starts_with(reserved) {
+                    issues.push(Incompatibility::VzReservedGuestPath {
+                        index,
+                        guest_path: m.guest_path.clone(),
+                        reserved,
+                    });
+                }
+            }
+        }
+        if let Some(&first) = seen.get(&target) {
+            issues.push(Incompatibility::DuplicateGuestPath {
+                first,
+                first_guest_path: mounts[first].guest_path.clone(),
+                second: index,
+                second_guest_path: m.guest_path.clone(),
+                target,
+            });
+        } else {
+            seen.insert(target, index);
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MountKind;
+
+    fn mounts(paths: &[&str]) -> Vec<MountSpec> {
+        paths
+            .iter()
+            .map(|p| MountSpec {
+                host_path: PathBuf::from("/tmp/x07-guest-paths"),
+                guest_path: PathBuf::from(p),
+                readonly: true,
+                kind: MountKind::Bind,
+            })
+            .collect()
+    }
+
+    fn default_denylist() -> Vec<PathBuf> {
+        DEFAULT_GUEST_PATH_DENYLIST
+            .iter()
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    #[test]
+    fn rejection_matrix() {
+        let denylist = default_denylist();
+        let cases: &[(&str, Incompatibility)] = &[
+            (
+                "data",
+                Incompatibility::GuestPathNotAbsolute {
+                    index: 0,
+                    guest_path: PathBuf::from("data"),
+                },
+            ),
+            (
+                "../x07/in",
+                Incompatibility::GuestPathNotAbsolute {
+                    index: 0,
+                    guest_path: PathBuf::from("../x07/in"),
+                },
+            ),
+            (
+                "/",
+                Incompatibility::GuestPathDenied {
+                    index: 0,
+                    guest_path: PathBuf::from("/"),
+                    target: PathBuf::from("/"),
+                },
+            ),
+            (
+                "/x07/../..",
+                Incompatibility::GuestPathDenied {
+                    index: 0,
+                    guest_path: PathBuf::from("/x07/../.."),
+                    target: PathBuf::from("/"),
+                },
+            ),
+            (
+                "/x07/../proc",
+                Incompatibility::GuestPathDenied {
+                    index: 0,
+                    guest_path: PathBuf::from("/x07/../proc"),
+                    target: PathBuf::from("/proc"),
+                },
+            ),
+            (
+                "/sys/.",
+                Incompatibility::GuestPathDenied {
+                    index: 0,
+                    guest_path: PathBuf::from("/sys/."),
+                    target: PathBuf::from("/sys"),
+                },
+            ),
+            (
+                "/dev",
+                Incompatibility::GuestPathDenied {
+                    index: 0,
+                    guest_path: PathBuf::from("/dev"),
+                    target: PathBuf::from("/dev"),
+                },
+            ),
+        ];
+        for (path, want) in cases {
+            assert_eq!(
+                guest_path_issues(&mounts(&[path]), VmBackend::Docker, &denylist),
+                vec![want.clone()],
+                "{path}"
+            );
+        }
+
+        // Only exact targets are denied.
+        assert!(
+            guest_path_issues(&mounts(&["/dev/shm/x07"]), VmBackend::Docker, &denylist).is_empty()
+        );
+        // The denylist is configurable.
+        assert!(guest_path_issues(&mounts(&["/proc"]), VmBackend::Docker, &[]).is_empty());
+        assert_eq!(
+            guest_path_issues(
+                &mounts(&["/srv/./data"]),
+                VmBackend::Docker,
+                &[PathBuf::from("/srv/data")]
+            )
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn duplicate_targets_name_both_entries() {
+        let issues = guest_path_issues(
+            &mounts(&["/data", "/x07/in", "/x07/../data/."]),
+            VmBackend::Docker,
+            &default_denylist(),
+        );
+        assert_eq!(
+            issues,
+            vec![Incompatibility::DuplicateGuestPath {
+                first: 0,
+                first_guest_path: PathBuf::from("/data"),
+                second: 2,
+                second_guest_path: PathBuf::from("/x07/../data/."),
+                target: PathBuf::from("/data"),
+            }]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "mounts[0] (/data) and mounts[2] (/x07/../data/.) both target /data"
+        );
+    }
+
+    #[test]
+    fn vz_reserves_job_dirs() {
+        let denylist = default_denylist();
+        let nested = mounts(&["/x07/in", "/x07/out", "/x07/in/extra", "/x07/out/../out/x"]);
+        assert_eq!(
+            guest_path_issues(&nested, VmBackend::Vz, &denylist),
+            vec![
+                Incompatibility::VzReservedGuestPath {
+                    index: 2,
+                    guest_path: PathBuf::from("/x07/in/extra"),
+                    reserved: VZ_JOB_IN_GUEST_PATH,
+                },
+                Incompatibility::VzReservedGuestPath {
+                    index: 3,
+                    guest_path: PathBuf::from("/x07/out/../out/x"),
+                    reserved: VZ_JOB_OUT_GUEST_PATH,
+                },
+            ]
+        );
+        // Other backends mount nested targets on top of each other.
+        assert!(guest_path_issues(&nested, VmBackend::Docker, &denylist).is_empty());
+    }
+}
@@ -0,0 +1,507 @@
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use x07_contracts::X07_OS_RUNNER_REPORT_SCHEMA_VERSION;
+
+use crate::{normalize_abs_path, MountKind, RunSpec, VZ_JOB_OUT_GUEST_PATH};
+
+/// Hard ceiling on guest stdout accepted as a report, independent of the
+/// per-job `max_stdout_bytes` capture limit.
+pub const GUEST_STDOUT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+pub const ENV_VM_MAX_REPORT_FILE_BYTES: &str = "X07_VM_MAX_REPORT_FILE_BYTES";
+/// Cap on a [`RunSpec::report_file`] read back by the host when the env var
+/// is unset. Above [`GUEST_STDOUT_MAX_BYTES`], since the file exists for
+/// reports that do not fit through stdout.
+pub const DEFAULT_MAX_REPORT_FILE_BYTES: u64 = 512 * 1024 * 1024;
+/// Tells docker/podman workloads where to write [`RunSpec::report_file`];
+/// on `vz` x07-guestd redirects the workload's stdout there instead.
+pub const ENV_GUEST_REPORT_FILE: &str = "X07_GUEST_REPORT_FILE";
+
+/// `X07_VM_MAX_REPORT_FILE_BYTES`, or [`DEFAULT_MAX_REPORT_FILE_BYTES`].
+pub fn max_report_file_bytes_from_env() -> u64 {
+    std::env::var(ENV_VM_MAX_REPORT_FILE_BYTES)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&v| v != 0)
+        .unwrap_or(DEFAULT_MAX_REPORT_FILE_BYTES)
+}
+
+/// Guest stdout that parsed as exactly one `x07-os-runner` report object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuestReport {
+    pub json: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuestOutputError {
+    /// The capture hit its byte limit (`stdout_truncated`), so the report is incomplete.
+    Truncated {
+        len: usize,
+    },
+    TooLarge {
+        len: usize,
+        max: usize,
+    },
+    Empty,
+    Malformed(String),
+    NotObject,
+    TrailingData {
+        offset: usize,
+    },
+    SchemaVersionMismatch {
+        got: Option<String>,
+    },
+    /// [`RunSpec::report_file`] (a guest path) was never written, and
+    /// `stdout` says why stdout held no report either.
+    ReportFileMissing {
+        path: PathBuf,
+        stdout: Box<GuestOutputError>,
+    },
+    ReportFileUnreadable {
+        path: PathBuf,
+        reason: String,
+    },
+    /// [`RunSpec::report_file`] exists but fails the same checks as stdout.
+    ReportFile {
+        path: PathBuf,
+        err: Box<GuestOutputError>,
+    },
+}
+
+impl std::fmt::Display for GuestOutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_for(f, "guest stdout")
+    }
+}
+
+impl std::error::Error for GuestOutputError {}
+
+impl GuestOutputError {
+    pub fn is_truncation(&self) -> bool {
+        matches!(self, GuestOutputError::Truncated { .. })
+    }
+
+    /// `source` names where the bytes came from: stdout or a report file.
+    fn fmt_for(&self, f: &mut std::fmt::Formatter<'_>, source: &str) -> std::fmt::Result {
+        match self {
+            GuestOutputError::Truncated { len } => {
+                write!(f, "{source} truncated at {len} bytes")
+            }
+            GuestOutputError::TooLarge { len, max } => {
+                write!(f, "{source} is {len} bytes (max {max})")
+            }
+            GuestOutputError::Empty => write!(f, "{source} is empty"),
+            GuestOutputError::Malformed(msg) => write!(f, "{source} is not JSON: {msg}"),
+            GuestOutputError::NotObject => write!(f, "{source} is not a JSON object"),
+            GuestOutputError::TrailingData { offset } => {
+                write!(f, "{source} has trailing data at byte {offset}")
+            }
+            GuestOutputError::SchemaVersionMismatch { got } => write!(
+                f,
+                "guest report schema_version mismatch: expected {X07_OS_RUNNER_REPORT_SCHEMA_VERSION:?} got {got:?}"
+            ),
+            GuestOutputError::ReportFileMissing { path, stdout } => {
+                write!(f, "guest report file {} is missing and ", path.display())?;
+                stdout.fmt_for(f, source)
+            }
+            GuestOutputError::ReportFileUnreadable { path, reason } => {
+                write!(f, "read guest report file {}: {reason}", path.display())
+            }
+            GuestOutputError::ReportFile { path, err } => {
+                err.fmt_for(f, &format!("guest report file {}", path.display()))
+            }
+        }
+    }
+}
+
+/// Checks that guest stdout is a single JSON object (surrounding whitespace
+/// allowed) carrying the `x07-os-runner` report `schema_version`.
+pub fn validate_guest_stdout(
+    stdout: &[u8],
+    stdout_truncated: bool,
+) -> Result<GuestReport, GuestOutputError> {
+    if stdout_truncated {
+        return Err(GuestOutputError::Truncated { len: stdout.len() });
+    }
+    if stdout.len() > GUEST_STDOUT_MAX_BYTES {
+        return Err(GuestOutputError::TooLarge {
+            len: stdout.len(),
+            max: GUEST_STDOUT_MAX_BYTES,
+        });
+    }
+    validate_report_bytes(stdout)
+}
+
+fn validate_report_bytes(bytes: &[u8]) -> Result<GuestReport, GuestOutputError> {
+    if bytes.iter().all(u8::is_ascii_whitespace) {
+        return Err(GuestOutputError::Empty);
+    }
+
+    let mut stream = serde_json::Deserializer::from_slice(bytes).into_iter::<Value>();
+    let json = match stream.next() {
+        Some(Ok(v)) => v,
+        Some(Err(err)) => return Err(GuestOutputError::Malformed(err.to_string())),
+        None => return Err(GuestOutputError::Empty),
+    };
+    let offset = stream.byte_offset();
+    if let Some(pos) = bytes[offset..]
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+    {
+        return Err(GuestOutputError::TrailingData {
+            offset: offset + pos,
+        });
+    }
+
+    let Some(obj) = json.as_object() else {
+        return Err(GuestOutputError::NotObject);
+    };
+    let schema_version = obj.get("schema_version").and_then(Value::as_str);
+    if schema_version != Some(X07_OS_RUNNER_REPORT_SCHEMA_VERSION) {
+        return Err(GuestOutputError::SchemaVersionMismatch {
+            got: schema_version.map(str::to_string),
+        });
+    }
+
+    Ok(GuestReport { json })
+}
+
+/// Host side of [`RunSpec::report_file`], under the `/x07/out` bind mount.
+/// `None` when the spec has no report file or no such mount, which
+/// [`RunSpec::validate_for`] rules out.
+pub(crate) fn report_file_host_path(spec: &RunSpec) -> Option<PathBuf> {
+    let guest_path = normalize_abs_path(spec.report_file.as_deref()?).ok()?;
+    let rel = guest_path.strip_prefix(VZ_JOB_OUT_GUEST_PATH).ok()?;
+    spec.mounts.iter().find_map(|m| {
+        let is_out = matches!(m.kind, MountKind::Bind)
+            && normalize_abs_path(&m.guest_path)
+                .is_ok_and(|p| p == Path::new(VZ_JOB_OUT_GUEST_PATH));
+        is_out.then(|| m.host_path.join(rel))
+    })
+}
+
+/// Reads a report file from the host, refusing more than `max_bytes`.
+/// `Ok(None)` when the guest never wrote it; `guest_path` is for errors.
+///
+/// The guest owns the directory, so anything but a regular file (a symlink
+/// to a host file, a FIFO) is refused without being read.
+pub fn read_guest_report_file(
+    host_path: &Path,
+    guest_path: &Path,
+    max_bytes: u64,
+) -> Result<Option<Vec<u8>>, GuestOutputError> {
+    let unreadable = |err: std::io::Error| GuestOutputError::ReportFileUnreadable {
+        path: guest_path.to_path_buf(),
+        reason: err.to_string(),
+    };
+    let too_large = |len: u64| GuestOutputError::ReportFile {
+        path: guest_path.to_path_buf(),
+        err: Box::new(GuestOutputError::TooLarge {
+            len: usize::try_from(len).unwrap_or(usize::MAX),
+            max: usize::try_from(max_bytes).unwrap_or(usize::MAX),
+        }),
+    };
+
+    let not_regular = || GuestOutputError::ReportFileUnreadable {
+        path: guest_path.to_path_buf(),
+        reason: "not a regular file".to_string(),
+    };
+
+    let file = match open_no_follow(host_path) {
+        Ok(f) => f,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        #[cfg(unix)]
+        Err(err) if err.raw_os_error() == Some(libc::ELOOP) => return Err(not_regular()),
+        Err(err) => return Err(unreadable(err)),
+    };
+    let meta = file.metadata().map_err(unreadable)?;
+    if !meta.is_file() {
+        return Err(not_regular());
+    }
+    let len = meta.len();
+    if len > max_bytes {
+        return Err(too_large(len));
+    }
+    // The file can still grow if the guest is somehow alive; never read
+    // past the cap.
+    let mut bytes = Vec::with_capacity(usize::try_from(len).unwrap_or(0));
+    file.take(max_bytes.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(unreadable)?;
+    if bytes.len() as u64 > max_bytes {
+        return Err(too_large(bytes.len() as u64));
+    }
+    Ok(Some(bytes))
+}
+
+/// Opens `path` for reading without following a symlink in its last
+/// component. `O_NONBLOCK` keeps a FIFO from stalling the open; it does not
+/// affect reads from a regular file.
+#[cfg(unix)]
+fn open_no_follow(path: &Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt as _;
+    std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn open_no_follow(path: &Path) -> std::io::Result<std::fs::File> {
+    if std::fs::symlink_metadata(path)?.file_type().is_symlink() {
+        return Err(std::io::Error::other("not a regular file"));
+    }
+    std::fs::File::open(path)
+}
+
+/// Picks the report for a run with [`RunSpec::report_file`] set. `file` is
+/// the result of [`read_guest_report_file`]. Stdout is expected to be empty;
+/// it is only used, with a warning, when the file is missing. The second
+/// value is that warning, or one about stdout output being ignored.
+pub fn resolve_guest_report(
+    report_file: &Path,
+    file: Result<Option<Vec<u8>>, GuestOutputError>,
+    stdout: &[u8],
+    stdout_truncated: bool,
+) -> (Result<GuestReport, GuestOutputError>, Option<String>) {
+    match file {
+        Err(err) => (Err(err), None),
+        Ok(Some(bytes)) => {
+            let report =
+                validate_report_bytes(&bytes).map_err(|err| GuestOutputError::ReportFile {
+                    path: report_file.to_path_buf(),
+                    err: Box::new(err),
+                });
+            let warning = (!stdout.iter().all(u8::is_ascii_whitespace)).then(|| {
+                format!(
+                    "guest wrote {} bytes to stdout although the report goes to {}; ignoring them",
+                    stdout.len(),
+                    report_file.display()
+                )
+            });
+            (report, warning)
+        }
+        Ok(None) => match validate_guest_stdout(stdout, stdout_truncated) {
+            Ok(report) => (
+                Ok(report),
+                Some(format!(
+                    "guest report file {} is missing; using the report from stdout",
+                    report_file.display()
+                )),
+            ),
+            Err(err) => (
+                Err(GuestOutputError::ReportFileMissing {
+                    path: report_file.to_path_buf(),
+                    stdout: Box::new(err),
+                }),
+                None,
+            ),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report_bytes(schema_version: &str) -> Vec<u8> {
+        let v = serde_json::json!({ "schema_version": schema_version, "ok": true });
+        let mut bytes = serde_json::to_vec(&v).unwrap();
+        bytes.push(b'\n');
+        bytes
+    }
+
+    #[test]
+    fn valid_report() {
+        let bytes = report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        let report = validate_guest_stdout(&bytes, false).unwrap();
+        assert_eq!(report.json["ok"], Value::Bool(true));
+    }
+
+    #[test]
+    fn trailing_garbage_is_rejected() {
+        let mut bytes = report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        let len = bytes.len();
+        bytes.extend_from_slice(b"  oops");
+        assert_eq!(
+            validate_guest_stdout(&bytes, false).unwrap_err(),
+            GuestOutputError::TrailingData { offset: len + 2 }
+        );
+
+        let mut two = report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        two.extend(report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION));
+        assert!(matches!(
+            validate_guest_stdout(&two, false).unwrap_err(),
+            GuestOutputError::TrailingData { .. }
+        ));
+    }
+
+    #[test]
+    fn truncation_is_distinct_from_malformation() {
+        let bytes = report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        let cut = &bytes[..bytes.len() / 2];
+
+        let err = validate_guest_stdout(cut, true).unwrap_err();
+        assert_eq!(err, GuestOutputError::Truncated { len: cut.len() });
+        assert!(err.is_truncation());
+
+        let err = validate_guest_stdout(cut, false).unwrap_err();
+        assert!(matches!(err, GuestOutputError::Malformed(_)), "{err}");
+        assert!(!err.is_truncation());
+    }
+
+    #[test]
+    fn wrong_schema_and_shape_are_rejected() {
+        assert_eq!(
+            validate_guest_stdout(&report_bytes("x07-os-runner.report@0.0.0"), false).unwrap_err(),
+            GuestOutputError::SchemaVersionMismatch {
+                got: Some("x07-os-runner.report@0.0.0".to_string())
+            }
+        );
+        assert_eq!(
+            validate_guest_stdout(b"{\"ok\":true}", false).unwrap_err(),
+            GuestOutputError::SchemaVersionMismatch { got: None }
+        );
+        assert_eq!(
+            validate_guest_stdout(b"[1,2]", false).unwrap_err(),
+            GuestOutputError::NotObject
+        );
+        assert_eq!(
+            validate_guest_stdout(b" \n", false).unwrap_err(),
+            GuestOutputError::Empty
+        );
+    }
+
+    const REPORT_FILE: &str = "/x07/out/report.json";
+
+    #[test]
+    fn report_file_is_validated_like_stdout() {
+        let path = Path::new(REPORT_FILE);
+        let bytes = report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        let (report, warning) = resolve_guest_report(path, Ok(Some(bytes)), b"\n", false);
+        assert_eq!(report.unwrap().json["ok"], Value::Bool(true));
+        assert_eq!(warning, None);
+
+        let (report, _) = resolve_guest_report(path, Ok(Some(b"[1]".to_vec())), b"", false);
+        let err = report.unwrap_err();
+        assert_eq!(
+            err,
+            GuestOutputError::ReportFile {
+                path: path.to_path_buf(),
+                err: Box::new(GuestOutputError::NotObject),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "guest report file /x07/out/report.json is not a JSON object"
+        );
+
+        // Stray stdout does not override the file, but is called out.
+        let bytes = report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        let (report, warning) = resolve_guest_report(path, Ok(Some(bytes)), b"noise", false);
+        assert!(report.is_ok());
+        assert!(warning.unwrap().contains("ignoring"));
+    }
+
+    #[test]
+    fn missing_report_file_falls_back_to_stdout() {
+        let path = Path::new(REPORT_FILE);
+        let stdout = report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        let (report, warning) = resolve_guest_report(path, Ok(None), &stdout, false);
+        assert!(report.is_ok());
+        assert!(warning
+            .unwrap()
+            .contains("is missing; using the report from stdout"));
+
+        let (report, warning) = resolve_guest_report(path, Ok(None), b"", false);
+        let err = report.unwrap_err();
+        assert_eq!(
+            err,
+            GuestOutputError::ReportFileMissing {
+                path: path.to_path_buf(),
+                stdout: Box::new(GuestOutputError::Empty),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "guest report file /x07/out/report.json is missing and guest stdout is empty"
+        );
+        assert_eq!(warning, None);
+
+        let (report, _) = resolve_guest_report(path, Ok(None), &stdout[..4], true);
+        assert!(matches!(
+            report.unwrap_err(),
+            GuestOutputError::ReportFileMissing { stdout, .. } if stdout.is_truncation()
+        ));
+    }
+
+    #[test]
+    fn report_file_read_is_capped() {
+        let dir = std::env::temp_dir().join(format!("x07_vm_report_file_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let host = dir.join("report.json");
+        let path = Path::new(REPORT_FILE);
+        assert_eq!(read_guest_report_file(&host, path, 16), Ok(None));
+
+        std::fs::write(&host, report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION)).unwrap();
+        let len = std::fs::metadata(&host).unwrap().len();
+        assert_eq!(
+            read_guest_report_file(&host, path, len)
+                .unwrap()
+                .map(|b| b.len() as u64),
+            Some(len)
+        );
+        assert_eq!(
+            read_guest_report_file(&host, path, len - 1),
+            Err(GuestOutputError::ReportFile {
+                path: path.to_path_buf(),
+                err: Box::new(GuestOutputError::TooLarge {
+                    len: len as usize,
+                    max: len as usize - 1,
+                }),
+            })
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_report_file_is_not_followed() {
+        let dir =
+            std::env::temp_dir().join(format!("x07_vm_report_file_symlink_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let secret = dir.join("host_secret.json");
+        std::fs::write(&secret, report_bytes(X07_OS_RUNNER_REPORT_SCHEMA_VERSION)).unwrap();
+        let host = dir.join("report.json");
+        std::os::unix::fs::symlink(&secret, &host).unwrap();
+
+        let path = Path::new(REPORT_FILE);
+        let not_regular = Err(GuestOutputError::ReportFileUnreadable {
+            path: path.to_path_buf(),
+            reason: "not a regular file".to_string(),
+        });
+        assert_eq!(read_guest_report_file(&host, path, 1 << 20), not_regular);
+
+        // Nor is anything else that is not a regular file.
+        std::fs::remove_file(&host).unwrap();
+        std::fs::create_dir(&host).unwrap();
+        assert_eq!(read_guest_report_file(&host, path, 1 << 20), not_regular);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn size_ceiling() {
+        let bytes = vec![b' '; GUEST_STDOUT_MAX_BYTES + 1];
+        assert_eq!(
+            validate_guest_stdout(&bytes, false).unwrap_err(),
+            GuestOutputError::TooLarge {
+                len: GUEST_STDOUT_MAX_BYTES + 1,
+                max: GUEST_STDOUT_MAX_BYTES
+            }
+        );
+    }
+}
@@ -0,0 +1,672 @@
+//! Getting firecracker-ctr guest images into the containerd namespace
+//! without a registry.
+//!
+//! [`import_image_oci_layout`] takes an OCI image layout directory (as
+//! written by `skopeo copy ... oci:<dir>` or `crane pull --format=oci`),
+//! checks that every blob it references is on disk, imports it with
+//! `ctr images import --index-name`, unpacks it into the configured
+//! snapshotter and reports the imported digest. [`ensure_image`] is the
+//! pre-pull entry point: it only pulls when the image is not already in the
+//! namespace, so hosts fed by imports never reach for a registry.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::digest::{check_digest, find_first_sha256_digest_in_text};
+use crate::{
+    ctr_base_args, run_command_capped, FirecrackerCtrConfig, JobEventKind, JobTimelineRecorder,
+    VmBackend,
+};
+
+/// Budget for `ctr images import` / `pull`; layers can be large.
+const CTR_IMAGE_TRANSFER_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+/// Budget for `ctr images unpack` into the snapshotter.
+const CTR_IMAGE_UNPACK_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+const CTR_IMAGE_LS_TIMEOUT_MS: u64 = 5_000;
+
+const MEDIA_TYPE_OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const MEDIA_TYPE_DOCKER_MANIFEST_LIST: &str =
+    "application/vnd.docker.distribution.manifest.list.v2+json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedImage {
+    pub name: String,
+    /// Digest `name` resolves to in the namespace after the import.
+    pub digest: String,
+}
+
+/// Result of [`ensure_image`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnsuredImage {
+    pub name: String,
+    pub digest: String,
+    /// `false` when the image was already in the namespace.
+    pub pulled: bool,
+}
+
+/// Returned (inside `anyhow::Error`) when an OCI layout, or what `ctr`
+/// managed to import from it, lacks a referenced blob; callers can
+/// `downcast_ref::<MissingBlob>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingBlob {
+    pub layout_dir: PathBuf,
+    pub digest: String,
+    /// What references the blob: `index.json`, a manifest digest, or
+    /// `ctr images import` when containerd reported it.
+    pub referenced_by: String,
+}
+
+impl std::fmt::Display for MissingBlob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "OCI layout {} is missing blob {} (referenced by {})",
+            self.layout_dir.display(),
+            self.digest,
+            self.referenced_by
+        )
+    }
+}
+
+impl std::error::Error for MissingBlob {}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciLayoutMarker {
+    image_layout_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciDescriptor {
+    #[serde(default)]
+    media_type: String,
+    digest: String,
+}
+
+/// An image index or an image manifest; only the descriptors are needed.
+#[derive(Debug, Deserialize)]
+struct OciBlobRefs {
+    #[serde(default)]
+    manifests: Vec<OciDescriptor>,
+    config: Option<OciDescriptor>,
+    #[serde(default)]
+    layers: Vec<OciDescriptor>,
+}
+
+/// Imports the OCI layout at `layout_dir` as `ref_name`, unpacks it into
+/// `cfg.snapshotter` and, when `expected_digest` is given, fails with
+/// [`crate::DigestMismatch`] unless the imported image resolves to it.
+pub fn import_image_oci_layout(
+    cfg: &FirecrackerCtrConfig,
+    layout_dir: &Path,
+    ref_name: &str,
+    expected_digest: Option<&str>,
+) -> Result<ImportedImage> {
+    check_oci_layout_blobs(layout_dir)?;
+
+    let archive = LayoutArchive::create(layout_dir)?;
+    let out = run_command_capped(
+        ctr_import_command(cfg, &archive.path, ref_name),
+        CTR_IMAGE_TRANSFER_TIMEOUT_MS,
+        256 * 1024,
+        256 * 1024,
+    )
+    .with_context(|| format!("firecracker-ctr images import {}", layout_dir.display()))?;
+    if out.timed_out {
+        anyhow::bail!("firecracker-ctr images import timed out");
+    }
+    if out.exit_status != 0 {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        if let Some(err) = missing_blob_from_ctr_stderr(layout_dir, &stderr) {
+            return Err(err.into());
+        }
+        anyhow::bail!("firecracker-ctr images import failed: {stderr}");
+    }
+    drop(archive);
+
+    unpack_image(cfg, ref_name)?;
+    let digest = crate::digest::resolve_ctr_image_target_digest(cfg, ref_name)?;
+    if let Some(expected) = expected_digest {
+        check_digest(VmBackend::FirecrackerCtr, expected, digest.clone())?;
+    }
+    Ok(ImportedImage {
+        name: ref_name.to_string(),
+        digest,
+    })
+}
+
+/// Makes `image` available to `firecracker-ctr run`: an image already in the
+/// namespace (imported or pulled earlier) is used as is, otherwise it is
+/// pulled. Either way it ends up unpacked into `cfg.snapshotter`.
+pub fn ensure_image(cfg: &FirecrackerCtrConfig, image: &str) -> Result<EnsuredImage> {
+    ensure_image_timed(cfg, image, &JobTimelineRecorder::start())
+}
+
+/// [`ensure_image`], recording [`JobEventKind::PullStarted`] and
+/// [`JobEventKind::PullFinished`] around a pull.
+pub fn ensure_image_timed(
+    cfg: &FirecrackerCtrConfig,
+    image: &str,
+    timeline: &JobTimelineRecorder,
+) -> Result<EnsuredImage> {
+    let pulled = !image_is_local(cfg, image)?;
+    if pulled {
+        timeline.record(JobEventKind::PullStarted);
+        let out = run_command_capped(
+            ctr_pull_command(cfg, image),
+            CTR_IMAGE_TRANSFER_TIMEOUT_MS,
+            256 * 1024,
+            256 * 1024,
+        )
+        .with_context(|| format!("firecracker-ctr images pull {image}"))?;
+        if out.timed_out {
+            anyhow::bail!("firecracker-ctr images pull timed out");
+        }
+        if out.exit_status != 0 {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            anyhow::bail!("firecracker-ctr images pull failed: {stderr}");
+        }
+        timeline.record(JobEventKind::PullFinished);
+    }
+    unpack_image(cfg, image)?;
+    let digest = crate::digest::resolve_ctr_image_target_digest(cfg, image)?;
+    Ok(EnsuredImage {
+        name: image.to_string(),
+        digest,
+        pulled,
+    })
+}
+
+fn image_is_local(cfg: &FirecrackerCtrConfig, image: &str) -> Result<bool> {
+    let out = run_command_capped(
+        ctr_image_ls_command(cfg, image),
+        CTR_IMAGE_LS_TIMEOUT_MS,
+        256 * 1024,
+        64 * 1024,
+    )
+    .with_context(|| format!("firecracker-ctr images ls {image}"))?;
+    if out.timed_out {
+        anyhow::bail!("firecracker-ctr images ls timed out");
+    }
+    if out.exit_status != 0 {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        anyhow::bail!("firecracker-ctr images ls failed: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .any(|l| l.trim() == image))
+}
+
+fn unpack_image(cfg: &FirecrackerCtrConfig, image: &str) -> Result<()> {
+    let out = run_command_capped(
+        ctr_unpack_command(cfg, image),
+        CTR_IMAGE_UNPACK_TIMEOUT_MS,
+        64 * 1024,
+        256 * 1024,
+    )
+    .with_context(|| format!("firecracker-ctr images unpack {image}"))?;
+    if out.timed_out {
+        anyhow::bail!("firecracker-ctr images unpack timed out");
+    }
+    if out.exit_status != 0 {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        anyhow::bail!(
+            "firecracker-ctr images unpack --snapshotter {} failed: {stderr}",
+            cfg.snapshotter
+        );
+    }
+    Ok(())
+}
+
+fn ctr_import_command(cfg: &FirecrackerCtrConfig, archive: &Path, ref_name: &str) -> Command {
+    let mut cmd = Command::new(&cfg.bin);
+    cmd.args(ctr_base_args(cfg));
+    cmd.args(["images", "import", "--no-unpack", "--index-name", ref_name]);
+    cmd.arg(archive);
+    cmd
+}
+
+fn ctr_unpack_command(cfg: &FirecrackerCtrConfig, image: &str) -> Command {
+    let mut cmd = Command::new(&cfg.bin);
+    cmd.args(ctr_base_args(cfg));
+    cmd.args(["images", "unpack", "--snapshotter", &cfg.snapshotter, image]);
+    cmd
+}
+
+fn ctr_image_ls_command(cfg: &FirecrackerCtrConfig, image: &str) -> Command {
+    let mut cmd = Command::new(&cfg.bin);
+    cmd.args(ctr_base_args(cfg));
+    cmd.args(["images", "ls", "--quiet"]);
+    cmd.arg(format!("name=={image}"));
+    cmd
+}
+
+fn ctr_pull_command(cfg: &FirecrackerCtrConfig, image: &str) -> Command {
+    let mut cmd = Command::new(&cfg.bin);
+    cmd.args(ctr_base_args(cfg));
+    cmd.args(["images", "pull", "--snapshotter", &cfg.snapshotter, image]);
+    cmd
+}
+
+/// `ctr images import` reads a tar stream, so the layout is archived to a
+/// temp file first; the file is removed on drop.
+struct LayoutArchive {
+    path: PathBuf,
+}
+
+impl LayoutArchive {
+    fn create(layout_dir: &Path) -> Result<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "x07_vm_oci_layout_{}_{}.tar",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let archive = Self { path };
+        let mut cmd = Command::new("tar");
+        cmd.arg("-cf").arg(&archive.path);
+        cmd.arg("-C").arg(layout_dir).arg(".");
+        let out = run_command_capped(cmd, CTR_IMAGE_TRANSFER_TIMEOUT_MS, 64 * 1024, 64 * 1024)
+            .with_context(|| format!("archive OCI layout {}", layout_dir.display()))?;
+        if out.timed_out || out.exit_status != 0 {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            anyhow::bail!(
+                "archive OCI layout {} failed: {stderr}",
+                layout_dir.display()
+            );
+        }
+        Ok(archive)
+    }
+}
+
+impl Drop for LayoutArchive {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Walks `index.json` down to every manifest's config and layers and fails
+/// with [`MissingBlob`] on the first one not under `blobs/`. Nested indexes
+/// may omit platforms (layouts are often trimmed to one), but at least one
+/// of their manifests must be present.
+fn check_oci_layout_blobs(layout_dir: &Path) -> Result<()> {
+    let marker_path = layout_dir.join("oci-layout");
+    let marker: OciLayoutMarker = serde_json::from_slice(
+        &std::fs::read(&marker_path)
+            .with_context(|| format!("read OCI layout marker: {}", marker_path.display()))?,
+    )
+    .with_context(|| format!("parse OCI layout marker: {}", marker_path.display()))?;
+    if marker.image_layout_version != "1.0.0" {
+        anyhow::bail!(
+            "unsupported OCI imageLayoutVersion {:?} in {}",
+            marker.image_layout_version,
+            marker_path.display()
+        );
+    }
+
+    let index_path = layout_dir.join("index.json");
+    let index: OciBlobRefs = serde_json::from_slice(
+        &std::fs::read(&index_path)
+            .with_context(|| format!("read OCI index: {}", index_path.display()))?,
+    )
+    .with_context(|| format!("parse OCI index: {}", index_path.display()))?;
+    if index.manifests.is_empty() {
+        anyhow::bail!("OCI index {} lists no manifests", index_path.display());
+    }
+
+    let mut checked = BTreeSet::new();
+    for desc in &index.manifests {
+        check_descriptor(layout_dir, desc, "index.json", &mut checked)?;
+    }
+    Ok(())
+}
+
+fn check_descriptor(
+    layout_dir: &Path,
+    desc: &OciDescriptor,
+    referenced_by: &str,
+    checked: &mut BTreeSet<String>,
+) -> Result<()> {
+    if !checked.insert(desc.digest.clone()) {
+        return Ok(());
+    }
+    let bytes = read_blob(layout_dir, &desc.digest, referenced_by)?;
+    let is_index = desc.media_type == MEDIA_TYPE_OCI_INDEX
+        || desc.media_type == MEDIA_TYPE_DOCKER_MANIFEST_LIST;
+    if !is_index && !desc.media_type.contains("manifest") {
+        return Ok(());
+    }
+    let refs: OciBlobRefs = serde_json::from_slice(&bytes)
+        .with_context(|| format!("parse OCI blob {}", desc.digest))?;
+
+    if is_index {
+        let present: Vec<&OciDescriptor> = refs
+            .manifests
+            .iter()
+            .filter(|m| blob_path(layout_dir, &m.digest).is_some_and(|p| p.is_file()))
+            .collect();
+        let Some(first) = refs.manifests.first() else {
+            anyhow::bail!("OCI index {} lists no manifests", desc.digest);
+        };
+        if present.is_empty() {
+            return Err(missing_blob(layout_dir, &first.digest, &desc.digest));
+        }
+        for m in present {
+            check_descriptor(layout_dir, m, &desc.digest, checked)?;
+        }
+        return Ok(());
+    }
+
+    for blob in refs.config.iter().chain(&refs.layers) {
+        if checked.insert(blob.digest.clone()) {
+            let path = blob_path(layout_dir, &blob.digest);
+            if !path.is_some_and(|p| p.is_file()) {
+                return Err(missing_blob(layout_dir, &blob.digest, &desc.digest));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_blob(layout_dir: &Path, digest: &str, referenced_by: &str) -> Result<Vec<u8>> {
+    let Some(path) = blob_path(layout_dir, digest) else {
+        anyhow::bail!("malformed blob digest {digest:?} (referenced by {referenced_by})");
+    };
+    match std::fs::read(&path) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(missing_blob(layout_dir, digest, referenced_by))
+        }
+        Err(e) => Err(e).with_context(|| format!("read OCI blob {}", path.display())),
+    }
+}
+
+/// `blobs/<alg>/<hex>`, or `None` for a digest that would escape `blobs/`.
+fn blob_path(layout_dir: &Path, digest: &str) -> Option<PathBuf> {
+    let (alg, hex) = digest.split_once(':')?;
+    let safe = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric());
+    if !safe(alg) || !safe(hex) {
+        return None;
+    }
+    Some(layout_dir.join("blobs").join(alg).join(hex))
+}
+
+fn missing_blob(layout_dir: &Path, digest: &str, referenced_by: &str) -> anyhow::Error {
+    MissingBlob {
+        layout_dir: layout_dir.to_path_buf(),
+        digest: digest.to_string(),
+        referenced_by: referenced_by.to_string(),
+    }
+    .into()
+}
+
+/// containerd reports an incomplete archive as `content digest sha256:...:
+/// not found` (or `failed to get reader: ... not found`).
+fn missing_blob_from_ctr_stderr(layout_dir: &Path, stderr: &str) -> Option<MissingBlob> {
+    if !stderr.contains("not found") {
+        return None;
+    }
+    let digest = find_first_sha256_digest_in_text(stderr)?;
+    Some(MissingBlob {
+        layout_dir: layout_dir.to_path_buf(),
+        digest,
+        referenced_by: "ctr images import".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest as _, Sha256};
+
+    fn cfg() -> FirecrackerCtrConfig {
+        FirecrackerCtrConfig {
+            bin: "firecracker-ctr".into(),
+            address: "/run/fc/containerd.sock".to_string(),
+            namespace: "x07".to_string(),
+            runtime: crate::DEFAULT_FIRECRACKER_RUNTIME.to_string(),
+            snapshotter: "devmapper".to_string(),
+        }
+    }
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    struct Layout {
+        dir: PathBuf,
+    }
+
+    impl Layout {
+        /// A one-manifest layout; returns the layer and config digests too.
+        fn new(name: &str) -> (Self, String, String) {
+            let dir = std::env::temp_dir()
+                .join(format!("x07_vm_oci_layout_{name}_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(dir.join("blobs/sha256")).unwrap();
+            let layout = Self { dir };
+            std::fs::write(
+                layout.dir.join("oci-layout"),
+                br#"{"imageLayoutVersion":"1.0.0"}"#,
+            )
+            .unwrap();
+            let layer = layout.put_blob(b"layer-bytes");
+            let config = layout.put_blob(b"{}");
+            let manifest = serde_json::json!({
+                "schemaVersion": 2,
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "config": { "mediaType": "application/vnd.oci.image.config.v1+json", "digest": config, "size": 2 },
+                "layers": [{ "mediaType": "application/vnd.oci.image.layer.v1.tar", "digest": layer, "size": 11 }],
+            });
+            let manifest = layout.put_blob(&serde_json::to_vec(&manifest).unwrap());
+            let index = serde_json::json!({
+                "schemaVersion": 2,
+                "manifests": [{ "mediaType": "application/vnd.oci.image.manifest.v1+json", "digest": manifest, "size": 0 }],
+            });
+            std::fs::write(
+                layout.dir.join("index.json"),
+                serde_json::to_vec(&index).unwrap(),
+            )
+            .unwrap();
+            (layout, layer, config)
+        }
+
+        fn put_blob(&self, bytes: &[u8]) -> String {
+            let hex = format!("{:x}", Sha256::digest(bytes));
+            std::fs::write(self.dir.join("blobs/sha256").join(&hex), bytes).unwrap();
+            format!("sha256:{hex}")
+        }
+
+        fn remove_blob(&self, digest: &str) {
+            std::fs::remove_file(blob_path(&self.dir, digest).unwrap()).unwrap();
+        }
+    }
+
+    impl Drop for Layout {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn ctr_commands_target_namespace_and_snapshotter() {
+        let cfg = cfg();
+        let base = ["--address", "/run/fc/containerd.sock", "--namespace", "x07"];
+
+        let import = args(&ctr_import_command(
+            &cfg,
+            Path::new("/tmp/layout.tar"),
+            "x07/guest:1",
+        ));
+        assert_eq!(import[..4], base);
+        assert_eq!(
+            import[4..],
+            [
+                "images",
+                "import",
+                "--no-unpack",
+                "--index-name",
+                "x07/guest:1",
+                "/tmp/layout.tar"
+            ]
+        );
+
+        let unpack = args(&ctr_unpack_command(&cfg, "x07/guest:1"));
+        assert_eq!(unpack[..4], base);
+        assert_eq!(
+            unpack[4..],
+            [
+                "images",
+                "unpack",
+                "--snapshotter",
+                "devmapper",
+                "x07/guest:1"
+            ]
+        );
+
+        let ls = args(&ctr_image_ls_command(&cfg, "x07/guest:1"));
+        assert_eq!(ls[4..], ["images", "ls", "--quiet", "name==x07/guest:1"]);
+
+        let pull = args(&ctr_pull_command(&cfg, "x07/guest:1"));
+        assert_eq!(
+            pull[4..],
+            [
+                "images",
+                "pull",
+                "--snapshotter",
+                "devmapper",
+                "x07/guest:1"
+            ]
+        );
+    }
+
+    #[test]
+    fn complete_layout_passes_blob_check() {
+        let (layout, _, _) = Layout::new("complete");
+        check_oci_layout_blobs(&layout.dir).unwrap();
+    }
+
+    #[test]
+    fn missing_layer_is_named() {
+        let (layout, layer, _) = Layout::new("missing_layer");
+        layout.remove_blob(&layer);
+        let err = check_oci_layout_blobs(&layout.dir).unwrap_err();
+        let missing = err.downcast_ref::<MissingBlob>().expect("MissingBlob");
+        assert_eq!(missing.digest, layer);
+        assert!(missing.referenced_by.starts_with("sha256:"));
+        assert!(err.to_string().contains(&layer));
+    }
+
+    #[test]
+    fn missing_config_is_named() {
+        let (layout, _, config) = Layout::new("missing_config");
+        layout.remove_blob(&config);
+        let err = check_oci_layout_blobs(&layout.dir).unwrap_err();
+        assert_eq!(err.downcast_ref::<MissingBlob>().unwrap().digest, config);
+    }
+
+    #[test]
+    fn ctr_not_found_stderr_maps_to_missing_blob() {
+        let d = format!("sha256:{}", "ab".repeat(32));
+        let stderr = format!("ctr: content digest {d}: not found\n");
+        let missing = missing_blob_from_ctr_stderr(Path::new("/l"), &stderr).unwrap();
+        assert_eq!(missing.digest, d);
+        assert_eq!(missing.referenced_by, "ctr images import");
+        assert!(missing_blob_from_ctr_stderr(Path::new("/l"), "ctr: permission denied").is_none());
+    }
+
+    #[test]
+    fn blob_path_rejects_traversal() {
+        assert!(blob_path(Path::new("/l"), "sha256:../../etc").is_none());
+        assert!(blob_path(Path::new("/l"), "nocolon").is_none());
+        assert_eq!(
+            blob_path(Path::new("/l"), "sha256:abc"),
+            Some(PathBuf::from("/l/blobs/sha256/abc"))
+        );
+    }
+
+    /// A `ctr` stand-in whose image store is a `pulled` marker file.
+    #[cfg(unix)]
+    fn fake_ctr(dir: &Path) -> FirecrackerCtrConfig {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let bin = dir.join("ctr");
+        let script = format!(
+            r#"#!/bin/sh
+dir=$(dirname "$0")
+case "$*" in
+  *"images ls"*) [ -f "$dir/pulled" ] && echo x07/guest:1 ;;
+  *"images pull"*) touch "$dir/pulled" ;;
+  *"images info"*) echo "target sha256:{}" ;;
+esac
+exit 0
+"#,
+            "ab".repeat(32)
+        );
+        std::fs::write(&bin, script).unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        FirecrackerCtrConfig {
+            bin: bin.into_os_string(),
+            ..cfg()
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ensure_image_records_pull_events_only_when_pulling() {
+        let (layout, _, _) = Layout::new("fake_ctr");
+        let cfg = fake_ctr(&layout.dir);
+
+        let timeline = JobTimelineRecorder::start();
+        let ensured = ensure_image_timed(&cfg, "x07/guest:1", &timeline).unwrap();
+        assert!(ensured.pulled);
+        assert_eq!(ensured.digest, format!("sha256:{}", "ab".repeat(32)));
+        let kinds: Vec<JobEventKind> = timeline.snapshot().events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [JobEventKind::PullStarted, JobEventKind::PullFinished]
+        );
+
+        let timeline = JobTimelineRecorder::start();
+        let ensured = ensure_image_timed(&cfg, "x07/guest:1", &timeline).unwrap();
+        assert!(!ensured.pulled);
+        assert!(timeline.snapshot().events.is_empty());
+    }
+
+    /// Needs a reachable firecracker-containerd and a busybox OCI layout,
+    /// e.g. `skopeo copy docker://busybox:latest oci:/tmp/busybox-oci`, named
+    /// by `X07_VM_TEST_OCI_LAYOUT`; skipped otherwise.
+    #[test]
+    fn import_busybox_layout_and_ensure_uses_it() {
+        let Some(layout_dir) = std::env::var_os("X07_VM_TEST_OCI_LAYOUT") else {
+            return;
+        };
+        let cfg = crate::firecracker_ctr_config_from_env();
+        let name = format!("x07-test/busybox-import:{}", std::process::id());
+
+        let imported = import_image_oci_layout(&cfg, Path::new(&layout_dir), &name, None)
+            .expect("import layout");
+        assert!(imported.digest.starts_with("sha256:"));
+
+        let again =
+            import_image_oci_layout(&cfg, Path::new(&layout_dir), &name, Some(&imported.digest))
+                .expect("re-import with expected digest");
+        assert_eq!(again.digest, imported.digest);
+
+        let ensured = ensure_image(&cfg, &name).expect("ensure imported image");
+        assert!(!ensured.pulled);
+        assert_eq!(ensured.digest, imported.digest);
+
+        let mut rm = Command::new(&cfg.bin);
+        rm.args(ctr_base_args(&cfg)).args(["images", "rm", &name]);
+        let _ = run_command_capped(rm, 10_000, 4096, 4096);
+    }
+}
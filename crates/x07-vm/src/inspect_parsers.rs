@@ -1,17 +1,46 @@
 use std::collections::BTreeMap;
 
+use serde::Serialize;
 use serde_json::Value;
 
-use crate::{X07_LABEL_SCHEMA_KEY, X07_LABEL_SCHEMA_VALUE};
+use crate::{
+    X07_LABEL_JOB_FILE_KEY, X07_LABEL_SCHEMA_KEY, X07_LABEL_SCHEMA_VALUE,
+    X07_LABEL_STATE_ROOT_ID_KEY,
+};
 
 pub type Labels = BTreeMap<String, String>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct OwnedContainer {
     pub id: String,
     pub labels: Labels,
     pub status: Option<String>,
     pub primary_ipv4_cidr: Option<String>,
+    /// [`X07_LABEL_JOB_FILE_KEY`], relative to the state root named by
+    /// `state_root_id`.
+    pub job_file: Option<String>,
+    /// [`X07_LABEL_STATE_ROOT_ID_KEY`].
+    pub state_root_id: Option<String>,
+}
+
+impl OwnedContainer {
+    fn from_labels(
+        id: String,
+        labels: Labels,
+        status: Option<String>,
+        primary_ipv4_cidr: Option<String>,
+    ) -> Self {
+        let job_file = labels.get(X07_LABEL_JOB_FILE_KEY).cloned();
+        let state_root_id = labels.get(X07_LABEL_STATE_ROOT_ID_KEY).cloned();
+        Self {
+            id,
+            labels,
+            status,
+            primary_ipv4_cidr,
+            job_file,
+            state_root_id,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -173,12 +202,82 @@ pub fn parse_apple_container_json_owned(input: &str) -> Result<Vec<OwnedContaine
             continue;
         }
 
-        out.push(OwnedContainer {
+        out.push(OwnedContainer::from_labels(
             id,
             labels,
             status,
             primary_ipv4_cidr,
+        ));
+    }
+    Ok(out)
+}
+
+/// Parses `docker inspect` / `podman inspect` output (an array of container
+/// objects). `id` is the container name (what x07-vm passes as `--name`),
+/// falling back to the full id when there is no name.
+pub fn parse_docker_inspect_json_owned(input: &str) -> Result<Vec<OwnedContainer>, ParseError> {
+    let root: Value = serde_json::from_str(input)?;
+    let entries: Vec<Value> = match root {
+        Value::Array(a) => a,
+        Value::Object(_) => vec![root],
+        other => {
+            return Err(ParseError::new(format!(
+                "docker: expected array/object, got {}",
+                json_type_name(&other)
+            )))
+        }
+    };
+
+    let mut out: Vec<OwnedContainer> = Vec::new();
+    for e in entries {
+        let name = get_path(&e, &[seg(&["Name", "name"])])
+            .and_then(scalar_to_string)
+            .map(|n| n.trim_start_matches('/').to_string())
+            .filter(|n| !n.is_empty());
+        let Some(id) =
+            name.or_else(|| get_path(&e, &[seg(&["Id", "ID", "id"])]).and_then(scalar_to_string))
+        else {
+            continue;
+        };
+
+        let status = get_path(&e, &[seg(&["State", "state"]), seg(&["Status", "status"])])
+            .and_then(scalar_to_string);
+
+        let ip = get_path(&e, &[seg(&["NetworkSettings"]), seg(&["IPAddress"])])
+            .and_then(scalar_to_string)
+            .filter(|ip| !ip.is_empty());
+        let prefix = get_path(&e, &[seg(&["NetworkSettings"]), seg(&["IPPrefixLen"])])
+            .and_then(scalar_to_string);
+        let primary_ipv4_cidr = ip.map(|ip| match prefix {
+            Some(p) if p != "0" => format!("{ip}/{p}"),
+            _ => ip,
         });
+
+        let labels_val = get_path(
+            &e,
+            &[seg(&["Config", "config"]), seg(&["Labels", "labels"])],
+        );
+        let labels: Labels = match labels_val {
+            Some(Value::Object(_)) => parse_labels_object(labels_val.unwrap())?,
+            Some(Value::Null) | None => Labels::new(),
+            Some(other) => {
+                return Err(ParseError::new(format!(
+                    "docker: labels exists but is {}/not object",
+                    json_type_name(other)
+                )))
+            }
+        };
+
+        if !is_owned_by_x07(&labels) {
+            continue;
+        }
+
+        out.push(OwnedContainer::from_labels(
+            id,
+            labels,
+            status,
+            primary_ipv4_cidr,
+        ));
     }
     Ok(out)
 }
@@ -217,12 +316,7 @@ pub fn parse_ctr_container_info_json_owned(
         return Ok(None);
     }
 
-    Ok(Some(OwnedContainer {
-        id,
-        labels,
-        status: None,
-        primary_ipv4_cidr: None,
-    }))
+    Ok(Some(OwnedContainer::from_labels(id, labels, None, None)))
 }
 
 #[cfg(test)]
@@ -313,6 +407,100 @@ mod tests {
         assert!(is_owned_by_x07(&owned.labels));
     }
 
+    #[test]
+    fn docker_inspect_owned() {
+        let input = r#"
+        [
+          {
+            "Id": "4f1c0e3a9b",
+            "Name": "/x07-r1",
+            "State": { "Status": "running" },
+            "Config": {
+              "Labels": {
+                "io.x07.schema": "1",
+                "io.x07.run_id": "r1",
+                "io.x07.deadline_unix_ms": "1700000060000"
+              }
+            },
+            "NetworkSettings": { "IPAddress": "172.17.0.2", "IPPrefixLen": 16 }
+          },
+          {
+            "Id": "9a9a",
+            "Name": "/unrelated",
+            "State": { "Status": "exited" },
+            "Config": { "Labels": null }
+          }
+        ]
+        "#;
+
+        let owned = parse_docker_inspect_json_owned(input).unwrap();
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].id, "x07-r1");
+        assert_eq!(owned[0].status.as_deref(), Some("running"));
+        assert_eq!(owned[0].primary_ipv4_cidr.as_deref(), Some("172.17.0.2/16"));
+    }
+
+    #[test]
+    fn podman_inspect_owned() {
+        // podman: no leading slash on the name, and no IP in rootless mode.
+        let input = r#"
+        [
+          {
+            "Id": "abc123",
+            "Name": "x07-r2",
+            "State": { "Status": "exited" },
+            "Config": { "Labels": { "io.x07.schema": "1" } },
+            "NetworkSettings": { "IPAddress": "" }
+          }
+        ]
+        "#;
+
+        let owned = parse_docker_inspect_json_owned(input).unwrap();
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].id, "x07-r2");
+        assert_eq!(owned[0].status.as_deref(), Some("exited"));
+        assert_eq!(owned[0].primary_ipv4_cidr, None);
+    }
+
+    #[test]
+    fn job_file_labels_are_parsed() {
+        let apple = r#"
+        [
+          {
+            "configuration": {
+              "id": "abc",
+              "labels": {
+                "io.x07.schema": "1",
+                "io.x07.state_root_id": "sr-00aa",
+                "io.x07.job_file": "r1/job.json"
+              }
+            }
+          }
+        ]
+        "#;
+        let owned = parse_apple_container_json_owned(apple).unwrap();
+        assert_eq!(owned[0].job_file.as_deref(), Some("r1/job.json"));
+        assert_eq!(owned[0].state_root_id.as_deref(), Some("sr-00aa"));
+
+        let ctr = r#"
+        {
+          "id": "c1",
+          "labels": { "io.x07.schema": "1", "io.x07.job_file": "r2/job.json" }
+        }
+        "#;
+        let owned = parse_ctr_container_info_json_owned(ctr).unwrap().unwrap();
+        assert_eq!(owned.job_file.as_deref(), Some("r2/job.json"));
+        assert_eq!(owned.state_root_id, None);
+    }
+
+    #[test]
+    fn job_file_labels_absent() {
+        let input = r#"{ "id": "c4", "labels": { "io.x07.schema": "1" } }"#;
+        let owned = parse_ctr_container_info_json_owned(input).unwrap().unwrap();
+        assert_eq!(owned.job_file, None);
+        assert_eq!(owned.state_root_id, None);
+    }
+
     #[test]
     fn ctr_not_owned() {
         let input = r#"{ "id": "c3", "labels": { "foo": "bar" } }"#;
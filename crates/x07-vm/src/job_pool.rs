@@ -0,0 +1,626 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    cancel_job, default_vm_state_root, resolve_sibling_or_path, CancelOutcome, DefaultVmDriver,
+    FirecrackerCtrConfig, RunId, RunOutput, RunSpec, VmBackend, VmDriver, VmJobRunParams,
+};
+
+/// Shared per-pool job settings. Each job gets `<state_root>/<run_id>/` as its
+/// state dir and a deadline of `wall_ms` from the moment it starts.
+#[derive(Debug, Clone)]
+pub struct JobPoolConfig {
+    pub state_root: PathBuf,
+    pub reaper_bin: PathBuf,
+    pub firecracker_cfg: Option<FirecrackerCtrConfig>,
+}
+
+impl JobPoolConfig {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            state_root: default_vm_state_root()?,
+            reaper_bin: resolve_sibling_or_path("x07-vm-reaper"),
+            firecracker_cfg: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Run every queued job before stopping.
+    Drain,
+    /// Fail queued jobs immediately; running jobs still finish.
+    CancelPending,
+}
+
+/// Runs VM jobs on a fixed number of worker threads, starting queued jobs in
+/// submission order. Per-job timeouts stay with each job's reaper/kill plan.
+///
+/// [`JobPool::new`] preflights the backend once, so an unusable backend fails
+/// there instead of on every job. When a job fails later, the driver's
+/// [`VmDriver::preflight`] runs again: if it passes only that job fails,
+/// otherwise the backend is treated as gone and queued jobs and later
+/// submissions fail fast with its error. Dropping the pool behaves like
+/// [`ShutdownMode::CancelPending`].
+pub struct JobPool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+    preflight_warnings: Vec<String>,
+}
+
+pub struct JobHandle {
+    run_id: RunId,
+    rx: mpsc::Receiver<Result<RunOutput>>,
+    shared: Arc<Shared>,
+}
+
+struct QueuedJob {
+    spec: RunSpec,
+    tx: mpsc::Sender<Result<RunOutput>>,
+}
+
+struct State {
+    queue: VecDeque<QueuedJob>,
+    shutdown: bool,
+    backend_error: Option<String>,
+}
+
+struct Shared {
+    driver: Box<dyn VmDriver + Send + Sync>,
+    config: JobPoolConfig,
+    state: Mutex<State>,
+    cv: Condvar,
+}
+
+impl JobPool {
+    pub fn new(backend: VmBackend, max_concurrent: usize) -> Result<Self> {
+        let config = JobPoolConfig::from_env()?;
+        let driver = DefaultVmDriver::new(backend);
+        let preflight_warnings = driver.preflight(config.firecracker_cfg.as_ref())?;
+        let mut pool = Self::with_driver(driver, max_concurrent, config)?;
+        pool.preflight_warnings = preflight_warnings;
+        Ok(pool)
+    }
+
+    pub fn with_driver<D>(driver: D, max_concurrent: usize, config: JobPoolConfig) -> Result<Self>
+    where
+        D: VmDriver + Send + Sync + 'static,
+    {
+        if max_concurrent == 0 {
+            anyhow::bail!("JobPool max_concurrent must be >= 1");
+        }
+
+        let shared = Arc::new(Shared {
+            driver: Box::new(driver),
+            config,
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                shutdown: false,
+                backend_error: None,
+            }),
+            cv: Condvar::new(),
+        });
+
+        let mut workers = Vec::with_capacity(max_concurrent);
+        for idx in 0..max_concurrent {
+            let shared = Arc::clone(&shared);
+            let worker = std::thread::Builder::new()
+                .name(format!("x07-vm-job-pool-{idx}"))
+                .spawn(move || worker_loop(&shared))
+                .context("spawn job pool worker")?;
+            workers.push(worker);
+        }
+
+        Ok(Self {
+            shared,
+            workers,
+            preflight_warnings: Vec::new(),
+        })
+    }
+
+    pub fn submit(&self, spec: RunSpec) -> JobHandle {
+        let (tx, rx) = mpsc::channel();
+        let run_id = spec.run_id.clone();
+
+        let mut state = lock_state(&self.shared);
+        if let Some(err) = &state.backend_error {
+            let _ = tx.send(Err(backend_failed(err)));
+        } else if state.shutdown {
+            let _ = tx.send(Err(anyhow::anyhow!("job pool is shut down")));
+        } else {
+            state.queue.push_back(QueuedJob { spec, tx });
+            self.shared.cv.notify_one();
+        }
+
+        JobHandle {
+            run_id,
+            rx,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Non-fatal findings of the [`JobPool::new`] preflight.
+    pub fn preflight_warnings(&self) -> &[String] {
+        &self.preflight_warnings
+    }
+
+    /// Number of jobs waiting for a free slot.
+    pub fn queued(&self) -> usize {
+        lock_state(&self.shared).queue.len()
+    }
+
+    pub fn shutdown(mut self, mode: ShutdownMode) {
+        self.shutdown_inner(mode);
+    }
+
+    fn shutdown_inner(&mut self, mode: ShutdownMode) {
+        {
+            let mut state = lock_state(&self.shared);
+            state.shutdown = true;
+            if mode == ShutdownMode::CancelPending {
+                for job in state.queue.drain(..) {
+                    let _ = job
+                        .tx
+                        .send(Err(anyhow::anyhow!("job cancelled: pool shut down")));
+                }
+            }
+            self.shared.cv.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for JobPool {
+    fn drop(&mut self) {
+        self.shutdown_inner(ShutdownMode::CancelPending);
+    }
+}
+
+impl JobHandle {
+    pub fn run_id(&self) -> &RunId {
+        &self.run_id
+    }
+
+    /// Cancels the job. A job still queued is dropped without starting and its
+    /// [`JobHandle::wait`] fails; a started job goes through [`cancel_job`]
+    /// on its `job.json`, which fails if the driver has not written it yet.
+    pub fn cancel(&self, grace_ms: u64) -> Result<CancelOutcome> {
+        {
+            let mut state = lock_state(&self.shared);
+            if let Some(pos) = state
+                .queue
+                .iter()
+                .position(|job| job.spec.run_id == self.run_id)
+            {
+                if let Some(job) = state.queue.remove(pos) {
+                    let _ = job
+                        .tx
+                        .send(Err(anyhow::anyhow!("job cancelled before it started")));
+                }
+                return Ok(CancelOutcome::NotStarted);
+            }
+        }
+        let job_file = self
+            .shared
+            .config
+            .state_root
+            .join(&self.run_id)
+            .join("job.json");
+        cancel_job(&job_file, grace_ms, "job-pool")
+    }
+
+    pub fn wait(self) -> Result<RunOutput> {
+        self.rx
+            .recv()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("job pool dropped job {}", self.run_id)))
+    }
+}
+
+fn lock_state(shared: &Shared) -> std::sync::MutexGuard<'_, State> {
+    shared
+        .state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn backend_failed(err: &str) -> anyhow::Error {
+    anyhow::anyhow!("vm backend failed: {err}")
+}
+
+fn worker_loop(shared: &Shared) {
+    loop {
+        let job = {
+            let mut state = lock_state(shared);
+            loop {
+                if let Some(job) = state.queue.pop_front() {
+                    break job;
+                }
+                if state.shutdown {
+                    return;
+                }
+                state = shared
+                    .cv
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        };
+
+        // A local state dir problem fails just this job; a driver error
+        // re-checks the backend before the next job can hit it too.
+        let res = match create_state_dir(shared, &job.spec) {
+            Ok(state_dir) => {
+                let res = run_driver(shared, &job.spec, &state_dir);
+                if res.is_err() {
+                    check_backend(shared);
+                }
+                res
+            }
+            Err(err) => Err(err),
+        };
+        let _ = job.tx.send(res);
+    }
+}
+
+fn check_backend(shared: &Shared) {
+    let Err(err) = shared
+        .driver
+        .preflight(shared.config.firecracker_cfg.as_ref())
+    else {
+        return;
+    };
+    let msg = format!("{err:#}");
+    let mut state = lock_state(shared);
+    if state.backend_error.is_none() {
+        for queued in state.queue.drain(..) {
+            let _ = queued.tx.send(Err(backend_failed(&msg)));
+        }
+        state.backend_error = Some(msg);
+    }
+}
+
+fn create_state_dir(shared: &Shared, spec: &RunSpec) -> Result<PathBuf> {
+    let state_dir = shared.config.state_root.join(&spec.run_id);
+    std::fs::create_dir_all(&state_dir)
+        .with_context(|| format!("create job state dir: {}", state_dir.display()))?;
+    Ok(state_dir)
+}
+
+fn run_driver(shared: &Shared, spec: &RunSpec, state_dir: &Path) -> Result<RunOutput> {
+    let created_unix_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system time before unix epoch")?
+        .as_millis()
+        .try_into()
+        .unwrap_or(u64::MAX);
+    let deadline_unix_ms = created_unix_ms.saturating_add(spec.limits.wall_ms.max(1));
+
+    shared.driver.run_job(
+        spec,
+        VmJobRunParams {
+            state_root: &shared.config.state_root,
+            state_dir,
+            reaper_bin: &shared.config.reaper_bin,
+            created_unix_ms,
+            deadline_unix_ms,
+            firecracker_cfg: shared.config.firecracker_cfg.as_ref(),
+            spawn_reaper: true,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LimitsSpec, NetworkMode, VmCaps};
+    use std::collections::BTreeMap;
+    use std::io::ErrorKind;
+    use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let base = std::env::temp_dir();
+            let pid = std::process::id();
+
+            for _ in 0..256 {
+                let attempt_id = TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+                let path = base.join(format!("{prefix}_{pid}_{attempt_id}"));
+                match std::fs::create_dir(&path) {
+                    Ok(()) => return Self { path },
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                    Err(e) => panic!("create temp dir {path:?}: {e}"),
+                }
+            }
+
+            panic!("failed to create unique temp dir");
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    #[derive(Default)]
+    struct MockState {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+        started: Mutex<Vec<String>>,
+        backend_down: AtomicBool,
+    }
+
+    struct MockDriver {
+        state: Arc<MockState>,
+        run_ms: u64,
+        fail_run_id: Option<&'static str>,
+        /// The failing job also takes the backend down with it.
+        fail_kills_backend: bool,
+    }
+
+    impl VmDriver for MockDriver {
+        fn run_job(&self, spec: &RunSpec, _params: VmJobRunParams<'_>) -> Result<RunOutput> {
+            let active = self.state.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.state.max_active.fetch_max(active, Ordering::SeqCst);
+            self.state
+                .started
+                .lock()
+                .unwrap()
+                .push(spec.run_id.to_string());
+
+            std::thread::sleep(Duration::from_millis(self.run_ms));
+            self.state.active.fetch_sub(1, Ordering::SeqCst);
+
+            if self.fail_run_id == Some(spec.run_id.as_str()) {
+                if self.fail_kills_backend {
+                    self.state.backend_down.store(true, Ordering::SeqCst);
+                }
+                anyhow::bail!("driver failed");
+            }
+            Ok(RunOutput {
+                exit_status: 0,
+                exit_signal: None,
+                timed_out: false,
+                stdout: spec.run_id.as_str().as_bytes().to_vec(),
+                stderr: Vec::new(),
+                stdout_truncated: false,
+                stderr_truncated: false,
+                guest_report: None,
+                timeline: crate::JobTimeline::default(),
+                mount_digests: None,
+                persisted_output: None,
+                guest_clock_skew_ms: None,
+                dry_run: None,
+                warnings: Vec::new(),
+            })
+        }
+
+        fn capabilities(&self) -> VmCaps {
+            VmCaps::for_backend(VmBackend::Docker)
+        }
+
+        fn preflight(&self, _cfg: Option<&FirecrackerCtrConfig>) -> Result<Vec<String>> {
+            if self.state.backend_down.load(Ordering::SeqCst) {
+                anyhow::bail!("daemon not reachable");
+            }
+            Ok(Vec::new())
+        }
+    }
+
+    fn spec(run_id: &str) -> RunSpec {
+        RunSpec {
+            run_id: RunId::parse(run_id).unwrap(),
+            backend: VmBackend::Docker,
+            image: "x07-test".to_string(),
+            image_digest: None,
+            argv: vec!["true".to_string()],
+            env: BTreeMap::new(),
+            mounts: Vec::new(),
+            workdir: None,
+            limits: LimitsSpec {
+                wall_ms: 1_000,
+                grace_ms: 100,
+                cleanup_ms: 100,
+                mem_bytes: None,
+                vcpus: None,
+                max_stdout_bytes: 1024,
+                max_stderr_bytes: 1024,
+                network: NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+                shm_size_bytes: None,
+            },
+            digest_mounts: false,
+            dry_run: false,
+            report_file: None,
+        }
+    }
+
+    fn new_pool(
+        tmp: &TempDir,
+        state: &Arc<MockState>,
+        max_concurrent: usize,
+        run_ms: u64,
+        fail_run_id: Option<&'static str>,
+    ) -> JobPool {
+        new_pool_with(tmp, state, max_concurrent, run_ms, fail_run_id, false)
+    }
+
+    fn new_pool_with(
+        tmp: &TempDir,
+        state: &Arc<MockState>,
+        max_concurrent: usize,
+        run_ms: u64,
+        fail_run_id: Option<&'static str>,
+        fail_kills_backend: bool,
+    ) -> JobPool {
+        JobPool::with_driver(
+            MockDriver {
+                state: Arc::clone(state),
+                run_ms,
+                fail_run_id,
+                fail_kills_backend,
+            },
+            max_concurrent,
+            JobPoolConfig {
+                state_root: tmp.path.clone(),
+                reaper_bin: PathBuf::from("x07-vm-reaper"),
+                firecracker_cfg: None,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn concurrency_never_exceeds_bound() {
+        let tmp = TempDir::new("x07_vm_job_pool");
+        let state = Arc::new(MockState::default());
+        let pool = new_pool(&tmp, &state, 3, 20, None);
+
+        let handles: Vec<JobHandle> = (0..12)
+            .map(|i| pool.submit(spec(&format!("job{i}"))))
+            .collect();
+        for h in handles {
+            let run_id = h.run_id().to_string();
+            let out = h.wait().unwrap();
+            assert_eq!(out.stdout, run_id.as_bytes());
+        }
+
+        let max_active = state.max_active.load(Ordering::SeqCst);
+        assert!((1..=3).contains(&max_active), "max_active={max_active}");
+        assert!(tmp.path.join("job0").is_dir());
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn queued_jobs_start_in_fifo_order() {
+        let tmp = TempDir::new("x07_vm_job_pool");
+        let state = Arc::new(MockState::default());
+        let pool = new_pool(&tmp, &state, 1, 1, None);
+
+        let ids: Vec<String> = (0..8).map(|i| format!("fifo{i}")).collect();
+        let handles: Vec<JobHandle> = ids.iter().map(|id| pool.submit(spec(id))).collect();
+        for h in handles {
+            h.wait().unwrap();
+        }
+
+        assert_eq!(*state.started.lock().unwrap(), ids);
+    }
+
+    #[test]
+    fn driver_error_fails_only_its_job() {
+        let tmp = TempDir::new("x07_vm_job_pool");
+        let state = Arc::new(MockState::default());
+        let pool = new_pool(&tmp, &state, 1, 5, Some("bad"));
+
+        let bad = pool.submit(spec("bad"));
+        let queued: Vec<JobHandle> = (0..4)
+            .map(|i| pool.submit(spec(&format!("later{i}"))))
+            .collect();
+
+        let err = bad.wait().unwrap_err();
+        assert!(format!("{err:#}").contains("driver failed"), "{err:#}");
+        for h in queued {
+            let run_id = h.run_id().to_string();
+            assert_eq!(h.wait().unwrap().stdout, run_id.as_bytes());
+        }
+        let out = pool.submit(spec("after")).wait().unwrap();
+        assert_eq!(out.stdout, b"after");
+
+        assert_eq!(state.started.lock().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn backend_failure_fails_queued_jobs_fast() {
+        let tmp = TempDir::new("x07_vm_job_pool");
+        let state = Arc::new(MockState::default());
+        let pool = new_pool_with(&tmp, &state, 1, 20, Some("bad"), true);
+
+        let bad = pool.submit(spec("bad"));
+        let queued: Vec<JobHandle> = (0..4)
+            .map(|i| pool.submit(spec(&format!("later{i}"))))
+            .collect();
+
+        let err = bad.wait().unwrap_err();
+        assert!(format!("{err:#}").contains("driver failed"), "{err:#}");
+        for h in queued {
+            let err = h.wait().unwrap_err();
+            let msg = format!("{err:#}");
+            assert!(msg.contains("vm backend failed"), "{msg}");
+            assert!(msg.contains("daemon not reachable"), "{msg}");
+        }
+        let err = pool.submit(spec("after")).wait().unwrap_err();
+        assert!(format!("{err:#}").contains("vm backend failed"), "{err:#}");
+
+        assert_eq!(*state.started.lock().unwrap(), vec!["bad".to_string()]);
+    }
+
+    #[test]
+    fn cancel_drops_queued_job_before_it_starts() {
+        let tmp = TempDir::new("x07_vm_job_pool");
+        let state = Arc::new(MockState::default());
+        let pool = new_pool(&tmp, &state, 1, 50, None);
+
+        let running = pool.submit(spec("busy"));
+        while state.started.lock().unwrap().is_empty() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let queued = pool.submit(spec("queued"));
+        assert_eq!(queued.cancel(100).unwrap(), CancelOutcome::NotStarted);
+        assert_eq!(pool.queued(), 0);
+
+        running.wait().unwrap();
+        let err = queued.wait().unwrap_err();
+        assert!(format!("{err:#}").contains("cancelled"), "{err:#}");
+        assert_eq!(*state.started.lock().unwrap(), vec!["busy".to_string()]);
+    }
+
+    #[test]
+    fn shutdown_drains_or_cancels_pending() {
+        let tmp = TempDir::new("x07_vm_job_pool");
+
+        let state = Arc::new(MockState::default());
+        let pool = new_pool(&tmp, &state, 1, 5, None);
+        let handles: Vec<JobHandle> = (0..4)
+            .map(|i| pool.submit(spec(&format!("drain{i}"))))
+            .collect();
+        pool.shutdown(ShutdownMode::Drain);
+        for h in handles {
+            h.wait().unwrap();
+        }
+        assert_eq!(state.started.lock().unwrap().len(), 4);
+
+        let state = Arc::new(MockState::default());
+        let pool = new_pool(&tmp, &state, 1, 50, None);
+        let first = pool.submit(spec("cancel0"));
+        while state.started.lock().unwrap().is_empty() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let pending: Vec<JobHandle> = (1..4)
+            .map(|i| pool.submit(spec(&format!("cancel{i}"))))
+            .collect();
+        assert_eq!(pool.queued(), 3);
+        pool.shutdown(ShutdownMode::CancelPending);
+
+        first.wait().unwrap();
+        for h in pending {
+            let err = h.wait().unwrap_err();
+            assert!(format!("{err:#}").contains("cancelled"), "{err:#}");
+        }
+        assert_eq!(*state.started.lock().unwrap(), vec!["cancel0".to_string()]);
+    }
+}
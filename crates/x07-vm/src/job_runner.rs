@@ -2,15 +2,26 @@ use std::path::Path;
 
 use anyhow::{Context, Result};
 
+use crate::cancel::keep_recorded_cancellation;
+use crate::clock_skew::{read_guest_clock, skew_warning, ContainerClockProbe};
+use crate::dry_run::{backend_run_plan, dry_run_output};
+use crate::guest_report::report_file_host_path;
+use crate::kill_plan::read_warned_marker;
+use crate::output_persist::OutputPersist;
 use crate::{
-    apple_container_cleanup, apple_container_hard_kill, container_id_from_run_id, docker_cleanup,
-    docker_hard_kill, firecracker_ctr_cleanup, firecracker_ctr_config_from_env,
-    firecracker_ctr_hard_kill, podman_cleanup, podman_hard_kill, run_apple_container,
-    run_apple_container_passthrough, run_docker, run_docker_passthrough, run_firecracker_ctr,
-    run_firecracker_ctr_passthrough, run_podman, run_podman_passthrough, spawn_reaper,
-    spawn_vz_helper, spawn_vz_helper_passthrough, sweep_orphans_best_effort, touch_done_marker,
-    vz_cleanup_scratch, wait_child_output_capped, wait_child_passthrough, write_job_file,
-    x07_label_set, CtrJob, FirecrackerCtrConfig, RunOutput, RunSpec, VmBackend, VmCaps, VmJob,
+    apple_container_cleanup, apple_container_hard_kill, clock_probe_from_env,
+    clock_skew_warn_ms_from_env, container_id_from_run_id, digest_mounts, docker_cleanup,
+    docker_hard_kill, ensure_image_timed, firecracker_ctr_cleanup, firecracker_ctr_config_from_env,
+    firecracker_ctr_hard_kill, guest_clock_skew_ms, max_mount_digest_bytes_from_env,
+    max_report_file_bytes_from_env, podman_cleanup, podman_hard_kill, preflight_vm_backend,
+    read_guest_report_file, resolve_guest_report, run_apple_container_passthrough,
+    run_apple_container_timed, run_docker_like, run_docker_passthrough,
+    run_firecracker_ctr_passthrough, run_firecracker_ctr_timed, run_podman_passthrough,
+    spawn_reaper, spawn_vz_helper, spawn_vz_helper_passthrough, sweep_orphans_best_effort,
+    touch_done_marker, validate_guest_stdout, validate_shm_size, vz_cleanup_scratch, vz_job_mounts,
+    vz_workload_limits, wait_child_output_capped_timed, wait_child_passthrough, write_job_file,
+    write_job_spec, x07_label_set, ClockWindow, CtrJob, FirecrackerCtrConfig, JobEventKind,
+    JobTimelineRecorder, RunOutput, RunSpec, VmBackend, VmCaps, VmJob,
 };
 
 pub struct VmJobRunParams<'a> {
@@ -20,12 +31,26 @@ pub struct VmJobRunParams<'a> {
     pub created_unix_ms: u64,
     pub deadline_unix_ms: u64,
     pub firecracker_cfg: Option<&'a FirecrackerCtrConfig>,
+    /// Normally `true`. When `false`, no job file is written and no reaper is
+    /// forked: the in-process wall timeout and cleanup are the only
+    /// enforcement, so only use it for short `--rm` runs. If the parent
+    /// crashes mid-job nothing external kills the container, and neither
+    /// [`crate::sweep_orphans_best_effort`] nor [`crate::cancel_job`] can
+    /// find it.
+    pub spawn_reaper: bool,
 }
 
 pub trait VmDriver {
     fn run_job(&self, spec: &RunSpec, params: VmJobRunParams<'_>) -> Result<RunOutput>;
 
     fn capabilities(&self) -> VmCaps;
+
+    /// Checks that the backend is usable at all, returning non-fatal findings.
+    /// [`crate::JobPool`] also calls it after a failed job to tell a dead
+    /// backend from a job-level error.
+    fn preflight(&self, _firecracker_cfg: Option<&FirecrackerCtrConfig>) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -71,6 +96,10 @@ impl VmDriver for DefaultVmDriver {
     fn capabilities(&self) -> VmCaps {
         VmCaps::for_backend(self.backend)
     }
+
+    fn preflight(&self, firecracker_cfg: Option<&FirecrackerCtrConfig>) -> Result<Vec<String>> {
+        preflight_vm_backend(self.backend, firecracker_cfg)
+    }
 }
 
 pub fn run_vm_job(spec: &RunSpec, params: VmJobRunParams<'_>) -> Result<RunOutput> {
@@ -92,7 +121,36 @@ fn run_vm_job_mode(
     params: VmJobRunParams<'_>,
     io_mode: VmIoMode,
 ) -> Result<RunOutput> {
-    let container_id = container_id_from_run_id(&spec.run_id)?;
+    spec.validate_for(spec.backend)?;
+    let container_id = container_id_from_run_id(spec.run_id.as_str())?;
+    validate_shm_size(&spec.limits)?;
+    let mut warnings = Vec::new();
+    if spec.limits.shm_size_bytes.is_some()
+        && !matches!(spec.backend, VmBackend::Docker | VmBackend::Podman)
+    {
+        warnings.push(format!(
+            "shm_size_bytes is ignored by the {} backend (the guest sizes its own /dev/shm)",
+            spec.backend
+        ));
+    }
+    if spec.limits.warn_before_deadline_ms.is_some() && spec.backend == VmBackend::AppleContainer {
+        warnings.push(format!(
+            "warn_before_deadline_ms is ignored by the {} backend",
+            spec.backend
+        ));
+    }
+
+    // `spec.json` records the spec as given: a replay gets its own log dir.
+    let requested = spec;
+    let with_log_dir;
+    let spec = if spec.limits.persist_output && spec.limits.persist_output_dir.is_none() {
+        let mut s = spec.clone();
+        s.limits.persist_output_dir = Some(params.state_dir.join(crate::PERSISTED_OUTPUT_DIR_NAME));
+        with_log_dir = s;
+        &with_log_dir
+    } else {
+        spec
+    };
 
     let job_file = params.state_dir.join("job.json");
     let done_marker = params.state_dir.join("done");
@@ -104,6 +162,7 @@ fn run_vm_job_mode(
         params.created_unix_ms,
         params.deadline_unix_ms,
         spec.image_digest.as_deref(),
+        &job_file,
     )?;
 
     let firecracker_cfg = if spec.backend == VmBackend::FirecrackerCtr {
@@ -117,17 +176,56 @@ fn run_vm_job_mode(
         None
     };
 
+    if spec.dry_run {
+        let plan = backend_run_plan(
+            spec,
+            &container_id,
+            &labels,
+            firecracker_cfg.as_ref(),
+            params.state_dir,
+            io_mode == VmIoMode::Passthrough,
+        )?;
+        let mut out = dry_run_output(plan);
+        out.warnings = warnings;
+        return Ok(out);
+    }
+
     let _ = sweep_orphans_best_effort(params.state_root, spec.backend, firecracker_cfg.as_ref());
 
     let grace_ms = spec.limits.grace_ms;
     let cleanup_ms = spec.limits.cleanup_ms;
 
-    let out = match spec.backend {
+    let mount_digests = if spec.digest_mounts {
+        let digests = digest_mounts(&spec.mounts, max_mount_digest_bytes_from_env())?;
+        for m in &digests.skipped {
+            warnings.push(format!(
+                "mount {} is larger than {} bytes; not digested",
+                m.host_path.display(),
+                digests.max_digest_bytes
+            ));
+        }
+        Some(digests)
+    } else {
+        None
+    };
+    let spec_file = write_job_spec(
+        params.state_dir,
+        requested,
+        mount_digests.as_ref(),
+        firecracker_cfg.as_ref(),
+    )?;
+
+    let timeline = JobTimelineRecorder::start();
+
+    let (mut job, mut out) = match spec.backend {
         VmBackend::Vz => {
+            let persist = OutputPersist::for_limits(&spec.limits)?;
+            timeline.record(JobEventKind::CreateStarted);
             let spawned = match io_mode {
                 VmIoMode::Capture => spawn_vz_helper(spec, params.state_dir)?,
                 VmIoMode::Passthrough => spawn_vz_helper_passthrough(spec, params.state_dir)?,
             };
+            timeline.record(JobEventKind::TaskStarted);
 
             let job = VmJob {
                 schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
@@ -140,23 +238,35 @@ fn run_vm_job_mode(
                 grace_ms,
                 cleanup_ms,
                 ctr: None,
+                timeline: None,
+                mount_digests: mount_digests.clone(),
+                cancelled: false,
+                cancellation: None,
+                vz_workload_limits: Some(vz_workload_limits(&spec.limits)?),
+                persisted_output: None,
+                guest_clock_skew_ms: None,
+                spec_file: Some(spec_file.clone()),
+                warn_before_deadline_ms: spec.limits.warn_before_deadline_ms,
+                warned: false,
+                warned_unix_ms: None,
             };
-            write_job_file(&job_file, &job)?;
-            spawn_reaper(params.reaper_bin, &job_file)?;
+            start_reaper(&params, &job_file, &job)?;
 
             let out = match io_mode {
-                VmIoMode::Capture => wait_child_output_capped(
+                VmIoMode::Capture => wait_child_output_capped_timed(
                     spawned.child,
                     spec.limits.wall_ms,
                     spec.limits.max_stdout_bytes,
                     spec.limits.max_stderr_bytes,
+                    persist.as_ref(),
+                    &timeline,
                 )?,
                 VmIoMode::Passthrough => {
                     wait_child_passthrough(spawned.child, spec.limits.wall_ms)?
                 }
             };
             let _ = vz_cleanup_scratch(params.state_dir);
-            out
+            (job, out)
         }
 
         VmBackend::AppleContainer => {
@@ -171,15 +281,29 @@ fn run_vm_job_mode(
                 grace_ms,
                 cleanup_ms,
                 ctr: None,
+                timeline: None,
+                mount_digests: mount_digests.clone(),
+                cancelled: false,
+                cancellation: None,
+                vz_workload_limits: None,
+                persisted_output: None,
+                guest_clock_skew_ms: None,
+                spec_file: Some(spec_file.clone()),
+                warn_before_deadline_ms: spec.limits.warn_before_deadline_ms,
+                warned: false,
+                warned_unix_ms: None,
             };
-            write_job_file(&job_file, &job)?;
-            spawn_reaper(params.reaper_bin, &job_file)?;
-            match io_mode {
-                VmIoMode::Capture => run_apple_container(spec, &container_id, &labels)?,
+            start_reaper(&params, &job_file, &job)?;
+            timeline.record(JobEventKind::CreateStarted);
+            let out = match io_mode {
+                VmIoMode::Capture => {
+                    run_apple_container_timed(spec, &container_id, &labels, &timeline)?
+                }
                 VmIoMode::Passthrough => {
                     run_apple_container_passthrough(spec, &container_id, &labels)?
                 }
-            }
+            };
+            (job, out)
         }
 
         VmBackend::Docker => {
@@ -194,13 +318,30 @@ fn run_vm_job_mode(
                 grace_ms,
                 cleanup_ms,
                 ctr: None,
+                timeline: None,
+                mount_digests: mount_digests.clone(),
+                cancelled: false,
+                cancellation: None,
+                vz_workload_limits: None,
+                persisted_output: None,
+                guest_clock_skew_ms: None,
+                spec_file: Some(spec_file.clone()),
+                warn_before_deadline_ms: spec.limits.warn_before_deadline_ms,
+                warned: false,
+                warned_unix_ms: None,
             };
-            write_job_file(&job_file, &job)?;
-            spawn_reaper(params.reaper_bin, &job_file)?;
-            match io_mode {
-                VmIoMode::Capture => run_docker(spec, &container_id, &labels)?,
+            start_reaper(&params, &job_file, &job)?;
+            timeline.record(JobEventKind::CreateStarted);
+            let probe =
+                clock_probe_from_env().then(|| ContainerClockProbe::start("docker", &container_id));
+            let mut out = match io_mode {
+                VmIoMode::Capture => {
+                    run_docker_like("docker", spec, &container_id, &labels, false, &timeline)?
+                }
                 VmIoMode::Passthrough => run_docker_passthrough(spec, &container_id, &labels)?,
-            }
+            };
+            out.guest_clock_skew_ms = probe.and_then(ContainerClockProbe::finish);
+            (job, out)
         }
 
         VmBackend::Podman => {
@@ -215,19 +356,37 @@ fn run_vm_job_mode(
                 grace_ms,
                 cleanup_ms,
                 ctr: None,
+                timeline: None,
+                mount_digests: mount_digests.clone(),
+                cancelled: false,
+                cancellation: None,
+                vz_workload_limits: None,
+                persisted_output: None,
+                guest_clock_skew_ms: None,
+                spec_file: Some(spec_file.clone()),
+                warn_before_deadline_ms: spec.limits.warn_before_deadline_ms,
+                warned: false,
+                warned_unix_ms: None,
             };
-            write_job_file(&job_file, &job)?;
-            spawn_reaper(params.reaper_bin, &job_file)?;
-            match io_mode {
-                VmIoMode::Capture => run_podman(spec, &container_id, &labels)?,
+            start_reaper(&params, &job_file, &job)?;
+            timeline.record(JobEventKind::CreateStarted);
+            let probe =
+                clock_probe_from_env().then(|| ContainerClockProbe::start("podman", &container_id));
+            let mut out = match io_mode {
+                VmIoMode::Capture => {
+                    run_docker_like("podman", spec, &container_id, &labels, true, &timeline)?
+                }
                 VmIoMode::Passthrough => run_podman_passthrough(spec, &container_id, &labels)?,
-            }
+            };
+            out.guest_clock_skew_ms = probe.and_then(ContainerClockProbe::finish);
+            (job, out)
         }
 
         VmBackend::FirecrackerCtr => {
             let cfg = firecracker_cfg
                 .as_ref()
                 .context("internal error: firecracker cfg missing")?;
+            ensure_image_timed(cfg, &spec.image, &timeline)?;
 
             let job = VmJob {
                 schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
@@ -243,19 +402,57 @@ fn run_vm_job_mode(
                     bin: cfg.bin.to_string_lossy().to_string(),
                     address: cfg.address.clone(),
                     namespace: cfg.namespace.clone(),
+                    runtime: cfg.runtime.clone(),
+                    snapshotter: cfg.snapshotter.clone(),
                 }),
+                timeline: None,
+                mount_digests: mount_digests.clone(),
+                cancelled: false,
+                cancellation: None,
+                vz_workload_limits: None,
+                persisted_output: None,
+                guest_clock_skew_ms: None,
+                spec_file: Some(spec_file.clone()),
+                warn_before_deadline_ms: spec.limits.warn_before_deadline_ms,
+                warned: false,
+                warned_unix_ms: None,
             };
-            write_job_file(&job_file, &job)?;
-            spawn_reaper(params.reaper_bin, &job_file)?;
+            start_reaper(&params, &job_file, &job)?;
 
-            match io_mode {
-                VmIoMode::Capture => run_firecracker_ctr(spec, cfg, &container_id, &labels)?,
+            timeline.record(JobEventKind::CreateStarted);
+            let out = match io_mode {
+                VmIoMode::Capture => {
+                    run_firecracker_ctr_timed(spec, cfg, &container_id, &labels, &timeline)?
+                }
                 VmIoMode::Passthrough => {
                     run_firecracker_ctr_passthrough(spec, cfg, &container_id, &labels)?
                 }
-            }
+            };
+            (job, out)
         }
     };
+    timeline.record_once(JobEventKind::Exited);
+
+    if spec.backend == VmBackend::Vz {
+        let exec_start = vz_job_mounts(spec)
+            .ok()
+            .and_then(|(_, job_out, _)| read_guest_clock(&job_out, spec.run_id.as_str()));
+        let window = ClockWindow::from_timeline(
+            &timeline.snapshot(),
+            JobEventKind::TaskStarted,
+            JobEventKind::Exited,
+        );
+        if let (Some(exec_start), Some(window)) = (exec_start, window) {
+            out.guest_clock_skew_ms = Some(guest_clock_skew_ms(exec_start, window));
+        }
+    }
+    if let Some(skew_ms) = out.guest_clock_skew_ms {
+        warnings.extend(skew_warning(skew_ms, clock_skew_warn_ms_from_env()));
+    }
+
+    if io_mode == VmIoMode::Capture && !out.timed_out {
+        attach_guest_report(spec, &mut out, &mut warnings);
+    }
 
     if out.timed_out {
         match spec.backend {
@@ -305,6 +502,362 @@ fn run_vm_job_mode(
         }
     }
 
+    timeline.record(JobEventKind::CleanupDone);
+    out.timeline = timeline.snapshot();
+    out.mount_digests = mount_digests;
+    out.warnings = warnings;
+    job.timeline = Some(out.timeline.clone());
+    job.persisted_output = out.persisted_output.clone();
+    job.guest_clock_skew_ms = out.guest_clock_skew_ms;
+    if let Some(warned_unix_ms) = read_warned_marker(params.state_dir) {
+        job.warned = true;
+        job.warned_unix_ms = Some(warned_unix_ms);
+    }
+    let recorded = if params.spawn_reaper {
+        keep_recorded_cancellation(&job_file, &mut job);
+        write_job_file(&job_file, &job)
+    } else {
+        Ok(())
+    };
+
     touch_done_marker(&done_marker)?;
+    recorded.context("record finished job")?;
     Ok(out)
 }
+
+/// Writes the job file and forks its reaper, unless
+/// [`VmJobRunParams::spawn_reaper`] is off.
+fn start_reaper(params: &VmJobRunParams<'_>, job_file: &Path, job: &VmJob) -> Result<()> {
+    if !params.spawn_reaper {
+        return Ok(());
+    }
+    write_job_file(job_file, job)?;
+    spawn_reaper(params.reaper_bin, job_file)
+}
+
+/// Sets `out.guest_report` from the report file or stdout. A report taken
+/// from stdout in place of a missing report file is a run warning.
+fn attach_guest_report(spec: &RunSpec, out: &mut RunOutput, warnings: &mut Vec<String>) {
+    if let Some(report_file) = spec.report_file.as_deref() {
+        let file = match report_file_host_path(spec) {
+            Some(host_path) => {
+                read_guest_report_file(&host_path, report_file, max_report_file_bytes_from_env())
+            }
+            None => Ok(None),
+        };
+        let (report, warning) =
+            resolve_guest_report(report_file, file, &out.stdout, out.stdout_truncated);
+        warnings.extend(warning);
+        out.guest_report = Some(report);
+    } else if matches!(spec.backend, VmBackend::Vz | VmBackend::FirecrackerCtr) {
+        out.guest_report = Some(validate_guest_stdout(&out.stdout, out.stdout_truncated));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RunId;
+
+    fn job() -> VmJob {
+        VmJob {
+            schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
+            run_id: RunId::parse("x07-job-runner-test").unwrap(),
+            backend: VmBackend::Docker,
+            container_id: "x07-job-runner-test".to_string(),
+            pid: None,
+            created_unix_ms: 0,
+            deadline_unix_ms: 0,
+            grace_ms: 0,
+            cleanup_ms: 0,
+            ctr: None,
+            timeline: None,
+            mount_digests: None,
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: None,
+            warned: false,
+            warned_unix_ms: None,
+        }
+    }
+
+    fn params<'a>(state_dir: &'a Path, reaper_bin: &'a Path, spawn: bool) -> VmJobRunParams<'a> {
+        VmJobRunParams {
+            state_root: state_dir,
+            state_dir,
+            reaper_bin,
+            created_unix_ms: 0,
+            deadline_unix_ms: 0,
+            firecracker_cfg: None,
+            spawn_reaper: spawn,
+        }
+    }
+
+    #[test]
+    fn disabled_reaper_writes_no_job_file_and_forks_nothing() {
+        let dir = std::env::temp_dir().join(format!("x07_vm_job_runner_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let job_file = dir.join("job.json");
+        // Spawning this would fail, so Ok means no reaper was started.
+        let reaper_bin = dir.join("no-such-reaper");
+
+        start_reaper(&params(&dir, &reaper_bin, false), &job_file, &job()).unwrap();
+        assert!(!job_file.exists());
+
+        let err = start_reaper(&params(&dir, &reaper_bin, true), &job_file, &job()).unwrap_err();
+        assert!(format!("{err:#}").contains("spawn reaper"), "{err:#}");
+        assert!(job_file.is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_report_file_falls_back_to_stdout_with_a_run_warning() {
+        let dir =
+            std::env::temp_dir().join(format!("x07_vm_report_fallback_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let spec = RunSpec {
+            run_id: RunId::parse("x07-report-fallback").unwrap(),
+            backend: VmBackend::Docker,
+            image: "x07-test".to_string(),
+            image_digest: None,
+            argv: vec!["true".to_string()],
+            env: Default::default(),
+            mounts: vec![crate::MountSpec {
+                host_path: dir.clone(),
+                guest_path: Path::new(crate::JOB_GUEST_OUT_DIR).to_path_buf(),
+                readonly: false,
+                kind: crate::MountKind::Bind,
+            }],
+            workdir: None,
+            limits: crate::LimitsSpec {
+                wall_ms: 1_000,
+                grace_ms: 100,
+                cleanup_ms: 100,
+                mem_bytes: None,
+                shm_size_bytes: None,
+                vcpus: None,
+                max_stdout_bytes: 1024,
+                max_stderr_bytes: 1024,
+                network: crate::NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+            },
+            digest_mounts: false,
+            dry_run: false,
+            report_file: Some(Path::new(crate::JOB_GUEST_OUT_DIR).join(crate::JOB_REPORT_FILE)),
+        };
+        let mut stdout = serde_json::to_vec(&serde_json::json!({
+            "schema_version": x07_contracts::X07_OS_RUNNER_REPORT_SCHEMA_VERSION,
+            "ok": true,
+        }))
+        .unwrap();
+        stdout.push(b'\n');
+        let mut out = RunOutput {
+            exit_status: 0,
+            exit_signal: None,
+            timed_out: false,
+            stdout,
+            stderr: Vec::new(),
+            stdout_truncated: false,
+            stderr_truncated: false,
+            guest_report: None,
+            timeline: crate::JobTimeline::default(),
+            mount_digests: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            dry_run: None,
+            warnings: Vec::new(),
+        };
+
+        let mut warnings = Vec::new();
+        attach_guest_report(&spec, &mut out, &mut warnings);
+        let report = out.guest_report.expect("guest report").unwrap();
+        assert_eq!(report.json["ok"], true);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(
+            warnings[0].contains("is missing; using the report from stdout"),
+            "{warnings:?}"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Image with `/bin/sh`, `head` and `tr` (e.g. `busybox`) to run a
+    /// docker job against; the test is skipped without it.
+    const ENV_E2E_DOCKER_IMAGE: &str = "X07_VM_E2E_DOCKER_IMAGE";
+
+    #[test]
+    #[ignore]
+    fn docker_report_file_carries_a_report_past_the_stdout_cap() {
+        let Ok(image) = std::env::var(ENV_E2E_DOCKER_IMAGE) else {
+            return;
+        };
+        let blob_bytes: usize = 6 * 1024 * 1024;
+        let run_id = RunId::parse(&format!("x07-report-file-{}", std::process::id())).unwrap();
+        let state_root = std::env::temp_dir().join("x07_vm_report_file_e2e");
+        let ws = crate::JobWorkspace::create(&state_root, &run_id).unwrap();
+
+        let env = crate::ENV_GUEST_REPORT_FILE;
+        let script = format!(
+            r#"f="${env}"; printf '{{"schema_version":"%s","blob":"' "$0" > "$f"; head -c {blob_bytes} /dev/zero | tr '\000' A >> "$f"; printf '"}}\n' >> "$f""#
+        );
+        let spec = RunSpec {
+            run_id: run_id.clone(),
+            backend: VmBackend::Docker,
+            image,
+            image_digest: None,
+            argv: vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                script,
+                x07_contracts::X07_OS_RUNNER_REPORT_SCHEMA_VERSION.to_string(),
+            ],
+            env: Default::default(),
+            mounts: ws.mounts(),
+            workdir: None,
+            limits: crate::LimitsSpec {
+                wall_ms: 60_000,
+                grace_ms: 1_000,
+                cleanup_ms: 5_000,
+                mem_bytes: None,
+                shm_size_bytes: None,
+                vcpus: None,
+                max_stdout_bytes: 1024,
+                max_stderr_bytes: 64 * 1024,
+                network: crate::NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+            },
+            digest_mounts: false,
+            dry_run: false,
+            report_file: Some(Path::new(crate::JOB_GUEST_OUT_DIR).join(crate::JOB_REPORT_FILE)),
+        };
+
+        let reaper_bin = ws.root().join("no-such-reaper");
+        let out = run_vm_job(
+            &spec,
+            VmJobRunParams {
+                state_root: &state_root,
+                state_dir: ws.root(),
+                reaper_bin: &reaper_bin,
+                created_unix_ms: 0,
+                deadline_unix_ms: u64::MAX,
+                firecracker_cfg: None,
+                spawn_reaper: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            out.exit_status,
+            0,
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        assert!(out.stdout.is_empty());
+        let report = out.guest_report.expect("guest report").unwrap();
+        assert_eq!(report.json["blob"].as_str().unwrap().len(), blob_bytes);
+        ws.discard().unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    fn docker_deadline_warning_lets_the_workload_flush() {
+        let Ok(image) = std::env::var(ENV_E2E_DOCKER_IMAGE) else {
+            return;
+        };
+        let run_id = RunId::parse(&format!("x07-deadline-warn-{}", std::process::id())).unwrap();
+        let state_root = std::env::temp_dir().join("x07_vm_deadline_warn_e2e");
+        let ws = crate::JobWorkspace::create(&state_root, &run_id).unwrap();
+
+        let limits = crate::LimitsSpec {
+            wall_ms: 10_000,
+            grace_ms: 1_000,
+            cleanup_ms: 5_000,
+            mem_bytes: None,
+            shm_size_bytes: None,
+            vcpus: None,
+            max_stdout_bytes: 64 * 1024,
+            max_stderr_bytes: 64 * 1024,
+            network: crate::NetworkMode::None,
+            persist_output: false,
+            persist_output_dir: None,
+            warn_before_deadline_ms: Some(6_000),
+        };
+        let spec = RunSpec {
+            run_id: run_id.clone(),
+            backend: VmBackend::Docker,
+            image,
+            image_digest: None,
+            argv: vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                "trap 'echo flushed; exit 0' TERM; echo started; while :; do sleep 0.1; done"
+                    .to_string(),
+            ],
+            env: Default::default(),
+            mounts: ws.mounts(),
+            workdir: None,
+            limits: limits.clone(),
+            digest_mounts: false,
+            dry_run: false,
+            report_file: None,
+        };
+
+        // Stand in for the reaper: same job, same kill plan.
+        let created_unix_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let deadline_unix_ms = created_unix_ms + limits.wall_ms;
+        let reaper_job = VmJob {
+            run_id: run_id.clone(),
+            container_id: container_id_from_run_id(run_id.as_str()).unwrap(),
+            created_unix_ms,
+            deadline_unix_ms,
+            grace_ms: limits.grace_ms,
+            cleanup_ms: limits.cleanup_ms,
+            warn_before_deadline_ms: limits.warn_before_deadline_ms,
+            ..job()
+        };
+        let state_dir = ws.root().to_path_buf();
+        let reaper = std::thread::spawn(move || {
+            crate::enforce_kill_plan_for_job(&reaper_job, &state_dir, &state_dir.join("done"))
+        });
+
+        let reaper_bin = ws.root().join("no-such-reaper");
+        let out = run_vm_job(
+            &spec,
+            VmJobRunParams {
+                state_root: &state_root,
+                state_dir: ws.root(),
+                reaper_bin: &reaper_bin,
+                created_unix_ms,
+                deadline_unix_ms,
+                firecracker_cfg: None,
+                spawn_reaper: false,
+            },
+        )
+        .unwrap();
+        let res = reaper.join().unwrap().unwrap();
+
+        assert!(!out.timed_out);
+        assert_eq!(
+            out.exit_status,
+            0,
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "started\nflushed\n");
+        assert_eq!(res, crate::KillResult::CompletedBeforeDeadline);
+        let warned = read_warned_marker(ws.root()).expect("warned marker");
+        assert!(warned >= created_unix_ms && warned < deadline_unix_ms - limits.grace_ms);
+        ws.discard().unwrap();
+    }
+}
@@ -0,0 +1,502 @@
+//! Replayable job specs.
+//!
+//! Before launching, [`crate::run_vm_job`] writes the [`RunSpec`] it was given
+//! to `spec.json` in the job's state dir as a [`JobSpecDoc`], and points
+//! [`VmJob::spec_file`](crate::VmJob::spec_file) at it. Secret-looking env
+//! values are replaced with [`REDACTED`] (see [`redact_env_value`]), so the
+//! document is safe to keep around. [`replay_job_spec`] rebuilds the spec
+//! and runs it again under a fresh run id; redacted values must come back
+//! through [`ReplayOverrides::env`].
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    firecracker_ctr_config_from_job, redact_env_value, CtrJob, FirecrackerCtrConfig, LimitsSpec,
+    MountDigests, MountKind, MountSpec, RunId, RunOutput, RunSpec, VmBackend, VmDriver,
+    VmJobRunParams, REDACTED,
+};
+
+pub const VM_JOB_SPEC_SCHEMA_VERSION: &str = "x07.vm.job.spec@0.1.0";
+
+/// Name of the spec document inside a job's state dir.
+pub const JOB_SPEC_FILE_NAME: &str = "spec.json";
+
+/// Prefix of the run ids [`replay_job_spec`] generates.
+const REPLAY_RUN_ID_PREFIX: &str = "x07-replay";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobSpecDoc {
+    pub schema_version: String,
+    pub run_id: RunId,
+    pub backend: VmBackend,
+    pub image: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_digest: Option<String>,
+    pub argv: Vec<String>,
+    /// Secret-looking values are [`REDACTED`].
+    pub env: BTreeMap<String, String>,
+    pub mounts: Vec<JobSpecMount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<PathBuf>,
+    pub limits: LimitsSpec,
+    pub digest_mounts: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_file: Option<PathBuf>,
+    /// `firecracker-ctr` only: the backend config the job ran with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ctr: Option<CtrJob>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobSpecMount {
+    pub host_path: PathBuf,
+    pub guest_path: PathBuf,
+    pub readonly: bool,
+    pub kind: MountKind,
+    /// Manifest digest of `host_path` when [`RunSpec::digest_mounts`] was on
+    /// and the mount was small enough to digest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_sha256: Option<String>,
+}
+
+/// Changes applied to a recorded spec before it is replayed.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOverrides {
+    /// Set or replace env vars; every [`REDACTED`] value needs an entry.
+    pub env: BTreeMap<String, String>,
+    /// Defaults to a freshly generated `x07-replay-…` id. Must differ from
+    /// the recorded run id.
+    pub run_id: Option<RunId>,
+}
+
+pub struct ReplayParams<'a> {
+    pub state_root: &'a Path,
+    pub reaper_bin: &'a Path,
+    /// Used instead of the recorded [`JobSpecDoc::ctr`] when set.
+    pub firecracker_cfg: Option<&'a FirecrackerCtrConfig>,
+}
+
+impl JobSpecDoc {
+    pub fn from_run_spec(
+        spec: &RunSpec,
+        mount_digests: Option<&MountDigests>,
+        firecracker_cfg: Option<&FirecrackerCtrConfig>,
+    ) -> Self {
+        let env = spec
+            .env
+            .iter()
+            .map(|(k, v)| (k.clone(), redact_env_value(k, v).to_string()))
+            .collect();
+        let mounts = spec
+            .mounts
+            .iter()
+            .map(|m| JobSpecMount {
+                host_path: m.host_path.clone(),
+                guest_path: m.guest_path.clone(),
+                readonly: m.readonly,
+                kind: m.kind.clone(),
+                manifest_sha256: mount_digests.and_then(|d| {
+                    d.digested
+                        .iter()
+                        .find(|md| md.host_path == m.host_path && md.guest_path == m.guest_path)
+                        .map(|md| md.manifest_sha256.clone())
+                }),
+            })
+            .collect();
+        let ctr = firecracker_cfg
+            .filter(|_| spec.backend == VmBackend::FirecrackerCtr)
+            .map(|cfg| CtrJob {
+                bin: cfg.bin.to_string_lossy().to_string(),
+                address: cfg.address.clone(),
+                namespace: cfg.namespace.clone(),
+                runtime: cfg.runtime.clone(),
+                snapshotter: cfg.snapshotter.clone(),
+            });
+
+        Self {
+            schema_version: VM_JOB_SPEC_SCHEMA_VERSION.to_string(),
+            run_id: spec.run_id.clone(),
+            backend: spec.backend,
+            image: spec.image.clone(),
+            image_digest: spec.image_digest.clone(),
+            argv: spec.argv.clone(),
+            env,
+            mounts,
+            workdir: spec.workdir.clone(),
+            limits: spec.limits.clone(),
+            digest_mounts: spec.digest_mounts,
+            report_file: spec.report_file.clone(),
+            ctr,
+        }
+    }
+
+    /// The spec to replay. Fails if a [`REDACTED`] env value has no override,
+    /// naming every such var.
+    pub fn to_run_spec(&self, overrides: &ReplayOverrides) -> Result<RunSpec> {
+        let mut env = self.env.clone();
+        env.extend(overrides.env.clone());
+        let missing: Vec<&str> = env
+            .iter()
+            .filter(|(_, v)| v.as_str() == REDACTED)
+            .map(|(k, _)| k.as_str())
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "job spec {} has redacted env values; pass them as overrides: {}",
+                self.run_id,
+                missing.join(", ")
+            );
+        }
+
+        let run_id = match &overrides.run_id {
+            Some(id) if *id == self.run_id => {
+                anyhow::bail!("replay run_id must differ from the recorded run_id {id}")
+            }
+            Some(id) => id.clone(),
+            None => RunId::generate(REPLAY_RUN_ID_PREFIX)?,
+        };
+
+        Ok(RunSpec {
+            run_id,
+            backend: self.backend,
+            image: self.image.clone(),
+            image_digest: self.image_digest.clone(),
+            argv: self.argv.clone(),
+            env,
+            mounts: self
+                .mounts
+                .iter()
+                .map(|m| MountSpec {
+                    host_path: m.host_path.clone(),
+                    guest_path: m.guest_path.clone(),
+                    readonly: m.readonly,
+                    kind: m.kind.clone(),
+                })
+                .collect(),
+            workdir: self.workdir.clone(),
+            limits: self.limits.clone(),
+            digest_mounts: self.digest_mounts,
+            dry_run: false,
+            report_file: self.report_file.clone(),
+        })
+    }
+}
+
+/// Writes `spec` to [`JOB_SPEC_FILE_NAME`] in `state_dir` and returns its path.
+pub fn write_job_spec(
+    state_dir: &Path,
+    spec: &RunSpec,
+    mount_digests: Option<&MountDigests>,
+    firecracker_cfg: Option<&FirecrackerCtrConfig>,
+) -> Result<PathBuf> {
+    let doc = JobSpecDoc::from_run_spec(spec, mount_digests, firecracker_cfg);
+    std::fs::create_dir_all(state_dir)
+        .with_context(|| format!("create job dir: {}", state_dir.display()))?;
+    let path = state_dir.join(JOB_SPEC_FILE_NAME);
+    let mut bytes = serde_json::to_vec_pretty(&doc)?;
+    bytes.push(b'\n');
+    std::fs::write(&path, &bytes).with_context(|| format!("write job spec: {}", path.display()))?;
+    Ok(path)
+}
+
+pub fn read_job_spec(path: &Path) -> Result<JobSpecDoc> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("read job spec: {}", path.display()))?;
+    let doc: JobSpecDoc = serde_json::from_slice(&bytes)
+        .with_context(|| format!("parse job spec: {}", path.display()))?;
+    if doc.schema_version != VM_JOB_SPEC_SCHEMA_VERSION {
+        anyhow::bail!(
+            "unsupported job spec schema_version {:?} in {} (expected {VM_JOB_SPEC_SCHEMA_VERSION})",
+            doc.schema_version,
+            path.display()
+        );
+    }
+    Ok(doc)
+}
+
+/// Runs the spec recorded at `spec_path` again through `driver`, in a new
+/// state dir under `params.state_root`.
+pub fn replay_job_spec(
+    driver: &dyn VmDriver,
+    spec_path: &Path,
+    overrides: &ReplayOverrides,
+    params: ReplayParams<'_>,
+) -> Result<RunOutput> {
+    let doc = read_job_spec(spec_path)?;
+    let spec = doc
+        .to_run_spec(overrides)
+        .with_context(|| format!("replay job spec: {}", spec_path.display()))?;
+    let recorded_cfg = doc.ctr.as_ref().map(firecracker_ctr_config_from_job);
+
+    let state_dir = params.state_root.join(&spec.run_id);
+    std::fs::create_dir_all(&state_dir)
+        .with_context(|| format!("create job state dir: {}", state_dir.display()))?;
+    let created_unix_ms = now_unix_ms()?;
+    let deadline_unix_ms = created_unix_ms.saturating_add(spec.limits.wall_ms.max(1));
+
+    driver.run_job(
+        &spec,
+        VmJobRunParams {
+            state_root: params.state_root,
+            state_dir: &state_dir,
+            reaper_bin: params.reaper_bin,
+            created_unix_ms,
+            deadline_unix_ms,
+            firecracker_cfg: params.firecracker_cfg.or(recorded_cfg.as_ref()),
+            spawn_reaper: true,
+        },
+    )
+}
+
+fn now_unix_ms() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system time before unix epoch")?
+        .as_millis()
+        .try_into()
+        .unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JobTimeline, MountDigest, NetworkMode, VmCaps};
+    use std::ffi::OsString;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+
+    static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    fn temp_dir(prefix: &str) -> PathBuf {
+        let n = TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("{prefix}_{}_{n}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn spec() -> RunSpec {
+        RunSpec {
+            run_id: RunId::parse("x07-job-spec-test").unwrap(),
+            backend: VmBackend::FirecrackerCtr,
+            image: "ghcr.io/x07lang/x07-runner:latest".to_string(),
+            image_digest: Some(format!("sha256:{}", "a".repeat(64))),
+            argv: vec!["/bin/run".to_string(), "--fast".to_string()],
+            env: BTreeMap::from([
+                ("LANG".to_string(), "C.UTF-8".to_string()),
+                ("GITHUB_TOKEN".to_string(), "ghp_secret".to_string()),
+            ]),
+            mounts: vec![
+                MountSpec {
+                    host_path: PathBuf::from("/srv/in"),
+                    guest_path: PathBuf::from("/x07/in"),
+                    readonly: true,
+                    kind: MountKind::Bind,
+                },
+                MountSpec {
+                    host_path: PathBuf::new(),
+                    guest_path: PathBuf::from("/cache"),
+                    readonly: false,
+                    kind: MountKind::NamedVolume {
+                        name: "x07-cache".to_string(),
+                    },
+                },
+            ],
+            workdir: Some(PathBuf::from("/work")),
+            limits: LimitsSpec {
+                wall_ms: 30_000,
+                grace_ms: 1_000,
+                cleanup_ms: 5_000,
+                mem_bytes: Some(512 << 20),
+                shm_size_bytes: None,
+                vcpus: Some(2),
+                max_stdout_bytes: 1 << 20,
+                max_stderr_bytes: 1 << 20,
+                network: NetworkMode::None,
+                persist_output: true,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+            },
+            digest_mounts: true,
+            dry_run: false,
+            report_file: Some(PathBuf::from("/x07/out/report.json")),
+        }
+    }
+
+    fn digests() -> MountDigests {
+        MountDigests {
+            max_digest_bytes: 1 << 30,
+            digested: vec![MountDigest {
+                host_path: PathBuf::from("/srv/in"),
+                guest_path: PathBuf::from("/x07/in"),
+                manifest_sha256: "b".repeat(64),
+                files: 3,
+                total_bytes: 42,
+            }],
+            skipped: Vec::new(),
+        }
+    }
+
+    fn ctr_cfg() -> FirecrackerCtrConfig {
+        FirecrackerCtrConfig {
+            bin: OsString::from("/opt/fc/firecracker-ctr"),
+            address: "/run/fc/containerd.sock".to_string(),
+            namespace: "x07-ci".to_string(),
+            runtime: "aws.firecracker".to_string(),
+            snapshotter: "devmapper".to_string(),
+        }
+    }
+
+    #[test]
+    fn job_spec_round_trips() {
+        let doc = JobSpecDoc::from_run_spec(&spec(), Some(&digests()), Some(&ctr_cfg()));
+        assert_eq!(doc.schema_version, VM_JOB_SPEC_SCHEMA_VERSION);
+        assert_eq!(doc.mounts[0].manifest_sha256, Some("b".repeat(64)));
+        assert_eq!(doc.mounts[1].manifest_sha256, None);
+        assert_eq!(doc.ctr.as_ref().unwrap().namespace, "x07-ci");
+
+        let bytes = serde_json::to_vec(&doc).unwrap();
+        let back: JobSpecDoc = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(back, doc);
+
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["limits"]["network"], "none");
+        assert_eq!(value["mounts"][1]["kind"]["type"], "named_volume");
+        assert_eq!(value["mounts"][1]["kind"]["name"], "x07-cache");
+
+        let dir = temp_dir("x07_job_spec_rt");
+        let path = write_job_spec(&dir, &spec(), Some(&digests()), Some(&ctr_cfg())).unwrap();
+        assert_eq!(path, dir.join(JOB_SPEC_FILE_NAME));
+        assert_eq!(read_job_spec(&path).unwrap(), doc);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn job_spec_redacts_secrets_and_requires_them_back() {
+        let doc = JobSpecDoc::from_run_spec(&spec(), None, None);
+        assert_eq!(doc.env["GITHUB_TOKEN"], REDACTED);
+        assert_eq!(doc.env["LANG"], "C.UTF-8");
+        assert!(doc.ctr.is_none());
+        assert!(doc.mounts.iter().all(|m| m.manifest_sha256.is_none()));
+
+        let err = doc.to_run_spec(&ReplayOverrides::default()).unwrap_err();
+        assert!(format!("{err:#}").contains("GITHUB_TOKEN"), "{err:#}");
+
+        let overrides = ReplayOverrides {
+            env: BTreeMap::from([("GITHUB_TOKEN".to_string(), "ghp_again".to_string())]),
+            run_id: None,
+        };
+        let replayed = doc.to_run_spec(&overrides).unwrap();
+        assert_eq!(replayed.env["GITHUB_TOKEN"], "ghp_again");
+        assert_ne!(replayed.run_id, doc.run_id);
+        assert!(replayed.run_id.as_str().starts_with("x07-replay-"));
+
+        let same_id = ReplayOverrides {
+            run_id: Some(doc.run_id.clone()),
+            ..overrides
+        };
+        assert!(doc.to_run_spec(&same_id).is_err());
+    }
+
+    #[test]
+    fn job_spec_rejects_unknown_schema_version() {
+        let dir = temp_dir("x07_job_spec_schema");
+        let mut doc = JobSpecDoc::from_run_spec(&spec(), None, None);
+        doc.schema_version = "x07.vm.job.spec@9.0.0".to_string();
+        let path = dir.join(JOB_SPEC_FILE_NAME);
+        std::fs::write(&path, serde_json::to_vec(&doc).unwrap()).unwrap();
+        assert!(read_job_spec(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    struct RecordingDriver {
+        runs: Mutex<Vec<(RunSpec, PathBuf, Option<String>)>>,
+    }
+
+    impl VmDriver for RecordingDriver {
+        fn run_job(&self, spec: &RunSpec, params: VmJobRunParams<'_>) -> Result<RunOutput> {
+            assert!(params.state_dir.is_dir());
+            assert!(params.deadline_unix_ms > params.created_unix_ms);
+            self.runs.lock().unwrap().push((
+                spec.clone(),
+                params.state_dir.to_path_buf(),
+                params.firecracker_cfg.map(|c| c.namespace.clone()),
+            ));
+            Ok(RunOutput {
+                exit_status: 0,
+                exit_signal: None,
+                timed_out: false,
+                stdout: b"ok".to_vec(),
+                stderr: Vec::new(),
+                stdout_truncated: false,
+                stderr_truncated: false,
+                guest_report: None,
+                timeline: JobTimeline::default(),
+                mount_digests: None,
+                persisted_output: None,
+                guest_clock_skew_ms: None,
+                dry_run: None,
+                warnings: Vec::new(),
+            })
+        }
+
+        fn capabilities(&self) -> VmCaps {
+            VmCaps::for_backend(VmBackend::FirecrackerCtr)
+        }
+    }
+
+    #[test]
+    fn replay_runs_recorded_spec_under_fresh_run_id() {
+        let root = temp_dir("x07_job_spec_replay");
+        let original = spec();
+        let spec_path = write_job_spec(
+            &root.join(original.run_id.as_str()),
+            &original,
+            Some(&digests()),
+            Some(&ctr_cfg()),
+        )
+        .unwrap();
+
+        let driver = RecordingDriver {
+            runs: Mutex::new(Vec::new()),
+        };
+        let params = || ReplayParams {
+            state_root: &root,
+            reaper_bin: Path::new("x07-vm-reaper"),
+            firecracker_cfg: None,
+        };
+
+        let err = replay_job_spec(&driver, &spec_path, &ReplayOverrides::default(), params())
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("redacted"), "{err:#}");
+        assert!(driver.runs.lock().unwrap().is_empty());
+
+        let overrides = ReplayOverrides {
+            env: BTreeMap::from([("GITHUB_TOKEN".to_string(), "ghp_secret".to_string())]),
+            run_id: Some(RunId::parse("x07-replayed").unwrap()),
+        };
+        let out = replay_job_spec(&driver, &spec_path, &overrides, params()).unwrap();
+        assert_eq!(out.stdout, b"ok");
+
+        let runs = driver.runs.lock().unwrap();
+        let (replayed, state_dir, namespace) = &runs[0];
+        assert_eq!(replayed.run_id.as_str(), "x07-replayed");
+        assert_eq!(state_dir, &root.join("x07-replayed"));
+        assert_eq!(namespace.as_deref(), Some("x07-ci"));
+        assert_eq!(replayed.env, original.env);
+        assert_eq!(replayed.argv, original.argv);
+        assert_eq!(replayed.limits, original.limits);
+        assert_eq!(replayed.report_file, original.report_file);
+        assert_eq!(
+            replayed.mounts.iter().map(|m| &m.kind).collect::<Vec<_>>(),
+            original.mounts.iter().map(|m| &m.kind).collect::<Vec<_>>()
+        );
+        drop(runs);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
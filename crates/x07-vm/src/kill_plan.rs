@@ -2,14 +2,25 @@ use std::path::Path;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     firecracker_ctr_config_from_env, firecracker_ctr_config_from_job, hard_kill_pid_and_group,
-    run_command_capped, vz_cleanup_scratch, FirecrackerCtrConfig, VmBackend, VmJob,
+    normalize_abs_path, read_job_spec, run_control_command, vz_cleanup_scratch,
+    FirecrackerCtrConfig, VmBackend, VmJob, CONTROL_CMD_LIMITS, VZ_JOB_IN_GUEST_PATH,
 };
 
+/// Touched in the vz job's `/x07/in` dir to ask guestd for a SIGTERM; see
+/// [`crate::LimitsSpec::warn_before_deadline_ms`].
+pub const DEADLINE_WARNING_FILE_NAME: &str = "deadline_warning";
+
+/// Written by the reaper in the job's state dir once it sent the deadline
+/// warning; holds the unix ms it was sent at.
+pub(crate) const WARNED_MARKER_FILE_NAME: &str = "warned";
+
 #[derive(Debug, Clone, Copy)]
 struct KillSchedule {
+    t_warn: Option<Instant>,
     t_soft: Instant,
     t_hard: Instant,
     t_cleanup_deadline: Instant,
@@ -17,22 +28,19 @@ struct KillSchedule {
 
 impl KillSchedule {
     fn from_plan(plan: &KillPlan) -> Self {
-        let now_unix_ms = now_unix_ms().unwrap_or(u64::MAX);
-        let now = Instant::now();
-
-        let soft_in = Duration::from_millis(plan.t_soft_unix_ms.saturating_sub(now_unix_ms));
-        let hard_in = Duration::from_millis(plan.t_hard_unix_ms.saturating_sub(now_unix_ms));
-        let cleanup_in =
-            Duration::from_millis(plan.t_cleanup_deadline_unix_ms.saturating_sub(now_unix_ms));
-
-        let t_soft = now.checked_add(soft_in).unwrap_or(now);
-        let t_hard = now.checked_add(hard_in).unwrap_or(now);
-        let t_cleanup_deadline = now.checked_add(cleanup_in).unwrap_or(now);
+        Self::from_plan_at(plan, now_unix_ms().unwrap_or(u64::MAX), Instant::now())
+    }
 
+    fn from_plan_at(plan: &KillPlan, now_unix_ms: u64, now: Instant) -> Self {
+        let at = |unix_ms: u64| {
+            now.checked_add(Duration::from_millis(unix_ms.saturating_sub(now_unix_ms)))
+                .unwrap_or(now)
+        };
         KillSchedule {
-            t_soft,
-            t_hard,
-            t_cleanup_deadline,
+            t_warn: plan.t_warn_unix_ms.map(at),
+            t_soft: at(plan.t_soft_unix_ms),
+            t_hard: at(plan.t_hard_unix_ms),
+            t_cleanup_deadline: at(plan.t_cleanup_deadline_unix_ms),
         }
     }
 }
@@ -129,6 +137,8 @@ pub struct KillPlan {
     pub backend: VmBackend,
     pub target: TargetRef,
 
+    /// When to send the deadline warning, if any; always before `t_soft_unix_ms`.
+    pub t_warn_unix_ms: Option<u64>,
     pub t_soft_unix_ms: u64,
     pub t_hard_unix_ms: u64,
     pub t_cleanup_deadline_unix_ms: u64,
@@ -155,6 +165,7 @@ impl KillPlan {
             target: TargetRef {
                 id: job.container_id.clone(),
             },
+            t_warn_unix_ms: warn_at_unix_ms(job, t_soft),
             t_soft_unix_ms: t_soft,
             t_hard_unix_ms: t_hard,
             t_cleanup_deadline_unix_ms: t_cleanup_deadline,
@@ -168,7 +179,19 @@ impl KillPlan {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// `deadline - warn_before_deadline_ms`, no earlier than the job's start. A
+/// warning that would not land before the soft stop at `t_soft` is dropped.
+fn warn_at_unix_ms(job: &VmJob, t_soft: u64) -> Option<u64> {
+    let warn_ms = job.warn_before_deadline_ms?;
+    let t_warn = job
+        .deadline_unix_ms
+        .saturating_sub(warn_ms)
+        .max(job.created_unix_ms);
+    (t_warn < t_soft).then_some(t_warn)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum KillResult {
     CompletedBeforeDeadline,
     KilledAtHardDeadline,
@@ -190,6 +213,12 @@ pub trait KillBackend {
     fn build_cleanup(&self, t: &TargetRef, op_timeout: Duration) -> Vec<CommandSpec>;
 
     fn build_probe(&self, t: &TargetRef, op_timeout: Duration) -> Option<CommandSpec>;
+
+    /// SIGTERM to the workload ahead of the soft stop. Backends that cannot
+    /// signal a running task send no warning.
+    fn build_warn(&self, _t: &TargetRef, _op_timeout: Duration) -> Vec<CommandSpec> {
+        Vec::new()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -320,6 +349,21 @@ impl KillBackend for DockerLikeCli {
         }]
     }
 
+    fn build_warn(&self, t: &TargetRef, op_timeout: Duration) -> Vec<CommandSpec> {
+        vec![CommandSpec {
+            program: self.bin.clone(),
+            args: vec![
+                "kill".to_string(),
+                "--signal".to_string(),
+                Signal::Term.for_docker_like().to_string(),
+                t.id.clone(),
+            ],
+            env: vec![],
+            timeout: op_timeout,
+            best_effort: true,
+        }]
+    }
+
     fn build_cleanup(&self, t: &TargetRef, op_timeout: Duration) -> Vec<CommandSpec> {
         vec![CommandSpec {
             program: self.bin.clone(),
@@ -461,6 +505,26 @@ impl KillBackend for CtrLike {
         ]
     }
 
+    fn build_warn(&self, t: &TargetRef, op_timeout: Duration) -> Vec<CommandSpec> {
+        vec![CommandSpec {
+            program: self.bin.clone(),
+            args: self
+                .base_args(op_timeout)
+                .into_iter()
+                .chain(vec![
+                    "tasks".to_string(),
+                    "kill".to_string(),
+                    "--signal".to_string(),
+                    Signal::Term.for_ctr_like().to_string(),
+                    t.id.clone(),
+                ])
+                .collect(),
+            env: vec![],
+            timeout: op_timeout,
+            best_effort: true,
+        }]
+    }
+
     fn build_probe(&self, t: &TargetRef, op_timeout: Duration) -> Option<CommandSpec> {
         Some(CommandSpec {
             program: self.bin.clone(),
@@ -597,6 +661,51 @@ where
     }
 }
 
+/// Waits for the plan's warning time and sends the backend's warning. The
+/// commands are best effort: a failed one is ignored. Returns the unix ms the
+/// warning went out at, or `None` when there is no warning to send or the job
+/// finished (or reached its soft stop) first.
+pub fn send_deadline_warning<FRun, FDone>(
+    plan: &KillPlan,
+    backend: &dyn KillBackend,
+    mut run_cmd: FRun,
+    mut is_done: FDone,
+) -> Option<u64>
+where
+    FRun: FnMut(CommandSpec) -> ExecResult,
+    FDone: FnMut() -> bool,
+{
+    let schedule = KillSchedule::from_plan(plan);
+    let t_warn = schedule.t_warn?;
+    let seq = backend.build_warn(&plan.target, plan.op_timeout);
+    if seq.is_empty() {
+        return None;
+    }
+
+    loop {
+        if is_done() {
+            return None;
+        }
+        let now = Instant::now();
+        if now >= schedule.t_soft {
+            return None;
+        }
+        if now >= t_warn {
+            break;
+        }
+        std::thread::sleep(
+            t_warn
+                .saturating_duration_since(now)
+                .min(Duration::from_millis(250))
+                .max(Duration::from_millis(1)),
+        );
+    }
+
+    let sent_unix_ms = now_unix_ms().unwrap_or(plan.t_warn_unix_ms.unwrap_or(0));
+    run_seq(schedule, seq, &mut run_cmd);
+    Some(sent_unix_ms)
+}
+
 pub fn enforce_kill_plan_for_job(
     job: &VmJob,
     state_dir: &Path,
@@ -605,47 +714,78 @@ pub fn enforce_kill_plan_for_job(
     let plan = KillPlan::from_job(job);
     let is_done = || done_marker.is_file();
 
-    match job.backend {
-        VmBackend::Vz => enforce_vz_kill(job, state_dir, done_marker),
-        VmBackend::AppleContainer => Ok(enforce_kill_plan(
-            &plan,
-            &MacContainerCli::new("container"),
-            run_command_spec,
-            is_done,
-        )),
-        VmBackend::Docker => Ok(enforce_kill_plan(
-            &plan,
-            &DockerLikeCli::new("docker"),
-            run_command_spec,
-            is_done,
-        )),
-        VmBackend::Podman => Ok(enforce_kill_plan(
-            &plan,
-            &DockerLikeCli::new("podman"),
-            run_command_spec,
-            is_done,
-        )),
+    let ctr_backend;
+    let backend: &dyn KillBackend = match job.backend {
+        VmBackend::Vz => return enforce_vz_kill(job, state_dir, done_marker),
+        VmBackend::AppleContainer => &MacContainerCli::new("container"),
+        VmBackend::Docker => &DockerLikeCli::new("docker"),
+        VmBackend::Podman => &DockerLikeCli::new("podman"),
         VmBackend::FirecrackerCtr => {
             let cfg = job
                 .ctr
                 .as_ref()
                 .map(firecracker_ctr_config_from_job)
                 .unwrap_or_else(firecracker_ctr_config_from_env);
-            let backend = CtrLike::from_firecracker_cfg(&cfg);
-            Ok(enforce_kill_plan(
-                &plan,
-                &backend,
-                run_command_spec,
-                is_done,
-            ))
+            ctr_backend = CtrLike::from_firecracker_cfg(&cfg);
+            &ctr_backend
         }
+    };
+
+    if let Some(sent_unix_ms) = send_deadline_warning(&plan, backend, run_command_spec, is_done) {
+        record_warning(state_dir, sent_unix_ms);
     }
+    Ok(enforce_kill_plan(&plan, backend, run_command_spec, is_done))
+}
+
+/// Best effort: the runner copies the marker into the job file when it
+/// finishes, see [`read_warned_marker`].
+fn record_warning(state_dir: &Path, sent_unix_ms: u64) {
+    let _ = std::fs::write(
+        state_dir.join(WARNED_MARKER_FILE_NAME),
+        format!("{sent_unix_ms}\n"),
+    );
+}
+
+pub(crate) fn read_warned_marker(state_dir: &Path) -> Option<u64> {
+    std::fs::read_to_string(state_dir.join(WARNED_MARKER_FILE_NAME))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// vz has no exec channel: touch [`DEADLINE_WARNING_FILE_NAME`] in the job's
+/// `/x07/in` dir, found through its `spec.json`, and let guestd signal the
+/// workload.
+fn warn_vz_guest(job: &VmJob) -> Option<u64> {
+    let spec = read_job_spec(job.spec_file.as_deref()?).ok()?;
+    let job_in = spec.mounts.iter().find(|m| {
+        normalize_abs_path(&m.guest_path).is_ok_and(|p| p == Path::new(VZ_JOB_IN_GUEST_PATH))
+    })?;
+    let sent_unix_ms = now_unix_ms().ok()?;
+    std::fs::write(
+        job_in.host_path.join(DEADLINE_WARNING_FILE_NAME),
+        format!("{sent_unix_ms}\n"),
+    )
+    .ok()?;
+    Some(sent_unix_ms)
 }
 
 fn enforce_vz_kill(job: &VmJob, state_dir: &Path, done_marker: &Path) -> Result<KillResult> {
     let plan = KillPlan::from_job(job);
     let schedule = KillSchedule::from_plan(&plan);
 
+    if let Some(t_warn) = schedule.t_warn {
+        if !done_marker.is_file() && Instant::now() < t_warn {
+            sleep_until_or_done(t_warn, done_marker)?;
+        }
+        if !done_marker.is_file() && Instant::now() < schedule.t_soft {
+            if let Some(sent_unix_ms) = warn_vz_guest(job) {
+                record_warning(state_dir, sent_unix_ms);
+            }
+        }
+    }
+
     if !done_marker.is_file() && Instant::now() < schedule.t_soft {
         sleep_until_or_done(schedule.t_soft, done_marker)?;
     }
@@ -744,16 +884,23 @@ fn run_command_spec(spec: CommandSpec) -> ExecResult {
     }
 
     let wall_ms: u64 = spec.timeout.as_millis().try_into().unwrap_or(u64::MAX);
-    let out = run_command_capped(cmd, wall_ms.max(1), 64 * 1024, 64 * 1024).unwrap_or_else(|_| {
-        crate::RunOutput {
+    let out = run_control_command(cmd, CONTROL_CMD_LIMITS.with_wall_ms(wall_ms.max(1)))
+        .unwrap_or_else(|_| crate::RunOutput {
             exit_status: 1,
+            exit_signal: None,
             timed_out: true,
             stdout: Vec::new(),
             stderr: Vec::new(),
             stdout_truncated: false,
             stderr_truncated: false,
-        }
-    });
+            guest_report: None,
+            timeline: crate::JobTimeline::default(),
+            mount_digests: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            dry_run: None,
+            warnings: Vec::new(),
+        });
 
     ExecResult {
         exit_status: out.exit_status,
@@ -821,6 +968,157 @@ mod tests {
                 best_effort: true,
             })
         }
+
+        fn build_warn(&self, t: &TargetRef, op_timeout: Duration) -> Vec<CommandSpec> {
+            vec![CommandSpec {
+                program: "warn".to_string(),
+                args: vec![t.id.clone()],
+                env: vec![],
+                timeout: op_timeout,
+                best_effort: true,
+            }]
+        }
+    }
+
+    fn job(created: u64, deadline: u64, grace_ms: u64, warn_ms: Option<u64>) -> VmJob {
+        VmJob {
+            schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
+            run_id: crate::RunId::parse("r1").unwrap(),
+            backend: VmBackend::Docker,
+            container_id: "x07-test".to_string(),
+            pid: None,
+            created_unix_ms: created,
+            deadline_unix_ms: deadline,
+            grace_ms,
+            cleanup_ms: 1_000,
+            ctr: None,
+            timeline: None,
+            mount_digests: None,
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: warn_ms,
+            warned: false,
+            warned_unix_ms: None,
+        }
+    }
+
+    fn exec_ok() -> ExecResult {
+        ExecResult {
+            exit_status: 0,
+            timed_out: false,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn warn_time_lands_before_the_soft_stop() {
+        let plan = |warn_ms| KillPlan::from_job(&job(1_000, 11_000, 1_000, warn_ms));
+
+        assert_eq!(plan(None).t_warn_unix_ms, None);
+        assert_eq!(plan(Some(3_000)).t_warn_unix_ms, Some(8_000));
+        assert_eq!(plan(Some(3_000)).t_soft_unix_ms, 10_000);
+        // At or after the soft stop there is nothing to warn about.
+        assert_eq!(plan(Some(1_000)).t_warn_unix_ms, None);
+        assert_eq!(plan(Some(500)).t_warn_unix_ms, None);
+        assert_eq!(plan(Some(0)).t_warn_unix_ms, None);
+        // Never before the job started.
+        assert_eq!(plan(Some(60_000)).t_warn_unix_ms, Some(1_000));
+        assert_eq!(
+            KillPlan::from_job(&job(1_000, 1_500, 1_000, Some(60_000))).t_warn_unix_ms,
+            None
+        );
+    }
+
+    #[test]
+    fn schedule_maps_warn_time_onto_the_mock_clock() {
+        let plan = KillPlan::from_job(&job(1_000, 11_000, 1_000, Some(3_000)));
+        let now = Instant::now();
+
+        let schedule = KillSchedule::from_plan_at(&plan, 5_000, now);
+        assert_eq!(schedule.t_warn, Some(now + Duration::from_millis(3_000)));
+        assert_eq!(schedule.t_soft, now + Duration::from_millis(5_000));
+        assert_eq!(schedule.t_hard, now + Duration::from_millis(6_000));
+        assert_eq!(
+            schedule.t_cleanup_deadline,
+            now + Duration::from_millis(7_000)
+        );
+
+        // Already past the warning: it is due right away.
+        let late = KillSchedule::from_plan_at(&plan, 9_000, now);
+        assert_eq!(late.t_warn, Some(now));
+        assert_eq!(late.t_soft, now + Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn deadline_warning_is_sent_once_and_failures_are_ignored() {
+        let now = now_unix_ms().expect("now_unix_ms");
+        let mut j = job(now.saturating_sub(5_000), now + 30_000, 1_000, Some(40_000));
+        let plan = KillPlan::from_job(&j);
+        assert!(plan.t_warn_unix_ms.is_some());
+
+        let calls: RefCell<Vec<String>> = RefCell::new(Vec::new());
+        let sent = send_deadline_warning(
+            &plan,
+            &FakeBackend,
+            |spec: CommandSpec| {
+                calls.borrow_mut().push(spec.program.clone());
+                ExecResult {
+                    exit_status: 1,
+                    timed_out: true,
+                    stdout: Vec::new(),
+                    stderr: b"boom".to_vec(),
+                }
+            },
+            || false,
+        );
+        assert!(sent.is_some_and(|t| t >= now));
+        assert_eq!(calls.into_inner(), vec!["warn".to_string()]);
+
+        // A finished job is not warned.
+        let sent = send_deadline_warning(&plan, &FakeBackend, |_| exec_ok(), || true);
+        assert_eq!(sent, None);
+
+        // Neither is a job without a warning, nor one whose backend has none.
+        j.warn_before_deadline_ms = None;
+        let plan_none = KillPlan::from_job(&j);
+        let calls: RefCell<Vec<String>> = RefCell::new(Vec::new());
+        let sent = send_deadline_warning(
+            &plan_none,
+            &FakeBackend,
+            |spec: CommandSpec| {
+                calls.borrow_mut().push(spec.program);
+                exec_ok()
+            },
+            || false,
+        );
+        assert_eq!(sent, None);
+        assert!(calls.into_inner().is_empty());
+        let sent = send_deadline_warning(
+            &plan,
+            &MacContainerCli::new("container"),
+            |_| exec_ok(),
+            || false,
+        );
+        assert_eq!(sent, None);
+    }
+
+    #[test]
+    fn warned_marker_round_trips() {
+        let dir = std::env::temp_dir().join(format!(
+            "x07_vm_warned_marker_{}_{}",
+            std::process::id(),
+            now_unix_ms().unwrap()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(read_warned_marker(&dir), None);
+        record_warning(&dir, 1_234);
+        assert_eq!(read_warned_marker(&dir), Some(1_234));
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[test]
@@ -832,6 +1130,7 @@ mod tests {
             target: TargetRef {
                 id: "x07-test".into(),
             },
+            t_warn_unix_ms: None,
             t_soft_unix_ms: now.saturating_sub(1_000),
             t_hard_unix_ms: now.saturating_add(50),
             t_cleanup_deadline_unix_ms: now.saturating_add(30_000),
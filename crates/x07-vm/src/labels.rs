@@ -3,6 +3,9 @@ use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::RunId;
 
 pub const X07_LABEL_SCHEMA_KEY: &str = "io.x07.schema";
 pub const X07_LABEL_SCHEMA_VALUE: &str = "1";
@@ -13,6 +16,11 @@ pub const X07_LABEL_DEADLINE_UNIX_MS_KEY: &str = "io.x07.deadline_unix_ms";
 pub const X07_LABEL_IMAGE_DIGEST_KEY: &str = "io.x07.image_digest";
 pub const X07_LABEL_BACKEND_KEY: &str = "io.x07.backend";
 pub const X07_LABEL_CREATED_UNIX_MS_KEY: &str = "io.x07.created_unix_ms";
+/// Job file path relative to the state root, e.g. `<run_id>/job.json`; only
+/// meaningful to a runner whose state root matches [`X07_LABEL_STATE_ROOT_ID_KEY`].
+pub const X07_LABEL_JOB_FILE_KEY: &str = "io.x07.job_file";
+/// [`state_root_id`] of the state root [`X07_LABEL_JOB_FILE_KEY`] is relative to.
+pub const X07_LABEL_STATE_ROOT_ID_KEY: &str = "io.x07.state_root_id";
 
 const CONTAINERD_KV_MAX_BYTES: usize = 4096;
 const RUNNER_INSTANCE_FILE: &str = "runner_instance";
@@ -62,29 +70,28 @@ impl std::error::Error for LabelError {}
 
 #[derive(Debug, Clone)]
 pub struct X07LabelSet {
-    pub run_id: String,
+    pub run_id: RunId,
     pub runner_instance: String,
     pub deadline_unix_ms: u64,
     pub job_id: Option<String>,
     pub image_digest: Option<String>,
     pub backend: Option<String>,
     pub created_unix_ms: Option<u64>,
+    /// `(state_root_id, job file relative to that state root)`.
+    pub job_file: Option<(String, String)>,
 }
 
 impl X07LabelSet {
-    pub fn new(
-        run_id: impl Into<String>,
-        runner_instance: impl Into<String>,
-        deadline_unix_ms: u64,
-    ) -> Self {
+    pub fn new(run_id: RunId, runner_instance: impl Into<String>, deadline_unix_ms: u64) -> Self {
         Self {
-            run_id: run_id.into(),
+            run_id,
             runner_instance: runner_instance.into(),
             deadline_unix_ms,
             job_id: None,
             image_digest: None,
             backend: None,
             created_unix_ms: None,
+            job_file: None,
         }
     }
 
@@ -108,10 +115,19 @@ impl X07LabelSet {
         self
     }
 
+    pub fn with_job_file(
+        mut self,
+        state_root_id: impl Into<String>,
+        rel_path: impl Into<String>,
+    ) -> Self {
+        self.job_file = Some((state_root_id.into(), rel_path.into()));
+        self
+    }
+
     fn kv_pairs_ordered(&self) -> Vec<(&'static str, String)> {
-        let mut out = Vec::with_capacity(8);
+        let mut out = Vec::with_capacity(10);
         out.push((X07_LABEL_SCHEMA_KEY, X07_LABEL_SCHEMA_VALUE.to_string()));
-        out.push((X07_LABEL_RUN_ID_KEY, self.run_id.clone()));
+        out.push((X07_LABEL_RUN_ID_KEY, self.run_id.to_string()));
         if let Some(job_id) = &self.job_id {
             out.push((X07_LABEL_JOB_ID_KEY, job_id.clone()));
         }
@@ -130,14 +146,15 @@ impl X07LabelSet {
         if let Some(ts) = self.created_unix_ms {
             out.push((X07_LABEL_CREATED_UNIX_MS_KEY, ts.to_string()));
         }
+        if let Some((root_id, rel)) = &self.job_file {
+            out.push((X07_LABEL_STATE_ROOT_ID_KEY, root_id.clone()));
+            out.push((X07_LABEL_JOB_FILE_KEY, rel.clone()));
+        }
 
         out
     }
 
     pub fn validate(&self) -> Result<(), LabelError> {
-        if self.run_id.is_empty() {
-            return Err(LabelError::Empty("run_id"));
-        }
         if self.runner_instance.is_empty() {
             return Err(LabelError::Empty("runner_instance"));
         }
@@ -200,6 +217,30 @@ pub fn read_or_create_runner_instance_id(state_root: &Path) -> Result<String> {
     Ok(id)
 }
 
+/// Stable id of `state_root` for [`X07_LABEL_STATE_ROOT_ID_KEY`]: a hash of its
+/// canonical path, so labels do not expose the host layout.
+pub fn state_root_id(state_root: &Path) -> String {
+    let canon = std::fs::canonicalize(state_root).unwrap_or_else(|_| state_root.to_path_buf());
+    let digest = Sha256::digest(canon.as_os_str().as_encoded_bytes());
+    format!("sr-{}", hex_lower(&digest[..8]))
+}
+
+/// Resolves a [`X07_LABEL_JOB_FILE_KEY`] value under `state_root`. Rejects
+/// absolute paths and `..` components.
+pub fn resolve_job_file_label(state_root: &Path, rel: &str) -> Option<PathBuf> {
+    let rel = Path::new(rel);
+    if rel.as_os_str().is_empty() {
+        return None;
+    }
+    if !rel
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(state_root.join(rel))
+}
+
 fn runner_instance_path(state_root: &Path) -> PathBuf {
     let dir = state_root.parent().unwrap_or(state_root);
     dir.join(RUNNER_INSTANCE_FILE)
@@ -410,7 +451,7 @@ mod tests {
 
     #[test]
     fn label_set_renders_required_keys() {
-        let set = X07LabelSet::new("r1", "ri-abc", 123)
+        let set = X07LabelSet::new(RunId::parse("r1").unwrap(), "ri-abc", 123)
             .with_job_id("j1")
             .with_backend("vm.vz")
             .with_created_unix_ms(11);
@@ -421,9 +462,30 @@ mod tests {
         assert!(kv.iter().any(|s| s == "io.x07.deadline_unix_ms=123"));
     }
 
+    #[test]
+    fn job_file_labels_are_rendered_as_a_pair() {
+        let set = X07LabelSet::new(RunId::parse("r1").unwrap(), "ri-abc", 123)
+            .with_job_file("sr-0011", "r1/job.json");
+        let kv = set.render_kv_strings().unwrap();
+        assert!(kv.iter().any(|s| s == "io.x07.state_root_id=sr-0011"));
+        assert!(kv.iter().any(|s| s == "io.x07.job_file=r1/job.json"));
+    }
+
+    #[test]
+    fn job_file_label_stays_under_the_state_root() {
+        let root = Path::new("/state");
+        assert_eq!(
+            resolve_job_file_label(root, "r1/job.json"),
+            Some(PathBuf::from("/state/r1/job.json"))
+        );
+        assert_eq!(resolve_job_file_label(root, "../r1/job.json"), None);
+        assert_eq!(resolve_job_file_label(root, "/etc/passwd"), None);
+        assert_eq!(resolve_job_file_label(root, ""), None);
+    }
+
     #[test]
     fn label_value_rejects_whitespace() {
-        let set = X07LabelSet::new("r1", "bad value", 123);
+        let set = X07LabelSet::new(RunId::parse("r1").unwrap(), "bad value", 123);
         assert!(set.render_kv_strings().is_err());
     }
 }
@@ -9,35 +9,113 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use x07_contracts::X07_OS_RUNNER_REPORT_SCHEMA_VERSION;
 
+use control_cmd::run_control_command;
+use output_persist::OutputPersist;
+
+mod backend_version;
+mod cancel;
 mod caps;
+mod clock_skew;
+mod container_list;
+mod control_cmd;
 mod digest;
+mod dry_run;
+mod guest_paths;
+mod guest_report;
+mod image_import;
 mod inspect_parsers;
+mod job_pool;
 mod job_runner;
+mod job_spec;
 mod kill_plan;
 mod labels;
+mod mount_digest;
+mod output_persist;
+mod preflight;
 mod reaper_joiner;
+mod run_id;
 mod sweep;
+mod timeline;
+mod vz_limits;
+mod workspace;
 
-pub use caps::VmCaps;
-pub use digest::{resolve_vm_guest_digest, verify_vm_guest_digest};
+pub use backend_version::{
+    backend_version_requirements, check_backend_version, parse_backend_version, BackendVersion,
+    VersionTooOld,
+};
+pub use cancel::{cancel_job, CancelOutcome, JobCancellation};
+pub use caps::{backend_capabilities, BackendCaps, Incompatibility, IncompatibleSpec, VmCaps};
+pub use clock_skew::{
+    clock_probe_from_env, clock_skew_warn_ms_from_env, guest_clock_skew_ms, skew_exceeds,
+    ClockWindow, GuestClockRecord, DEFAULT_CLOCK_SKEW_WARN_MS, ENV_VM_CLOCK_PROBE,
+    ENV_VM_CLOCK_SKEW_WARN_MS, GUEST_CLOCK_FILE, GUEST_CLOCK_SCHEMA_VERSION,
+};
+pub use container_list::{list_owned_containers, to_table_string, BackendFilter, ListedContainer};
+pub use control_cmd::{ControlCmdLimits, CONTROL_CMD_LIMITS, CONTROL_CMD_LIST_LIMITS};
+pub use digest::{resolve_vm_guest_digest, verify_vm_guest_digest, DigestMismatch};
+pub use dry_run::{plan_run, redact_env_value, RunPlan, REDACTED};
+pub use guest_paths::{
+    guest_path_denylist_from_env, DEFAULT_GUEST_PATH_DENYLIST, ENV_VM_GUEST_PATH_DENYLIST,
+};
+pub use guest_report::{
+    max_report_file_bytes_from_env, read_guest_report_file, resolve_guest_report,
+    validate_guest_stdout, GuestOutputError, GuestReport, DEFAULT_MAX_REPORT_FILE_BYTES,
+    ENV_GUEST_REPORT_FILE, ENV_VM_MAX_REPORT_FILE_BYTES, GUEST_STDOUT_MAX_BYTES,
+};
+pub use image_import::{
+    ensure_image, ensure_image_timed, import_image_oci_layout, EnsuredImage, ImportedImage,
+    MissingBlob,
+};
 pub use inspect_parsers::{
-    is_owned_by_x07, parse_apple_container_json_owned, parse_ctr_container_info_json_owned, Labels,
-    OwnedContainer, ParseError,
+    is_owned_by_x07, parse_apple_container_json_owned, parse_ctr_container_info_json_owned,
+    parse_docker_inspect_json_owned, Labels, OwnedContainer, ParseError,
 };
+pub use job_pool::{JobHandle, JobPool, JobPoolConfig, ShutdownMode};
 pub use job_runner::{
     run_vm_job, run_vm_job_passthrough, DefaultVmDriver, VmDriver, VmJobRunParams,
 };
+pub use job_spec::{
+    read_job_spec, replay_job_spec, write_job_spec, JobSpecDoc, JobSpecMount, ReplayOverrides,
+    ReplayParams, JOB_SPEC_FILE_NAME, VM_JOB_SPEC_SCHEMA_VERSION,
+};
 pub use kill_plan::{
-    enforce_kill_plan, enforce_kill_plan_for_job, CommandSpec, ExecResult, KillBackend, KillPlan,
-    KillResult, RetryPolicy, Signal, TargetRef,
+    enforce_kill_plan, enforce_kill_plan_for_job, send_deadline_warning, CommandSpec, ExecResult,
+    KillBackend, KillPlan, KillResult, RetryPolicy, Signal, TargetRef, DEADLINE_WARNING_FILE_NAME,
 };
 pub use labels::{
-    read_or_create_runner_instance_id, LabelError, X07LabelSet, X07_LABEL_BACKEND_KEY,
-    X07_LABEL_CREATED_UNIX_MS_KEY, X07_LABEL_DEADLINE_UNIX_MS_KEY, X07_LABEL_IMAGE_DIGEST_KEY,
+    read_or_create_runner_instance_id, resolve_job_file_label, state_root_id, LabelError,
+    X07LabelSet, X07_LABEL_BACKEND_KEY, X07_LABEL_CREATED_UNIX_MS_KEY,
+    X07_LABEL_DEADLINE_UNIX_MS_KEY, X07_LABEL_IMAGE_DIGEST_KEY, X07_LABEL_JOB_FILE_KEY,
     X07_LABEL_JOB_ID_KEY, X07_LABEL_RUNNER_INSTANCE_KEY, X07_LABEL_RUN_ID_KEY,
-    X07_LABEL_SCHEMA_KEY, X07_LABEL_SCHEMA_VALUE,
+    X07_LABEL_SCHEMA_KEY, X07_LABEL_SCHEMA_VALUE, X07_LABEL_STATE_ROOT_ID_KEY,
+};
+pub use mount_digest::{
+    digest_mounts, max_mount_digest_bytes_from_env, verify_job_mounts, MountDigest, MountDigests,
+    MountDrift, MountDriftKind, MountFileDigest, SkippedMountDigest,
+    DEFAULT_MAX_MOUNT_DIGEST_BYTES, ENV_VM_MAX_MOUNT_DIGEST_BYTES,
+};
+pub use output_persist::{
+    max_persisted_output_bytes_from_env, PersistedOutput, PersistedStream,
+    DEFAULT_MAX_PERSISTED_OUTPUT_BYTES, ENV_VM_MAX_PERSISTED_OUTPUT_BYTES,
+    PERSISTED_OUTPUT_DIR_NAME,
 };
+pub use preflight::{
+    preflight_retry_timeout_ms_from_env, preflight_timeout_ms_from_env, BackendResolution,
+    PreflightAttempt, PreflightOutcome, DEFAULT_VM_PREFLIGHT_TIMEOUT_MS,
+    ENV_VM_PREFLIGHT_RETRY_TIMEOUT_MS, ENV_VM_PREFLIGHT_TIMEOUT_MS,
+};
+pub use run_id::{RunId, RunIdError, RUN_ID_MAX_BYTES};
 pub use sweep::{sweep_orphans_best_effort, SweepReport};
+pub use timeline::{JobEvent, JobEventKind, JobTimeline, JobTimelineRecorder};
+pub use vz_limits::{
+    derive_vz_workload_limits, vz_workload_limits, VzWorkloadLimits,
+    DEFAULT_VZ_GUEST_OVERHEAD_BYTES, DEFAULT_VZ_MEM_BYTES, DEFAULT_VZ_WORKLOAD_PIDS_MAX,
+    ENV_VZ_GUEST_OVERHEAD_BYTES, ENV_VZ_WORKLOAD_PIDS_MAX, VZ_EXIT_WORKLOAD_OOM,
+    VZ_MIN_WORKLOAD_MEM_BYTES,
+};
+pub use workspace::{
+    JobArtifacts, JobWorkspace, JOB_GUEST_IN_DIR, JOB_GUEST_OUT_DIR, JOB_REPORT_FILE,
+};
 
 pub const VM_JOB_SCHEMA_VERSION: &str = "x07.vm.job@0.1.0";
 
@@ -62,34 +140,75 @@ pub const DEFAULT_FIRECRACKER_RUNTIME: &str = "aws.firecracker";
 pub const DEFAULT_FIRECRACKER_SNAPSHOTTER: &str = "devmapper";
 pub const DEFAULT_CONTAINERD_NAMESPACE: &str = "x07";
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Longest container id any backend is given (see [`validate_container_id`]).
+pub const CONTAINER_ID_MAX_BYTES: usize = 128;
+
+pub(crate) const VZ_JOB_IN_GUEST_PATH: &str = "/x07/in";
+pub(crate) const VZ_JOB_OUT_GUEST_PATH: &str = "/x07/out";
+/// Mounts the vz helper shares besides `/x07/in` and `/x07/out`.
+pub(crate) const VZ_MAX_EXTRA_MOUNTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NetworkMode {
     None,
     Default,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MountKind {
+    /// `host_path` is bind-mounted at `guest_path`.
+    #[default]
+    Bind,
+    /// docker/podman named volume; `host_path` is unused. The volume outlives
+    /// the container, so it persists across restarts of the same job. x07
+    /// creates it with the run's `io.x07.*` labels before the first run, so
+    /// sweep can find and reap volumes whose runner is gone.
+    NamedVolume { name: String },
+}
+
 #[derive(Debug, Clone)]
 pub struct MountSpec {
     pub host_path: PathBuf,
     pub guest_path: PathBuf,
     pub readonly: bool,
+    pub kind: MountKind,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LimitsSpec {
     pub wall_ms: u64,
     pub grace_ms: u64,
     pub cleanup_ms: u64,
     pub mem_bytes: Option<u64>,
+    /// `/dev/shm` size for docker/podman (`--shm-size`, rounded up to MiB).
+    /// Other backends ignore it with a warning. Must be <= `mem_bytes` when
+    /// both are set.
+    pub shm_size_bytes: Option<u64>,
     pub vcpus: Option<u32>,
     pub max_stdout_bytes: usize,
     pub max_stderr_bytes: usize,
     pub network: NetworkMode,
+    /// Also tee the full stdout/stderr to `stdout.log` / `stderr.log` under
+    /// `persist_output_dir`, capped by `X07_VM_MAX_PERSISTED_OUTPUT_BYTES`
+    /// rather than `max_stdout_bytes`. [`run_vm_job`] defaults the dir to
+    /// `logs/` under the job's state dir, so the files go away with it.
+    pub persist_output: bool,
+    /// Implies `persist_output`.
+    pub persist_output_dir: Option<PathBuf>,
+    /// Send the workload a SIGTERM this long before the hard deadline, ahead
+    /// of the usual soft stop, so it can flush partial output. Best effort:
+    /// docker, podman and firecracker-ctr signal the task, vz touches
+    /// [`DEADLINE_WARNING_FILE_NAME`] in the `/x07/in` mount for guestd to
+    /// forward. Ignored when it would not land before `deadline - grace_ms`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_before_deadline_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
 pub struct RunSpec {
-    pub run_id: String,
+    pub run_id: RunId,
     pub backend: VmBackend,
     pub image: String,
     pub image_digest: Option<String>,
@@ -98,16 +217,50 @@ pub struct RunSpec {
     pub mounts: Vec<MountSpec>,
     pub workdir: Option<PathBuf>,
     pub limits: LimitsSpec,
+    /// Digest read-only bind mounts before launch and record them in the job
+    /// file and [`RunOutput::mount_digests`].
+    pub digest_mounts: bool,
+    /// Validate the spec and build the backend command (or, for `vz`, the
+    /// helper command and guest request), then return it as
+    /// [`RunOutput::dry_run`] without spawning, sweeping, writing a job file
+    /// or forking a reaper. See [`plan_run`].
+    pub dry_run: bool,
+    /// Guest path under `/x07/out` (e.g. `/x07/out/report.json`) that gets
+    /// the report instead of stdout, for reports too big for the stdout
+    /// caps. On `vz` x07-guestd redirects the workload's stdout there; on
+    /// docker/podman the workload gets the path in `X07_GUEST_REPORT_FILE`
+    /// and writes it itself. [`run_vm_job`] reads it back through the
+    /// `/x07/out` bind mount; see [`resolve_guest_report`].
+    pub report_file: Option<PathBuf>,
 }
 
 #[derive(Debug)]
 pub struct RunOutput {
+    /// The exit code, or `128 + signal` when the process was killed.
     pub exit_status: i32,
+    /// Signal that killed the process (unix only), so a SIGKILL (often an
+    /// OOM kill) can be told apart from a genuine `exit(137)`.
+    pub exit_signal: Option<i32>,
     pub timed_out: bool,
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
     pub stdout_truncated: bool,
     pub stderr_truncated: bool,
+    /// Set by [`run_vm_job`] for captured `vz` / `firecracker-ctr` runs, and
+    /// captured runs on any backend with [`RunSpec::report_file`], that did
+    /// not time out; `None` otherwise.
+    pub guest_report: Option<std::result::Result<GuestReport, GuestOutputError>>,
+    pub timeline: JobTimeline,
+    /// Set when [`RunSpec::digest_mounts`] is on.
+    pub mount_digests: Option<MountDigests>,
+    /// Set for captured runs with [`LimitsSpec::persist_output`] on.
+    pub persisted_output: Option<PersistedOutput>,
+    /// Guest clock minus host clock; see [`guest_clock_skew_ms`].
+    pub guest_clock_skew_ms: Option<i64>,
+    /// Set instead of running anything when [`RunSpec::dry_run`] is on.
+    pub dry_run: Option<RunPlan>,
+    /// Non-fatal problems with the run, e.g. a limit the backend ignored.
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -153,7 +306,7 @@ impl std::str::FromStr for VmBackend {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VmJob {
     pub schema_version: String,
-    pub run_id: String,
+    pub run_id: RunId,
     pub backend: VmBackend,
     pub container_id: String,
     pub pid: Option<u32>,
@@ -162,13 +315,63 @@ pub struct VmJob {
     pub grace_ms: u64,
     pub cleanup_ms: u64,
     pub ctr: Option<CtrJob>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+    /// Written back once the job finishes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<JobTimeline>,
+    /// Read-only mount manifests taken before launch; see [`verify_job_mounts`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mount_digests: Option<MountDigests>,
+    /// Set by [`cancel_job`] before it kills the job.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub cancelled: bool,
+    /// Who cancelled the job and how the kill went; see [`cancel_job`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancellation: Option<JobCancellation>,
+    /// vz only: how `mem_bytes` was split between the guest and the workload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vz_workload_limits: Option<VzWorkloadLimits>,
+    /// Where the full stdout/stderr went; see [`LimitsSpec::persist_output`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persisted_output: Option<PersistedOutput>,
+    /// Copied from [`RunOutput::guest_clock_skew_ms`] once the job finishes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest_clock_skew_ms: Option<i64>,
+    /// The job's `spec.json`; see [`replay_job_spec`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_file: Option<PathBuf>,
+    /// Copied from [`LimitsSpec::warn_before_deadline_ms`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_before_deadline_ms: Option<u64>,
+    /// Set once the reaper sent the deadline warning; see
+    /// [`LimitsSpec::warn_before_deadline_ms`].
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub warned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warned_unix_ms: Option<u64>,
+}
+
+fn is_false(v: &bool) -> bool {
+    !*v
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CtrJob {
     pub bin: String,
     pub address: String,
     pub namespace: String,
+    /// Job files written before these were persisted fall back to the defaults.
+    #[serde(default = "default_firecracker_runtime")]
+    pub runtime: String,
+    #[serde(default = "default_firecracker_snapshotter")]
+    pub snapshotter: String,
+}
+
+fn default_firecracker_runtime() -> String {
+    DEFAULT_FIRECRACKER_RUNTIME.to_string()
+}
+
+fn default_firecracker_snapshotter() -> String {
+    DEFAULT_FIRECRACKER_SNAPSHOTTER.to_string()
 }
 
 #[derive(Debug, Clone)]
@@ -204,8 +407,8 @@ pub fn firecracker_ctr_config_from_job(job: &CtrJob) -> FirecrackerCtrConfig {
         bin: OsString::from(job.bin.clone()),
         address: job.address.clone(),
         namespace: job.namespace.clone(),
-        runtime: DEFAULT_FIRECRACKER_RUNTIME.to_string(),
-        snapshotter: DEFAULT_FIRECRACKER_SNAPSHOTTER.to_string(),
+        runtime: job.runtime.clone(),
+        snapshotter: job.snapshotter.clone(),
     }
 }
 
@@ -276,7 +479,12 @@ fn is_executable(path: &Path) -> bool {
     false
 }
 
-fn preflight_macos_vm_backend(backend: VmBackend) -> Result<()> {
+/// Version warnings (see [`preflight_backend_version`]) go to `warnings`.
+fn preflight_macos_vm_backend(
+    backend: VmBackend,
+    timeout_ms: u64,
+    warnings: &mut Vec<String>,
+) -> PreflightOutcome {
     let mut cmd = match backend {
         VmBackend::AppleContainer => {
             let mut c = Command::new("container");
@@ -284,7 +492,14 @@ fn preflight_macos_vm_backend(backend: VmBackend) -> Result<()> {
             c
         }
         VmBackend::Vz => {
-            let helper = resolve_vz_helper_bin()?;
+            let helper = match resolve_vz_helper_bin() {
+                Ok(helper) => helper,
+                Err(err) => {
+                    return PreflightOutcome::Failed {
+                        message: format!("{err:#}"),
+                    }
+                }
+            };
             let mut c = Command::new(helper);
             c.arg("preflight");
             c
@@ -299,20 +514,41 @@ fn preflight_macos_vm_backend(backend: VmBackend) -> Result<()> {
             c.arg("info");
             c
         }
-        VmBackend::FirecrackerCtr => anyhow::bail!("preflight_macos_vm_backend: invalid backend"),
+        VmBackend::FirecrackerCtr => {
+            return PreflightOutcome::Failed {
+                message: "preflight_macos_vm_backend: invalid backend".to_string(),
+            }
+        }
     };
 
     cmd.stdin(Stdio::null());
-    let out = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
-        .with_context(|| format!("preflight {backend}"))?;
-    if out.timed_out {
-        anyhow::bail!("preflight {backend} timed out");
-    }
-    if out.exit_status != 0 {
-        let stderr = String::from_utf8_lossy(&out.stderr);
-        anyhow::bail!("preflight {backend} failed: {stderr}");
+    let out = run_control_command(cmd, CONTROL_CMD_LIMITS.with_wall_ms(timeout_ms));
+    match preflight::classify_preflight_output(backend, out, timeout_ms) {
+        PreflightOutcome::Ok => match preflight_backend_version(backend) {
+            Ok(warning) => {
+                warnings.extend(warning);
+                PreflightOutcome::Ok
+            }
+            Err(err) => PreflightOutcome::Failed {
+                message: format!("{err:#}"),
+            },
+        },
+        other => other,
     }
-    Ok(())
+}
+
+/// Rejects backend CLIs older than the supported minimum; an untested or
+/// unreadable version is returned as a warning.
+fn preflight_backend_version(backend: VmBackend) -> Result<Option<String>> {
+    let Some(cmd) = backend_version::backend_version_command(backend) else {
+        return Ok(None);
+    };
+    let out = match run_control_command(cmd, CONTROL_CMD_LIMITS) {
+        Ok(out) if !out.timed_out && out.exit_status == 0 => out,
+        _ => return Ok(Some(format!("could not determine {backend} version"))),
+    };
+    let text = String::from_utf8_lossy(&out.stdout);
+    check_backend_version(backend, &text).map_err(anyhow::Error::new)
 }
 
 fn preflight_linux_firecracker_backend(cfg: &FirecrackerCtrConfig) -> Result<()> {
@@ -337,15 +573,68 @@ fn preflight_linux_firecracker_backend(cfg: &FirecrackerCtrConfig) -> Result<()>
     Ok(())
 }
 
+/// Checks that an already chosen `backend` is usable, using the same
+/// preflight as [`resolve_vm_backend`], and returns its warnings.
+/// `firecracker_cfg` defaults to [`firecracker_ctr_config_from_env`].
+pub fn preflight_vm_backend(
+    backend: VmBackend,
+    firecracker_cfg: Option<&FirecrackerCtrConfig>,
+) -> Result<Vec<String>> {
+    if backend == VmBackend::FirecrackerCtr {
+        match firecracker_cfg {
+            Some(cfg) => preflight_linux_firecracker_backend(cfg)?,
+            None => preflight_linux_firecracker_backend(&firecracker_ctr_config_from_env())?,
+        }
+        return Ok(Vec::new());
+    }
+    let mut warnings = Vec::new();
+    match preflight_macos_vm_backend(backend, preflight_timeout_ms_from_env(), &mut warnings) {
+        PreflightOutcome::Ok => Ok(warnings),
+        PreflightOutcome::TimedOut { timeout_ms } => {
+            anyhow::bail!("{backend} preflight timed out after {timeout_ms} ms")
+        }
+        PreflightOutcome::Failed { message } => {
+            anyhow::bail!("{backend} preflight failed: {message}")
+        }
+    }
+}
+
+/// Preflight warnings are dropped; use [`resolve_vm_backend_with_record`] to
+/// surface them.
 pub fn resolve_vm_backend() -> Result<VmBackend> {
+    resolve_vm_backend_with_record().map(|r| r.backend)
+}
+
+/// Like [`resolve_vm_backend`], also returning each preflight attempt.
+///
+/// macOS preflights wait [`preflight_timeout_ms_from_env`] per backend; a
+/// preferred backend that timed out is retried once when
+/// [`ENV_VM_PREFLIGHT_RETRY_TIMEOUT_MS`] is set.
+pub fn resolve_vm_backend_with_record() -> Result<BackendResolution> {
     if let Ok(raw) = std::env::var(ENV_VM_BACKEND) {
         let backend = VmBackend::from_str(&raw)?;
         if cfg!(target_os = "macos") {
             if matches!(backend, VmBackend::FirecrackerCtr) {
                 anyhow::bail!("unsupported {ENV_VM_BACKEND}={backend} on macOS");
             }
-            preflight_macos_vm_backend(backend)?;
-            return Ok(backend);
+            let mut warnings = Vec::new();
+            let (chosen, attempts) = preflight::select_backend(
+                &[backend],
+                preflight_timeout_ms_from_env(),
+                preflight_retry_timeout_ms_from_env(),
+                |b, ms| preflight_macos_vm_backend(b, ms, &mut warnings),
+            );
+            if chosen.is_none() {
+                anyhow::bail!(
+                    "{ENV_VM_BACKEND}={backend} is not usable:\n{}",
+                    preflight::describe_attempts(&attempts)
+                );
+            }
+            return Ok(BackendResolution {
+                backend,
+                attempts,
+                warnings,
+            });
         }
         if cfg!(target_os = "linux") {
             if backend != VmBackend::FirecrackerCtr {
@@ -355,7 +644,11 @@ pub fn resolve_vm_backend() -> Result<VmBackend> {
             }
             let cfg = firecracker_ctr_config_from_env();
             preflight_linux_firecracker_backend(&cfg)?;
-            return Ok(backend);
+            return Ok(BackendResolution {
+                backend,
+                attempts: Vec::new(),
+                warnings: Vec::new(),
+            });
         }
         anyhow::bail!("VM backend is not supported on this platform");
     }
@@ -364,31 +657,44 @@ pub fn resolve_vm_backend() -> Result<VmBackend> {
         let accept_weaker_isolation = read_accept_weaker_isolation_env().unwrap_or(false);
 
         let macos_major = macos_product_major_version().unwrap_or(0);
-        if macos_major >= 26 && preflight_macos_vm_backend(VmBackend::AppleContainer).is_ok() {
-            return Ok(VmBackend::AppleContainer);
+        let mut candidates = Vec::new();
+        if macos_major >= 26 {
+            candidates.push(VmBackend::AppleContainer);
         }
-
-        if preflight_macos_vm_backend(VmBackend::Vz).is_ok() {
-            return Ok(VmBackend::Vz);
+        candidates.push(VmBackend::Vz);
+        if accept_weaker_isolation {
+            candidates.extend([VmBackend::Podman, VmBackend::Docker]);
         }
 
-        if accept_weaker_isolation {
-            for backend in [VmBackend::Podman, VmBackend::Docker] {
-                if preflight_macos_vm_backend(backend).is_ok() {
-                    return Ok(backend);
-                }
-            }
+        let mut warnings = Vec::new();
+        let (chosen, attempts) = preflight::select_backend(
+            &candidates,
+            preflight_timeout_ms_from_env(),
+            preflight_retry_timeout_ms_from_env(),
+            |b, ms| preflight_macos_vm_backend(b, ms, &mut warnings),
+        );
+        if let Some(backend) = chosen {
+            return Ok(BackendResolution {
+                backend,
+                attempts,
+                warnings,
+            });
         }
 
         anyhow::bail!(
-            "no supported VM backend found on macOS\n\nfix:\n  - install the signed {DEFAULT_VZ_HELPER_BIN} helper + provide a VZ guest bundle ({ENV_VZ_GUEST_BUNDLE}), or\n  - on macOS 26+: install and start Apple container, or\n  - (weaker isolation) set {ENV_ACCEPT_WEAKER_ISOLATION}=1 and use Docker Desktop / Podman"
+            "no supported VM backend found on macOS\n\npreflight:\n{}\n\nfix:\n  - install the signed {DEFAULT_VZ_HELPER_BIN} helper + provide a VZ guest bundle ({ENV_VZ_GUEST_BUNDLE}), or\n  - on macOS 26+: install and start Apple container, or\n  - (weaker isolation) set {ENV_ACCEPT_WEAKER_ISOLATION}=1 and use Docker Desktop / Podman",
+            preflight::describe_attempts(&attempts)
         );
     }
 
     if cfg!(target_os = "linux") {
         let cfg = firecracker_ctr_config_from_env();
         preflight_linux_firecracker_backend(&cfg)?;
-        return Ok(VmBackend::FirecrackerCtr);
+        return Ok(BackendResolution {
+            backend: VmBackend::FirecrackerCtr,
+            attempts: Vec::new(),
+            warnings: Vec::new(),
+        });
     }
 
     anyhow::bail!("VM backend is not supported on this platform");
@@ -476,8 +782,8 @@ pub fn validate_container_id(id: &str) -> Result<()> {
     if !id.is_ascii() {
         anyhow::bail!("container id must be ASCII");
     }
-    if id.len() > 128 {
-        anyhow::bail!("container id must be <= 128 bytes");
+    if id.len() > CONTAINER_ID_MAX_BYTES {
+        anyhow::bail!("container id must be <= {CONTAINER_ID_MAX_BYTES} bytes");
     }
     let first = id.as_bytes()[0] as char;
     if !matches!(first, 'A'..='Z' | 'a'..='z' | '0'..='9') {
@@ -492,17 +798,20 @@ pub fn validate_container_id(id: &str) -> Result<()> {
     Ok(())
 }
 
+/// `job_file` is labelled relative to `state_root` (see
+/// [`X07_LABEL_JOB_FILE_KEY`]); a job file outside it is not labelled.
 pub fn x07_label_set(
     state_root: &Path,
-    run_id: &str,
+    run_id: &RunId,
     backend: VmBackend,
     created_unix_ms: u64,
     deadline_unix_ms: u64,
     image_digest: Option<&str>,
+    job_file: &Path,
 ) -> Result<BTreeMap<String, String>> {
     let runner_instance = read_or_create_runner_instance_id(state_root)?;
-    let set = X07LabelSet::new(run_id, runner_instance, deadline_unix_ms)
-        .with_job_id(run_id)
+    let set = X07LabelSet::new(run_id.clone(), runner_instance, deadline_unix_ms)
+        .with_job_id(run_id.as_str())
         .with_backend(format!("vm.{backend}"))
         .with_created_unix_ms(created_unix_ms);
     let set = if let Some(d) = image_digest {
@@ -510,9 +819,25 @@ pub fn x07_label_set(
     } else {
         set
     };
+    let rel = job_file.strip_prefix(state_root).ok().and_then(|rel| {
+        rel.iter()
+            .map(|c| c.to_str())
+            .collect::<Option<Vec<&str>>>()
+            .map(|parts| parts.join("/"))
+    });
+    let set = match rel {
+        Some(rel) if !rel.is_empty() => set.with_job_file(state_root_id(state_root), rel),
+        _ => set,
+    };
     set.to_btreemap().map_err(anyhow::Error::new)
 }
 
+pub fn read_job_file(path: &Path) -> Result<VmJob> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("read job file: {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("parse job JSON: {}", path.display()))
+}
+
 pub fn write_job_file(path: &Path, job: &VmJob) -> Result<()> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
@@ -520,7 +845,10 @@ pub fn write_job_file(path: &Path, job: &VmJob) -> Result<()> {
     }
     let mut bytes = serde_json::to_vec_pretty(job)?;
     bytes.push(b'\n');
-    std::fs::write(path, &bytes).with_context(|| format!("write job file: {}", path.display()))?;
+    // The reaper may read the job file at any time: replace it atomically.
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, &bytes).with_context(|| format!("write job file: {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("write job file: {}", path.display()))?;
     Ok(())
 }
 
@@ -607,7 +935,7 @@ pub(crate) enum CommandIoMode {
 }
 
 #[derive(Debug, Serialize)]
-struct GuestRequestJson {
+pub(crate) struct GuestRequestJson {
     schema_version: &'static str,
     run_id: String,
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -615,6 +943,7 @@ struct GuestRequestJson {
     exec: GuestExecJson,
     expect: GuestExpectJson,
     limits: GuestLimitsJson,
+    report_clock: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -635,6 +964,8 @@ struct GuestExecJson {
 struct GuestExpectJson {
     stdout_schema_version: &'static str,
     stdout_is_single_json_object: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report_file: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -642,6 +973,8 @@ struct GuestLimitsJson {
     wall_ms: u64,
     stdout_max_bytes: u64,
     stderr_max_bytes: u64,
+    workload_mem_bytes: u64,
+    workload_pids_max: u32,
 }
 
 fn resolve_vz_guest_bundle(spec_image: &str) -> Result<PathBuf> {
@@ -704,7 +1037,41 @@ pub fn vz_cleanup_scratch(state_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn vz_helper_command(spec: &RunSpec, state_dir: &Path) -> Result<Command> {
+/// Splits `spec.mounts` into the required `/x07/in` and `/x07/out` host dirs
+/// and the extra mounts shared with the guest. The reservations themselves
+/// are enforced by [`RunSpec::validate_for`].
+pub(crate) fn vz_job_mounts(spec: &RunSpec) -> Result<(PathBuf, PathBuf, Vec<&MountSpec>)> {
+    spec.validate_for(VmBackend::Vz)?;
+
+    let mut job_in = None;
+    let mut job_out = None;
+    let mut extra_mounts: Vec<&MountSpec> = Vec::new();
+    for m in &spec.mounts {
+        let target = normalize_abs_path(&m.guest_path)?;
+        if target == Path::new(VZ_JOB_IN_GUEST_PATH) {
+            job_in = Some(m.host_path.clone());
+        } else if target == Path::new(VZ_JOB_OUT_GUEST_PATH) {
+            job_out = Some(m.host_path.clone());
+        } else {
+            extra_mounts.push(m);
+        }
+    }
+    let job_in = job_in.context("vz backend requires a /x07/in mount")?;
+    let job_out = job_out.context("vz backend requires a /x07/out mount")?;
+    Ok((job_in, job_out, extra_mounts))
+}
+
+/// The vz helper invocation and the request it reads from
+/// `<job_in>/request.json`.
+pub(crate) struct VzHelperPlan {
+    pub(crate) cmd: Command,
+    pub(crate) job_in: PathBuf,
+    pub(crate) request: GuestRequestJson,
+}
+
+/// [`vz_helper_plan_with`] after the host checks: macOS, a guest bundle
+/// (digest-verified when one is pinned) and a helper binary.
+pub(crate) fn vz_helper_plan(spec: &RunSpec, state_dir: &Path) -> Result<VzHelperPlan> {
     if spec.backend != VmBackend::Vz {
         anyhow::bail!("spawn_vz_helper: backend mismatch (expected vz)");
     }
@@ -720,35 +1087,29 @@ fn vz_helper_command(spec: &RunSpec, state_dir: &Path) -> Result<Command> {
             bundle_dir.display()
         );
     }
-
-    let job_in_guest_path = Path::new("/x07/in");
-    let job_out_guest_path = Path::new("/x07/out");
-
-    let job_in = spec
-        .mounts
-        .iter()
-        .find(|m| m.guest_path == job_in_guest_path)
-        .map(|m| m.host_path.clone())
-        .context("vz backend requires a /x07/in mount")?;
-    let job_out = spec
-        .mounts
-        .iter()
-        .find(|m| m.guest_path == job_out_guest_path)
-        .map(|m| m.host_path.clone())
-        .context("vz backend requires a /x07/out mount")?;
-
-    let extra_mounts: Vec<&MountSpec> = spec
-        .mounts
-        .iter()
-        .filter(|m| m.guest_path != job_in_guest_path && m.guest_path != job_out_guest_path)
-        .collect();
-    if extra_mounts.len() > 64 {
-        anyhow::bail!(
-            "vz backend supports at most 64 extra mounts, got {}",
-            extra_mounts.len()
-        );
+    let expected_digest = spec
+        .image_digest
+        .clone()
+        .or_else(|| std::env::var(ENV_VM_GUEST_IMAGE_DIGEST).ok())
+        .filter(|d| !d.trim().is_empty());
+    if let Some(expected_digest) = expected_digest {
+        digest::verify_vz_guest_bundle_digest(&bundle_dir, expected_digest.trim())?;
     }
 
+    let helper = resolve_vz_helper_bin()?;
+    vz_helper_plan_with(spec, state_dir, &bundle_dir, &helper)
+}
+
+/// Builds the helper command and guest request without touching the host.
+pub(crate) fn vz_helper_plan_with(
+    spec: &RunSpec,
+    state_dir: &Path,
+    bundle_dir: &Path,
+    helper: &Path,
+) -> Result<VzHelperPlan> {
+    let (job_in, job_out, extra_mounts) = vz_job_mounts(spec)?;
+    let workload = vz_workload_limits(&spec.limits)?;
+
     let exec_path =
         resolve_guest_exec_path(spec.argv.first().map(|s| s.as_str()).unwrap_or_default())?;
 
@@ -781,7 +1142,7 @@ fn vz_helper_command(spec: &RunSpec, state_dir: &Path) -> Result<Command> {
 
     let req = GuestRequestJson {
         schema_version: "x07.guest.request@1",
-        run_id: spec.run_id.clone(),
+        run_id: spec.run_id.to_string(),
         mounts: req_mounts,
         exec: GuestExecJson {
             path: exec_path,
@@ -791,24 +1152,25 @@ fn vz_helper_command(spec: &RunSpec, state_dir: &Path) -> Result<Command> {
         expect: GuestExpectJson {
             stdout_schema_version: X07_OS_RUNNER_REPORT_SCHEMA_VERSION,
             stdout_is_single_json_object: true,
+            report_file: spec.report_file.as_ref().map(|p| p.display().to_string()),
         },
         limits: GuestLimitsJson {
             wall_ms: spec.limits.wall_ms.max(1),
             stdout_max_bytes: spec.limits.max_stdout_bytes.try_into().unwrap_or(u64::MAX),
             stderr_max_bytes: spec.limits.max_stderr_bytes.try_into().unwrap_or(u64::MAX),
+            workload_mem_bytes: workload.workload_mem_bytes,
+            workload_pids_max: workload.workload_pids_max,
         },
+        report_clock: true,
     };
-    write_guest_request_json(&job_in, &req)?;
-
-    let helper = resolve_vz_helper_bin()?;
     let mut cmd = Command::new(helper);
     cmd.arg("run");
-    cmd.arg("--run-id").arg(&spec.run_id);
+    cmd.arg("--run-id").arg(spec.run_id.as_str());
     cmd.arg("--bundle").arg(bundle_dir);
     cmd.arg("--state-dir").arg(state_dir);
 
-    let mem_bytes = spec.limits.mem_bytes.unwrap_or(512 * 1024 * 1024);
-    cmd.arg("--mem-bytes").arg(mem_bytes.to_string());
+    cmd.arg("--mem-bytes")
+        .arg(workload.vm_mem_bytes.to_string());
     if let Some(v) = spec.limits.vcpus {
         cmd.arg("--cpus").arg(v.to_string());
     }
@@ -830,7 +1192,17 @@ fn vz_helper_command(spec: &RunSpec, state_dir: &Path) -> Result<Command> {
         cmd.arg(if readonly { "ro" } else { "rw" });
     }
 
-    Ok(cmd)
+    Ok(VzHelperPlan {
+        cmd,
+        job_in,
+        request: req,
+    })
+}
+
+fn vz_helper_command(spec: &RunSpec, state_dir: &Path) -> Result<Command> {
+    let plan = vz_helper_plan(spec, state_dir)?;
+    write_guest_request_json(&plan.job_in, &plan.request)?;
+    Ok(plan.cmd)
 }
 
 fn configure_child_stdio(cmd: &mut Command, io_mode: CommandIoMode) {
@@ -929,6 +1301,89 @@ fn validate_mount_kv_string_safe(path: &Path, label: &str) -> Result<()> {
     Ok(())
 }
 
+pub(crate) fn validate_shm_size(limits: &LimitsSpec) -> Result<()> {
+    if let (Some(shm), Some(mem)) = (limits.shm_size_bytes, limits.mem_bytes) {
+        if shm > mem {
+            anyhow::bail!("shm_size_bytes ({shm}) must be <= mem_bytes ({mem})");
+        }
+    }
+    Ok(())
+}
+
+fn bytes_to_mib_ceil(bytes: u64) -> u64 {
+    bytes.saturating_add(1024 * 1024 - 1) / (1024 * 1024)
+}
+
+fn validate_volume_name(name: &str) -> Result<()> {
+    let first = name.bytes().next().context("named volume name is empty")?;
+    if !first.is_ascii_alphanumeric() {
+        anyhow::bail!("named volume {name:?} must start with [A-Za-z0-9]");
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !matches!(c, 'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '.' | '-'))
+    {
+        anyhow::bail!("named volume {name:?} contains invalid character {c:?}");
+    }
+    Ok(())
+}
+
+fn reject_named_volumes(spec: &RunSpec, backend: VmBackend) -> Result<()> {
+    for m in &spec.mounts {
+        if let MountKind::NamedVolume { name } = &m.kind {
+            anyhow::bail!("{backend} backend does not support named volume mounts ({name:?})");
+        }
+    }
+    Ok(())
+}
+
+fn named_volumes(spec: &RunSpec) -> impl Iterator<Item = &str> {
+    spec.mounts.iter().filter_map(|m| match &m.kind {
+        MountKind::NamedVolume { name } => Some(name.as_str()),
+        MountKind::Bind => None,
+    })
+}
+
+/// `<bin> volume create` with the run's labels. Creating an existing volume is
+/// a no-op (podman needs `--ignore`), and its original labels are kept.
+pub(crate) fn named_volume_create_command(
+    bin: &str,
+    name: &str,
+    labels: &BTreeMap<String, String>,
+) -> Result<Command> {
+    validate_volume_name(name)?;
+    let mut cmd = Command::new(bin);
+    cmd.arg("volume").arg("create");
+    if bin == "podman" {
+        cmd.arg("--ignore");
+    }
+    for (k, v) in labels {
+        cmd.arg("--label").arg(format!("{k}={v}"));
+    }
+    cmd.arg(name);
+    Ok(cmd)
+}
+
+fn ensure_named_volumes(
+    bin: &str,
+    spec: &RunSpec,
+    labels: &BTreeMap<String, String>,
+) -> Result<()> {
+    for name in named_volumes(spec) {
+        let out = named_volume_create_command(bin, name, labels)?
+            .stdin(Stdio::null())
+            .output()
+            .with_context(|| format!("{bin} volume create {name}"))?;
+        if !out.status.success() {
+            anyhow::bail!(
+                "{bin} volume create {name} failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+    }
+    Ok(())
+}
+
 fn docker_like_command(
     bin: &str,
     spec: &RunSpec,
@@ -952,10 +1407,15 @@ fn docker_like_command(
         }
     }
 
+    validate_shm_size(&spec.limits)?;
     if let Some(mem_bytes) = spec.limits.mem_bytes {
         // Docker expects a human-ish unit; round up to MiB.
-        let mib = (mem_bytes.saturating_add(1024 * 1024 - 1)) / (1024 * 1024);
-        cmd.arg("--memory").arg(format!("{mib}m"));
+        cmd.arg("--memory")
+            .arg(format!("{}m", bytes_to_mib_ceil(mem_bytes)));
+    }
+    if let Some(shm_bytes) = spec.limits.shm_size_bytes {
+        cmd.arg("--shm-size")
+            .arg(format!("{}m", bytes_to_mib_ceil(shm_bytes)));
     }
     if let Some(vcpus) = spec.limits.vcpus {
         cmd.arg("--cpus").arg(vcpus.to_string());
@@ -975,16 +1435,27 @@ fn docker_like_command(
     for (k, v) in &spec.env {
         cmd.arg("--env").arg(format!("{k}={v}"));
     }
+    if let Some(report_file) = spec.report_file.as_ref() {
+        if !spec.env.contains_key(ENV_GUEST_REPORT_FILE) {
+            cmd.arg("--env")
+                .arg(format!("{ENV_GUEST_REPORT_FILE}={}", report_file.display()));
+        }
+    }
 
     for m in &spec.mounts {
-        validate_mount_kv_string_safe(&m.host_path, "host")?;
         validate_mount_kv_string_safe(&m.guest_path, "guest")?;
 
-        let mut mount = format!(
-            "type=bind,source={},target={}",
-            m.host_path.display(),
-            m.guest_path.display()
-        );
+        let source = match &m.kind {
+            MountKind::Bind => {
+                validate_mount_kv_string_safe(&m.host_path, "host")?;
+                format!("type=bind,source={}", m.host_path.display())
+            }
+            MountKind::NamedVolume { name } => {
+                validate_volume_name(name)?;
+                format!("type=volume,source={name}")
+            }
+        };
+        let mut mount = format!("{source},target={}", m.guest_path.display());
         if m.readonly {
             mount.push_str(",readonly");
         }
@@ -999,19 +1470,23 @@ fn docker_like_command(
     Ok(cmd)
 }
 
-fn run_docker_like(
+pub(crate) fn run_docker_like(
     bin: &str,
     spec: &RunSpec,
     container_id: &str,
     labels: &BTreeMap<String, String>,
     include_annotations: bool,
+    timeline: &JobTimelineRecorder,
 ) -> Result<RunOutput> {
     let cmd = docker_like_command(bin, spec, container_id, labels, include_annotations, false)?;
-    run_command_capped(
+    ensure_named_volumes(bin, spec, labels)?;
+    run_command_capped_timed(
         cmd,
         spec.limits.wall_ms,
         spec.limits.max_stdout_bytes,
         spec.limits.max_stderr_bytes,
+        OutputPersist::for_limits(&spec.limits)?.as_ref(),
+        timeline,
     )
 }
 
@@ -1020,7 +1495,14 @@ pub fn run_docker(
     container_id: &str,
     labels: &BTreeMap<String, String>,
 ) -> Result<RunOutput> {
-    run_docker_like("docker", spec, container_id, labels, false)
+    run_docker_like(
+        "docker",
+        spec,
+        container_id,
+        labels,
+        false,
+        &JobTimelineRecorder::start(),
+    )
 }
 
 pub fn run_podman(
@@ -1028,7 +1510,14 @@ pub fn run_podman(
     container_id: &str,
     labels: &BTreeMap<String, String>,
 ) -> Result<RunOutput> {
-    run_docker_like("podman", spec, container_id, labels, true)
+    run_docker_like(
+        "podman",
+        spec,
+        container_id,
+        labels,
+        true,
+        &JobTimelineRecorder::start(),
+    )
 }
 
 pub fn run_docker_passthrough(
@@ -1037,6 +1526,7 @@ pub fn run_docker_passthrough(
     labels: &BTreeMap<String, String>,
 ) -> Result<RunOutput> {
     let cmd = docker_like_command("docker", spec, container_id, labels, false, true)?;
+    ensure_named_volumes("docker", spec, labels)?;
     run_command_passthrough(cmd, spec.limits.wall_ms)
 }
 
@@ -1046,6 +1536,7 @@ pub fn run_podman_passthrough(
     labels: &BTreeMap<String, String>,
 ) -> Result<RunOutput> {
     let cmd = docker_like_command("podman", spec, container_id, labels, true, true)?;
+    ensure_named_volumes("podman", spec, labels)?;
     run_command_passthrough(cmd, spec.limits.wall_ms)
 }
 
@@ -1054,6 +1545,7 @@ fn apple_container_command(
     container_id: &str,
     labels: &BTreeMap<String, String>,
 ) -> Result<Command> {
+    reject_named_volumes(spec, VmBackend::AppleContainer)?;
     let mut cmd = Command::new("container");
     cmd.arg("run");
     cmd.arg("--name").arg(container_id);
@@ -1114,13 +1606,24 @@ pub fn run_apple_container(
     spec: &RunSpec,
     container_id: &str,
     labels: &BTreeMap<String, String>,
+) -> Result<RunOutput> {
+    run_apple_container_timed(spec, container_id, labels, &JobTimelineRecorder::start())
+}
+
+pub(crate) fn run_apple_container_timed(
+    spec: &RunSpec,
+    container_id: &str,
+    labels: &BTreeMap<String, String>,
+    timeline: &JobTimelineRecorder,
 ) -> Result<RunOutput> {
     let cmd = apple_container_command(spec, container_id, labels)?;
-    run_command_capped(
+    run_command_capped_timed(
         cmd,
         spec.limits.wall_ms,
         spec.limits.max_stdout_bytes,
         spec.limits.max_stderr_bytes,
+        OutputPersist::for_limits(&spec.limits)?.as_ref(),
+        timeline,
     )
 }
 
@@ -1140,7 +1643,7 @@ fn docker_like_soft_stop(bin: &str, container_id: &str, grace_ms: u64) -> Result
         .arg("--time")
         .arg(secs.to_string())
         .arg(container_id);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("{bin} stop {container_id}"))?;
     Ok(())
 }
@@ -1159,7 +1662,7 @@ pub fn apple_container_soft_stop(container_id: &str) -> Result<()> {
         .arg("--signal")
         .arg("SIGTERM")
         .arg(container_id);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("container kill SIGTERM {container_id}"))?;
     Ok(())
 }
@@ -1170,7 +1673,7 @@ fn docker_like_hard_kill(bin: &str, container_id: &str) -> Result<()> {
         .arg("--signal")
         .arg("SIGKILL")
         .arg(container_id);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("{bin} kill {container_id}"))?;
     Ok(())
 }
@@ -1189,7 +1692,7 @@ pub fn apple_container_hard_kill(container_id: &str) -> Result<()> {
         .arg("--signal")
         .arg("KILL")
         .arg(container_id);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("container kill KILL {container_id}"))?;
     Ok(())
 }
@@ -1197,7 +1700,7 @@ pub fn apple_container_hard_kill(container_id: &str) -> Result<()> {
 fn docker_like_cleanup(bin: &str, container_id: &str) -> Result<()> {
     let mut cmd = Command::new(bin);
     cmd.arg("rm").arg("-f").arg(container_id);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("{bin} rm -f {container_id}"))?;
     Ok(())
 }
@@ -1213,7 +1716,7 @@ pub fn podman_cleanup(container_id: &str) -> Result<()> {
 pub fn apple_container_cleanup(container_id: &str) -> Result<()> {
     let mut cmd = Command::new("container");
     cmd.arg("delete").arg("--force").arg(container_id);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("container delete --force {container_id}"))?;
     Ok(())
 }
@@ -1238,6 +1741,7 @@ fn firecracker_ctr_command(
     container_id: &str,
     labels: &BTreeMap<String, String>,
 ) -> Result<Command> {
+    reject_named_volumes(spec, VmBackend::FirecrackerCtr)?;
     let mut cmd = Command::new(&cfg.bin);
     cmd.args(ctr_base_args(cfg));
     cmd.arg("run");
@@ -1298,13 +1802,31 @@ pub fn run_firecracker_ctr(
     cfg: &FirecrackerCtrConfig,
     container_id: &str,
     labels: &BTreeMap<String, String>,
+) -> Result<RunOutput> {
+    run_firecracker_ctr_timed(
+        spec,
+        cfg,
+        container_id,
+        labels,
+        &JobTimelineRecorder::start(),
+    )
+}
+
+pub(crate) fn run_firecracker_ctr_timed(
+    spec: &RunSpec,
+    cfg: &FirecrackerCtrConfig,
+    container_id: &str,
+    labels: &BTreeMap<String, String>,
+    timeline: &JobTimelineRecorder,
 ) -> Result<RunOutput> {
     let cmd = firecracker_ctr_command(spec, cfg, container_id, labels)?;
-    run_command_capped(
+    run_command_capped_timed(
         cmd,
         spec.limits.wall_ms,
         spec.limits.max_stdout_bytes,
         spec.limits.max_stderr_bytes,
+        OutputPersist::for_limits(&spec.limits)?.as_ref(),
+        timeline,
     )
 }
 
@@ -1336,7 +1858,7 @@ pub fn firecracker_ctr_soft_stop(
             OsString::from("SIGTERM"),
             OsString::from(container_id),
         ]);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("firecracker-ctr tasks kill SIGTERM {container_id}"))?;
     Ok(())
 }
@@ -1354,7 +1876,7 @@ pub fn firecracker_ctr_hard_kill(cfg: &FirecrackerCtrConfig, container_id: &str)
             OsString::from("SIGKILL"),
             OsString::from(container_id),
         ]);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("firecracker-ctr tasks kill SIGKILL {container_id}"))?;
     Ok(())
 }
@@ -1370,7 +1892,7 @@ pub fn firecracker_ctr_cleanup(cfg: &FirecrackerCtrConfig, container_id: &str) -
             OsString::from("--force"),
             OsString::from(container_id),
         ]);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("firecracker-ctr tasks delete --force {container_id}"))?;
 
     let mut cmd = Command::new(&cfg.bin);
@@ -1382,7 +1904,7 @@ pub fn firecracker_ctr_cleanup(cfg: &FirecrackerCtrConfig, container_id: &str) -
             OsString::from("delete"),
             OsString::from(container_id),
         ]);
-    let _ = run_command_capped(cmd, 2_000, 64 * 1024, 64 * 1024)
+    let _ = run_control_command(cmd, CONTROL_CMD_LIMITS)
         .with_context(|| format!("firecracker-ctr containers delete {container_id}"))?;
     Ok(())
 }
@@ -1409,28 +1931,69 @@ pub(crate) fn wait_child_with_wall_timeout_ms(
 }
 
 pub fn wait_child_output_capped(
+    child: std::process::Child,
+    wall_ms: u64,
+    stdout_cap: usize,
+    stderr_cap: usize,
+) -> Result<RunOutput> {
+    wait_child_output_capped_timed(
+        child,
+        wall_ms,
+        stdout_cap,
+        stderr_cap,
+        None,
+        &JobTimelineRecorder::start(),
+    )
+}
+
+pub(crate) fn wait_child_output_capped_timed(
     mut child: std::process::Child,
     wall_ms: u64,
     stdout_cap: usize,
     stderr_cap: usize,
+    persist: Option<&OutputPersist>,
+    timeline: &JobTimelineRecorder,
 ) -> Result<RunOutput> {
-    let stdout = child.stdout.take().context("take stdout")?;
+    let stdout =
+        timeline::FirstByteReader::new(child.stdout.take().context("take stdout")?, timeline);
     let stderr = child.stderr.take().context("take stderr")?;
 
-    let stdout_thread = std::thread::spawn(move || -> std::io::Result<(Vec<u8>, bool)> {
-        x07_host_runner::read_to_end_capped(stdout, stdout_cap)
+    let logs = persist
+        .map(|p| -> Result<_> { Ok((p.open("stdout")?, p.open("stderr")?)) })
+        .transpose();
+    let (stdout_log, stderr_log) = match logs {
+        Ok(Some((stdout_log, stderr_log))) => (Some(stdout_log), Some(stderr_log)),
+        Ok(None) => (None, None),
+        Err(err) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err);
+        }
+    };
+    let stdout_thread = std::thread::spawn(move || {
+        output_persist::read_to_end_capped_teed(stdout, stdout_cap, stdout_log)
     });
-    let stderr_thread = std::thread::spawn(move || -> std::io::Result<(Vec<u8>, bool)> {
-        x07_host_runner::read_to_end_capped(stderr, stderr_cap)
+    let stderr_thread = std::thread::spawn(move || {
+        output_persist::read_to_end_capped_teed(stderr, stderr_cap, stderr_log)
     });
 
     let (status, timed_out) = wait_child_with_wall_timeout_ms(&mut child, wall_ms)?;
-    let (stdout_bytes, stdout_truncated) = stdout_thread
+    timeline.record_once(JobEventKind::Exited);
+    let (stdout_bytes, stdout_truncated, stdout_persisted) = stdout_thread
         .join()
-        .unwrap_or_else(|_| Ok((Vec::new(), false)))?;
-    let (stderr_bytes, stderr_truncated) = stderr_thread
+        .unwrap_or_else(|_| Ok((Vec::new(), false, None)))?;
+    let (stderr_bytes, stderr_truncated, stderr_persisted) = stderr_thread
         .join()
-        .unwrap_or_else(|_| Ok((Vec::new(), false)))?;
+        .unwrap_or_else(|_| Ok((Vec::new(), false, None)))?;
+    let persisted_output = match (persist, stdout_persisted, stderr_persisted) {
+        (Some(p), Some(stdout), Some(stderr)) => Some(PersistedOutput {
+            dir: p.dir.clone(),
+            max_bytes: p.max_bytes,
+            stdout,
+            stderr,
+        }),
+        _ => None,
+    };
 
     #[cfg(unix)]
     let exit_signal = {
@@ -1447,11 +2010,19 @@ pub fn wait_child_output_capped(
 
     Ok(RunOutput {
         exit_status,
+        exit_signal,
         timed_out,
         stdout: stdout_bytes,
         stderr: stderr_bytes,
         stdout_truncated,
         stderr_truncated,
+        guest_report: None,
+        timeline: timeline.snapshot(),
+        mount_digests: None,
+        persisted_output,
+        guest_clock_skew_ms: None,
+        dry_run: None,
+        warnings: Vec::new(),
     })
 }
 
@@ -1476,26 +2047,53 @@ pub(crate) fn wait_child_passthrough(
 
     Ok(RunOutput {
         exit_status,
+        exit_signal,
         timed_out,
         stdout: Vec::new(),
         stderr: Vec::new(),
         stdout_truncated: false,
         stderr_truncated: false,
+        guest_report: None,
+        timeline: JobTimeline::default(),
+        mount_digests: None,
+        persisted_output: None,
+        guest_clock_skew_ms: None,
+        dry_run: None,
+        warnings: Vec::new(),
     })
 }
 
 fn run_command_capped(
+    cmd: Command,
+    wall_ms: u64,
+    stdout_cap: usize,
+    stderr_cap: usize,
+) -> Result<RunOutput> {
+    run_command_capped_timed(
+        cmd,
+        wall_ms,
+        stdout_cap,
+        stderr_cap,
+        None,
+        &JobTimelineRecorder::start(),
+    )
+}
+
+pub(crate) fn run_command_capped_timed(
     mut cmd: Command,
     wall_ms: u64,
     stdout_cap: usize,
     stderr_cap: usize,
+    persist: Option<&OutputPersist>,
+    timeline: &JobTimelineRecorder,
 ) -> Result<RunOutput> {
     cmd.stdin(Stdio::null());
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
     let child = cmd.spawn().context("spawn command")?;
-    wait_child_output_capped(child, wall_ms, stdout_cap, stderr_cap)
+    timeline.record(JobEventKind::TaskStarted);
+    wait_child_output_capped_timed(child, wall_ms, stdout_cap, stderr_cap, persist, timeline)
 }
 
 fn run_command_passthrough(mut cmd: Command, wall_ms: u64) -> Result<RunOutput> {
@@ -1541,6 +2139,7 @@ pub fn append_root_mounts(
             host_path,
             guest_path,
             readonly,
+            kind: MountKind::Bind,
         });
         if !readonly {
             entry.readonly = false;
@@ -1680,6 +2279,32 @@ mod tests {
         assert!(default_grace_ms(100_000) <= 2_000);
     }
 
+    #[test]
+    fn ctr_job_round_trips_runtime_and_snapshotter() {
+        let job = CtrJob {
+            bin: "/opt/fc/firecracker-ctr".to_string(),
+            address: "/run/fc/containerd.sock".to_string(),
+            namespace: "x07-ci".to_string(),
+            runtime: "aws.firecracker.v2".to_string(),
+            snapshotter: "overlayfs".to_string(),
+        };
+        let bytes = serde_json::to_vec(&job).unwrap();
+        let back: CtrJob = serde_json::from_slice(&bytes).unwrap();
+        let cfg = firecracker_ctr_config_from_job(&back);
+        assert_eq!(cfg.bin, OsString::from("/opt/fc/firecracker-ctr"));
+        assert_eq!(cfg.address, "/run/fc/containerd.sock");
+        assert_eq!(cfg.namespace, "x07-ci");
+        assert_eq!(cfg.runtime, "aws.firecracker.v2");
+        assert_eq!(cfg.snapshotter, "overlayfs");
+
+        let old: CtrJob = serde_json::from_str(
+            r#"{"bin":"firecracker-ctr","address":"/sock","namespace":"x07"}"#,
+        )
+        .unwrap();
+        assert_eq!(old.runtime, DEFAULT_FIRECRACKER_RUNTIME);
+        assert_eq!(old.snapshotter, DEFAULT_FIRECRACKER_SNAPSHOTTER);
+    }
+
     #[test]
     fn container_id_validation() {
         validate_container_id("x07-abc.DEF_123").unwrap();
@@ -1688,6 +2313,42 @@ mod tests {
         assert!(validate_container_id(&"a".repeat(129)).is_err());
     }
 
+    #[test]
+    fn guest_request_carries_workload_limits() {
+        let workload = derive_vz_workload_limits(
+            Some(512 * 1024 * 1024),
+            DEFAULT_VZ_GUEST_OVERHEAD_BYTES,
+            DEFAULT_VZ_WORKLOAD_PIDS_MAX,
+        )
+        .unwrap();
+        let limits = GuestLimitsJson {
+            wall_ms: 1000,
+            stdout_max_bytes: 1024,
+            stderr_max_bytes: 1024,
+            workload_mem_bytes: workload.workload_mem_bytes,
+            workload_pids_max: workload.workload_pids_max,
+        };
+        let v = serde_json::to_value(&limits).unwrap();
+        assert_eq!(v["workload_mem_bytes"], 416 * 1024 * 1024);
+        assert_eq!(v["workload_pids_max"], 1024);
+
+        let job: VmJob = serde_json::from_value(serde_json::json!({
+            "schema_version": VM_JOB_SCHEMA_VERSION,
+            "run_id": "test-run",
+            "backend": "vz",
+            "container_id": "x07-test-run",
+            "pid": null,
+            "created_unix_ms": 0,
+            "deadline_unix_ms": 0,
+            "grace_ms": 0,
+            "cleanup_ms": 0,
+            "ctr": null,
+            "vz_workload_limits": workload,
+        }))
+        .unwrap();
+        assert_eq!(job.vz_workload_limits, Some(workload));
+    }
+
     #[test]
     fn mount_kv_string_validation_rejects_comma() {
         assert!(validate_mount_kv_string_safe(Path::new("/tmp/has,comma"), "host").is_err());
@@ -1696,7 +2357,7 @@ mod tests {
     #[test]
     fn docker_passthrough_command_requests_interactive_stdin() {
         let spec = RunSpec {
-            run_id: "test-run".to_string(),
+            run_id: RunId::parse("test-run").unwrap(),
             backend: VmBackend::Docker,
             image: "example:latest".to_string(),
             image_digest: None,
@@ -1713,7 +2374,14 @@ mod tests {
                 max_stdout_bytes: 1_024,
                 max_stderr_bytes: 1_024,
                 network: NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+                shm_size_bytes: None,
             },
+            digest_mounts: false,
+            dry_run: false,
+            report_file: None,
         };
 
         let cmd = docker_like_command(
@@ -1733,6 +2401,185 @@ mod tests {
         assert!(args.iter().any(|arg| arg == "-i"));
     }
 
+    #[test]
+    fn docker_command_sets_shm_size_rounded_up_to_mib() {
+        let mut spec = RunSpec {
+            run_id: RunId::parse("test-run").unwrap(),
+            backend: VmBackend::Docker,
+            image: "example:latest".to_string(),
+            image_digest: None,
+            argv: Vec::new(),
+            env: BTreeMap::new(),
+            mounts: Vec::new(),
+            workdir: None,
+            limits: LimitsSpec {
+                wall_ms: 1_000,
+                grace_ms: 100,
+                cleanup_ms: 100,
+                mem_bytes: Some(1024 * 1024 * 1024),
+                shm_size_bytes: Some(256 * 1024 * 1024 + 1),
+                vcpus: None,
+                max_stdout_bytes: 1_024,
+                max_stderr_bytes: 1_024,
+                network: NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+            },
+            digest_mounts: false,
+            dry_run: false,
+            report_file: None,
+        };
+        let argv = |spec: &RunSpec| -> Result<Vec<String>> {
+            let cmd = docker_like_command(
+                "docker",
+                spec,
+                "test-container",
+                &BTreeMap::new(),
+                false,
+                false,
+            )?;
+            Ok(cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect())
+        };
+
+        let args = argv(&spec).unwrap();
+        let at = args
+            .iter()
+            .position(|a| a == "--shm-size")
+            .expect("--shm-size");
+        assert_eq!(args[at + 1], "257m");
+
+        spec.limits.shm_size_bytes = Some(64 * 1024 * 1024);
+        let args = argv(&spec).unwrap();
+        let at = args
+            .iter()
+            .position(|a| a == "--shm-size")
+            .expect("--shm-size");
+        assert_eq!(args[at + 1], "64m");
+
+        spec.limits.shm_size_bytes = None;
+        assert!(!argv(&spec).unwrap().iter().any(|a| a == "--shm-size"));
+
+        spec.limits.shm_size_bytes = Some(2 * 1024 * 1024 * 1024);
+        let err = argv(&spec).unwrap_err();
+        assert!(err.to_string().contains("must be <= mem_bytes"), "{err}");
+    }
+
+    #[test]
+    fn docker_command_maps_named_volume_mounts() {
+        let spec = RunSpec {
+            run_id: RunId::parse("test-run").unwrap(),
+            backend: VmBackend::Podman,
+            image: "example:latest".to_string(),
+            image_digest: None,
+            argv: Vec::new(),
+            env: BTreeMap::new(),
+            mounts: vec![
+                MountSpec {
+                    host_path: PathBuf::from("/tmp/job-in"),
+                    guest_path: PathBuf::from("/x07/in"),
+                    readonly: true,
+                    kind: MountKind::Bind,
+                },
+                MountSpec {
+                    host_path: PathBuf::new(),
+                    guest_path: PathBuf::from("/x07/scratch"),
+                    readonly: false,
+                    kind: MountKind::NamedVolume {
+                        name: "x07-scratch.1".to_string(),
+                    },
+                },
+            ],
+            workdir: None,
+            limits: LimitsSpec {
+                wall_ms: 1_000,
+                grace_ms: 100,
+                cleanup_ms: 100,
+                mem_bytes: None,
+                shm_size_bytes: None,
+                vcpus: None,
+                max_stdout_bytes: 1_024,
+                max_stderr_bytes: 1_024,
+                network: NetworkMode::None,
+                persist_output: false,
+                persist_output_dir: None,
+                warn_before_deadline_ms: None,
+            },
+            digest_mounts: false,
+            dry_run: false,
+            report_file: None,
+        };
+        let args = |cmd: Command| -> Vec<String> {
+            cmd.get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect()
+        };
+
+        let run = args(
+            docker_like_command(
+                "podman",
+                &spec,
+                "test-container",
+                &BTreeMap::new(),
+                true,
+                false,
+            )
+            .unwrap(),
+        );
+        let mounts: Vec<&str> = run
+            .windows(2)
+            .filter(|w| w[0] == "--mount")
+            .map(|w| w[1].as_str())
+            .collect();
+        assert_eq!(
+            mounts,
+            [
+                "type=bind,source=/tmp/job-in,target=/x07/in,readonly",
+                "type=volume,source=x07-scratch.1,target=/x07/scratch",
+            ]
+        );
+
+        let labels = BTreeMap::from([(X07_LABEL_RUN_ID_KEY.to_string(), "test-run".to_string())]);
+        for (bin, expected) in [
+            (
+                "docker",
+                &[
+                    "volume",
+                    "create",
+                    "--label",
+                    "io.x07.run_id=test-run",
+                    "x07-scratch.1",
+                ][..],
+            ),
+            (
+                "podman",
+                &[
+                    "volume",
+                    "create",
+                    "--ignore",
+                    "--label",
+                    "io.x07.run_id=test-run",
+                    "x07-scratch.1",
+                ][..],
+            ),
+        ] {
+            let create = args(named_volume_create_command(bin, "x07-scratch.1", &labels).unwrap());
+            assert_eq!(create, expected, "{bin}");
+        }
+
+        let mut bad = spec.clone();
+        bad.mounts[1].kind = MountKind::NamedVolume {
+            name: "../etc".to_string(),
+        };
+        assert!(docker_like_command("docker", &bad, "c", &BTreeMap::new(), false, false).is_err());
+
+        let err = apple_container_command(&spec, "c", &BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains("named volume"), "{err}");
+    }
+
     #[cfg(unix)]
     #[test]
     fn mount_kv_string_validation_rejects_nul() {
@@ -1743,4 +2590,22 @@ mod tests {
         let p = PathBuf::from(os);
         assert!(validate_mount_kv_string_safe(&p, "host").is_err());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn signal_death_is_reported_apart_from_exit_code() {
+        let sh = |script: &str| {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", script]);
+            run_command_capped(cmd, 5_000, 1024, 1024).unwrap()
+        };
+
+        let killed = sh("kill -KILL $$");
+        assert_eq!(killed.exit_signal, Some(libc::SIGKILL));
+        assert_eq!(killed.exit_status, 128 + libc::SIGKILL);
+
+        let exited = sh("exit 137");
+        assert_eq!(exited.exit_signal, None);
+        assert_eq!(exited.exit_status, 137);
+    }
 }
@@ -0,0 +1,541 @@
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::{MountKind, MountSpec, VmJob};
+
+pub const ENV_VM_MAX_MOUNT_DIGEST_BYTES: &str = "X07_VM_MAX_MOUNT_DIGEST_BYTES";
+pub const DEFAULT_MAX_MOUNT_DIGEST_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Upper bound on hashing threads per mount.
+const MAX_DIGEST_THREADS: usize = 8;
+
+/// One manifest entry: a regular file (content sha256) or a symlink (sha256
+/// of its target, `size` 0). `path` is relative to the mount root, `/`-joined.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountFileDigest {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Digest of a read-only bind mount taken before launch. `manifest_sha256`
+/// covers the sorted [`MountFileDigest`] list, so any added, removed or edited
+/// file changes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountDigest {
+    pub host_path: PathBuf,
+    pub guest_path: PathBuf,
+    pub manifest_sha256: String,
+    pub files: u64,
+    pub total_bytes: u64,
+}
+
+/// A mount left undigested because its files exceed `max_digest_bytes`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedMountDigest {
+    pub host_path: PathBuf,
+    pub guest_path: PathBuf,
+    /// Bytes seen before the walk stopped; at least `max_digest_bytes + 1`.
+    pub seen_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountDigests {
+    pub max_digest_bytes: u64,
+    pub digested: Vec<MountDigest>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedMountDigest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MountDriftKind {
+    Changed { manifest_sha256: String },
+    Missing,
+    TooLarge { seen_bytes: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountDrift {
+    pub host_path: PathBuf,
+    pub guest_path: PathBuf,
+    pub expected_manifest_sha256: String,
+    pub drift: MountDriftKind,
+}
+
+/// `X07_VM_MAX_MOUNT_DIGEST_BYTES`, or [`DEFAULT_MAX_MOUNT_DIGEST_BYTES`].
+pub fn max_mount_digest_bytes_from_env() -> u64 {
+    std::env::var(ENV_VM_MAX_MOUNT_DIGEST_BYTES)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_MOUNT_DIGEST_BYTES)
+}
+
+/// Digests every read-only bind mount in `mounts`. Writable and named-volume
+/// mounts are not inputs and are left out. Mounts larger than
+/// `max_digest_bytes` are listed in `skipped` instead of failing the run.
+pub fn digest_mounts(mounts: &[MountSpec], max_digest_bytes: u64) -> Result<MountDigests> {
+    let mut out = MountDigests {
+        max_digest_bytes,
+        ..MountDigests::default()
+    };
+    for m in mounts {
+        if !m.readonly || m.kind != MountKind::Bind {
+            continue;
+        }
+        match mount_manifest(&m.host_path, max_digest_bytes)? {
+            ManifestOutcome::Manifest(files) => out.digested.push(MountDigest {
+                host_path: m.host_path.clone(),
+                guest_path: m.guest_path.clone(),
+                manifest_sha256: manifest_sha256(&files),
+                files: files.len() as u64,
+                total_bytes: files.iter().map(|f| f.size).sum(),
+            }),
+            ManifestOutcome::TooLarge { seen_bytes } => out.skipped.push(SkippedMountDigest {
+                host_path: m.host_path.clone(),
+                guest_path: m.guest_path.clone(),
+                seen_bytes,
+            }),
+        }
+    }
+    Ok(out)
+}
+
+/// Re-hashes the mounts recorded in `job` and returns the ones that no longer
+/// match. Recorded host paths are resolved under `current_fs` (pass `/` for
+/// the live host, or the root of a restored snapshot).
+pub fn verify_job_mounts(job: &VmJob, current_fs: &Path) -> Result<Vec<MountDrift>> {
+    let Some(digests) = &job.mount_digests else {
+        return Ok(Vec::new());
+    };
+    let mut drifts = Vec::new();
+    for d in &digests.digested {
+        let rel = d.host_path.strip_prefix("/").unwrap_or(&d.host_path);
+        let path = current_fs.join(rel);
+        let drift = if std::fs::metadata(&path).is_err() {
+            Some(MountDriftKind::Missing)
+        } else {
+            match mount_manifest(&path, digests.max_digest_bytes)? {
+                ManifestOutcome::Manifest(files) => {
+                    let got = manifest_sha256(&files);
+                    (got != d.manifest_sha256).then_some(MountDriftKind::Changed {
+                        manifest_sha256: got,
+                    })
+                }
+                ManifestOutcome::TooLarge { seen_bytes } => {
+                    Some(MountDriftKind::TooLarge { seen_bytes })
+                }
+            }
+        };
+        if let Some(drift) = drift {
+            drifts.push(MountDrift {
+                host_path: d.host_path.clone(),
+                guest_path: d.guest_path.clone(),
+                expected_manifest_sha256: d.manifest_sha256.clone(),
+                drift,
+            });
+        }
+    }
+    Ok(drifts)
+}
+
+enum ManifestOutcome {
+    Manifest(Vec<MountFileDigest>),
+    TooLarge { seen_bytes: u64 },
+}
+
+enum Entry {
+    File {
+        rel: String,
+        path: PathBuf,
+        size: u64,
+    },
+    Symlink {
+        rel: String,
+        target: PathBuf,
+    },
+}
+
+fn mount_manifest(root: &Path, max_digest_bytes: u64) -> Result<ManifestOutcome> {
+    let mut entries = Vec::new();
+    let mut seen_bytes = 0u64;
+    // The bind mount exposes what a symlinked root points at, so follow it;
+    // symlinks inside the tree are recorded as links.
+    let meta =
+        std::fs::metadata(root).with_context(|| format!("stat mount: {}", root.display()))?;
+    let complete = if meta.is_dir() {
+        walk(root, "", max_digest_bytes, &mut seen_bytes, &mut entries)?
+    } else {
+        // A single bind-mounted file is its own one-entry manifest.
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        push_entry(
+            root,
+            name,
+            &meta,
+            max_digest_bytes,
+            &mut seen_bytes,
+            &mut entries,
+        )?
+    };
+    if !complete {
+        return Ok(ManifestOutcome::TooLarge { seen_bytes });
+    }
+
+    let mut files = hash_entries(&entries)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ManifestOutcome::Manifest(files))
+}
+
+/// Returns `false` as soon as the byte budget is exceeded.
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    max_digest_bytes: u64,
+    seen_bytes: &mut u64,
+    out: &mut Vec<Entry>,
+) -> Result<bool> {
+    let rd = std::fs::read_dir(dir).with_context(|| format!("read_dir: {}", dir.display()))?;
+    for entry in rd {
+        let entry = entry.with_context(|| format!("read_dir entry: {}", dir.display()))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let rel = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let path = entry.path();
+        let meta = std::fs::symlink_metadata(&path)
+            .with_context(|| format!("stat: {}", path.display()))?;
+        let complete = if meta.is_dir() {
+            walk(&path, &rel, max_digest_bytes, seen_bytes, out)?
+        } else {
+            push_entry(&path, rel, &meta, max_digest_bytes, seen_bytes, out)?
+        };
+        if !complete {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn push_entry(
+    path: &Path,
+    rel: String,
+    meta: &std::fs::Metadata,
+    max_digest_bytes: u64,
+    seen_bytes: &mut u64,
+    out: &mut Vec<Entry>,
+) -> Result<bool> {
+    if meta.file_type().is_symlink() {
+        let target =
+            std::fs::read_link(path).with_context(|| format!("readlink: {}", path.display()))?;
+        out.push(Entry::Symlink { rel, target });
+    } else if meta.is_file() {
+        *seen_bytes = seen_bytes.saturating_add(meta.len());
+        if *seen_bytes > max_digest_bytes {
+            return Ok(false);
+        }
+        out.push(Entry::File {
+            rel,
+            path: path.to_path_buf(),
+            size: meta.len(),
+        });
+    }
+    Ok(true)
+}
+
+/// Hashes `entries` on up to [`MAX_DIGEST_THREADS`] scoped threads.
+fn hash_entries(entries: &[Entry]) -> Result<Vec<MountFileDigest>> {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_DIGEST_THREADS)
+        .min(entries.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<MountFileDigest>> = Mutex::new(Vec::with_capacity(entries.len()));
+    let first_err: Mutex<Option<anyhow::Error>> = Mutex::new(None);
+
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(entry) = entries.get(i) else {
+                    break;
+                };
+                match hash_entry(entry) {
+                    Ok(d) => results.lock().expect("digest results lock").push(d),
+                    Err(err) => {
+                        first_err
+                            .lock()
+                            .expect("digest error lock")
+                            .get_or_insert(err);
+                        next.store(entries.len(), Ordering::Relaxed);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    if let Some(err) = first_err.into_inner().expect("digest error lock") {
+        return Err(err);
+    }
+    Ok(results.into_inner().expect("digest results lock"))
+}
+
+fn hash_entry(entry: &Entry) -> Result<MountFileDigest> {
+    match entry {
+        Entry::File { rel, path, size } => {
+            let mut f =
+                std::fs::File::open(path).with_context(|| format!("open: {}", path.display()))?;
+            let mut h = Sha256::new();
+            let mut buf = [0u8; 64 * 1024];
+            loop {
+                let n = f
+                    .read(&mut buf)
+                    .with_context(|| format!("read: {}", path.display()))?;
+                if n == 0 {
+                    break;
+                }
+                h.update(&buf[..n]);
+            }
+            Ok(MountFileDigest {
+                path: rel.clone(),
+                sha256: hex_lower(&h.finalize()),
+                size: *size,
+            })
+        }
+        Entry::Symlink { rel, target } => {
+            let mut h = Sha256::new();
+            h.update(b"symlink\0");
+            h.update(target.to_string_lossy().as_bytes());
+            Ok(MountFileDigest {
+                path: rel.clone(),
+                sha256: hex_lower(&h.finalize()),
+                size: 0,
+            })
+        }
+    }
+}
+
+fn manifest_sha256(files: &[MountFileDigest]) -> String {
+    let mut h = Sha256::new();
+    for f in files {
+        h.update(f.path.as_bytes());
+        h.update(b"\0");
+        h.update(f.sha256.as_bytes());
+        h.update(b"\0");
+        h.update(f.size.to_le_bytes());
+        h.update(b"\n");
+    }
+    hex_lower(&h.finalize())
+}
+
+fn hex_lower(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push_str(&format!("{b:02x}"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RunId, VmBackend};
+    use std::sync::atomic::AtomicU64;
+
+    static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    fn temp_dir(prefix: &str) -> PathBuf {
+        let n = TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("{prefix}_{}_{n}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn write(root: &Path, rel: &str, bytes: &[u8]) {
+        let path = root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    fn mount(host: &Path, guest: &str, readonly: bool) -> MountSpec {
+        MountSpec {
+            host_path: host.to_path_buf(),
+            guest_path: PathBuf::from(guest),
+            readonly,
+            kind: MountKind::Bind,
+        }
+    }
+
+    fn job(digests: MountDigests) -> VmJob {
+        VmJob {
+            schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
+            run_id: RunId::parse("x07-mount-digest-test").unwrap(),
+            backend: VmBackend::Docker,
+            container_id: "x07-mount-digest-test".to_string(),
+            pid: None,
+            created_unix_ms: 0,
+            deadline_unix_ms: 0,
+            grace_ms: 0,
+            cleanup_ms: 0,
+            ctr: None,
+            timeline: None,
+            mount_digests: Some(digests),
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: None,
+            warned: false,
+            warned_unix_ms: None,
+        }
+    }
+
+    #[test]
+    fn digests_readonly_bind_mounts_only() {
+        let root = temp_dir("x07_vm_mount_digest_ro");
+        let ro = root.join("ro");
+        let rw = root.join("rw");
+        write(&ro, "a.txt", b"alpha");
+        write(&ro, "sub/b.txt", b"beta");
+        write(&rw, "c.txt", b"gamma");
+        let volume = MountSpec {
+            kind: MountKind::NamedVolume {
+                name: "cache".to_string(),
+            },
+            ..mount(Path::new(""), "/cache", true)
+        };
+
+        let got = digest_mounts(
+            &[mount(&ro, "/in", true), mount(&rw, "/out", false), volume],
+            1024,
+        )
+        .unwrap();
+        assert_eq!(got.digested.len(), 1);
+        assert!(got.skipped.is_empty());
+        let d = &got.digested[0];
+        assert_eq!(d.guest_path, PathBuf::from("/in"));
+        assert_eq!((d.files, d.total_bytes), (2, 9));
+        assert_eq!(d.manifest_sha256.len(), 64);
+
+        // Same content elsewhere hashes the same; an edit does not.
+        let copy = root.join("copy");
+        write(&copy, "a.txt", b"alpha");
+        write(&copy, "sub/b.txt", b"beta");
+        let again = digest_mounts(&[mount(&copy, "/in", true)], 1024).unwrap();
+        assert_eq!(again.digested[0].manifest_sha256, d.manifest_sha256);
+        write(&copy, "sub/b.txt", b"BETA");
+        let edited = digest_mounts(&[mount(&copy, "/in", true)], 1024).unwrap();
+        assert_ne!(edited.digested[0].manifest_sha256, d.manifest_sha256);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn over_threshold_mount_is_skipped_not_failed() {
+        let root = temp_dir("x07_vm_mount_digest_big");
+        let small = root.join("small");
+        let big = root.join("big");
+        write(&small, "x", b"1234");
+        write(&big, "one", &[0u8; 600]);
+        write(&big, "two", &[0u8; 600]);
+
+        let got = digest_mounts(
+            &[mount(&small, "/small", true), mount(&big, "/big", true)],
+            1000,
+        )
+        .unwrap();
+        assert_eq!(got.digested.len(), 1);
+        assert_eq!(got.digested[0].guest_path, PathBuf::from("/small"));
+        assert_eq!(got.skipped.len(), 1);
+        assert_eq!(got.skipped[0].guest_path, PathBuf::from("/big"));
+        assert!(got.skipped[0].seen_bytes > 1000);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn verify_job_mounts_reports_drift() {
+        let root = temp_dir("x07_vm_mount_digest_verify");
+        let keep = root.join("keep");
+        let edit = root.join("edit");
+        let gone = root.join("gone");
+        for dir in [&keep, &edit, &gone] {
+            write(dir, "f.txt", b"v1");
+        }
+        let file_mount = root.join("single.bin");
+        std::fs::write(&file_mount, b"one file").unwrap();
+
+        let digests = digest_mounts(
+            &[
+                mount(&keep, "/keep", true),
+                mount(&edit, "/edit", true),
+                mount(&gone, "/gone", true),
+                mount(&file_mount, "/single.bin", true),
+            ],
+            1024,
+        )
+        .unwrap();
+        assert_eq!(digests.digested.len(), 4);
+        let job = job(digests);
+
+        let roundtrip: VmJob = serde_json::from_slice(&serde_json::to_vec(&job).unwrap()).unwrap();
+        assert!(verify_job_mounts(&roundtrip, Path::new("/"))
+            .unwrap()
+            .is_empty());
+
+        write(&edit, "new.txt", b"added");
+        std::fs::remove_dir_all(&gone).unwrap();
+        let drifts = verify_job_mounts(&roundtrip, Path::new("/")).unwrap();
+        let kinds: Vec<(PathBuf, &MountDriftKind)> = drifts
+            .iter()
+            .map(|d| (d.guest_path.clone(), &d.drift))
+            .collect();
+        assert_eq!(kinds.len(), 2, "{drifts:?}");
+        assert_eq!(kinds[0].0, PathBuf::from("/edit"));
+        assert!(matches!(kinds[0].1, MountDriftKind::Changed { .. }));
+        assert_eq!(kinds[1], (PathBuf::from("/gone"), &MountDriftKind::Missing));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_mount_root_digests_the_directory_behind_it() {
+        let root = temp_dir("x07_vm_mount_digest_symlink_root");
+        let real = root.join("real");
+        write(&real, "f.txt", b"v1");
+        let link = root.join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let digests = digest_mounts(&[mount(&link, "/in", true)], 1024).unwrap();
+        assert_eq!(digests.digested.len(), 1);
+        assert_eq!(
+            (digests.digested[0].files, digests.digested[0].total_bytes),
+            (1, 2)
+        );
+        let job = job(digests);
+        assert!(verify_job_mounts(&job, Path::new("/")).unwrap().is_empty());
+
+        write(&real, "f.txt", b"v2");
+        let drifts = verify_job_mounts(&job, Path::new("/")).unwrap();
+        assert_eq!(drifts.len(), 1, "{drifts:?}");
+        assert!(matches!(drifts[0].drift, MountDriftKind::Changed { .. }));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
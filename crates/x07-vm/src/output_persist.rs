@@ -0,0 +1,286 @@
+//! Full stdout/stderr of a job, teed to files next to the capped in-memory
+//! capture.
+//!
+//! Each stream is written to `<dir>/stdout.log` (or `stderr.log`). When the
+//! file reaches half of the persisted byte cap it is renamed to
+//! `stdout.log.1` (replacing the previous one) and a fresh `stdout.log` is
+//! started, so the two files together never exceed the cap and always end
+//! with the tail of the stream, where crash backtraces are.
+
+use std::fs::File;
+use std::io::{Read, Write as _};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::LimitsSpec;
+
+pub const ENV_VM_MAX_PERSISTED_OUTPUT_BYTES: &str = "X07_VM_MAX_PERSISTED_OUTPUT_BYTES";
+/// Per-stream cap on persisted output when the env var is unset.
+pub const DEFAULT_MAX_PERSISTED_OUTPUT_BYTES: u64 = 256 * 1024 * 1024;
+/// Directory under the job's state dir used when `persist_output` is set
+/// without `persist_output_dir`.
+pub const PERSISTED_OUTPUT_DIR_NAME: &str = "logs";
+
+/// `X07_VM_MAX_PERSISTED_OUTPUT_BYTES`, or [`DEFAULT_MAX_PERSISTED_OUTPUT_BYTES`].
+pub fn max_persisted_output_bytes_from_env() -> u64 {
+    std::env::var(ENV_VM_MAX_PERSISTED_OUTPUT_BYTES)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&v| v != 0)
+        .unwrap_or(DEFAULT_MAX_PERSISTED_OUTPUT_BYTES)
+}
+
+/// Where one stream was persisted, recorded in the job file and
+/// [`crate::RunOutput::persisted_output`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedStream {
+    /// The newest segment; ends with the tail of the stream.
+    pub path: PathBuf,
+    /// The segment before `path`, once the stream has rotated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotated_path: Option<PathBuf>,
+    pub bytes_total: u64,
+    /// Older segments were dropped (or a write failed), so the files do not
+    /// hold the whole stream.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedOutput {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+    pub stdout: PersistedStream,
+    pub stderr: PersistedStream,
+}
+
+/// Persistence settings for one run.
+#[derive(Debug, Clone)]
+pub(crate) struct OutputPersist {
+    pub(crate) dir: PathBuf,
+    pub(crate) max_bytes: u64,
+}
+
+impl OutputPersist {
+    /// `Some` when `limits.persist_output_dir` is set (`run_vm_job` fills it
+    /// in from the state dir when only `persist_output` is). Creates the dir.
+    pub(crate) fn for_limits(limits: &LimitsSpec) -> Result<Option<Self>> {
+        let Some(dir) = &limits.persist_output_dir else {
+            return Ok(None);
+        };
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("create output dir {}", dir.display()))?;
+        Ok(Some(Self {
+            dir: dir.clone(),
+            max_bytes: max_persisted_output_bytes_from_env(),
+        }))
+    }
+
+    pub(crate) fn open(&self, name: &str) -> Result<RotatingLog> {
+        RotatingLog::create(&self.dir, name, self.max_bytes)
+    }
+}
+
+/// `<dir>/<name>.log` plus one rotated predecessor, together at most
+/// `max_bytes`.
+#[derive(Debug)]
+pub(crate) struct RotatingLog {
+    path: PathBuf,
+    rotated_path: PathBuf,
+    segment_bytes: u64,
+    file: Option<File>,
+    segment_len: u64,
+    bytes_total: u64,
+    rotations: u64,
+    failed: bool,
+}
+
+impl RotatingLog {
+    pub(crate) fn create(dir: &Path, name: &str, max_bytes: u64) -> Result<Self> {
+        let path = dir.join(format!("{name}.log"));
+        let rotated_path = dir.join(format!("{name}.log.1"));
+        // A previous run's segment would otherwise pass for this one's.
+        let _ = std::fs::remove_file(&rotated_path);
+        let file = File::create(&path).with_context(|| format!("create {}", path.display()))?;
+        Ok(Self {
+            path,
+            rotated_path,
+            segment_bytes: (max_bytes / 2).max(1),
+            file: Some(file),
+            segment_len: 0,
+            bytes_total: 0,
+            rotations: 0,
+            failed: false,
+        })
+    }
+
+    /// Best effort: a failed write or rotation stops persisting and marks the
+    /// stream truncated instead of failing the job.
+    pub(crate) fn write(&mut self, mut buf: &[u8]) {
+        self.bytes_total += buf.len() as u64;
+        while !buf.is_empty() {
+            if self.segment_len == self.segment_bytes && self.rotate().is_err() {
+                self.fail();
+            }
+            let Some(file) = self.file.as_mut() else {
+                return;
+            };
+            let room = (self.segment_bytes - self.segment_len).min(buf.len() as u64) as usize;
+            if file.write_all(&buf[..room]).is_err() {
+                self.fail();
+                return;
+            }
+            self.segment_len += room as u64;
+            buf = &buf[room..];
+        }
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+        std::fs::rename(&self.path, &self.rotated_path)?;
+        self.file = Some(File::create(&self.path)?);
+        self.segment_len = 0;
+        self.rotations += 1;
+        Ok(())
+    }
+
+    fn fail(&mut self) {
+        self.file = None;
+        self.failed = true;
+    }
+
+    pub(crate) fn finish(self) -> PersistedStream {
+        PersistedStream {
+            rotated_path: (self.rotations > 0).then(|| self.rotated_path.clone()),
+            path: self.path,
+            bytes_total: self.bytes_total,
+            truncated: self.failed || self.rotations > 1,
+        }
+    }
+}
+
+/// [`x07_host_runner::read_to_end_capped`] that also tees every byte read
+/// into `log`.
+pub(crate) fn read_to_end_capped_teed<R: Read>(
+    mut reader: R,
+    cap: usize,
+    mut log: Option<RotatingLog>,
+) -> std::io::Result<(Vec<u8>, bool, Option<PersistedStream>)> {
+    let mut buf = Vec::new();
+    let mut tmp = [0u8; 8192];
+    let mut truncated = false;
+
+    loop {
+        let n = reader.read(&mut tmp)?;
+        if n == 0 {
+            break;
+        }
+        if let Some(log) = log.as_mut() {
+            log.write(&tmp[..n]);
+        }
+        if truncated {
+            continue;
+        }
+        let remaining = cap.saturating_sub(buf.len());
+        if n <= remaining {
+            buf.extend_from_slice(&tmp[..n]);
+        } else {
+            buf.extend_from_slice(&tmp[..remaining]);
+            truncated = true;
+        }
+    }
+
+    Ok((buf, truncated, log.map(RotatingLog::finish)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "x07_vm_output_persist_{name}_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rotating_log_keeps_the_tail_within_cap() {
+        let dir = temp_dir("rotate");
+        let mut log = RotatingLog::create(&dir, "stdout", 8).unwrap();
+        log.write(b"0123456789");
+        log.write(b"abcdef");
+        let s = log.finish();
+
+        assert_eq!(s.bytes_total, 16);
+        assert!(s.truncated);
+        assert_eq!(std::fs::read(&s.path).unwrap(), b"cdef");
+        assert_eq!(std::fs::read(s.rotated_path.unwrap()).unwrap(), b"89ab");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotating_log_under_cap_is_complete() {
+        let dir = temp_dir("complete");
+        std::fs::write(dir.join("stderr.log.1"), b"stale").unwrap();
+        let mut log = RotatingLog::create(&dir, "stderr", 8).unwrap();
+        log.write(b"abcd");
+        let s = log.finish();
+        assert_eq!(s.bytes_total, 4);
+        assert!(!s.truncated);
+        assert_eq!(s.rotated_path, None);
+        assert_eq!(std::fs::read(&s.path).unwrap(), b"abcd");
+        assert!(!dir.join("stderr.log.1").exists());
+
+        // One rotation keeps everything: the old segment plus the new one.
+        let mut log = RotatingLog::create(&dir, "stderr", 8).unwrap();
+        log.write(b"abcdef");
+        let s = log.finish();
+        assert!(!s.truncated);
+        assert_eq!(std::fs::read(s.rotated_path.unwrap()).unwrap(), b"abcd");
+        assert_eq!(std::fs::read(&s.path).unwrap(), b"ef");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn persisted_stdout_keeps_tail_past_in_memory_cap() {
+        let dir = temp_dir("run");
+        let persist = OutputPersist {
+            dir: dir.clone(),
+            max_bytes: 1024 * 1024,
+        };
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args([
+            "-c",
+            "i=0; while [ $i -lt 200 ]; do printf 'line %03d\\n' $i; i=$((i+1)); done; \
+             printf backtrace; printf oops >&2",
+        ]);
+        let out = crate::run_command_capped_timed(
+            cmd,
+            5_000,
+            64,
+            1024,
+            Some(&persist),
+            &crate::JobTimelineRecorder::start(),
+        )
+        .unwrap();
+
+        assert!(out.stdout_truncated);
+        assert_eq!(out.stdout.len(), 64);
+        assert!(out.stdout.starts_with(b"line 000\n"));
+        let p = out.persisted_output.expect("persisted output");
+        assert_eq!(p.dir, dir);
+        assert!(!p.stdout.truncated);
+        assert_eq!(p.stdout.bytes_total, 200 * 9 + 9);
+        let full = std::fs::read(&p.stdout.path).unwrap();
+        assert!(full.starts_with(b"line 000\n"));
+        assert!(full.ends_with(b"line 199\nbacktrace"));
+        assert_eq!(std::fs::read(&p.stderr.path).unwrap(), b"oops");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
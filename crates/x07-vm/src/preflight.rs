@@ -0,0 +1,242 @@
+//! Preflight outcomes recorded while [`crate::resolve_vm_backend_with_record`]
+//! picks a backend.
+//!
+//! A preflight that times out (e.g. `container system info` against a cold
+//! Apple container daemon) is kept apart from one that failed outright, so
+//! the preferred backend can get one more, longer attempt before falling back.
+
+use anyhow::Result;
+
+use crate::{RunOutput, VmBackend};
+
+pub const ENV_VM_PREFLIGHT_TIMEOUT_MS: &str = "X07_VM_PREFLIGHT_TIMEOUT_MS";
+/// Wall time for each backend's preflight command.
+pub const DEFAULT_VM_PREFLIGHT_TIMEOUT_MS: u64 = 5_000;
+/// When set (> 0), a preferred backend whose preflight timed out is tried once
+/// more with this timeout before falling back.
+pub const ENV_VM_PREFLIGHT_RETRY_TIMEOUT_MS: &str = "X07_VM_PREFLIGHT_RETRY_TIMEOUT_MS";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightOutcome {
+    Ok,
+    /// No answer within `timeout_ms`; the backend may just be slow to start.
+    TimedOut {
+        timeout_ms: u64,
+    },
+    /// The backend is missing, broken or unsupported.
+    Failed {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightAttempt {
+    pub backend: VmBackend,
+    pub timeout_ms: u64,
+    pub outcome: PreflightOutcome,
+}
+
+/// The chosen backend and every preflight run to get there, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendResolution {
+    pub backend: VmBackend,
+    pub attempts: Vec<PreflightAttempt>,
+    /// Non-fatal preflight findings, e.g. an untested backend CLI version.
+    pub warnings: Vec<String>,
+}
+
+/// `X07_VM_PREFLIGHT_TIMEOUT_MS`, or [`DEFAULT_VM_PREFLIGHT_TIMEOUT_MS`].
+pub fn preflight_timeout_ms_from_env() -> u64 {
+    std::env::var(ENV_VM_PREFLIGHT_TIMEOUT_MS)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(DEFAULT_VM_PREFLIGHT_TIMEOUT_MS)
+}
+
+/// `X07_VM_PREFLIGHT_RETRY_TIMEOUT_MS`; `None` when unset or 0 (no retry).
+pub fn preflight_retry_timeout_ms_from_env() -> Option<u64> {
+    std::env::var(ENV_VM_PREFLIGHT_RETRY_TIMEOUT_MS)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&v| v > 0)
+}
+
+pub(crate) fn classify_preflight_output(
+    backend: VmBackend,
+    out: Result<RunOutput>,
+    timeout_ms: u64,
+) -> PreflightOutcome {
+    match out {
+        Err(err) => PreflightOutcome::Failed {
+            message: format!("preflight {backend}: {err:#}"),
+        },
+        Ok(out) if out.timed_out => PreflightOutcome::TimedOut { timeout_ms },
+        Ok(out) if out.exit_status != 0 => PreflightOutcome::Failed {
+            message: format!(
+                "preflight {backend} failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            ),
+        },
+        Ok(_) => PreflightOutcome::Ok,
+    }
+}
+
+/// Preflights `candidates` in order and returns the first that passes. Only
+/// the first candidate (the preferred one) is retried, once, and only when it
+/// timed out and `retry_timeout_ms` is set.
+pub(crate) fn select_backend<F>(
+    candidates: &[VmBackend],
+    timeout_ms: u64,
+    retry_timeout_ms: Option<u64>,
+    mut preflight: F,
+) -> (Option<VmBackend>, Vec<PreflightAttempt>)
+where
+    F: FnMut(VmBackend, u64) -> PreflightOutcome,
+{
+    let mut attempts = Vec::new();
+    for (i, &backend) in candidates.iter().enumerate() {
+        let mut outcome = preflight(backend, timeout_ms);
+        attempts.push(PreflightAttempt {
+            backend,
+            timeout_ms,
+            outcome: outcome.clone(),
+        });
+        if let (0, PreflightOutcome::TimedOut { .. }, Some(retry_ms)) =
+            (i, &outcome, retry_timeout_ms)
+        {
+            outcome = preflight(backend, retry_ms);
+            attempts.push(PreflightAttempt {
+                backend,
+                timeout_ms: retry_ms,
+                outcome: outcome.clone(),
+            });
+        }
+        if outcome == PreflightOutcome::Ok {
+            return (Some(backend), attempts);
+        }
+    }
+    (None, attempts)
+}
+
+/// One line per attempt, for "no backend found" errors.
+pub(crate) fn describe_attempts(attempts: &[PreflightAttempt]) -> String {
+    attempts
+        .iter()
+        .map(|a| match &a.outcome {
+            PreflightOutcome::Ok => format!("  - {}: ok", a.backend),
+            PreflightOutcome::TimedOut { timeout_ms } => format!(
+                "  - {}: timed out after {timeout_ms}ms (raise {ENV_VM_PREFLIGHT_TIMEOUT_MS})",
+                a.backend
+            ),
+            PreflightOutcome::Failed { message } => format!("  - {}: {message}", a.backend),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_command_capped;
+    use std::process::Command;
+
+    #[cfg(unix)]
+    #[test]
+    fn classifies_timeout_apart_from_failure() {
+        let mut slow = Command::new("sh");
+        slow.args(["-c", "exec sleep 5"]);
+        assert_eq!(
+            classify_preflight_output(
+                VmBackend::AppleContainer,
+                run_command_capped(slow, 100, 1024, 1024),
+                100
+            ),
+            PreflightOutcome::TimedOut { timeout_ms: 100 }
+        );
+
+        let mut broken = Command::new("sh");
+        broken.args(["-c", "echo 'daemon not running' >&2; exit 3"]);
+        let outcome = classify_preflight_output(
+            VmBackend::AppleContainer,
+            run_command_capped(broken, 5_000, 1024, 1024),
+            5_000,
+        );
+        assert_eq!(
+            outcome,
+            PreflightOutcome::Failed {
+                message: "preflight apple-container failed: daemon not running".to_string()
+            }
+        );
+
+        let missing = Command::new("/nonexistent/x07-preflight-bin");
+        let outcome = classify_preflight_output(
+            VmBackend::Docker,
+            run_command_capped(missing, 5_000, 1024, 1024),
+            5_000,
+        );
+        assert!(
+            matches!(outcome, PreflightOutcome::Failed { .. }),
+            "{outcome:?}"
+        );
+
+        let ok = Command::new("true");
+        assert_eq!(
+            classify_preflight_output(
+                VmBackend::Docker,
+                run_command_capped(ok, 5_000, 1024, 1024),
+                5_000
+            ),
+            PreflightOutcome::Ok
+        );
+    }
+
+    #[test]
+    fn retries_only_a_timed_out_preferred_backend() {
+        let candidates = [VmBackend::AppleContainer, VmBackend::Vz];
+
+        // Cold daemon: answers within the longer window.
+        let (chosen, attempts) =
+            select_backend(&candidates, 5_000, Some(20_000), |b, ms| match (b, ms) {
+                (VmBackend::AppleContainer, 20_000) => PreflightOutcome::Ok,
+                (VmBackend::AppleContainer, _) => PreflightOutcome::TimedOut { timeout_ms: ms },
+                _ => panic!("fell back to {b}"),
+            });
+        assert_eq!(chosen, Some(VmBackend::AppleContainer));
+        assert_eq!(
+            attempts.iter().map(|a| a.timeout_ms).collect::<Vec<_>>(),
+            vec![5_000, 20_000]
+        );
+
+        // Without a retry window the timeout falls back right away.
+        let (chosen, attempts) = select_backend(&candidates, 5_000, None, |b, ms| match b {
+            VmBackend::AppleContainer => PreflightOutcome::TimedOut { timeout_ms: ms },
+            _ => PreflightOutcome::Ok,
+        });
+        assert_eq!(chosen, Some(VmBackend::Vz));
+        assert_eq!(attempts.len(), 2);
+
+        // A hard failure is never retried, and neither is a fallback timeout.
+        let (chosen, attempts) =
+            select_backend(&candidates, 5_000, Some(20_000), |b, ms| match b {
+                VmBackend::AppleContainer => PreflightOutcome::Failed {
+                    message: "not installed".to_string(),
+                },
+                _ => PreflightOutcome::TimedOut { timeout_ms: ms },
+            });
+        assert_eq!(chosen, None);
+        assert_eq!(
+            attempts
+                .iter()
+                .map(|a| (a.backend, a.timeout_ms))
+                .collect::<Vec<_>>(),
+            vec![(VmBackend::AppleContainer, 5_000), (VmBackend::Vz, 5_000)]
+        );
+        let text = describe_attempts(&attempts);
+        assert!(text.contains("apple-container: not installed"), "{text}");
+        assert!(
+            text.contains("vz: timed out after 5000ms (raise X07_VM_PREFLIGHT_TIMEOUT_MS)"),
+            "{text}"
+        );
+    }
+}
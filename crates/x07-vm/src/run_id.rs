@@ -0,0 +1,288 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Longest accepted run id; `x07-<run_id>` must stay within the 128-byte container id limit.
+pub const RUN_ID_MAX_BYTES: usize = 100;
+
+const RUN_ID_SUFFIX_LEN: usize = 6;
+const BASE32_LOWER: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+static RUN_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Validated VM job run id: 1..=100 bytes of `[A-Za-z0-9_.-]`, starting with
+/// `[A-Za-z0-9]`. Every `RunId` yields a valid container id and a safe
+/// single-component state dir name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RunId(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunIdError {
+    Empty,
+    TooLong { len: usize, max: usize },
+    InvalidStart(char),
+    InvalidChar(char),
+}
+
+impl std::fmt::Display for RunIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunIdError::Empty => f.write_str("run_id is empty"),
+            RunIdError::TooLong { len, max } => {
+                write!(f, "run_id is {len} bytes (max {max})")
+            }
+            RunIdError::InvalidStart(c) => {
+                write!(f, "run_id must start with [A-Za-z0-9], got {c:?}")
+            }
+            RunIdError::InvalidChar(c) => {
+                write!(f, "run_id contains invalid character {c:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RunIdError {}
+
+impl RunId {
+    pub fn parse(s: &str) -> Result<Self, RunIdError> {
+        validate_run_id(s)?;
+        Ok(Self(s.to_string()))
+    }
+
+    /// `<prefix>-<unix_ms>-<6 random base32 chars>`. Fails only if `prefix`
+    /// itself is not a valid run id prefix or is too long.
+    pub fn generate(prefix: &str) -> Result<Self, RunIdError> {
+        let unix_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let suffix = random_base32(RUN_ID_SUFFIX_LEN);
+        Self::parse(&format!("{prefix}-{unix_ms}-{suffix}"))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl std::fmt::Display for RunId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for RunId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<std::path::Path> for RunId {
+    fn as_ref(&self) -> &std::path::Path {
+        std::path::Path::new(&self.0)
+    }
+}
+
+impl std::str::FromStr for RunId {
+    type Err = RunIdError;
+
+    fn from_str(s: &str) -> Result<Self, RunIdError> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for RunId {
+    type Error = RunIdError;
+
+    fn try_from(s: String) -> Result<Self, RunIdError> {
+        validate_run_id(&s)?;
+        Ok(Self(s))
+    }
+}
+
+impl TryFrom<&str> for RunId {
+    type Error = RunIdError;
+
+    fn try_from(s: &str) -> Result<Self, RunIdError> {
+        Self::parse(s)
+    }
+}
+
+impl From<RunId> for String {
+    fn from(id: RunId) -> Self {
+        id.0
+    }
+}
+
+fn validate_run_id(s: &str) -> Result<(), RunIdError> {
+    let Some(first) = s.chars().next() else {
+        return Err(RunIdError::Empty);
+    };
+    if s.len() > RUN_ID_MAX_BYTES {
+        return Err(RunIdError::TooLong {
+            len: s.len(),
+            max: RUN_ID_MAX_BYTES,
+        });
+    }
+    if !first.is_ascii_alphanumeric() {
+        return Err(RunIdError::InvalidStart(first));
+    }
+    if let Some(c) = s
+        .chars()
+        .find(|c| !matches!(c, 'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '.' | '-'))
+    {
+        return Err(RunIdError::InvalidChar(c));
+    }
+    Ok(())
+}
+
+fn random_base32(len: usize) -> String {
+    let mut bytes = [0u8; 8];
+    let from_urandom = std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| {
+            use std::io::Read as _;
+            f.read_exact(&mut bytes)
+        })
+        .is_ok();
+    let mut bits = u64::from_le_bytes(bytes);
+    if !from_urandom {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let n = RUN_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+        bits = fallback_bits(nanos, std::process::id(), n);
+    }
+    encode_base32(bits, len)
+}
+
+/// splitmix64 over time, pid and a process-local counter.
+fn fallback_bits(nanos: u64, pid: u32, n: u64) -> u64 {
+    let mut z = nanos ^ (u64::from(pid) << 32) ^ n.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn encode_base32(mut bits: u64, len: usize) -> String {
+    let mut out = String::with_capacity(len);
+    for _ in 0..len {
+        out.push(BASE32_LOWER[(bits & 0x1f) as usize] as char);
+        bits >>= 5;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn generate_produces_valid_container_ids() {
+        let id = RunId::generate("test").unwrap();
+        let parts: Vec<&str> = id.as_str().split('-').collect();
+        assert_eq!(parts.len(), 3, "{id}");
+        assert_eq!(parts[0], "test");
+        assert!(parts[1].bytes().all(|b| b.is_ascii_digit()));
+        assert_eq!(parts[2].len(), RUN_ID_SUFFIX_LEN);
+        assert!(parts[2].bytes().all(|b| BASE32_LOWER.contains(&b)));
+        crate::container_id_from_run_id(id.as_str()).unwrap();
+    }
+
+    #[test]
+    fn fallback_suffixes_do_not_collide_for_same_instant() {
+        // Same clock reading and pid: only the counter tells ids apart.
+        let suffixes: BTreeSet<String> = (0..500)
+            .map(|n| {
+                encode_base32(
+                    fallback_bits(1_700_000_000_000_000_000, 4242, n),
+                    RUN_ID_SUFFIX_LEN,
+                )
+            })
+            .collect();
+        assert_eq!(suffixes.len(), 500);
+        assert!(suffixes
+            .iter()
+            .all(|s| s.len() == RUN_ID_SUFFIX_LEN && s.bytes().all(|b| BASE32_LOWER.contains(&b))));
+    }
+
+    #[test]
+    fn generate_rejects_bad_prefix() {
+        assert_eq!(
+            RunId::generate("bad prefix").unwrap_err(),
+            RunIdError::InvalidChar(' ')
+        );
+        assert!(matches!(
+            RunId::generate(&"p".repeat(RUN_ID_MAX_BYTES)).unwrap_err(),
+            RunIdError::TooLong { .. }
+        ));
+    }
+
+    #[test]
+    fn parse_validates_charset_and_length() {
+        RunId::parse("1700000000000-123-0-build").unwrap();
+        RunId::parse("abc.DEF_123").unwrap();
+        RunId::parse(&"a".repeat(RUN_ID_MAX_BYTES)).unwrap();
+
+        assert_eq!(RunId::parse("").unwrap_err(), RunIdError::Empty);
+        assert_eq!(
+            RunId::parse(&"a".repeat(RUN_ID_MAX_BYTES + 1)).unwrap_err(),
+            RunIdError::TooLong {
+                len: RUN_ID_MAX_BYTES + 1,
+                max: RUN_ID_MAX_BYTES
+            }
+        );
+        assert_eq!(
+            RunId::parse("..").unwrap_err(),
+            RunIdError::InvalidStart('.')
+        );
+        assert_eq!(
+            RunId::parse("-x").unwrap_err(),
+            RunIdError::InvalidStart('-')
+        );
+        assert_eq!(
+            RunId::parse("a/b").unwrap_err(),
+            RunIdError::InvalidChar('/')
+        );
+        assert_eq!(
+            RunId::parse("a\u{e9}").unwrap_err(),
+            RunIdError::InvalidChar('\u{e9}')
+        );
+    }
+
+    #[test]
+    fn serde_round_trips_as_plain_string() {
+        let id = RunId::parse("job1").unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), "\"job1\"");
+        let back: RunId = serde_json::from_str("\"job1\"").unwrap();
+        assert_eq!(back, id);
+        assert!(serde_json::from_str::<RunId>("\"../etc\"").is_err());
+    }
+
+    #[test]
+    fn existing_job_files_still_load() {
+        let raw = r#"{
+          "schema_version": "x07.vm.job@0.1.0",
+          "run_id": "1700000000000-4242-0-run",
+          "backend": "docker",
+          "container_id": "x07-1700000000000-4242-0-run",
+          "pid": null,
+          "created_unix_ms": 1700000000000,
+          "deadline_unix_ms": 1700000001000,
+          "grace_ms": 100,
+          "cleanup_ms": 100,
+          "ctr": null
+        }"#;
+        let job: crate::VmJob = serde_json::from_str(raw).unwrap();
+        assert_eq!(job.run_id.as_str(), "1700000000000-4242-0-run");
+
+        let out = serde_json::to_value(&job).unwrap();
+        assert_eq!(out["run_id"], "1700000000000-4242-0-run");
+    }
+}
@@ -1,15 +1,21 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
 
+use crate::container_list::{list_apple_owned, list_ctr_owned};
 use crate::{
-    apple_container_cleanup, apple_container_hard_kill, firecracker_ctr_cleanup,
-    firecracker_ctr_config_from_env, firecracker_ctr_config_from_job, firecracker_ctr_hard_kill,
-    hard_kill_pid_and_group, parse_apple_container_json_owned, parse_ctr_container_info_json_owned,
-    vz_cleanup_scratch, FirecrackerCtrConfig, VmBackend, VmJob, X07_LABEL_DEADLINE_UNIX_MS_KEY,
+    apple_container_cleanup, apple_container_hard_kill, enforce_kill_plan_for_job,
+    firecracker_ctr_cleanup, firecracker_ctr_config_from_env, firecracker_ctr_config_from_job,
+    firecracker_ctr_hard_kill, hard_kill_pid_and_group, read_job_file, resolve_job_file_label,
+    state_root_id, vz_cleanup_scratch, FirecrackerCtrConfig, OwnedContainer, VmBackend, VmJob,
+    X07_LABEL_DEADLINE_UNIX_MS_KEY,
 };
 
+/// Floor for the cleanup budget of a job-file kill plan run by sweep, so a job
+/// with `cleanup_ms: 0` still gets its hard kill and cleanup commands.
+const SWEEP_MIN_CLEANUP_MS: u64 = 2_000;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct SweepReport {
     pub state_reaped: usize,
@@ -25,12 +31,14 @@ pub fn sweep_orphans_best_effort(
 
     let state_reaped = sweep_state_dirs_best_effort(state_root, now).unwrap_or(0);
     let runtime_reaped = match backend {
-        VmBackend::AppleContainer => sweep_apple_container_runtime_best_effort(now).unwrap_or(0),
+        VmBackend::AppleContainer => {
+            sweep_apple_container_runtime_best_effort(state_root, now).unwrap_or(0)
+        }
         VmBackend::FirecrackerCtr => {
             let cfg = firecracker_cfg
                 .cloned()
                 .unwrap_or_else(firecracker_ctr_config_from_env);
-            sweep_firecracker_runtime_best_effort(now, &cfg).unwrap_or(0)
+            sweep_firecracker_runtime_best_effort(state_root, now, &cfg).unwrap_or(0)
         }
         VmBackend::Vz | VmBackend::Docker | VmBackend::Podman => 0,
     };
@@ -134,24 +142,74 @@ fn reap_job_best_effort(job: &VmJob, state_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn sweep_apple_container_runtime_best_effort(now_unix_ms: u64) -> Result<usize> {
-    if !cfg!(target_os = "macos") {
-        return Ok(0);
+/// How sweep reaps an expired runtime container.
+#[derive(Debug)]
+enum SweepAction {
+    /// Run [`enforce_kill_plan_for_job`] with the job loaded from the
+    /// container's job-file label.
+    JobKillPlan { job: Box<VmJob>, state_dir: PathBuf },
+    /// No usable job file: generic hard kill and cleanup by container id.
+    Generic,
+}
+
+fn sweep_action(
+    state_root: &Path,
+    root_id: &str,
+    c: &OwnedContainer,
+    now_unix_ms: u64,
+) -> SweepAction {
+    if c.state_root_id.as_deref() != Some(root_id) {
+        return SweepAction::Generic;
     }
+    let Some(job_file) = c
+        .job_file
+        .as_deref()
+        .and_then(|rel| resolve_job_file_label(state_root, rel))
+    else {
+        return SweepAction::Generic;
+    };
+    let Some(state_dir) = job_file.parent().map(Path::to_path_buf) else {
+        return SweepAction::Generic;
+    };
+    if state_dir.join("done").is_file() {
+        return SweepAction::Generic;
+    }
+    let Ok(mut job) = read_job_file(&job_file) else {
+        return SweepAction::Generic;
+    };
+    if job.schema_version != crate::VM_JOB_SCHEMA_VERSION || job.container_id != c.id {
+        return SweepAction::Generic;
+    }
+    // The plan is scheduled from the job's deadline; rebase it to now so an
+    // expired plan still runs its kill and cleanup steps.
+    job.deadline_unix_ms = now_unix_ms;
+    job.created_unix_ms = job.created_unix_ms.min(now_unix_ms);
+    job.cleanup_ms = job.cleanup_ms.max(SWEEP_MIN_CLEANUP_MS);
+    SweepAction::JobKillPlan {
+        job: Box::new(job),
+        state_dir,
+    }
+}
 
-    let mut cmd = std::process::Command::new("container");
-    cmd.args(["list", "--all", "--format", "json"]);
-    let out = crate::run_command_capped(cmd, 2_000, 256 * 1024, 256 * 1024)?;
-    if out.timed_out || out.exit_status != 0 {
-        return Ok(0);
+/// Returns false when `action` is [`SweepAction::Generic`] so the caller runs
+/// its backend's generic kill.
+fn run_job_kill_plan(action: SweepAction) -> bool {
+    match action {
+        SweepAction::JobKillPlan { job, state_dir } => {
+            let _ = enforce_kill_plan_for_job(&job, &state_dir, &state_dir.join("done"));
+            true
+        }
+        SweepAction::Generic => false,
     }
+}
 
-    let s = String::from_utf8_lossy(&out.stdout);
-    let owned = match parse_apple_container_json_owned(&s) {
+fn sweep_apple_container_runtime_best_effort(state_root: &Path, now_unix_ms: u64) -> Result<usize> {
+    let owned = match list_apple_owned() {
         Ok(v) => v,
         Err(_) => return Ok(0),
     };
 
+    let root_id = state_root_id(state_root);
     let mut reaped: usize = 0;
     for c in owned {
         let Some(deadline_ms) = parse_deadline_label(&c.labels) else {
@@ -161,8 +219,10 @@ fn sweep_apple_container_runtime_best_effort(now_unix_ms: u64) -> Result<usize>
             continue;
         }
 
-        let _ = apple_container_hard_kill(&c.id);
-        let _ = apple_container_cleanup(&c.id);
+        if !run_job_kill_plan(sweep_action(state_root, &root_id, &c, now_unix_ms)) {
+            let _ = apple_container_hard_kill(&c.id);
+            let _ = apple_container_cleanup(&c.id);
+        }
         reaped += 1;
     }
 
@@ -170,50 +230,14 @@ fn sweep_apple_container_runtime_best_effort(now_unix_ms: u64) -> Result<usize>
 }
 
 fn sweep_firecracker_runtime_best_effort(
+    state_root: &Path,
     now_unix_ms: u64,
     cfg: &FirecrackerCtrConfig,
 ) -> Result<usize> {
-    if !cfg!(target_os = "linux") {
-        return Ok(0);
-    }
-
-    let mut cmd = std::process::Command::new(&cfg.bin);
-    cmd.args(crate::ctr_base_args(cfg));
-    cmd.arg("--timeout").arg("2s");
-    cmd.args(["containers", "list", "-q"]);
-    let out = crate::run_command_capped(cmd, 2_000, 256 * 1024, 256 * 1024)?;
-    if out.timed_out || out.exit_status != 0 {
-        return Ok(0);
-    }
-
-    let ids = String::from_utf8_lossy(&out.stdout)
-        .lines()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .take(512)
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
-
+    let root_id = state_root_id(state_root);
     let mut reaped: usize = 0;
-    for id in ids {
-        let mut info_cmd = std::process::Command::new(&cfg.bin);
-        info_cmd.args(crate::ctr_base_args(cfg));
-        info_cmd.arg("--timeout").arg("2s");
-        info_cmd.args(["containers", "info"]);
-        info_cmd.arg(&id);
-        let info = crate::run_command_capped(info_cmd, 2_000, 256 * 1024, 256 * 1024)?;
-        if info.timed_out || info.exit_status != 0 {
-            continue;
-        }
-
-        let s = String::from_utf8_lossy(&info.stdout);
-        let owned = match parse_ctr_container_info_json_owned(&s) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-        let Some(owned) = owned else {
-            continue;
-        };
+    for owned in list_ctr_owned(cfg)? {
+        let id = owned.id.clone();
         let Some(deadline_ms) = parse_deadline_label(&owned.labels) else {
             continue;
         };
@@ -221,8 +245,10 @@ fn sweep_firecracker_runtime_best_effort(
             continue;
         }
 
-        let _ = firecracker_ctr_hard_kill(cfg, &id);
-        let _ = firecracker_ctr_cleanup(cfg, &id);
+        if !run_job_kill_plan(sweep_action(state_root, &root_id, &owned, now_unix_ms)) {
+            let _ = firecracker_ctr_hard_kill(cfg, &id);
+            let _ = firecracker_ctr_cleanup(cfg, &id);
+        }
         reaped += 1;
     }
 
@@ -238,6 +264,7 @@ fn parse_deadline_label(labels: &crate::Labels) -> Option<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parse_apple_container_json_owned;
     use std::io::ErrorKind;
     use std::path::PathBuf;
     use std::sync::atomic::{AtomicU64, Ordering};
@@ -281,6 +308,124 @@ mod tests {
         }
     }
 
+    fn test_job(run_id: &str, backend: VmBackend, now: u64) -> VmJob {
+        VmJob {
+            schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
+            run_id: crate::RunId::parse(run_id).unwrap(),
+            backend,
+            container_id: format!("x07-{run_id}"),
+            pid: None,
+            created_unix_ms: now.saturating_sub(10_000),
+            deadline_unix_ms: now.saturating_sub(1),
+            grace_ms: 1,
+            cleanup_ms: 0,
+            ctr: None,
+            timeline: None,
+            mount_digests: None,
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: None,
+            warned: false,
+            warned_unix_ms: None,
+        }
+    }
+
+    fn apple_inspect_fixture(id: &str, extra_labels: &str) -> OwnedContainer {
+        let input = format!(
+            r#"[{{ "configuration": {{ "id": "{id}", "labels": {{
+                "io.x07.schema": "1", "io.x07.deadline_unix_ms": "1"{extra_labels}
+            }} }} }}]"#
+        );
+        parse_apple_container_json_owned(&input)
+            .unwrap()
+            .pop()
+            .unwrap()
+    }
+
+    #[test]
+    fn sweep_dispatch_uses_the_labelled_job_file() {
+        let tmp = TempDir::new("x07_vm_sweep_dispatch");
+        let state_root = &tmp.path;
+        let root_id = state_root_id(state_root);
+        let now = now_unix_ms().unwrap();
+
+        let job_dir = state_root.join("r1");
+        crate::write_job_file(
+            &job_dir.join("job.json"),
+            &test_job("r1", VmBackend::AppleContainer, now),
+        )
+        .unwrap();
+
+        let labelled = apple_inspect_fixture(
+            "x07-r1",
+            &format!(r#", "io.x07.state_root_id": "{root_id}", "io.x07.job_file": "r1/job.json""#),
+        );
+        match sweep_action(state_root, &root_id, &labelled, now) {
+            SweepAction::JobKillPlan { job, state_dir } => {
+                assert_eq!(job.container_id, "x07-r1");
+                assert_eq!(state_dir, job_dir);
+                assert_eq!(job.deadline_unix_ms, now);
+                assert_eq!(job.cleanup_ms, SWEEP_MIN_CLEANUP_MS);
+            }
+            SweepAction::Generic => panic!("expected the job kill plan"),
+        }
+
+        // A different state root, or a job file naming another container,
+        // falls back to the generic kill.
+        assert!(matches!(
+            sweep_action(state_root, "sr-other", &labelled, now),
+            SweepAction::Generic
+        ));
+        let mismatched = apple_inspect_fixture(
+            "x07-r9",
+            &format!(r#", "io.x07.state_root_id": "{root_id}", "io.x07.job_file": "r1/job.json""#),
+        );
+        assert!(matches!(
+            sweep_action(state_root, &root_id, &mismatched, now),
+            SweepAction::Generic
+        ));
+    }
+
+    #[test]
+    fn sweep_dispatch_falls_back_without_a_job_file() {
+        let tmp = TempDir::new("x07_vm_sweep_dispatch");
+        let state_root = &tmp.path;
+        let root_id = state_root_id(state_root);
+        let now = now_unix_ms().unwrap();
+
+        let unlabelled = apple_inspect_fixture("x07-r1", "");
+        assert!(matches!(
+            sweep_action(state_root, &root_id, &unlabelled, now),
+            SweepAction::Generic
+        ));
+
+        let missing = apple_inspect_fixture(
+            "x07-r2",
+            &format!(r#", "io.x07.state_root_id": "{root_id}", "io.x07.job_file": "r2/job.json""#),
+        );
+        assert!(matches!(
+            sweep_action(state_root, &root_id, &missing, now),
+            SweepAction::Generic
+        ));
+
+        // A job that already finished is not re-planned.
+        let job_dir = state_root.join("r2");
+        crate::write_job_file(
+            &job_dir.join("job.json"),
+            &test_job("r2", VmBackend::AppleContainer, now),
+        )
+        .unwrap();
+        std::fs::write(job_dir.join("done"), b"done\n").unwrap();
+        assert!(matches!(
+            sweep_action(state_root, &root_id, &missing, now),
+            SweepAction::Generic
+        ));
+    }
+
     #[test]
     fn sweep_reaps_expired_job_state_dir() {
         let tmp = TempDir::new("x07_vm_sweep");
@@ -292,7 +437,7 @@ mod tests {
 
         let job = VmJob {
             schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
-            run_id: "job1".to_string(),
+            run_id: crate::RunId::parse("job1").unwrap(),
             backend: VmBackend::Vz,
             container_id: "x07-job1".to_string(),
             pid: None,
@@ -301,6 +446,17 @@ mod tests {
             grace_ms: 1,
             cleanup_ms: 1,
             ctr: None,
+            timeline: None,
+            mount_digests: None,
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: None,
+            warned: false,
+            warned_unix_ms: None,
         };
 
         let mut bytes = serde_json::to_vec_pretty(&job).unwrap();
@@ -323,7 +479,7 @@ mod tests {
 
         let job = VmJob {
             schema_version: crate::VM_JOB_SCHEMA_VERSION.to_string(),
-            run_id: "job2".to_string(),
+            run_id: crate::RunId::parse("job2").unwrap(),
             backend: VmBackend::Vz,
             container_id: "x07-job2".to_string(),
             pid: None,
@@ -332,6 +488,17 @@ mod tests {
             grace_ms: 1,
             cleanup_ms: 1,
             ctr: None,
+            timeline: None,
+            mount_digests: None,
+            cancelled: false,
+            cancellation: None,
+            vz_workload_limits: None,
+            persisted_output: None,
+            guest_clock_skew_ms: None,
+            spec_file: None,
+            warn_before_deadline_ms: None,
+            warned: false,
+            warned_unix_ms: None,
         };
 
         let mut bytes = serde_json::to_vec_pretty(&job).unwrap();
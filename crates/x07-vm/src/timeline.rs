@@ -0,0 +1,167 @@
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobEventKind {
+    /// Only recorded when firecracker-ctr has to pull the image first.
+    PullStarted,
+    PullFinished,
+    CreateStarted,
+    TaskStarted,
+    FirstStdoutByte,
+    Exited,
+    CleanupDone,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobEvent {
+    pub kind: JobEventKind,
+    /// Milliseconds since [`JobTimeline::start_unix_ms`], from a monotonic clock.
+    pub at_ms: u64,
+}
+
+/// Where a VM job's wall time went. Events are in recording order, which is
+/// also non-decreasing `at_ms` order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobTimeline {
+    pub start_unix_ms: u64,
+    pub events: Vec<JobEvent>,
+}
+
+impl JobTimeline {
+    pub fn first(&self, kind: JobEventKind) -> Option<u64> {
+        self.events.iter().find(|e| e.kind == kind).map(|e| e.at_ms)
+    }
+}
+
+/// Cheap, clonable, thread-safe event sink. Recording is best-effort and
+/// never fails.
+#[derive(Debug, Clone)]
+pub struct JobTimelineRecorder {
+    inner: Arc<RecorderInner>,
+}
+
+#[derive(Debug)]
+struct RecorderInner {
+    origin: Instant,
+    start_unix_ms: u64,
+    events: Mutex<Vec<(JobEventKind, Duration)>>,
+}
+
+impl JobTimelineRecorder {
+    pub fn start() -> Self {
+        let start_unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis().try_into().unwrap_or(u64::MAX))
+            .unwrap_or(0);
+        Self {
+            inner: Arc::new(RecorderInner {
+                origin: Instant::now(),
+                start_unix_ms,
+                events: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub fn record(&self, kind: JobEventKind) {
+        if let Ok(mut events) = self.inner.events.lock() {
+            // Timestamp under the lock so vector order matches time order.
+            events.push((kind, self.inner.origin.elapsed()));
+        }
+    }
+
+    /// Records `kind` unless it is already present.
+    pub fn record_once(&self, kind: JobEventKind) {
+        if let Ok(mut events) = self.inner.events.lock() {
+            if !events.iter().any(|(k, _)| *k == kind) {
+                events.push((kind, self.inner.origin.elapsed()));
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> JobTimeline {
+        let events = match self.inner.events.lock() {
+            Ok(events) => events
+                .iter()
+                .map(|(kind, at)| JobEvent {
+                    kind: *kind,
+                    at_ms: at.as_millis().try_into().unwrap_or(u64::MAX),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        JobTimeline {
+            start_unix_ms: self.inner.start_unix_ms,
+            events,
+        }
+    }
+}
+
+/// Records [`JobEventKind::FirstStdoutByte`] on the first non-empty read.
+pub(crate) struct FirstByteReader<R> {
+    inner: R,
+    timeline: Option<JobTimelineRecorder>,
+}
+
+impl<R> FirstByteReader<R> {
+    pub(crate) fn new(inner: R, timeline: &JobTimelineRecorder) -> Self {
+        Self {
+            inner,
+            timeline: Some(timeline.clone()),
+        }
+    }
+}
+
+impl<R: Read> Read for FirstByteReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            if let Some(timeline) = self.timeline.take() {
+                timeline.record_once(JobEventKind::FirstStdoutByte);
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_once_keeps_first_event() {
+        let timeline = JobTimelineRecorder::start();
+        timeline.record_once(JobEventKind::Exited);
+        std::thread::sleep(Duration::from_millis(5));
+        timeline.record_once(JobEventKind::Exited);
+
+        let snap = timeline.snapshot();
+        assert_eq!(snap.events.len(), 1);
+        assert_eq!(snap.first(JobEventKind::Exited), Some(snap.events[0].at_ms));
+        assert_eq!(snap.first(JobEventKind::CleanupDone), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn captured_command_records_first_stdout_byte() {
+        let timeline = JobTimelineRecorder::start();
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "sleep 0.05; printf hi; sleep 0.05"]);
+        let out = crate::run_command_capped_timed(cmd, 5_000, 1024, 1024, None, &timeline).unwrap();
+        assert_eq!(out.stdout, b"hi");
+
+        let t = &out.timeline;
+        let task = t.first(JobEventKind::TaskStarted).expect("task started");
+        let first = t.first(JobEventKind::FirstStdoutByte).expect("first byte");
+        let exited = t.first(JobEventKind::Exited).expect("exited");
+        assert!(task + 40 <= first, "{t:?}");
+        assert!(first + 40 <= exited, "{t:?}");
+
+        let json = serde_json::to_value(t).unwrap();
+        assert_eq!(json["events"][1]["kind"], "first_stdout_byte");
+    }
+}
@@ -0,0 +1,131 @@
+//! Workload limits x07-guestd enforces inside a vz guest.
+//!
+//! `LimitsSpec.mem_bytes` sizes the whole VM, which the guest kernel and
+//! x07-guestd share with the workload. x07-guestd puts the exec'd process in
+//! its own cgroup capped at `mem_bytes` minus a guest overhead, so a workload
+//! that runs out of memory is OOM-killed on its own and reported with
+//! [`VZ_EXIT_WORKLOAD_OOM`] instead of taking the guest init down with it.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::LimitsSpec;
+
+/// VM memory when `LimitsSpec.mem_bytes` is unset.
+pub const DEFAULT_VZ_MEM_BYTES: u64 = 512 * 1024 * 1024;
+pub const ENV_VZ_GUEST_OVERHEAD_BYTES: &str = "X07_VM_VZ_GUEST_OVERHEAD_BYTES";
+/// Memory kept back for the guest kernel and x07-guestd.
+pub const DEFAULT_VZ_GUEST_OVERHEAD_BYTES: u64 = 96 * 1024 * 1024;
+/// Floor for the workload cgroup's `memory.max`.
+pub const VZ_MIN_WORKLOAD_MEM_BYTES: u64 = 32 * 1024 * 1024;
+pub const ENV_VZ_WORKLOAD_PIDS_MAX: &str = "X07_VM_VZ_WORKLOAD_PIDS_MAX";
+/// Default `pids.max` for the workload cgroup.
+pub const DEFAULT_VZ_WORKLOAD_PIDS_MAX: u32 = 1024;
+/// Guest exit status when the workload cgroup recorded an OOM kill.
+pub const VZ_EXIT_WORKLOAD_OOM: i32 = 125;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VzWorkloadLimits {
+    /// Memory given to the whole VM (`--mem-bytes`).
+    pub vm_mem_bytes: u64,
+    pub guest_overhead_bytes: u64,
+    /// `memory.max` of the workload cgroup.
+    pub workload_mem_bytes: u64,
+    /// `pids.max` of the workload cgroup.
+    pub workload_pids_max: u32,
+}
+
+/// Splits the VM memory between the guest and the workload. Fails unless
+/// `guest_overhead_bytes < mem_bytes`; the workload share is clamped to
+/// [`VZ_MIN_WORKLOAD_MEM_BYTES`] (and never above `mem_bytes`).
+pub fn derive_vz_workload_limits(
+    mem_bytes: Option<u64>,
+    guest_overhead_bytes: u64,
+    workload_pids_max: u32,
+) -> Result<VzWorkloadLimits> {
+    let vm_mem_bytes = mem_bytes.unwrap_or(DEFAULT_VZ_MEM_BYTES);
+    if guest_overhead_bytes >= vm_mem_bytes {
+        anyhow::bail!(
+            "vz guest overhead ({guest_overhead_bytes} bytes) must be < mem_bytes ({vm_mem_bytes}); raise limits.mem_bytes or lower {ENV_VZ_GUEST_OVERHEAD_BYTES}"
+        );
+    }
+    if workload_pids_max == 0 {
+        anyhow::bail!("{ENV_VZ_WORKLOAD_PIDS_MAX} must be > 0");
+    }
+    let workload_mem_bytes = (vm_mem_bytes - guest_overhead_bytes)
+        .max(VZ_MIN_WORKLOAD_MEM_BYTES)
+        .min(vm_mem_bytes);
+    Ok(VzWorkloadLimits {
+        vm_mem_bytes,
+        guest_overhead_bytes,
+        workload_mem_bytes,
+        workload_pids_max,
+    })
+}
+
+/// [`derive_vz_workload_limits`] with the overhead and pids cap taken from
+/// `X07_VM_VZ_GUEST_OVERHEAD_BYTES` / `X07_VM_VZ_WORKLOAD_PIDS_MAX`.
+pub fn vz_workload_limits(limits: &LimitsSpec) -> Result<VzWorkloadLimits> {
+    let overhead =
+        env_number(ENV_VZ_GUEST_OVERHEAD_BYTES)?.unwrap_or(DEFAULT_VZ_GUEST_OVERHEAD_BYTES);
+    let pids_max = match env_number(ENV_VZ_WORKLOAD_PIDS_MAX)? {
+        Some(v) => u32::try_from(v)
+            .map_err(|_| anyhow::anyhow!("{ENV_VZ_WORKLOAD_PIDS_MAX} is out of range: {v}"))?,
+        None => DEFAULT_VZ_WORKLOAD_PIDS_MAX,
+    };
+    derive_vz_workload_limits(limits.mem_bytes, overhead, pids_max)
+}
+
+fn env_number(name: &str) -> Result<Option<u64>> {
+    match std::env::var(name) {
+        Ok(raw) if !raw.trim().is_empty() => raw
+            .trim()
+            .parse::<u64>()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("{name} must be a non-negative integer (got {raw:?})")),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn workload_gets_mem_minus_overhead() {
+        let l = derive_vz_workload_limits(None, DEFAULT_VZ_GUEST_OVERHEAD_BYTES, 1024).unwrap();
+        assert_eq!(
+            l,
+            VzWorkloadLimits {
+                vm_mem_bytes: 512 * MIB,
+                guest_overhead_bytes: 96 * MIB,
+                workload_mem_bytes: 416 * MIB,
+                workload_pids_max: 1024,
+            }
+        );
+
+        let l = derive_vz_workload_limits(Some(2048 * MIB), 128 * MIB, 64).unwrap();
+        assert_eq!(l.workload_mem_bytes, 1920 * MIB);
+        assert_eq!(l.workload_pids_max, 64);
+    }
+
+    #[test]
+    fn workload_share_is_clamped_to_minimum() {
+        let l = derive_vz_workload_limits(Some(100 * MIB), 96 * MIB, 1024).unwrap();
+        assert_eq!(l.workload_mem_bytes, VZ_MIN_WORKLOAD_MEM_BYTES);
+
+        // Never more than the VM itself.
+        let l = derive_vz_workload_limits(Some(16 * MIB), 8 * MIB, 1024).unwrap();
+        assert_eq!(l.workload_mem_bytes, 16 * MIB);
+    }
+
+    #[test]
+    fn overhead_must_be_below_mem_bytes() {
+        let err = derive_vz_workload_limits(Some(96 * MIB), 96 * MIB, 1024).unwrap_err();
+        assert!(err.to_string().contains("must be < mem_bytes"), "{err}");
+        assert!(derive_vz_workload_limits(Some(64 * MIB), 128 * MIB, 1024).is_err());
+        assert!(derive_vz_workload_limits(None, 0, 0).is_err());
+    }
+}
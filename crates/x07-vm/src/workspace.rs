@@ -0,0 +1,321 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use x07_contracts::X07_OS_RUNNER_REPORT_SCHEMA_VERSION;
+
+use crate::{MountKind, MountSpec, RunId};
+
+pub const JOB_GUEST_IN_DIR: &str = "/x07/in";
+pub const JOB_GUEST_OUT_DIR: &str = "/x07/out";
+pub const JOB_REPORT_FILE: &str = "report.json";
+
+/// Host-side layout of a single VM job under `<state_root>/<run_id>/`:
+/// `in/` (mounted read-only at `/x07/in`), `out/` (mounted read-write at
+/// `/x07/out`) and `logs/` (host only). The job dir doubles as the
+/// `state_dir` passed to [`crate::run_vm_job`].
+///
+/// Nothing is removed on drop; call [`JobWorkspace::discard`] or
+/// [`JobWorkspace::retain`] once the job is done.
+#[derive(Debug)]
+pub struct JobWorkspace {
+    root: PathBuf,
+    in_dir: PathBuf,
+    out_dir: PathBuf,
+    logs_dir: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobArtifacts {
+    /// Parsed `out/report.json`, if the guest wrote one.
+    pub report_json: Option<Value>,
+    /// Regular files under `out/`, sorted. Symlinks and other entries are ignored.
+    pub files: Vec<PathBuf>,
+    pub total_bytes: u64,
+}
+
+impl JobWorkspace {
+    pub fn create(state_root: &Path, run_id: &RunId) -> Result<Self> {
+        let root = state_root.join(run_id);
+        let in_dir = root.join("in");
+        let out_dir = root.join("out");
+        let logs_dir = root.join("logs");
+
+        std::fs::create_dir_all(state_root)
+            .with_context(|| format!("create vm state root: {}", state_root.display()))?;
+        std::fs::create_dir(&root)
+            .with_context(|| format!("create job dir: {}", root.display()))?;
+        for dir in [&in_dir, &out_dir, &logs_dir] {
+            std::fs::create_dir(dir)
+                .with_context(|| format!("create job dir: {}", dir.display()))?;
+        }
+
+        // The guest may run as an unprivileged uid: it only needs to read `in/`
+        // and write `out/`; `logs/` stays host-private.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            for (dir, mode) in [
+                (&root, 0o755),
+                (&in_dir, 0o755),
+                (&out_dir, 0o777),
+                (&logs_dir, 0o700),
+            ] {
+                std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode))
+                    .with_context(|| format!("chmod {mode:o}: {}", dir.display()))?;
+            }
+        }
+
+        Ok(Self {
+            root,
+            in_dir,
+            out_dir,
+            logs_dir,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn in_dir(&self) -> &Path {
+        &self.in_dir
+    }
+
+    pub fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    pub fn logs_dir(&self) -> &Path {
+        &self.logs_dir
+    }
+
+    /// Mounts for `/x07/in` (read-only) and `/x07/out` (read-write).
+    pub fn mounts(&self) -> Vec<MountSpec> {
+        vec![
+            MountSpec {
+                host_path: self.in_dir.clone(),
+                guest_path: PathBuf::from(JOB_GUEST_IN_DIR),
+                readonly: true,
+                kind: MountKind::Bind,
+            },
+            MountSpec {
+                host_path: self.out_dir.clone(),
+                guest_path: PathBuf::from(JOB_GUEST_OUT_DIR),
+                readonly: false,
+                kind: MountKind::Bind,
+            },
+        ]
+    }
+
+    /// Gathers the guest's outputs. Fails if `out/` holds more than `max_bytes`
+    /// or if `out/report.json` is not an `x07-os-runner` report.
+    pub fn collect(&self, max_bytes: u64) -> Result<JobArtifacts> {
+        let mut files = Vec::new();
+        let mut total_bytes: u64 = 0;
+        let mut stack = vec![self.out_dir.clone()];
+        while let Some(dir) = stack.pop() {
+            for entry in
+                std::fs::read_dir(&dir).with_context(|| format!("read_dir: {}", dir.display()))?
+            {
+                let entry = entry.context("read_dir entry")?;
+                let path = entry.path();
+                let md = std::fs::symlink_metadata(&path)
+                    .with_context(|| format!("metadata: {}", path.display()))?;
+                if md.is_dir() {
+                    stack.push(path);
+                } else if md.is_file() {
+                    total_bytes = total_bytes.saturating_add(md.len());
+                    if total_bytes > max_bytes {
+                        anyhow::bail!(
+                            "job outputs exceed {max_bytes} bytes: {}",
+                            self.out_dir.display()
+                        );
+                    }
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+
+        let report_path = self.out_dir.join(JOB_REPORT_FILE);
+        let report_json = if files.contains(&report_path) {
+            let bytes = std::fs::read(&report_path)
+                .with_context(|| format!("read job report: {}", report_path.display()))?;
+            let v: Value = serde_json::from_slice(&bytes)
+                .with_context(|| format!("parse job report: {}", report_path.display()))?;
+            let schema_version = v.get("schema_version").and_then(Value::as_str);
+            if schema_version != Some(X07_OS_RUNNER_REPORT_SCHEMA_VERSION) {
+                anyhow::bail!(
+                    "job report schema_version mismatch: expected {X07_OS_RUNNER_REPORT_SCHEMA_VERSION:?} got {schema_version:?}"
+                );
+            }
+            Some(v)
+        } else {
+            None
+        };
+
+        Ok(JobArtifacts {
+            report_json,
+            files,
+            total_bytes,
+        })
+    }
+
+    /// Keeps the job dir on disk (e.g. for debugging a failed job) and returns its path.
+    pub fn retain(self) -> PathBuf {
+        self.root
+    }
+
+    pub fn discard(self) -> Result<()> {
+        std::fs::remove_dir_all(&self.root)
+            .with_context(|| format!("remove job dir: {}", self.root.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let base = std::env::temp_dir();
+            let pid = std::process::id();
+
+            for _ in 0..256 {
+                let attempt_id = TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+                let path = base.join(format!("{prefix}_{pid}_{attempt_id}"));
+                match std::fs::create_dir(&path) {
+                    Ok(()) => return Self { path },
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                    Err(e) => panic!("create temp dir {path:?}: {e}"),
+                }
+            }
+
+            panic!("failed to create unique temp dir");
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn run_id(s: &str) -> RunId {
+        RunId::parse(s).unwrap()
+    }
+
+    fn write_report(ws: &JobWorkspace, schema_version: &str) {
+        let report = serde_json::json!({ "schema_version": schema_version, "ok": true });
+        std::fs::write(
+            ws.out_dir().join(JOB_REPORT_FILE),
+            serde_json::to_vec(&report).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn create_lays_out_dirs_and_mounts() {
+        let tmp = TempDir::new("x07_vm_workspace");
+        let ws = JobWorkspace::create(&tmp.path, &run_id("job1")).unwrap();
+
+        assert_eq!(ws.root(), tmp.path.join("job1"));
+        assert!(ws.in_dir().is_dir());
+        assert!(ws.out_dir().is_dir());
+        assert!(ws.logs_dir().is_dir());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(ws.in_dir()), 0o755);
+            assert_eq!(mode(ws.out_dir()), 0o777);
+            assert_eq!(mode(ws.logs_dir()), 0o700);
+        }
+
+        let mounts = ws.mounts();
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts[0].host_path, ws.in_dir());
+        assert_eq!(mounts[0].guest_path, Path::new(JOB_GUEST_IN_DIR));
+        assert!(mounts[0].readonly);
+        assert_eq!(mounts[1].host_path, ws.out_dir());
+        assert_eq!(mounts[1].guest_path, Path::new(JOB_GUEST_OUT_DIR));
+        assert!(!mounts[1].readonly);
+
+        assert!(JobWorkspace::create(&tmp.path, &run_id("job1")).is_err());
+    }
+
+    #[test]
+    fn collect_without_report() {
+        let tmp = TempDir::new("x07_vm_workspace");
+        let ws = JobWorkspace::create(&tmp.path, &run_id("job2")).unwrap();
+        std::fs::create_dir(ws.out_dir().join("sub")).unwrap();
+        std::fs::write(ws.out_dir().join("a.bin"), b"abc").unwrap();
+        std::fs::write(ws.out_dir().join("sub").join("b.bin"), b"hello").unwrap();
+
+        let artifacts = ws.collect(1024).unwrap();
+        assert!(artifacts.report_json.is_none());
+        assert_eq!(
+            artifacts.files,
+            vec![
+                ws.out_dir().join("a.bin"),
+                ws.out_dir().join("sub").join("b.bin"),
+            ]
+        );
+        assert_eq!(artifacts.total_bytes, 8);
+    }
+
+    #[test]
+    fn collect_with_report_validates_schema_version() {
+        let tmp = TempDir::new("x07_vm_workspace");
+        let ws = JobWorkspace::create(&tmp.path, &run_id("job3")).unwrap();
+
+        write_report(&ws, X07_OS_RUNNER_REPORT_SCHEMA_VERSION);
+        let artifacts = ws.collect(1024 * 1024).unwrap();
+        let report = artifacts.report_json.expect("report");
+        assert_eq!(report["ok"], Value::Bool(true));
+        assert_eq!(artifacts.files, vec![ws.out_dir().join(JOB_REPORT_FILE)]);
+
+        write_report(&ws, "x07-os-runner.report@0.0.0");
+        let err = ws.collect(1024 * 1024).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("schema_version mismatch"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn collect_enforces_size_cap() {
+        let tmp = TempDir::new("x07_vm_workspace");
+        let ws = JobWorkspace::create(&tmp.path, &run_id("job4")).unwrap();
+        std::fs::write(ws.out_dir().join("big.bin"), vec![0u8; 4096]).unwrap();
+
+        assert_eq!(ws.collect(4096).unwrap().total_bytes, 4096);
+        let err = ws.collect(4095).unwrap_err();
+        assert!(format!("{err:#}").contains("exceed 4095 bytes"), "{err:#}");
+    }
+
+    #[test]
+    fn retain_keeps_and_discard_removes() {
+        let tmp = TempDir::new("x07_vm_workspace");
+
+        let kept = JobWorkspace::create(&tmp.path, &run_id("job5"))
+            .unwrap()
+            .retain();
+        assert!(kept.join("out").is_dir());
+
+        let ws = JobWorkspace::create(&tmp.path, &run_id("job6")).unwrap();
+        let root = ws.root().to_path_buf();
+        ws.discard().unwrap();
+        assert!(!root.exists());
+    }
+}
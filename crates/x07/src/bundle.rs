@@ -19,8 +19,8 @@ use x07_runner_common::sandbox_backend::{
 use x07_runner_common::{auto_ffi, os_env, os_paths, os_policy};
 use x07_vm::{
     default_cleanup_ms, default_grace_ms, firecracker_ctr_config_from_env,
-    resolve_sibling_or_path as resolve_sibling_or_path_vm, resolve_vm_backend, LimitsSpec,
-    MountSpec, NetworkMode, RunSpec, VmBackend,
+    resolve_sibling_or_path as resolve_sibling_or_path_vm, resolve_vm_backend_with_record,
+    LimitsSpec, MountKind, MountSpec, NetworkMode, RunSpec, VmBackend,
 };
 use x07_worlds::WorldId;
 use x07c::project;
@@ -531,11 +531,19 @@ pub fn cmd_bundle(
         world_tag: world.as_str().to_string(),
         fuel_init: solve_fuel,
         mem_cap_bytes: max_memory_bytes,
+        arena_cap_bytes: None,
         debug_borrow_checks: args.debug_borrow_checks,
         enable_fs: compile_options.enable_fs,
         enable_rr: compile_options.enable_rr,
         enable_kv: compile_options.enable_kv,
         extra_cc_args,
+        extra_defines: Vec::new(),
+        warnings_as_errors: false,
+        sanitizers: Vec::new(),
+        options_fingerprint: None,
+        cache_salt: Vec::new(),
+        cache_dir_override: None,
+        bypass_cache: false,
     };
 
     let wrapper = NativeCliWrapperOpts {
@@ -551,6 +559,7 @@ pub fn cmd_bundle(
         &toolchain,
         &out_path,
         &wrapper,
+        None,
     )?;
 
     if let Some(attestation_path) = &args.emit_attestation {
@@ -761,7 +770,11 @@ fn cmd_bundle_vm(params: CmdBundleVmParams<'_>) -> Result<std::process::ExitCode
         .validate_basic()
         .map_err(|e| anyhow::anyhow!("policy invalid: {e}"))?;
 
-    let backend = resolve_vm_backend()?;
+    let resolution = resolve_vm_backend_with_record()?;
+    for warning in &resolution.warnings {
+        eprintln!("warning: {warning}");
+    }
+    let backend = resolution.backend;
 
     let guest_image =
         std::env::var("X07_VM_GUEST_IMAGE").unwrap_or_else(|_| default_vm_guest_image());
@@ -993,13 +1006,17 @@ fn build_vm_payload_bundle(params: VmPayloadBundleParams<'_>) -> Result<VmPayloa
         std::env::set_var(x07_vm::ENV_ACCEPT_WEAKER_ISOLATION, "1");
     }
 
-    let backend = resolve_vm_backend()?;
+    let resolution = resolve_vm_backend_with_record()?;
+    for warning in &resolution.warnings {
+        eprintln!("warning: {warning}");
+    }
+    let backend = resolution.backend;
 
     let created_unix_ms = now_unix_ms()?;
     let run_id = {
         let pid = std::process::id();
         let n = VM_RUN_COUNTER.fetch_add(1, Ordering::Relaxed);
-        format!("{created_unix_ms}-{pid}-{n}")
+        x07_vm::RunId::parse(&format!("{created_unix_ms}-{pid}-{n}"))?
     };
 
     let wall_ms = policy.limits.wall_ms.max(1);
@@ -1170,11 +1187,13 @@ fn build_vm_payload_bundle(params: VmPayloadBundleParams<'_>) -> Result<VmPayloa
             host_path: job_in.clone(),
             guest_path: PathBuf::from("/x07/in"),
             readonly: true,
+            kind: MountKind::Bind,
         },
         MountSpec {
             host_path: job_out.clone(),
             guest_path: PathBuf::from("/x07/out"),
             readonly: false,
+            kind: MountKind::Bind,
         },
     ];
 
@@ -1187,6 +1206,10 @@ fn build_vm_payload_bundle(params: VmPayloadBundleParams<'_>) -> Result<VmPayloa
         max_stdout_bytes: 16 * 1024 * 1024,
         max_stderr_bytes: 16 * 1024 * 1024,
         network: NetworkMode::None,
+        persist_output: false,
+        persist_output_dir: None,
+        warn_before_deadline_ms: None,
+        shm_size_bytes: None,
     };
 
     let spec = RunSpec {
@@ -1203,6 +1226,9 @@ fn build_vm_payload_bundle(params: VmPayloadBundleParams<'_>) -> Result<VmPayloa
         mounts,
         workdir: Some(PathBuf::from("/opt/x07")),
         limits,
+        digest_mounts: false,
+        dry_run: false,
+        report_file: None,
     };
 
     let firecracker_cfg = if backend == VmBackend::FirecrackerCtr {
@@ -1221,8 +1247,12 @@ fn build_vm_payload_bundle(params: VmPayloadBundleParams<'_>) -> Result<VmPayloa
             created_unix_ms,
             deadline_unix_ms,
             firecracker_cfg: firecracker_cfg.as_ref(),
+            spawn_reaper: true,
         },
     )?;
+    for warning in &out.warnings {
+        eprintln!("warning: {warning}");
+    }
 
     if !out.stderr.is_empty() {
         let _ = std::io::Write::write_all(&mut std::io::stderr(), &out.stderr);
@@ -1628,7 +1658,7 @@ fn host_compile_report_json(
 ) -> Result<Value> {
     let b64 = base64::engine::general_purpose::STANDARD;
     let exit_code: u8 = if compile.ok { 0 } else { 1 };
-    Ok(serde_json::json!({
+    let mut report = serde_json::json!({
         "schema_version": X07_HOST_RUNNER_REPORT_SCHEMA_VERSION,
         "mode": mode,
         "exit_code": exit_code,
@@ -1641,13 +1671,25 @@ fn host_compile_report_json(
             "compiled_exe": compile.compiled_exe.as_ref().map(|p| p.display().to_string()),
             "compiled_exe_size": compile.compiled_exe_size,
             "compile_error": compile.compile_error,
+            "options_fingerprint": compile.options_fingerprint,
+            "native_cache_key": compile.native_cache_key,
             "stdout_b64": b64.encode(&compile.stdout),
             "stderr_b64": b64.encode(&compile.stderr),
             "fuel_used": compile.fuel_used,
             "trap": compile.trap,
         },
         "solve": serde_json::Value::Null,
-    }))
+    });
+    if !compile.native_backend_resolutions.is_empty() {
+        report["compile"]["native_backend_resolutions"] =
+            serde_json::to_value(&compile.native_backend_resolutions)
+                .context("serialize native backend resolutions")?;
+    }
+    if !compile.cc_warnings.is_empty() {
+        report["compile"]["cc_warnings"] =
+            serde_json::to_value(&compile.cc_warnings).context("serialize cc warnings")?;
+    }
+    Ok(report)
 }
 
 fn write_compile_attestation(path: &Path, attestation: &CompileAttestation) -> Result<()> {
@@ -1701,8 +1743,14 @@ fn compute_double_build_rebuild(
             .unwrap_or_else(|| PathBuf::from("x07-rebuild")),
     );
 
-    let rebuild_result =
-        x07_host_runner::compile_bundle_exe(program, compile_options, toolchain, &tmp_out, wrapper);
+    let rebuild_result = x07_host_runner::compile_bundle_exe(
+        program,
+        compile_options,
+        toolchain,
+        &tmp_out,
+        wrapper,
+        None,
+    );
 
     let rebuild = match rebuild_result {
         Ok(rebuild) if rebuild.compile.ok && tmp_out.is_file() => {
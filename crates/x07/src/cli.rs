@@ -431,17 +431,11 @@ fn compile_specrows_v1(
 ) -> Result<SpecrowsCompileOutput> {
     let config = x07_host_runner::RunnerConfig {
         world: x07_worlds::WorldId::SolvePure,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: 50_000_000,
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 64 * 1024 * 1024,
         cpu_time_limit_seconds: 30,
-        debug_borrow_checks: false,
+        ..Default::default()
     };
 
     let compile_options = x07_host_runner::compile_options_for_world(
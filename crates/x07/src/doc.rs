@@ -396,6 +396,12 @@ fn special_form_doc(name: &str) -> Option<&'static str> {
              Alias of `std.prng.lcg_next_u32`.\n\
              Example: [\"prng.lcg_next_u32\", 123]",
         ),
+        "prng.run_seed_u32" => Some(
+            "prng.run_seed_u32() -> i32\n\
+             Per-run seed set by the host runner (`--solve-seed`), folded to 32 bits; 0 when unset.\n\
+             Use it as the initial `prng.lcg_next_u32` state.\n\
+             Example: [\"prng.lcg_next_u32\", [\"prng.run_seed_u32\"]]",
+        ),
         "chan.bytes.new" => Some(
             "chan.bytes.new(cap: i32) -> i32\n\
              Create a bytes channel with capacity `cap`.\n\
@@ -1642,6 +1648,7 @@ fn special_form_names() -> &'static [&'static str] {
         "parse.u32_dec",
         "parse.u32_dec_at",
         "prng.lcg_next_u32",
+        "prng.run_seed_u32",
         "std.stream.pipe_v1",
         "task.cancel",
         "task.is_finished",
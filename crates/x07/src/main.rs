@@ -297,6 +297,11 @@ struct TestArgs {
     #[arg(long, value_name = "N")]
     pbt_cases: Option<u32>,
 
+    /// Per-run seed exposed to tests as `prng.run_seed_u32` (default: unset,
+    /// i.e. 0). Recorded in PBT repros so replays reuse it.
+    #[arg(long, value_name = "U64")]
+    solve_seed: Option<u64>,
+
     /// Override per-test max shrink attempts.
     #[arg(long, value_name = "N")]
     pbt_max_shrinks: Option<u32>,
@@ -1511,7 +1516,7 @@ fn run_one_test(
         .or_else(|| args.manifest.parent().map(|p| p.to_path_buf()))
         .or_else(|| std::env::current_dir().ok());
 
-    let runner_config = runner_config_for_test(test)?;
+    let runner_config = runner_config_for_test(test, args.solve_seed)?;
 
     let compiled_out = exe_out_path.as_deref();
     let compile_res = x07_host_runner::compile_program_with_options(
@@ -1864,7 +1869,7 @@ fn run_one_pbt_test(
         .or_else(|| args.manifest.parent().map(|p| p.to_path_buf()))
         .or_else(|| std::env::current_dir().ok());
 
-    let runner_config = runner_config_for_test(test)?;
+    let runner_config = runner_config_for_test(test, args.solve_seed)?;
     let compiled_out = exe_out_path.as_deref();
     let compile_res = x07_host_runner::compile_program_with_options(
         &driver_src,
@@ -1928,6 +1933,7 @@ fn run_one_pbt_test(
         case_cfg.max_memory_bytes = repro.budget.max_mem_bytes as usize;
         case_cfg.max_output_bytes = repro.budget.max_output_bytes as usize;
         case_cfg.cpu_time_limit_seconds = ms_to_ceiling_seconds(repro.budget.timeout_ms)?;
+        case_cfg.solve_seed = repro.solve_seed_u64;
 
         let mut first_obs: Option<ObservedRun> = None;
         let mut last_run: Option<RunnerResult> = None;
@@ -2457,17 +2463,12 @@ fn run_one_test_os(
 
                     let runner_cfg = RunnerConfig {
                         world: WorldId::SolveRr,
-                        fixture_fs_dir: None,
-                        fixture_fs_root: None,
-                        fixture_fs_latency_index: None,
                         fixture_rr_dir: Some(fixture_rr_dir),
-                        fixture_kv_dir: None,
-                        fixture_kv_seed: None,
                         solve_fuel: test.solve_fuel.unwrap_or(X07TEST_SOLVE_FUEL),
                         max_memory_bytes: 64 * 1024 * 1024,
                         max_output_bytes: 1024 * 1024,
                         cpu_time_limit_seconds,
-                        debug_borrow_checks: false,
+                        ..Default::default()
                     };
 
                     match contract_repro::write_repro(
@@ -2606,7 +2607,7 @@ fn run_one_test_os(
     Ok(result)
 }
 
-fn runner_config_for_test(test: &TestDecl) -> Result<RunnerConfig> {
+fn runner_config_for_test(test: &TestDecl, solve_seed: Option<u64>) -> Result<RunnerConfig> {
     let cpu_time_limit_seconds = match test.timeout_ms {
         Some(ms) => ms_to_ceiling_seconds(ms)?,
         None => 5,
@@ -2614,17 +2615,12 @@ fn runner_config_for_test(test: &TestDecl) -> Result<RunnerConfig> {
 
     let mut cfg = RunnerConfig {
         world: test.world,
-        fixture_fs_dir: None,
-        fixture_fs_root: None,
-        fixture_fs_latency_index: None,
-        fixture_rr_dir: None,
-        fixture_kv_dir: None,
-        fixture_kv_seed: None,
         solve_fuel: test.solve_fuel.unwrap_or(X07TEST_SOLVE_FUEL),
         max_memory_bytes: 64 * 1024 * 1024,
         max_output_bytes: 1024 * 1024,
         cpu_time_limit_seconds,
-        debug_borrow_checks: false,
+        solve_seed,
+        ..Default::default()
     };
 
     match test.world {
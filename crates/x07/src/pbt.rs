@@ -690,6 +690,9 @@ pub(crate) struct PbtRepro {
     pub failure: FailureInfo,
     pub counterexample: CounterexampleInfo,
     pub budget: PbtCaseBudget,
+    /// `RunnerConfig::solve_seed` of the failing run; replays reuse it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solve_seed_u64: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1256,6 +1259,7 @@ pub(crate) fn run_pbt_suite(args: RunPbtSuiteArgs<'_>) -> Result<(PbtSuiteRun, P
             case_bytes_b64: base64::engine::general_purpose::STANDARD.encode(&case_bytes),
         },
         budget: *budget,
+        solve_seed_u64: base_cfg.solve_seed,
     };
 
     let (status_tag, status_code_u32) = match kind {
@@ -800,6 +800,7 @@ pub fn cmd_run(
                     max_output_bytes: max_output_bytes_effective,
                     cpu_time_limit_seconds: cpu_time_limit_seconds_effective,
                     debug_borrow_checks: args.debug_borrow_checks,
+                    ..Default::default()
                 };
 
                 let repro_root = project_root
@@ -29,6 +29,10 @@ ev_bytes x07_ext_db_pg_query_v1(ev_bytes req, ev_bytes caps);
 ev_bytes x07_ext_db_pg_exec_v1(ev_bytes req, ev_bytes caps);
 ev_bytes x07_ext_db_pg_close_v1(ev_bytes req, ev_bytes caps);
 
+// Read-only usage snapshot: a DataModel map of live_conns/max_live_conns and
+// queries_used/max_queries (a max of 0 means unlimited).
+ev_bytes x07_ext_db_pg_stats_v1(void);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif
@@ -28,6 +28,24 @@ ev_bytes x07_ext_db_redis_open_v1(ev_bytes req, ev_bytes caps);
 ev_bytes x07_ext_db_redis_cmd_v1(ev_bytes req, ev_bytes caps);
 ev_bytes x07_ext_db_redis_close_v1(ev_bytes req, ev_bytes caps);
 
+// Pooled open/close: acquire reuses an idle connection for the same open
+// params, release parks the connection instead of closing it.
+ev_bytes x07_ext_db_redis_acquire_v1(ev_bytes req, ev_bytes caps);
+ev_bytes x07_ext_db_redis_release_v1(uint32_t conn_id);
+
+// Drains one pending RESP3 push message after SUBSCRIBE; the connection is then
+// dedicated to pub/sub.
+ev_bytes x07_ext_db_redis_get_push_v1(uint32_t conn_id, uint32_t timeout_ms);
+
+// Lists keys matching a glob by walking SCAN to completion or max_keys (never
+// KEYS); returns a map {complete, keys} with keys sorted and deduplicated.
+ev_bytes x07_ext_db_redis_scan_v1(uint32_t conn_id, ev_bytes match_pattern, uint32_t count_hint,
+                                  uint32_t max_keys, ev_bytes caps);
+
+// Read-only usage snapshot: a DataModel map of live_conns/max_live_conns and
+// queries_used/max_queries (a max of 0 means unlimited).
+ev_bytes x07_ext_db_redis_stats_v1(void);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif
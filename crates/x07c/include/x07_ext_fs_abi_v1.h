@@ -48,8 +48,18 @@ ev_result_i32 x07_ext_fs_remove_file_v1(ev_bytes path, ev_bytes caps);
 ev_result_i32 x07_ext_fs_remove_dir_all_v1(ev_bytes path, ev_bytes caps);
 ev_result_i32 x07_ext_fs_rename_v1(ev_bytes src, ev_bytes dst, ev_bytes caps);
 ev_result_bytes x07_ext_fs_list_dir_sorted_text_v1(ev_bytes path, ev_bytes caps);
+ev_result_bytes x07_ext_fs_list_dir_page_v1(ev_bytes path, ev_bytes start_after_name, int32_t page_size, ev_bytes caps);
+ev_result_bytes x07_ext_fs_list_dir_page_meta_v1(ev_bytes path, ev_bytes start_after_name, int32_t page_size, ev_bytes caps);
 ev_result_bytes x07_ext_fs_walk_glob_sorted_text_v1(ev_bytes root, ev_bytes glob, ev_bytes caps);
+ev_result_bytes x07_ext_fs_du_v1(ev_bytes root, ev_bytes caps);
+ev_result_bytes x07_ext_fs_copy_tree_v1(ev_bytes src, ev_bytes dst, ev_bytes caps);
 ev_result_bytes x07_ext_fs_stat_v1(ev_bytes path, ev_bytes caps);
+ev_result_i32 x07_ext_fs_set_mtime_v1(ev_bytes path, uint64_t secs, uint32_t nanos, ev_bytes caps);
+ev_result_i32 x07_ext_fs_set_mode_v1(ev_bytes path, uint32_t mode, ev_bytes caps);
+ev_result_bytes x07_ext_fs_stat_v2(ev_bytes path, ev_bytes caps);
+ev_result_bytes x07_ext_fs_stat_many_v1(ev_bytes paths, ev_bytes caps);
+ev_result_bytes x07_ext_fs_wait_change_v1(ev_bytes path, uint64_t baseline_mtime_ns, uint64_t baseline_size, uint32_t timeout_ms, ev_bytes caps);
+ev_result_bytes x07_ext_fs_policy_info_v1(ev_bytes caps);
 
 // v1 streaming write handle API used by os.fs.stream_* builtins.
 ev_result_i32 x07_ext_fs_stream_open_write_v1(ev_bytes path, ev_bytes caps);
@@ -64,6 +74,11 @@ ev_result_i32 x07_ext_fs_stream_read_into_v1(int32_t reader_handle, uint8_t* dst
 ev_result_i32 x07_ext_fs_stream_close_read_v1(int32_t reader_handle);
 int32_t x07_ext_fs_stream_drop_read_v1(int32_t reader_handle);
 
+// v1 advisory locks (held on `<path>.x07_lock`; released on process exit).
+ev_result_i32 x07_ext_fs_lock_acquire_v1(ev_bytes path, int32_t exclusive_flag, int32_t wait_ms, ev_bytes caps);
+ev_result_i32 x07_ext_fs_lock_release_v1(int32_t lock_handle);
+int32_t x07_ext_fs_lock_release_all_v1(void);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif
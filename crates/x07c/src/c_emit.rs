@@ -702,6 +702,9 @@ struct Emitter<'a> {
     program: &'a Program,
     options: CompileOptions,
     out: String,
+    // Newlines in `out[..out_lines_scanned]`, for `#line` directives.
+    out_lines: usize,
+    out_lines_scanned: usize,
     suppress_output: bool,
     indent: usize,
     tmp_counter: u32,
@@ -6420,6 +6420,17 @@ impl<'a> Emitter<'a> {
                         self.line(state, format!("goto st_{cont};"));
                         return Ok(());
                     }
+                    "prng.run_seed_u32" => {
+                        if !args.is_empty() || dest.ty != Ty::I32 {
+                            return Err(CompilerError::new(
+                                CompileErrorKind::Typing,
+                                "prng.run_seed_u32 expects 0 args".to_string(),
+                            ));
+                        }
+                        self.line(state, format!("{} = rt_prng_run_seed_u32();", dest.c_name));
+                        self.line(state, format!("goto st_{cont};"));
+                        return Ok(());
+                    }
                     "vec_u8.with_capacity" => {
                         if args.len() != 1 || dest.ty != Ty::VecU8 || args[0].ty != Ty::I32 {
                             return Err(CompilerError::new(
@@ -1059,6 +1059,7 @@ impl<'a> Emitter<'a> {
             "parse.u32_dec" => self.emit_parse_u32_dec_to(args, dest_ty, dest),
             "parse.u32_dec_at" => self.emit_parse_u32_dec_at_to(args, dest_ty, dest),
             "prng.lcg_next_u32" => self.emit_prng_lcg_next_u32_to(args, dest_ty, dest),
+            "prng.run_seed_u32" => self.emit_prng_run_seed_u32_to(args, dest_ty, dest),
 
             "vec_u8.with_capacity" => self.emit_vec_u8_new_to(args, dest_ty, dest),
             "vec_u8.len" => self.emit_vec_u8_len_to(args, dest_ty, dest),
@@ -5865,6 +5866,28 @@ Use a signed comparison like `(>= x 0)` when checking for negatives, or remove t
         Ok(())
     }
 
+    pub(super) fn emit_prng_run_seed_u32_to(
+        &mut self,
+        args: &[Expr],
+        dest_ty: Ty,
+        dest: &str,
+    ) -> Result<(), CompilerError> {
+        if !args.is_empty() {
+            return Err(CompilerError::new(
+                CompileErrorKind::Parse,
+                "prng.run_seed_u32 expects 0 args".to_string(),
+            ));
+        }
+        if dest_ty != Ty::I32 {
+            return Err(CompilerError::new(
+                CompileErrorKind::Typing,
+                "prng.run_seed_u32 returns i32".to_string(),
+            ));
+        }
+        self.line(&format!("{dest} = rt_prng_run_seed_u32();"));
+        Ok(())
+    }
+
     pub(super) fn emit_vec_u8_new_to(
         &mut self,
         args: &[Expr],
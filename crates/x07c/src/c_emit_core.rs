@@ -13,6 +13,7 @@ impl<'a> Emitter<'a> {
         if self.options.world.is_standalone_only() {
             self.push_str(c_emit_worlds::RUNTIME_C_OS);
         }
+        self.push_str(RUNTIME_C_DBG_SITES);
         self.push_char('\n');
 
         if self.options.profile_fns {
@@ -510,7 +511,18 @@ impl<'a> Emitter<'a> {
 
     pub(super) fn emit_source_line_for_module(&mut self, module_id: &str) {
         let file = module_id.replace('.', "/") + ".x07.json";
-        self.line(&format!("#line 1 \"{}\"", c_escape_c_string(&file)));
+        // Keep `__LINE__` equal to the physical line in the generated C so that
+        // debug borrow violation sites can be looked up in the emitted source.
+        self.out_lines += self.out[self.out_lines_scanned..]
+            .bytes()
+            .filter(|&b| b == b'\n')
+            .count();
+        self.out_lines_scanned = self.out.len();
+        let next_line = self.out_lines + 2;
+        self.line(&format!(
+            "#line {next_line} \"{}\"",
+            c_escape_c_string(&file)
+        ));
     }
 
     pub(super) fn push_scope(&mut self) {
@@ -693,6 +705,8 @@ impl<'a> Emitter<'a> {
             program,
             options,
             out: String::new(),
+            out_lines: 0,
+            out_lines_scanned: 0,
             suppress_output: false,
             indent: 0,
             tmp_counter: 0,
@@ -2123,6 +2137,9 @@ int snprintf(char* s, size_t n, const char* fmt, ...);
 #ifndef X07_DBG_BORROW_CAP
 #define X07_DBG_BORROW_CAP 65536u
 #endif
+#ifndef X07_DBG_VIOLATION_SITES_CAP
+#define X07_DBG_VIOLATION_SITES_CAP 16u
+#endif
 #endif
 
 typedef struct {
@@ -2455,6 +2472,11 @@ typedef struct {
   uint32_t dbg_borrows_cap;
 
   uint64_t dbg_borrow_violations;
+  // C source lines of the first X07_DBG_VIOLATION_SITES_CAP violations.
+  uint32_t dbg_violation_sites[X07_DBG_VIOLATION_SITES_CAP];
+  uint32_t dbg_violation_sites_len;
+  // C source line of the generated call into the runtime that is in progress.
+  uint32_t dbg_site;
 #endif
   uint64_t fs_read_file_calls;
   uint64_t fs_list_dir_calls;
@@ -2649,6 +2671,57 @@ bytes_t x07_ext_db_redis_open_v1(bytes_t req, bytes_t caps);
 bytes_t x07_ext_db_redis_cmd_v1(bytes_t req, bytes_t caps);
 bytes_t x07_ext_db_redis_close_v1(bytes_t req, bytes_t caps);
 
+// Per-backend query/exec/cmd wall-time histograms for the metrics line. The
+// weak stubs report no buckets when a backend library is not linked.
+#define X07_DB_LATENCY_BUCKETS 5u
+__attribute__((weak)) uint32_t x07_ext_db_sqlite_latency_hist_v1(uint64_t* out) {
+  (void)out;
+  return 0;
+}
+__attribute__((weak)) uint32_t x07_ext_db_pg_latency_hist_v1(uint64_t* out) {
+  (void)out;
+  return 0;
+}
+__attribute__((weak)) uint32_t x07_ext_db_redis_latency_hist_v1(uint64_t* out) {
+  (void)out;
+  return 0;
+}
+
+// Writes `,"db_latency_ms":{"<backend>":[5 counts],...}` for the backends that
+// recorded any call, or an empty string when none did.
+static void rt_db_latency_json(char* buf, size_t cap) {
+  static const char* const names[3] = {"sqlite", "pg", "redis"};
+  uint32_t (*const hists[3])(uint64_t*) = {
+    x07_ext_db_sqlite_latency_hist_v1,
+    x07_ext_db_pg_latency_hist_v1,
+    x07_ext_db_redis_latency_hist_v1,
+  };
+  size_t off = 0;
+  buf[0] = 0;
+  for (uint32_t i = 0; i < 3; i++) {
+    uint64_t b[X07_DB_LATENCY_BUCKETS] = {0};
+    if (hists[i](b) != X07_DB_LATENCY_BUCKETS) continue;
+    if ((b[0] | b[1] | b[2] | b[3] | b[4]) == 0) continue;
+    int n = snprintf(
+      buf + off,
+      cap - off,
+      "%s\"%s\":[%" PRIu64 ",%" PRIu64 ",%" PRIu64 ",%" PRIu64 ",%" PRIu64 "]",
+      off == 0 ? ",\"db_latency_ms\":{" : ",",
+      names[i],
+      b[0], b[1], b[2], b[3], b[4]
+    );
+    if (n < 0 || (size_t)n >= cap - off - 1) {
+      buf[0] = 0;
+      return;
+    }
+    off += (size_t)n;
+  }
+  if (off != 0) {
+    buf[off++] = '}';
+    buf[off] = 0;
+  }
+}
+
 // --- X07_EXT_OBJ_S3_START
 // Native ext-obj-s3 backend entrypoint (linked from deps/x07/libx07_ext_obj_s3.*).
 bytes_t x07_obj_s3_dispatch_v1(bytes_t req, bytes_t caps);
@@ -3095,20 +3168,35 @@ static void rt_dbg_init(ctx_t* ctx) {
       );
 
   ctx->dbg_borrow_violations = 0;
+  ctx->dbg_violation_sites_len = 0;
+  ctx->dbg_site = 0;
+}
+
+static void rt_dbg_violation(ctx_t* ctx, uint32_t site) {
+  ctx->dbg_borrow_violations += 1;
+  if (ctx->dbg_violation_sites_len < X07_DBG_VIOLATION_SITES_CAP) {
+    ctx->dbg_violation_sites[ctx->dbg_violation_sites_len++] = site;
+  }
 }
 
 static uint64_t rt_dbg_borrow_acquire(
     ctx_t* ctx,
     uint64_t alloc_id,
     uint32_t off_bytes,
-    uint32_t len_bytes
+    uint32_t len_bytes,
+    uint32_t site
 );
 
-static uint64_t rt_dbg_alloc_register(ctx_t* ctx, uint8_t* base_ptr, uint32_t size_bytes) {
+static uint64_t rt_dbg_alloc_register(
+    ctx_t* ctx,
+    uint8_t* base_ptr,
+    uint32_t size_bytes,
+    uint32_t site
+) {
   if (size_bytes == 0) return 0;
   if (!ctx->dbg_allocs || ctx->dbg_allocs_cap == 0) return 0;
   if (ctx->dbg_allocs_len >= ctx->dbg_allocs_cap) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint32_t idx = ctx->dbg_allocs_len++;
@@ -3116,15 +3204,15 @@ static uint64_t rt_dbg_alloc_register(ctx_t* ctx, uint8_t* base_ptr, uint32_t si
   rec->base_ptr = base_ptr;
   rec->size_bytes = size_bytes;
   rec->alive = 1;
-  rec->borrow_id = rt_dbg_borrow_acquire(ctx, (uint64_t)idx + 1, 0, size_bytes);
+  rec->borrow_id = rt_dbg_borrow_acquire(ctx, (uint64_t)idx + 1, 0, size_bytes, site);
   return (uint64_t)idx + 1;
 }
 
-static void rt_dbg_alloc_kill(ctx_t* ctx, uint64_t alloc_id) {
+static void rt_dbg_alloc_kill(ctx_t* ctx, uint64_t alloc_id, uint32_t site) {
   if (alloc_id == 0) return;
   uint64_t idx = alloc_id - 1;
   if (!ctx->dbg_allocs || idx >= (uint64_t)ctx->dbg_allocs_len) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return;
   }
   ctx->dbg_allocs[idx].alive = 0;
@@ -3134,12 +3222,13 @@ static uint64_t rt_dbg_alloc_find(
     ctx_t* ctx,
     uint8_t* ptr,
     uint32_t len_bytes,
-    uint32_t* out_off_bytes
+    uint32_t* out_off_bytes,
+    uint32_t site
 ) {
   if (out_off_bytes) *out_off_bytes = 0;
   if (len_bytes == 0) return 0;
   if (!ctx->dbg_allocs) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uintptr_t p = (uintptr_t)ptr;
@@ -3153,14 +3242,14 @@ static uint64_t rt_dbg_alloc_find(
     if (p >= base && p < end) {
       uint32_t off = (uint32_t)(p - base);
       if (off > rec->size_bytes || rec->size_bytes - off < len_bytes) {
-        ctx->dbg_borrow_violations += 1;
+        rt_dbg_violation(ctx, site);
         return 0;
       }
       if (out_off_bytes) *out_off_bytes = off;
       return (uint64_t)idx + 1;
     }
   }
-  ctx->dbg_borrow_violations += 1;
+  rt_dbg_violation(ctx, site);
   return 0;
 }
 
@@ -3191,16 +3280,16 @@ static uint64_t rt_dbg_alloc_try_find(
   return 0;
 }
 
-static uint64_t rt_dbg_alloc_borrow_id(ctx_t* ctx, uint64_t alloc_id) {
+static uint64_t rt_dbg_alloc_borrow_id(ctx_t* ctx, uint64_t alloc_id, uint32_t site) {
   if (alloc_id == 0) return 0;
   uint64_t idx = alloc_id - 1;
   if (!ctx->dbg_allocs || idx >= (uint64_t)ctx->dbg_allocs_len) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint64_t bid = ctx->dbg_allocs[idx].borrow_id;
   if (bid == 0) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   return bid;
@@ -3210,33 +3299,34 @@ static uint64_t rt_dbg_borrow_acquire(
     ctx_t* ctx,
     uint64_t alloc_id,
     uint32_t off_bytes,
-    uint32_t len_bytes
+    uint32_t len_bytes,
+    uint32_t site
 ) {
   if (len_bytes == 0) return 0;
   if (alloc_id == 0) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint64_t aidx = alloc_id - 1;
   if (!ctx->dbg_allocs || aidx >= (uint64_t)ctx->dbg_allocs_len) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   dbg_alloc_rec_t* a = &ctx->dbg_allocs[aidx];
   if (!a->alive) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   if (off_bytes > a->size_bytes || a->size_bytes - off_bytes < len_bytes) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   if (!ctx->dbg_borrows || ctx->dbg_borrows_cap == 0) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   if (ctx->dbg_borrows_len >= ctx->dbg_borrows_cap) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint32_t idx = ctx->dbg_borrows_len++;
@@ -3248,11 +3338,11 @@ static uint64_t rt_dbg_borrow_acquire(
   return (uint64_t)idx + 1;
 }
 
-static void rt_dbg_borrow_release(ctx_t* ctx, uint64_t borrow_id) {
+static void rt_dbg_borrow_release(ctx_t* ctx, uint64_t borrow_id, uint32_t site) {
   if (borrow_id == 0) return;
   uint64_t idx = borrow_id - 1;
   if (!ctx->dbg_borrows || idx >= (uint64_t)ctx->dbg_borrows_len) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return;
   }
   ctx->dbg_borrows[idx].active = 0;
@@ -3262,49 +3352,50 @@ static uint32_t rt_dbg_borrow_check(
     ctx_t* ctx,
     uint64_t borrow_id,
     uint32_t off_bytes,
-    uint32_t len_bytes
+    uint32_t len_bytes,
+    uint32_t site
 ) {
   if (len_bytes == 0) return 1;
   if (borrow_id == 0) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint64_t idx = borrow_id - 1;
   if (!ctx->dbg_borrows || idx >= (uint64_t)ctx->dbg_borrows_len) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   dbg_borrow_rec_t* b = &ctx->dbg_borrows[idx];
   if (!b->active) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint64_t alloc_id = b->alloc_id;
   if (alloc_id == 0) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint64_t aidx = alloc_id - 1;
   if (!ctx->dbg_allocs || aidx >= (uint64_t)ctx->dbg_allocs_len) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   dbg_alloc_rec_t* a = &ctx->dbg_allocs[aidx];
   if (!a->alive) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   if (off_bytes < b->off_bytes) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   uint32_t rel = off_bytes - b->off_bytes;
   if (rel > b->len_bytes || b->len_bytes - rel < len_bytes) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   if (off_bytes > a->size_bytes || a->size_bytes - off_bytes < len_bytes) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   return 1;
@@ -3343,7 +3434,7 @@ static bytes_t rt_bytes_alloc(ctx_t* ctx, uint32_t len) {
   out.len = len;
   out.ptr = (uint8_t*)rt_alloc(ctx, len, 1);
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_alloc_register(ctx, out.ptr, len);
+  (void)rt_dbg_alloc_register(ctx, out.ptr, len, ctx->dbg_site);
 #endif
   return out;
 }
@@ -3378,12 +3469,12 @@ static bytes_view_t rt_view_from_literal(ctx_t* ctx, const uint8_t* ptr, uint32_
   uint32_t off = 0;
   uint64_t aid = rt_dbg_alloc_try_find(ctx, (uint8_t*)ptr, len, &off);
   if (aid == 0) {
-    aid = rt_dbg_alloc_register(ctx, (uint8_t*)ptr, len);
+    aid = rt_dbg_alloc_register(ctx, (uint8_t*)ptr, len, ctx->dbg_site);
     off = 0;
   }
   out.aid = aid;
   out.off_bytes = off;
-  out.bid = rt_dbg_alloc_borrow_id(ctx, aid);
+  out.bid = rt_dbg_alloc_borrow_id(ctx, aid, ctx->dbg_site);
 #endif
   return out;
 }
@@ -3404,8 +3495,8 @@ static void rt_bytes_drop(ctx_t* ctx, bytes_t* b) {
     return;
   }
 #ifdef X07_DEBUG_BORROW
-  uint64_t aid = rt_dbg_alloc_find(ctx, b->ptr, b->len, NULL);
-  rt_dbg_alloc_kill(ctx, aid);
+  uint64_t aid = rt_dbg_alloc_find(ctx, b->ptr, b->len, NULL, ctx->dbg_site);
+  rt_dbg_alloc_kill(ctx, aid, ctx->dbg_site);
 #endif
   uint32_t size = b->len;
   // Heap allocator stores the requested size in the allocation header; use it for exact accounting.
@@ -3443,7 +3534,7 @@ static void rt_task_out_drop(ctx_t* ctx, rt_task_out_t* out) {
 
 static bytes_t rt_view_to_bytes(ctx_t* ctx, bytes_view_t v) {
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, v.bid, v.off_bytes, v.len);
+  (void)rt_dbg_borrow_check(ctx, v.bid, v.off_bytes, v.len, ctx->dbg_site);
 #endif
   bytes_t out = rt_bytes_alloc(ctx, v.len);
   if (v.len != 0) {
@@ -3454,13 +3545,13 @@ static bytes_t rt_view_to_bytes(ctx_t* ctx, bytes_view_t v) {
 }
 
 #ifdef X07_DEBUG_BORROW
-static uint32_t rt_dbg_bytes_check(ctx_t* ctx, bytes_t b) {
+static uint32_t rt_dbg_bytes_check(ctx_t* ctx, bytes_t b, uint32_t site) {
   if (b.len == 0) return 1;
-  uint64_t aid = rt_dbg_alloc_find(ctx, b.ptr, b.len, NULL);
+  uint64_t aid = rt_dbg_alloc_find(ctx, b.ptr, b.len, NULL, site);
   if (aid == 0) return 0;
   dbg_alloc_rec_t* a = &ctx->dbg_allocs[aid - 1];
   if (!a->alive) {
-    ctx->dbg_borrow_violations += 1;
+    rt_dbg_violation(ctx, site);
     return 0;
   }
   return 1;
@@ -3470,7 +3561,7 @@ static uint32_t rt_dbg_bytes_check(ctx_t* ctx, bytes_t b) {
 static uint32_t rt_bytes_get_u8(ctx_t* ctx, bytes_t b, uint32_t idx) {
   if (idx >= b.len) rt_trap("bytes.get_u8 oob");
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_bytes_check(ctx, b)) return 0;
+  if (!rt_dbg_bytes_check(ctx, b, ctx->dbg_site)) return 0;
 #else
   (void)ctx;
 #endif
@@ -3480,7 +3571,7 @@ static uint32_t rt_bytes_get_u8(ctx_t* ctx, bytes_t b, uint32_t idx) {
 static bytes_t rt_bytes_set_u8(ctx_t* ctx, bytes_t b, uint32_t idx, uint32_t v) {
   if (idx >= b.len) rt_trap("bytes.set_u8 oob");
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_bytes_check(ctx, b)) return b;
+  if (!rt_dbg_bytes_check(ctx, b, ctx->dbg_site)) return b;
 #else
   (void)ctx;
 #endif
@@ -3491,8 +3582,8 @@ static bytes_t rt_bytes_set_u8(ctx_t* ctx, bytes_t b, uint32_t idx, uint32_t v)
 static bytes_t rt_bytes_copy(ctx_t* ctx, bytes_t src, bytes_t dst) {
   if (dst.len < src.len) rt_trap("bytes.copy dst too small");
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_bytes_check(ctx, src)) return dst;
-  if (!rt_dbg_bytes_check(ctx, dst)) return dst;
+  if (!rt_dbg_bytes_check(ctx, src, ctx->dbg_site)) return dst;
+  if (!rt_dbg_bytes_check(ctx, dst, ctx->dbg_site)) return dst;
 #endif
   if (src.len != 0) {
     memcpy(dst.ptr, src.ptr, src.len);
@@ -3507,7 +3598,7 @@ static bytes_t rt_bytes_slice(ctx_t* ctx, bytes_t b, uint32_t start, uint32_t le
 
 #ifdef X07_DEBUG_BORROW
   // Bulk ops bypass per-byte checks; validate once up front.
-  uint32_t ok = rt_dbg_bytes_check(ctx, b);
+  uint32_t ok = rt_dbg_bytes_check(ctx, b, ctx->dbg_site);
   if (!ok && b.len != 0) {
     return rt_bytes_alloc(ctx, len);
   }
@@ -3526,8 +3617,8 @@ static bytes_t rt_bytes_concat(ctx_t* ctx, bytes_t a, bytes_t b) {
   uint32_t lb = b.len;
   if (UINT32_MAX - la < lb) rt_trap("bytes.concat overflow");
 #ifdef X07_DEBUG_BORROW
-  uint32_t ok_a = rt_dbg_bytes_check(ctx, a);
-  uint32_t ok_b = rt_dbg_bytes_check(ctx, b);
+  uint32_t ok_a = rt_dbg_bytes_check(ctx, a, ctx->dbg_site);
+  uint32_t ok_b = rt_dbg_bytes_check(ctx, b, ctx->dbg_site);
   if ((!ok_a && la != 0) || (!ok_b && lb != 0)) {
     return rt_bytes_alloc(ctx, la + lb);
   }
@@ -3550,8 +3641,8 @@ static uint32_t rt_bytes_eq(ctx_t* ctx, bytes_t a, bytes_t b) {
   uint32_t b_prefix_len = 0;
   ctx->last_bytes_eq_valid = 0;
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_bytes_check(ctx, a)) return UINT32_C(0);
-  if (!rt_dbg_bytes_check(ctx, b)) return UINT32_C(0);
+  if (!rt_dbg_bytes_check(ctx, a, ctx->dbg_site)) return UINT32_C(0);
+  if (!rt_dbg_bytes_check(ctx, b, ctx->dbg_site)) return UINT32_C(0);
 #endif
   if (a.len != b.len) goto mismatch;
   if (a.len == 0) return UINT32_C(1);
@@ -3586,8 +3677,8 @@ static uint32_t rt_bytes_cmp_range(
   if (b_off > b.len || b.len - b_off < b_len) rt_trap("bytes.cmp_range oob");
 
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_bytes_check(ctx, a)) return UINT32_C(0);
-  if (!rt_dbg_bytes_check(ctx, b)) return UINT32_C(0);
+  if (!rt_dbg_bytes_check(ctx, a, ctx->dbg_site)) return UINT32_C(0);
+  if (!rt_dbg_bytes_check(ctx, b, ctx->dbg_site)) return UINT32_C(0);
 #else
   (void)ctx;
 #endif
@@ -3609,11 +3700,11 @@ static bytes_view_t rt_bytes_view(ctx_t* ctx, bytes_t b) {
 #ifdef X07_DEBUG_BORROW
   if (b.len == 0) return rt_view_empty(ctx);
   uint32_t off = 0;
-  uint64_t aid = rt_dbg_alloc_find(ctx, b.ptr, b.len, &off);
+  uint64_t aid = rt_dbg_alloc_find(ctx, b.ptr, b.len, &off, ctx->dbg_site);
   out.ptr = b.ptr;
   out.aid = aid;
   out.off_bytes = off;
-  out.bid = rt_dbg_alloc_borrow_id(ctx, aid);
+  out.bid = rt_dbg_alloc_borrow_id(ctx, aid, ctx->dbg_site);
 #else
   out.ptr = (b.len == 0) ? ctx->heap.mem : b.ptr;
 #endif
@@ -3630,12 +3721,12 @@ static bytes_view_t rt_bytes_subview(ctx_t* ctx, bytes_t b, uint32_t start, uint
     return rt_view_empty(ctx);
   }
   uint32_t base_off = 0;
-  uint64_t aid = rt_dbg_alloc_find(ctx, b.ptr, b.len, &base_off);
+  uint64_t aid = rt_dbg_alloc_find(ctx, b.ptr, b.len, &base_off, ctx->dbg_site);
   uint32_t off = base_off + start;
   out.ptr = b.ptr + start;
   out.aid = aid;
   out.off_bytes = off;
-  out.bid = rt_dbg_alloc_borrow_id(ctx, aid);
+  out.bid = rt_dbg_alloc_borrow_id(ctx, aid, ctx->dbg_site);
 #else
   out.ptr = (len == 0) ? ctx->heap.mem : (b.ptr + start);
 #endif
@@ -3645,7 +3736,7 @@ static bytes_view_t rt_bytes_subview(ctx_t* ctx, bytes_t b, uint32_t start, uint
 static uint32_t rt_view_get_u8(ctx_t* ctx, bytes_view_t v, uint32_t idx) {
   if (idx >= v.len) rt_trap("view.get_u8 oob");
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_borrow_check(ctx, v.bid, v.off_bytes + idx, 1)) return 0;
+  if (!rt_dbg_borrow_check(ctx, v.bid, v.off_bytes + idx, 1, ctx->dbg_site)) return 0;
 #else
   (void)ctx;
 #endif
@@ -3675,8 +3766,8 @@ static uint32_t rt_view_eq(ctx_t* ctx, bytes_view_t a, bytes_view_t b) {
   if (a.len != b.len) goto mismatch;
   if (a.len == 0) return UINT32_C(1);
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_borrow_check(ctx, a.bid, a.off_bytes, a.len)) return UINT32_C(0);
-  if (!rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b.len)) return UINT32_C(0);
+  if (!rt_dbg_borrow_check(ctx, a.bid, a.off_bytes, a.len, ctx->dbg_site)) return UINT32_C(0);
+  if (!rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b.len, ctx->dbg_site)) return UINT32_C(0);
 #endif
   if (memcmp(a.ptr, b.ptr, a.len) == 0) return UINT32_C(1);
 
@@ -3686,8 +3777,8 @@ mismatch:
   b_prefix_len =
       (b.len < X07_ASSERT_BYTES_EQ_PREFIX_MAX) ? b.len : X07_ASSERT_BYTES_EQ_PREFIX_MAX;
 #ifdef X07_DEBUG_BORROW
-  if (a_prefix_len && !rt_dbg_borrow_check(ctx, a.bid, a.off_bytes, a_prefix_len)) return UINT32_C(0);
-  if (b_prefix_len && !rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b_prefix_len)) return UINT32_C(0);
+  if (a_prefix_len && !rt_dbg_borrow_check(ctx, a.bid, a.off_bytes, a_prefix_len, ctx->dbg_site)) return UINT32_C(0);
+  if (b_prefix_len && !rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b_prefix_len, ctx->dbg_site)) return UINT32_C(0);
 #endif
   ctx->last_bytes_eq_valid = 1;
   ctx->last_bytes_eq_a_len = a.len;
@@ -3710,8 +3801,8 @@ static uint32_t rt_view_cmp_range(
   if (b_off > b.len || b.len - b_off < b_len) rt_trap("view.cmp_range oob");
 
 #ifdef X07_DEBUG_BORROW
-  if (!rt_dbg_borrow_check(ctx, a.bid, a.off_bytes + a_off, a_len)) return UINT32_C(0);
-  if (!rt_dbg_borrow_check(ctx, b.bid, b.off_bytes + b_off, b_len)) return UINT32_C(0);
+  if (!rt_dbg_borrow_check(ctx, a.bid, a.off_bytes + a_off, a_len, ctx->dbg_site)) return UINT32_C(0);
+  if (!rt_dbg_borrow_check(ctx, b.bid, b.off_bytes + b_off, b_len, ctx->dbg_site)) return UINT32_C(0);
 #else
   (void)ctx;
 #endif
@@ -6657,7 +6748,7 @@ static result_i32_t rt_rr_open_v1(ctx_t* ctx, bytes_view_t cfg) {
   ctx->rr_open_calls += 1;
 
 #ifdef X07_DEBUG_BORROW
-  if (cfg.len != 0 && !rt_dbg_borrow_check(ctx, cfg.bid, cfg.off_bytes, cfg.len)) {
+  if (cfg.len != 0 && !rt_dbg_borrow_check(ctx, cfg.bid, cfg.off_bytes, cfg.len, ctx->dbg_site)) {
     return (result_i32_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_CFG_INVALID };
   }
 #endif
@@ -6943,9 +7034,9 @@ static result_bytes_t rt_rr_next_v1(ctx_t* ctx, int32_t handle_i32, bytes_view_t
   ctx->rr_next_calls += 1;
 
 #ifdef X07_DEBUG_BORROW
-  if (kind.len != 0 && !rt_dbg_borrow_check(ctx, kind.bid, kind.off_bytes, kind.len)) return (result_bytes_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_ENTRY_INVALID };
-  if (op.len != 0 && !rt_dbg_borrow_check(ctx, op.bid, op.off_bytes, op.len)) return (result_bytes_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_ENTRY_INVALID };
-  if (key.len != 0 && !rt_dbg_borrow_check(ctx, key.bid, key.off_bytes, key.len)) return (result_bytes_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_ENTRY_INVALID };
+  if (kind.len != 0 && !rt_dbg_borrow_check(ctx, kind.bid, kind.off_bytes, kind.len, ctx->dbg_site)) return (result_bytes_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_ENTRY_INVALID };
+  if (op.len != 0 && !rt_dbg_borrow_check(ctx, op.bid, op.off_bytes, op.len, ctx->dbg_site)) return (result_bytes_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_ENTRY_INVALID };
+  if (key.len != 0 && !rt_dbg_borrow_check(ctx, key.bid, key.off_bytes, key.len, ctx->dbg_site)) return (result_bytes_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_ENTRY_INVALID };
 #endif
 
   if (out_latency_ticks) *out_latency_ticks = UINT32_C(0);
@@ -7068,7 +7159,7 @@ static bytes_t rt_rr_entry_resp_v1(ctx_t* ctx, bytes_view_t entry) {
   if (!X07_ENABLE_RR) rt_trap("rr disabled");
 
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, entry.bid, entry.off_bytes, entry.len);
+  (void)rt_dbg_borrow_check(ctx, entry.bid, entry.off_bytes, entry.len, ctx->dbg_site);
 #endif
 
   if (entry.len < 2) rt_trap("rr.entry_resp_v1 invalid entry");
@@ -7111,7 +7202,7 @@ static int32_t rt_rr_entry_err_v1(ctx_t* ctx, bytes_view_t entry) {
   if (!X07_ENABLE_RR) rt_trap("rr disabled");
 
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, entry.bid, entry.off_bytes, entry.len);
+  (void)rt_dbg_borrow_check(ctx, entry.bid, entry.off_bytes, entry.len, ctx->dbg_site);
 #endif
 
   if (entry.len < 2) rt_trap("rr.entry_err_v1 invalid entry");
@@ -7149,7 +7240,7 @@ static result_i32_t rt_rr_append_v1(ctx_t* ctx, int32_t handle_i32, bytes_view_t
   ctx->rr_append_calls += 1;
 
 #ifdef X07_DEBUG_BORROW
-  if (entry.len != 0 && !rt_dbg_borrow_check(ctx, entry.bid, entry.off_bytes, entry.len)) {
+  if (entry.len != 0 && !rt_dbg_borrow_check(ctx, entry.bid, entry.off_bytes, entry.len, ctx->dbg_site)) {
     return (result_i32_t){ .tag = UINT32_C(0), .payload.err = RT_RR_ERR_ENTRY_INVALID };
   }
 #endif
@@ -7281,7 +7372,7 @@ static void rt_kv_ensure_cap(ctx_t* ctx, uint32_t need) {
 
 static uint32_t rt_kv_find(ctx_t* ctx, bytes_view_t key) {
 #ifdef X07_DEBUG_BORROW
-  if (key.len != 0 && !rt_dbg_borrow_check(ctx, key.bid, key.off_bytes, key.len)) {
+  if (key.len != 0 && !rt_dbg_borrow_check(ctx, key.bid, key.off_bytes, key.len, ctx->dbg_site)) {
     return UINT32_MAX;
   }
 #endif
@@ -7424,7 +7515,7 @@ static uint32_t rt_kv_latency_ticks(ctx_t* ctx, bytes_view_t key) {
   if (!X07_ENABLE_KV) rt_trap("kv disabled");
   rt_kv_latency_load(ctx);
 #ifdef X07_DEBUG_BORROW
-  if (key.len != 0 && !rt_dbg_borrow_check(ctx, key.bid, key.off_bytes, key.len)) {
+  if (key.len != 0 && !rt_dbg_borrow_check(ctx, key.bid, key.off_bytes, key.len, ctx->dbg_site)) {
     return ctx->kv_latency_default_ticks;
   }
 #endif
@@ -7487,7 +7578,7 @@ static void rt_kv_init(ctx_t* ctx) {
 
 static uint32_t rt_codec_read_u32_le(ctx_t* ctx, bytes_view_t buf, uint32_t offset) {
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, buf.bid, buf.off_bytes, buf.len);
+  (void)rt_dbg_borrow_check(ctx, buf.bid, buf.off_bytes, buf.len, ctx->dbg_site);
 #else
   (void)ctx;
 #endif
@@ -7555,7 +7646,7 @@ static uint32_t rt_parse_u32_dec_slice(ctx_t* ctx, uint8_t* ptr, uint32_t len) {
 
 static uint32_t rt_parse_u32_dec(ctx_t* ctx, bytes_view_t buf) {
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, buf.bid, buf.off_bytes, buf.len);
+  (void)rt_dbg_borrow_check(ctx, buf.bid, buf.off_bytes, buf.len, ctx->dbg_site);
 #endif
   return rt_parse_u32_dec_slice(ctx, buf.ptr, buf.len);
 }
@@ -7563,7 +7654,7 @@ static uint32_t rt_parse_u32_dec(ctx_t* ctx, bytes_view_t buf) {
 static uint32_t rt_parse_u32_dec_at(ctx_t* ctx, bytes_view_t buf, uint32_t offset) {
   if (offset > buf.len) rt_trap("parse.u32_dec_at oob");
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, buf.bid, buf.off_bytes + offset, buf.len - offset);
+  (void)rt_dbg_borrow_check(ctx, buf.bid, buf.off_bytes + offset, buf.len - offset, ctx->dbg_site);
 #endif
   return rt_parse_u32_dec_slice(ctx, buf.ptr + offset, buf.len - offset);
 }
@@ -7572,6 +7663,28 @@ static uint32_t rt_prng_lcg_next_u32(uint32_t state) {
   return state * UINT32_C(1103515245) + UINT32_C(12345);
 }
 
+// Per-run seed; the host runner names the env var via `-DX07_SEED_ENV`.
+static uint64_t rt_run_seed = 0;
+
+#ifdef X07_SEED_ENV
+static void rt_run_seed_init(void) {
+  const char* s = getenv(X07_SEED_ENV);
+  if (!s || !s[0]) return;
+  uint64_t v = 0;
+  for (const char* p = s; *p; p++) {
+    if (*p < '0' || *p > '9') rt_trap("run seed is not a decimal u64");
+    uint64_t d = (uint64_t)(*p - '0');
+    if (v > (UINT64_MAX - d) / UINT64_C(10)) rt_trap("run seed out of range");
+    v = v * UINT64_C(10) + d;
+  }
+  rt_run_seed = v;
+}
+#endif
+
+static uint32_t rt_prng_run_seed_u32(void) {
+  return (uint32_t)(rt_run_seed ^ (rt_run_seed >> 32));
+}
+
 typedef struct {
   uint8_t* data;
   uint32_t len;
@@ -7587,7 +7700,7 @@ static vec_u8_t rt_vec_u8_new(ctx_t* ctx, uint32_t cap) {
   v.cap = cap;
   v.data = (cap == 0) ? ctx->heap.mem : (uint8_t*)rt_alloc(ctx, cap, 1);
 #ifdef X07_DEBUG_BORROW
-  v.dbg_aid = (cap == 0) ? 0 : rt_dbg_alloc_register(ctx, v.data, cap);
+  v.dbg_aid = (cap == 0) ? 0 : rt_dbg_alloc_register(ctx, v.data, cap, ctx->dbg_site);
 #endif
   return v;
 }
@@ -7600,7 +7713,7 @@ static void rt_vec_u8_drop(ctx_t* ctx, vec_u8_t* v) {
     return;
   }
 #ifdef X07_DEBUG_BORROW
-  rt_dbg_alloc_kill(ctx, v->dbg_aid);
+  rt_dbg_alloc_kill(ctx, v->dbg_aid, ctx->dbg_site);
   v->dbg_aid = 0;
 #endif
   rt_free(ctx, v->data, v->cap, 1);
@@ -7655,8 +7768,8 @@ static vec_u8_t rt_vec_u8_push(ctx_t* ctx, vec_u8_t v, uint32_t val) {
       rt_mem_on_memcpy(ctx, v.len);
     }
 #ifdef X07_DEBUG_BORROW
-    rt_dbg_alloc_kill(ctx, v.dbg_aid);
-    v.dbg_aid = rt_dbg_alloc_register(ctx, data, new_cap);
+    rt_dbg_alloc_kill(ctx, v.dbg_aid, ctx->dbg_site);
+    v.dbg_aid = rt_dbg_alloc_register(ctx, data, new_cap, ctx->dbg_site);
 #endif
     if (old_data && old_cap) {
       rt_free(ctx, old_data, old_cap, 1);
@@ -7687,8 +7800,8 @@ static vec_u8_t rt_vec_u8_reserve_exact(ctx_t* ctx, vec_u8_t v, uint32_t additio
     rt_mem_on_memcpy(ctx, v.len);
   }
 #ifdef X07_DEBUG_BORROW
-  rt_dbg_alloc_kill(ctx, v.dbg_aid);
-  v.dbg_aid = rt_dbg_alloc_register(ctx, data, need);
+  rt_dbg_alloc_kill(ctx, v.dbg_aid, ctx->dbg_site);
+  v.dbg_aid = rt_dbg_alloc_register(ctx, data, need, ctx->dbg_site);
 #endif
   if (old_data && old_cap) {
     rt_free(ctx, old_data, old_cap, 1);
@@ -7725,8 +7838,8 @@ static vec_u8_t rt_vec_u8_extend_zeroes(ctx_t* ctx, vec_u8_t v, uint32_t n) {
       rt_mem_on_memcpy(ctx, v.len);
     }
 #ifdef X07_DEBUG_BORROW
-    rt_dbg_alloc_kill(ctx, v.dbg_aid);
-    v.dbg_aid = rt_dbg_alloc_register(ctx, data, new_cap);
+    rt_dbg_alloc_kill(ctx, v.dbg_aid, ctx->dbg_site);
+    v.dbg_aid = rt_dbg_alloc_register(ctx, data, new_cap, ctx->dbg_site);
 #endif
     if (old_data && old_cap) {
       rt_free(ctx, old_data, old_cap, 1);
@@ -7744,7 +7857,7 @@ static vec_u8_t rt_vec_u8_extend_zeroes(ctx_t* ctx, vec_u8_t v, uint32_t n) {
 
 static vec_u8_t rt_vec_u8_extend_bytes(ctx_t* ctx, vec_u8_t v, bytes_view_t b) {
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b.len);
+  (void)rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b.len, ctx->dbg_site);
 #endif
   if (b.len > UINT32_MAX - v.len) rt_trap("vec_u8.extend_bytes overflow");
   uint32_t need = v.len + b.len;
@@ -7772,8 +7885,8 @@ static vec_u8_t rt_vec_u8_extend_bytes(ctx_t* ctx, vec_u8_t v, bytes_view_t b) {
       rt_mem_on_memcpy(ctx, v.len);
     }
 #ifdef X07_DEBUG_BORROW
-    rt_dbg_alloc_kill(ctx, v.dbg_aid);
-    v.dbg_aid = rt_dbg_alloc_register(ctx, data, new_cap);
+    rt_dbg_alloc_kill(ctx, v.dbg_aid, ctx->dbg_site);
+    v.dbg_aid = rt_dbg_alloc_register(ctx, data, new_cap, ctx->dbg_site);
 #endif
     if (old_data && old_cap) {
       rt_free(ctx, old_data, old_cap, 1);
@@ -7824,7 +7937,7 @@ static bytes_view_t rt_vec_u8_as_view(ctx_t* ctx, vec_u8_t v) {
   out.ptr = v.data;
   out.aid = v.dbg_aid;
   out.off_bytes = 0;
-  out.bid = rt_dbg_alloc_borrow_id(ctx, out.aid);
+  out.bid = rt_dbg_alloc_borrow_id(ctx, out.aid, ctx->dbg_site);
 #else
   out.ptr = (out.len == 0) ? ctx->heap.mem : v.data;
 #endif
@@ -8866,7 +8979,7 @@ static bytes_t rt_json_jcs_canon_doc_v1(
     uint32_t max_object_total_bytes
 ) {
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, input.bid, input.off_bytes, input.len);
+  (void)rt_dbg_borrow_check(ctx, input.bid, input.off_bytes, input.len, ctx->dbg_site);
 #endif
 
   rt_json_jcs_state_t st;
@@ -9019,7 +9132,7 @@ static bytes_t rt_bytes_alloc_aligned(ctx_t* ctx, uint32_t len, uint32_t align)
   out.len = len;
   out.ptr = (uint8_t*)rt_alloc(ctx, len, align);
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_alloc_register(ctx, out.ptr, len);
+  (void)rt_dbg_alloc_register(ctx, out.ptr, len, ctx->dbg_site);
 #endif
   return out;
 }
@@ -9267,10 +9380,10 @@ static bytes_view_t rt_view_from_ptr(ctx_t* ctx, const uint8_t* ptr, uint32_t le
 #ifdef X07_DEBUG_BORROW
   if (len == 0) return rt_view_empty(ctx);
   uint32_t off = 0;
-  uint64_t aid = rt_dbg_alloc_find(ctx, (uint8_t*)ptr, len, &off);
+  uint64_t aid = rt_dbg_alloc_find(ctx, (uint8_t*)ptr, len, &off, ctx->dbg_site);
   v.aid = aid;
   v.off_bytes = off;
-  v.bid = rt_dbg_alloc_borrow_id(ctx, aid);
+  v.bid = rt_dbg_alloc_borrow_id(ctx, aid, ctx->dbg_site);
 #endif
   return v;
 }
@@ -9608,7 +9721,7 @@ static bytes_view_t rt_scratch_u8_fixed_as_view(ctx_t* ctx, uint32_t handle) {
 
 static result_i32_t rt_scratch_u8_fixed_try_write(ctx_t* ctx, uint32_t handle, bytes_view_t b) {
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b.len);
+  (void)rt_dbg_borrow_check(ctx, b.bid, b.off_bytes, b.len, ctx->dbg_site);
 #endif
   rt_scratch_u8_fixed_t* s = rt_scratch_u8_fixed_ptr(ctx, handle);
   if (b.len > UINT32_MAX - s->buf.len) {
@@ -9834,7 +9947,7 @@ static uint32_t rt_hash_mix32(uint32_t h) {
 
 static uint32_t rt_hash_fnv1a32_view(ctx_t* ctx, bytes_view_t v) {
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_borrow_check(ctx, v.bid, v.off_bytes, v.len);
+  (void)rt_dbg_borrow_check(ctx, v.bid, v.off_bytes, v.len, ctx->dbg_site);
 #else
   (void)ctx;
 #endif
@@ -11232,6 +11345,97 @@ static void rt_ctx_cleanup(ctx_t* ctx) {
   ctx->map_value_cap = 0;
 }
 "#;
+const RUNTIME_C_DBG_SITES: &str = r#"
+// Debug borrow checks report violations by the generated C line that called
+// into the runtime. Everything below this point is program code, so each
+// checked runtime entry point records its call site before it runs.
+#ifdef X07_DEBUG_BORROW
+static inline void rt_dbg_site_set(ctx_t* ctx, uint32_t site) {
+  ctx->dbg_site = site;
+}
+
+#define rt_bytes_alloc(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_alloc((ctx), __VA_ARGS__))
+#define rt_bytes_alloc_aligned(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_alloc_aligned((ctx), __VA_ARGS__))
+#define rt_bytes_from_literal(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_from_literal((ctx), __VA_ARGS__))
+#define rt_bytes_clone(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_clone((ctx), __VA_ARGS__))
+#define rt_bytes_drop(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_drop((ctx), __VA_ARGS__))
+#define rt_bytes_get_u8(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_get_u8((ctx), __VA_ARGS__))
+#define rt_bytes_set_u8(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_set_u8((ctx), __VA_ARGS__))
+#define rt_bytes_copy(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_copy((ctx), __VA_ARGS__))
+#define rt_bytes_slice(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_slice((ctx), __VA_ARGS__))
+#define rt_bytes_concat(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_concat((ctx), __VA_ARGS__))
+#define rt_bytes_eq(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_eq((ctx), __VA_ARGS__))
+#define rt_bytes_cmp_range(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_cmp_range((ctx), __VA_ARGS__))
+#define rt_bytes_view(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_view((ctx), __VA_ARGS__))
+#define rt_bytes_subview(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_bytes_subview((ctx), __VA_ARGS__))
+#define rt_view_from_literal(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_view_from_literal((ctx), __VA_ARGS__))
+#define rt_view_from_ptr(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_view_from_ptr((ctx), __VA_ARGS__))
+#define rt_view_to_bytes(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_view_to_bytes((ctx), __VA_ARGS__))
+#define rt_view_get_u8(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_view_get_u8((ctx), __VA_ARGS__))
+#define rt_view_eq(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_view_eq((ctx), __VA_ARGS__))
+#define rt_view_cmp_range(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_view_cmp_range((ctx), __VA_ARGS__))
+#define rt_vec_u8_new(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_vec_u8_new((ctx), __VA_ARGS__))
+#define rt_vec_u8_drop(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_vec_u8_drop((ctx), __VA_ARGS__))
+#define rt_vec_u8_push(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_vec_u8_push((ctx), __VA_ARGS__))
+#define rt_vec_u8_reserve_exact(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_vec_u8_reserve_exact((ctx), __VA_ARGS__))
+#define rt_vec_u8_extend_zeroes(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_vec_u8_extend_zeroes((ctx), __VA_ARGS__))
+#define rt_vec_u8_extend_bytes(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_vec_u8_extend_bytes((ctx), __VA_ARGS__))
+#define rt_vec_u8_as_view(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_vec_u8_as_view((ctx), __VA_ARGS__))
+#define rt_rr_open_v1(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_rr_open_v1((ctx), __VA_ARGS__))
+#define rt_rr_next_v1(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_rr_next_v1((ctx), __VA_ARGS__))
+#define rt_rr_entry_resp_v1(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_rr_entry_resp_v1((ctx), __VA_ARGS__))
+#define rt_rr_entry_err_v1(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_rr_entry_err_v1((ctx), __VA_ARGS__))
+#define rt_rr_append_v1(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_rr_append_v1((ctx), __VA_ARGS__))
+#define rt_kv_find(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_kv_find((ctx), __VA_ARGS__))
+#define rt_kv_latency_ticks(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_kv_latency_ticks((ctx), __VA_ARGS__))
+#define rt_codec_read_u32_le(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_codec_read_u32_le((ctx), __VA_ARGS__))
+#define rt_parse_u32_dec(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_parse_u32_dec((ctx), __VA_ARGS__))
+#define rt_parse_u32_dec_at(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_parse_u32_dec_at((ctx), __VA_ARGS__))
+#define rt_json_jcs_canon_doc_v1(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_json_jcs_canon_doc_v1((ctx), __VA_ARGS__))
+#define rt_scratch_u8_fixed_try_write(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_scratch_u8_fixed_try_write((ctx), __VA_ARGS__))
+#define rt_hash_fnv1a32_view(ctx, ...) \
+  (rt_dbg_site_set((ctx), (uint32_t)__LINE__), rt_hash_fnv1a32_view((ctx), __VA_ARGS__))
+#endif
+"#;
 const RUNTIME_C_MAIN: &str = r#"
 static int rt_read_exact(int fd, uint8_t* dst, uint32_t len) {
   uint32_t off = 0;
@@ -11260,10 +11464,29 @@ static int rt_write_exact(int fd, const uint8_t* src, uint32_t len) {
   return 0;
 }
 
+// The runner may pass a dedicated pipe for the metrics line (X07_METRICS_FD);
+// otherwise it goes to stderr as the last JSON line.
+static int rt_metrics_fd(void) {
+  const char* s = getenv("X07_METRICS_FD");
+  if (!s || !s[0]) return STDERR_FILENO;
+  int fd = 0;
+  for (const char* p = s; *p; p++) {
+    if (*p < '0' || *p > '9' || fd > 1023) return STDERR_FILENO;
+    fd = fd * 10 + (*p - '0');
+  }
+  return (fd > STDERR_FILENO && fd <= 1023) ? fd : STDERR_FILENO;
+}
+
 int main(void) {
 #if defined(SIGPIPE) && defined(SIG_IGN)
   (void)signal(SIGPIPE, SIG_IGN);
 #endif
+  // Keep the metrics pipe out of processes the program spawns: a child that
+  // outlives the run would otherwise hold the runner's read end open.
+  {
+    int metrics_fd = rt_metrics_fd();
+    if (metrics_fd != STDERR_FILENO) (void)fcntl(metrics_fd, F_SETFD, FD_CLOEXEC);
+  }
 
   const uint32_t mem_cap = (uint32_t)(X07_MEM_CAP);
   int mem_is_mmap = 0;
@@ -11298,6 +11521,9 @@ int main(void) {
 #endif
 
   rt_kv_init(&ctx);
+#ifdef X07_SEED_ENV
+  rt_run_seed_init();
+#endif
 
   uint8_t len_buf[4];
   if (rt_read_exact(STDIN_FILENO, len_buf, 4) != 0) return 2;
@@ -11317,7 +11543,7 @@ int main(void) {
   rt_ext_ctx = NULL;
 
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_bytes_check(&ctx, out);
+  (void)rt_dbg_bytes_check(&ctx, out, (uint32_t)__LINE__);
 #endif
 
   uint32_t out_len = out.len;
@@ -11395,9 +11621,27 @@ int main(void) {
     ctx.sched_stats.sched_trace_hash
   );
 
+  char metrics_buf[8192];
+  int metrics_len;
+  char db_latency_str[256];
+  rt_db_latency_json(db_latency_str, sizeof(db_latency_str));
 #ifdef X07_DEBUG_BORROW
-  fprintf(
-    stderr,
+  char dbg_sites_str[X07_DBG_VIOLATION_SITES_CAP * 11u + 1u];
+  size_t dbg_sites_off = 0;
+  dbg_sites_str[0] = 0;
+  for (uint32_t i = 0; i < ctx.dbg_violation_sites_len; i++) {
+    int n = snprintf(
+      dbg_sites_str + dbg_sites_off,
+      sizeof(dbg_sites_str) - dbg_sites_off,
+      i == 0 ? "%" PRIu32 : ",%" PRIu32,
+      ctx.dbg_violation_sites[i]
+    );
+    if (n < 0 || (size_t)n >= sizeof(dbg_sites_str) - dbg_sites_off) break;
+    dbg_sites_off += (size_t)n;
+  }
+  metrics_len = snprintf(
+    metrics_buf,
+    sizeof(metrics_buf),
     "{\"fuel_used\":%" PRIu64 ",\"heap_used\":%u,\"fs_read_file_calls\":%" PRIu64 ",\"fs_list_dir_calls\":%" PRIu64 ","
     "\"rr_open_calls\":%" PRIu64 ",\"rr_close_calls\":%" PRIu64 ",\"rr_stats_calls\":%" PRIu64 ","
     "\"rr_next_calls\":%" PRIu64 ",\"rr_next_miss_calls\":%" PRIu64 ",\"rr_append_calls\":%" PRIu64 ","
@@ -11415,7 +11659,7 @@ int main(void) {
     "\"live_allocs\":%" PRIu64 ",\"peak_live_allocs\":%" PRIu64 ","
     "\"memcpy_bytes\":%" PRIu64 "},"
     "\"debug_stats\":{"
-    "\"borrow_violations\":%" PRIu64 "}}\n",
+    "\"borrow_violations\":%" PRIu64 ",\"borrow_violation_sites\":[%s]}%s}\n",
     fuel_used,
     heap_used,
     ctx.fs_read_file_calls,
@@ -11450,11 +11694,14 @@ int main(void) {
     ctx.mem_stats.live_allocs,
     ctx.mem_stats.peak_live_allocs,
     ctx.mem_stats.memcpy_bytes,
-    ctx.dbg_borrow_violations
+    ctx.dbg_borrow_violations,
+    dbg_sites_str,
+    db_latency_str
   );
 #else
-  fprintf(
-    stderr,
+  metrics_len = snprintf(
+    metrics_buf,
+    sizeof(metrics_buf),
     "{\"fuel_used\":%" PRIu64 ",\"heap_used\":%u,\"fs_read_file_calls\":%" PRIu64 ",\"fs_list_dir_calls\":%" PRIu64 ","
     "\"rr_open_calls\":%" PRIu64 ",\"rr_close_calls\":%" PRIu64 ",\"rr_stats_calls\":%" PRIu64 ","
     "\"rr_next_calls\":%" PRIu64 ",\"rr_next_miss_calls\":%" PRIu64 ",\"rr_append_calls\":%" PRIu64 ","
@@ -11470,7 +11717,7 @@ int main(void) {
     "\"bytes_alloc_total\":%" PRIu64 ",\"bytes_freed_total\":%" PRIu64 ","
     "\"live_bytes\":%" PRIu64 ",\"peak_live_bytes\":%" PRIu64 ","
     "\"live_allocs\":%" PRIu64 ",\"peak_live_allocs\":%" PRIu64 ","
-    "\"memcpy_bytes\":%" PRIu64 "}}\n",
+    "\"memcpy_bytes\":%" PRIu64 "}%s}\n",
     fuel_used,
     heap_used,
     ctx.fs_read_file_calls,
@@ -11504,10 +11751,20 @@ int main(void) {
     ctx.mem_stats.peak_live_bytes,
     ctx.mem_stats.live_allocs,
     ctx.mem_stats.peak_live_allocs,
-    ctx.mem_stats.memcpy_bytes
+    ctx.mem_stats.memcpy_bytes,
+    db_latency_str
   );
 #endif
   fflush(stderr);
+  if (metrics_len > 0) {
+    uint32_t n = (uint32_t)metrics_len;
+    if (n >= (uint32_t)sizeof(metrics_buf)) n = (uint32_t)sizeof(metrics_buf) - 1;
+    int metrics_fd = rt_metrics_fd();
+    if (rt_write_exact(metrics_fd, (const uint8_t*)metrics_buf, n) != 0
+        && metrics_fd != STDERR_FILENO) {
+      (void)rt_write_exact(STDERR_FILENO, (const uint8_t*)metrics_buf, n);
+    }
+  }
   if (mem_is_mmap) {
     (void)munmap(mem, (size_t)mem_cap);
   } else {
@@ -11605,7 +11862,7 @@ bytes_t x07_solve_v2(
   rt_ext_ctx = NULL;
 
 #ifdef X07_DEBUG_BORROW
-  (void)rt_dbg_bytes_check(&ctx, out);
+  (void)rt_dbg_bytes_check(&ctx, out, (uint32_t)__LINE__);
 #endif
 
   rt_bytes_drop(&ctx, &input_bytes);
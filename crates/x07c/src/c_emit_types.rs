@@ -4480,6 +4480,15 @@ impl InferCtx {
                         }
                         Ok(Ty::I32.into())
                     }
+                    "prng.run_seed_u32" => {
+                        if !args.is_empty() {
+                            return Err(CompilerError::new(
+                                CompileErrorKind::Parse,
+                                "prng.run_seed_u32 expects 0 args".to_string(),
+                            ));
+                        }
+                        Ok(Ty::I32.into())
+                    }
                     "regex.compile_opts_v1" => {
                         if args.len() != 2 {
                             return Err(CompilerError::new(
@@ -57,7 +57,8 @@ pub fn guide_md() -> String {
 
     out.push_str("## Builtins\n\n");
     out.push_str("- `input` is the input byte view (bytes_view); refer to it as the atom string `input` in expressions.\n");
-    out.push_str("- `iface.make_v1(data: i32, vtable: i32) -> iface` constructs an interface record value.\n\n");
+    out.push_str("- `iface.make_v1(data: i32, vtable: i32) -> iface` constructs an interface record value.\n");
+    out.push_str("- `prng.run_seed_u32() -> i32` is the per-run seed from the host runner (`--solve-seed`), folded to 32 bits; 0 when unset.\n\n");
 
     out.push_str("## Core Forms\n\n");
     out.push_str("- `begin`: `[\"begin\", e1, e2, ...]` evaluates sequentially and returns the last expression\n");
@@ -16,6 +16,7 @@ pub(crate) mod mem_provenance;
 pub mod module_source;
 pub mod native;
 pub mod optimize;
+pub mod options_fingerprint;
 pub mod program;
 pub mod project;
 pub mod records;
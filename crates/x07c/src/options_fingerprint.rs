@@ -0,0 +1,286 @@
+//! Canonical fingerprint of the semantically relevant [`CompileOptions`].
+//!
+//! Module roots and `arch_root` contribute digests of the files they resolve
+//! to rather than their paths, so relocating an identical tree keeps the
+//! fingerprint stable while editing a module changes it.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::compile::{CompileOptions, ContractMode};
+
+pub const COMPILE_OPTIONS_FINGERPRINT_SCHEMA_VERSION: &str =
+    "x07.compile-options-fingerprint@0.1.0";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOptionsFingerprint {
+    canonical: Vec<u8>,
+    sha256: String,
+}
+
+impl CompileOptionsFingerprint {
+    pub fn compute(options: &CompileOptions) -> std::io::Result<Self> {
+        let canonical = canonical_options_json(options)?;
+        let sha256 = sha256_hex(&canonical);
+        Ok(Self { canonical, sha256 })
+    }
+
+    /// Lowercase hex sha256 of [`Self::canonical_json`].
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    /// Canonical JSON (sorted keys, no whitespace) the digest is taken over.
+    pub fn canonical_json(&self) -> &[u8] {
+        &self.canonical
+    }
+}
+
+impl std::fmt::Display for CompileOptionsFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.sha256)
+    }
+}
+
+fn canonical_options_json(options: &CompileOptions) -> std::io::Result<Vec<u8>> {
+    // Exhaustive on purpose: a new `CompileOptions` field must be classified here.
+    let CompileOptions {
+        world,
+        compat,
+        enable_fs,
+        enable_rr,
+        enable_kv,
+        module_roots,
+        prefer_module_roots_first,
+        arch_root,
+        emit_main,
+        freestanding,
+        optimize,
+        profile_fns,
+        contract_mode,
+        allow_unsafe,
+        allow_ffi,
+        allow_internal_only_heads_in_entry,
+        allow_non_exported_calls_in_entry,
+    } = options;
+
+    let module_roots = module_roots
+        .iter()
+        .map(|root| tree_digest(root, is_module_source).map(Value::String))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let arch = match arch_root {
+        Some(root) => Value::String(tree_digest(&root.join("arch"), |_| true)?),
+        None => Value::Null,
+    };
+    let contract_mode = match contract_mode {
+        ContractMode::RuntimeTrap => "runtime_trap",
+        ContractMode::VerifyBmc => "verify_bmc",
+    };
+
+    let doc: BTreeMap<&str, Value> = BTreeMap::from([
+        (
+            "schema_version",
+            json!(COMPILE_OPTIONS_FINGERPRINT_SCHEMA_VERSION),
+        ),
+        ("world", json!(world.as_str())),
+        ("compat", json!(compat.version.as_str())),
+        ("compat_strict", json!(compat.strict)),
+        ("enable_fs", json!(enable_fs)),
+        ("enable_rr", json!(enable_rr)),
+        ("enable_kv", json!(enable_kv)),
+        ("module_roots", Value::Array(module_roots)),
+        (
+            "prefer_module_roots_first",
+            json!(prefer_module_roots_first),
+        ),
+        ("arch", arch),
+        ("emit_main", json!(emit_main)),
+        ("freestanding", json!(freestanding)),
+        ("optimize", json!(optimize)),
+        ("profile_fns", json!(profile_fns)),
+        ("contract_mode", json!(contract_mode)),
+        ("allow_unsafe", json!(allow_unsafe)),
+        ("allow_ffi", json!(allow_ffi)),
+        (
+            "allow_internal_only_heads_in_entry",
+            json!(allow_internal_only_heads_in_entry),
+        ),
+        (
+            "allow_non_exported_calls_in_entry",
+            json!(allow_non_exported_calls_in_entry),
+        ),
+    ]);
+    Ok(serde_json::to_vec(&doc).expect("serialize compile options fingerprint"))
+}
+
+fn is_module_source(rel: &str) -> bool {
+    rel.ends_with(".x07.json") || rel.ends_with(".x07t")
+}
+
+/// Digest over `(relative path, content sha256)` of every matching file under
+/// `root`, in sorted path order. A missing root digests as empty.
+fn tree_digest(root: &Path, include: fn(&str) -> bool) -> std::io::Result<String> {
+    let mut files: BTreeMap<String, PathBuf> = BTreeMap::new();
+    if root.is_dir() {
+        collect_files(root, "", include, &mut files)?;
+    }
+    let mut h = Sha256::new();
+    for (rel, path) in &files {
+        let bytes = std::fs::read(path)?;
+        h.update(rel.as_bytes());
+        h.update(b"\0");
+        h.update(sha256_hex(&bytes).as_bytes());
+        h.update(b"\n");
+    }
+    Ok(hex_lower(&h.finalize()))
+}
+
+fn collect_files(
+    dir: &Path,
+    prefix: &str,
+    include: fn(&str) -> bool,
+    out: &mut BTreeMap<String, PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let rel = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let path = entry.path();
+        let meta = std::fs::metadata(&path)?;
+        if meta.is_dir() {
+            collect_files(&path, &rel, include, out)?;
+        } else if meta.is_file() && include(&rel) {
+            out.insert(rel, path);
+        }
+    }
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex_lower(&Sha256::digest(bytes))
+}
+
+fn hex_lower(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir(prefix: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time")
+            .as_nanos();
+        let dir =
+            std::env::temp_dir().join(format!("x07c-{prefix}-{}-{unique}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    fn write_module(root: &Path, rel: &str, src: &str) {
+        let path = root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, src).unwrap();
+    }
+
+    #[test]
+    fn canonical_json_has_sorted_keys_and_is_stable() {
+        let a = CompileOptions {
+            enable_fs: true,
+            optimize: false,
+            ..Default::default()
+        };
+        let b = CompileOptions {
+            optimize: false,
+            enable_fs: true,
+            ..Default::default()
+        };
+
+        let fa = CompileOptionsFingerprint::compute(&a).unwrap();
+        let fb = CompileOptionsFingerprint::compute(&b).unwrap();
+        assert_eq!(fa, fb);
+        assert_eq!(fa.sha256().len(), 64);
+
+        let doc: serde_json::Map<String, Value> =
+            serde_json::from_slice(fa.canonical_json()).unwrap();
+        let keys: Vec<&String> = doc.keys().collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+        assert_eq!(
+            serde_json::to_vec(&doc).unwrap(),
+            fa.canonical_json(),
+            "canonical form must round-trip byte for byte"
+        );
+    }
+
+    #[test]
+    fn semantic_flags_change_fingerprint() {
+        let base = CompileOptionsFingerprint::compute(&CompileOptions::default()).unwrap();
+        let variants = [
+            CompileOptions {
+                enable_rr: true,
+                ..Default::default()
+            },
+            CompileOptions {
+                emit_main: false,
+                ..Default::default()
+            },
+            CompileOptions {
+                allow_unsafe: Some(false),
+                ..Default::default()
+            },
+            CompileOptions {
+                contract_mode: ContractMode::VerifyBmc,
+                ..Default::default()
+            },
+        ];
+        for v in &variants {
+            let fp = CompileOptionsFingerprint::compute(v).unwrap();
+            assert_ne!(fp, base, "{v:?}");
+        }
+    }
+
+    #[test]
+    fn module_roots_hash_content_not_paths() {
+        let a = temp_dir("fp-root-a");
+        let b = temp_dir("fp-root-b");
+        for root in [&a, &b] {
+            write_module(root, "app/main.x07.json", "{\"kind\":\"module\"}");
+            write_module(root, "app/notes.txt", "not a module");
+        }
+        let opts = |root: &Path| CompileOptions {
+            module_roots: vec![root.to_path_buf()],
+            ..Default::default()
+        };
+
+        let fa = CompileOptionsFingerprint::compute(&opts(&a)).unwrap();
+        let fb = CompileOptionsFingerprint::compute(&opts(&b)).unwrap();
+        assert_eq!(fa, fb, "identical content under different paths");
+
+        write_module(&b, "app/notes.txt", "edited, still not a module");
+        let fb = CompileOptionsFingerprint::compute(&opts(&b)).unwrap();
+        assert_eq!(fa, fb, "non-module files are ignored");
+
+        write_module(&b, "app/main.x07.json", "{\"kind\":\"module\",\"v\":2}");
+        let fb = CompileOptionsFingerprint::compute(&opts(&b)).unwrap();
+        assert_ne!(fa, fb, "module content changes the fingerprint");
+
+        std::fs::remove_dir_all(&a).unwrap();
+        std::fs::remove_dir_all(&b).unwrap();
+    }
+}
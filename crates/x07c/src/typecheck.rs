@@ -3302,6 +3302,10 @@ fn add_builtin_sigs(sigs: &mut BTreeMap<String, FnSigAst>) {
         "prng.lcg_next_u32".to_string(),
         mono("prng.lcg_next_u32", &[("seed", "i32")], "i32"),
     );
+    sigs.insert(
+        "prng.run_seed_u32".to_string(),
+        mono("prng.run_seed_u32", &[], "i32"),
+    );
 
     sigs.insert(
         "option_i32.none".to_string(),
@@ -84,7 +84,7 @@ fn golden_sha256_solve_pure_stream_json_canon_pipe() {
     let c = compile(program.as_slice(), CompileOptions::default());
     assert_eq!(
         sha256_hex(&c),
-        "e76f9fba240ab567b0c3727abb269820a806e5f3aa570bef97d23b62c8b30122"
+        "141de56063f268fdc40cbb44140d96ed31453a37af85711b98a8bfbf175157c4"
     );
 }
 
@@ -112,7 +112,7 @@ fn golden_sha256_solve_pure_async_tasks() {
     let c = compile(program.as_slice(), CompileOptions::default());
     assert_eq!(
         sha256_hex(&c),
-        "dc85e23c979bcc3c77fa988135dd35a3303aadb707d6642f4e9d29ca21638b47"
+        "2fc3ce52b36d93cce6a0a6a5f28ea6217ffab1508438883f6e374ea92f9eb182"
     );
 }
 
@@ -133,7 +133,7 @@ fn golden_sha256_solve_pure_contracts_runtime_trap() {
     let c = compile(program.as_slice(), CompileOptions::default());
     assert_eq!(
         sha256_hex(&c),
-        "2157fbacd9d9a0847092cb7ca5c372606c06557c704201ec5f9f1ab1dd9be0b0"
+        "4a0cf86657c67643d4676e444a32f8d96328d53cd6436dab53269729b652fde0"
     );
 }
 
@@ -148,7 +148,7 @@ fn golden_sha256_run_os_os_fs_read_file() {
     let c = compile(program.as_slice(), options);
     assert_eq!(
         sha256_hex(&c),
-        "480bf61ca7e6057d9b6ba2977e7640c94fcf7afe8f968a6f0d5056c009e9fae3"
+        "dbdd5aaf55368a3ef7288e2aa74d9ee149b063bcef622b461154503e79ebc20c"
     );
 }
 
@@ -197,6 +197,6 @@ fn golden_sha256_run_os_mega_fixture() {
     let c = compile(program.as_slice(), options);
     assert_eq!(
         sha256_hex(&c),
-        "948cda74ba5e3206e56ece7da6270d3cc5496363fdcf5678b8be3e275e36fcc3"
+        "93d6b3b78b9d41b7e116a1e3b049e441e8fff1efa2b45e3c529839f306fd5840"
     );
 }